@@ -0,0 +1,342 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::{string::String, vec::Vec};
+use syn::{
+	parenthesized,
+	parse::{Parse, ParseStream, Result},
+	punctuated::Punctuated,
+	token::{Comma, Paren},
+	Attribute, Data, DeriveInput, Error, Field, Ident, Lit, LitInt, LitStr, Meta, MetaNameValue, NestedMeta, Path,
+	WherePredicate,
+};
+
+use crate::type_def::is_phantom_data;
+
+/// An item of a `#[metadata(...)]` attribute.
+enum Item {
+	/// An item in the regular attribute syntax, e.g. `name = "..."`.
+	Meta(Meta),
+	/// The where predicates of `bounds(...)` which do not fit the regular attribute syntax.
+	Bounds(Ident, Punctuated<WherePredicate, Comma>),
+}
+
+impl Parse for Item {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let is_bounds = input.peek2(Paren) && input.fork().parse::<Ident>().is_ok_and(|ident| ident == "bounds");
+		if is_bounds {
+			let ident = input.parse()?;
+			let content;
+			parenthesized!(content in input);
+			Ok(Item::Bounds(ident, content.parse_terminated(WherePredicate::parse)?))
+		} else {
+			input.parse().map(Item::Meta)
+		}
+	}
+}
+
+/// Returns the items of all `#[metadata(...)]` attributes.
+fn items(attrs: &[Attribute]) -> Result<Vec<Item>> {
+	let mut items = Vec::new();
+	for attr in attrs.iter().filter(|attr| attr.path.is_ident("metadata")) {
+		items.extend(attr.parse_args_with(Punctuated::<Item, Comma>::parse_terminated)?);
+	}
+	Ok(items)
+}
+
+/// Returns the items of all `#[metadata(...)]` attributes of a field or variant.
+fn metadata_items(attrs: &[Attribute]) -> Result<Vec<Meta>> {
+	items(attrs)?
+		.into_iter()
+		.map(|item| match item {
+			Item::Meta(meta) => Ok(meta),
+			Item::Bounds(ident, _) => Err(Error::new_spanned(ident, "`bounds` is only supported on types")),
+		})
+		.collect()
+}
+
+/// Returns the string literal of the given `key = "value"` attribute item.
+fn lit_str(meta: &Meta) -> Result<LitStr> {
+	match meta {
+		Meta::NameValue(name_value) => match &name_value.lit {
+			Lit::Str(lit) => Ok(lit.clone()),
+			lit => Err(Error::new_spanned(lit, "expected a string literal")),
+		},
+		meta => Err(Error::new_spanned(meta, "expected `key = \"value\"`")),
+	}
+}
+
+/// Returns the integer literal of the given `key = 42` attribute item.
+fn lit_int(meta: &Meta) -> Result<LitInt> {
+	match meta {
+		Meta::NameValue(name_value) => match &name_value.lit {
+			Lit::Int(lit) => Ok(lit.clone()),
+			lit => Err(Error::new_spanned(lit, "expected an integer literal")),
+		},
+		meta => Err(Error::new_spanned(meta, "expected `key = 42`")),
+	}
+}
+
+/// Returns the lines of the `///` doc comments, i.e. the `#[doc = "..."]` attributes.
+///
+/// The single space following `///` is stripped from each line. Returns no lines
+/// unless the `docs` feature is enabled.
+pub fn docs(attrs: &[Attribute]) -> Vec<String> {
+	if !cfg!(feature = "docs") {
+		return Vec::new();
+	}
+	attrs
+		.iter()
+		.filter(|attr| attr.path.is_ident("doc"))
+		.filter_map(|attr| match attr.parse_meta() {
+			Ok(Meta::NameValue(MetaNameValue { lit: Lit::Str(lit), .. })) => Some(lit.value()),
+			_ => None,
+		})
+		.map(|line| match line.strip_prefix(' ') {
+			Some(line) => line.into(),
+			None => line,
+		})
+		.collect()
+}
+
+/// Returns `true` if the given string is an ASCII Rust identifier as required by the metadata.
+fn is_identifier(s: &str) -> bool {
+	let mut chars = s.chars();
+	match chars.next() {
+		Some(head) => {
+			(head == '_' || head.is_ascii_alphabetic()) && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+		}
+		None => false,
+	}
+}
+
+/// Attributes given to the type via `#[metadata(...)]`.
+#[derive(Default)]
+pub struct TypeAttrs {
+	/// The tag field name of internally or adjacently tagged enums, `#[metadata(tag = "...")]`.
+	pub tag: Option<LitStr>,
+	/// The content field name of adjacently tagged enums, `#[metadata(content = "...")]`.
+	pub content: Option<LitStr>,
+	/// Whether the enum is untagged, `#[metadata(untagged)]`.
+	pub untagged: bool,
+	/// The stable identifier of the type, `#[metadata(id = "...")]`.
+	pub id: Option<LitStr>,
+	/// The variant of `RawFormat` of an opaque payload type, `#[metadata(raw = "...")]`.
+	pub raw: Option<Ident>,
+	/// The name of the type overriding its identifier, `#[metadata(name = "...")]`.
+	pub name: Option<LitStr>,
+	/// The segments of the namespace of the type overriding its module path, `#[metadata(namespace = "...")]`.
+	pub namespace: Option<Vec<LitStr>>,
+	/// Whether the struct is described as its single field, `#[metadata(transparent)]`.
+	pub transparent: bool,
+	/// The where predicates replacing the derived `Metadata` bounds, `#[metadata(bounds(...))]`.
+	pub bounds: Option<Vec<WherePredicate>>,
+	/// The type parameters neither bounded by `Metadata` nor part of the identifier,
+	/// `#[metadata(skip_type_params(...))]`.
+	pub skip_type_params: Vec<Ident>,
+}
+
+impl TypeAttrs {
+	/// Parses the `#[metadata(...)]` attributes of a type.
+	pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+		let mut type_attrs = Self::default();
+		for item in items(attrs)? {
+			let meta = match item {
+				Item::Meta(meta) => meta,
+				Item::Bounds(_, predicates) => {
+					type_attrs.bounds.get_or_insert_with(Vec::new).extend(predicates);
+					continue;
+				}
+			};
+			if meta.path().is_ident("tag") {
+				type_attrs.tag = Some(lit_str(&meta)?);
+			} else if meta.path().is_ident("content") {
+				type_attrs.content = Some(lit_str(&meta)?);
+			} else if meta.path().is_ident("id") {
+				type_attrs.id = Some(lit_str(&meta)?);
+			} else if meta.path().is_ident("name") {
+				let lit = lit_str(&meta)?;
+				if !is_identifier(&lit.value()) {
+					return Err(Error::new_spanned(lit, "expected an ASCII identifier"));
+				}
+				type_attrs.name = Some(lit);
+			} else if meta.path().is_ident("namespace") {
+				let lit = lit_str(&meta)?;
+				let value = lit.value();
+				let segments = value.split("::").collect::<Vec<_>>();
+				if !segments.iter().all(|segment| is_identifier(segment)) {
+					return Err(Error::new_spanned(
+						lit,
+						"expected a path of ASCII identifiers, e.g. `a::b`",
+					));
+				}
+				type_attrs.namespace = Some(
+					segments
+						.into_iter()
+						.map(|segment| LitStr::new(segment, lit.span()))
+						.collect(),
+				);
+			} else if meta.path().is_ident("raw") {
+				let lit = lit_str(&meta)?;
+				let variant = match lit.value().as_str() {
+					"json" => "Json",
+					"scale" => "Scale",
+					_ => return Err(Error::new_spanned(lit, "expected `json` or `scale`")),
+				};
+				type_attrs.raw = Some(Ident::new(variant, lit.span()));
+			} else if meta.path().is_ident("untagged") {
+				if let Meta::Path(_) = meta {
+					type_attrs.untagged = true;
+				} else {
+					return Err(Error::new_spanned(meta, "expected `untagged` without arguments"));
+				}
+			} else if meta.path().is_ident("transparent") {
+				if let Meta::Path(_) = meta {
+					type_attrs.transparent = true;
+				} else {
+					return Err(Error::new_spanned(meta, "expected `transparent` without arguments"));
+				}
+			} else if meta.path().is_ident("skip_type_params") {
+				let list = match &meta {
+					Meta::List(list) => list,
+					meta => return Err(Error::new_spanned(meta, "expected `skip_type_params(T, ...)`")),
+				};
+				for nested in &list.nested {
+					match nested {
+						NestedMeta::Meta(Meta::Path(path)) if path.get_ident().is_some() => {
+							type_attrs.skip_type_params.extend(path.get_ident().cloned());
+						}
+						nested => return Err(Error::new_spanned(nested, "expected a type parameter")),
+					}
+				}
+			} else {
+				return Err(Error::new_spanned(meta.path(), "unknown metadata attribute"));
+			}
+		}
+		if let (None, Some(content)) = (&type_attrs.tag, &type_attrs.content) {
+			return Err(Error::new_spanned(
+				content,
+				"`content` requires a `tag` to be specified",
+			));
+		}
+		if let (true, Some(tag)) = (type_attrs.untagged, &type_attrs.tag) {
+			return Err(Error::new_spanned(tag, "untagged enums cannot have a `tag`"));
+		}
+		if type_attrs.transparent {
+			let conflict = type_attrs
+				.name
+				.as_ref()
+				.or(type_attrs.id.as_ref())
+				.or(type_attrs.tag.as_ref())
+				.or_else(|| type_attrs.namespace.as_ref().and_then(|segments| segments.first()));
+			if let Some(lit) = conflict {
+				return Err(Error::new_spanned(lit, "transparent types have no identifier of their own"));
+			}
+			if let Some(raw) = &type_attrs.raw {
+				return Err(Error::new_spanned(raw, "transparent types have no definition of their own"));
+			}
+		}
+		Ok(type_attrs)
+	}
+
+	/// Returns the single field of a `#[metadata(transparent)]` struct besides its `PhantomData` fields.
+	///
+	/// Returns `None` if the type is not transparent.
+	pub fn transparent_field<'a>(&self, ast: &'a DeriveInput) -> Result<Option<&'a Field>> {
+		if !self.transparent {
+			return Ok(None);
+		}
+		let fields = match &ast.data {
+			Data::Struct(s) => s.fields.iter().filter(|f| !is_phantom_data(&f.ty)).collect::<Vec<_>>(),
+			_ => Vec::new(),
+		};
+		match fields.as_slice() {
+			[field] => Ok(Some(*field)),
+			_ => Err(Error::new_spanned(
+				&ast.ident,
+				"`transparent` requires a struct with exactly one field besides `PhantomData` fields",
+			)),
+		}
+	}
+
+	/// Returns `true` if the enum representation deviates from the default external tagging.
+	pub fn has_enum_repr(&self) -> bool {
+		self.tag.is_some() || self.untagged
+	}
+}
+
+/// Attributes given to a field via `#[metadata(...)]`.
+#[derive(Default)]
+pub struct FieldAttrs {
+	/// The label of the logical section the field belongs to, `#[metadata(group = "...")]`.
+	pub group: Option<LitStr>,
+	/// The variant of `Sensitivity` of the field, `#[metadata(sensitivity = "...")]`.
+	pub sensitivity: Option<Ident>,
+	/// The format hint for displaying the values of the field, `#[metadata(display = "...")]`.
+	pub display: Option<LitStr>,
+	/// The module whose `type_id` and `type_def` functions describe the field, `#[metadata(with = "...")]`.
+	pub with: Option<Path>,
+}
+
+impl FieldAttrs {
+	/// Parses the `#[metadata(...)]` attributes of a field.
+	pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+		let mut field_attrs = Self::default();
+		for meta in metadata_items(attrs)? {
+			if meta.path().is_ident("group") {
+				field_attrs.group = Some(lit_str(&meta)?);
+			} else if meta.path().is_ident("sensitivity") {
+				let lit = lit_str(&meta)?;
+				let variant = match lit.value().as_str() {
+					"public" => "Public",
+					"internal" => "Internal",
+					"secret" => "Secret",
+					_ => return Err(Error::new_spanned(lit, "expected `public`, `internal` or `secret`")),
+				};
+				field_attrs.sensitivity = Some(Ident::new(variant, lit.span()));
+			} else if meta.path().is_ident("display") {
+				field_attrs.display = Some(lit_str(&meta)?);
+			} else if meta.path().is_ident("with") {
+				field_attrs.with = Some(lit_str(&meta)?.parse()?);
+			} else {
+				return Err(Error::new_spanned(meta.path(), "unknown metadata attribute"));
+			}
+		}
+		Ok(field_attrs)
+	}
+}
+
+/// Attributes given to an enum variant via `#[metadata(...)]`.
+#[derive(Default)]
+pub struct VariantAttrs {
+	/// The application-level error code of the variant, `#[metadata(code = 42)]`.
+	pub code: Option<u32>,
+}
+
+impl VariantAttrs {
+	/// Parses the `#[metadata(...)]` attributes of an enum variant.
+	pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+		let mut variant_attrs = Self::default();
+		for meta in metadata_items(attrs)? {
+			if meta.path().is_ident("code") {
+				variant_attrs.code = Some(lit_int(&meta)?.base10_parse()?);
+			} else {
+				return Err(Error::new_spanned(meta.path(), "unknown metadata attribute"));
+			}
+		}
+		Ok(variant_attrs)
+	}
+}
@@ -19,6 +19,7 @@
 extern crate alloc;
 extern crate proc_macro;
 
+mod attr;
 mod impl_wrapper;
 mod metadata;
 mod type_def;
@@ -26,17 +27,17 @@ mod type_id;
 
 use proc_macro::TokenStream;
 
-#[proc_macro_derive(TypeId)]
+#[proc_macro_derive(TypeId, attributes(metadata))]
 pub fn type_id(input: TokenStream) -> TokenStream {
 	type_id::generate(input.into()).into()
 }
 
-#[proc_macro_derive(TypeDef)]
+#[proc_macro_derive(TypeDef, attributes(metadata))]
 pub fn type_def(input: TokenStream) -> TokenStream {
 	type_def::generate(input.into()).into()
 }
 
-#[proc_macro_derive(Metadata)]
+#[proc_macro_derive(Metadata, attributes(metadata))]
 pub fn metadata(input: TokenStream) -> TokenStream {
 	metadata::generate(input.into()).into()
 }
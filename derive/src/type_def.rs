@@ -14,14 +14,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::{
+	string::{String, ToString},
+	vec::Vec,
+};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::{
-	parse::Result, parse_quote, punctuated::Punctuated, token::Comma, Data, DataEnum, DataStruct, DataUnion,
-	DeriveInput, Expr, ExprLit, Field, Fields, Lit, Variant,
+	parse::Result,
+	parse_quote,
+	punctuated::Punctuated,
+	token::Comma,
+	visit::{self, Visit},
+	visit_mut::VisitMut,
+	Data, DataEnum, DataStruct, DataUnion, DeriveInput, Error, Expr, ExprLit, Field, Fields, Ident, Lifetime, Lit,
+	Type, TypePath, Variant,
 };
 
-use crate::impl_wrapper::wrap;
+use crate::{
+	attr::{self, FieldAttrs, TypeAttrs, VariantAttrs},
+	impl_wrapper::wrap,
+};
 
 pub fn generate(input: TokenStream2) -> TokenStream2 {
 	match generate_impl(input) {
@@ -32,44 +45,273 @@ pub fn generate(input: TokenStream2) -> TokenStream2 {
 
 pub fn generate_impl(input: TokenStream2) -> Result<TokenStream2> {
 	let mut ast: DeriveInput = syn::parse2(input)?;
-
-	ast.generics.type_params_mut().for_each(|p| {
-		p.bounds.push(parse_quote!(_type_metadata::Metadata));
-		p.bounds.push(parse_quote!('static));
-	});
+	let attrs = TypeAttrs::from_attrs(&ast.attrs)?;
+	add_bounds(&mut ast, &attrs, true)?;
 
 	let ident = &ast.ident;
 	let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+	let transparent = attrs.transparent_field(&ast)?;
 
-	let def = match &ast.data {
-		Data::Struct(ref s) => generate_struct_def(s),
-		Data::Enum(ref e) => generate_enum_def(e),
-		Data::Union(ref u) => generate_union_def(u),
+	let def = if let Some(field) = transparent {
+		let (_, type_def) = field_fns(field)?;
+		quote! {
+			#type_def()
+		}
+	} else if let Some(format) = &attrs.raw {
+		quote! {
+			_type_metadata::TypeDef::raw(_type_metadata::RawFormat::#format)
+		}
+	} else {
+		match &ast.data {
+			Data::Struct(ref s) => generate_struct_def(s),
+			Data::Enum(ref e) => generate_enum_def(e, &attrs)?,
+			Data::Union(ref u) => generate_union_def(u),
+		}
+	};
+
+	let (field_count, variant_count) = match &ast.data {
+		Data::Struct(s) => (s.fields.iter().filter(|f| !is_phantom_data(&f.ty)).count(), 0),
+		Data::Enum(e) => (0, e.variants.len()),
+		Data::Union(u) => (u.fields.named.iter().filter(|f| !is_phantom_data(&f.ty)).count(), 0),
+	};
+	let metadata_hash = metadata_hash(&quote! { #ident #def }.to_string());
+	// Transparent types keep the niche of their field unless it is described by other functions.
+	let niche = match transparent {
+		Some(field) if FieldAttrs::from_attrs(&field.attrs)?.with.is_none() => {
+			let ty = static_type(&field.ty);
+			Some(quote! {
+				const NICHE: bool = <#ty as _type_metadata::HasTypeDef>::NICHE;
+			})
+		}
+		_ => None,
 	};
 
 	let has_type_def_impl = quote! {
 		impl #impl_generics _type_metadata::HasTypeDef for #ident #ty_generics #where_clause {
+			#niche
+
 			fn type_def() -> _type_metadata::TypeDef {
 				#def.into()
 			}
 		}
+
+		impl #impl_generics _type_metadata::TypeConsts for #ident #ty_generics #where_clause {
+			const FIELD_COUNT: usize = #field_count;
+			const VARIANT_COUNT: usize = #variant_count;
+			const METADATA_HASH: u64 = #metadata_hash;
+		}
 	};
 
-	Ok(wrap(ident, "HAS_TYPE_DEF", has_type_def_impl))
+	Ok(wrap(has_type_def_impl))
+}
+
+/// Returns the paths of the `type_id` and `type_def` functions describing the given field.
+///
+/// These are the functions of the module given by `#[metadata(with = "...")]`, if any,
+/// or else the ones of the `HasTypeId` and `HasTypeDef` implementations of the field type.
+pub fn field_fns(field: &Field) -> Result<(TokenStream2, TokenStream2)> {
+	let ty = static_type(&field.ty);
+	Ok(match FieldAttrs::from_attrs(&field.attrs)?.with {
+		Some(with) => (quote!(#with::type_id), quote!(#with::type_def)),
+		None => (
+			quote!(<#ty as _type_metadata::HasTypeId>::type_id),
+			quote!(<#ty as _type_metadata::HasTypeDef>::type_def),
+		),
+	})
+}
+
+/// Bounds the type parameters by `Metadata + 'static` and, if `projections` is set, the field
+/// types referring to their associated types by `Metadata`.
+///
+/// Parameters given by `#[metadata(skip_type_params(...))]` are only bounded by `'static` which
+/// `Metadata` requires of the type itself. The predicates of `#[metadata(bounds(...))]` replace
+/// all derived `Metadata` bounds.
+pub fn add_bounds(ast: &mut DeriveInput, attrs: &TypeAttrs, projections: bool) -> Result<()> {
+	let unknown = attrs
+		.skip_type_params
+		.iter()
+		.find(|skipped| ast.generics.type_params().all(|param| param.ident != **skipped));
+	if let Some(skipped) = unknown {
+		return Err(Error::new_spanned(skipped, "unknown type parameter"));
+	}
+	ast.generics.type_params_mut().for_each(|p| {
+		if attrs.bounds.is_none() && !attrs.skip_type_params.contains(&p.ident) {
+			p.bounds.push(parse_quote!(_type_metadata::Metadata));
+		}
+		p.bounds.push(parse_quote!('static));
+	});
+	match &attrs.bounds {
+		Some(bounds) => ast.generics.make_where_clause().predicates.extend(bounds.iter().cloned()),
+		None if projections => add_projection_bounds(ast),
+		None => {}
+	}
+	Ok(())
+}
+
+/// Bounds all field types that refer to associated types of type parameters by `Metadata`.
+///
+/// The bounds of a type parameter `T: Config` do not imply that e.g. `T::AccountId` implements
+/// `Metadata`, so fields of such types need their own bounds. Fields described via
+/// `#[metadata(with = "...")]` are skipped since their types need not implement `Metadata`.
+fn add_projection_bounds(ast: &mut DeriveInput) {
+	let params = ast
+		.generics
+		.type_params()
+		.map(|param| param.ident.clone())
+		.collect::<Vec<_>>();
+	let fields: Vec<&Field> = match &ast.data {
+		Data::Struct(s) => s.fields.iter().collect(),
+		Data::Enum(e) => e.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+		Data::Union(u) => u.fields.named.iter().collect(),
+	};
+	let fields = fields.into_iter().filter(|field| {
+		!is_phantom_data(&field.ty) && FieldAttrs::from_attrs(&field.attrs).map_or(true, |attrs| attrs.with.is_none())
+	});
+	let mut bounded: Vec<(String, Type)> = Vec::new();
+	for field in fields {
+		let mut projections = Projections {
+			params: &params,
+			found: false,
+		};
+		projections.visit_type(&field.ty);
+		let ty = static_type(&field.ty);
+		let rendered = quote!(#ty).to_string();
+		if projections.found && bounded.iter().all(|(other, _)| *other != rendered) {
+			bounded.push((rendered, ty));
+		}
+	}
+	let where_clause = ast.generics.make_where_clause();
+	for (_, ty) in bounded {
+		where_clause
+			.predicates
+			.push(parse_quote!(#ty: _type_metadata::Metadata + 'static));
+	}
+}
+
+/// Finds associated types of type parameters, e.g. `T::AccountId` or `<T as Config>::AccountId`.
+struct Projections<'a> {
+	params: &'a [Ident],
+	found: bool,
+}
+
+impl<'ast> Visit<'ast> for Projections<'_> {
+	fn visit_type_path(&mut self, ty: &'ast TypePath) {
+		let is_param = |path: &syn::Path| path.segments.first().is_some_and(|s| self.params.contains(&s.ident));
+		self.found |= match &ty.qself {
+			Some(qself) => matches!(&*qself.ty, Type::Path(param) if is_param(&param.path)),
+			None => ty.path.segments.len() > 1 && is_param(&ty.path),
+		};
+		visit::visit_type_path(self, ty);
+	}
+}
+
+/// Returns `true` if the given type is `PhantomData`, e.g. `PhantomData<T>` or `core::marker::PhantomData<T>`.
+///
+/// Phantom data carries no data and is thus left out of derived definitions, so that
+/// type parameters only used as markers need not implement `Metadata` with `skip_type_params`.
+pub fn is_phantom_data(ty: &Type) -> bool {
+	match ty {
+		Type::Path(path) if path.qself.is_none() => path
+			.path
+			.segments
+			.last()
+			.is_some_and(|segment| segment.ident == "PhantomData"),
+		Type::Group(group) => is_phantom_data(&group.elem),
+		_ => false,
+	}
+}
+
+/// Returns the given type with all of its lifetimes replaced by `'static`.
+///
+/// Lifetimes never appear in the metadata, e.g. `&'a T` is described as `T` for all `'a`,
+/// while `Metadata` is only implemented for `'static` types. Hence fields of types with
+/// lifetime parameters are described by their `'static` counterparts.
+fn static_type(ty: &Type) -> Type {
+	struct StaticLifetimes;
+
+	impl VisitMut for StaticLifetimes {
+		fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+			*lifetime = parse_quote!('static);
+		}
+	}
+
+	let mut ty = ty.clone();
+	StaticLifetimes.visit_type_mut(&mut ty);
+	ty
+}
+
+/// Returns the 64-bit FNV-1a hash of the given rendering of a type definition.
+fn metadata_hash(rendered: &str) -> u64 {
+	rendered.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+	})
+}
+
+/// Returns the builder call assigning the doc comments of the given attributes, if any.
+pub fn generate_docs(attrs: &[syn::Attribute]) -> Option<TokenStream2> {
+	let docs = attr::docs(attrs);
+	if docs.is_empty() {
+		return None;
+	}
+	Some(quote! {
+		.with_docs(&[ #( #docs ),* ])
+	})
 }
 
 type FieldsList = Punctuated<Field, Comma>;
 
 fn generate_fields_def(fields: &FieldsList) -> TokenStream2 {
-	let fields_def = fields.iter().map(|f| {
-		let (ty, ident) = (&f.ty, &f.ident);
-		let meta_type = quote! {
-			<#ty as _type_metadata::Metadata>::meta_type()
+	let named = fields.iter().all(|f| f.ident.is_some());
+	let fields = fields.iter().filter(|f| !is_phantom_data(&f.ty)).collect::<Vec<_>>();
+	if fields.is_empty() {
+		// The element type cannot be inferred if all fields are phantom data.
+		let field_type = if named {
+			quote!(_type_metadata::NamedField)
+		} else {
+			quote!(_type_metadata::UnnamedField)
+		};
+		return quote! { __core::Vec::<#field_type>::new() };
+	}
+	let fields_def = fields.into_iter().map(|f| {
+		let (ty, ident) = (static_type(&f.ty), &f.ident);
+		let attrs = match FieldAttrs::from_attrs(&f.attrs) {
+			Ok(attrs) => attrs,
+			Err(err) => return err.to_compile_error(),
+		};
+		let meta_type = match &attrs.with {
+			Some(with) => quote! {
+				_type_metadata::MetaType::from_fns(#with::type_def, #with::type_id, #with::type_def)
+			},
+			None => quote! {
+				<#ty as _type_metadata::Metadata>::meta_type()
+			},
 		};
 		if let Some(i) = ident {
+			let group = attrs.group.map(|group| {
+				quote! {
+					.with_group(#group)
+				}
+			});
+			let sensitivity = attrs.sensitivity.map(|sensitivity| {
+				quote! {
+					.with_sensitivity(_type_metadata::Sensitivity::#sensitivity)
+				}
+			});
+			let display = attrs.display.map(|display| {
+				quote! {
+					.with_display(#display)
+				}
+			});
+			let docs = generate_docs(&f.attrs);
 			quote! {
-				_type_metadata::NamedField::new(stringify!(#i), #meta_type)
+				_type_metadata::NamedField::new(stringify!(#i), #meta_type)#group#sensitivity#display#docs
 			}
+		} else if let Some(group) = attrs.group {
+			Error::new_spanned(group, "groups are only supported on named fields").to_compile_error()
+		} else if let Some(sensitivity) = attrs.sensitivity {
+			Error::new_spanned(sensitivity, "sensitivities are only supported on named fields").to_compile_error()
+		} else if let Some(display) = attrs.display {
+			Error::new_spanned(display, "display hints are only supported on named fields").to_compile_error()
 		} else {
 			quote! {
 				_type_metadata::UnnamedField::new(#meta_type)
@@ -84,90 +326,121 @@ fn generate_struct_def(data_struct: &DataStruct) -> TokenStream2 {
 		Fields::Named(ref fs) => {
 			let fields = generate_fields_def(&fs.named);
 			quote! {
-				_type_metadata::TypeDefStruct::new(#fields)
+				_type_metadata::TypeDefComposite::new(#fields)
 			}
 		}
 		Fields::Unnamed(ref fs) => {
 			let fields = generate_fields_def(&fs.unnamed);
 			quote! {
-				_type_metadata::TypeDefTupleStruct::new(#fields)
+				_type_metadata::TypeDefComposite::new(#fields)
 			}
 		}
 		Fields::Unit => quote! {
-			_type_metadata::TypeDefTupleStruct::unit()
+			_type_metadata::TypeDefComposite::unit()
 		},
 	}
 }
 
 type VariantList = Punctuated<Variant, Comma>;
 
-fn generate_c_like_enum_def(variants: &VariantList) -> TokenStream2 {
-	let variants_def = variants.into_iter().enumerate().map(|(i, v)| {
-		let name = &v.ident;
-		let discriminant = if let Some((
-			_,
-			Expr::Lit(ExprLit {
-				lit: Lit::Int(lit_int), ..
-			}),
-		)) = &v.discriminant
-		{
-			match lit_int.base10_parse::<u64>() {
-				Ok(i) => i,
-				Err(err) => return err.to_compile_error(),
+fn generate_variant_code(variant: &Variant) -> Result<TokenStream2> {
+	let attrs = VariantAttrs::from_attrs(&variant.attrs)?;
+	Ok(attrs.code.map(|code| quote! { .with_code(#code) }).unwrap_or_default())
+}
+
+fn generate_discriminants(variants: &VariantList) -> Result<Vec<u64>> {
+	variants
+		.into_iter()
+		.enumerate()
+		.map(|(i, v)| {
+			if let Some((
+				_,
+				Expr::Lit(ExprLit {
+					lit: Lit::Int(lit_int), ..
+				}),
+			)) = &v.discriminant
+			{
+				lit_int.base10_parse::<u64>()
+			} else {
+				Ok(i as u64)
 			}
-		} else {
-			i as u64
-		};
-		quote! {
-			_type_metadata::ClikeEnumVariant::new(stringify!(#name), #discriminant)
-		}
-	});
-	quote! {
-		_type_metadata::TypeDefClikeEnum::new(__core::vec![#( #variants_def, )*])
-	}
+		})
+		.collect()
 }
 
 fn is_c_like_enum(variants: &VariantList) -> bool {
 	// any variant has an explicit discriminant
 	variants.iter().any(|v| v.discriminant.is_some()) ||
 	// all variants are unit
-	variants.iter().all(|v| match v.fields {
-		Fields::Unit => true,
-		_ => false,
-	})
+	variants.iter().all(|v| matches!(v.fields, Fields::Unit))
 }
 
-fn generate_enum_def(data_enum: &DataEnum) -> TokenStream2 {
+fn generate_enum_repr(attrs: &TypeAttrs) -> TokenStream2 {
+	match (&attrs.tag, &attrs.content) {
+		(Some(tag), Some(content)) => quote! {
+			_type_metadata::EnumRepr::adjacent(#tag, #content)
+		},
+		(Some(tag), None) => quote! {
+			_type_metadata::EnumRepr::internal(#tag)
+		},
+		_ => quote! {
+			_type_metadata::EnumRepr::Untagged
+		},
+	}
+}
+
+fn generate_enum_def(data_enum: &DataEnum, attrs: &TypeAttrs) -> Result<TokenStream2> {
 	let variants = &data_enum.variants;
 
-	if is_c_like_enum(&variants) {
-		return generate_c_like_enum_def(variants);
-	}
+	let discriminants = if !is_c_like_enum(variants) {
+		None
+	} else if !attrs.has_enum_repr() {
+		Some(generate_discriminants(variants)?)
+	} else if let Some(v) = variants.iter().find(|v| v.discriminant.is_some()) {
+		return Err(Error::new_spanned(
+			v,
+			"enum representation attributes are not supported for enums with explicit discriminants",
+		));
+	} else {
+		None
+	};
 
-	let variants_def = variants.into_iter().map(|v| {
+	let variants_def = variants.into_iter().enumerate().map(|(i, v)| {
 		let ident = &v.ident;
 		let v_name = quote! {stringify!(#ident) };
-		match v.fields {
+		let code = match generate_variant_code(v) {
+			Ok(code) => code,
+			Err(err) => return err.to_compile_error(),
+		};
+		let fields = match v.fields {
 			Fields::Named(ref fs) => {
 				let fields = generate_fields_def(&fs.named);
-				quote! {
-					_type_metadata::EnumVariantStruct::new(#v_name, #fields).into()
-				}
+				quote! { .with_fields(#fields) }
 			}
 			Fields::Unnamed(ref fs) => {
 				let fields = generate_fields_def(&fs.unnamed);
-				quote! {
-					_type_metadata::EnumVariantTupleStruct::new(#v_name, #fields).into()
-				}
+				quote! { .with_fields(#fields) }
 			}
-			Fields::Unit => quote! {
-				_type_metadata::EnumVariantUnit::new(#v_name).into()
-			},
+			Fields::Unit => quote! {},
+		};
+		let discriminant = discriminants.as_ref().map(|discriminants| {
+			let discriminant = discriminants[i];
+			quote! { .with_discriminant(#discriminant) }
+		});
+		let docs = generate_docs(&v.attrs);
+		quote! {
+			_type_metadata::Variant::new(#v_name)#fields#discriminant#code#docs
 		}
 	});
-	quote! {
-		_type_metadata::TypeDefEnum::new(__core::vec![#( #variants_def, )*])
-	}
+	let repr = if attrs.has_enum_repr() {
+		let repr = generate_enum_repr(attrs);
+		quote! { .with_repr(#repr) }
+	} else {
+		quote! {}
+	};
+	Ok(quote! {
+		_type_metadata::TypeDefVariant::new(__core::vec![#( #variants_def, )*])#repr
+	})
 }
 
 fn generate_union_def(data_union: &DataUnion) -> TokenStream2 {
@@ -14,11 +14,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::vec::Vec;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{parse::Result, parse_quote, DeriveInput};
+use syn::{parse::Result, DeriveInput};
 
-use crate::impl_wrapper::wrap;
+use crate::{
+	attr::TypeAttrs,
+	impl_wrapper::wrap,
+	type_def::{add_bounds, field_fns, generate_docs},
+};
 
 pub fn generate(input: TokenStream2) -> TokenStream2 {
 	match generate_impl(input) {
@@ -29,32 +34,102 @@ pub fn generate(input: TokenStream2) -> TokenStream2 {
 
 pub fn generate_impl(input: TokenStream2) -> Result<TokenStream2> {
 	let mut ast: DeriveInput = syn::parse2(input)?;
+	let attrs = TypeAttrs::from_attrs(&ast.attrs)?;
 
-	ast.generics.type_params_mut().for_each(|p| {
-		p.bounds.push(parse_quote!(_type_metadata::Metadata));
-		p.bounds.push(parse_quote!('static));
-	});
+	add_bounds(&mut ast, &attrs, attrs.transparent)?;
 
 	let ident = &ast.ident;
 	let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
-	let generic_type_ids = ast.generics.type_params().map(|ty| {
-		let ty_ident = &ty.ident;
+	if let Some(field) = attrs.transparent_field(&ast)? {
+		let (type_id, _) = field_fns(field)?;
+		let has_type_id_impl = quote! {
+			impl #impl_generics _type_metadata::HasTypeId for #ident #ty_generics #where_clause {
+				fn type_id() -> _type_metadata::TypeId {
+					#type_id()
+				}
+			}
+		};
+		return Ok(wrap(has_type_id_impl));
+	}
+	let generic_type_ids = ast
+		.generics
+		.type_params()
+		.filter(|ty| !attrs.skip_type_params.contains(&ty.ident))
+		.map(|ty| {
+			let ty_ident = &ty.ident;
+			quote! {
+				<#ty_ident as _type_metadata::Metadata>::meta_type()
+			}
+		});
+	let const_params = ast.generics.const_params().map(|param| &param.ident).collect::<Vec<_>>();
+	let const_params = if const_params.is_empty() {
+		None
+	} else {
+		Some(quote! {
+			.with_const_params(__core::vec![ #( _type_metadata::ConstParam::from(#const_params) ),* ])
+		})
+	};
+	let name = match &attrs.name {
+		Some(name) => quote!(#name),
+		None => quote!(stringify!(#ident)),
+	};
+	let (namespace, path) = match &attrs.namespace {
+		Some(segments) => {
+			let path = segments
+				.iter()
+				.map(|segment| segment.value())
+				.collect::<Vec<_>>()
+				.join("::");
+			(
+				quote! {
+					_type_metadata::Namespace::from_validated(__core::vec![ #( #segments ),* ])
+				},
+				quote!(#path),
+			)
+		}
+		None => (
+			quote! {
+				_type_metadata::Namespace::from_validated(module_path!().split("::").collect())
+			},
+			quote!(module_path!()),
+		),
+	};
+	let stable_id = attrs.id.as_ref().map(|id| {
 		quote! {
-			<#ty_ident as _type_metadata::Metadata>::meta_type()
+			.with_stable_id(#id)
 		}
 	});
+	let docs = generate_docs(&ast.attrs);
+	// Two derives of the same crate with identical namespace and name define the same
+	// symbol which rustc rejects when compiling the crate.
+	let unique_name = if cfg!(feature = "unique-names") {
+		quote! {
+			#[used]
+			#[export_name = concat!(
+				"type-metadata: duplicate type name `", #path, "::", #name, "`"
+			)]
+			static __UNIQUE_NAME: u8 = 0;
+		}
+	} else {
+		quote! {}
+	};
 	let has_type_id_impl = quote! {
+		#unique_name
+
 		impl #impl_generics _type_metadata::HasTypeId for #ident #ty_generics #where_clause {
 			fn type_id() -> _type_metadata::TypeId {
 				_type_metadata::TypeIdCustom::new(
-					stringify!(#ident),
-					_type_metadata::Namespace::from_module_path(module_path!())
-						.expect("namespace from module path cannot fail"),
+					#name,
+					#namespace,
 					__core::vec![ #( #generic_type_ids ),* ],
-				).into()
+				)
+				#const_params
+				#stable_id
+				#docs
+				.into()
 			}
 		}
 	};
 
-	Ok(wrap(ident, "HAS_TYPE_ID", has_type_id_impl))
+	Ok(wrap(has_type_id_impl))
 }
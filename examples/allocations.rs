@@ -0,0 +1,84 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counts the allocations and measures the time spent on registry construction.
+//!
+//! Compare the results with and without the `smallvec` feature:
+//!
+//! ```sh
+//! cargo run --release --example allocations
+//! cargo run --release --example allocations --features smallvec
+//! ```
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	collections::BTreeMap,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Instant,
+};
+use type_metadata::{register_types, Registry, RegistryBuilder};
+
+/// The system allocator that counts the number of allocations.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ROUNDS: u32 = 1_000;
+
+fn build_registry() -> Registry {
+	let mut builder = RegistryBuilder::new();
+	register_types!(
+		&mut builder,
+		Option<u32>,
+		Result<Vec<u8>, String>,
+		(bool, char, [u64; 4]),
+		BTreeMap<String, Vec<Option<i128>>>,
+		core::ops::ControlFlow<bool, u8>,
+		core::task::Poll<Option<String>>,
+		core::cmp::Ordering,
+	);
+	builder.build()
+}
+
+fn main() {
+	let before = ALLOCATIONS.load(Ordering::Relaxed);
+	let start = Instant::now();
+	for _ in 0..ROUNDS {
+		drop(build_registry());
+	}
+	let elapsed = start.elapsed();
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+	println!(
+		"smallvec: {}, allocations per registry: {}, time per registry: {:?}",
+		cfg!(feature = "smallvec"),
+		allocations / ROUNDS as usize,
+		elapsed / ROUNDS,
+	);
+}
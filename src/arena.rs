@@ -0,0 +1,84 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sharing of compact type definitions.
+//!
+//! Registries of many generic instantiations compact identical definitions over and over,
+//! e.g. the unit composite of every marker type or the builtin definition of every tuple,
+//! slice and array. The `DefArena` of a registry hash-conses compact definitions so that
+//! all types with identical definitions share a single allocation, also across branches.
+//!
+//! Only whole definitions are shared, their fields and variants are not. This saves memory
+//! but leaves serialized registries unchanged since they always contain every definition.
+
+use crate::form::CompactForm;
+use crate::tm_std::*;
+use crate::TypeDef;
+
+/// Hash-conses the compact type definitions of a registry.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DefArena {
+	/// The shared definitions by their hashes.
+	///
+	/// Definitions with colliding hashes share the same bucket.
+	defs: BTreeMap<u64, Vec<Arc<TypeDef<CompactForm>>>>,
+}
+
+impl DefArena {
+	/// Returns the shared allocation of the given definition, allocating it if needed.
+	pub(crate) fn share(&mut self, def: TypeDef<CompactForm>) -> Arc<TypeDef<CompactForm>> {
+		let mut hasher = FnvHasher::default();
+		def.hash(&mut hasher);
+		let bucket = self.defs.entry(hasher.finish()).or_default();
+		match bucket.iter().find(|shared| ***shared == def) {
+			Some(shared) => Arc::clone(shared),
+			None => {
+				let shared = Arc::new(def);
+				bucket.push(Arc::clone(&shared));
+				shared
+			}
+		}
+	}
+
+	/// Returns the number of distinct definitions held by the arena.
+	#[cfg(test)]
+	pub(crate) fn len(&self) -> usize {
+		self.defs.values().map(Vec::len).sum()
+	}
+}
+
+/// The 64-bit FNV-1a hasher.
+///
+/// Unlike the default hasher of `std` this is also available without `std`.
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+	fn default() -> Self {
+		Self(0xcbf2_9ce4_8422_2325)
+	}
+}
+
+impl Hasher for FnvHasher {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3);
+		}
+	}
+}
@@ -0,0 +1,887 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of divergences between serialization and metadata.
+//!
+//! Serde attributes such as `#[serde(skip)]`, `#[serde(rename)]` or `#[serde(flatten)]`
+//! change the serialized structure of a type while its metadata still describes the
+//! Rust definition. An `Audit` serializes a value through a tracing serializer and
+//! compares the emitted structure with the registered definitions of its type.
+//!
+//! Only custom types outside of the prelude namespace are audited. The contents of
+//! sequences, maps and options as well as non-externally tagged enums and unions are
+//! not audited since their serialized structure does not map onto their definitions.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{audit::{Audit, Divergence}, *};
+//! # use serde::ser::{Serialize, SerializeStruct, Serializer};
+//! struct Account {
+//!     balance: u64,
+//!     cache: u64,
+//! }
+//!
+//! # impl HasTypeId for Account {
+//! #     fn type_id() -> TypeId {
+//! #         TypeIdCustom::new("Account", Namespace::new(vec!["app"]).unwrap(), vec![]).into()
+//! #     }
+//! # }
+//! # impl HasTypeDef for Account {
+//! #     fn type_def() -> TypeDef {
+//! #         TypeDefComposite::new(vec![NamedField::of::<u64>("balance"), NamedField::of::<u64>("cache")]).into()
+//! #     }
+//! # }
+//! // As derived by serde for a `#[serde(skip)]` on `cache`.
+//! impl Serialize for Account {
+//!     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+//!         let mut state = serializer.serialize_struct("Account", 1)?;
+//!         state.serialize_field("balance", &self.balance)?;
+//!         state.end()
+//!     }
+//! }
+//!
+//! let account = Account { balance: 1, cache: 2 };
+//! assert_eq!(
+//!     Audit::new().check(&account),
+//!     Ok(vec![Divergence::MissingField { path: "cache".to_string() }])
+//! );
+//! ```
+
+use crate::tm_std::*;
+use crate::{EnumRepr, Field, MetaType, Metadata, TypeDef, TypeId};
+use serde::ser::{
+	self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+	SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+
+/// Configures which divergences are reported by an audit.
+///
+/// By default fields that are part of the metadata but not serialized are reported
+/// while the names of serialized types are not compared with their type identifiers.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct Audit {
+	/// Whether metadata fields that are not serialized are tolerated.
+	allow_missing_fields: bool,
+	/// Whether serialized type names are compared with their type identifiers.
+	check_type_names: bool,
+}
+
+impl Audit {
+	/// Creates a new audit with the default configuration.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Tolerates fields of the metadata that are not serialized.
+	///
+	/// This is required for fields that are skipped conditionally, e.g. via
+	/// `#[serde(skip_serializing_if = "...")]`, depending on the audited value.
+	pub fn allow_missing_fields(mut self) -> Self {
+		self.allow_missing_fields = true;
+		self
+	}
+
+	/// Reports types whose serialized names differ from their type identifiers,
+	/// e.g. due to `#[serde(rename = "...")]` on the type.
+	pub fn check_type_names(mut self) -> Self {
+		self.check_type_names = true;
+		self
+	}
+
+	/// Serializes the given value and returns all divergences between its serialized
+	/// structure and its metadata in order of their detection.
+	///
+	/// # Errors
+	///
+	/// If the value fails to serialize itself.
+	pub fn check<T>(&self, value: &T) -> Result<Vec<Divergence>, AuditError>
+	where
+		T: Serialize + Metadata,
+	{
+		let mut divergences = Vec::new();
+		value.serialize(Tracer {
+			audit: self,
+			ty: T::meta_type(),
+			path: String::new(),
+			divergences: &mut divergences,
+		})?;
+		Ok(divergences)
+	}
+}
+
+/// A divergence between the serialized structure of a value and its metadata.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Divergence {
+	/// If the serialized name of a type differs from the name of its type identifier.
+	RenamedType {
+		/// The path of the value of the type.
+		path: String,
+		/// The serialized name of the type.
+		name: String,
+	},
+	/// If a field of the metadata is not serialized, e.g. due to `#[serde(skip)]`.
+	MissingField {
+		/// The path of the field.
+		path: String,
+	},
+	/// If a serialized field is not part of the metadata, e.g. due to `#[serde(rename)]`.
+	UnexpectedField {
+		/// The path of the field.
+		path: String,
+	},
+	/// If a serialized variant is not part of the metadata.
+	UnexpectedVariant {
+		/// The path of the variant.
+		path: String,
+	},
+	/// If the serialized structure does not match the kind of the definition,
+	/// e.g. due to `#[serde(flatten)]` or `#[serde(transparent)]`.
+	ShapeMismatch {
+		/// The path of the value.
+		path: String,
+	},
+}
+
+/// An error that may be encountered upon auditing a value.
+#[derive(PartialEq, Eq, Debug)]
+pub enum AuditError {
+	/// If the value fails to serialize itself.
+	Serialize {
+		/// The error message of the value.
+		message: String,
+	},
+}
+
+impl Display for AuditError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			AuditError::Serialize { message } => write!(f, "failed to serialize the audited value: {}", message),
+		}
+	}
+}
+
+impl std::error::Error for AuditError {}
+
+impl ser::Error for AuditError {
+	fn custom<T: Display>(message: T) -> Self {
+		AuditError::Serialize {
+			message: message.to_string(),
+		}
+	}
+}
+
+/// The expected fields of a composite or variant.
+enum Fields {
+	/// Named fields by their names.
+	Named(Vec<(&'static str, MetaType)>),
+	/// Unnamed fields in order.
+	Unnamed(Vec<MetaType>),
+}
+
+impl Fields {
+	/// Returns the expected fields of a composite or variant with the given fields.
+	fn of(fields: &[Field]) -> Self {
+		if !fields.is_empty() && fields.iter().all(|field| field.name().is_some()) {
+			Fields::Named(
+				fields
+					.iter()
+					.filter_map(|field| field.name().map(|name| (*name, *field.ty())))
+					.collect(),
+			)
+		} else {
+			Fields::Unnamed(fields.iter().map(|field| *field.ty()).collect())
+		}
+	}
+
+	/// Returns `true` if there are no expected fields.
+	fn is_empty(&self) -> bool {
+		match self {
+			Fields::Named(fields) => fields.is_empty(),
+			Fields::Unnamed(fields) => fields.is_empty(),
+		}
+	}
+}
+
+/// The expected serialized structure of an audited type.
+enum Expected {
+	/// The type is not audited.
+	Any,
+	/// A struct or tuple struct.
+	Composite(Fields),
+	/// A C-like enum with the given variant names.
+	ClikeEnum(Vec<&'static str>),
+	/// An externally tagged enum with the given variants.
+	Enum(Vec<(&'static str, Fields)>),
+}
+
+impl Expected {
+	/// Returns the expected serialized structure of the given type.
+	fn of(ty: &MetaType) -> Self {
+		match ty.type_id() {
+			TypeId::Custom(custom) if !custom.namespace().segments().is_empty() => (),
+			_ => return Expected::Any,
+		}
+		match ty.type_def().normalize() {
+			TypeDef::Composite(def) => Expected::Composite(Fields::of(def.fields())),
+			TypeDef::Variant(def) if def.is_clike() => {
+				Expected::ClikeEnum(def.variants().iter().map(|variant| *variant.name()).collect())
+			}
+			TypeDef::Variant(def) if matches!(def.repr(), EnumRepr::External) => Expected::Enum(
+				def.variants()
+					.iter()
+					.map(|variant| (*variant.name(), Fields::of(variant.fields())))
+					.collect(),
+			),
+			_ => Expected::Any,
+		}
+	}
+}
+
+/// A serializer that compares the serialized structure of a value with its metadata.
+struct Tracer<'a> {
+	/// The configuration of the audit.
+	audit: &'a Audit,
+	/// The type of the traced value.
+	ty: MetaType,
+	/// The path of the traced value.
+	path: String,
+	/// The divergences detected so far.
+	divergences: &'a mut Vec<Divergence>,
+}
+
+impl<'a> Tracer<'a> {
+	/// Returns the path of the given segment within the traced value.
+	fn join(&self, segment: &str) -> String {
+		if self.path.is_empty() {
+			segment.to_string()
+		} else {
+			format!("{}.{}", self.path, segment)
+		}
+	}
+
+	/// Returns a tracer of the value of the given type at the given segment.
+	fn child(&mut self, segment: &str, ty: MetaType) -> Tracer<'_> {
+		Tracer {
+			audit: self.audit,
+			ty,
+			path: self.join(segment),
+			divergences: &mut *self.divergences,
+		}
+	}
+
+	/// Reports a shape mismatch of the traced value.
+	fn mismatch(&mut self) {
+		let path = self.path.clone();
+		self.divergences.push(Divergence::ShapeMismatch { path });
+	}
+
+	/// Compares the serialized name of the traced type with its type identifier.
+	fn check_name(&mut self, name: &str) {
+		if !self.audit.check_type_names {
+			return;
+		}
+		if let TypeId::Custom(custom) = self.ty.type_id() {
+			if *custom.name() != name {
+				let path = self.path.clone();
+				self.divergences.push(Divergence::RenamedType {
+					path,
+					name: name.to_string(),
+				});
+			}
+		}
+	}
+
+	/// Traces a value without any fields.
+	fn scalar(mut self) -> Result<(), AuditError> {
+		if !matches!(Expected::of(&self.ty), Expected::Any) {
+			self.mismatch();
+		}
+		Ok(())
+	}
+
+	/// Traces a composite value and returns the expected fields, if any.
+	fn composite(&mut self, name: &str) -> Option<Fields> {
+		match Expected::of(&self.ty) {
+			Expected::Any => None,
+			Expected::Composite(fields) => {
+				self.check_name(name);
+				Some(fields)
+			}
+			_ => {
+				self.mismatch();
+				None
+			}
+		}
+	}
+
+	/// Traces an enum value and returns the expected fields of its variant, if any.
+	///
+	/// Upon success the path of the tracer is extended by the variant name.
+	fn variant(&mut self, name: &str, variant: &str) -> Option<Fields> {
+		let fields = match Expected::of(&self.ty) {
+			Expected::Any => return None,
+			Expected::ClikeEnum(variants) => variants
+				.into_iter()
+				.find(|expected| *expected == variant)
+				.map(|_| Fields::Unnamed(Vec::new())),
+			Expected::Enum(variants) => variants
+				.into_iter()
+				.find(|(expected, _)| *expected == variant)
+				.map(|(_, fields)| fields),
+			Expected::Composite(_) => {
+				self.mismatch();
+				return None;
+			}
+		};
+		self.check_name(name);
+		self.path = self.join(variant);
+		if fields.is_none() {
+			let path = self.path.clone();
+			self.divergences.push(Divergence::UnexpectedVariant { path });
+		}
+		fields
+	}
+
+	/// Returns a compound that audits the given expected fields.
+	fn compound(self, expected: Option<Fields>) -> Compound<'a> {
+		Compound {
+			tracer: self,
+			expected,
+			seen: Vec::new(),
+			index: 0,
+		}
+	}
+}
+
+/// Audits the fields of a serialized composite or variant.
+struct Compound<'a> {
+	/// The tracer of the composite or variant.
+	tracer: Tracer<'a>,
+	/// The expected fields or `None` if the fields are not audited.
+	expected: Option<Fields>,
+	/// The names of the serialized named fields.
+	seen: Vec<&'static str>,
+	/// The number of serialized unnamed fields.
+	index: usize,
+}
+
+impl Compound<'_> {
+	/// Audits the serialized named field.
+	fn named_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		let ty = match &self.expected {
+			Some(Fields::Named(fields)) => fields.iter().find(|(expected, _)| *expected == name).map(|(_, ty)| *ty),
+			Some(Fields::Unnamed(_)) => {
+				self.tracer.mismatch();
+				self.expected = None;
+				return Ok(());
+			}
+			None => return Ok(()),
+		};
+		self.seen.push(name);
+		match ty {
+			Some(ty) => value.serialize(self.tracer.child(name, ty)),
+			None => {
+				let path = self.tracer.join(name);
+				self.tracer.divergences.push(Divergence::UnexpectedField { path });
+				Ok(())
+			}
+		}
+	}
+
+	/// Audits the serialized unnamed field.
+	fn unnamed_field<T>(&mut self, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		let ty = match &self.expected {
+			Some(Fields::Unnamed(fields)) => fields.get(self.index).copied(),
+			Some(Fields::Named(_)) => {
+				self.tracer.mismatch();
+				self.expected = None;
+				return Ok(());
+			}
+			None => return Ok(()),
+		};
+		self.index += 1;
+		match ty {
+			Some(ty) => value.serialize(self.tracer.child(&(self.index - 1).to_string(), ty)),
+			None => Ok(()),
+		}
+	}
+
+	/// Reports all expected fields that have not been serialized.
+	fn end(mut self) -> Result<(), AuditError> {
+		match &self.expected {
+			Some(Fields::Named(fields)) if !self.tracer.audit.allow_missing_fields => {
+				for (name, _) in fields {
+					if !self.seen.contains(name) {
+						let path = self.tracer.join(name);
+						self.tracer.divergences.push(Divergence::MissingField { path });
+					}
+				}
+			}
+			Some(Fields::Unnamed(fields)) if fields.len() != self.index => self.tracer.mismatch(),
+			_ => (),
+		}
+		Ok(())
+	}
+}
+
+impl<'a> Serializer for Tracer<'a> {
+	type Ok = ();
+	type Error = AuditError;
+	type SerializeSeq = Compound<'a>;
+	type SerializeTuple = Compound<'a>;
+	type SerializeTupleStruct = Compound<'a>;
+	type SerializeTupleVariant = Compound<'a>;
+	type SerializeMap = Compound<'a>;
+	type SerializeStruct = Compound<'a>;
+	type SerializeStructVariant = Compound<'a>;
+
+	fn serialize_bool(self, _: bool) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_i8(self, _: i8) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_i16(self, _: i16) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_i32(self, _: i32) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_i64(self, _: i64) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_i128(self, _: i128) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_u8(self, _: u8) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_u16(self, _: u16) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_u32(self, _: u32) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_u64(self, _: u64) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_u128(self, _: u128) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_f32(self, _: f32) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_f64(self, _: f64) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_char(self, _: char) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_str(self, _: &str) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_bytes(self, _: &[u8]) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_none(self) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_some<T>(self, _: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		self.scalar()
+	}
+
+	fn serialize_unit(self) -> Result<(), AuditError> {
+		self.scalar()
+	}
+
+	fn serialize_unit_struct(mut self, name: &'static str) -> Result<(), AuditError> {
+		match self.composite(name) {
+			Some(fields) if !fields.is_empty() => self.mismatch(),
+			_ => (),
+		}
+		Ok(())
+	}
+
+	fn serialize_unit_variant(mut self, name: &'static str, _: u32, variant: &'static str) -> Result<(), AuditError> {
+		match self.variant(name, variant) {
+			Some(fields) if !fields.is_empty() => self.mismatch(),
+			_ => (),
+		}
+		Ok(())
+	}
+
+	fn serialize_newtype_struct<T>(mut self, name: &'static str, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		let expected = self.composite(name);
+		let mut compound = self.compound(expected);
+		compound.unnamed_field(value)?;
+		compound.end()
+	}
+
+	fn serialize_newtype_variant<T>(
+		mut self,
+		name: &'static str,
+		_: u32,
+		variant: &'static str,
+		value: &T,
+	) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		let expected = self.variant(name, variant);
+		let mut compound = self.compound(expected);
+		compound.unnamed_field(value)?;
+		compound.end()
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<Compound<'a>, AuditError> {
+		self.serialize_map(None)
+	}
+
+	fn serialize_tuple(self, _: usize) -> Result<Compound<'a>, AuditError> {
+		self.serialize_map(None)
+	}
+
+	fn serialize_tuple_struct(mut self, name: &'static str, _: usize) -> Result<Compound<'a>, AuditError> {
+		let expected = self.composite(name);
+		Ok(self.compound(expected))
+	}
+
+	fn serialize_tuple_variant(
+		mut self,
+		name: &'static str,
+		_: u32,
+		variant: &'static str,
+		_: usize,
+	) -> Result<Compound<'a>, AuditError> {
+		let expected = self.variant(name, variant);
+		Ok(self.compound(expected))
+	}
+
+	fn serialize_map(mut self, _: Option<usize>) -> Result<Compound<'a>, AuditError> {
+		if !matches!(Expected::of(&self.ty), Expected::Any) {
+			self.mismatch();
+		}
+		Ok(self.compound(None))
+	}
+
+	fn serialize_struct(mut self, name: &'static str, _: usize) -> Result<Compound<'a>, AuditError> {
+		let expected = self.composite(name);
+		Ok(self.compound(expected))
+	}
+
+	fn serialize_struct_variant(
+		mut self,
+		name: &'static str,
+		_: u32,
+		variant: &'static str,
+		_: usize,
+	) -> Result<Compound<'a>, AuditError> {
+		let expected = self.variant(name, variant);
+		Ok(self.compound(expected))
+	}
+}
+
+impl SerializeSeq for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_element<T>(&mut self, _: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		Ok(())
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Ok(())
+	}
+}
+
+impl SerializeTuple for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_element<T>(&mut self, _: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		Ok(())
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Ok(())
+	}
+}
+
+impl SerializeMap for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_key<T>(&mut self, _: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		Ok(())
+	}
+
+	fn serialize_value<T>(&mut self, _: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		Ok(())
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Ok(())
+	}
+}
+
+impl SerializeTupleStruct for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_field<T>(&mut self, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		self.unnamed_field(value)
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Compound::end(self)
+	}
+}
+
+impl SerializeTupleVariant for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_field<T>(&mut self, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		self.unnamed_field(value)
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Compound::end(self)
+	}
+}
+
+impl SerializeStruct for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		self.named_field(name, value)
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Compound::end(self)
+	}
+}
+
+impl SerializeStructVariant for Compound<'_> {
+	type Ok = ();
+	type Error = AuditError;
+
+	fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), AuditError>
+	where
+		T: Serialize + ?Sized,
+	{
+		self.named_field(name, value)
+	}
+
+	fn end(self) -> Result<(), AuditError> {
+		Compound::end(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		HasTypeDef, HasTypeId, NamedField, Namespace, TypeDefComposite, TypeDefVariant, TypeIdCustom, UnnamedField,
+		Variant,
+	};
+
+	/// A struct whose serialized fields are given by its contents.
+	struct Inner(Vec<&'static str>);
+
+	/// A struct that contains an inner struct.
+	struct Outer(Inner);
+
+	/// An enum whose serialized variant name is given by its contents.
+	struct Kind(&'static str);
+
+	/// Returns the type identifier of a test type with the given name.
+	fn type_id(name: &'static str) -> TypeId {
+		TypeIdCustom::new(name, Namespace::new(vec!["audit"]).unwrap(), vec![]).into()
+	}
+
+	impl HasTypeId for Inner {
+		fn type_id() -> TypeId {
+			type_id("Inner")
+		}
+	}
+
+	impl HasTypeDef for Inner {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![NamedField::of::<u8>("a"), NamedField::of::<bool>("b")]).into()
+		}
+	}
+
+	impl Serialize for Inner {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			let mut state = serializer.serialize_struct("Inner", self.0.len())?;
+			for field in &self.0 {
+				state.serialize_field(field, &0u8)?;
+			}
+			state.end()
+		}
+	}
+
+	impl HasTypeId for Outer {
+		fn type_id() -> TypeId {
+			type_id("Outer")
+		}
+	}
+
+	impl HasTypeDef for Outer {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![NamedField::of::<Inner>("inner")]).into()
+		}
+	}
+
+	impl Serialize for Outer {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			let mut state = serializer.serialize_struct("Renamed", 1)?;
+			state.serialize_field("inner", &self.0)?;
+			state.end()
+		}
+	}
+
+	impl HasTypeId for Kind {
+		fn type_id() -> TypeId {
+			type_id("Kind")
+		}
+	}
+
+	impl HasTypeDef for Kind {
+		fn type_def() -> TypeDef {
+			TypeDefVariant::new(vec![
+				Variant::new("A"),
+				Variant::new("B").with_fields(vec![UnnamedField::of::<Inner>()]),
+			])
+			.into()
+		}
+	}
+
+	impl Serialize for Kind {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			match self.0 {
+				"B" => serializer.serialize_newtype_variant("Kind", 1, "B", &Inner(vec!["a"])),
+				"Map" => serializer.serialize_map(None)?.end(),
+				variant => serializer.serialize_unit_variant("Kind", 0, variant),
+			}
+		}
+	}
+
+	#[test]
+	fn fields() {
+		let audit = Audit::new();
+		assert_eq!(audit.check(&Inner(vec!["a", "b"])), Ok(vec![]));
+		assert_eq!(
+			audit.check(&Inner(vec!["a", "c"])),
+			Ok(vec![
+				Divergence::UnexpectedField { path: "c".to_string() },
+				Divergence::MissingField { path: "b".to_string() },
+			])
+		);
+		assert_eq!(audit.allow_missing_fields().check(&Inner(vec!["a"])), Ok(vec![]));
+	}
+
+	#[test]
+	fn nested_fields() {
+		let outer = Outer(Inner(vec!["b"]));
+		assert_eq!(
+			Audit::new().check(&outer),
+			Ok(vec![Divergence::MissingField {
+				path: "inner.a".to_string()
+			}])
+		);
+		assert_eq!(
+			Audit::new().allow_missing_fields().check_type_names().check(&outer),
+			Ok(vec![Divergence::RenamedType {
+				path: String::new(),
+				name: "Renamed".to_string(),
+			}])
+		);
+	}
+
+	#[test]
+	fn variants() {
+		let audit = Audit::new();
+		assert_eq!(audit.check(&Kind("A")), Ok(vec![]));
+		assert_eq!(
+			audit.check(&Kind("B")),
+			Ok(vec![Divergence::MissingField {
+				path: "B.0.b".to_string()
+			}])
+		);
+		assert_eq!(
+			audit.check(&Kind("C")),
+			Ok(vec![Divergence::UnexpectedVariant { path: "C".to_string() }])
+		);
+		assert_eq!(
+			audit.check(&Kind("Map")),
+			Ok(vec![Divergence::ShapeMismatch { path: String::new() }])
+		);
+	}
+}
@@ -0,0 +1,515 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Separates the construction of a registry from querying it.
+//!
+//! A `RegistryBuilder` registers types but neither resolves nor serializes them, while
+//! a `Registry` resolves, searches and serializes the types registered by its builder.
+//! APIs state the capability they need in their signatures by taking either a
+//! `&mut RegistryBuilder` or a `&Registry`. The registration methods of `Registry`
+//! itself are deprecated in favor of the ones of `RegistryBuilder`.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{Metadata, Registry, RegistryBuilder};
+//! /// Registers the types of the subsystem but cannot inspect the registry.
+//! fn register_types(builder: &mut RegistryBuilder) {
+//!     builder.register::<Option<u32>>();
+//!     builder.register_alias("Balance", u128::meta_type());
+//! }
+//!
+//! /// Inspects the registry but cannot register into it.
+//! fn count_types(registry: &Registry) -> usize {
+//!     registry.iter_resolved().count()
+//! }
+//!
+//! let mut builder = RegistryBuilder::new();
+//! register_types(&mut builder);
+//! let registry = builder.build();
+//! assert_eq!(count_types(&registry), 3);
+//! ```
+
+#[cfg(feature = "std")]
+use crate::pool::StringPool;
+use crate::{
+	interner::UntrackedSymbol, telemetry::RegistryHooks, tm_std::*, MetaType, Metadata, RegisterSubsystem, Registry,
+	ReservedError, Transaction, TransactionError,
+};
+
+/// A registry that may only be registered into.
+///
+/// Consumed by `build` once all types have been registered. A built registry cannot
+/// be turned back into a builder, use `RegistryBuilder::branch` in order to register
+/// further types into a copy of it instead.
+#[derive(Debug, Default, Clone)]
+pub struct RegistryBuilder {
+	/// The registry under construction.
+	registry: Registry,
+}
+
+impl RegistryBuilder {
+	/// Creates a new builder of an empty registry.
+	pub fn new() -> Self {
+		Self {
+			registry: Registry::new(),
+		}
+	}
+
+	/// Creates a new builder of an empty registry that interns its strings into the given string pool.
+	///
+	/// See `Registry::with_string_pool` for further details.
+	#[cfg(feature = "std")]
+	pub fn with_string_pool(pool: &'static StringPool) -> Self {
+		Self {
+			registry: Registry::with_string_pool(pool),
+		}
+	}
+
+	/// Creates a new builder of a registry with the given types reserving the first type symbols.
+	///
+	/// See `Registry::with_reserved` for further details.
+	///
+	/// # Errors
+	///
+	/// If the same type is reserved more than once.
+	pub fn with_reserved<T>(reserved: T) -> Result<Self, ReservedError>
+	where
+		T: IntoIterator<Item = MetaType>,
+	{
+		Registry::with_reserved(reserved).map(|registry| Self { registry })
+	}
+
+	/// Creates a new builder registering into a copy-on-write branch of the given registry.
+	///
+	/// The given registry is left unchanged, so tooling may explore speculative changes, e.g.
+	/// compare a registry with a branch that has some types added. See `Registry::branch`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	///
+	/// let mut builder = RegistryBuilder::branch(&registry);
+	/// assert_eq!(builder.register_type(&<Option<u32>>::meta_type()), symbol);
+	/// builder.register_type(&<Vec<bool>>::meta_type());
+	/// let branch = builder.build();
+	/// assert_eq!(registry.iter_resolved().count(), 2);
+	/// assert_eq!(branch.iter_resolved().count(), 5);
+	/// ```
+	pub fn branch(registry: &Registry) -> Self {
+		Self {
+			registry: registry.branch(),
+		}
+	}
+
+	/// Starts recording every subsequently registered type, discarding previous recordings.
+	///
+	/// See `Registry::start_recording` for further details.
+	pub fn start_recording(&mut self) {
+		self.registry.start_recording()
+	}
+
+	/// Installs the given hooks, replacing the ones installed before.
+	///
+	/// The hooks stay installed in the built registry.
+	pub fn set_hooks<H>(&mut self, hooks: H)
+	where
+		H: RegistryHooks + 'static,
+	{
+		self.registry.set_hooks(hooks)
+	}
+
+	/// Registers the given type and returns its associated type ID symbol.
+	pub fn register<T>(&mut self) -> UntrackedSymbol<AnyTypeId>
+	where
+		T: Metadata + ?Sized,
+	{
+		self.register_type(&T::meta_type())
+	}
+
+	/// Registers the given type into the registry and returns
+	/// its associated type ID symbol.
+	///
+	/// # Note
+	///
+	/// The returned type ID symbol stays valid for the built registry, so it can be
+	/// resolved back to the associated type definition with `Registry::resolve`.
+	pub fn register_type(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.registry.compact_type(ty)
+	}
+
+	/// Registers the given type into the registry together with auxiliary types
+	/// registered by `register` and returns the type ID symbol of the given type.
+	///
+	/// This is the sanctioned way for manual `Metadata` implementations to register helper types
+	/// that are not reachable from their definitions. The given type and all of its sub-types are
+	/// registered first and `register` is called afterwards, so that the helper types always
+	/// follow their parent type. If the type has already been registered `register` is not
+	/// called since its helper types have been registered along with it.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type_with(&<Option<u32>>::meta_type(), |builder| {
+	///     builder.register_type(&<[u32; 4]>::meta_type());
+	/// });
+	/// assert_eq!(symbol.id().get(), 1);
+	/// // The helper type follows the type and its sub-type `u32`.
+	/// assert_eq!(builder.register_type(&<[u32; 4]>::meta_type()).id().get(), 3);
+	/// ```
+	pub fn register_type_with<F>(&mut self, ty: &MetaType, register: F) -> UntrackedSymbol<AnyTypeId>
+	where
+		F: FnOnce(&mut RegistryBuilder),
+	{
+		let (inserted, symbol) = self.registry.compact_new_type(ty);
+		if inserted {
+			register(self);
+		}
+		symbol
+	}
+
+	/// Registers the given type into the registry without computing its identifier
+	/// and definition and returns its associated type ID symbol.
+	///
+	/// This is considerably cheaper than `register_type` for large registries of which
+	/// only the type symbols are needed right away since neither the type nor any of its
+	/// sub-types are compacted. This is deferred until `build` is called.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type_id_only(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	/// assert_eq!(registry.deferred_len(), 0);
+	/// assert!(registry.resolve(&symbol).is_some());
+	/// ```
+	pub fn register_type_id_only(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.registry.defer_type(ty)
+	}
+
+	/// Registers the given type under the given domain-level alias name
+	/// and returns its associated type ID symbol.
+	///
+	/// Aliases preserve names used in documentation and user interfaces, e.g. `Balance`,
+	/// even if the aliased type is a bare primitive such as `u128`.
+	/// Registering an alias name that already exists replaces the aliased type.
+	pub fn register_alias(&mut self, name: &'static str, ty: MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.registry.alias_type(name, ty)
+	}
+
+	/// Registers the given string into the registry and returns
+	/// its respective associated string symbol.
+	///
+	/// If the registry has run out of string symbols the string is dropped,
+	/// the first string symbol is returned and the registry is marked as exhausted.
+	pub fn register_string(&mut self, string: &'static str) -> UntrackedSymbol<&'static str> {
+		self.registry.compact_string(string)
+	}
+
+	/// Registers all types of the given subsystem.
+	pub fn register_subsystem<S>(&mut self)
+	where
+		S: RegisterSubsystem + ?Sized,
+	{
+		S::register_all(self)
+	}
+
+	/// Runs the given closure and commits all of its registrations at once.
+	///
+	/// Either all types registered by the closure end up in the registry or none of them
+	/// so that no partially registered and thus inconsistent registry can be observed.
+	/// Strings interned into a shared string pool are not rolled back, and installed hooks
+	/// have already been called for the registrations of a failed transaction.
+	///
+	/// # Errors
+	///
+	/// - If the transaction has been aborted by the closure.
+	/// - If an alias name of the transaction already aliases another type.
+	/// - If a type of the transaction shares its identifier with another type
+	///   of the registry that has a different definition.
+	///
+	/// In all these cases the registry is left unchanged.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder, TransactionError};
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.register_alias("Balance", u128::meta_type());
+	/// let result = builder.transaction(|tx| {
+	///     tx.register::<Option<u32>>();
+	///     tx.register_alias("Balance", u64::meta_type());
+	/// });
+	/// assert_eq!(result, Err(TransactionError::AliasConflict { name: "Balance" }));
+	/// assert_eq!(builder.build().iter_resolved().count(), 1);
+	/// ```
+	pub fn transaction<F, T>(&mut self, f: F) -> Result<T, TransactionError>
+	where
+		F: FnOnce(&mut Transaction<'_>) -> T,
+	{
+		self.registry.transact(f)
+	}
+
+	/// Records all types registered by `f` under the given tag and returns the result of `f`.
+	///
+	/// Tags nest, so types registered by an inner call are recorded under the inner tag.
+	/// This has no effect unless the registry is recording, see `start_recording`.
+	pub fn tagged<F, T>(&mut self, tag: &'static str, f: F) -> T
+	where
+		F: FnOnce(&mut RegistryBuilder) -> T,
+	{
+		let outer = self.registry.swap_tag(Some(tag));
+		let result = f(self);
+		self.registry.swap_tag(outer);
+		result
+	}
+
+	/// Completes all deferred types and returns the constructed registry.
+	pub fn build(mut self) -> Registry {
+		self.registry.complete_deferred();
+		self.registry
+	}
+}
+
+/// The registrations of a registry that have moved to `RegistryBuilder`.
+impl Registry {
+	/// Registers the given string into the registry and returns
+	/// its respective associated string symbol.
+	#[deprecated(note = "use `RegistryBuilder::register_string` instead")]
+	pub fn register_string(&mut self, string: &'static str) -> UntrackedSymbol<&'static str> {
+		self.compact_string(string)
+	}
+
+	/// Registers the given type into the registry and returns
+	/// its associated type ID symbol.
+	#[deprecated(note = "use `RegistryBuilder::register_type` instead")]
+	pub fn register_type(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.compact_type(ty)
+	}
+
+	/// Registers the given type into the registry together with auxiliary types
+	/// registered by `register` and returns the type ID symbol of the given type.
+	#[deprecated(note = "use `RegistryBuilder::register_type_with` instead")]
+	pub fn register_type_with<F>(&mut self, ty: &MetaType, register: F) -> UntrackedSymbol<AnyTypeId>
+	where
+		F: FnOnce(&mut Registry),
+	{
+		let (inserted, symbol) = self.compact_new_type(ty);
+		if inserted {
+			register(self);
+		}
+		symbol
+	}
+
+	/// Registers the given type into the registry without computing its identifier
+	/// and definition and returns its associated type ID symbol.
+	#[deprecated(note = "use `RegistryBuilder::register_type_id_only` instead")]
+	pub fn register_type_id_only(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.defer_type(ty)
+	}
+
+	/// Registers the given type under the given domain-level alias name
+	/// and returns its associated type ID symbol.
+	#[deprecated(note = "use `RegistryBuilder::register_alias` instead")]
+	pub fn register_alias(&mut self, name: &'static str, ty: MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.alias_type(name, ty)
+	}
+
+	/// Registers all types of the given subsystem into the registry.
+	#[deprecated(note = "use `RegistryBuilder::register_subsystem` instead")]
+	pub fn register_subsystem<S>(&mut self)
+	where
+		S: RegisterSubsystem + ?Sized,
+	{
+		let mut builder = RegistryBuilder {
+			registry: core::mem::take(self),
+		};
+		S::register_all(&mut builder);
+		*self = builder.registry;
+	}
+
+	/// Runs the given closure and commits all of its registrations at once.
+	#[deprecated(note = "use `RegistryBuilder::transaction` instead")]
+	pub fn transaction<F, T>(&mut self, f: F) -> Result<T, TransactionError>
+	where
+		F: FnOnce(&mut Transaction<'_>) -> T,
+	{
+		self.transact(f)
+	}
+
+	/// Records all types registered by `f` under the given tag and returns the result of `f`.
+	#[deprecated(note = "use `RegistryBuilder::tagged` instead")]
+	pub fn tagged<F, T>(&mut self, tag: &'static str, f: F) -> T
+	where
+		F: FnOnce(&mut Registry) -> T,
+	{
+		let outer = self.swap_tag(Some(tag));
+		let result = f(self);
+		self.swap_tag(outer);
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn builds_the_same_registry_as_deprecated_registrations() {
+		let mut builder = RegistryBuilder::new();
+		builder.register::<Option<u32>>();
+		builder.register_alias("Balance", u128::meta_type());
+		builder.register_string("extra");
+
+		let mut registry = Registry::new();
+		#[allow(deprecated)]
+		{
+			registry.register_type(&<Option<u32>>::meta_type());
+			registry.register_alias("Balance", u128::meta_type());
+			registry.register_string("extra");
+		}
+		assert_eq!(builder.build(), registry);
+	}
+
+	#[test]
+	fn register_subsystem() {
+		struct Subsystem;
+
+		impl RegisterSubsystem for Subsystem {
+			fn register_all(builder: &mut RegistryBuilder) {
+				crate::register_types!(builder, u32, Option<bool>,);
+			}
+		}
+
+		let mut builder = RegistryBuilder::new();
+		builder.register_subsystem::<Subsystem>();
+
+		let mut expected = RegistryBuilder::new();
+		expected.register::<u32>();
+		expected.register::<Option<bool>>();
+		assert_eq!(builder.build(), expected.build());
+	}
+
+	#[test]
+	fn register_type_with_orders_helpers_after_parent() {
+		let mut builder = RegistryBuilder::new();
+		let mut calls = 0;
+		let mut register = |builder: &mut RegistryBuilder| {
+			calls += 1;
+			builder.register::<bool>().id().get()
+		};
+		let mut helper = 0;
+		let option = builder.register_type_with(&<Option<u8>>::meta_type(), |builder| {
+			helper = register(builder);
+		});
+		assert_eq!((option.id().get(), helper), (1, 3));
+		// Registering the type again neither calls the closure nor changes any symbols.
+		let again = builder.register_type_with(&<Option<u8>>::meta_type(), |builder| {
+			register(builder);
+		});
+		assert_eq!((again, calls), (option, 1));
+	}
+
+	#[test]
+	fn build_completes_deferred_types() {
+		let mut builder = RegistryBuilder::new();
+		let deferred = builder.register_type_id_only(&<Option<u8>>::meta_type());
+		let registry = builder.build();
+		assert_eq!(registry.deferred_len(), 0);
+		assert!(registry.resolve(&deferred).is_some());
+
+		let mut expected = RegistryBuilder::new();
+		expected.register::<Option<u8>>();
+		assert_eq!(registry, expected.build());
+	}
+
+	#[test]
+	fn with_reserved_fills_the_first_symbols() {
+		let reserved = vec![u32::meta_type(), bool::meta_type()];
+		let mut builder = RegistryBuilder::with_reserved(reserved.clone()).unwrap();
+		builder.register::<Option<u8>>();
+		let registry = builder.build();
+		assert_eq!(registry.verify_reserved(reserved), Ok(()));
+		assert_eq!(
+			registry.type_symbol::<Option<u8>>().map(|symbol| symbol.id().get()),
+			Some(3)
+		);
+		assert_eq!(
+			RegistryBuilder::with_reserved(vec![u32::meta_type(), u32::meta_type()]).map(RegistryBuilder::build),
+			Err(ReservedError::DuplicateType { index: 1 })
+		);
+	}
+
+	#[test]
+	fn failed_transaction_leaves_builder_unchanged() {
+		let mut builder = RegistryBuilder::new();
+		builder.register::<u8>();
+		let before = builder.clone().build();
+		let result = builder.transaction(|tx| {
+			tx.register::<Option<u16>>();
+			tx.abort();
+		});
+		assert_eq!(result, Err(TransactionError::Aborted));
+		assert_eq!(builder.build(), before);
+	}
+
+	#[test]
+	fn tagged_records_nested_tags() {
+		let mut builder = RegistryBuilder::new();
+		builder.start_recording();
+		builder.tagged("outer", |builder| {
+			builder.register::<Option<u8>>();
+			builder.tagged("inner", |builder| builder.register::<bool>());
+		});
+		builder.register::<u16>();
+		let tags = builder
+			.build()
+			.recorded()
+			.iter()
+			.map(|registration| registration.tag())
+			.collect::<Vec<_>>();
+		assert_eq!(tags, vec![Some("outer"), Some("outer"), Some("inner"), None]);
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn deprecated_subsystem_registration_keeps_the_string_pool() {
+		struct Subsystem;
+
+		impl RegisterSubsystem for Subsystem {
+			fn register_all(builder: &mut RegistryBuilder) {
+				crate::register_types!(builder, u8, Option<u8>);
+			}
+		}
+
+		let pool = crate::pool::StringPool::global();
+		let mut registry = Registry::with_string_pool(pool);
+		#[allow(deprecated)]
+		registry.register_subsystem::<Subsystem>();
+
+		let mut builder = RegistryBuilder::with_string_pool(pool);
+		builder.register_subsystem::<Subsystem>();
+		assert_eq!(registry, builder.build());
+	}
+}
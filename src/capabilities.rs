@@ -0,0 +1,159 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Capabilities of the producer of serialized metadata.
+//!
+//! Registries serialized with a layout carry the capabilities of their producer in their
+//! header next to the format version. This allows clients to adapt to producers that have
+//! been compiled with different optional features without trial-and-error parsing.
+
+use core::ops::{BitAnd, BitOr};
+#[cfg(feature = "scale")]
+use parity_scale_codec::{Decode, Encode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A set of capabilities of a metadata producer.
+///
+/// Serialized as the raw bits. Unknown bits of newer producers are kept so
+/// that capabilities can be passed on without loss.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+	/// The producer is able to serialize registries in the nested layout.
+	pub const NESTED_LAYOUT: Self = Self(1 << 0);
+	/// Custom type identifiers may carry stable identifiers.
+	pub const STABLE_IDS: Self = Self(1 << 1);
+	/// Named fields may be assigned to groups.
+	pub const FIELD_GROUPS: Self = Self(1 << 2);
+	/// Enum variants may carry application-level error codes.
+	pub const ERROR_CODES: Self = Self(1 << 3);
+	/// Named fields may be marked with a sensitivity.
+	pub const SENSITIVITIES: Self = Self(1 << 4);
+	/// Registries may have aliases.
+	pub const ALIASES: Self = Self(1 << 5);
+	/// Boxes and references are kept in the metadata instead of being transparent.
+	///
+	/// Set if the producer has been compiled with the `reference-types` feature.
+	pub const REFERENCE_TYPES: Self = Self(1 << 6);
+	/// Enum variants carrying fields may have discriminants.
+	pub const DISCRIMINATED_VARIANTS: Self = Self(1 << 7);
+	/// Retired types may be kept as tombstones.
+	pub const TOMBSTONES: Self = Self(1 << 8);
+	/// Enums may encode their fieldless variant as the zero value of a field.
+	pub const NICHES: Self = Self(1 << 9);
+	/// Types may be opaque raw payloads with a declared format.
+	pub const RAW_PAYLOADS: Self = Self(1 << 10);
+	/// Custom types, named fields and enum variants may carry documentation.
+	pub const DOCS: Self = Self(1 << 11);
+	/// Named fields may carry a format hint for displaying their values.
+	pub const DISPLAY_HINTS: Self = Self(1 << 12);
+	/// Custom type identifiers may carry the values of const generic parameters.
+	pub const CONST_PARAMS: Self = Self(1 << 13);
+
+	/// Returns the empty set of capabilities.
+	///
+	/// This is assumed for producers that predate capabilities.
+	pub const fn empty() -> Self {
+		Self(0)
+	}
+
+	/// Returns the capabilities of this build of the crate.
+	pub const fn current() -> Self {
+		let bits = Self::NESTED_LAYOUT.0
+			| Self::STABLE_IDS.0
+			| Self::FIELD_GROUPS.0
+			| Self::ERROR_CODES.0
+			| Self::SENSITIVITIES.0
+			| Self::ALIASES.0
+			| Self::DISCRIMINATED_VARIANTS.0
+			| Self::TOMBSTONES.0
+			| Self::NICHES.0
+			| Self::RAW_PAYLOADS.0
+			| Self::DOCS.0
+			| Self::DISPLAY_HINTS.0
+			| Self::CONST_PARAMS.0;
+		#[cfg(feature = "reference-types")]
+		let bits = bits | Self::REFERENCE_TYPES.0;
+		Self(bits)
+	}
+
+	/// Creates capabilities from the given raw bits including unknown ones.
+	pub const fn from_bits(bits: u32) -> Self {
+		Self(bits)
+	}
+
+	/// Returns the raw bits of the capabilities.
+	pub const fn bits(self) -> u32 {
+		self.0
+	}
+
+	/// Returns `true` if all of the given capabilities are set.
+	pub const fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// Returns the capabilities of `required` that are not set.
+	///
+	/// This is useful in order to report what a producer lacks.
+	pub const fn missing(self, required: Self) -> Self {
+		Self(required.0 & !self.0)
+	}
+
+	/// Returns `true` if no capabilities are set.
+	pub const fn is_empty(self) -> bool {
+		self.0 == 0
+	}
+}
+
+impl BitOr for Capabilities {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
+impl BitAnd for Capabilities {
+	type Output = Self;
+
+	fn bitand(self, rhs: Self) -> Self {
+		Self(self.0 & rhs.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn set_operations() {
+		let required = Capabilities::STABLE_IDS | Capabilities::REFERENCE_TYPES;
+		let producer = Capabilities::NESTED_LAYOUT | Capabilities::STABLE_IDS;
+		assert!(producer.contains(Capabilities::STABLE_IDS));
+		assert!(!producer.contains(required));
+		assert_eq!(producer.missing(required), Capabilities::REFERENCE_TYPES);
+		assert_eq!(producer & required, Capabilities::STABLE_IDS);
+		assert!(Capabilities::empty().is_empty());
+		assert!(Capabilities::current().contains(Capabilities::NESTED_LAYOUT | Capabilities::ALIASES));
+		// Unknown bits of newer producers are kept.
+		assert_eq!(Capabilities::from_bits(1 << 31).bits(), 1 << 31);
+	}
+}
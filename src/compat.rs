@@ -0,0 +1,636 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compatibility checks between registries.
+//!
+//! Compares the types of an old and a new registry, e.g. of two releases of the same
+//! crate, and reports all added, removed and changed types. Since type symbols are
+//! not stable across registries types are matched by their fully qualified paths.
+//!
+//! # Crate renames
+//!
+//! Renaming a crate changes the first segment of the namespaces of all of its types.
+//! By default such types no longer match which reports all of them as removed and added.
+//! A `NamespaceEquivalence` maps renamed crates onto each other so that logically
+//! identical types across renames still compare equal.
+
+use crate::tm_std::*;
+use crate::{
+	form::CompactForm, interner::UntrackedSymbol, Builtin, DiscriminantValue, EnumRepr, EnumVariant, Field, NamedField,
+	Registry, TypeDef, TypeDefComposite, TypeDefVariant, TypeId, UnnamedField,
+};
+
+/// Determines which namespaces are considered equal upon comparing registries.
+///
+/// By default namespaces are only equal if all of their segments are equal.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::compat::NamespaceEquivalence;
+/// let equivalence = NamespaceEquivalence::new().with_rename("old_crate", "new_crate");
+/// ```
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct NamespaceEquivalence {
+	/// Maps the names of renamed crates to their new names.
+	renames: BTreeMap<String, String>,
+}
+
+impl NamespaceEquivalence {
+	/// Creates a new strict namespace equivalence.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Treats namespaces starting with the crate name `old` as equal to
+	/// the same namespaces starting with the crate name `new` instead.
+	pub fn with_rename(mut self, old: &str, new: &str) -> Self {
+		self.renames.insert(old.to_string(), new.to_string());
+		self
+	}
+
+	/// Returns the canonical name of the given crate name.
+	fn canonical<'a>(&'a self, segment: &'a str) -> &'a str {
+		self.renames.get(segment).map(String::as_str).unwrap_or(segment)
+	}
+}
+
+/// A difference between the types of two registries.
+#[derive(PartialEq, Eq, Debug)]
+pub enum TypeChange {
+	/// If a type only exists in the new registry.
+	Added {
+		/// The fully qualified path of the type.
+		path: String,
+	},
+	/// If a type only exists in the old registry.
+	Removed {
+		/// The fully qualified path of the type.
+		path: String,
+	},
+	/// If the definition of a type differs between both registries.
+	Changed {
+		/// The fully qualified path of the type.
+		path: String,
+	},
+}
+
+impl TypeChange {
+	/// Returns the fully qualified path of the changed type.
+	///
+	/// Namespaces of renamed crates are reported with the new crate name.
+	pub fn path(&self) -> &str {
+		match self {
+			TypeChange::Added { path } | TypeChange::Removed { path } | TypeChange::Changed { path } => path,
+		}
+	}
+}
+
+/// Returns all differences between the types of the old and the new registry
+/// in order of the paths of the changed types.
+///
+/// Namespaces are compared using the given namespace equivalence.
+pub fn diff(old: &Registry, new: &Registry, equivalence: &NamespaceEquivalence) -> Vec<TypeChange> {
+	let old = Signatures::new(old, equivalence).collect();
+	let new = Signatures::new(new, equivalence).collect();
+	let mut changes = Vec::new();
+	for (path, signatures) in &old {
+		match new.get(path) {
+			None => changes.push(TypeChange::Removed { path: path.clone() }),
+			Some(new_signatures) if new_signatures != signatures => {
+				changes.push(TypeChange::Changed { path: path.clone() })
+			}
+			Some(_) => (),
+		}
+	}
+	changes.extend(
+		new.keys()
+			.filter(|path| !old.contains_key(*path))
+			.map(|path| TypeChange::Added { path: path.clone() }),
+	);
+	changes.sort_by(|lhs, rhs| lhs.path().cmp(rhs.path()));
+	changes
+}
+
+/// The kind of change of a type symbol between two registries.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ChangeKind {
+	/// If the type has the same definition in both registries.
+	Unchanged,
+	/// If the definition of the type differs between both registries.
+	Changed,
+	/// If the type only exists in the new registry.
+	Added,
+	/// If the type only exists in the old registry.
+	Removed,
+}
+
+/// The mapping of a type symbol of the old registry onto the new registry.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SymbolRemap {
+	/// The fully qualified path of the type.
+	///
+	/// Namespaces of renamed crates are reported with the new crate name.
+	pub path: String,
+	/// The symbol of the type within the old registry unless the type was added.
+	pub old: Option<UntrackedSymbol<AnyTypeId>>,
+	/// The symbol of the type within the new registry unless the type was removed.
+	pub new: Option<UntrackedSymbol<AnyTypeId>>,
+	/// The kind of change of the type.
+	pub kind: ChangeKind,
+}
+
+/// Returns the mappings of the type symbols of the old registry onto the type symbols
+/// of the new registry for every type of both registries in order of their paths.
+///
+/// The changes are expected to be the result of `diff` for the same registries and
+/// namespace equivalence. Types sharing the same path, e.g. `str` and `String`, are paired
+/// by identical definitions first and otherwise in order of their symbols.
+///
+/// This allows migrating data persisted along with the symbols of the old registry,
+/// e.g. values keyed by type, to the symbols of the new registry.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{compat::{self, ChangeKind, NamespaceEquivalence}, Metadata, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// builder.register_type(&<(u32, bool)>::meta_type());
+/// let old = builder.build();
+/// let mut builder = RegistryBuilder::new();
+/// builder.register_type(&<(u64, bool)>::meta_type());
+/// let new = builder.build();
+///
+/// let equivalence = NamespaceEquivalence::new();
+/// let changes = compat::diff(&old, &new, &equivalence);
+/// let kinds = compat::remap(&old, &new, &changes, &equivalence)
+///     .map(|remap| (remap.path, remap.kind))
+///     .collect::<Vec<_>>();
+/// assert!(kinds.contains(&("bool".to_string(), ChangeKind::Unchanged)));
+/// assert!(kinds.contains(&("u32".to_string(), ChangeKind::Removed)));
+/// assert!(kinds.contains(&("u64".to_string(), ChangeKind::Added)));
+/// ```
+pub fn remap(old: &Registry, new: &Registry, changes: &[TypeChange], equivalence: &NamespaceEquivalence) -> SymbolRemaps {
+	let mut old_symbols = Signatures::new(old, equivalence).symbols();
+	let mut new_symbols = Signatures::new(new, equivalence).symbols();
+	let paths = old_symbols
+		.keys()
+		.chain(new_symbols.keys())
+		.cloned()
+		.collect::<BTreeSet<_>>();
+	let mut remaps = Vec::new();
+	for path in paths {
+		let mut old_types = old_symbols.remove(&path).unwrap_or_default();
+		let mut new_types = new_symbols.remove(&path).unwrap_or_default();
+		let changed = changes
+			.iter()
+			.any(|change| matches!(change, TypeChange::Changed { path: changed } if *changed == path));
+		let record = |old, new, kind| SymbolRemap {
+			path: path.clone(),
+			old,
+			new,
+			kind,
+		};
+		old_types.retain(|(old_symbol, definition)| {
+			match new_types.iter().position(|(_, new_definition)| new_definition == definition) {
+				Some(index) => {
+					let (new_symbol, _) = new_types.remove(index);
+					remaps.push(record(Some(*old_symbol), Some(new_symbol), ChangeKind::Unchanged));
+					false
+				}
+				None => true,
+			}
+		});
+		let mut new_types = new_types.into_iter();
+		for (old_symbol, _) in old_types {
+			match new_types.next() {
+				Some((new_symbol, _)) => {
+					let kind = if changed { ChangeKind::Changed } else { ChangeKind::Unchanged };
+					remaps.push(record(Some(old_symbol), Some(new_symbol), kind))
+				}
+				None => remaps.push(record(Some(old_symbol), None, ChangeKind::Removed)),
+			}
+		}
+		remaps.extend(new_types.map(|(new_symbol, _)| record(None, Some(new_symbol), ChangeKind::Added)));
+	}
+	SymbolRemaps {
+		remaps: remaps.into_iter(),
+	}
+}
+
+/// An iterator over the symbol mappings between two registries, see `remap`.
+#[derive(Clone, Debug)]
+pub struct SymbolRemaps {
+	/// The remaining mappings in order.
+	remaps: vec::IntoIter<SymbolRemap>,
+}
+
+impl Iterator for SymbolRemaps {
+	type Item = SymbolRemap;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.remaps.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.remaps.size_hint()
+	}
+}
+
+impl ExactSizeIterator for SymbolRemaps {}
+
+/// Renders the paths and definitions of the types of a registry canonically.
+struct Signatures<'a> {
+	/// The registry of the types.
+	registry: &'a Registry,
+	/// The equivalence used to canonicalize namespaces.
+	equivalence: &'a NamespaceEquivalence,
+}
+
+impl<'a> Signatures<'a> {
+	/// Creates a renderer for the types of the given registry.
+	fn new(registry: &'a Registry, equivalence: &'a NamespaceEquivalence) -> Self {
+		Self { registry, equivalence }
+	}
+
+	/// Returns the canonical definitions of all types of the registry by their paths.
+	///
+	/// Distinct types may share the same path, e.g. `str` and `String`, and thus
+	/// all of their distinct definitions are collected in order.
+	fn collect(&self) -> BTreeMap<String, Vec<String>> {
+		let mut signatures = BTreeMap::<String, Vec<String>>::new();
+		for (symbol, id_def) in self.registry.types() {
+			let definitions = signatures.entry(self.path(symbol)).or_default();
+			definitions.push(self.definition(id_def.def()));
+			definitions.sort();
+			definitions.dedup();
+		}
+		signatures
+	}
+
+	/// Returns the symbols of all types of the registry along with their canonical
+	/// definitions by their paths in order of their symbols.
+	fn symbols(&self) -> BTreeMap<String, Vec<(UntrackedSymbol<AnyTypeId>, String)>> {
+		let mut symbols = BTreeMap::<String, Vec<_>>::new();
+		for (symbol, id_def) in self.registry.types() {
+			symbols
+				.entry(self.path(symbol))
+				.or_default()
+				.push((symbol, self.definition(id_def.def())));
+		}
+		symbols
+	}
+
+	/// Returns the string associated with the given string symbol.
+	fn string(&self, symbol: UntrackedSymbol<&'static str>) -> &'static str {
+		self.registry.resolved_string(symbol)
+	}
+
+	/// Returns the canonical paths of the given types separated by commas.
+	fn paths(&self, symbols: &[UntrackedSymbol<AnyTypeId>]) -> String {
+		symbols
+			.iter()
+			.map(|symbol| self.path(*symbol))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
+	/// Returns the canonical fully qualified path of the given type
+	/// including the paths of its generic parameters.
+	fn path(&self, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+		let id = match self.registry.resolve_type(symbol) {
+			Some(id_def) => id_def.id(),
+			None => return format!("#{}", symbol.id()),
+		};
+		match id {
+			TypeId::Custom(custom) => {
+				let mut path = String::new();
+				for (index, segment) in custom.namespace().segments().iter().enumerate() {
+					let segment = self.string(*segment);
+					path.push_str(if index == 0 {
+						self.equivalence.canonical(segment)
+					} else {
+						segment
+					});
+					path.push_str("::");
+				}
+				path.push_str(self.string(*custom.name()));
+				let params = custom
+					.type_params()
+					.iter()
+					.map(|param| self.path(*param))
+					.chain(custom.const_params().iter().map(ToString::to_string))
+					.collect::<Vec<_>>();
+				if !params.is_empty() {
+					path.push_str(&format!("<{}>", params.join(", ")));
+				}
+				path
+			}
+			TypeId::Slice(slice) => format!("[{}]", self.path(*slice.type_param())),
+			TypeId::Array(array) => format!("[{}; {}]", self.path(array.type_param), array.len),
+			TypeId::Tuple(tuple) => format!("({})", self.paths(&tuple.type_params)),
+			TypeId::Primitive(primitive) => primitive.name().to_string(),
+		}
+	}
+
+	/// Returns the canonical rendering of the given named fields.
+	fn named_fields(&self, fields: &[NamedField<CompactForm>]) -> String {
+		fields
+			.iter()
+			.map(|field| format!("{}: {}", self.string(*field.name()), self.path(*field.ty())))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
+	/// Returns the canonical rendering of the given unnamed fields.
+	fn unnamed_fields(&self, fields: &[UnnamedField<CompactForm>]) -> String {
+		fields
+			.iter()
+			.map(|field| self.path(*field.ty()))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
+	/// Returns the canonical rendering of the given named or unnamed fields.
+	fn fields(&self, fields: &[Field<CompactForm>]) -> String {
+		fields
+			.iter()
+			.map(|field| match field.name() {
+				Some(name) => format!("{}: {}", self.string(*name), self.path(*field.ty())),
+				None => self.path(*field.ty()),
+			})
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
+	/// Returns the canonical rendering of the given composite.
+	///
+	/// Composites render like the equivalent struct or tuple-struct.
+	fn composite(&self, def: &TypeDefComposite<CompactForm>) -> String {
+		let fields = self.fields(def.fields());
+		if def.is_named() {
+			format!("struct {{ {} }}", fields)
+		} else {
+			format!("struct ({})", fields)
+		}
+	}
+
+	/// Returns the canonical rendering of the given enum representation.
+	fn repr(&self, repr: &EnumRepr<CompactForm>) -> String {
+		match repr {
+			EnumRepr::External => "external".to_string(),
+			EnumRepr::Internal { tag } => format!("internal({})", self.string(*tag)),
+			EnumRepr::Adjacent { tag, content } => {
+				format!("adjacent({}, {})", self.string(*tag), self.string(*content))
+			}
+			EnumRepr::Untagged => "untagged".to_string(),
+		}
+	}
+
+	/// Returns the canonical rendering of the given discriminant value of a union.
+	///
+	/// Strings are quoted in order to tell them apart from integers.
+	fn discriminant(&self, value: &DiscriminantValue<CompactForm>) -> String {
+		match value {
+			DiscriminantValue::UInt(value) => value.to_string(),
+			DiscriminantValue::Int(value) => value.to_string(),
+			DiscriminantValue::Str(value) => format!("{:?}", self.string(*value)),
+		}
+	}
+
+	/// Returns the canonical rendering of the given variant definition.
+	///
+	/// C-like enums render like the equivalent C-like enum and all other enums render
+	/// like the equivalent Rust enum with the discriminants of their variants, if any.
+	fn variant_def(&self, def: &TypeDefVariant<CompactForm>) -> String {
+		let variants = def
+			.variants()
+			.iter()
+			.map(|variant| {
+				let name = self.string(*variant.name());
+				let fields = self.fields(variant.fields());
+				let mut rendered = if variant.fields().is_empty() {
+					name.to_string()
+				} else if variant.fields().iter().all(|field| field.name().is_some()) {
+					format!("{} {{ {} }}", name, fields)
+				} else {
+					format!("{}({})", name, fields)
+				};
+				if let Some(discriminant) = variant.discriminant() {
+					rendered.push_str(&format!(" = {}", discriminant));
+				}
+				rendered + &code(variant.code())
+			})
+			.collect::<Vec<_>>();
+		if def.is_clike() {
+			format!("enum {{ {} }}", variants.join(", "))
+		} else {
+			let niche = if def.is_niche() { " niche" } else { "" };
+			format!("enum {}{} {{ {} }}", self.repr(def.repr()), niche, variants.join(", "))
+		}
+	}
+
+	/// Returns the canonical rendering of the given type definition.
+	///
+	/// Referred types are rendered by their canonical paths.
+	fn definition(&self, def: &TypeDef<CompactForm>) -> String {
+		match def {
+			TypeDef::Builtin(Builtin::Builtin) => "builtin".to_string(),
+			TypeDef::Builtin(Builtin::Json) => "json".to_string(),
+			TypeDef::Builtin(Builtin::Tombstone) => "tombstone".to_string(),
+			TypeDef::Builtin(Builtin::Raw(format)) => format!("raw {}", format.name()),
+			TypeDef::Struct(def) => format!("struct {{ {} }}", self.named_fields(def.fields())),
+			TypeDef::TupleStruct(def) => format!("struct ({})", self.unnamed_fields(def.fields())),
+			TypeDef::Composite(def) => self.composite(def),
+			TypeDef::ClikeEnum(def) => {
+				let variants = def
+					.variants()
+					.iter()
+					.map(|variant| {
+						format!(
+							"{} = {}{}",
+							self.string(*variant.name()),
+							variant.discriminant(),
+							code(variant.code())
+						)
+					})
+					.collect::<Vec<_>>();
+				format!("enum {{ {} }}", variants.join(", "))
+			}
+			TypeDef::Enum(def) => {
+				let repr = self.repr(def.repr());
+				let variants = def
+					.variants()
+					.iter()
+					.map(|variant| {
+						let rendered = match variant {
+							EnumVariant::Unit(variant) => self.string(*variant.name()).to_string(),
+							EnumVariant::Struct(variant) => format!(
+								"{} {{ {} }}",
+								self.string(*variant.name()),
+								self.named_fields(variant.fields())
+							),
+							EnumVariant::TupleStruct(variant) => format!(
+								"{}({})",
+								self.string(*variant.name()),
+								self.unnamed_fields(variant.fields())
+							),
+						};
+						rendered + &code(variant.code())
+					})
+					.collect::<Vec<_>>();
+				format!("enum {} {{ {} }}", repr, variants.join(", "))
+			}
+			TypeDef::Variant(def) => self.variant_def(def),
+			TypeDef::Union(def) => {
+				let mut rendered = format!("union {{ {} }}", self.named_fields(def.fields()));
+				if let Some(discriminator) = def.discriminator() {
+					let field = discriminator
+						.field_path()
+						.iter()
+						.map(|segment| self.string(*segment))
+						.collect::<Vec<_>>();
+					let mapping = discriminator
+						.mapping()
+						.iter()
+						.map(|mapping| {
+							format!(
+								"{} => {}",
+								self.discriminant(mapping.value()),
+								self.string(*mapping.member())
+							)
+						})
+						.collect::<Vec<_>>();
+					rendered.push_str(&format!(" by {} {{ {} }}", field.join("."), mapping.join(", ")));
+				}
+				rendered
+			}
+		}
+	}
+}
+
+/// Returns the canonical rendering of the given optional error code of a variant.
+fn code(code: Option<u32>) -> String {
+	code.map(|code| format!(" @ {}", code)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HasTypeDef, HasTypeId, MetaType, Metadata, Namespace, TypeDefComposite, TypeIdCustom};
+	use core::marker::PhantomData;
+
+	/// A struct `Foo` with a single field `a` of type `T` within the given crate.
+	struct Foo<T>(PhantomData<T>);
+
+	/// The crate names used by the test types.
+	trait Crate {
+		const NAME: &'static str;
+	}
+
+	struct OldCrate;
+	struct NewCrate;
+
+	impl Crate for OldCrate {
+		const NAME: &'static str = "old_crate";
+	}
+
+	impl Crate for NewCrate {
+		const NAME: &'static str = "new_crate";
+	}
+
+	impl<C: Crate + 'static, T: Metadata + 'static> HasTypeId for Foo<(C, T)> {
+		fn type_id() -> TypeId {
+			TypeIdCustom::new("Foo", Namespace::new(vec![C::NAME, "module"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl<C: Crate + 'static, T: Metadata + 'static> HasTypeDef for Foo<(C, T)> {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![NamedField::of::<T>("a")]).into()
+		}
+	}
+
+	fn registry_of(ty: MetaType) -> Registry {
+		let mut registry = Registry::new();
+		registry.compact_type(&ty);
+		registry
+	}
+
+	#[test]
+	fn renamed_crates_compare_equal() {
+		let old = registry_of(<Foo<(OldCrate, u32)>>::meta_type());
+		let new = registry_of(<Foo<(NewCrate, u32)>>::meta_type());
+		assert_eq!(
+			diff(&old, &new, &NamespaceEquivalence::new()),
+			vec![
+				TypeChange::Added {
+					path: "new_crate::module::Foo".to_string()
+				},
+				TypeChange::Removed {
+					path: "old_crate::module::Foo".to_string()
+				},
+			]
+		);
+		let equivalence = NamespaceEquivalence::new().with_rename("old_crate", "new_crate");
+		assert_eq!(diff(&old, &new, &equivalence), vec![]);
+		let changed = registry_of(<Foo<(NewCrate, u64)>>::meta_type());
+		assert_eq!(
+			diff(&old, &changed, &equivalence),
+			vec![
+				TypeChange::Changed {
+					path: "new_crate::module::Foo".to_string()
+				},
+				TypeChange::Removed {
+					path: "u32".to_string()
+				},
+				TypeChange::Added {
+					path: "u64".to_string()
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn remap_symbols_of_renamed_crates() {
+		let old = registry_of(<Foo<(OldCrate, u32)>>::meta_type());
+		let new = registry_of(<Foo<(NewCrate, u64)>>::meta_type());
+		let equivalence = NamespaceEquivalence::new().with_rename("old_crate", "new_crate");
+		let changes = diff(&old, &new, &equivalence);
+		let symbol = |registry: &Registry, path: &str| {
+			let signatures = Signatures::new(registry, &equivalence);
+			registry
+				.types()
+				.map(|(symbol, _)| symbol)
+				.find(|symbol| signatures.path(*symbol) == path)
+		};
+		let remap = |path: &str, kind| SymbolRemap {
+			path: path.to_string(),
+			old: symbol(&old, path),
+			new: symbol(&new, path),
+			kind,
+		};
+		assert_eq!(
+			super::remap(&old, &new, &changes, &equivalence).collect::<Vec<_>>(),
+			vec![
+				remap("new_crate::module::Foo", ChangeKind::Changed),
+				remap("u32", ChangeKind::Removed),
+				remap("u64", ChangeKind::Added),
+			]
+		);
+		assert!(super::remap(&old, &old, &[], &equivalence).all(|remap| remap.kind == ChangeKind::Unchanged));
+	}
+}
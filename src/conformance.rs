@@ -0,0 +1,355 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance test vectors of the serialized registry format.
+//!
+//! The vectors are the canonical serializations of a few small registries in every layout.
+//! They are stored as pretty printed JSON files in the `conformance/v<VERSION>` directory of
+//! this crate, named `<vector>.<layout>.json`, and embedded into it. Alternative
+//! implementations, e.g. readers written in JavaScript or Python, may consume the files
+//! directly or be bridged into `verify_conformance` in order to validate their output
+//! against this reference implementation.
+//!
+//! Vectors of a published version never change. Changes to the format bump `VERSION`
+//! and add a new directory next to the previous ones.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::conformance::{reference_producer, verify_conformance};
+//! verify_conformance(reference_producer).unwrap();
+//! ```
+
+// Test utilities report mismatches by panicking just like the assertions they complement.
+#![cfg_attr(
+	feature = "no-panic",
+	allow(clippy::expect_used, clippy::indexing_slicing, clippy::panic)
+)]
+
+use crate::layout::Layout;
+use crate::snapshot::diff;
+use crate::tm_std::*;
+use crate::{
+	EnumRepr, HasTypeDef, HasTypeId, Metadata, NamedField, Namespace, RegistryBuilder, TypeDef, TypeDefUnion,
+	TypeDefVariant, TypeId, TypeIdCustom, UnionDiscriminator, UnnamedField, Variant,
+};
+use core::cmp::Ordering;
+use core::fmt::{Display, Error as FmtError, Formatter};
+use core::num::{NonZeroU32, NonZeroU64};
+use core::ops::ControlFlow;
+use std::collections::BTreeMap;
+
+/// The version of the format the vectors conform to.
+pub const VERSION: u32 = 1;
+
+/// All layouts in the order of the expected serializations of a vector.
+pub const LAYOUTS: [Layout; 3] = [Layout::Flat, Layout::Nested, Layout::Tagged];
+
+/// Returns the name of the layout as used in the file names of the vectors.
+pub fn layout_name(layout: Layout) -> &'static str {
+	match layout {
+		Layout::Flat => "flat",
+		Layout::Nested => "nested",
+		Layout::Tagged => "tagged",
+	}
+}
+
+/// A conformance test vector.
+#[derive(Clone, Copy)]
+pub struct Vector {
+	/// The name of the vector.
+	name: &'static str,
+	/// What the vector covers.
+	description: &'static str,
+	/// Registers the types of the vector into an empty registry.
+	register: fn(&mut RegistryBuilder),
+	/// The expected serializations in the order of `LAYOUTS`.
+	expected: [&'static str; 3],
+}
+
+impl core::fmt::Debug for Vector {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.debug_struct("Vector").field("name", &self.name).finish()
+	}
+}
+
+impl Vector {
+	/// Returns the name of the vector.
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// Returns what the vector covers.
+	pub fn description(&self) -> &'static str {
+		self.description
+	}
+
+	/// Registers the types of the vector into the given builder just like the reference producer.
+	pub fn register(&self, builder: &mut RegistryBuilder) {
+		(self.register)(builder)
+	}
+
+	/// Returns the expected serialization of the vector in the given layout.
+	pub fn expected(&self, layout: Layout) -> &'static str {
+		self.expected[LAYOUTS
+			.iter()
+			.position(|other| *other == layout)
+			.expect("all layouts have vectors")]
+	}
+
+	/// Returns the file name of the expected serialization in the given layout.
+	pub fn file_name(&self, layout: Layout) -> String {
+		format!("{}.{}.json", self.name, layout_name(layout))
+	}
+}
+
+/// Declares a vector with its expected serializations embedded from the `conformance` directory.
+macro_rules! vector {
+	( $name:literal, $description:literal, $register:expr ) => {
+		Vector {
+			name: $name,
+			description: $description,
+			register: $register,
+			expected: [
+				include_str!(concat!("../conformance/v1/", $name, ".flat.json")),
+				include_str!(concat!("../conformance/v1/", $name, ".nested.json")),
+				include_str!(concat!("../conformance/v1/", $name, ".tagged.json")),
+			],
+		}
+	};
+}
+
+/// The vectors of the current version.
+const VECTORS: [Vector; 6] = [
+	vector!("primitives", "All primitive types.", |builder| {
+		register_types!(
+			builder,
+			bool,
+			char,
+			&'static str,
+			u8,
+			u16,
+			u32,
+			u64,
+			u128,
+			i8,
+			i16,
+			i32,
+			i64,
+			i128
+		);
+	}),
+	vector!(
+		"sequences",
+		"Arrays, nested arrays, slices with and without encoding, and tuples including the unit tuple.",
+		|builder| {
+			register_types!(builder, [u8; 4], [[u16; 2]; 3], [u8], Vec<bool>, (), (u8, bool, String));
+		}
+	),
+	vector!(
+		"composites",
+		"Unit, tuple-struct and struct definitions of standard library types.",
+		|builder| {
+			register_types!(builder, PhantomData<u8>, NonZeroU64, BTreeMap<String, u32>);
+		}
+	),
+	vector!(
+		"enums",
+		"C-like enums, enums with niches and enums with unit and tuple-struct variants.",
+		|builder| {
+			register_types!(builder, Ordering, Option<NonZeroU32>, Result<u8, bool>, ControlFlow<bool, u8>);
+		}
+	),
+	vector!(
+		"custom",
+		"Namespaced types with named fields, an internally tagged enum and a discriminated union.",
+		|builder| {
+			register_types!(builder, Event, Payload);
+		}
+	),
+	vector!(
+		"aliases",
+		"Aliases and types that have been registered by identifier only first.",
+		|builder| {
+			builder.register_alias("Balance", u128::meta_type());
+			builder.register_type_id_only(&<Option<[u8; 20]>>::meta_type());
+			builder.register_type(&u64::meta_type());
+		}
+	),
+];
+
+/// Returns the conformance test vectors of the current version.
+pub fn vectors() -> &'static [Vector] {
+	&VECTORS
+}
+
+/// Returns the namespace of the custom types of the vectors.
+fn conformance_namespace() -> Namespace {
+	Namespace::new(vec!["conformance"]).expect("namespace is a valid Rust identifier")
+}
+
+/// An internally tagged enum with struct and unit variants.
+struct Event;
+
+impl HasTypeId for Event {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Event", conformance_namespace(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for Event {
+	fn type_def() -> TypeDef {
+		TypeDefVariant::new(vec![
+			Variant::new("Created").with_fields(vec![NamedField::of::<u64>("id")]),
+			Variant::new("Moved")
+				.with_fields(vec![UnnamedField::of::<u16>(), UnnamedField::of::<u16>()])
+				.with_code(7),
+			Variant::new("Deleted"),
+		])
+		.with_repr(EnumRepr::internal("type"))
+		.into()
+	}
+}
+
+/// A union whose active member is selected by a discriminating field.
+struct Payload;
+
+impl HasTypeId for Payload {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Payload", conformance_namespace(), vec![])
+			.with_stable_id("3f9e2d1c-7b5a-4c8e-a6f0-2d4b6c8e0a1f")
+			.into()
+	}
+}
+
+impl HasTypeDef for Payload {
+	fn type_def() -> TypeDef {
+		TypeDefUnion::new(vec![NamedField::of::<u32>("int"), NamedField::of::<bool>("flag")])
+			.with_discriminator(UnionDiscriminator::new(vec!["kind"], vec![(0u8, "int"), (1, "flag")]))
+			.expect("members are fields of the union")
+			.into()
+	}
+}
+
+/// The serialization of the reference implementation of the vector in the given layout.
+///
+/// Serializations are pretty printed JSON with a trailing newline just like the vector files.
+/// They omit the `"capabilities"` field since it depends on the features of the producer.
+pub fn reference_producer(vector: &Vector, layout: Layout) -> String {
+	let mut builder = RegistryBuilder::new();
+	vector.register(&mut builder);
+	let registry = builder.build();
+	let json = serde_json::to_string(&registry.with_layout(layout))
+		.expect("registries without deferred types are always serializable");
+	let mut json = pretty(&without_capabilities(
+		serde_json::from_str(&json).expect("serialized registries are valid JSON"),
+	));
+	json.push('\n');
+	json
+}
+
+/// Removes the capabilities of the producer from the serialized registry.
+fn without_capabilities(mut value: serde_json::Value) -> serde_json::Value {
+	if let serde_json::Value::Object(fields) = &mut value {
+		fields.remove("capabilities");
+	}
+	value
+}
+
+/// Pretty prints the JSON value.
+fn pretty(value: &serde_json::Value) -> String {
+	serde_json::to_string_pretty(value).expect("JSON values are always serializable")
+}
+
+/// An error upon verifying the conformance of a producer.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ConformanceError {
+	/// The producer returned invalid JSON.
+	InvalidJson {
+		/// The name of the vector.
+		vector: &'static str,
+		/// The layout of the serialization.
+		layout: Layout,
+		/// The error of the JSON parser.
+		error: String,
+	},
+	/// The producer returned JSON that differs from the expected serialization.
+	Mismatch {
+		/// The name of the vector.
+		vector: &'static str,
+		/// The layout of the serialization.
+		layout: Layout,
+		/// The line based diff from the expected to the produced JSON.
+		diff: String,
+	},
+}
+
+impl Display for ConformanceError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			ConformanceError::InvalidJson { vector, layout, error } => write!(
+				f,
+				"invalid JSON for vector `{}` in the {} layout: {}",
+				vector,
+				layout_name(*layout),
+				error
+			),
+			ConformanceError::Mismatch { vector, layout, diff } => write!(
+				f,
+				"mismatch for vector `{}` in the {} layout:\n{}",
+				vector,
+				layout_name(*layout),
+				diff
+			),
+		}
+	}
+}
+
+impl std::error::Error for ConformanceError {}
+
+/// Verifies that the producer serializes all vectors in all layouts as expected.
+///
+/// The producer is called with every vector and layout and returns its serialization of
+/// the registry described by the vector. Serializations are compared as JSON values, so
+/// they need not be pretty printed, and their `"capabilities"` are ignored. Returns the
+/// first deviation, if any.
+pub fn verify_conformance<P>(mut producer: P) -> Result<(), ConformanceError>
+where
+	P: FnMut(&Vector, Layout) -> String,
+{
+	for vector in vectors() {
+		for layout in LAYOUTS.iter().copied() {
+			let actual = producer(vector, layout);
+			let actual = serde_json::from_str(&actual)
+				.map(without_capabilities)
+				.map_err(|error| ConformanceError::InvalidJson {
+					vector: vector.name,
+					layout,
+					error: error.to_string(),
+				})?;
+			let expected =
+				serde_json::from_str::<serde_json::Value>(vector.expected(layout)).expect("vectors are valid JSON");
+			if actual != expected {
+				return Err(ConformanceError::Mismatch {
+					vector: vector.name,
+					layout,
+					diff: diff(&pretty(&expected), &pretty(&actual)),
+				});
+			}
+		}
+	}
+	Ok(())
+}
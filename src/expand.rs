@@ -0,0 +1,277 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Expansion of a single type into a self-contained definition tree.
+//!
+//! In contrast to the registry that deduplicates types and refers to them by
+//! their type symbols, the expanded tree inlines the identifiers and definitions
+//! of all sub-types. This is useful to describe a single type, e.g. the response
+//! type of an introspection endpoint, without setting up a registry.
+//!
+//! Every type is inlined at its first occurrence in depth-first order. All later
+//! occurrences, including recursive ones, refer back to it by its expansion index.
+
+use crate::tm_std::*;
+use crate::{form::ExpandedForm, MetaType, Metadata, TypeDef, TypeId};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+/// Expands the identifiers and definitions of the given type and all of its sub-types
+/// into a self-contained tree.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::expand_type;
+/// let expanded = expand_type::<Option<Vec<bool>>>();
+/// assert_eq!(expanded.index(), 0);
+/// assert!(expanded.def().is_some());
+/// ```
+pub fn expand_type<T>() -> ExpandedType
+where
+	T: Metadata + ?Sized + 'static,
+{
+	Expander::new().expand(&MetaType::new::<T>())
+}
+
+/// Converts the implementor into its expanded form.
+pub(crate) trait IntoExpanded {
+	/// The expanded version of `Self`.
+	type Output;
+
+	/// Expands `self` by inlining all of its sub-types using the expander.
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output;
+}
+
+/// Keeps track of the already expanded types during an expansion.
+pub(crate) struct Expander {
+	/// The expansion indices of all types expanded so far.
+	indices: BTreeMap<AnyTypeId, usize>,
+}
+
+impl Expander {
+	/// Creates a new expander that has not yet expanded any types.
+	fn new() -> Self {
+		Self {
+			indices: BTreeMap::new(),
+		}
+	}
+
+	/// Expands the given type if it has not been expanded before or
+	/// refers back to its earlier expansion otherwise.
+	pub(crate) fn expand(&mut self, ty: &MetaType) -> ExpandedType {
+		if let Some(&index) = self.indices.get(&ty.any_id()) {
+			return ExpandedType {
+				meta_type: *ty,
+				index,
+				def: None,
+			};
+		}
+		let index = self.indices.len();
+		self.indices.insert(ty.any_id(), index);
+		let id = ty.type_id().into_expanded(self);
+		let def = ty.type_def().into_expanded(self);
+		ExpandedType {
+			meta_type: *ty,
+			index,
+			def: Some(Rc::new(ExpandedTypeDef { index, id, def })),
+		}
+	}
+}
+
+/// A type within an expanded type tree.
+///
+/// This is either the inlined identifier and definition of the type
+/// or a reference to the place in the tree where it has been inlined.
+#[derive(Clone, Debug)]
+pub struct ExpandedType {
+	/// The expanded type.
+	meta_type: MetaType,
+	/// The expansion index of the type.
+	index: usize,
+	/// The inlined identifier and definition if this is the first occurrence of the type.
+	def: Option<Rc<ExpandedTypeDef>>,
+}
+
+impl PartialEq for ExpandedType {
+	fn eq(&self, other: &Self) -> bool {
+		self.meta_type == other.meta_type && self.index == other.index && self.def == other.def
+	}
+}
+
+impl Eq for ExpandedType {}
+
+impl Hash for ExpandedType {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		// Hashes only what the ordering compares to stay consistent with equality.
+		self.index.hash(state);
+		self.meta_type.hash(state);
+	}
+}
+
+impl PartialOrd for ExpandedType {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for ExpandedType {
+	fn cmp(&self, other: &Self) -> Ordering {
+		// Identical types always have identical expansions at the same index.
+		(self.index, &self.meta_type, self.def.is_some()).cmp(&(other.index, &other.meta_type, other.def.is_some()))
+	}
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ExpandedType {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		/// The serialized form of a reference to an already inlined type.
+		#[derive(Serialize)]
+		struct ExpandedTypeRef {
+			#[serde(rename = "ref")]
+			index: usize,
+		}
+
+		match &self.def {
+			Some(def) => def.serialize(serializer),
+			None => ExpandedTypeRef { index: self.index }.serialize(serializer),
+		}
+	}
+}
+
+impl ExpandedType {
+	/// Returns the meta type of the expanded type.
+	pub fn meta_type(&self) -> &MetaType {
+		&self.meta_type
+	}
+
+	/// Returns the expansion index of the type.
+	///
+	/// This is the same for all occurrences of the same type within the tree.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the inlined identifier and definition of the type or `None`
+	/// if the type has already been inlined elsewhere in the tree.
+	pub fn def(&self) -> Option<&ExpandedTypeDef> {
+		self.def.as_deref()
+	}
+}
+
+/// The inlined identifier and definition of a type within an expanded type tree.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExpandedTypeDef {
+	/// The expansion index of the type.
+	index: usize,
+	/// The identifier of the type.
+	id: TypeId<ExpandedForm>,
+	/// The definition of the type.
+	def: TypeDef<ExpandedForm>,
+}
+
+impl ExpandedTypeDef {
+	/// Returns the expansion index of the type.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the identifier of the type with all sub-types inlined.
+	pub fn id(&self) -> &TypeId<ExpandedForm> {
+		&self.id
+	}
+
+	/// Returns the definition of the type with all sub-types inlined.
+	pub fn def(&self) -> &TypeDef<ExpandedForm> {
+		&self.def
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{TypeDefComposite, TypeIdCustom};
+
+	#[allow(unused)]
+	struct Node {
+		next: Option<Box<Node>>,
+		value: u8,
+		other: u8,
+	}
+
+	impl crate::HasTypeId for Node {
+		fn type_id() -> TypeId {
+			TypeIdCustom::new("Node", crate::Namespace::new(vec!["expand"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Node {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![
+				crate::NamedField::of::<Option<Box<Node>>>("next"),
+				crate::NamedField::of::<u8>("value"),
+				crate::NamedField::of::<u8>("other"),
+			])
+			.into()
+		}
+	}
+
+	// Relies on boxes and references sharing the metadata of the referenced type.
+	#[cfg(not(feature = "reference-types"))]
+	#[test]
+	fn recursive_and_repeated_types_refer_back() {
+		let node = expand_type::<Node>();
+		assert_eq!(node.meta_type(), &Node::meta_type());
+		let fields = match node.def().unwrap().def() {
+			TypeDef::Composite(def) => def.fields(),
+			_ => panic!("expected a composite definition"),
+		};
+		// `Option<Box<Node>>` is inlined at its first occurrence.
+		let option = fields[0].ty().def().unwrap();
+		assert_eq!(option.index(), 1);
+		// Its type parameter `Box<Node>` is inlined within its identifier ...
+		let boxed = match option.id() {
+			TypeId::Custom(custom) => &custom.type_params()[0],
+			_ => panic!("expected a custom type identifier"),
+		};
+		assert_eq!(boxed.def().map(|def| def.index()), Some(2));
+		// ... so that the `Some` variant of its definition refers back to it.
+		match option.def() {
+			TypeDef::Variant(def) => {
+				let some = &def.variants()[1];
+				assert_eq!(some.fields()[0].ty().index(), 2);
+				assert!(some.fields()[0].ty().def().is_none());
+			}
+			_ => panic!("expected a variant definition"),
+		}
+		// `Box<Node>` shares the definition of `Node` whose recursive
+		// `Option<Box<Node>>` field refers back to its first occurrence.
+		let recursive = match boxed.def().unwrap().def() {
+			TypeDef::Composite(def) => def.fields()[0].ty(),
+			_ => panic!("expected a composite definition"),
+		};
+		assert_eq!(recursive.index(), 1);
+		assert!(recursive.def().is_none());
+		// `u8` has already been inlined within `Box<Node>` so both fields refer back to it.
+		assert!(fields[1].ty().def().is_none());
+		assert_eq!(fields[2].ty().index(), fields[1].ty().index());
+		assert!(fields[2].ty().def().is_none());
+	}
+}
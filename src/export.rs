@@ -0,0 +1,132 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exporters that turn the contents of a registry into other formats.
+//!
+//! Exporters operate on a filled `Registry` and therefore only ever see
+//! the compact form of type identifiers and definitions.
+
+pub mod avro;
+pub mod html;
+pub mod json_schema;
+pub mod openapi;
+pub mod thrift;
+
+use crate::tm_std::*;
+use crate::{form::CompactForm, interner::UntrackedSymbol, Registry, TypeId};
+
+/// Returns the string associated with the given string symbol or a placeholder
+/// if the symbol does not belong to the registry.
+fn resolve_string(registry: &Registry, symbol: UntrackedSymbol<&'static str>) -> &'static str {
+	registry.resolve_string(symbol).unwrap_or("<unknown>")
+}
+
+/// Returns the name of the type associated with the given type symbol
+/// including its generic parameters, e.g. `Option<[u8; 32]>`.
+fn type_name(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+	let id = match registry.resolve_type(symbol) {
+		Some(id_def) => id_def.id(),
+		None => return format!("#{}", symbol.id()),
+	};
+	let names = |params: &[UntrackedSymbol<AnyTypeId>]| {
+		params
+			.iter()
+			.map(|param| type_name(registry, *param))
+			.collect::<Vec<_>>()
+			.join(", ")
+	};
+	match id {
+		TypeId::Custom(custom) => {
+			let name = resolve_string(registry, *custom.name());
+			let params = custom
+				.type_params()
+				.iter()
+				.map(|param| type_name(registry, *param))
+				.chain(custom.const_params().iter().map(ToString::to_string))
+				.collect::<Vec<_>>();
+			if params.is_empty() {
+				name.to_string()
+			} else {
+				format!("{}<{}>", name, params.join(", "))
+			}
+		}
+		TypeId::Slice(slice) => format!("[{}]", type_name(registry, *slice.type_param())),
+		TypeId::Array(array) => format!("[{}; {}]", type_name(registry, array.type_param), array.len),
+		TypeId::Tuple(tuple) if tuple.type_params.len() == 1 => format!("({},)", names(&tuple.type_params)),
+		TypeId::Tuple(tuple) => format!("({})", names(&tuple.type_params)),
+		TypeId::Primitive(primitive) => primitive.name().to_string(),
+	}
+}
+
+/// Returns the fully qualified path of the type associated with the given
+/// type symbol, e.g. `my_crate::module::Foo<bool>`.
+///
+/// Types within the prelude namespace are returned without any prefix.
+fn type_path(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+	let name = type_name(registry, symbol);
+	match registry.resolve_type(symbol).map(|id_def| id_def.id()) {
+		Some(TypeId::<CompactForm>::Custom(custom)) if !custom.namespace().segments().is_empty() => {
+			let mut path = custom
+				.namespace()
+				.segments()
+				.iter()
+				.map(|segment| resolve_string(registry, *segment))
+				.collect::<Vec<_>>()
+				.join("::");
+			path.push_str("::");
+			path.push_str(&name);
+			path
+		}
+		_ => name,
+	}
+}
+
+/// Returns the given text as JSON string literal.
+fn json_string(text: &str) -> String {
+	let mut json = String::with_capacity(text.len() + 2);
+	json.push('"');
+	for c in text.chars() {
+		match c {
+			'"' => json.push_str("\\\""),
+			'\\' => json.push_str("\\\\"),
+			'\n' => json.push_str("\\n"),
+			'\r' => json.push_str("\\r"),
+			'\t' => json.push_str("\\t"),
+			c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+			c => json.push(c),
+		}
+	}
+	json.push('"');
+	json
+}
+
+/// Turns the given type name into an identifier, e.g. `Option<[u8; 32]>` into `Option_u8_32`.
+///
+/// Every run of characters that are invalid within identifiers is replaced by a single `_`.
+fn identifier(name: &str) -> String {
+	let mut identifier = String::with_capacity(name.len());
+	for c in name.chars() {
+		if c.is_ascii_alphanumeric() || c == '_' {
+			identifier.push(c);
+		} else if !identifier.is_empty() && !identifier.ends_with('_') {
+			identifier.push('_');
+		}
+	}
+	while identifier.ends_with('_') {
+		identifier.pop();
+	}
+	identifier
+}
@@ -0,0 +1,431 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates Avro schemas (`.avsc`) from the types of a registry.
+//!
+//! Types are mapped as follows:
+//!
+//! - `bool` becomes `boolean`, `char` and `str` become `string`.
+//! - Integers that fit into 32 bit signed integers become `int`, `u32` and `i64` become `long`.
+//!   `u64`, `u128` and `i128` become `fixed` types of their size named after the integer type
+//!   and hold the little endian bytes of the integer.
+//! - Slices and arrays of `u8` become `bytes` and `fixed` types respectively, all others `array`.
+//! - The unit tuple becomes `null`, all other tuples become records with fields `_0`, `_1`, ...
+//! - Composites become records whose unnamed fields are named by their index as for tuples.
+//! - Variants whose variants all lack fields become enums. Variants of the shape of `Option`
+//!   become a union of `null` and the type of `Some`. All other variants become a union of
+//!   records, one per variant, within the namespace of the variant type.
+//! - JSON values become `string`, raw payloads `string` or `bytes` depending on their format.
+//!
+//! Named types are placed into the namespace of the type, with `::` replaced by `.`, and
+//! generic types are named after their type parameters, e.g. `Option<u32>` becomes
+//! `Option_u32`. Every named type is defined the first time it is used and referred to by
+//! its full name afterwards, so that recursive types are supported.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{export::avro, Metadata, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! let ty = builder.register_type(&<Option<u32>>::meta_type());
+//! let registry = builder.build();
+//! assert_eq!(avro::schema(&registry, ty).unwrap(), r#"["null","long"]"#);
+//! ```
+
+use super::{identifier, json_string, resolve_string, type_name};
+use crate::tm_std::*;
+use crate::{
+	form::CompactForm, interner::UntrackedSymbol, Builtin, Field, RawFormat, Registry, TypeDef, TypeDefVariant, TypeId,
+	TypeIdPrimitive,
+};
+
+/// An error upon generating an Avro schema.
+#[derive(PartialEq, Eq, Debug)]
+pub enum AvroError {
+	/// The type is not registered with the registry or has been registered by identifier only.
+	UnknownType {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The type has no Avro representation, e.g. unions or retired types.
+	Unsupported {
+		/// The name of the type.
+		name: String,
+	},
+}
+
+impl Display for AvroError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			AvroError::UnknownType { id } => write!(f, "unknown type with symbol {}", id),
+			AvroError::Unsupported { name } => write!(f, "type `{}` has no Avro representation", name),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AvroError {}
+
+/// Generates the Avro schema of the given type.
+///
+/// All named types used by the type are defined inline so that the schema is self-contained.
+///
+/// # Errors
+///
+/// If the type or one of the types it uses is not registered or has no Avro representation.
+pub fn schema(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, AvroError> {
+	Generator {
+		registry,
+		defined: BTreeSet::new(),
+	}
+	.schema(symbol)
+}
+
+/// Generates the Avro schemas of all composite and variant types of the given registry.
+///
+/// Maps the full names of the types to their self-contained schemas. Variant types that are
+/// represented as unions, e.g. `Option<T>`, have no name and are therefore skipped.
+///
+/// # Errors
+///
+/// If any of the types uses a type that is not registered or has no Avro representation.
+pub fn generate(registry: &Registry) -> Result<BTreeMap<String, String>, AvroError> {
+	let mut schemas = BTreeMap::new();
+	for (symbol, id_def) in registry.types() {
+		let named = match id_def.def().clone().normalize() {
+			TypeDef::Composite(_) => true,
+			TypeDef::Variant(def) => def.variants().iter().all(|variant| variant.fields().is_empty()),
+			_ => false,
+		};
+		if !named || !matches!(id_def.id(), TypeId::Custom(_)) {
+			continue;
+		}
+		schemas.insert(full_name(registry, symbol), schema(registry, symbol)?);
+	}
+	Ok(schemas)
+}
+
+/// Returns the Avro namespace of the given type, which is empty for types outside of any namespace.
+fn namespace(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+	match registry.resolve_type(symbol).map(|id_def| id_def.id()) {
+		Some(TypeId::<CompactForm>::Custom(custom)) => custom
+			.namespace()
+			.segments()
+			.iter()
+			.map(|segment| resolve_string(registry, *segment))
+			.collect::<Vec<_>>()
+			.join("."),
+		_ => String::new(),
+	}
+}
+
+/// Returns the full Avro name of the given type, i.e. its name within its namespace.
+fn full_name(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+	let name = match registry.resolve_type(symbol).map(|id_def| id_def.id()) {
+		Some(TypeId::Tuple(_)) => format!("Tuple_{}", identifier(&type_name(registry, symbol))),
+		_ => identifier(&type_name(registry, symbol)),
+	};
+	qualify(&namespace(registry, symbol), &name)
+}
+
+/// Returns the full name of the given name within the given namespace.
+fn qualify(namespace: &str, name: &str) -> String {
+	if namespace.is_empty() {
+		name.to_string()
+	} else {
+		format!("{}.{}", namespace, name)
+	}
+}
+
+/// Returns the schema of a record with the given full name and fields.
+///
+/// The fields are pairs of field names and field schemas.
+fn record(full_name: &str, fields: &[(String, String)]) -> String {
+	let fields = fields
+		.iter()
+		.map(|(name, ty)| format!("{{\"name\":{},\"type\":{}}}", json_string(name), ty))
+		.collect::<Vec<_>>();
+	format!(
+		"{{\"type\":\"record\",\"name\":{},\"fields\":[{}]}}",
+		json_string(full_name),
+		fields.join(",")
+	)
+}
+
+/// Generates schemas while keeping track of the named types that have already been defined.
+struct Generator<'a> {
+	registry: &'a Registry,
+	/// The full names of all named types defined so far.
+	defined: BTreeSet<String>,
+}
+
+impl Generator<'_> {
+	/// Returns the schema of the given type.
+	fn schema(&mut self, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, AvroError> {
+		let id_def = self
+			.registry
+			.resolve_type(symbol)
+			.ok_or(AvroError::UnknownType { id: symbol.id().get() })?;
+		match id_def.id() {
+			TypeId::Primitive(primitive) => Ok(self.primitive(primitive)),
+			TypeId::Slice(slice) if self.is_u8(*slice.type_param()) => Ok("\"bytes\"".to_string()),
+			TypeId::Slice(slice) => Ok(format!(
+				"{{\"type\":\"array\",\"items\":{}}}",
+				self.schema(*slice.type_param())?
+			)),
+			TypeId::Array(array) if self.is_u8(array.type_param) => {
+				Ok(self.fixed(&full_name(self.registry, symbol), usize::from(array.len)))
+			}
+			TypeId::Array(array) => Ok(format!(
+				"{{\"type\":\"array\",\"items\":{}}}",
+				self.schema(array.type_param)?
+			)),
+			TypeId::Tuple(tuple) if tuple.type_params.is_empty() => Ok("\"null\"".to_string()),
+			TypeId::Tuple(tuple) => {
+				let full_name = full_name(self.registry, symbol);
+				if !self.defined.insert(full_name.clone()) {
+					return Ok(json_string(&full_name));
+				}
+				let fields = tuple
+					.type_params
+					.iter()
+					.enumerate()
+					.map(|(index, param)| Ok((format!("_{}", index), self.schema(*param)?)))
+					.collect::<Result<Vec<_>, _>>()?;
+				Ok(record(&full_name, &fields))
+			}
+			TypeId::Custom(_) => match id_def.def().clone().normalize() {
+				TypeDef::Composite(def) => {
+					let full_name = full_name(self.registry, symbol);
+					if !self.defined.insert(full_name.clone()) {
+						return Ok(json_string(&full_name));
+					}
+					Ok(record(&full_name, &self.fields(def.fields())?))
+				}
+				TypeDef::Variant(def) => self.variant(symbol, &def),
+				TypeDef::Builtin(Builtin::Json) | TypeDef::Builtin(Builtin::Raw(RawFormat::Json)) => {
+					Ok("\"string\"".to_string())
+				}
+				TypeDef::Builtin(Builtin::Raw(RawFormat::Scale)) => Ok("\"bytes\"".to_string()),
+				_ => Err(AvroError::Unsupported {
+					name: type_name(self.registry, symbol),
+				}),
+			},
+		}
+	}
+
+	/// Returns the schema of the given primitive type.
+	fn primitive(&mut self, primitive: &TypeIdPrimitive) -> String {
+		match primitive {
+			TypeIdPrimitive::Bool => "\"boolean\"".to_string(),
+			TypeIdPrimitive::Char | TypeIdPrimitive::Str => "\"string\"".to_string(),
+			TypeIdPrimitive::U8
+			| TypeIdPrimitive::U16
+			| TypeIdPrimitive::I8
+			| TypeIdPrimitive::I16
+			| TypeIdPrimitive::I32 => "\"int\"".to_string(),
+			TypeIdPrimitive::U32 | TypeIdPrimitive::I64 => "\"long\"".to_string(),
+			TypeIdPrimitive::U64 => self.fixed("u64", 8),
+			TypeIdPrimitive::U128 => self.fixed("u128", 16),
+			TypeIdPrimitive::I128 => self.fixed("i128", 16),
+		}
+	}
+
+	/// Returns the schema of a fixed type of the given full name and size.
+	fn fixed(&mut self, full_name: &str, size: usize) -> String {
+		if self.defined.insert(full_name.to_string()) {
+			format!(
+				"{{\"type\":\"fixed\",\"name\":{},\"size\":{}}}",
+				json_string(full_name),
+				size
+			)
+		} else {
+			json_string(full_name)
+		}
+	}
+
+	/// Returns `true` if the given type is `u8`.
+	fn is_u8(&self, symbol: UntrackedSymbol<AnyTypeId>) -> bool {
+		matches!(
+			self.registry.resolve_type(symbol).map(|id_def| id_def.id()),
+			Some(TypeId::Primitive(TypeIdPrimitive::U8))
+		)
+	}
+
+	/// Returns the names and schemas of the given fields.
+	///
+	/// Unnamed fields are named by their index.
+	fn fields(&mut self, fields: &[Field<CompactForm>]) -> Result<Vec<(String, String)>, AvroError> {
+		fields
+			.iter()
+			.enumerate()
+			.map(|(index, field)| {
+				let name = match field.name() {
+					Some(name) => resolve_string(self.registry, *name).to_string(),
+					None => format!("_{}", index),
+				};
+				Ok((name, self.schema(*field.ty())?))
+			})
+			.collect()
+	}
+
+	/// Returns the schema of the given variant type.
+	fn variant(
+		&mut self,
+		symbol: UntrackedSymbol<AnyTypeId>,
+		def: &TypeDefVariant<CompactForm>,
+	) -> Result<String, AvroError> {
+		let full_name = full_name(self.registry, symbol);
+		let variants = def.variants();
+		if variants.iter().all(|variant| variant.fields().is_empty()) {
+			if !self.defined.insert(full_name.clone()) {
+				return Ok(json_string(&full_name));
+			}
+			let symbols = variants
+				.iter()
+				.map(|variant| json_string(resolve_string(self.registry, *variant.name())))
+				.collect::<Vec<_>>();
+			return Ok(format!(
+				"{{\"type\":\"enum\",\"name\":{},\"symbols\":[{}]}}",
+				json_string(&full_name),
+				symbols.join(",")
+			));
+		}
+		let is_option = match variants {
+			[none, some] => {
+				resolve_string(self.registry, *none.name()) == "None"
+					&& none.fields().is_empty()
+					&& resolve_string(self.registry, *some.name()) == "Some"
+					&& some.fields().len() == 1
+			}
+			_ => false,
+		};
+		let mut records = Vec::with_capacity(variants.len());
+		for variant in variants {
+			let fields = self.fields(variant.fields())?;
+			// Avro does not allow unions to be nested directly within unions.
+			if let Some((_, field)) = fields.first().filter(|(_, field)| is_option && !field.starts_with('[')) {
+				return Ok(format!("[\"null\",{}]", field));
+			}
+			let variant_name = qualify(&full_name, resolve_string(self.registry, *variant.name()));
+			if !self.defined.insert(variant_name.clone()) {
+				records.push(json_string(&variant_name));
+			} else {
+				records.push(record(&variant_name, &fields));
+			}
+		}
+		Ok(format!("[{}]", records.join(",")))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Metadata, TypeDefComposite, TypeDefVariant, TypeIdCustom, Variant};
+
+	#[allow(unused)]
+	struct Node {
+		value: u64,
+		children: Vec<Node>,
+	}
+
+	impl crate::HasTypeId for Node {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Node", crate::Namespace::new(vec!["avro", "tree"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Node {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![
+				Field::named("value", u64::meta_type()),
+				Field::named("children", <Vec<Node>>::meta_type()),
+			])
+			.into()
+		}
+	}
+
+	#[allow(unused)]
+	enum Event {
+		Transfer([u8; 4], u128),
+		Remark(Option<String>),
+		Unknown,
+	}
+
+	impl crate::HasTypeId for Event {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Event", crate::Namespace::new(vec!["avro"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Event {
+		fn type_def() -> TypeDef {
+			TypeDefVariant::new(vec![
+				Variant::new("Transfer").with_fields(vec![
+					Field::unnamed(<[u8; 4]>::meta_type()),
+					Field::unnamed(u128::meta_type()),
+				]),
+				Variant::new("Remark").with_fields(vec![Field::unnamed(<Option<String>>::meta_type())]),
+				Variant::new("Unknown"),
+			])
+			.into()
+		}
+	}
+
+	#[test]
+	fn generates_recursive_records() {
+		let mut registry = Registry::new();
+		let node = registry.compact_type(&Node::meta_type());
+		assert_eq!(
+			schema(&registry, node).unwrap(),
+			"{\"type\":\"record\",\"name\":\"avro.tree.Node\",\"fields\":[\
+			 {\"name\":\"value\",\"type\":{\"type\":\"fixed\",\"name\":\"u64\",\"size\":8}},\
+			 {\"name\":\"children\",\"type\":{\"type\":\"record\",\"name\":\"Vec_Node\",\"fields\":[\
+			 {\"name\":\"elems\",\"type\":{\"type\":\"array\",\"items\":\"avro.tree.Node\"}}]}}]}"
+		);
+	}
+
+	#[test]
+	fn generates_unions_of_variants() {
+		let mut registry = Registry::new();
+		let event = registry.compact_type(&Event::meta_type());
+		assert_eq!(
+			schema(&registry, event).unwrap(),
+			"[{\"type\":\"record\",\"name\":\"avro.Event.Transfer\",\"fields\":[\
+			 {\"name\":\"_0\",\"type\":{\"type\":\"fixed\",\"name\":\"u8_4\",\"size\":4}},\
+			 {\"name\":\"_1\",\"type\":{\"type\":\"fixed\",\"name\":\"u128\",\"size\":16}}]},\
+			 {\"type\":\"record\",\"name\":\"avro.Event.Remark\",\"fields\":[\
+			 {\"name\":\"_0\",\"type\":[\"null\",\"string\"]}]},\
+			 {\"type\":\"record\",\"name\":\"avro.Event.Unknown\",\"fields\":[]}]"
+		);
+		let schemas = generate(&registry).unwrap();
+		assert!(schemas.contains_key("Vec_u8"));
+		assert!(!schemas.contains_key("avro.Event"));
+	}
+
+	#[test]
+	fn generates_enums_and_tuples() {
+		let mut registry = Registry::new();
+		let result = registry.compact_type(&<Result<(), (bool, i32)>>::meta_type());
+		assert_eq!(
+			schema(&registry, result).unwrap(),
+			"[{\"type\":\"record\",\"name\":\"Result_bool_i32.Ok\",\"fields\":[{\"name\":\"_0\",\"type\":\"null\"}]},\
+			 {\"type\":\"record\",\"name\":\"Result_bool_i32.Err\",\"fields\":[{\"name\":\"_0\",\"type\":\
+			 {\"type\":\"record\",\"name\":\"Tuple_bool_i32\",\"fields\":[\
+			 {\"name\":\"_0\",\"type\":\"boolean\"},{\"name\":\"_1\",\"type\":\"int\"}]}}]}]"
+		);
+	}
+}
@@ -0,0 +1,440 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates a static, cross-linked HTML documentation site from a registry.
+//!
+//! The generated site consists of
+//!
+//! - `index.html`: an overview over all registered types,
+//! - `types/<symbol>.html`: one page per registered type listing its fields
+//!   or variants as well as the types it uses and is used by,
+//! - `search-index.json`: a search index over all types for client-side search.
+//!
+//! Pages refer to each other using relative links only so that the site
+//! can be hosted from any location or browsed directly from the file system.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{export::html, Metadata, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! builder.register_type(&<Option<u32>>::meta_type());
+//! let registry = builder.build();
+//! let site = html::generate(&registry);
+//! assert!(site.page("index.html").is_some());
+//! assert!(site.page("search-index.json").is_some());
+//! ```
+
+use super::{json_string, resolve_string, type_name, type_path};
+use crate::tm_std::*;
+use crate::{
+	form::CompactForm, interner::UntrackedSymbol, registry::TypeIdDef, Builtin, EnumVariant, Field, NamedField,
+	RawFormat, Registry, TypeDef, TypeId, UnnamedField,
+};
+
+/// A generated static documentation site.
+///
+/// Maps the relative paths of the pages of the site to their contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Site {
+	pages: BTreeMap<String, String>,
+}
+
+impl Site {
+	/// Returns the contents of the page at the given relative path.
+	pub fn page(&self, path: &str) -> Option<&str> {
+		self.pages.get(path).map(String::as_str)
+	}
+
+	/// Returns an iterator over the relative paths and contents of all pages.
+	pub fn pages(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.pages.iter().map(|(path, page)| (path.as_str(), page.as_str()))
+	}
+
+	/// Writes all pages of the site into the given directory.
+	///
+	/// Missing directories are created as needed and existing pages are overwritten.
+	#[cfg(feature = "std")]
+	pub fn write_to<P>(&self, dir: P) -> std::io::Result<()>
+	where
+		P: AsRef<std::path::Path>,
+	{
+		for (path, page) in self.pages() {
+			let path = dir.as_ref().join(path);
+			if let Some(parent) = path.parent() {
+				std::fs::create_dir_all(parent)?;
+			}
+			std::fs::write(path, page)?;
+		}
+		Ok(())
+	}
+}
+
+/// Generates the documentation site for all types of the given registry.
+pub fn generate(registry: &Registry) -> Site {
+	let mut used_by = BTreeMap::<UntrackedSymbol<AnyTypeId>, Vec<UntrackedSymbol<AnyTypeId>>>::new();
+	for (symbol, id_def) in registry.types() {
+		for type_ref in id_def.type_refs() {
+			let users = used_by.entry(type_ref).or_default();
+			if !users.contains(&symbol) {
+				users.push(symbol);
+			}
+		}
+	}
+	let mut pages = BTreeMap::new();
+	pages.insert("index.html".to_string(), index_page(registry));
+	for (symbol, id_def) in registry.types() {
+		let users = used_by.get(&symbol).map(Vec::as_slice).unwrap_or(&[]);
+		pages.insert(page_path(symbol), type_page(registry, symbol, id_def, users));
+	}
+	pages.insert("search-index.json".to_string(), search_index(registry));
+	Site { pages }
+}
+
+/// Returns the path of the page of the given type relative to the site root.
+fn page_path(symbol: UntrackedSymbol<AnyTypeId>) -> String {
+	format!("types/{}.html", symbol.id())
+}
+
+/// Returns a human readable description of the kind of the given type.
+fn kind(id_def: &TypeIdDef) -> &'static str {
+	match (id_def.id(), id_def.def()) {
+		(TypeId::Primitive(_), _) => "primitive",
+		(TypeId::Slice(_), _) => "slice",
+		(TypeId::Array(_), _) => "array",
+		(TypeId::Tuple(_), _) => "tuple",
+		(TypeId::Custom(_), TypeDef::Builtin(Builtin::Builtin)) => "builtin",
+		(TypeId::Custom(_), TypeDef::Builtin(Builtin::Json)) => "JSON",
+		(TypeId::Custom(_), TypeDef::Builtin(Builtin::Tombstone)) => "retired",
+		(TypeId::Custom(_), TypeDef::Builtin(Builtin::Raw(RawFormat::Json))) => "raw JSON",
+		(TypeId::Custom(_), TypeDef::Builtin(Builtin::Raw(RawFormat::Scale))) => "raw SCALE",
+		(TypeId::Custom(_), TypeDef::Struct(_)) => "struct",
+		(TypeId::Custom(_), TypeDef::TupleStruct(_)) => "tuple struct",
+		(TypeId::Custom(_), TypeDef::Composite(def)) if def.is_named() => "struct",
+		(TypeId::Custom(_), TypeDef::Composite(_)) => "tuple struct",
+		(TypeId::Custom(_), TypeDef::ClikeEnum(_)) => "C-like enum",
+		(TypeId::Custom(_), TypeDef::Enum(_)) => "enum",
+		(TypeId::Custom(_), TypeDef::Variant(def)) if def.is_clike() => "C-like enum",
+		(TypeId::Custom(_), TypeDef::Variant(_)) => "enum",
+		(TypeId::Custom(_), TypeDef::Union(_)) => "union",
+	}
+}
+
+/// Escapes the given text for use within HTML text and attribute values.
+fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&#39;"),
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+/// Wraps the given body into a complete HTML document.
+///
+/// The `root` is the relative path from the page to the site root.
+fn document(title: &str, root: &str, body: &str) -> String {
+	format!(
+		"<!DOCTYPE html>\n\
+		 <html>\n\
+		 <head>\n\
+		 <meta charset=\"utf-8\">\n\
+		 <title>{title}</title>\n\
+		 </head>\n\
+		 <body>\n\
+		 <nav><a href=\"{root}index.html\">Index</a></nav>\n\
+		 <main>\n{body}</main>\n\
+		 </body>\n\
+		 </html>\n",
+		title = escape(title),
+		root = root,
+		body = body,
+	)
+}
+
+/// Returns a link to the page of the given type.
+fn link(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>, root: &str) -> String {
+	format!(
+		"<a href=\"{}{}\">{}</a>",
+		root,
+		page_path(symbol),
+		escape(&type_name(registry, symbol))
+	)
+}
+
+/// Renders the overview page listing all registered types.
+fn index_page(registry: &Registry) -> String {
+	let mut body = String::from("<h1>Types</h1>\n<table>\n<tr><th>Type</th><th>Kind</th><th>Path</th></tr>\n");
+	for (symbol, id_def) in registry.types() {
+		body.push_str(&format!(
+			"<tr><td>{}</td><td>{}</td><td><code>{}</code></td></tr>\n",
+			link(registry, symbol, ""),
+			kind(id_def),
+			escape(&type_path(registry, symbol)),
+		));
+	}
+	body.push_str("</table>\n");
+	let mut aliases = registry.aliases().peekable();
+	if aliases.peek().is_some() {
+		body.push_str("<h1>Aliases</h1>\n<table>\n<tr><th>Alias</th><th>Type</th></tr>\n");
+		for (name, symbol) in aliases {
+			body.push_str(&format!(
+				"<tr><td><code>{}</code></td><td>{}</td></tr>\n",
+				escape(name),
+				link(registry, symbol, ""),
+			));
+		}
+		body.push_str("</table>\n");
+	}
+	document("Types", "", &body)
+}
+
+/// Renders a table of named fields.
+fn named_fields_table(registry: &Registry, fields: &[NamedField<CompactForm>]) -> String {
+	let mut table = String::from("<table>\n<tr><th>Name</th><th>Type</th></tr>\n");
+	for field in fields {
+		table.push_str(&format!(
+			"<tr><td><code>{}</code></td><td>{}</td></tr>\n",
+			escape(resolve_string(registry, *field.name())),
+			link(registry, *field.ty(), "../"),
+		));
+	}
+	table.push_str("</table>\n");
+	table
+}
+
+/// Renders a table of unnamed fields.
+fn unnamed_fields_table(registry: &Registry, fields: &[UnnamedField<CompactForm>]) -> String {
+	let mut table = String::from("<table>\n<tr><th>Index</th><th>Type</th></tr>\n");
+	for (index, field) in fields.iter().enumerate() {
+		table.push_str(&format!(
+			"<tr><td>{}</td><td>{}</td></tr>\n",
+			index,
+			link(registry, *field.ty(), "../"),
+		));
+	}
+	table.push_str("</table>\n");
+	table
+}
+
+/// Renders a table of the fields of a composite or variant.
+///
+/// Named fields are listed by their names, unnamed fields by their indices.
+fn fields_table(registry: &Registry, fields: &[Field<CompactForm>]) -> String {
+	let named = !fields.is_empty() && fields.iter().all(|field| field.name().is_some());
+	let mut table = if named {
+		String::from("<table>\n<tr><th>Name</th><th>Type</th></tr>\n")
+	} else {
+		String::from("<table>\n<tr><th>Index</th><th>Type</th></tr>\n")
+	};
+	for (index, field) in fields.iter().enumerate() {
+		let name = match field.name() {
+			Some(name) => format!("<code>{}</code>", escape(resolve_string(registry, *name))),
+			None => index.to_string(),
+		};
+		table.push_str(&format!(
+			"<tr><td>{}</td><td>{}</td></tr>\n",
+			name,
+			link(registry, *field.ty(), "../"),
+		));
+	}
+	table.push_str("</table>\n");
+	table
+}
+
+/// Renders a list of links to the given types under the given heading.
+fn type_list(registry: &Registry, heading: &str, symbols: &[UntrackedSymbol<AnyTypeId>]) -> String {
+	if symbols.is_empty() {
+		return String::new();
+	}
+	let mut list = format!("<h2>{}</h2>\n<ul>\n", heading);
+	for symbol in symbols {
+		list.push_str(&format!("<li>{}</li>\n", link(registry, *symbol, "../")));
+	}
+	list.push_str("</ul>\n");
+	list
+}
+
+/// Renders the page of a single type.
+fn type_page(
+	registry: &Registry,
+	symbol: UntrackedSymbol<AnyTypeId>,
+	id_def: &TypeIdDef,
+	used_by: &[UntrackedSymbol<AnyTypeId>],
+) -> String {
+	let path = type_path(registry, symbol);
+	let mut body = format!(
+		"<h1><code>{}</code></h1>\n<p class=\"kind\">{}</p>\n",
+		escape(&path),
+		kind(id_def)
+	);
+	match id_def.def() {
+		TypeDef::Builtin(_) => (),
+		TypeDef::Struct(def) => {
+			body.push_str("<h2>Fields</h2>\n");
+			body.push_str(&named_fields_table(registry, def.fields()));
+		}
+		TypeDef::TupleStruct(def) => {
+			body.push_str("<h2>Fields</h2>\n");
+			body.push_str(&unnamed_fields_table(registry, def.fields()));
+		}
+		TypeDef::Composite(def) => {
+			body.push_str("<h2>Fields</h2>\n");
+			body.push_str(&fields_table(registry, def.fields()));
+		}
+		TypeDef::ClikeEnum(def) => {
+			body.push_str("<h2>Variants</h2>\n<table>\n<tr><th>Name</th><th>Discriminant</th></tr>\n");
+			for variant in def.variants() {
+				body.push_str(&format!(
+					"<tr><td><code>{}</code></td><td>{}</td></tr>\n",
+					escape(resolve_string(registry, *variant.name())),
+					variant.discriminant(),
+				));
+			}
+			body.push_str("</table>\n");
+		}
+		TypeDef::Enum(def) => {
+			body.push_str("<h2>Variants</h2>\n");
+			for variant in def.variants() {
+				let (name, fields) = match variant {
+					EnumVariant::Unit(variant) => (variant.name(), String::new()),
+					EnumVariant::Struct(variant) => (variant.name(), named_fields_table(registry, variant.fields())),
+					EnumVariant::TupleStruct(variant) => {
+						(variant.name(), unnamed_fields_table(registry, variant.fields()))
+					}
+				};
+				let name = escape(resolve_string(registry, *name));
+				body.push_str(&format!(
+					"<h3 id=\"variant.{}\"><code>{}</code></h3>\n{}",
+					name, name, fields
+				));
+			}
+		}
+		TypeDef::Variant(def) if def.is_clike() => {
+			body.push_str("<h2>Variants</h2>\n<table>\n<tr><th>Name</th><th>Discriminant</th></tr>\n");
+			for variant in def.variants() {
+				body.push_str(&format!(
+					"<tr><td><code>{}</code></td><td>{}</td></tr>\n",
+					escape(resolve_string(registry, *variant.name())),
+					variant.discriminant().unwrap_or_default(),
+				));
+			}
+			body.push_str("</table>\n");
+		}
+		TypeDef::Variant(def) => {
+			body.push_str("<h2>Variants</h2>\n");
+			for variant in def.variants() {
+				let name = escape(resolve_string(registry, *variant.name()));
+				body.push_str(&format!("<h3 id=\"variant.{}\"><code>{}</code></h3>\n", name, name));
+				if let Some(discriminant) = variant.discriminant() {
+					body.push_str(&format!("<p>Discriminant: {}</p>\n", discriminant));
+				}
+				if !variant.fields().is_empty() {
+					body.push_str(&fields_table(registry, variant.fields()));
+				}
+			}
+		}
+		TypeDef::Union(def) => {
+			body.push_str("<h2>Fields</h2>\n");
+			body.push_str(&named_fields_table(registry, def.fields()));
+		}
+	}
+	body.push_str(&type_list(registry, "Uses", &id_def.type_refs()));
+	body.push_str(&type_list(registry, "Used By", used_by));
+	document(&path, "../", &body)
+}
+
+/// Renders the search index over all registered types as JSON array.
+fn search_index(registry: &Registry) -> String {
+	let entries = registry
+		.types()
+		.map(|(symbol, id_def)| {
+			format!(
+				"{{\"id\":{},\"name\":{},\"path\":{},\"kind\":{},\"url\":{}}}",
+				symbol.id(),
+				json_string(&type_name(registry, symbol)),
+				json_string(&type_path(registry, symbol)),
+				json_string(kind(id_def)),
+				json_string(&page_path(symbol)),
+			)
+		})
+		.collect::<Vec<_>>();
+	format!("[{}]", entries.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Metadata, TypeDefComposite, TypeIdCustom};
+
+	#[allow(unused)]
+	struct Wrapper {
+		value: Option<u8>,
+	}
+
+	impl crate::HasTypeId for Wrapper {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Wrapper", crate::Namespace::new(vec!["html"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Wrapper {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![NamedField::of::<Option<u8>>("value")]).into()
+		}
+	}
+
+	#[test]
+	fn generates_cross_linked_pages() {
+		let mut registry = Registry::new();
+		let wrapper = registry.compact_type(&Wrapper::meta_type());
+		let option = registry.compact_type(&<Option<u8>>::meta_type());
+		let site = generate(&registry);
+
+		let index = site.page("index.html").unwrap();
+		assert!(index.contains("<a href=\"types/1.html\">Wrapper</a>"));
+		assert!(index.contains("<code>html::Wrapper</code>"));
+
+		let wrapper_page = site.page(&page_path(wrapper)).unwrap();
+		assert!(wrapper_page
+			.contains("<td><code>value</code></td><td><a href=\"../types/2.html\">Option&lt;u8&gt;</a></td>"));
+
+		let option_page = site.page(&page_path(option)).unwrap();
+		assert!(option_page.contains("<h3 id=\"variant.Some\"><code>Some</code></h3>"));
+		assert!(option_page.contains("<h2>Used By</h2>\n<ul>\n<li><a href=\"../types/1.html\">Wrapper</a></li>"));
+
+		let search_index = site.page("search-index.json").unwrap();
+		assert!(search_index.starts_with(
+			"[{\"id\":1,\"name\":\"Wrapper\",\"path\":\"html::Wrapper\",\"kind\":\"struct\",\"url\":\"types/1.html\"}"
+		));
+		assert_eq!(site.pages().count(), registry.types().count() + 2);
+	}
+
+	#[test]
+	fn escapes_text() {
+		assert_eq!(
+			escape("<a href=\"x\">&'</a>"),
+			"&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+		);
+		assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+	}
+}
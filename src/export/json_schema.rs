@@ -0,0 +1,356 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates JSON Schemas (draft 2020-12) from the types of a registry.
+//!
+//! The schemas describe the JSON representation of the types as produced by `serde_json`
+//! after the definitions of the registry. Types are mapped as follows:
+//!
+//! - `bool` becomes `boolean`, `char` and `str` become `string`, the former of length one.
+//! - Integers become `integer` with the `int32` or `int64` format or bounds where needed.
+//! - Slices become arrays, arrays become arrays of their length.
+//! - The unit tuple becomes `null`, all other tuples become arrays of the types of their fields.
+//! - Composites with named fields become objects with all fields required. Composites without
+//!   fields become `null`, with a single unnamed field the type of the field, and with several
+//!   unnamed fields arrays like tuples. Note that `Vec<T>` therefore becomes an object whose
+//!   `elems` property holds the array, just as in the registry.
+//! - Variants whose variants all lack fields become string enums. Variants of the shape of
+//!   `Option` become `null` or the type of `Some`. All other variants become externally tagged,
+//!   i.e. unit variants become their name and all others an object with the name as sole key.
+//! - JSON values and raw JSON payloads accept everything, raw SCALE payloads become byte arrays.
+//!
+//! Custom types are defined under their name within their namespace with `::` replaced by `.`,
+//! e.g. `my_crate.Foo`, and referred to by `$ref` so that recursive types are supported.
+//! Generic types are named after their type parameters, e.g. `Option<u32>` becomes `Option_u32`.
+//! All other types are inlined. The documentation of types and fields becomes their description.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{export::json_schema, Metadata, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! let ty = builder.register_type(&<Option<u32>>::meta_type());
+//! let registry = builder.build();
+//! assert_eq!(
+//!     json_schema::schema(&registry, ty).unwrap(),
+//!     concat!(
+//!         r##"{"$schema":"https://json-schema.org/draft/2020-12/schema","$ref":"#/$defs/Option_u32","##,
+//!         r##""$defs":{"Option_u32":{"oneOf":[{"type":"null"},{"type":"integer","format":"int64","##,
+//!         r##""minimum":0,"maximum":4294967295}]}}}"##,
+//!     ),
+//! );
+//! ```
+
+use super::{identifier, json_string, resolve_string, type_name};
+use crate::tm_std::*;
+use crate::{
+	form::CompactForm, interner::UntrackedSymbol, Builtin, Field, RawFormat, Registry, TypeDef, TypeDefVariant, TypeId,
+	TypeIdPrimitive, Variant,
+};
+
+/// The identifier of the JSON Schema dialect of the generated schemas.
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// An error upon generating a JSON Schema.
+#[derive(PartialEq, Eq, Debug)]
+pub enum JsonSchemaError {
+	/// The type is not registered with the registry or has been registered by identifier only.
+	UnknownType {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The type has no JSON representation, e.g. unions or retired types.
+	Unsupported {
+		/// The name of the type.
+		name: String,
+	},
+}
+
+impl Display for JsonSchemaError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			JsonSchemaError::UnknownType { id } => write!(f, "unknown type with symbol {}", id),
+			JsonSchemaError::Unsupported { name } => write!(f, "type `{}` has no JSON representation", name),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonSchemaError {}
+
+/// Generates the JSON Schema of the given type.
+///
+/// All custom types used by the type are defined in `$defs` so that the schema is self-contained.
+///
+/// # Errors
+///
+/// If the type or one of the types it uses is not registered or has no JSON representation.
+pub fn schema(registry: &Registry, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, JsonSchemaError> {
+	let mut generator = Generator::new(registry, "#/$defs/");
+	let name = generator.define(symbol)?;
+	let reference = generator.reference(&name);
+	Ok(format!(
+		"{{\"$schema\":{},\"$ref\":{},\"$defs\":{}}}",
+		json_string(DIALECT),
+		json_string(&reference),
+		object(generator.into_definitions())
+	))
+}
+
+/// Returns a JSON object of the given keys and JSON values.
+pub(super) fn object<K, I>(members: I) -> String
+where
+	K: AsRef<str>,
+	I: IntoIterator<Item = (K, String)>,
+{
+	let members = members
+		.into_iter()
+		.map(|(key, value)| format!("{}:{}", json_string(key.as_ref()), value))
+		.collect::<Vec<_>>();
+	format!("{{{}}}", members.join(","))
+}
+
+/// Returns the schema of an array whose items have the given schemas, in order.
+fn tuple_schema(items: Vec<String>) -> String {
+	format!(
+		"{{\"type\":\"array\",\"prefixItems\":[{}],\"items\":false,\"minItems\":{len},\"maxItems\":{len}}}",
+		items.join(","),
+		len = items.len()
+	)
+}
+
+/// Returns the given schema with the given description if it is not empty.
+fn describe(schema: String, description: &str) -> String {
+	match schema.strip_prefix('{') {
+		Some(rest) if !description.is_empty() => {
+			let separator = if rest == "}" { "" } else { "," };
+			format!("{{\"description\":{}{}{}", json_string(description), separator, rest)
+		}
+		_ => schema,
+	}
+}
+
+/// Generates schemas while collecting the definitions of the custom types.
+pub(super) struct Generator<'a> {
+	registry: &'a Registry,
+	/// The prefix of references to definitions, e.g. `#/$defs/`.
+	prefix: &'static str,
+	/// The definitions by name, `None` while the definition is being generated.
+	definitions: BTreeMap<String, Option<String>>,
+}
+
+impl<'a> Generator<'a> {
+	/// Creates a generator that refers to definitions with the given prefix.
+	pub(super) fn new(registry: &'a Registry, prefix: &'static str) -> Self {
+		Self {
+			registry,
+			prefix,
+			definitions: BTreeMap::new(),
+		}
+	}
+
+	/// Returns the reference to the definition of the given name.
+	pub(super) fn reference(&self, name: &str) -> String {
+		format!("{}{}", self.prefix, name)
+	}
+
+	/// Returns the definitions of all types defined so far by name.
+	pub(super) fn into_definitions(self) -> BTreeMap<String, String> {
+		self.definitions
+			.into_iter()
+			.filter_map(|(name, definition)| definition.map(|definition| (name, definition)))
+			.collect()
+	}
+
+	/// Defines the given type, whatever its kind, and returns the name of its definition.
+	pub(super) fn define(&mut self, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, JsonSchemaError> {
+		let name = self.name(symbol);
+		if !self.definitions.contains_key(&name) {
+			self.definitions.insert(name.clone(), None);
+			let definition = self.definition(symbol)?;
+			self.definitions.insert(name.clone(), Some(definition));
+		}
+		Ok(name)
+	}
+
+	/// Returns the name of the definition of the given type.
+	fn name(&self, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+		let id = self.registry.resolve_type(symbol).map(|id_def| id_def.id());
+		let name = identifier(&type_name(self.registry, symbol));
+		match id {
+			Some(TypeId::Custom(custom)) => custom
+				.namespace()
+				.segments()
+				.iter()
+				.map(|segment| resolve_string(self.registry, *segment))
+				.chain(core::iter::once(name.as_str()))
+				.collect::<Vec<_>>()
+				.join("."),
+			Some(TypeId::Tuple(tuple)) if tuple.is_unit() => "Unit".to_string(),
+			Some(TypeId::Tuple(_)) => format!("Tuple_{}", name),
+			_ => name,
+		}
+	}
+
+	/// Returns the schema of the given type, i.e. a reference for custom types.
+	fn schema(&mut self, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, JsonSchemaError> {
+		match self.registry.resolve_type(symbol).map(|id_def| id_def.id()) {
+			Some(TypeId::Custom(_)) => {
+				let name = self.define(symbol)?;
+				Ok(object(vec![("$ref", json_string(&self.reference(&name)))]))
+			}
+			_ => self.definition(symbol),
+		}
+	}
+
+	/// Returns the full schema of the given type.
+	fn definition(&mut self, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, JsonSchemaError> {
+		let id_def = self
+			.registry
+			.resolve_type(symbol)
+			.ok_or(JsonSchemaError::UnknownType { id: symbol.id().get() })?;
+		match id_def.id() {
+			TypeId::Primitive(primitive) => Ok(primitive_schema(primitive).to_string()),
+			TypeId::Slice(slice) => Ok(format!(
+				"{{\"type\":\"array\",\"items\":{}}}",
+				self.schema(*slice.type_param())?
+			)),
+			TypeId::Array(array) => Ok(format!(
+				"{{\"type\":\"array\",\"items\":{},\"minItems\":{len},\"maxItems\":{len}}}",
+				self.schema(*array.type_param())?,
+				len = array.len()
+			)),
+			TypeId::Tuple(tuple) if tuple.is_unit() => Ok("{\"type\":\"null\"}".to_string()),
+			TypeId::Tuple(tuple) => Ok(tuple_schema(
+				tuple
+					.type_params()
+					.iter()
+					.map(|param| self.schema(*param))
+					.collect::<Result<_, _>>()?,
+			)),
+			TypeId::Custom(custom) => {
+				let schema = match id_def.def().clone().normalize() {
+					TypeDef::Composite(def) => self.fields(def.fields())?,
+					TypeDef::Variant(def) => self.variant(&def)?,
+					TypeDef::Builtin(Builtin::Json) | TypeDef::Builtin(Builtin::Raw(RawFormat::Json)) => {
+						"{}".to_string()
+					}
+					TypeDef::Builtin(Builtin::Raw(RawFormat::Scale)) => format!(
+						"{{\"type\":\"array\",\"items\":{}}}",
+						primitive_schema(&TypeIdPrimitive::U8)
+					),
+					_ => {
+						return Err(JsonSchemaError::Unsupported {
+							name: type_name(self.registry, symbol),
+						})
+					}
+				};
+				Ok(describe(schema, &self.docs(custom.docs())))
+			}
+		}
+	}
+
+	/// Returns the given documentation as a single string.
+	fn docs(&self, docs: &[UntrackedSymbol<&'static str>]) -> String {
+		docs.iter()
+			.map(|line| resolve_string(self.registry, *line).trim())
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	/// Returns the schema of the given fields of a composite or variant.
+	fn fields(&mut self, fields: &[Field<CompactForm>]) -> Result<String, JsonSchemaError> {
+		match fields {
+			[] => Ok("{\"type\":\"null\"}".to_string()),
+			[field] if field.name().is_none() => self.schema(*field.ty()),
+			_ if fields.iter().all(|field| field.name().is_some()) => {
+				let mut properties = Vec::with_capacity(fields.len());
+				for field in fields {
+					let name = field.name().map_or("", |name| resolve_string(self.registry, *name));
+					let schema = describe(self.schema(*field.ty())?, &self.docs(field.docs()));
+					properties.push((name, schema));
+				}
+				let required = properties.iter().map(|(name, _)| json_string(name)).collect::<Vec<_>>();
+				Ok(format!(
+					"{{\"type\":\"object\",\"properties\":{},\"required\":[{}],\"additionalProperties\":false}}",
+					object(properties),
+					required.join(",")
+				))
+			}
+			_ => Ok(tuple_schema(
+				fields
+					.iter()
+					.map(|field| self.schema(*field.ty()))
+					.collect::<Result<_, _>>()?,
+			)),
+		}
+	}
+
+	/// Returns the schema of the given variant type.
+	fn variant(&mut self, def: &TypeDefVariant<CompactForm>) -> Result<String, JsonSchemaError> {
+		let registry = self.registry;
+		let variants = def.variants();
+		let name = |variant: &Variant<CompactForm>| resolve_string(registry, *variant.name());
+		if variants.iter().all(|variant| variant.fields().is_empty()) {
+			let names = variants
+				.iter()
+				.map(|variant| json_string(name(variant)))
+				.collect::<Vec<_>>();
+			return Ok(format!("{{\"type\":\"string\",\"enum\":[{}]}}", names.join(",")));
+		}
+		if let [none, some] = variants {
+			if name(none) == "None" && none.fields().is_empty() && name(some) == "Some" && some.fields().len() == 1 {
+				return Ok(format!(
+					"{{\"oneOf\":[{{\"type\":\"null\"}},{}]}}",
+					self.fields(some.fields())?
+				));
+			}
+		}
+		let mut schemas = Vec::with_capacity(variants.len());
+		for variant in variants {
+			let name = name(variant);
+			let schema = if variant.fields().is_empty() {
+				format!("{{\"const\":{}}}", json_string(name))
+			} else {
+				format!(
+					"{{\"type\":\"object\",\"properties\":{},\"required\":[{}],\"additionalProperties\":false}}",
+					object(vec![(name, self.fields(variant.fields())?)]),
+					json_string(name)
+				)
+			};
+			schemas.push(describe(schema, &self.docs(variant.docs())));
+		}
+		Ok(format!("{{\"oneOf\":[{}]}}", schemas.join(",")))
+	}
+}
+
+/// Returns the schema of the given primitive type.
+fn primitive_schema(primitive: &TypeIdPrimitive) -> &'static str {
+	match primitive {
+		TypeIdPrimitive::Bool => "{\"type\":\"boolean\"}",
+		TypeIdPrimitive::Char => "{\"type\":\"string\",\"minLength\":1,\"maxLength\":1}",
+		TypeIdPrimitive::Str => "{\"type\":\"string\"}",
+		TypeIdPrimitive::U8 => "{\"type\":\"integer\",\"minimum\":0,\"maximum\":255}",
+		TypeIdPrimitive::U16 => "{\"type\":\"integer\",\"minimum\":0,\"maximum\":65535}",
+		TypeIdPrimitive::U32 => "{\"type\":\"integer\",\"format\":\"int64\",\"minimum\":0,\"maximum\":4294967295}",
+		TypeIdPrimitive::U64 | TypeIdPrimitive::U128 => "{\"type\":\"integer\",\"minimum\":0}",
+		TypeIdPrimitive::I8 => "{\"type\":\"integer\",\"minimum\":-128,\"maximum\":127}",
+		TypeIdPrimitive::I16 => "{\"type\":\"integer\",\"minimum\":-32768,\"maximum\":32767}",
+		TypeIdPrimitive::I32 => "{\"type\":\"integer\",\"format\":\"int32\"}",
+		TypeIdPrimitive::I64 => "{\"type\":\"integer\",\"format\":\"int64\"}",
+		TypeIdPrimitive::I128 => "{\"type\":\"integer\"}",
+	}
+}
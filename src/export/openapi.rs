@@ -0,0 +1,171 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the `components/schemas` section of OpenAPI 3.1 documents from the types of a registry.
+//!
+//! OpenAPI 3.1 schemas are JSON Schemas of draft 2020-12, so the types are mapped as by the
+//! `json_schema` module. Every selected root type and every custom type used by them becomes
+//! a component and is referred to by `#/components/schemas/<name>`.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{export::openapi, Metadata, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! let ty = builder.register_type(&<Option<bool>>::meta_type());
+//! let registry = builder.build();
+//! assert_eq!(
+//!     openapi::components(&registry, &[ty]).unwrap(),
+//!     r#"{"components":{"schemas":{"Option_bool":{"oneOf":[{"type":"null"},{"type":"boolean"}]}}}}"#,
+//! );
+//! ```
+
+use super::json_schema::{object, Generator, JsonSchemaError};
+use crate::tm_std::*;
+use crate::{interner::UntrackedSymbol, Registry};
+
+/// The prefix of references to the components of an OpenAPI document.
+const PREFIX: &str = "#/components/schemas/";
+
+/// Generates the schemas of the given root types and all custom types used by them.
+///
+/// Maps the component names to their schemas in order to be merged into existing documents.
+///
+/// # Errors
+///
+/// If one of the types is not registered or has no JSON representation.
+pub fn schemas(
+	registry: &Registry,
+	roots: &[UntrackedSymbol<AnyTypeId>],
+) -> Result<BTreeMap<String, String>, JsonSchemaError> {
+	let mut generator = Generator::new(registry, PREFIX);
+	for root in roots {
+		generator.define(*root)?;
+	}
+	Ok(generator.into_definitions())
+}
+
+/// Generates the `components` object with the schemas of the given root types
+/// and all custom types used by them.
+///
+/// # Errors
+///
+/// If one of the types is not registered or has no JSON representation.
+pub fn components(registry: &Registry, roots: &[UntrackedSymbol<AnyTypeId>]) -> Result<String, JsonSchemaError> {
+	let schemas = object(schemas(registry, roots)?);
+	Ok(object(vec![("components", object(vec![("schemas", schemas)]))]))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Field, Metadata, TypeDef, TypeDefComposite, TypeDefVariant, TypeIdCustom, Variant};
+
+	#[allow(unused)]
+	struct Node {
+		label: String,
+		children: Vec<Node>,
+	}
+
+	impl crate::HasTypeId for Node {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Node", crate::Namespace::new(vec!["api"]).unwrap(), vec![])
+				.with_docs(&["A node of a tree."])
+				.into()
+		}
+	}
+
+	impl crate::HasTypeDef for Node {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![
+				Field::named("label", String::meta_type()),
+				Field::named("children", <Vec<Node>>::meta_type()),
+			])
+			.into()
+		}
+	}
+
+	#[allow(unused)]
+	enum Command {
+		Move { x: i32, y: i32 },
+		Resize(u16, u16),
+		Stop,
+	}
+
+	impl crate::HasTypeId for Command {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Command", crate::Namespace::new(vec!["api"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Command {
+		fn type_def() -> TypeDef {
+			TypeDefVariant::new(vec![
+				Variant::new("Move").with_fields(vec![
+					Field::named("x", i32::meta_type()),
+					Field::named("y", i32::meta_type()),
+				]),
+				Variant::new("Resize")
+					.with_fields(vec![Field::unnamed(u16::meta_type()), Field::unnamed(u16::meta_type())]),
+				Variant::new("Stop"),
+			])
+			.into()
+		}
+	}
+
+	#[test]
+	fn generates_recursive_components() {
+		let mut registry = Registry::new();
+		let node = registry.compact_type(&Node::meta_type());
+		let schemas = schemas(&registry, &[node]).unwrap();
+		assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["Vec_Node", "api.Node"]);
+		assert_eq!(
+			schemas["api.Node"],
+			"{\"description\":\"A node of a tree.\",\"type\":\"object\",\"properties\":{\
+			 \"label\":{\"type\":\"string\"},\
+			 \"children\":{\"$ref\":\"#/components/schemas/Vec_Node\"}},\
+			 \"required\":[\"label\",\"children\"],\"additionalProperties\":false}"
+		);
+		assert_eq!(
+			schemas["Vec_Node"],
+			"{\"type\":\"object\",\"properties\":{\
+			 \"elems\":{\"type\":\"array\",\"items\":{\"$ref\":\"#/components/schemas/api.Node\"}}},\
+			 \"required\":[\"elems\"],\"additionalProperties\":false}"
+		);
+	}
+
+	#[test]
+	fn generates_externally_tagged_variants() {
+		let mut registry = Registry::new();
+		let command = registry.compact_type(&Command::meta_type());
+		let tuple = registry.compact_type(&<(bool, [u8; 2])>::meta_type());
+		assert_eq!(
+			components(&registry, &[command, tuple]).unwrap(),
+			"{\"components\":{\"schemas\":{\
+			 \"Tuple_bool_u8_2\":{\"type\":\"array\",\"prefixItems\":[{\"type\":\"boolean\"},\
+			 {\"type\":\"array\",\"items\":{\"type\":\"integer\",\"minimum\":0,\"maximum\":255},\"minItems\":2,\"maxItems\":2}],\
+			 \"items\":false,\"minItems\":2,\"maxItems\":2},\
+			 \"api.Command\":{\"oneOf\":[\
+			 {\"type\":\"object\",\"properties\":{\"Move\":{\"type\":\"object\",\"properties\":{\
+			 \"x\":{\"type\":\"integer\",\"format\":\"int32\"},\"y\":{\"type\":\"integer\",\"format\":\"int32\"}},\
+			 \"required\":[\"x\",\"y\"],\"additionalProperties\":false}},\"required\":[\"Move\"],\"additionalProperties\":false},\
+			 {\"type\":\"object\",\"properties\":{\"Resize\":{\"type\":\"array\",\"prefixItems\":[\
+			 {\"type\":\"integer\",\"minimum\":0,\"maximum\":65535},{\"type\":\"integer\",\"minimum\":0,\"maximum\":65535}],\
+			 \"items\":false,\"minItems\":2,\"maxItems\":2}},\"required\":[\"Resize\"],\"additionalProperties\":false},\
+			 {\"const\":\"Stop\"}]}}}}"
+		);
+	}
+}
@@ -0,0 +1,468 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates a Thrift IDL document from the types of a registry.
+//!
+//! Types are mapped as follows:
+//!
+//! - `bool` becomes `bool`, `char` and `str` become `string`.
+//! - `i8` becomes `byte`, all other integers become the smallest of `i16`, `i32` and `i64`
+//!   they fit into. `u64`, `u128` and `i128` become `binary` holding the little endian
+//!   bytes of the integer.
+//! - Slices and arrays of `u8` become `binary`, all others `list`.
+//! - Tuples become structs with fields `_0`, `_1`, ... and the unit tuple the empty struct `Unit`.
+//! - Composites become structs whose unnamed fields are named by their index as for tuples.
+//!   Fields of the shape of `Option` become `optional` fields of the type of `Some`.
+//! - Variants whose variants all lack fields become enums of their discriminants or positions.
+//!   All other variants become unions with one field per variant. Variants without fields
+//!   are of type `Unit`, variants with a single field of the type of that field and all
+//!   others of a struct of their fields named after the union and the variant.
+//! - JSON values become `string`, raw payloads `string` or `binary` depending on their format.
+//!
+//! Definitions are named after their types, e.g. `Option<u32>` becomes `Option_u32`, and
+//! after their full paths if several types share the same name. Types are defined before
+//! they are used except for recursive types.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{export::thrift::{self, FieldIds}, Metadata, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! builder.register_type(&<(u16, Option<String>)>::meta_type());
+//! let registry = builder.build();
+//! let idl = thrift::generate(&registry, FieldIds::Sequential).unwrap();
+//! assert!(idl.contains("struct Tuple_u16_Option_str {\n  1: i32 _0,\n"));
+//! ```
+
+use super::{identifier, resolve_string, type_name, type_path};
+use crate::tm_std::*;
+use crate::{
+	form::CompactForm, interner::UntrackedSymbol, Builtin, Field, RawFormat, Registry, TypeDef, TypeDefVariant, TypeId,
+	TypeIdPrimitive,
+};
+
+/// How the identifiers of the fields of structs and unions are assigned.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum FieldIds {
+	/// Fields are numbered by their position starting at `1`.
+	///
+	/// Identifiers change when fields are inserted or reordered.
+	#[default]
+	Sequential,
+	/// Fields are numbered by a hash of their names.
+	///
+	/// Identifiers stay the same when fields are inserted or reordered, but different names
+	/// may collide in which case generation fails.
+	Hashed,
+}
+
+/// An error upon generating a Thrift IDL document.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ThriftError {
+	/// The type is not registered with the registry or has been registered by identifier only.
+	UnknownType {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The type has no Thrift representation, e.g. unions or retired types.
+	Unsupported {
+		/// The name of the type.
+		name: String,
+	},
+	/// Two fields of a type have been assigned the same hashed identifier.
+	FieldIdCollision {
+		/// The name of the type.
+		name: String,
+		/// The assigned field identifier.
+		field_id: i16,
+	},
+}
+
+impl Display for ThriftError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			ThriftError::UnknownType { id } => write!(f, "unknown type with symbol {}", id),
+			ThriftError::Unsupported { name } => write!(f, "type `{}` has no Thrift representation", name),
+			ThriftError::FieldIdCollision { name, field_id } => {
+				write!(f, "fields of type `{}` collide on identifier {}", name, field_id)
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ThriftError {}
+
+/// Generates the Thrift IDL document defining all types of the given registry.
+///
+/// # Errors
+///
+/// If any of the types uses a type that is not registered or has no Thrift representation
+/// or if hashed field identifiers collide.
+pub fn generate(registry: &Registry, field_ids: FieldIds) -> Result<String, ThriftError> {
+	let mut names = BTreeMap::<String, usize>::new();
+	for (symbol, _) in registry.types() {
+		*names.entry(identifier(&type_name(registry, symbol))).or_default() += 1;
+	}
+	let mut generator = Generator {
+		registry,
+		field_ids,
+		names,
+		defined: BTreeSet::new(),
+		defined_unit: false,
+		idl: String::new(),
+	};
+	for (symbol, _) in registry.types() {
+		generator.define(symbol)?;
+	}
+	Ok(generator.idl)
+}
+
+/// Returns the identifier of the given field name in the given position.
+fn field_id(field_ids: FieldIds, name: &str, position: usize) -> i16 {
+	match field_ids {
+		FieldIds::Sequential => (position + 1) as i16,
+		FieldIds::Hashed => {
+			// 32-bit FNV-1a
+			let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+				(hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+			});
+			(hash % i16::MAX as u32) as i16 + 1
+		}
+	}
+}
+
+/// Generates definitions while keeping track of the types that have already been defined.
+struct Generator<'a> {
+	registry: &'a Registry,
+	field_ids: FieldIds,
+	/// The number of types sharing the same name.
+	names: BTreeMap<String, usize>,
+	/// The types defined or being defined so far.
+	defined: BTreeSet<UntrackedSymbol<AnyTypeId>>,
+	/// Whether the empty struct `Unit` has been defined.
+	defined_unit: bool,
+	/// The definitions generated so far.
+	idl: String,
+}
+
+impl Generator<'_> {
+	/// Returns the name of the definition of the given type.
+	fn name(&self, symbol: UntrackedSymbol<AnyTypeId>) -> String {
+		let name = identifier(&type_name(self.registry, symbol));
+		let name = if self.names.get(&name).copied().unwrap_or_default() > 1 {
+			identifier(&type_path(self.registry, symbol))
+		} else {
+			name
+		};
+		match self.registry.resolve_type(symbol).map(|id_def| id_def.id()) {
+			Some(TypeId::Tuple(_)) => format!("Tuple_{}", name),
+			_ => name,
+		}
+	}
+
+	/// Returns `true` if the given type is `u8`.
+	fn is_u8(&self, symbol: UntrackedSymbol<AnyTypeId>) -> bool {
+		matches!(
+			self.registry.resolve_type(symbol).map(|id_def| id_def.id()),
+			Some(TypeId::Primitive(TypeIdPrimitive::U8))
+		)
+	}
+
+	/// Returns the type of `Some` if the given type has the shape of `Option`.
+	fn option_param(&self, symbol: UntrackedSymbol<AnyTypeId>) -> Option<UntrackedSymbol<AnyTypeId>> {
+		let id_def = self.registry.resolve_type(symbol)?;
+		match id_def.def().clone().normalize() {
+			TypeDef::Variant(def) => match def.variants() {
+				[none, some]
+					if resolve_string(self.registry, *none.name()) == "None"
+						&& none.fields().is_empty()
+						&& resolve_string(self.registry, *some.name()) == "Some" =>
+				{
+					match some.fields() {
+						[field] => Some(*field.ty()),
+						_ => None,
+					}
+				}
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// Defines the given type and all types it uses unless already defined and returns
+	/// the Thrift type to refer to it.
+	fn define(&mut self, symbol: UntrackedSymbol<AnyTypeId>) -> Result<String, ThriftError> {
+		let id_def = self
+			.registry
+			.resolve_type(symbol)
+			.ok_or(ThriftError::UnknownType { id: symbol.id().get() })?;
+		let name = self.name(symbol);
+		match id_def.id() {
+			TypeId::Primitive(primitive) => return Ok(primitive_type(primitive).to_string()),
+			TypeId::Slice(slice) if self.is_u8(*slice.type_param()) => return Ok("binary".to_string()),
+			TypeId::Slice(slice) => return Ok(format!("list<{}>", self.define(*slice.type_param())?)),
+			TypeId::Array(array) if self.is_u8(array.type_param) => return Ok("binary".to_string()),
+			TypeId::Array(array) => return Ok(format!("list<{}>", self.define(array.type_param)?)),
+			TypeId::Tuple(tuple) if tuple.type_params.is_empty() => return Ok(self.unit()),
+			TypeId::Tuple(_) | TypeId::Custom(_) => (),
+		}
+		let def = id_def.def().clone().normalize();
+		match def {
+			TypeDef::Builtin(Builtin::Json) | TypeDef::Builtin(Builtin::Raw(RawFormat::Json)) => {
+				return Ok("string".to_string())
+			}
+			TypeDef::Builtin(Builtin::Raw(RawFormat::Scale)) => return Ok("binary".to_string()),
+			TypeDef::Composite(_) | TypeDef::Variant(_) => (),
+			_ if matches!(id_def.id(), TypeId::Tuple(_)) => (),
+			_ => {
+				return Err(ThriftError::Unsupported {
+					name: type_name(self.registry, symbol),
+				})
+			}
+		}
+		if !self.defined.insert(symbol) {
+			return Ok(name);
+		}
+		let definition = match (id_def.id(), def) {
+			(TypeId::Tuple(tuple), _) => {
+				let fields = tuple
+					.type_params
+					.iter()
+					.enumerate()
+					.map(|(index, param)| (format!("_{}", index), *param))
+					.collect::<Vec<_>>();
+				self.structure("struct", &name, &fields)?
+			}
+			(_, TypeDef::Composite(def)) => self.structure("struct", &name, &self.fields(def.fields()))?,
+			(_, TypeDef::Variant(def)) => self.variant(&name, &def)?,
+			_ => {
+				return Err(ThriftError::Unsupported {
+					name: type_name(self.registry, symbol),
+				})
+			}
+		};
+		self.idl.push_str(&definition);
+		Ok(name)
+	}
+
+	/// Defines the empty struct `Unit` unless already defined and returns its name.
+	fn unit(&mut self) -> String {
+		if !self.defined_unit {
+			self.defined_unit = true;
+			self.idl.push_str("struct Unit {}\n\n");
+		}
+		"Unit".to_string()
+	}
+
+	/// Returns the names and types of the given fields.
+	///
+	/// Unnamed fields are named by their index.
+	fn fields(&self, fields: &[Field<CompactForm>]) -> Vec<(String, UntrackedSymbol<AnyTypeId>)> {
+		fields
+			.iter()
+			.enumerate()
+			.map(|(index, field)| {
+				let name = match field.name() {
+					Some(name) => resolve_string(self.registry, *name).to_string(),
+					None => format!("_{}", index),
+				};
+				(name, *field.ty())
+			})
+			.collect()
+	}
+
+	/// Returns the definition of a struct or union with the given fields.
+	///
+	/// Defines the types of the fields first.
+	fn structure(
+		&mut self,
+		kind: &str,
+		name: &str,
+		fields: &[(String, UntrackedSymbol<AnyTypeId>)],
+	) -> Result<String, ThriftError> {
+		let mut ids = BTreeSet::new();
+		let mut definition = format!("{} {} {{\n", kind, name);
+		for (position, (field_name, ty)) in fields.iter().enumerate() {
+			let id = field_id(self.field_ids, field_name, position);
+			if !ids.insert(id) {
+				return Err(ThriftError::FieldIdCollision {
+					name: name.to_string(),
+					field_id: id,
+				});
+			}
+			let (optional, ty) = match self.option_param(*ty) {
+				Some(param) if kind == "struct" => ("optional ", self.define(param)?),
+				_ => ("", self.define(*ty)?),
+			};
+			definition.push_str(&format!("  {}: {}{} {},\n", id, optional, ty, field_name));
+		}
+		definition.push_str("}\n\n");
+		Ok(definition)
+	}
+
+	/// Returns the definition of an enum or union of the given variant type.
+	fn variant(&mut self, name: &str, def: &TypeDefVariant<CompactForm>) -> Result<String, ThriftError> {
+		if def.variants().iter().all(|variant| variant.fields().is_empty()) {
+			let mut definition = format!("enum {} {{\n", name);
+			for (position, variant) in def.variants().iter().enumerate() {
+				definition.push_str(&format!(
+					"  {} = {},\n",
+					resolve_string(self.registry, *variant.name()),
+					variant.discriminant().unwrap_or(position as u64)
+				));
+			}
+			definition.push_str("}\n\n");
+			return Ok(definition);
+		}
+		let mut fields = Vec::with_capacity(def.variants().len());
+		let mut variant_types = Vec::new();
+		for variant in def.variants() {
+			let variant_name = resolve_string(self.registry, *variant.name());
+			let ty = match variant.fields() {
+				[] => Err(self.unit()),
+				[field] => Ok(*field.ty()),
+				variant_fields => {
+					let struct_name = format!("{}_{}", name, variant_name);
+					variant_types.push(self.structure("struct", &struct_name, &self.fields(variant_fields))?);
+					Err(struct_name)
+				}
+			};
+			fields.push((variant_name.to_string(), ty));
+		}
+		for variant_type in variant_types {
+			self.idl.push_str(&variant_type);
+		}
+		let mut ids = BTreeSet::new();
+		let mut definition = format!("union {} {{\n", name);
+		for (position, (field_name, ty)) in fields.into_iter().enumerate() {
+			let id = field_id(self.field_ids, &field_name, position);
+			if !ids.insert(id) {
+				return Err(ThriftError::FieldIdCollision {
+					name: name.to_string(),
+					field_id: id,
+				});
+			}
+			let ty = match ty {
+				Ok(symbol) => self.define(symbol)?,
+				Err(name) => name,
+			};
+			definition.push_str(&format!("  {}: {} {},\n", id, ty, field_name));
+		}
+		definition.push_str("}\n\n");
+		Ok(definition)
+	}
+}
+
+/// Returns the Thrift type of the given primitive type.
+fn primitive_type(primitive: &TypeIdPrimitive) -> &'static str {
+	match primitive {
+		TypeIdPrimitive::Bool => "bool",
+		TypeIdPrimitive::Char | TypeIdPrimitive::Str => "string",
+		TypeIdPrimitive::I8 => "byte",
+		TypeIdPrimitive::U8 | TypeIdPrimitive::I16 => "i16",
+		TypeIdPrimitive::U16 | TypeIdPrimitive::I32 => "i32",
+		TypeIdPrimitive::U32 | TypeIdPrimitive::I64 => "i64",
+		TypeIdPrimitive::U64 | TypeIdPrimitive::U128 | TypeIdPrimitive::I128 => "binary",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Metadata, TypeDefComposite, TypeIdCustom, Variant};
+
+	#[allow(unused)]
+	struct Account {
+		nonce: u32,
+		memo: Option<String>,
+		status: Status,
+	}
+
+	impl crate::HasTypeId for Account {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Account", crate::Namespace::new(vec!["thrift"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Account {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![
+				Field::named("nonce", u32::meta_type()),
+				Field::named("memo", <Option<String>>::meta_type()),
+				Field::named("status", Status::meta_type()),
+			])
+			.into()
+		}
+	}
+
+	#[allow(unused)]
+	enum Status {
+		Active,
+		Frozen = 7,
+	}
+
+	impl crate::HasTypeId for Status {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Status", crate::Namespace::new(vec!["thrift"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for Status {
+		fn type_def() -> TypeDef {
+			TypeDefVariant::new(vec![
+				Variant::new("Active"),
+				Variant::new("Frozen").with_discriminant(7),
+			])
+			.into()
+		}
+	}
+
+	#[test]
+	fn generates_structs_and_enums() {
+		let mut registry = Registry::new();
+		registry.compact_type(&Account::meta_type());
+		let idl = generate(&registry, FieldIds::Sequential).unwrap();
+		let status = idl.find("enum Status {\n  Active = 0,\n  Frozen = 7,\n}\n").unwrap();
+		let account = idl
+			.find("struct Account {\n  1: i64 nonce,\n  2: optional string memo,\n  3: Status status,\n}\n")
+			.unwrap();
+		assert!(status < account);
+		assert!(idl.contains("union Option_str {\n  1: Unit None,\n  2: string Some,\n}\n"));
+		assert_eq!(idl.matches("struct Unit {}").count(), 1);
+	}
+
+	#[test]
+	fn generates_unions_of_variants() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Result<(u8, bool), [u16; 2]>>::meta_type());
+		let idl = generate(&registry, FieldIds::Sequential).unwrap();
+		assert!(idl.contains("struct Tuple_u8_bool {\n  1: i16 _0,\n  2: bool _1,\n}\n"));
+		assert!(idl.contains("union Result_u8_bool_u16_2 {\n  1: Tuple_u8_bool Ok,\n  2: list<i32> Err,\n}\n"));
+	}
+
+	#[test]
+	fn hashes_field_ids() {
+		let mut registry = Registry::new();
+		registry.compact_type(&Account::meta_type());
+		let idl = generate(&registry, FieldIds::Hashed).unwrap();
+		let nonce = field_id(FieldIds::Hashed, "nonce", 0);
+		assert_eq!(nonce, field_id(FieldIds::Hashed, "nonce", 2));
+		assert!(nonce > 0);
+		assert!(idl.contains(&format!("  {}: i64 nonce,\n", nonce)));
+		assert_eq!(identifier("Result<(), (bool, i32)>"), "Result_bool_i32");
+	}
+}
@@ -29,35 +29,61 @@
 //! can no longer be used in order to retrieve information from the
 //! original registry easily. Its sole purpose is for compact serialization.
 //!
+//! The resolved form is `ResolvedForm` and represents compact type identifiers
+//! and definitions read back from a registry with all their strings resolved.
+//!
+//! The expanded form is `ExpandedForm` and represents a self-contained tree of
+//! type identifiers and definitions that have their sub-types inlined.
+//!
 //! Other forms, such as a compact form that is still bound to the registry
 //! (also via lifetime tracking) are possible but current not needed.
 
 use crate::tm_std::*;
-use crate::{interner::UntrackedSymbol, meta_type::MetaType};
+use crate::{expand::ExpandedType, interner::UntrackedSymbol, meta_type::MetaType};
+#[cfg(feature = "serde")]
 use serde::Serialize;
 
+/// Bound for the string types of forms.
+///
+/// This requires `Serialize` only if the `serde` feature is enabled.
+#[cfg(feature = "serde")]
+pub trait FormString: Serialize {}
+
+#[cfg(feature = "serde")]
+impl<T> FormString for T where T: Serialize {}
+
+/// Bound for the string types of forms.
+///
+/// This requires `Serialize` only if the `serde` feature is enabled.
+#[cfg(not(feature = "serde"))]
+pub trait FormString {}
+
+#[cfg(not(feature = "serde"))]
+impl<T> FormString for T {}
+
 /// Trait to control the internal structures of type identifiers and definitions.
 ///
 /// This allows for type-level separation between free forms that can be instantiated
 /// out of the flux and compact forms that require some sort of interning data structures.
 pub trait Form {
 	/// The string type.
-	type String: Serialize + PartialEq + Eq + PartialOrd + Ord + Clone + core::fmt::Debug;
+	type String: FormString + PartialEq + Eq + PartialOrd + Ord + Hash + Clone + core::fmt::Debug;
 	/// The type identifier type.
-	type TypeId: PartialEq + Eq + PartialOrd + Ord + Clone + core::fmt::Debug;
+	type TypeId: PartialEq + Eq + PartialOrd + Ord + Hash + Clone + core::fmt::Debug;
 	/// A type identifier with indirection.
 	///
 	/// # Note
 	///
 	/// This is an optimization for the compact forms.
-	type IndirectTypeId: PartialEq + Eq + PartialOrd + Ord + Clone + core::fmt::Debug;
+	type IndirectTypeId: PartialEq + Eq + PartialOrd + Ord + Hash + Clone + core::fmt::Debug;
 }
 
 /// A meta meta-type.
 ///
 /// Allows to be converted into other forms such as compact form
 /// through the registry and `IntoCompact`.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum MetaForm {}
 
 impl Form for MetaForm {
@@ -73,7 +99,8 @@ impl Form for MetaForm {
 /// This resolves some lifetime issues with self-referential structs (such as
 /// the registry itself) but can no longer be used to resolve to the original
 /// underlying data.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum CompactForm {}
 
 impl Form for CompactForm {
@@ -81,3 +108,46 @@ impl Form for CompactForm {
 	type TypeId = UntrackedSymbol<AnyTypeId>;
 	type IndirectTypeId = Self::TypeId;
 }
+
+/// Compact form that has all its strings resolved by the registry.
+///
+/// Types are still referred to by their type symbols within the registry.
+/// This is the form of the items yielded by `Registry::iter_resolved`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ResolvedForm {}
+
+impl Form for ResolvedForm {
+	type String = &'static str;
+	type TypeId = UntrackedSymbol<AnyTypeId>;
+	type IndirectTypeId = Self::TypeId;
+}
+
+/// Form that owns all its strings.
+///
+/// Types are still referred to by their type symbols within the registry.
+/// This is the form of the types of an `OwnedRegistry` which can be deserialized
+/// from any buffer since it does not borrow `'static` strings.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum OwnedForm {}
+
+impl Form for OwnedForm {
+	type String = String;
+	type TypeId = UntrackedSymbol<AnyTypeId>;
+	type IndirectTypeId = Self::TypeId;
+}
+
+/// Form that has all sub-types expanded inline.
+///
+/// This is the form of the type identifiers and definitions found
+/// in the tree produced by `expand_type` and requires no registry.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ExpandedForm {}
+
+impl Form for ExpandedForm {
+	type String = &'static str;
+	type TypeId = ExpandedType;
+	type IndirectTypeId = Self::TypeId;
+}
@@ -0,0 +1,64 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A global registry of the types annotated across the crate graph.
+//!
+//! Types are annotated with the `register_global!` macro in the crate defining them
+//! and collected by the linker, so no central list of all types has to be maintained.
+//! The registry is filled upon the first call of `global_registry` and never changes afterwards.
+
+use crate::{tm_std::*, MetaType, Registry, RegistryBuilder};
+use once_cell::sync::Lazy;
+
+/// A type annotated by `register_global!`.
+#[doc(hidden)]
+pub struct GlobalType {
+	/// Returns the meta type of the annotated type.
+	pub meta_type: fn() -> MetaType,
+}
+
+inventory::collect!(GlobalType);
+
+/// The registry of all annotated types.
+static GLOBAL_REGISTRY: Lazy<Registry> = Lazy::new(|| {
+	let mut types = inventory::iter::<GlobalType>
+		.into_iter()
+		.map(|ty| (ty.meta_type)())
+		.collect::<Vec<_>>();
+	// The order in which the linker collects the types is unspecified. Registering
+	// them in order of their names keeps the type symbols deterministic.
+	types.sort_by_key(|ty| ty.to_string());
+	types.dedup();
+	let mut builder = RegistryBuilder::new();
+	for ty in &types {
+		builder.register_type(ty);
+	}
+	builder.build()
+});
+
+/// Returns the registry of all types annotated by `register_global!` across the crate graph.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{global_registry, register_global};
+/// register_global! { u32, Option<bool> }
+///
+/// assert!(global_registry().iter_resolved().count() >= 2);
+/// ```
+pub fn global_registry() -> &'static Registry {
+	&GLOBAL_REGISTRY
+}
@@ -48,32 +48,31 @@ impl_metadata_for_primitives!(
 	i128 => TypeIdPrimitive::I128,
 );
 
-macro_rules! impl_metadata_for_array {
-	( $( $n:expr )* ) => {
-		$(
-			impl<T: Metadata + 'static> HasTypeId for [T; $n] {
-				fn type_id() -> TypeId {
-					TypeIdArray::new($n, MetaType::new::<T>()).into()
-				}
-			}
+/// The length of an array type as stored by `TypeIdArray`.
+struct ArrayLen<const N: usize>;
+
+impl<const N: usize> ArrayLen<N> {
+	/// Fails the compilation for arrays longer than `u16::MAX`.
+	const LEN: u16 = {
+		assert!(
+			N <= u16::MAX as usize,
+			"arrays longer than `u16::MAX` are not supported"
+		);
+		N as u16
+	};
+}
 
-			impl<T: Metadata> HasTypeDef for [T; $n] {
-				fn type_def() -> TypeDef {
-					TypeDef::builtin()
-				}
-			}
-		)*
+impl<T: Metadata + 'static, const N: usize> HasTypeId for [T; N] {
+	fn type_id() -> TypeId {
+		TypeIdArray::new(ArrayLen::<N>::LEN, MetaType::new::<T>()).into()
 	}
 }
 
-#[rustfmt::skip]
-impl_metadata_for_array!(
-        1  2  3  4  5  6  7  8  9
-    10 11 12 13 14 15 16 17 18 19
-    20 21 22 23 24 25 26 27 28 29
-    30 31 32
-	40 48 56 64 72 96 128 160 192 224 256
-);
+impl<T: Metadata, const N: usize> HasTypeDef for [T; N] {
+	fn type_def() -> TypeDef {
+		TypeDef::builtin()
+	}
+}
 
 macro_rules! impl_metadata_for_tuple {
     ( $($ty:ident),* ) => {
@@ -127,7 +126,7 @@ where
 	T: Metadata + 'static,
 {
 	fn type_def() -> TypeDef {
-		TypeDefStruct::new(vec![NamedField::new("elems", MetaType::new::<[T]>())]).into()
+		TypeDefComposite::new(vec![NamedField::new("elems", MetaType::new::<[T]>())]).into()
 	}
 }
 
@@ -145,14 +144,46 @@ where
 	T: Metadata + 'static,
 {
 	fn type_def() -> TypeDef {
-		TypeDefEnum::new(vec![
-			EnumVariantUnit::new("None").into(),
-			EnumVariantTupleStruct::new("Some", vec![UnnamedField::of::<T>()]).into(),
+		TypeDefVariant::new(vec![
+			Variant::new("None"),
+			Variant::new("Some").with_fields(vec![UnnamedField::of::<T>()]),
 		])
+		.with_niche(T::NICHE)
 		.into()
 	}
 }
 
+macro_rules! impl_metadata_for_non_zero {
+	( $( $t:ty => $name:literal: $inner:ty, )* ) => { $(
+		impl HasTypeId for $t {
+			fn type_id() -> TypeId {
+				TypeIdCustom::new($name, Namespace::prelude(), vec![]).into()
+			}
+		}
+
+		impl HasTypeDef for $t {
+			const NICHE: bool = true;
+
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![UnnamedField::of::<$inner>()]).into()
+			}
+		}
+	)* }
+}
+
+impl_metadata_for_non_zero!(
+	NonZeroU8 => "NonZeroU8": u8,
+	NonZeroU16 => "NonZeroU16": u16,
+	NonZeroU32 => "NonZeroU32": u32,
+	NonZeroU64 => "NonZeroU64": u64,
+	NonZeroU128 => "NonZeroU128": u128,
+	NonZeroI8 => "NonZeroI8": i8,
+	NonZeroI16 => "NonZeroI16": i16,
+	NonZeroI32 => "NonZeroI32": i32,
+	NonZeroI64 => "NonZeroI64": i64,
+	NonZeroI128 => "NonZeroI128": i128,
+);
+
 impl<T, E> HasTypeId for Result<T, E>
 where
 	T: Metadata + 'static,
@@ -169,9 +200,105 @@ where
 	E: Metadata + 'static,
 {
 	fn type_def() -> TypeDef {
-		TypeDefEnum::new(vec![
-			EnumVariantTupleStruct::new("Ok", vec![UnnamedField::of::<T>()]).into(),
-			EnumVariantTupleStruct::new("Err", vec![UnnamedField::of::<E>()]).into(),
+		TypeDefVariant::new(vec![
+			Variant::new("Ok").with_fields(vec![UnnamedField::of::<T>()]),
+			Variant::new("Err").with_fields(vec![UnnamedField::of::<E>()]),
+		])
+		.into()
+	}
+}
+
+impl<B, C> HasTypeId for ControlFlow<B, C>
+where
+	B: Metadata + 'static,
+	C: Metadata + 'static,
+{
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("ControlFlow", Namespace::prelude(), tuple_meta_type!(B, C)).into()
+	}
+}
+
+impl<B, C> HasTypeDef for ControlFlow<B, C>
+where
+	B: Metadata + 'static,
+	C: Metadata + 'static,
+{
+	fn type_def() -> TypeDef {
+		TypeDefVariant::new(vec![
+			Variant::new("Continue").with_fields(vec![UnnamedField::of::<C>()]),
+			Variant::new("Break").with_fields(vec![UnnamedField::of::<B>()]),
+		])
+		.into()
+	}
+}
+
+impl<T> HasTypeId for Poll<T>
+where
+	T: Metadata + 'static,
+{
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Poll", Namespace::prelude(), tuple_meta_type![T]).into()
+	}
+}
+
+impl<T> HasTypeDef for Poll<T>
+where
+	T: Metadata + 'static,
+{
+	fn type_def() -> TypeDef {
+		TypeDefVariant::new(vec![
+			Variant::new("Ready").with_fields(vec![UnnamedField::of::<T>()]),
+			Variant::new("Pending"),
+		])
+		.into()
+	}
+}
+
+impl HasTypeId for Ordering {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Ordering", Namespace::prelude(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for Ordering {
+	fn type_def() -> TypeDef {
+		// The discriminants of `Ordering` are negative and thus
+		// cannot be represented by variant discriminants.
+		TypeDefVariant::new(vec![
+			Variant::new("Less"),
+			Variant::new("Equal"),
+			Variant::new("Greater"),
+		])
+		.into()
+	}
+}
+
+impl HasTypeId for Duration {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Duration", Namespace::prelude(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for Duration {
+	fn type_def() -> TypeDef {
+		TypeDefComposite::new(vec![NamedField::of::<u64>("secs"), NamedField::of::<u32>("nanos")]).into()
+	}
+}
+
+#[cfg(feature = "std")]
+impl HasTypeId for SystemTime {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("SystemTime", Namespace::prelude(), vec![]).into()
+	}
+}
+
+// The fields are named as serialized by serde, i.e. relative to the unix epoch.
+#[cfg(feature = "std")]
+impl HasTypeDef for SystemTime {
+	fn type_def() -> TypeDef {
+		TypeDefComposite::new(vec![
+			NamedField::of::<u64>("secs_since_epoch"),
+			NamedField::of::<u32>("nanos_since_epoch"),
 		])
 		.into()
 	}
@@ -193,59 +320,190 @@ where
 	V: Metadata + 'static,
 {
 	fn type_def() -> TypeDef {
-		TypeDefStruct::new(vec![NamedField::new("elems", MetaType::new::<[(K, V)]>())]).into()
+		TypeDefComposite::new(vec![NamedField::new("elems", MetaType::new::<[(K, V)]>())]).into()
 	}
 }
 
-impl<T> HasTypeId for Box<T>
+#[cfg(feature = "std")]
+impl<K, V, S> HasTypeId for HashMap<K, V, S>
 where
-	T: HasTypeId + ?Sized,
+	K: Metadata + 'static,
+	V: Metadata + 'static,
 {
 	fn type_id() -> TypeId {
-		T::type_id()
+		TypeIdCustom::new("HashMap", Namespace::prelude(), tuple_meta_type!(K, V)).into()
 	}
 }
 
-impl<T> HasTypeDef for Box<T>
+#[cfg(feature = "std")]
+impl<K, V, S> HasTypeDef for HashMap<K, V, S>
 where
-	T: Metadata + ?Sized,
+	K: Metadata + 'static,
+	V: Metadata + 'static,
 {
 	fn type_def() -> TypeDef {
-		T::type_def()
+		TypeDefComposite::new(vec![NamedField::new("elems", MetaType::new::<[(K, V)]>())]).into()
 	}
 }
 
-impl<T> HasTypeId for &T
+#[cfg(feature = "std")]
+impl<T, S> HasTypeId for HashSet<T, S>
 where
-	T: HasTypeId + ?Sized,
+	T: Metadata + 'static,
 {
 	fn type_id() -> TypeId {
-		T::type_id()
+		TypeIdCustom::new("HashSet", Namespace::prelude(), tuple_meta_type!(T)).into()
 	}
 }
 
-impl<T> HasTypeDef for &T
+#[cfg(feature = "std")]
+impl<T, S> HasTypeDef for HashSet<T, S>
 where
-	T: Metadata + ?Sized,
+	T: Metadata + 'static,
 {
 	fn type_def() -> TypeDef {
-		T::type_def()
+		TypeDefComposite::new(vec![NamedField::new("elems", MetaType::new::<[T]>())]).into()
 	}
 }
 
-impl<T> HasTypeId for &mut T
+// Sets, deques and heaps are sequences of their elements like `Vec<T>`, yet keep their own
+// name so that consumers know about the ordering and uniqueness of the elements.
+
+macro_rules! impl_metadata_for_sequences {
+	( $( $name:literal => $ty:ty, )* ) => { $(
+		impl<T> HasTypeId for $ty
+		where
+			T: Metadata + 'static,
+		{
+			fn type_id() -> TypeId {
+				TypeIdCustom::new($name, Namespace::prelude(), tuple_meta_type!(T)).into()
+			}
+		}
+
+		impl<T> HasTypeDef for $ty
+		where
+			T: Metadata + 'static,
+		{
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![NamedField::new("elems", MetaType::new::<[T]>())]).into()
+			}
+		}
+	)* }
+}
+
+impl_metadata_for_sequences!(
+	"BTreeSet" => BTreeSet<T>,
+	"VecDeque" => VecDeque<T>,
+	"BinaryHeap" => BinaryHeap<T>,
+);
+
+// By default boxes and references are transparent: they share the identifier and
+// definition of the referenced type, e.g. `&'a str`, `Box<str>` and `String` all are `str`.
+// Lifetimes never appear in the metadata, so `&'a T` is the same type for all `'a`.
+//
+// With the `reference-types` feature the reference layer is kept as the prelude types
+// `Box<T>` and `Ref<T>`, the latter for both shared and mutable references.
+//
+// Either way they are serialized as the referenced type and so keep its niche.
+
+macro_rules! impl_metadata_for_references {
+	( $( $name:literal => $ty:ty, )* ) => { $(
+		#[cfg(not(feature = "reference-types"))]
+		impl<T> HasTypeId for $ty
+		where
+			T: HasTypeId + ?Sized,
+		{
+			fn type_id() -> TypeId {
+				T::type_id()
+			}
+		}
+
+		#[cfg(not(feature = "reference-types"))]
+		impl<T> HasTypeDef for $ty
+		where
+			T: Metadata + ?Sized,
+		{
+			const NICHE: bool = T::NICHE;
+
+			fn type_def() -> TypeDef {
+				T::type_def()
+			}
+		}
+
+		#[cfg(feature = "reference-types")]
+		impl<T> HasTypeId for $ty
+		where
+			T: Metadata + ?Sized + 'static,
+		{
+			fn type_id() -> TypeId {
+				TypeIdCustom::new($name, Namespace::prelude(), vec![T::meta_type()]).into()
+			}
+		}
+
+		#[cfg(feature = "reference-types")]
+		impl<T> HasTypeDef for $ty
+		where
+			T: Metadata + ?Sized + 'static,
+		{
+			const NICHE: bool = T::NICHE;
+
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![UnnamedField::of::<T>()]).into()
+			}
+		}
+	)* }
+}
+
+impl_metadata_for_references!(
+	"Box" => Box<T>,
+	"Ref" => &T,
+	"Ref" => &mut T,
+);
+
+// Shared pointers and copy-on-write references are always transparent, also with the
+// `reference-types` feature, since they are serialized as the value they point to.
+
+macro_rules! impl_metadata_for_shared_pointers {
+	( $( $ty:ty, )* ) => { $(
+		impl<T> HasTypeId for $ty
+		where
+			T: HasTypeId + ?Sized,
+		{
+			fn type_id() -> TypeId {
+				T::type_id()
+			}
+		}
+
+		impl<T> HasTypeDef for $ty
+		where
+			T: Metadata + ?Sized,
+		{
+			const NICHE: bool = T::NICHE;
+
+			fn type_def() -> TypeDef {
+				T::type_def()
+			}
+		}
+	)* }
+}
+
+impl_metadata_for_shared_pointers!(Rc<T>, Arc<T>,);
+
+impl<T> HasTypeId for Cow<'_, T>
 where
-	T: HasTypeId + ?Sized,
+	T: HasTypeId + ToOwned + ?Sized,
 {
 	fn type_id() -> TypeId {
 		T::type_id()
 	}
 }
 
-impl<T> HasTypeDef for &mut T
+impl<T> HasTypeDef for Cow<'_, T>
 where
-	T: Metadata + ?Sized,
+	T: Metadata + ToOwned + ?Sized,
 {
+	const NICHE: bool = T::NICHE;
+
 	fn type_def() -> TypeDef {
 		T::type_def()
 	}
@@ -289,7 +547,7 @@ impl HasTypeId for String {
 
 impl HasTypeDef for String {
 	fn type_def() -> TypeDef {
-		TypeDefStruct::new(vec![NamedField::new("vec", MetaType::new::<Vec<u8>>())]).into()
+		TypeDefComposite::new(vec![NamedField::new("vec", MetaType::new::<Vec<u8>>())]).into()
 	}
 }
 
@@ -307,6 +565,15 @@ where
 	T: Metadata + ?Sized,
 {
 	fn type_def() -> TypeDef {
-		TypeDefTupleStruct::new(vec![]).into()
+		TypeDefComposite::unit().into()
 	}
 }
+
+#[cfg(feature = "futures")]
+mod futures;
+#[cfg(feature = "num-bigint")]
+mod num_bigint;
+#[cfg(feature = "primitive-types")]
+mod primitive_types;
+#[cfg(feature = "serde_json")]
+mod serde_json;
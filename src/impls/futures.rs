@@ -0,0 +1,75 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementations for pinned pointers and boxed futures.
+//!
+//! Async service descriptors usually hold `Pin<Box<dyn Future<Output = T> + Send>>`.
+//! Pinning does not change the representation of a pointer, so `Pin<P>` is transparent
+//! and shares the identifier and definition of `P`. A future cannot be serialized and
+//! is therefore opaque: it is described by the prelude type `Future<T>` with a builtin
+//! definition that only records its output type `T`.
+
+use crate::tm_std::*;
+use crate::*;
+use core::{future::Future, pin::Pin};
+
+impl<P> HasTypeId for Pin<P>
+where
+	P: HasTypeId,
+{
+	fn type_id() -> TypeId {
+		P::type_id()
+	}
+}
+
+impl<P> HasTypeDef for Pin<P>
+where
+	P: HasTypeDef,
+{
+	const NICHE: bool = P::NICHE;
+
+	fn type_def() -> TypeDef {
+		P::type_def()
+	}
+}
+
+macro_rules! impl_metadata_for_futures {
+	( $( $ty:ty, )* ) => { $(
+		impl<T> HasTypeId for $ty
+		where
+			T: Metadata + 'static,
+		{
+			fn type_id() -> TypeId {
+				TypeIdCustom::new("Future", Namespace::prelude(), vec![T::meta_type()]).into()
+			}
+		}
+
+		impl<T> HasTypeDef for $ty
+		where
+			T: Metadata + 'static,
+		{
+			fn type_def() -> TypeDef {
+				TypeDef::builtin()
+			}
+		}
+	)* }
+}
+
+impl_metadata_for_futures!(
+	dyn Future<Output = T>,
+	dyn Future<Output = T> + Send,
+	dyn Future<Output = T> + Send + Sync,
+);
@@ -0,0 +1,94 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementations for the big integer types of the `num-bigint` crate.
+//!
+//! Big integers are represented as variable-length sequences of bytes.
+//! Signed big integers additionally carry their sign as C-like enum.
+
+use crate::tm_std::*;
+use crate::*;
+use ::num_bigint::{BigInt, BigUint, Sign};
+
+fn num_bigint_namespace() -> Namespace {
+	Namespace::from_validated(vec!["num_bigint"])
+}
+
+impl HasTypeId for BigUint {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("BigUint", num_bigint_namespace(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for BigUint {
+	fn type_def() -> TypeDef {
+		TypeDefComposite::new(vec![NamedField::of::<Vec<u8>>("bytes")]).into()
+	}
+}
+
+impl HasTypeId for Sign {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Sign", num_bigint_namespace(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for Sign {
+	fn type_def() -> TypeDef {
+		TypeDefVariant::new(vec![
+			Variant::new("Minus").with_discriminant(0),
+			Variant::new("NoSign").with_discriminant(1),
+			Variant::new("Plus").with_discriminant(2),
+		])
+		.into()
+	}
+}
+
+impl HasTypeId for BigInt {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("BigInt", num_bigint_namespace(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for BigInt {
+	fn type_def() -> TypeDef {
+		TypeDefComposite::new(vec![
+			NamedField::of::<Sign>("sign"),
+			NamedField::of::<BigUint>("magnitude"),
+		])
+		.into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn big_int_structure() {
+		assert_eq!(
+			BigInt::type_def(),
+			TypeDefComposite::new(vec![
+				NamedField::new("sign", Sign::meta_type()),
+				NamedField::new("magnitude", BigUint::meta_type()),
+			])
+			.into()
+		);
+		assert_eq!(
+			BigUint::type_def(),
+			TypeDefComposite::new(vec![NamedField::new("bytes", <Vec<u8>>::meta_type())]).into()
+		);
+	}
+}
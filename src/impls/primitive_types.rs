@@ -0,0 +1,88 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementations for the fixed-size hash and unsigned integer types
+//! of the `primitive-types` crate.
+//!
+//! All of them are represented as tuple-structs wrapping a byte array
+//! of their respective size.
+
+use crate::tm_std::*;
+use crate::*;
+use ::primitive_types::{H160, H256, H512, U128, U256, U512};
+
+macro_rules! impl_metadata_for_fixed_bytes {
+	( $( $t:ident => $n:expr, )* ) => { $(
+		impl HasTypeId for $t {
+			fn type_id() -> TypeId {
+				TypeIdCustom::new(
+					stringify!($t),
+					Namespace::from_validated(vec!["primitive_types"]),
+					vec![],
+				)
+				.into()
+			}
+		}
+
+		impl HasTypeDef for $t {
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![UnnamedField::of::<[u8; $n]>()]).into()
+			}
+		}
+	)* }
+}
+
+impl_metadata_for_fixed_bytes!(
+	H160 => 20,
+	H256 => 32,
+	H512 => 64,
+	U128 => 16,
+	U256 => 32,
+	U512 => 64,
+);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fixed_bytes_structure() {
+		assert_eq!(
+			H256::type_id(),
+			TypeIdCustom::new("H256", Namespace::new(vec!["primitive_types"]).unwrap(), vec![]).into()
+		);
+		assert_eq!(
+			U512::type_def(),
+			TypeDefComposite::new(vec![UnnamedField::of::<[u8; 64]>()]).into()
+		);
+	}
+
+	#[test]
+	fn hashes_and_integers_share_byte_arrays() {
+		let mut builder = RegistryBuilder::new();
+		let h256 = builder.register_type(&H256::meta_type());
+		let u256 = builder.register_type(&U256::meta_type());
+		let bytes = builder.register_type(&<[u8; 32]>::meta_type());
+		let registry = builder.build();
+		assert_ne!(h256, u256);
+		let field = |symbol| match registry.resolve(&symbol).unwrap().def() {
+			TypeDef::Composite(def) => *def.fields()[0].ty(),
+			_ => panic!("fixed bytes are composites"),
+		};
+		assert_eq!(field(h256), field(u256));
+		assert_eq!(field(h256), bytes);
+	}
+}
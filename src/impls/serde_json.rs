@@ -0,0 +1,81 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementations for the dynamic JSON types of the `serde_json` crate.
+//!
+//! Since the structure of these types is only known at runtime they are
+//! defined as arbitrary JSON values instead of being skipped. Raw values
+//! are passed through unparsed and thus defined as raw JSON payloads.
+
+use crate::tm_std::*;
+use crate::*;
+use ::serde_json::{value::RawValue, Map, Value};
+
+fn serde_json_namespace() -> Namespace {
+	Namespace::from_validated(vec!["serde_json"])
+}
+
+impl HasTypeId for Value {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Value", serde_json_namespace(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for Value {
+	fn type_def() -> TypeDef {
+		TypeDef::json()
+	}
+}
+
+impl HasTypeId for Map<String, Value> {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("Map", serde_json_namespace(), tuple_meta_type!(String, Value)).into()
+	}
+}
+
+impl HasTypeDef for Map<String, Value> {
+	fn type_def() -> TypeDef {
+		TypeDef::json()
+	}
+}
+
+impl HasTypeId for RawValue {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new("RawValue", serde_json_namespace(), vec![]).into()
+	}
+}
+
+impl HasTypeDef for RawValue {
+	fn type_def() -> TypeDef {
+		TypeDef::raw(RawFormat::Json)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn json_values() {
+		assert_eq!(
+			Value::type_id(),
+			TypeIdCustom::new("Value", Namespace::new(vec!["serde_json"]).unwrap(), vec![]).into()
+		);
+		assert_eq!(Value::type_def(), TypeDef::json());
+		assert_eq!(<Map<String, Value>>::type_def(), TypeDef::json());
+		assert_eq!(RawValue::type_def(), TypeDef::raw(RawFormat::Json));
+	}
+}
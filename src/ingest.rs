@@ -0,0 +1,464 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Restrictions on the strings of registries imported from untrusted sources.
+//!
+//! The symbols of a registry in the nested layout are always validated upon deserializing an
+//! `OwnedRegistry`, while its strings are taken as they are. Consumers importing registries from
+//! untrusted sources may additionally reject absurdly long strings, non-ASCII strings or namespaces
+//! that are not made of identifiers with `OwnedRegistry::deserialize_with`, which checks every string
+//! as soon as it has been read. Violations are reported with the symbol of the offending string or
+//! type, i.e. its position in the registry.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{layout::nested, IngestError, IngestOptions, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! let symbol = builder.register_string("a string that is way too long");
+//! let registry = builder.build();
+//! let options = IngestOptions::permissive().with_max_string_len(16).with_ascii_only(true);
+//! assert_eq!(
+//!     options.check(&nested::Registry::from(&registry)),
+//!     Err(IngestError::StringTooLong { symbol: symbol.id().get(), len: 29 }),
+//! );
+//! ```
+
+use crate::layout::nested;
+use crate::tm_std::*;
+use crate::utils::is_rust_identifier;
+use crate::OwnedRegistry;
+use core::fmt::{Display, Error as FmtError, Formatter};
+use serde::{
+	de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, Visitor},
+	Deserialize, Deserializer,
+};
+
+/// A string of a registry violating the `IngestOptions` it is imported with.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum IngestError {
+	/// A string is longer than the maximum string length.
+	StringTooLong {
+		/// The string symbol of the string.
+		symbol: u32,
+		/// The length of the string in bytes.
+		len: usize,
+	},
+	/// A string contains a non-ASCII character although only ASCII strings are allowed.
+	NonAsciiString {
+		/// The string symbol of the string.
+		symbol: u32,
+		/// The byte index of the first non-ASCII character within the string.
+		index: usize,
+	},
+	/// A segment of the namespace of a custom type is not a Rust identifier.
+	InvalidNamespace {
+		/// The type symbol of the custom type.
+		ty: u32,
+		/// The index of the segment within the namespace.
+		segment: usize,
+	},
+}
+
+impl Display for IngestError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			IngestError::StringTooLong { symbol, len } => {
+				write!(f, "string {} is too long with {} bytes", symbol, len)
+			}
+			IngestError::NonAsciiString { symbol, index } => {
+				write!(f, "string {} has a non-ASCII character at byte {}", symbol, index)
+			}
+			IngestError::InvalidNamespace { ty, segment } => {
+				write!(f, "namespace segment {} of type {} is not an identifier", segment, ty)
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IngestError {}
+
+/// The restrictions on the strings of an imported registry.
+///
+/// By default all strings are accepted.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct IngestOptions {
+	/// The maximum length of strings in bytes, if any.
+	max_string_len: Option<usize>,
+	/// Whether all namespace segments have to be Rust identifiers.
+	identifier_namespaces: bool,
+	/// Whether all strings have to be ASCII.
+	ascii_only: bool,
+}
+
+impl IngestOptions {
+	/// Creates options accepting all strings.
+	pub const fn permissive() -> Self {
+		Self {
+			max_string_len: None,
+			identifier_namespaces: false,
+			ascii_only: false,
+		}
+	}
+
+	/// Sets the maximum length of all strings, including documentation, in bytes.
+	pub const fn with_max_string_len(mut self, max_string_len: usize) -> Self {
+		self.max_string_len = Some(max_string_len);
+		self
+	}
+
+	/// Sets whether all segments of the namespaces of custom types have to be Rust identifiers
+	/// as required by `Namespace::new`.
+	pub const fn with_identifier_namespaces(mut self, identifier_namespaces: bool) -> Self {
+		self.identifier_namespaces = identifier_namespaces;
+		self
+	}
+
+	/// Sets whether all strings, including documentation, have to be ASCII.
+	pub const fn with_ascii_only(mut self, ascii_only: bool) -> Self {
+		self.ascii_only = ascii_only;
+		self
+	}
+
+	/// Checks the strings of the given registry against the options.
+	///
+	/// Strings are checked in order of their symbols before the namespaces of the types,
+	/// and the first violation is returned. Unknown symbols are left to the conversion
+	/// into an `OwnedRegistry`.
+	///
+	/// # Errors
+	///
+	/// If a string of the registry violates the options.
+	pub fn check(&self, registry: &nested::Registry) -> Result<(), IngestError> {
+		for (symbol, string) in (1..).zip(&registry.strings) {
+			self.check_string(symbol, string)?;
+		}
+		self.check_namespaces(registry)
+	}
+
+	/// Checks the string with the given symbol against the options.
+	fn check_string(&self, symbol: u32, string: &str) -> Result<(), IngestError> {
+		if self.max_string_len.is_some_and(|max| string.len() > max) {
+			return Err(IngestError::StringTooLong {
+				symbol,
+				len: string.len(),
+			});
+		}
+		if let Some(index) = self.ascii_only.then(|| string.find(|c: char| !c.is_ascii())).flatten() {
+			return Err(IngestError::NonAsciiString { symbol, index });
+		}
+		Ok(())
+	}
+
+	/// Checks the namespaces of the custom types of the given registry against the options.
+	fn check_namespaces(&self, registry: &nested::Registry) -> Result<(), IngestError> {
+		if !self.identifier_namespaces {
+			return Ok(());
+		}
+		for (ty, id_def) in (1..).zip(&registry.types) {
+			if let nested::TypeId::Custom { namespace, .. } = &id_def.id {
+				let invalid = namespace.iter().position(|segment| {
+					(*segment as usize)
+						.checked_sub(1)
+						.and_then(|index| registry.strings.get(index))
+						.is_some_and(|segment| !is_rust_identifier(segment))
+				});
+				if let Some(segment) = invalid {
+					return Err(IngestError::InvalidNamespace { ty, segment });
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+impl OwnedRegistry {
+	/// Deserializes a registry in the nested layout whose strings satisfy the given options.
+	///
+	/// Every string is checked as soon as it has been read, so that deserialization stops at the
+	/// first violating string without copying it or reading the remainder of the registry.
+	/// Namespaces are checked once all strings and types have been read.
+	///
+	/// # Errors
+	///
+	/// If the registry is malformed, its symbols do not resolve or its strings violate the options.
+	pub fn deserialize_with<'de, D>(deserializer: D, options: IngestOptions) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let registry = deserializer.deserialize_struct("Registry", FIELDS, RegistryVisitor { options })?;
+		options.check_namespaces(&registry).map_err(D::Error::custom)?;
+		Self::try_from(registry).map_err(D::Error::custom)
+	}
+}
+
+/// The fields of a registry in the nested layout.
+const FIELDS: &[&str] = &["version", "capabilities", "strings", "types", "aliases"];
+
+/// A field of a registry in the nested layout.
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+	/// The format version of the nested layout.
+	Version,
+	/// The capabilities of the producer.
+	Capabilities,
+	/// The strings in order of their symbols.
+	Strings,
+	/// The types in order of their symbols.
+	Types,
+	/// The domain-level names of types.
+	Aliases,
+	/// An unknown field which is ignored just like by `nested::Registry`.
+	#[serde(other)]
+	Unknown,
+}
+
+/// Rejects a field that has already been read.
+fn unique<T, E: Error>(value: &Option<T>, field: &'static str) -> Result<(), E> {
+	match value {
+		Some(_) => Err(E::duplicate_field(field)),
+		None => Ok(()),
+	}
+}
+
+/// Deserializes a registry in the nested layout checking its strings against the options.
+struct RegistryVisitor {
+	/// The options the strings are checked against.
+	options: IngestOptions,
+}
+
+impl<'de> Visitor<'de> for RegistryVisitor {
+	type Value = nested::Registry;
+
+	fn expecting(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.write_str("a registry in the nested layout")
+	}
+
+	fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+	where
+		A: SeqAccess<'de>,
+	{
+		let version = seq
+			.next_element::<nested::Version>()?
+			.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+		let capabilities = seq.next_element()?.unwrap_or_default();
+		let strings = seq
+			.next_element_seed(Strings { options: self.options })?
+			.ok_or_else(|| A::Error::invalid_length(2, &self))?;
+		let types = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(3, &self))?;
+		let aliases = seq.next_element()?.unwrap_or_default();
+		Ok(nested::Registry {
+			version: version.0,
+			capabilities,
+			strings,
+			types,
+			aliases,
+		})
+	}
+
+	fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+	where
+		A: MapAccess<'de>,
+	{
+		let (mut version, mut capabilities, mut strings, mut types, mut aliases) = (None, None, None, None, None);
+		while let Some(field) = map.next_key()? {
+			match field {
+				Field::Version => {
+					unique(&version, "version")?;
+					version = Some(map.next_value::<nested::Version>()?.0);
+				}
+				Field::Capabilities => {
+					unique(&capabilities, "capabilities")?;
+					capabilities = Some(map.next_value()?);
+				}
+				Field::Strings => {
+					unique(&strings, "strings")?;
+					strings = Some(map.next_value_seed(Strings { options: self.options })?);
+				}
+				Field::Types => {
+					unique(&types, "types")?;
+					types = Some(map.next_value()?);
+				}
+				Field::Aliases => {
+					unique(&aliases, "aliases")?;
+					aliases = Some(map.next_value()?);
+				}
+				Field::Unknown => {
+					map.next_value::<IgnoredAny>()?;
+				}
+			}
+		}
+		Ok(nested::Registry {
+			version: version.ok_or_else(|| A::Error::missing_field("version"))?,
+			capabilities: capabilities.unwrap_or_default(),
+			strings: strings.ok_or_else(|| A::Error::missing_field("strings"))?,
+			types: types.ok_or_else(|| A::Error::missing_field("types"))?,
+			aliases: aliases.unwrap_or_default(),
+		})
+	}
+}
+
+/// Deserializes the strings of a registry checking each of them against the options.
+struct Strings {
+	/// The options the strings are checked against.
+	options: IngestOptions,
+}
+
+impl<'de> DeserializeSeed<'de> for Strings {
+	type Value = Vec<String>;
+
+	fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_seq(self)
+	}
+}
+
+impl<'de> Visitor<'de> for Strings {
+	type Value = Vec<String>;
+
+	fn expecting(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.write_str("a sequence of strings")
+	}
+
+	fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+	where
+		A: SeqAccess<'de>,
+	{
+		let mut strings = Vec::new();
+		for symbol in 1..=u32::MAX {
+			match seq.next_element_seed(CheckedString {
+				options: self.options,
+				symbol,
+			})? {
+				Some(string) => strings.push(string),
+				None => break,
+			}
+		}
+		Ok(strings)
+	}
+}
+
+/// Deserializes a single string of a registry and checks it against the options before copying it.
+struct CheckedString {
+	/// The options the string is checked against.
+	options: IngestOptions,
+	/// The string symbol of the string.
+	symbol: u32,
+}
+
+impl<'de> DeserializeSeed<'de> for CheckedString {
+	type Value = String;
+
+	fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_str(self)
+	}
+}
+
+impl<'de> Visitor<'de> for CheckedString {
+	type Value = String;
+
+	fn expecting(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.write_str("a string")
+	}
+
+	fn visit_str<E: Error>(self, string: &str) -> Result<Self::Value, E> {
+		self.options.check_string(self.symbol, string).map_err(E::custom)?;
+		Ok(string.to_string())
+	}
+
+	fn visit_string<E: Error>(self, string: String) -> Result<Self::Value, E> {
+		self.options.check_string(self.symbol, &string).map_err(E::custom)?;
+		Ok(string)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Metadata, Registry};
+
+	/// Returns the given registry in the nested layout.
+	fn nested(registry: &Registry) -> nested::Registry {
+		nested::Registry::from(registry)
+	}
+
+	#[test]
+	fn accepts_registries_by_default() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<u32>>::meta_type());
+		registry.compact_string("Grüße");
+		assert_eq!(IngestOptions::default().check(&nested(&registry)), Ok(()));
+		assert_eq!(IngestOptions::default(), IngestOptions::permissive());
+	}
+
+	#[test]
+	fn reports_positions_of_violations() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<u32>>::meta_type());
+		let long = registry.compact_string("a rather long string");
+		let non_ascii = registry.compact_string("Grüße");
+		let registry = nested(&registry);
+		assert_eq!(
+			IngestOptions::permissive().with_max_string_len(8).check(&registry),
+			Err(IngestError::StringTooLong {
+				symbol: long.id().get(),
+				len: 20
+			})
+		);
+		assert_eq!(
+			IngestOptions::permissive().with_ascii_only(true).check(&registry),
+			Err(IngestError::NonAsciiString {
+				symbol: non_ascii.id().get(),
+				index: 2
+			})
+		);
+		assert_eq!(
+			IngestOptions::permissive()
+				.with_identifier_namespaces(true)
+				.check(&registry),
+			Ok(())
+		);
+	}
+
+	#[test]
+	fn rejects_non_identifier_namespaces() {
+		let mut registry = nested(&Registry::new());
+		registry.strings = vec!["Account".to_string(), "app".to_string(), "not-an-ident".to_string()];
+		registry.types.push(nested::TypeIdDef {
+			id: nested::TypeId::Custom {
+				name: 1,
+				namespace: vec![2, 3],
+				params: vec![],
+				consts: vec![],
+				id: None,
+				docs: vec![],
+			},
+			def: nested::TypeDef::TupleStruct { types: vec![] },
+		});
+		let options = IngestOptions::permissive().with_identifier_namespaces(true);
+		assert_eq!(
+			options.check(&registry),
+			Err(IngestError::InvalidNamespace { ty: 1, segment: 1 })
+		);
+	}
+}
@@ -22,34 +22,74 @@
 //!
 //! The interners provide a strict ordered sequence of cached (aka interned) elements
 //! and is later used for compact serialization within the registry.
+//!
+//! # Hashers
+//!
+//! By default interned elements are looked up using a `BTreeMap`.
+//! For big registries the following features switch to a `HashMap` with a fast hasher:
+//!
+//! - `fxhash-interner`: Uses the `FxHasher` of the `fxhash` crate.
+//! - `ahash-interner`: Uses the `AHasher` of the `ahash` crate.
+//!
+//! Both features require `std`. If both are enabled `fxhash-interner` takes precedence.
+//! Since the ordering of symbols is solely determined by the order of interning
+//! the chosen lookup map does not affect serialization.
 
 use crate::tm_std::*;
+#[cfg(feature = "serde")]
 use serde::Serialize;
 
 /// A symbol that is not lifetime tracked.
 ///
 /// This can be used by self-referential types but
 /// can no longer be used to resolve instances.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
-#[serde(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct UntrackedSymbol<T> {
 	id: NonZeroU32,
-	#[serde(skip)]
+	#[cfg_attr(feature = "serde", serde(skip))]
 	marker: PhantomData<fn() -> T>,
 }
 
 /// A symbol from an interner.
 ///
 /// Can be used to resolve to the associated instance.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
-#[serde(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Symbol<'a, T> {
 	id: NonZeroU32,
-	#[serde(skip)]
+	#[cfg_attr(feature = "serde", serde(skip))]
 	marker: PhantomData<fn() -> &'a T>,
 }
 
+impl<T> UntrackedSymbol<T> {
+	/// Returns the raw identifier of the symbol.
+	///
+	/// Identifiers start at 1 and reflect the order of interning.
+	pub fn id(&self) -> NonZeroU32 {
+		self.id
+	}
+
+	/// Creates a symbol from a raw identifier, e.g. of a deserialized registry.
+	pub(crate) fn from_id(id: NonZeroU32) -> Self {
+		Self {
+			id,
+			marker: PhantomData,
+		}
+	}
+}
+
 impl<T> Symbol<'_, T> {
+	/// Creates the symbol of the element at the given position of an interner.
+	fn at(index: usize) -> Option<Self> {
+		Some(Self {
+			id: symbol_id(index)?,
+			marker: PhantomData,
+		})
+	}
+
 	/// Removes the lifetime tracking for this symbol.
 	///
 	/// # Note
@@ -73,6 +113,18 @@ impl<T> Symbol<'_, T> {
 	}
 }
 
+/// The map used to look up the identifiers of already interned elements.
+#[cfg(not(any(feature = "fxhash-interner", feature = "ahash-interner")))]
+type InternerMap<T> = BTreeMap<T, usize>;
+
+/// The map used to look up the identifiers of already interned elements.
+#[cfg(feature = "fxhash-interner")]
+type InternerMap<T> = std::collections::HashMap<T, usize, core::hash::BuildHasherDefault<fxhash::FxHasher>>;
+
+/// The map used to look up the identifiers of already interned elements.
+#[cfg(all(feature = "ahash-interner", not(feature = "fxhash-interner")))]
+type InternerMap<T> = std::collections::HashMap<T, usize, core::hash::BuildHasherDefault<ahash::AHasher>>;
+
 /// Interning data structure generic over the element type.
 ///
 /// For the sake of simplicity and correctness we are using a rather naive implementation.
@@ -81,14 +133,15 @@ impl<T> Symbol<'_, T> {
 ///
 /// This is used in order to quite efficiently cache strings and type
 /// definitions uniquely identified by their associated type identifiers.
-#[derive(Debug, PartialEq, Eq, Serialize)]
-#[serde(transparent)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Interner<T> {
 	/// A mapping from the interned elements to their respective compact identifiers.
 	///
 	/// The idenfitiers can be used to retrieve information about the original element from the interner.
-	#[serde(skip)]
-	map: BTreeMap<T, usize>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	map: InternerMap<T>,
 	/// The ordered sequence of cached elements.
 	///
 	/// This is used to efficiently provide access to the cached elements and
@@ -97,66 +150,128 @@ pub struct Interner<T> {
 	vec: Vec<T>,
 }
 
+impl<T> PartialEq for Interner<T>
+where
+	T: PartialEq,
+{
+	fn eq(&self, other: &Self) -> bool {
+		// The lookup map is entirely determined by the sequence of interned elements.
+		self.vec == other.vec
+	}
+}
+
+impl<T> Eq for Interner<T> where T: Eq {}
+
 impl<T> Interner<T>
 where
-	T: Ord,
+	T: Ord + Hash,
 {
 	/// Creates a new empty interner.
 	pub fn new() -> Self {
 		Self {
-			map: BTreeMap::new(),
+			map: Default::default(),
 			vec: Vec::new(),
 		}
 	}
 }
 
-impl<T: Ord> Default for Interner<T> {
+impl<T: Ord + Hash> Default for Interner<T> {
 	fn default() -> Self {
 		Self::new()
 	}
 }
 
+/// An error that is encountered upon interning into an interner that has run out of symbols.
+///
+/// Symbols are backed by `NonZeroU32` so an interner holds at most `u32::MAX` elements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CapacityError;
+
+/// Returns the symbol identifier of the element at the given position of an interner.
+///
+/// Returns `None` if the position is beyond the range of symbol identifiers.
+pub(crate) fn symbol_id(index: usize) -> Option<NonZeroU32> {
+	let index: u32 = TryFrom::try_from(index).ok()?;
+	index.checked_add(1).and_then(NonZeroU32::new)
+}
+
 impl<T> Interner<T>
 where
-	T: Ord + Clone,
+	T: Ord + Hash + Clone,
 {
 	/// Interns the given element or returns its associated symbol if it has already been interned.
-	pub fn intern_or_get(&mut self, s: T) -> (bool, Symbol<T>) {
+	///
+	/// # Panics
+	///
+	/// If the interner has run out of symbols. Not available with the `no-panic` feature,
+	/// use `try_intern_or_get` instead.
+	#[cfg(not(feature = "no-panic"))]
+	pub fn intern_or_get(&mut self, s: T) -> (bool, Symbol<'_, T>) {
+		self.try_intern_or_get(s).expect("interners hold at most `u32::MAX` elements")
+	}
+
+	/// Interns the given element or returns its associated symbol if it has already been interned.
+	///
+	/// # Errors
+	///
+	/// If the element has not been interned yet and the interner has run out of symbols.
+	/// The interner is left unchanged in this case.
+	pub fn try_intern_or_get(&mut self, s: T) -> Result<(bool, Symbol<'_, T>), CapacityError> {
+		if let Some(&id) = self.map.get(&s) {
+			return Ok((false, Symbol::at(id).ok_or(CapacityError)?));
+		}
 		let next_id = self.vec.len();
-		let (inserted, sym_id) = match self.map.entry(s.clone()) {
-			Entry::Vacant(vacant) => {
-				vacant.insert(next_id);
-				self.vec.push(s);
-				(true, next_id)
-			}
-			Entry::Occupied(occupied) => (false, *occupied.get()),
-		};
-		(
-			inserted,
-			Symbol {
-				id: NonZeroU32::new((sym_id + 1) as u32).unwrap(),
-				marker: PhantomData,
-			},
-		)
+		let symbol = Symbol::at(next_id).ok_or(CapacityError)?;
+		self.map.insert(s.clone(), next_id);
+		self.vec.push(s);
+		Ok((true, symbol))
 	}
 
 	/// Returns the symbol of the given element or `None` if it hasn't been interned already.
-	pub fn get(&self, s: &T) -> Option<Symbol<T>> {
-		self.map.get(s).map(|&id| Symbol {
-			id: NonZeroU32::new(id as u32).unwrap(),
-			marker: PhantomData,
-		})
+	pub fn get(&self, s: &T) -> Option<Symbol<'_, T>> {
+		self.map.get(s).and_then(|&id| Symbol::at(id))
 	}
 
 	/// Resolves the original element given its associated symbol or
 	/// returns `None` if it has not been interned yet.
 	pub fn resolve(&self, sym: Symbol<T>) -> Option<&T> {
-		let idx = (sym.id.get() - 1) as usize;
-		if idx >= self.vec.len() {
-			return None;
+		self.vec.get((sym.id.get() - 1) as usize)
+	}
+
+	/// Removes all elements interned after the first `len` elements.
+	pub(crate) fn truncate(&mut self, len: usize) {
+		for element in self.vec.drain(len.min(self.vec.len())..) {
+			self.map.remove(&element);
 		}
+	}
+}
+
+impl<T> Interner<T> {
+	/// Returns the number of interned elements.
+	pub fn len(&self) -> usize {
+		self.vec.len()
+	}
+
+	/// Returns `true` if no elements have been interned.
+	pub fn is_empty(&self) -> bool {
+		self.vec.is_empty()
+	}
+
+	/// Resolves the original element given its associated untracked symbol.
+	///
+	/// # Note
+	///
+	/// Untracked symbols are not bound to the interner that created them.
+	/// Callers have to make sure that the symbol originates from this interner.
+	pub(crate) fn resolve_untracked(&self, sym: UntrackedSymbol<T>) -> Option<&T> {
 		self.vec.get((sym.id.get() - 1) as usize)
 	}
+
+	/// Returns all interned elements in order of their symbols.
+	#[cfg(feature = "serde")]
+	pub(crate) fn elements(&self) -> &[T] {
+		&self.vec
+	}
 }
 
 #[cfg(test)]
@@ -166,7 +281,7 @@ mod tests {
 	type StringInterner = Interner<&'static str>;
 
 	fn assert_id(interner: &mut StringInterner, new_symbol: &'static str, expected_id: u32) {
-		let actual_id = interner.intern_or_get(new_symbol).1.id.get();
+		let actual_id = interner.try_intern_or_get(new_symbol).unwrap().1.id.get();
 		assert_eq!(actual_id, expected_id,);
 	}
 
@@ -194,4 +309,22 @@ mod tests {
 		assert_resolve(&mut interner, 3, "1 2 3");
 		assert_resolve(&mut interner, 4, None);
 	}
+
+	#[test]
+	fn get_after_intern() {
+		let mut interner = StringInterner::new();
+		let hello = interner.try_intern_or_get("Hello").unwrap().1.into_untracked();
+		let world = interner.try_intern_or_get("World").unwrap().1.into_untracked();
+		assert_eq!(interner.get(&"Hello").map(Symbol::into_untracked), Some(hello));
+		assert_eq!(interner.get(&"World").map(Symbol::into_untracked), Some(world));
+		assert_eq!(interner.get(&"Foo"), None);
+	}
+
+	#[test]
+	fn symbol_ids_are_bounded() {
+		assert_eq!(symbol_id(0), NonZeroU32::new(1));
+		assert_eq!(symbol_id(u32::MAX as usize - 1), NonZeroU32::new(u32::MAX));
+		#[cfg(target_pointer_width = "64")]
+		assert_eq!(symbol_id(u32::MAX as usize), None);
+	}
 }
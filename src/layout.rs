@@ -0,0 +1,301 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Alternative serialization layouts of the registry.
+//!
+//! The default layout, `Layout::Flat`, is the one produced by serializing a `Registry`
+//! directly. It uses untagged enums with prefixed field names such as `"custom.name"`
+//! which keeps the output compact but is awkward to consume in languages without
+//! untagged enum support, e.g. in JavaScript.
+//!
+//! The alternative `Layout::Nested` tags every enum by wrapping its contents into an
+//! object keyed by the variant name, e.g. `{"custom": {"name": 1, ...}}`. The nested layout
+//! is described by the structures of the `nested` module which can also be deserialized.
+//!
+//! The opt-in `Layout::Tagged` tags every enum internally with a `"kind"` field instead,
+//! e.g. `{"kind": "custom", "name": 1, ...}`, as described by the `tagged` module.
+//!
+//! Use `Registry::with_layout` in order to serialize a registry in a chosen layout.
+//! The output then carries the layout in its `"version"` field and the capabilities
+//! of this build of the crate in its `"capabilities"` field.
+//!
+//! Clients that only refer to types by their identity may use `LayoutRegistry::ids_only`
+//! which omits all type definitions and the strings only used by them.
+//!
+//! Clients that track an evolving registry may keep it in a `cache::RegistryCache`.
+//!
+//! Reviewers may ask for an appendix with the size contributions of the namespaces
+//! using `LayoutRegistry::with_stats`.
+//!
+//! With the `scale` feature registries are encoded with the SCALE codec in the nested layout.
+
+pub mod cache;
+pub mod nested;
+#[cfg(feature = "scale-info")]
+pub mod portable;
+#[cfg(feature = "scale")]
+mod scale;
+pub mod stats;
+pub mod tagged;
+
+use crate::tm_std::*;
+use crate::{
+	registry::{DEFERRED_TYPES_ERROR, EXHAUSTED_ERROR},
+	Capabilities, Registry, TypeId,
+};
+use serde::ser::{Error as _, Serialize, SerializeStruct, Serializer};
+
+/// The layout of a serialized registry.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Layout {
+	/// The compact layout with untagged enums and prefixed field names.
+	#[default]
+	Flat,
+	/// The layout with externally tagged enums and nested objects.
+	Nested,
+	/// The layout with enums tagged internally by a `"kind"` field.
+	Tagged,
+}
+
+impl Layout {
+	/// Returns the format version that identifies the layout.
+	pub fn version(self) -> u32 {
+		match self {
+			Layout::Flat => 1,
+			Layout::Nested => 2,
+			Layout::Tagged => 3,
+		}
+	}
+
+	/// Returns the layout identified by the given format version if any.
+	pub fn from_version(version: u32) -> Option<Self> {
+		match version {
+			1 => Some(Layout::Flat),
+			2 => Some(Layout::Nested),
+			3 => Some(Layout::Tagged),
+			_ => None,
+		}
+	}
+}
+
+/// A registry that serializes in the given layout.
+///
+/// Created by `Registry::with_layout`.
+#[derive(Debug)]
+pub struct LayoutRegistry<'a> {
+	/// The registry with only the strings it refers to, see `Registry::localized`.
+	registry: Cow<'a, Registry>,
+	layout: Layout,
+	ids_only: bool,
+	stats: bool,
+}
+
+impl<'a> LayoutRegistry<'a> {
+	/// Creates a view of the registry that serializes in the given layout.
+	pub(crate) fn new(registry: &'a Registry, layout: Layout) -> Self {
+		Self {
+			registry: registry.localized(),
+			layout,
+			ids_only: false,
+			stats: false,
+		}
+	}
+
+	/// Serializes only the type identifiers without their definitions.
+	///
+	/// This interface view is considerably smaller and sufficient for clients that only
+	/// refer to types by their identity. Type and string symbols are the same as the ones
+	/// of the full registry so that both can be used side by side. Strings only used by
+	/// definitions are serialized as `null`. The output carries `"ids_only": true` and
+	/// can be deserialized into a `nested::Interface` in the nested layout.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{layout::Layout, Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	/// let interface = registry.with_layout(Layout::Nested).ids_only();
+	/// ```
+	pub fn ids_only(mut self) -> Self {
+		self.ids_only = true;
+		self
+	}
+
+	/// Appends the size contributions of the namespaces as `"stats"`, see `Registry::namespace_stats`.
+	///
+	/// Deserializers ignore the appendix.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{layout::Layout, Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	/// let with_stats = registry.with_layout(Layout::Nested).with_stats();
+	/// ```
+	pub fn with_stats(mut self) -> Self {
+		self.stats = true;
+		self
+	}
+
+	/// Returns the strings to serialize with the ones not referenced
+	/// by any type identifier removed if only identifiers are serialized.
+	fn strings(&self) -> Vec<Option<&'static str>> {
+		let mut strings = self.registry.strings().into_iter().map(Some).collect::<Vec<_>>();
+		if self.ids_only {
+			let mut used = vec![false; strings.len()];
+			for (_, id_def) in self.registry.types() {
+				if let TypeId::Custom(custom) = id_def.id() {
+					let symbols = custom.namespace().segments().iter().chain(custom.stable_id());
+					for symbol in core::iter::once(custom.name()).chain(symbols) {
+						if let Some(used) = used.get_mut(symbol.id().get() as usize - 1) {
+							*used = true;
+						}
+					}
+				}
+			}
+			for (name, _) in self.registry.alias_symbols() {
+				if let Some(used) = used.get_mut(name.id().get() as usize - 1) {
+					*used = true;
+				}
+			}
+			for (string, used) in strings.iter_mut().zip(used) {
+				if !used {
+					*string = None;
+				}
+			}
+		}
+		strings
+	}
+}
+
+impl Serialize for LayoutRegistry<'_> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		if self.registry.deferred_len() > 0 {
+			return Err(S::Error::custom(DEFERRED_TYPES_ERROR));
+		}
+		if self.registry.is_exhausted() {
+			return Err(S::Error::custom(EXHAUSTED_ERROR));
+		}
+		let aliases = self
+			.registry
+			.alias_symbols()
+			.map(|(name, ty)| nested::Alias::from((name, ty)))
+			.collect::<Vec<_>>();
+		let started = self.registry.hooks.start();
+		let mut state = serializer.serialize_struct("Registry", 7)?;
+		state.serialize_field("version", &self.layout.version())?;
+		state.serialize_field("capabilities", &Capabilities::current())?;
+		if self.ids_only {
+			state.serialize_field("ids_only", &true)?;
+		} else {
+			state.skip_field("ids_only")?;
+		}
+		state.serialize_field("strings", &self.strings())?;
+		match (self.layout, self.ids_only) {
+			(Layout::Flat, true) => {
+				let types = self.registry.types().map(|(_, id_def)| id_def.id()).collect::<Vec<_>>();
+				state.serialize_field("types", &types)?;
+			}
+			(Layout::Nested, true) => {
+				let types = self
+					.registry
+					.types()
+					.map(|(_, id_def)| nested::TypeId::from(id_def.id()))
+					.collect::<Vec<_>>();
+				state.serialize_field("types", &types)?;
+			}
+			(Layout::Tagged, true) => {
+				let types = self
+					.registry
+					.types()
+					.map(|(_, id_def)| tagged::TypeId::from(nested::TypeId::from(id_def.id())))
+					.collect::<Vec<_>>();
+				state.serialize_field("types", &types)?;
+			}
+			(Layout::Flat, false) => {
+				let types = self.registry.types().map(|(_, id_def)| id_def).collect::<Vec<_>>();
+				state.serialize_field("types", &types)?;
+			}
+			(Layout::Nested, false) => {
+				let types = self
+					.registry
+					.types()
+					.map(|(_, id_def)| nested::TypeIdDef::from(id_def))
+					.collect::<Vec<_>>();
+				state.serialize_field("types", &types)?;
+			}
+			(Layout::Tagged, false) => {
+				let types = self
+					.registry
+					.types()
+					.map(|(_, id_def)| tagged::TypeIdDef::from(nested::TypeIdDef::from(id_def)))
+					.collect::<Vec<_>>();
+				state.serialize_field("types", &types)?;
+			}
+		}
+		if aliases.is_empty() {
+			state.skip_field("aliases")?;
+		} else {
+			state.serialize_field("aliases", &aliases)?;
+		}
+		if self.stats {
+			state.serialize_field("stats", &self.registry.namespace_stats())?;
+		} else {
+			state.skip_field("stats")?;
+		}
+		let ok = state.end()?;
+		self.registry.hooks.serialized(self.layout, self.registry.types().count(), started);
+		Ok(ok)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn versions_identify_layouts() {
+		for layout in &[Layout::Flat, Layout::Nested, Layout::Tagged] {
+			assert_eq!(Layout::from_version(layout.version()), Some(*layout));
+		}
+		assert_eq!(Layout::from_version(0), None);
+		assert_eq!(Layout::default(), Layout::Flat);
+	}
+
+	#[test]
+	fn ids_only_omits_strings_of_definitions() {
+		use crate::Metadata;
+
+		let mut registry = Registry::new();
+		registry.alias_type("Maybe", <Option<bool>>::meta_type());
+		let layout = registry.with_layout(Layout::Nested);
+		assert_eq!(
+			layout.strings(),
+			vec![Some("Maybe"), Some("Option"), Some("None"), Some("Some")]
+		);
+		assert_eq!(
+			layout.ids_only().strings(),
+			vec![Some("Maybe"), Some("Option"), None, None]
+		);
+	}
+}
@@ -0,0 +1,370 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A client-side cache of a registry in the nested layout.
+//!
+//! Producers that evolve their registry with `Registry::extend_preserving` only ever append
+//! types. Instead of shipping the whole registry upon every change they may ship a `Delta`
+//! with the appended strings, types and aliases that refers to the previous registry by its
+//! `ContentHash`. Light clients keep the latest registry in a `RegistryCache` which applies
+//! deltas as well as full replacements and notifies its listeners about every change.
+
+use super::nested::{Alias, Registry, TypeIdDef};
+use crate::tm_std::*;
+use crate::Capabilities;
+use serde::{Deserialize, Serialize};
+
+/// The hash of the contents of a registry in the nested layout.
+///
+/// The hash is the same on all targets so that producers and clients can compare it.
+/// It is not cryptographically secure and only detects accidental mismatches.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContentHash(pub u64);
+
+impl Registry {
+	/// Returns the hash of the contents of the registry.
+	pub fn content_hash(&self) -> ContentHash {
+		let mut hasher = ContentHasher::default();
+		self.hash(&mut hasher);
+		ContentHash(hasher.finish())
+	}
+}
+
+/// The 64-bit FNV-1a hasher with all integers written as little-endian `u64`.
+///
+/// Writing integers independently of the width of `usize` and the endianness of
+/// the target keeps content hashes identical across targets.
+struct ContentHasher(u64);
+
+impl Default for ContentHasher {
+	fn default() -> Self {
+		Self(0xcbf2_9ce4_8422_2325)
+	}
+}
+
+impl Hasher for ContentHasher {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3);
+		}
+	}
+
+	fn write_u8(&mut self, i: u8) {
+		self.write_u64(i.into())
+	}
+
+	fn write_u16(&mut self, i: u16) {
+		self.write_u64(i.into())
+	}
+
+	fn write_u32(&mut self, i: u32) {
+		self.write_u64(i.into())
+	}
+
+	fn write_u64(&mut self, i: u64) {
+		self.write(&i.to_le_bytes())
+	}
+
+	fn write_usize(&mut self, i: usize) {
+		self.write_u64(i as u64)
+	}
+}
+
+/// The strings, types and aliases appended to a registry.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Delta {
+	/// The content hash of the registry the delta applies to.
+	pub base: ContentHash,
+	/// The capabilities of the producer of the extended registry.
+	#[serde(default)]
+	pub capabilities: Capabilities,
+	/// The appended strings in order of their symbols.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub strings: Vec<String>,
+	/// The appended types in order of their symbols.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub types: Vec<TypeIdDef>,
+	/// The appended aliases.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<Alias>,
+}
+
+impl Delta {
+	/// Returns the delta that extends the `base` registry to the given one.
+	///
+	/// Returns `None` if the registry does not merely append to the base registry,
+	/// e.g. because its strings have been reordered. Producers then have to ship
+	/// the whole registry instead.
+	pub fn between(base: &Registry, registry: &Registry) -> Option<Self> {
+		fn appended<T: PartialEq + Clone>(base: &[T], extended: &[T]) -> Option<Vec<T>> {
+			extended.strip_prefix(base).map(<[T]>::to_vec)
+		}
+		if base.version != registry.version {
+			return None;
+		}
+		Some(Self {
+			base: base.content_hash(),
+			capabilities: registry.capabilities,
+			strings: appended(&base.strings, &registry.strings)?,
+			types: appended(&base.types, &registry.types)?,
+			aliases: appended(&base.aliases, &registry.aliases)?,
+		})
+	}
+
+	/// Applies the delta to the given registry.
+	fn apply(self, registry: &mut Registry) {
+		registry.capabilities = self.capabilities;
+		registry.strings.extend(self.strings);
+		registry.types.extend(self.types);
+		registry.aliases.extend(self.aliases);
+	}
+}
+
+/// A change of the registry of a `RegistryCache`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Change {
+	/// The registry has been replaced as a whole.
+	Replaced {
+		/// The content hash of the previous registry, if any.
+		previous: Option<ContentHash>,
+		/// The content hash of the current registry.
+		current: ContentHash,
+	},
+	/// Types have been appended to the registry by a delta.
+	Extended {
+		/// The content hash of the previous registry.
+		previous: ContentHash,
+		/// The content hash of the current registry.
+		current: ContentHash,
+		/// The number of appended types.
+		///
+		/// Their type symbols directly follow those of the types of the previous registry.
+		types: usize,
+	},
+}
+
+/// An error upon applying a delta to a `RegistryCache`.
+#[derive(PartialEq, Eq, Debug)]
+pub enum CacheError {
+	/// The delta does not apply to the cached registry.
+	///
+	/// Clients have to request the whole registry from the producer.
+	BaseMismatch {
+		/// The content hash of the registry the delta applies to.
+		expected: ContentHash,
+		/// The content hash of the cached registry, if any.
+		actual: Option<ContentHash>,
+	},
+}
+
+impl Display for CacheError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			CacheError::BaseMismatch {
+				expected,
+				actual: Some(actual),
+			} => write!(
+				f,
+				"delta applies to registry {:016x} but the cached registry is {:016x}",
+				expected.0, actual.0
+			),
+			CacheError::BaseMismatch { expected, actual: None } => {
+				write!(
+					f,
+					"delta applies to registry {:016x} but no registry is cached",
+					expected.0
+				)
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CacheError {}
+
+/// A listener that is notified about every change of a `RegistryCache`.
+type Listener = Box<dyn FnMut(&Change, &Registry)>;
+
+/// Keeps the latest registry of a producer keyed by its content hash.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{layout::{cache::{Delta, RegistryCache}, nested}, Metadata, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// builder.register_type(&u8::meta_type());
+/// let base = nested::Registry::from(&builder.clone().build());
+/// builder.register_type(&<Option<u8>>::meta_type());
+/// let extended = nested::Registry::from(&builder.build());
+///
+/// let mut cache = RegistryCache::new();
+/// cache.subscribe(|change, _| println!("{:?}", change));
+/// cache.replace(base.clone());
+/// cache.apply(Delta::between(&base, &extended).unwrap()).unwrap();
+/// assert_eq!(cache.registry(), Some(&extended));
+/// ```
+#[derive(Default)]
+pub struct RegistryCache {
+	/// The cached registry together with its content hash.
+	cached: Option<(ContentHash, Registry)>,
+	/// The listeners to notify about changes.
+	listeners: Vec<Listener>,
+}
+
+impl Debug for RegistryCache {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.debug_struct("RegistryCache")
+			.field("cached", &self.cached)
+			.field("listeners", &self.listeners.len())
+			.finish()
+	}
+}
+
+impl RegistryCache {
+	/// Creates a new empty cache.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the cached registry, if any.
+	pub fn registry(&self) -> Option<&Registry> {
+		self.cached.as_ref().map(|(_, registry)| registry)
+	}
+
+	/// Returns the content hash of the cached registry, if any.
+	pub fn content_hash(&self) -> Option<ContentHash> {
+		self.cached.as_ref().map(|(hash, _)| *hash)
+	}
+
+	/// Registers a listener that is notified about every subsequent change of the cached registry.
+	pub fn subscribe<F>(&mut self, listener: F)
+	where
+		F: FnMut(&Change, &Registry) + 'static,
+	{
+		self.listeners.push(Box::new(listener));
+	}
+
+	/// Replaces the cached registry and returns its content hash.
+	///
+	/// Listeners are not notified if the contents of the registry are unchanged.
+	pub fn replace(&mut self, registry: Registry) -> ContentHash {
+		let current = registry.content_hash();
+		let previous = self.content_hash();
+		if previous != Some(current) {
+			self.cached = Some((current, registry));
+			self.notify(Change::Replaced { previous, current });
+		}
+		current
+	}
+
+	/// Applies the given delta to the cached registry and returns the new content hash.
+	///
+	/// # Errors
+	///
+	/// If the delta does not apply to the cached registry. The cache is left unchanged then.
+	pub fn apply(&mut self, delta: Delta) -> Result<ContentHash, CacheError> {
+		let (hash, registry) = match &mut self.cached {
+			Some((hash, registry)) if *hash == delta.base => (hash, registry),
+			cached => {
+				return Err(CacheError::BaseMismatch {
+					expected: delta.base,
+					actual: cached.as_ref().map(|(hash, _)| *hash),
+				})
+			}
+		};
+		let (previous, types) = (*hash, delta.types.len());
+		delta.apply(registry);
+		*hash = registry.content_hash();
+		let current = *hash;
+		if previous != current {
+			self.notify(Change::Extended {
+				previous,
+				current,
+				types,
+			});
+		}
+		Ok(current)
+	}
+
+	/// Notifies all listeners about the given change of the cached registry.
+	fn notify(&mut self, change: Change) {
+		if let Some((_, registry)) = &self.cached {
+			for listener in &mut self.listeners {
+				listener(&change, registry);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+
+	fn nested(types: &[crate::MetaType]) -> Registry {
+		let mut registry = crate::Registry::new();
+		for ty in types {
+			registry.compact_type(ty);
+		}
+		Registry::from(&registry)
+	}
+
+	#[test]
+	fn applies_deltas() {
+		use std::{cell::RefCell, rc::Rc};
+
+		let base = nested(&[u8::meta_type()]);
+		let extended = nested(&[u8::meta_type(), <[u8; 4]>::meta_type()]);
+		let changes = Rc::new(RefCell::new(Vec::new()));
+		let mut cache = RegistryCache::new();
+		let recorded = changes.clone();
+		cache.subscribe(move |change, _| recorded.borrow_mut().push(change.clone()));
+
+		let delta = Delta::between(&base, &extended).unwrap();
+		assert_eq!(
+			cache.apply(delta.clone()),
+			Err(CacheError::BaseMismatch {
+				expected: base.content_hash(),
+				actual: None,
+			})
+		);
+		assert_eq!(cache.replace(base.clone()), base.content_hash());
+		// Replacing a registry by an identical one is not a change.
+		cache.replace(base.clone());
+		assert_eq!(cache.apply(delta), Ok(extended.content_hash()));
+		assert_eq!(cache.registry(), Some(&extended));
+		assert_eq!(
+			*changes.borrow(),
+			vec![
+				Change::Replaced {
+					previous: None,
+					current: base.content_hash(),
+				},
+				Change::Extended {
+					previous: base.content_hash(),
+					current: extended.content_hash(),
+					types: 1,
+				},
+			]
+		);
+		assert_eq!(Delta::between(&extended, &base), None);
+	}
+}
@@ -0,0 +1,772 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The structures of the nested registry layout.
+//!
+//! In contrast to the compact form these structures own their data and refer to
+//! strings and types by the raw identifiers of their symbols. This allows to
+//! deserialize registries that have been serialized in the nested layout.
+
+use super::Layout;
+use crate::tm_std::*;
+use crate::{
+	form::CompactForm, interner::UntrackedSymbol, Builtin, Capabilities, ConstParam, Encoding, RawFormat,
+	Sensitivity, TypeIdPrimitive,
+};
+#[cfg(feature = "scale")]
+use parity_scale_codec::{Decode, Encode};
+use serde::{
+	de::{Error as _, IgnoredAny, Unexpected},
+	Deserialize, Deserializer, Serialize,
+};
+
+/// Returns the raw identifier of the given symbol.
+fn id<T>(symbol: &UntrackedSymbol<T>) -> u32 {
+	symbol.id().get()
+}
+
+/// Returns the raw identifiers of the given symbols.
+fn ids<T>(symbols: &[UntrackedSymbol<T>]) -> Vec<u32> {
+	symbols.iter().map(id).collect()
+}
+
+/// Deserializes the format version and rejects all but the nested layout.
+fn deserialize_version<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let version = u32::deserialize(deserializer)?;
+	match Layout::from_version(version) {
+		Some(Layout::Nested) => Ok(version),
+		_ => Err(D::Error::invalid_value(
+			Unexpected::Unsigned(version.into()),
+			&"the format version of the nested layout",
+		)),
+	}
+}
+
+/// The format version of the nested layout, rejecting the versions of all other layouts.
+#[derive(Deserialize)]
+pub(crate) struct Version(#[serde(deserialize_with = "deserialize_version")] pub(crate) u32);
+
+/// A registry in the nested layout.
+///
+/// Fields are accepted in any order and unknown fields, e.g. ones added by newer producers
+/// or the `"stats"` appendix, are ignored. Use `Registry::deserialize_strict` in order to
+/// reject unknown fields instead.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode))]
+pub struct Registry {
+	/// The format version of the nested layout.
+	#[serde(deserialize_with = "deserialize_version")]
+	pub version: u32,
+	/// The capabilities of the producer of the registry.
+	///
+	/// Empty for producers that predate capabilities.
+	#[serde(default)]
+	pub capabilities: Capabilities,
+	/// The registered strings in order of their symbols.
+	pub strings: Vec<String>,
+	/// The registered types in order of their symbols.
+	pub types: Vec<TypeIdDef>,
+	/// The domain-level names of registered types.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<Alias>,
+}
+
+impl Registry {
+	/// Deserializes a registry in the nested layout rejecting unknown fields of the registry.
+	///
+	/// This is meant for consumers that have to understand everything they read, e.g. validators
+	/// of registries that are pinned to a version of this crate. Only the `"stats"` appendix of
+	/// `LayoutRegistry::with_stats` is accepted besides the fields of the registry. The fields
+	/// of types and aliases are not checked.
+	///
+	/// May be used with `#[serde(deserialize_with = "...")]` for registries embedded into other data.
+	pub fn deserialize_strict<'de, D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let strict = StrictRegistry::deserialize(deserializer)?;
+		Ok(Self {
+			version: strict.version,
+			capabilities: strict.capabilities,
+			strings: strict.strings,
+			types: strict.types,
+			aliases: strict.aliases,
+		})
+	}
+}
+
+/// A registry in the nested layout that rejects unknown fields, see `Registry::deserialize_strict`.
+#[derive(Deserialize)]
+#[serde(rename = "Registry", deny_unknown_fields)]
+struct StrictRegistry {
+	#[serde(deserialize_with = "deserialize_version")]
+	version: u32,
+	#[serde(default)]
+	capabilities: Capabilities,
+	strings: Vec<String>,
+	types: Vec<TypeIdDef>,
+	#[serde(default)]
+	aliases: Vec<Alias>,
+	/// The size contributions of the namespaces which are ignored.
+	#[serde(default, rename = "stats")]
+	_stats: Option<IgnoredAny>,
+}
+
+impl From<&crate::Registry> for Registry {
+	fn from(registry: &crate::Registry) -> Self {
+		let registry = registry.localized();
+		Self {
+			version: Layout::Nested.version(),
+			capabilities: Capabilities::current(),
+			strings: registry.strings().iter().map(|string| string.to_string()).collect(),
+			types: registry.types().map(|(_, id_def)| id_def.into()).collect(),
+			aliases: registry.alias_symbols().map(Alias::from).collect(),
+		}
+	}
+}
+
+/// The interface view of a registry in the nested layout.
+///
+/// In contrast to a `Registry` it only contains the type identifiers.
+/// Serialized by `LayoutRegistry::ids_only`.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+pub struct Interface {
+	/// The format version of the nested layout.
+	#[serde(deserialize_with = "deserialize_version")]
+	pub version: u32,
+	/// The capabilities of the producer of the registry.
+	#[serde(default)]
+	pub capabilities: Capabilities,
+	/// Always `true` for interface views.
+	pub ids_only: bool,
+	/// The registered strings in order of their symbols.
+	///
+	/// Strings that are not referenced by any type identifier or alias are `None`.
+	pub strings: Vec<Option<String>>,
+	/// The identifiers of the registered types in order of their symbols.
+	pub types: Vec<TypeId>,
+	/// The domain-level names of registered types.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<Alias>,
+}
+
+/// A domain-level name of a registered type.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+pub struct Alias {
+	/// The name of the alias.
+	pub name: u32,
+	/// The aliased type.
+	#[serde(rename = "type")]
+	pub ty: u32,
+}
+
+impl From<(UntrackedSymbol<&'static str>, UntrackedSymbol<AnyTypeId>)> for Alias {
+	fn from((name, ty): (UntrackedSymbol<&'static str>, UntrackedSymbol<AnyTypeId>)) -> Self {
+		Self {
+			name: id(&name),
+			ty: id(&ty),
+		}
+	}
+}
+
+/// The pair of associated type identifier and definition.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+pub struct TypeIdDef {
+	/// The identifier of the type.
+	pub id: TypeId,
+	/// The definition of the type.
+	pub def: TypeDef,
+}
+
+impl From<&crate::registry::TypeIdDef> for TypeIdDef {
+	fn from(id_def: &crate::registry::TypeIdDef) -> Self {
+		Self {
+			id: id_def.id().into(),
+			def: id_def.def().into(),
+		}
+	}
+}
+
+/// A type identifier.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[serde(rename_all = "snake_case")]
+pub enum TypeId {
+	/// A custom type defined by the user.
+	Custom {
+		/// The name of the custom type.
+		name: u32,
+		/// The namespace of the custom type.
+		namespace: Vec<u32>,
+		/// The generic type parameters of the custom type.
+		params: Vec<u32>,
+		/// The values of the const generic parameters of the custom type.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		consts: Vec<ConstParam>,
+		/// The stable identifier of the custom type if one has been assigned.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		id: Option<u32>,
+		/// The documentation of the custom type, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+	/// A slice type with runtime known length.
+	Slice {
+		/// The element type of the slice.
+		#[serde(rename = "type")]
+		type_param: u32,
+		/// The encoding of the slice if it is known to represent raw bytes.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		encoding: Option<Encoding>,
+	},
+	/// An array type with compile-time known length.
+	Array {
+		/// The length of the array.
+		len: u16,
+		/// The element type of the array.
+		#[serde(rename = "type")]
+		type_param: u32,
+	},
+	/// A tuple type.
+	Tuple(Vec<u32>),
+	/// A Rust primitive type.
+	Primitive(TypeIdPrimitive),
+}
+
+impl From<&crate::TypeId<CompactForm>> for TypeId {
+	fn from(id: &crate::TypeId<CompactForm>) -> Self {
+		match id {
+			crate::TypeId::Custom(custom) => TypeId::Custom {
+				name: self::id(custom.name()),
+				namespace: ids(custom.namespace().segments()),
+				params: ids(custom.type_params()),
+				consts: custom.const_params().to_vec(),
+				id: custom.stable_id().map(self::id),
+				docs: ids(custom.docs()),
+			},
+			crate::TypeId::Slice(slice) => TypeId::Slice {
+				type_param: self::id(slice.type_param()),
+				encoding: slice.encoding(),
+			},
+			crate::TypeId::Array(array) => TypeId::Array {
+				len: array.len,
+				type_param: self::id(&array.type_param),
+			},
+			crate::TypeId::Tuple(tuple) => TypeId::Tuple(ids(&tuple.type_params)),
+			crate::TypeId::Primitive(primitive) => TypeId::Primitive(primitive.clone()),
+		}
+	}
+}
+
+/// A type definition.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[serde(rename_all = "snake_case")]
+pub enum TypeDef {
+	/// A builtin type that has an implied and known internal structure.
+	Builtin,
+	/// An arbitrary JSON value whose structure is not known statically.
+	Json,
+	/// A retired type whose definition has been removed.
+	Tombstone,
+	/// An opaque payload in the given format.
+	Raw {
+		/// The declared format of the payload.
+		format: RawFormat,
+	},
+	/// A struct with named fields.
+	///
+	/// Structs without fields are laid out as tuple-structs without types.
+	Struct {
+		/// The named fields of the struct.
+		fields: Vec<NamedField>,
+	},
+	/// A tuple-struct with unnamed fields.
+	///
+	/// Also the layout of unit structs and of structs without fields, since all
+	/// of them have the same unit composite as their definition.
+	TupleStruct {
+		/// The types of the unnamed fields of the tuple-struct.
+		types: Vec<u32>,
+	},
+	/// A C-like enum with simple named variants.
+	ClikeEnum {
+		/// The variants of the C-like enum.
+		variants: Vec<ClikeEnumVariant>,
+	},
+	/// A Rust enum with different kinds of variants.
+	Enum {
+		/// The variants of the enum.
+		variants: Vec<EnumVariant>,
+		/// The representation of the enum upon serialization.
+		#[serde(default, skip_serializing_if = "EnumRepr::is_external")]
+		repr: EnumRepr,
+		/// Whether the fieldless variant is encoded as the zero value of the field of the other variant.
+		#[serde(default, skip_serializing_if = "core::ops::Not::not")]
+		niche: bool,
+	},
+	/// An unsafe Rust union type.
+	Union {
+		/// The fields of the union.
+		fields: Vec<NamedField>,
+		/// The description of the field that selects the active member, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminator: Option<UnionDiscriminator>,
+	},
+}
+
+impl From<&crate::TypeDef<CompactForm>> for TypeDef {
+	fn from(def: &crate::TypeDef<CompactForm>) -> Self {
+		match def {
+			crate::TypeDef::Builtin(Builtin::Builtin) => TypeDef::Builtin,
+			crate::TypeDef::Builtin(Builtin::Json) => TypeDef::Json,
+			crate::TypeDef::Builtin(Builtin::Tombstone) => TypeDef::Tombstone,
+			crate::TypeDef::Builtin(Builtin::Raw(format)) => TypeDef::Raw { format: *format },
+			crate::TypeDef::Struct(def) if def.fields().is_empty() => TypeDef::TupleStruct { types: vec![] },
+			crate::TypeDef::Struct(def) => TypeDef::Struct {
+				fields: named_fields(def.fields()),
+			},
+			crate::TypeDef::TupleStruct(def) => TypeDef::TupleStruct {
+				types: unnamed_fields(def.fields()),
+			},
+			crate::TypeDef::Composite(def) if def.is_named() => TypeDef::Struct {
+				fields: fields(def.fields()),
+			},
+			crate::TypeDef::Composite(def) => TypeDef::TupleStruct {
+				types: def.fields().iter().map(|field| id(field.ty())).collect(),
+			},
+			crate::TypeDef::ClikeEnum(def) => TypeDef::ClikeEnum {
+				variants: def
+					.variants()
+					.iter()
+					.map(|variant| ClikeEnumVariant {
+						name: id(variant.name()),
+						discriminant: variant.discriminant(),
+						code: variant.code(),
+					})
+					.collect(),
+			},
+			crate::TypeDef::Enum(def) => TypeDef::Enum {
+				variants: def.variants().iter().map(EnumVariant::from).collect(),
+				repr: def.repr().into(),
+				niche: false,
+			},
+			crate::TypeDef::Variant(def) if def.is_clike() => TypeDef::ClikeEnum {
+				variants: def
+					.variants()
+					.iter()
+					.map(|variant| ClikeEnumVariant {
+						name: id(variant.name()),
+						discriminant: variant.discriminant().unwrap_or_default(),
+						code: variant.code(),
+					})
+					.collect(),
+			},
+			crate::TypeDef::Variant(def) => TypeDef::Enum {
+				variants: def.variants().iter().map(EnumVariant::from).collect(),
+				repr: def.repr().into(),
+				niche: def.is_niche(),
+			},
+			crate::TypeDef::Union(def) => TypeDef::Union {
+				fields: named_fields(def.fields()),
+				discriminator: def.discriminator().map(|discriminator| UnionDiscriminator {
+					field: ids(discriminator.field_path()),
+					mapping: discriminator
+						.mapping()
+						.iter()
+						.map(|mapping| UnionDiscriminatorMapping {
+							value: mapping.value().into(),
+							member: id(mapping.member()),
+						})
+						.collect(),
+				}),
+			},
+		}
+	}
+}
+
+/// Returns the nested layout of the given named fields.
+fn named_fields(fields: &[crate::NamedField<CompactForm>]) -> Vec<NamedField> {
+	fields
+		.iter()
+		.map(|field| NamedField {
+			name: id(field.name()),
+			ty: id(field.ty()),
+			group: field.group().map(id),
+			sensitivity: field.sensitivity(),
+			display: field.display().map(id),
+			docs: ids(field.docs()),
+		})
+		.collect()
+}
+
+/// Returns the nested layout of the given fields that are all named.
+fn fields(fields: &[crate::Field<CompactForm>]) -> Vec<NamedField> {
+	fields
+		.iter()
+		.map(|field| NamedField {
+			name: field.name().map(id).unwrap_or_default(),
+			ty: id(field.ty()),
+			group: field.group().map(id),
+			sensitivity: field.sensitivity(),
+			display: field.display().map(id),
+			docs: ids(field.docs()),
+		})
+		.collect()
+}
+
+/// Returns the types of the given unnamed fields.
+fn unnamed_fields(fields: &[crate::UnnamedField<CompactForm>]) -> Vec<u32> {
+	fields.iter().map(|field| id(field.ty())).collect()
+}
+
+/// A named field.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+pub struct NamedField {
+	/// The name of the field.
+	pub name: u32,
+	/// The type of the field.
+	#[serde(rename = "type")]
+	pub ty: u32,
+	/// The label of the logical section the field belongs to, if any.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub group: Option<u32>,
+	/// How sensitive the values of the field are.
+	#[serde(default, skip_serializing_if = "Sensitivity::is_public")]
+	pub sensitivity: Sensitivity,
+	/// The format hint for displaying the values of the field, if any.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub display: Option<u32>,
+	/// The documentation of the field, one entry per line.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub docs: Vec<u32>,
+}
+
+/// A C-like enum variant.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+pub struct ClikeEnumVariant {
+	/// The name of the variant.
+	pub name: u32,
+	/// The discriminant of the variant.
+	pub discriminant: u64,
+	/// The application-level error code of the variant, if any.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub code: Option<u32>,
+}
+
+/// A Rust enum variant.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[serde(rename_all = "snake_case")]
+pub enum EnumVariant {
+	/// A unit struct variant.
+	Unit {
+		/// The name of the variant.
+		name: u32,
+		/// The application-level error code of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminant: Option<u64>,
+		/// The documentation of the variant, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+	/// A struct variant with named fields.
+	Struct {
+		/// The name of the variant.
+		name: u32,
+		/// The named fields of the variant.
+		fields: Vec<NamedField>,
+		/// The application-level error code of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminant: Option<u64>,
+		/// The documentation of the variant, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+	/// A tuple-struct variant with unnamed fields.
+	TupleStruct {
+		/// The name of the variant.
+		name: u32,
+		/// The types of the unnamed fields of the variant.
+		types: Vec<u32>,
+		/// The application-level error code of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminant: Option<u64>,
+		/// The documentation of the variant, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+}
+
+impl From<&crate::EnumVariant<CompactForm>> for EnumVariant {
+	fn from(variant: &crate::EnumVariant<CompactForm>) -> Self {
+		match variant {
+			crate::EnumVariant::Unit(variant) => EnumVariant::Unit {
+				name: id(variant.name()),
+				code: variant.code(),
+				discriminant: None,
+				docs: ids(variant.docs()),
+			},
+			crate::EnumVariant::Struct(variant) => EnumVariant::Struct {
+				name: id(variant.name()),
+				fields: named_fields(variant.fields()),
+				code: variant.code(),
+				discriminant: None,
+				docs: ids(variant.docs()),
+			},
+			crate::EnumVariant::TupleStruct(variant) => EnumVariant::TupleStruct {
+				name: id(variant.name()),
+				types: unnamed_fields(variant.fields()),
+				code: variant.code(),
+				discriminant: None,
+				docs: ids(variant.docs()),
+			},
+		}
+	}
+}
+
+impl From<&crate::Variant<CompactForm>> for EnumVariant {
+	fn from(variant: &crate::Variant<CompactForm>) -> Self {
+		let name = id(variant.name());
+		let (code, discriminant, docs) = (variant.code(), variant.discriminant(), ids(variant.docs()));
+		if variant.fields().is_empty() {
+			EnumVariant::Unit {
+				name,
+				code,
+				discriminant,
+				docs,
+			}
+		} else if variant.fields().iter().all(|field| field.name().is_some()) {
+			EnumVariant::Struct {
+				name,
+				fields: fields(variant.fields()),
+				code,
+				discriminant,
+				docs,
+			}
+		} else {
+			EnumVariant::TupleStruct {
+				name,
+				types: variant.fields().iter().map(|field| id(field.ty())).collect(),
+				code,
+				discriminant,
+				docs,
+			}
+		}
+	}
+}
+
+/// The representation of an enum upon serialization.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[serde(rename_all = "snake_case")]
+pub enum EnumRepr {
+	/// The variant is externally tagged.
+	#[default]
+	External,
+	/// The variant is internally tagged.
+	Internal {
+		/// The name of the tag field.
+		tag: u32,
+	},
+	/// The variant is adjacently tagged.
+	Adjacent {
+		/// The name of the tag field.
+		tag: u32,
+		/// The name of the content field.
+		content: u32,
+	},
+	/// The variant is not tagged at all.
+	Untagged,
+}
+
+impl EnumRepr {
+	/// Returns `true` if this is the default externally tagged representation.
+	pub fn is_external(&self) -> bool {
+		matches!(self, EnumRepr::External)
+	}
+}
+
+impl From<&crate::EnumRepr<CompactForm>> for EnumRepr {
+	fn from(repr: &crate::EnumRepr<CompactForm>) -> Self {
+		match repr {
+			crate::EnumRepr::External => EnumRepr::External,
+			crate::EnumRepr::Internal { tag } => EnumRepr::Internal { tag: id(tag) },
+			crate::EnumRepr::Adjacent { tag, content } => EnumRepr::Adjacent {
+				tag: id(tag),
+				content: id(content),
+			},
+			crate::EnumRepr::Untagged => EnumRepr::Untagged,
+		}
+	}
+}
+
+/// Describes the field that selects the active member of a union.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+pub struct UnionDiscriminator {
+	/// The path of field names leading to the discriminating field.
+	pub field: Vec<u32>,
+	/// The mapping from discriminant values to union members.
+	pub mapping: Vec<UnionDiscriminatorMapping>,
+}
+
+/// Maps a discriminant value to the union member it selects.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+pub struct UnionDiscriminatorMapping {
+	/// The value of the discriminating field.
+	pub value: DiscriminantValue,
+	/// The name of the selected union member.
+	pub member: u32,
+}
+
+/// A value of the discriminating field of a union.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[serde(rename_all = "lowercase")]
+pub enum DiscriminantValue {
+	/// An unsigned integer of up to 64 bits or a character.
+	UInt(u64),
+	/// A signed integer of up to 64 bits.
+	Int(i64),
+	/// A string.
+	Str(u32),
+}
+
+impl From<&crate::DiscriminantValue<CompactForm>> for DiscriminantValue {
+	fn from(value: &crate::DiscriminantValue<CompactForm>) -> Self {
+		match value {
+			crate::DiscriminantValue::UInt(value) => DiscriminantValue::UInt(*value),
+			crate::DiscriminantValue::Int(value) => DiscriminantValue::Int(*value),
+			crate::DiscriminantValue::Str(value) => DiscriminantValue::Str(id(value)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HasTypeDef, HasTypeId, Metadata, Namespace, TypeDefComposite, TypeIdCustom};
+
+	#[test]
+	fn from_registry() {
+		let mut registry = crate::Registry::new();
+		registry.compact_type(&<Option<bool>>::meta_type());
+		assert_eq!(
+			Registry::from(&registry),
+			Registry {
+				version: 2,
+				capabilities: Capabilities::current(),
+				strings: vec!["Option".to_string(), "None".to_string(), "Some".to_string()],
+				types: vec![
+					TypeIdDef {
+						id: TypeId::Custom {
+							name: 1,
+							namespace: vec![],
+							params: vec![2],
+							consts: vec![],
+							id: None,
+							docs: vec![],
+						},
+						def: TypeDef::Enum {
+							variants: vec![
+								EnumVariant::Unit {
+									name: 2,
+									code: None,
+									discriminant: None,
+									docs: vec![],
+								},
+								EnumVariant::TupleStruct {
+									name: 3,
+									types: vec![2],
+									code: None,
+									discriminant: None,
+									docs: vec![],
+								},
+							],
+							repr: EnumRepr::External,
+							niche: false,
+						},
+					},
+					TypeIdDef {
+						id: TypeId::Primitive(TypeIdPrimitive::Bool),
+						def: TypeDef::Builtin,
+					},
+				],
+				aliases: vec![],
+			}
+		);
+	}
+
+	#[test]
+	fn fieldless_structs_are_unit_composites() {
+		struct Empty;
+		struct Unit;
+
+		impl HasTypeId for Empty {
+			fn type_id() -> crate::TypeId {
+				TypeIdCustom::new("Empty", Namespace::prelude(), vec![]).into()
+			}
+		}
+
+		// The superseded definition of field-less structs is laid out as a unit composite too.
+		#[allow(deprecated)]
+		impl HasTypeDef for Empty {
+			fn type_def() -> crate::TypeDef {
+				crate::TypeDefStruct::new(vec![]).into()
+			}
+		}
+
+		impl HasTypeId for Unit {
+			fn type_id() -> crate::TypeId {
+				TypeIdCustom::new("Unit", Namespace::prelude(), vec![]).into()
+			}
+		}
+
+		impl HasTypeDef for Unit {
+			fn type_def() -> crate::TypeDef {
+				TypeDefComposite::unit().into()
+			}
+		}
+
+		let mut registry = crate::Registry::new();
+		registry.compact_type(&Empty::meta_type());
+		registry.compact_type(&Unit::meta_type());
+		let defs = Registry::from(&registry)
+			.types
+			.into_iter()
+			.map(|id_def| id_def.def)
+			.collect::<Vec<_>>();
+		assert_eq!(defs, vec![TypeDef::TupleStruct { types: vec![] }; 2]);
+	}
+}
@@ -0,0 +1,883 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lossless conversion between the nested layout and the portable registry of `scale-info`.
+//!
+//! This allows projects that migrate to `scale-info` to translate their metadata
+//! artifacts in both directions. Registries are converted through the owned
+//! structures of the nested layout since a `PortableRegistry` owns its strings.
+//!
+//! Types are converted into their `scale-info` equivalents where there is one. Some types
+//! cannot be expressed by `scale-info`, e.g. unions, JSON values, non-externally tagged
+//! enums or fields with groups, sensitivities or display hints. These become composites
+//! without fields that keep the path and type parameters of custom types. All types that
+//! do not convert back into their original definitions carry them in a reserved last line
+//! of their documentation starting with `type-metadata:` followed by the JSON of the type in
+//! the nested layout. Such registries end with a reserved `type_metadata::Registry` type that
+//! carries the strings, aliases and capabilities of the registry in the same way. Converting
+//! a registry to `scale-info` and back thus always returns an equal registry.
+//!
+//! Registries that have not been converted from this crate are converted as far as possible
+//! and types that cannot be expressed in the nested layout are rejected with a `PortableError`.
+//! Beyond that the following definitions are normalized:
+//!
+//! - Type symbols of `scale-info` are zero-based while the ones of this crate start at 1.
+//! - Type parameters of `scale-info` are named, e.g. `T0`, `T1`, and so forth.
+//! - Field-less variant types become C-like enums.
+//! - Strings are interned in order of their first occurrence within the portable registry.
+//! - Documentation of unnamed fields is dropped since the nested layout cannot express it.
+
+use super::{
+	nested::{ClikeEnumVariant, EnumRepr, EnumVariant, NamedField, Registry, TypeDef, TypeId, TypeIdDef},
+	Layout,
+};
+use crate::tm_std::*;
+use crate::{Capabilities, Encoding, Sensitivity, TypeIdPrimitive};
+use core::convert::TryFrom;
+use scale_info::{
+	form::PortableForm, interner::UntrackedSymbol, Field, Path, PortableRegistry, PortableType, Type,
+	TypeDef as PortableTypeDef, TypeDefArray, TypeDefComposite, TypeDefPrimitive, TypeDefSequence, TypeDefTuple,
+	TypeDefVariant, TypeParameter, Variant,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A type symbol of `scale-info`.
+type PortableSymbol = UntrackedSymbol<AnyTypeId>;
+
+/// The prefix of the reserved documentation line that carries a type in the nested layout.
+const EXTENSION_PREFIX: &str = "type-metadata:";
+
+/// The path of the reserved type that carries the strings, aliases and capabilities of a registry.
+const REGISTRY_PATH: [&str; 2] = ["type_metadata", "Registry"];
+
+/// An error that may be encountered upon converting from or to a `scale-info` registry.
+#[derive(PartialEq, Eq, Debug)]
+pub enum PortableError {
+	/// If a string symbol does not refer to a registered string.
+	MissingString {
+		/// The raw identifier of the string symbol.
+		id: u32,
+	},
+	/// If a type symbol does not refer to a registered type.
+	MissingType {
+		/// The raw identifier of the type symbol within the source registry.
+		id: u32,
+	},
+	/// If a type cannot be expressed by the target format.
+	UnsupportedType {
+		/// The raw identifier of the type symbol within the source registry.
+		id: u32,
+	},
+	/// If the reserved documentation line of a type cannot be written or read.
+	InvalidExtension {
+		/// The raw identifier of the type symbol within the source registry.
+		id: u32,
+	},
+	/// If the `scale-info` registry has more strings than string symbols can refer to.
+	TooManyStrings,
+}
+
+/// Converts the given registry in the nested layout into a `scale-info` registry.
+///
+/// # Errors
+///
+/// - If the registry refers to strings or types it does not contain.
+pub fn to_portable(registry: &Registry) -> Result<PortableRegistry, PortableError> {
+	let converter = ToPortable { registry };
+	let mut types = Vec::with_capacity(registry.types.len() + 1);
+	for (index, id_def) in registry.types.iter().enumerate() {
+		let ty = match converter.portable_type(index as u32 + 1, id_def) {
+			Err(PortableError::UnsupportedType { .. }) => converter.opaque_type(id_def)?,
+			ty => ty?,
+		};
+		types.push(PortableType { id: index as u32, ty });
+	}
+	// Types that would not convert back into their definitions carry them in their documentation.
+	let mut back = FromPortable::new(&types, Some(&registry.strings));
+	let extended = types
+		.iter()
+		.zip(&registry.types)
+		.enumerate()
+		.filter(|(_, (ty, id_def))| back.type_id_def(ty.id, &ty.ty).as_ref() != Ok(*id_def))
+		.map(|(index, _)| index)
+		.collect::<Vec<_>>();
+	for index in extended.iter().copied() {
+		types[index]
+			.ty
+			.docs
+			.push(extension(index as u32 + 1, &registry.types[index])?);
+	}
+	let mut portable = PortableRegistry { types };
+	if !extended.is_empty() || from_portable(&portable).as_ref() != Ok(registry) {
+		let id = portable.types.len() as u32;
+		let carried = Registry {
+			types: Vec::new(),
+			..registry.clone()
+		};
+		portable.types.push(PortableType {
+			id,
+			ty: Type {
+				path: Path {
+					segments: REGISTRY_PATH.iter().map(|segment| segment.to_string()).collect(),
+				},
+				docs: vec![extension(id + 1, &carried)?],
+				..anonymous(PortableTypeDef::Composite(TypeDefComposite { fields: Vec::new() }))
+			},
+		});
+	}
+	Ok(portable)
+}
+
+/// Converts the given `scale-info` registry into a registry in the nested layout.
+///
+/// # Errors
+///
+/// - If the type symbols of the portable registry are not contiguous or refer to missing types.
+/// - If the portable registry contains types that this crate cannot express.
+/// - If a reserved documentation line of a registry converted by `to_portable` is invalid.
+pub fn from_portable(registry: &PortableRegistry) -> Result<Registry, PortableError> {
+	let (types, carried) = match registry.types.split_last() {
+		Some((last, types)) if is_reserved_registry(&last.ty) => {
+			let carried: Registry = parse_extension(last.id, &last.ty)?;
+			(types, Some(carried))
+		}
+		_ => (&registry.types[..], None),
+	};
+	let mut converter = FromPortable::new(types, carried.as_ref().map(|carried| &carried.strings));
+	let mut converted = Vec::with_capacity(types.len());
+	for (index, ty) in types.iter().enumerate() {
+		if ty.id as usize != index {
+			return Err(PortableError::MissingType { id: index as u32 });
+		}
+		converted.push(converter.type_id_def(ty.id, &ty.ty)?);
+	}
+	let (capabilities, aliases) = match carried {
+		Some(carried) => (carried.capabilities, carried.aliases),
+		None => (Capabilities::current(), Vec::new()),
+	};
+	Ok(Registry {
+		version: Layout::Nested.version(),
+		capabilities,
+		strings: converter.strings,
+		types: converted,
+		aliases,
+	})
+}
+
+/// Returns the reserved documentation line that carries the given value in the nested layout.
+fn extension<T: Serialize>(id: u32, value: &T) -> Result<String, PortableError> {
+	let json = serde_json::to_string(value).map_err(|_| PortableError::InvalidExtension { id })?;
+	Ok(format!("{}{}", EXTENSION_PREFIX, json))
+}
+
+/// Returns the value carried by the reserved last documentation line of the given type, if any.
+fn find_extension<T: DeserializeOwned>(id: u32, ty: &Type<PortableForm>) -> Option<Result<T, PortableError>> {
+	let json = ty.docs.last()?.strip_prefix(EXTENSION_PREFIX)?;
+	Some(serde_json::from_str(json).map_err(|_| PortableError::InvalidExtension { id }))
+}
+
+/// Returns the value carried by the reserved last documentation line of the given type.
+fn parse_extension<T: DeserializeOwned>(id: u32, ty: &Type<PortableForm>) -> Result<T, PortableError> {
+	find_extension(id, ty).unwrap_or(Err(PortableError::InvalidExtension { id }))
+}
+
+/// Returns whether the given type is the reserved type that carries the strings, aliases
+/// and capabilities of a registry converted by `to_portable`.
+fn is_reserved_registry(ty: &Type<PortableForm>) -> bool {
+	ty.path
+		.segments
+		.iter()
+		.map(String::as_str)
+		.eq(REGISTRY_PATH.iter().copied())
+		&& ty.docs.last().is_some_and(|doc| doc.starts_with(EXTENSION_PREFIX))
+}
+
+/// Returns the `scale-info` primitive of the given primitive type.
+fn portable_primitive(primitive: &TypeIdPrimitive) -> TypeDefPrimitive {
+	match primitive {
+		TypeIdPrimitive::Bool => TypeDefPrimitive::Bool,
+		TypeIdPrimitive::Char => TypeDefPrimitive::Char,
+		TypeIdPrimitive::Str => TypeDefPrimitive::Str,
+		TypeIdPrimitive::U8 => TypeDefPrimitive::U8,
+		TypeIdPrimitive::U16 => TypeDefPrimitive::U16,
+		TypeIdPrimitive::U32 => TypeDefPrimitive::U32,
+		TypeIdPrimitive::U64 => TypeDefPrimitive::U64,
+		TypeIdPrimitive::U128 => TypeDefPrimitive::U128,
+		TypeIdPrimitive::I8 => TypeDefPrimitive::I8,
+		TypeIdPrimitive::I16 => TypeDefPrimitive::I16,
+		TypeIdPrimitive::I32 => TypeDefPrimitive::I32,
+		TypeIdPrimitive::I64 => TypeDefPrimitive::I64,
+		TypeIdPrimitive::I128 => TypeDefPrimitive::I128,
+	}
+}
+
+/// Returns the primitive type of the given `scale-info` primitive if there is one.
+fn primitive(primitive: &TypeDefPrimitive) -> Option<TypeIdPrimitive> {
+	match primitive {
+		TypeDefPrimitive::Bool => Some(TypeIdPrimitive::Bool),
+		TypeDefPrimitive::Char => Some(TypeIdPrimitive::Char),
+		TypeDefPrimitive::Str => Some(TypeIdPrimitive::Str),
+		TypeDefPrimitive::U8 => Some(TypeIdPrimitive::U8),
+		TypeDefPrimitive::U16 => Some(TypeIdPrimitive::U16),
+		TypeDefPrimitive::U32 => Some(TypeIdPrimitive::U32),
+		TypeDefPrimitive::U64 => Some(TypeIdPrimitive::U64),
+		TypeDefPrimitive::U128 => Some(TypeIdPrimitive::U128),
+		TypeDefPrimitive::I8 => Some(TypeIdPrimitive::I8),
+		TypeDefPrimitive::I16 => Some(TypeIdPrimitive::I16),
+		TypeDefPrimitive::I32 => Some(TypeIdPrimitive::I32),
+		TypeDefPrimitive::I64 => Some(TypeIdPrimitive::I64),
+		TypeDefPrimitive::I128 => Some(TypeIdPrimitive::I128),
+		_ => None,
+	}
+}
+
+/// Returns a `scale-info` type without path and type parameters.
+fn anonymous(type_def: PortableTypeDef<PortableForm>) -> Type<PortableForm> {
+	Type {
+		path: Path { segments: Vec::new() },
+		type_params: Vec::new(),
+		type_def,
+		docs: Vec::new(),
+	}
+}
+
+/// Returns a `scale-info` field with the given name and type.
+fn field(name: Option<String>, ty: PortableSymbol) -> Field<PortableForm> {
+	Field {
+		name,
+		ty,
+		type_name: None,
+		docs: Vec::new(),
+	}
+}
+
+/// Converts registries in the nested layout into `scale-info` registries.
+struct ToPortable<'a> {
+	/// The registry in the nested layout.
+	registry: &'a Registry,
+}
+
+impl ToPortable<'_> {
+	/// Returns the string associated with the given string symbol.
+	fn string(&self, id: u32) -> Result<String, PortableError> {
+		(id as usize)
+			.checked_sub(1)
+			.and_then(|index| self.registry.strings.get(index))
+			.cloned()
+			.ok_or(PortableError::MissingString { id })
+	}
+
+	/// Returns the `scale-info` type symbol of the given type symbol.
+	fn symbol(&self, id: u32) -> Result<PortableSymbol, PortableError> {
+		if id == 0 || id as usize > self.registry.types.len() {
+			return Err(PortableError::MissingType { id });
+		}
+		Ok(PortableSymbol::from(id - 1))
+	}
+
+	/// Returns the `scale-info` type symbols of the given type symbols.
+	fn symbols(&self, ids: &[u32]) -> Result<Vec<PortableSymbol>, PortableError> {
+		ids.iter().map(|&id| self.symbol(id)).collect()
+	}
+
+	/// Returns the documentation lines associated with the given string symbols.
+	fn docs(&self, ids: &[u32]) -> Result<Vec<String>, PortableError> {
+		ids.iter().map(|&id| self.string(id)).collect()
+	}
+
+	/// Returns the encoding that is implied for slices of the given element type.
+	fn implied_encoding(&self, type_param: u32) -> Option<Encoding> {
+		let element = (type_param as usize)
+			.checked_sub(1)
+			.and_then(|index| self.registry.types.get(index));
+		match element {
+			Some(TypeIdDef {
+				id: TypeId::Primitive(TypeIdPrimitive::U8),
+				..
+			}) => Some(Encoding::Bytes),
+			_ => None,
+		}
+	}
+
+	/// Returns the `scale-info` fields of the given named fields of the type with the given type symbol.
+	fn named_fields(&self, id: u32, fields: &[NamedField]) -> Result<Vec<Field<PortableForm>>, PortableError> {
+		fields
+			.iter()
+			.map(|named| {
+				if named.group.is_some() || !named.sensitivity.is_public() || named.display.is_some() {
+					return Err(PortableError::UnsupportedType { id });
+				}
+				Ok(Field {
+					docs: self.docs(&named.docs)?,
+					..field(Some(self.string(named.name)?), self.symbol(named.ty)?)
+				})
+			})
+			.collect()
+	}
+
+	/// Returns the `scale-info` fields of the given unnamed field types.
+	fn unnamed_fields(&self, types: &[u32]) -> Result<Vec<Field<PortableForm>>, PortableError> {
+		types.iter().map(|&ty| Ok(field(None, self.symbol(ty)?))).collect()
+	}
+
+	/// Returns the composite without fields that stands in for the given type which `scale-info`
+	/// cannot express, keeping the path and type parameters of custom types.
+	fn opaque_type(&self, id_def: &TypeIdDef) -> Result<Type<PortableForm>, PortableError> {
+		let opaque = anonymous(PortableTypeDef::Composite(TypeDefComposite { fields: Vec::new() }));
+		match &id_def.id {
+			TypeId::Custom {
+				name,
+				namespace,
+				params,
+				docs,
+				..
+			} => Ok(Type {
+				docs: self.docs(docs)?,
+				..self.custom_type(name, namespace, params, opaque.type_def)?
+			}),
+			_ => Ok(opaque),
+		}
+	}
+
+	/// Returns the `scale-info` type with the given definition and the path and type parameters
+	/// of the custom type with the given name, namespace and type parameters.
+	fn custom_type(
+		&self,
+		name: &u32,
+		namespace: &[u32],
+		params: &[u32],
+		type_def: PortableTypeDef<PortableForm>,
+	) -> Result<Type<PortableForm>, PortableError> {
+		let mut segments = namespace
+			.iter()
+			.map(|&segment| self.string(segment))
+			.collect::<Result<Vec<_>, _>>()?;
+		segments.push(self.string(*name)?);
+		let mut type_params = Vec::with_capacity(params.len());
+		for (index, &param) in params.iter().enumerate() {
+			type_params.push(TypeParameter {
+				name: format!("T{}", index),
+				ty: Some(self.symbol(param)?),
+			});
+		}
+		Ok(Type {
+			path: Path { segments },
+			type_params,
+			..anonymous(type_def)
+		})
+	}
+
+	/// Returns the `scale-info` type of the given type with the given type symbol.
+	fn portable_type(&self, id: u32, id_def: &TypeIdDef) -> Result<Type<PortableForm>, PortableError> {
+		let unsupported = PortableError::UnsupportedType { id };
+		let (name, namespace, params, docs) = match (&id_def.id, &id_def.def) {
+			(TypeId::Primitive(primitive), _) => {
+				return Ok(anonymous(PortableTypeDef::Primitive(portable_primitive(primitive))));
+			}
+			(TypeId::Slice { type_param, encoding }, TypeDef::Builtin) => {
+				if *encoding != self.implied_encoding(*type_param) {
+					return Err(unsupported);
+				}
+				return Ok(anonymous(PortableTypeDef::Sequence(TypeDefSequence {
+					type_param: self.symbol(*type_param)?,
+				})));
+			}
+			(TypeId::Array { len, type_param }, TypeDef::Builtin) => {
+				return Ok(anonymous(PortableTypeDef::Array(TypeDefArray {
+					len: (*len).into(),
+					type_param: self.symbol(*type_param)?,
+				})));
+			}
+			(TypeId::Tuple(types), TypeDef::Builtin) => {
+				return Ok(anonymous(PortableTypeDef::Tuple(TypeDefTuple {
+					fields: self.symbols(types)?,
+				})));
+			}
+			(
+				TypeId::Custom {
+					name,
+					namespace,
+					params,
+					consts,
+					id: None,
+					docs,
+				},
+				_,
+			) if consts.is_empty() => (name, namespace, params, docs),
+			_ => return Err(unsupported),
+		};
+		let type_def = match &id_def.def {
+			TypeDef::Struct { fields } => PortableTypeDef::Composite(TypeDefComposite {
+				fields: self.named_fields(id, fields)?,
+			}),
+			TypeDef::TupleStruct { types } => PortableTypeDef::Composite(TypeDefComposite {
+				fields: self.unnamed_fields(types)?,
+			}),
+			TypeDef::ClikeEnum { variants } => {
+				let mut portable = Vec::with_capacity(variants.len());
+				for variant in variants {
+					if variant.code.is_some() {
+						return Err(unsupported);
+					}
+					portable.push(Variant {
+						name: self.string(variant.name)?,
+						fields: Vec::new(),
+						index: u8::try_from(variant.discriminant).map_err(|_| PortableError::UnsupportedType { id })?,
+						docs: Vec::new(),
+					});
+				}
+				PortableTypeDef::Variant(TypeDefVariant { variants: portable })
+			}
+			// SCALE always encodes the variant index, so niches are unsupported.
+			TypeDef::Enum {
+				variants,
+				repr: EnumRepr::External,
+				niche: false,
+			} => {
+				let mut portable = Vec::with_capacity(variants.len());
+				for (index, variant) in variants.iter().enumerate() {
+					let (name, fields, discriminant, docs) = match variant {
+						EnumVariant::Unit {
+							name,
+							code: None,
+							discriminant,
+							docs,
+						} => (name, Vec::new(), discriminant, docs),
+						EnumVariant::Struct {
+							name,
+							fields,
+							code: None,
+							discriminant,
+							docs,
+						} => (name, self.named_fields(id, fields)?, discriminant, docs),
+						EnumVariant::TupleStruct {
+							name,
+							types,
+							code: None,
+							discriminant,
+							docs,
+						} => (name, self.unnamed_fields(types)?, discriminant, docs),
+						_ => return Err(unsupported),
+					};
+					let index = discriminant.unwrap_or(index as u64);
+					portable.push(Variant {
+						name: self.string(*name)?,
+						fields,
+						index: u8::try_from(index).map_err(|_| PortableError::UnsupportedType { id })?,
+						docs: self.docs(docs)?,
+					});
+				}
+				PortableTypeDef::Variant(TypeDefVariant { variants: portable })
+			}
+			_ => return Err(unsupported),
+		};
+		Ok(Type {
+			docs: self.docs(docs)?,
+			..self.custom_type(name, namespace, params, type_def)?
+		})
+	}
+}
+
+/// Converts `scale-info` registries into registries in the nested layout.
+struct FromPortable<'a> {
+	/// The `scale-info` types without the reserved registry type.
+	types: &'a [PortableType],
+	/// Whether the types may carry their definitions in their documentation.
+	extended: bool,
+	/// The strings of the registry in order of their symbols.
+	///
+	/// These are the carried strings followed by the ones in order of their first occurrence.
+	strings: Vec<String>,
+	/// The raw identifiers of the first string symbols of the strings.
+	symbols: BTreeMap<String, u32>,
+}
+
+impl<'a> FromPortable<'a> {
+	/// Creates a converter of the given types, optionally carrying the strings of a registry.
+	fn new(types: &'a [PortableType], carried: Option<&Vec<String>>) -> Self {
+		let strings = carried.cloned().unwrap_or_default();
+		let mut symbols = BTreeMap::new();
+		for (index, string) in strings.iter().enumerate() {
+			symbols.entry(string.clone()).or_insert(index as u32 + 1);
+		}
+		Self {
+			types,
+			extended: carried.is_some(),
+			strings,
+			symbols,
+		}
+	}
+
+	/// Interns the given string and returns the raw identifier of its string symbol.
+	fn string(&mut self, string: &str) -> Result<u32, PortableError> {
+		if let Some(&symbol) = self.symbols.get(string) {
+			return Ok(symbol);
+		}
+		let symbol = u32::try_from(self.strings.len() + 1).map_err(|_| PortableError::TooManyStrings)?;
+		self.strings.push(string.to_string());
+		self.symbols.insert(string.to_string(), symbol);
+		Ok(symbol)
+	}
+
+	/// Returns the raw identifier of the type symbol of the given `scale-info` type symbol.
+	fn symbol(&self, symbol: &PortableSymbol) -> Result<u32, PortableError> {
+		if symbol.id as usize >= self.types.len() {
+			return Err(PortableError::MissingType { id: symbol.id });
+		}
+		Ok(symbol.id + 1)
+	}
+
+	/// Returns the raw identifiers of the type symbols of the given `scale-info` type symbols.
+	fn symbols(&self, symbols: &[PortableSymbol]) -> Result<Vec<u32>, PortableError> {
+		symbols.iter().map(|symbol| self.symbol(symbol)).collect()
+	}
+
+	/// Returns the encoding that is implied for slices of the given element type.
+	fn implied_encoding(&self, type_param: &PortableSymbol) -> Option<Encoding> {
+		let element = self.types.get(type_param.id as usize);
+		match element.map(|element| &element.ty.type_def) {
+			Some(PortableTypeDef::Primitive(TypeDefPrimitive::U8)) => Some(Encoding::Bytes),
+			_ => None,
+		}
+	}
+
+	/// Interns the given documentation lines and returns the raw identifiers of their string symbols.
+	fn docs(&mut self, docs: &[String]) -> Result<Vec<u32>, PortableError> {
+		docs.iter().map(|doc| self.string(doc)).collect()
+	}
+
+	/// Returns the named fields of the given `scale-info` fields if all of them are named.
+	fn named_fields(&mut self, fields: &[Field<PortableForm>]) -> Option<Result<Vec<NamedField>, PortableError>> {
+		let mut named = Vec::with_capacity(fields.len());
+		for field in fields {
+			let ty = match self.symbol(&field.ty) {
+				Ok(ty) => ty,
+				Err(err) => return Some(Err(err)),
+			};
+			let name = match self.string(field.name.as_ref()?) {
+				Ok(name) => name,
+				Err(err) => return Some(Err(err)),
+			};
+			let docs = match self.docs(&field.docs) {
+				Ok(docs) => docs,
+				Err(err) => return Some(Err(err)),
+			};
+			named.push(NamedField {
+				name,
+				ty,
+				group: None,
+				sensitivity: Sensitivity::Public,
+				display: None,
+				docs,
+			});
+		}
+		Some(Ok(named))
+	}
+
+	/// Returns the types of the given `scale-info` fields if all of them are unnamed.
+	fn unnamed_fields(&self, fields: &[Field<PortableForm>]) -> Option<Result<Vec<u32>, PortableError>> {
+		if fields.iter().any(|field| field.name.is_some()) {
+			return None;
+		}
+		Some(fields.iter().map(|field| self.symbol(&field.ty)).collect())
+	}
+
+	/// Returns the type identifier and definition of the given `scale-info` type.
+	fn type_id_def(&mut self, id: u32, ty: &Type<PortableForm>) -> Result<TypeIdDef, PortableError> {
+		if self.extended {
+			if let Some(id_def) = find_extension(id, ty) {
+				return id_def;
+			}
+		}
+		let unsupported = PortableError::UnsupportedType { id };
+		let (name, namespace) = match ty.path.segments.split_last() {
+			Some((name, namespace)) => (name, namespace),
+			None => {
+				let id = match &ty.type_def {
+					PortableTypeDef::Primitive(def) => TypeId::Primitive(primitive(def).ok_or(unsupported)?),
+					PortableTypeDef::Sequence(def) => TypeId::Slice {
+						type_param: self.symbol(&def.type_param)?,
+						encoding: self.implied_encoding(&def.type_param),
+					},
+					PortableTypeDef::Array(def) => TypeId::Array {
+						len: u16::try_from(def.len).map_err(|_| unsupported)?,
+						type_param: self.symbol(&def.type_param)?,
+					},
+					PortableTypeDef::Tuple(def) => TypeId::Tuple(self.symbols(&def.fields)?),
+					_ => return Err(unsupported),
+				};
+				return Ok(TypeIdDef {
+					id,
+					def: TypeDef::Builtin,
+				});
+			}
+		};
+		// The identifier is converted first since this crate registers it before the definition.
+		let id = TypeId::Custom {
+			name: self.string(name)?,
+			namespace: namespace
+				.iter()
+				.map(|segment| self.string(segment))
+				.collect::<Result<Vec<_>, _>>()?,
+			params: ty
+				.type_params
+				.iter()
+				.map(|param| param.ty.as_ref().ok_or(PortableError::UnsupportedType { id }))
+				.map(|param| param.and_then(|param| self.symbol(param)))
+				.collect::<Result<Vec<_>, _>>()?,
+			consts: vec![],
+			id: None,
+			docs: self.docs(&ty.docs)?,
+		};
+		let def = match &ty.type_def {
+			PortableTypeDef::Composite(def) if def.fields.is_empty() => TypeDef::TupleStruct { types: Vec::new() },
+			PortableTypeDef::Composite(def) => {
+				if let Some(types) = self.unnamed_fields(&def.fields) {
+					TypeDef::TupleStruct { types: types? }
+				} else if let Some(fields) = self.named_fields(&def.fields) {
+					TypeDef::Struct { fields: fields? }
+				} else {
+					return Err(unsupported);
+				}
+			}
+			// C-like enums cannot carry the documentation of their variants.
+			PortableTypeDef::Variant(def)
+				if def
+					.variants
+					.iter()
+					.all(|variant| variant.fields.is_empty() && variant.docs.is_empty()) =>
+			{
+				TypeDef::ClikeEnum {
+					variants: def
+						.variants
+						.iter()
+						.map(|variant| {
+							Ok(ClikeEnumVariant {
+								name: self.string(&variant.name)?,
+								discriminant: variant.index.into(),
+								code: None,
+							})
+						})
+						.collect::<Result<_, _>>()?,
+				}
+			}
+			PortableTypeDef::Variant(def) => {
+				let mut variants = Vec::with_capacity(def.variants.len());
+				for (index, variant) in def.variants.iter().enumerate() {
+					let name = self.string(&variant.name)?;
+					let code = None;
+					let docs = self.docs(&variant.docs)?;
+					// Indices deviating from the position are kept as explicit discriminants.
+					let discriminant =
+						Some(u64::from(variant.index)).filter(|discriminant| *discriminant != index as u64);
+					let variant = if variant.fields.is_empty() {
+						EnumVariant::Unit {
+							name,
+							code,
+							discriminant,
+							docs,
+						}
+					} else if let Some(types) = self.unnamed_fields(&variant.fields) {
+						EnumVariant::TupleStruct {
+							name,
+							types: types?,
+							code,
+							discriminant,
+							docs,
+						}
+					} else if let Some(fields) = self.named_fields(&variant.fields) {
+						EnumVariant::Struct {
+							name,
+							fields: fields?,
+							code,
+							discriminant,
+							docs,
+						}
+					} else {
+						return Err(unsupported);
+					};
+					variants.push(variant);
+				}
+				TypeDef::Enum {
+					variants,
+					repr: EnumRepr::External,
+					niche: false,
+				}
+			}
+			_ => return Err(unsupported),
+		};
+		Ok(TypeIdDef { id, def })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		EnumRepr as CompactEnumRepr, HasTypeDef, HasTypeId, Metadata, NamedField as CompactField, Namespace,
+		Registry as CompactRegistry, TypeDef as CompactTypeDef, TypeDefComposite as CompactComposite, TypeDefUnion,
+		TypeDefVariant as CompactVariantDef, TypeId as CompactTypeId, TypeIdCustom, UnionDiscriminator,
+		Variant as CompactVariant,
+	};
+	use core::marker::PhantomData;
+
+	/// A struct with field annotations, a stable identifier and a const generic parameter.
+	struct Account;
+
+	impl HasTypeId for Account {
+		fn type_id() -> CompactTypeId {
+			TypeIdCustom::new("Account", Namespace::new(vec!["app"]).unwrap(), vec![])
+				.with_const_params(vec![4u8.into()])
+				.with_stable_id("0a1b")
+				.into()
+		}
+	}
+
+	impl HasTypeDef for Account {
+		fn type_def() -> CompactTypeDef {
+			CompactComposite::new(vec![
+				CompactField::of::<u64>("balance").with_group("funds"),
+				CompactField::of::<String>("key")
+					.with_sensitivity(Sensitivity::Secret)
+					.with_display("hex"),
+			])
+			.into()
+		}
+	}
+
+	/// An internally tagged enum with error codes.
+	struct Event;
+
+	impl HasTypeId for Event {
+		fn type_id() -> CompactTypeId {
+			TypeIdCustom::new("Event", Namespace::new(vec!["app"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Event {
+		fn type_def() -> CompactTypeDef {
+			CompactVariantDef::new(vec![
+				CompactVariant::new("Created").with_fields(vec![CompactField::of::<Account>("account")]),
+				CompactVariant::new("Failed").with_code(7),
+			])
+			.with_repr(CompactEnumRepr::internal("type"))
+			.into()
+		}
+	}
+
+	/// A discriminated union.
+	struct Payload;
+
+	impl HasTypeId for Payload {
+		fn type_id() -> CompactTypeId {
+			TypeIdCustom::new("Payload", Namespace::new(vec!["app"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Payload {
+		fn type_def() -> CompactTypeDef {
+			TypeDefUnion::new(vec![CompactField::of::<u32>("int"), CompactField::of::<bool>("flag")])
+				.with_discriminator(UnionDiscriminator::new(vec!["kind"], vec![(0u8, "int"), (1, "flag")]))
+				.unwrap()
+				.into()
+		}
+	}
+
+	/// An arbitrary JSON value.
+	struct Settings;
+
+	impl HasTypeId for Settings {
+		fn type_id() -> CompactTypeId {
+			TypeIdCustom::new("Settings", Namespace::new(vec!["app"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Settings {
+		fn type_def() -> CompactTypeDef {
+			CompactTypeDef::json()
+		}
+	}
+
+	#[test]
+	fn round_trip() {
+		let mut registry = CompactRegistry::new();
+		registry.compact_type(&<Option<Vec<(u32, [bool; 4])>>>::meta_type());
+		registry.compact_type(&<Result<String, Box<u8>>>::meta_type());
+		let nested = Registry::from(&registry);
+		let portable = to_portable(&nested).unwrap();
+		assert_eq!(portable.types[0].ty.path.segments, vec!["Option".to_string()]);
+		let converted = from_portable(&portable).unwrap();
+		assert_eq!(converted, nested);
+		assert_eq!(to_portable(&converted), Ok(portable));
+	}
+
+	#[test]
+	fn round_trips_types_scale_info_cannot_express() {
+		let mut registry = CompactRegistry::new();
+		registry.compact_type(&Event::meta_type());
+		registry.compact_type(&Payload::meta_type());
+		registry.compact_type(&Settings::meta_type());
+		registry.compact_type(&<PhantomData<u8>>::meta_type());
+		registry.alias_type("Balance", u128::meta_type());
+		let nested = Registry::from(&registry);
+		let portable = to_portable(&nested).unwrap();
+		assert_eq!(from_portable(&portable), Ok(nested));
+
+		// Scale-info consumers see the union as an opaque composite.
+		let payload = portable
+			.types
+			.iter()
+			.map(|ty| &ty.ty)
+			.find(|ty| ty.path.segments == ["app", "Payload"])
+			.unwrap();
+		assert_eq!(
+			payload.type_def,
+			PortableTypeDef::Composite(TypeDefComposite { fields: Vec::new() })
+		);
+		assert!(payload.docs[0].starts_with(EXTENSION_PREFIX));
+		let last = &portable.types.last().unwrap().ty;
+		assert!(is_reserved_registry(last));
+	}
+
+	#[cfg(feature = "test-utils")]
+	#[test]
+	fn round_trips_the_corpus() {
+		let mut builder = crate::RegistryBuilder::new();
+		crate::snapshot::register_corpus(&mut builder);
+		let nested = Registry::from(&builder.build());
+		assert_eq!(from_portable(&to_portable(&nested).unwrap()), Ok(nested));
+	}
+
+	#[test]
+	fn keeps_natively_converted_registries_free_of_extensions() {
+		let mut registry = CompactRegistry::new();
+		registry.compact_type(&<Option<(u32, [bool; 4])>>::meta_type());
+		let nested = Registry::from(&registry);
+		let portable = to_portable(&nested).unwrap();
+		assert_eq!(portable.types.len(), nested.types.len());
+		assert!(portable.types.iter().all(|ty| ty.ty.docs.is_empty()));
+		assert_eq!(from_portable(&portable), Ok(nested));
+	}
+
+	#[test]
+	fn rejects_invalid_extensions() {
+		let mut registry = CompactRegistry::new();
+		registry.compact_type(&Payload::meta_type());
+		let mut portable = to_portable(&Registry::from(&registry)).unwrap();
+		portable.types[0].ty.docs = vec![format!("{}{{", EXTENSION_PREFIX)];
+		assert_eq!(from_portable(&portable), Err(PortableError::InvalidExtension { id: 0 }));
+	}
+}
@@ -0,0 +1,101 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SCALE encoding of registries in order to embed them on-chain.
+//!
+//! The compact forms only refer to their strings by symbols of the registry that
+//! compacted them. They are therefore encoded as their counterparts of the nested layout
+//! and decoded as those, e.g. a `Registry` is decoded as a `nested::Registry`.
+
+use super::{nested, Layout};
+use crate::{form::CompactForm, Registry, TypeDef, TypeId, TypeIdDef};
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+
+impl Encode for Registry {
+	/// Encodes the registry in the nested layout.
+	///
+	/// Deferred types are completed on a branch of the registry beforehand,
+	/// see `Registry::complete_deferred`.
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		if self.deferred_len() == 0 {
+			nested::Registry::from(self).encode_to(dest)
+		} else {
+			let mut registry = self.branch();
+			registry.complete_deferred();
+			nested::Registry::from(&registry).encode_to(dest)
+		}
+	}
+}
+
+impl Encode for TypeIdDef {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		nested::TypeIdDef::from(self).encode_to(dest)
+	}
+}
+
+impl Encode for TypeId<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		nested::TypeId::from(self).encode_to(dest)
+	}
+}
+
+impl Encode for TypeDef<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		nested::TypeDef::from(self).encode_to(dest)
+	}
+}
+
+impl Decode for nested::Registry {
+	/// Decodes a registry and rejects all but the nested layout.
+	fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+		let version = u32::decode(input)?;
+		if Layout::from_version(version) != Some(Layout::Nested) {
+			return Err("expected the format version of the nested layout".into());
+		}
+		Ok(Self {
+			version,
+			capabilities: Decode::decode(input)?,
+			strings: Decode::decode(input)?,
+			types: Decode::decode(input)?,
+			aliases: Decode::decode(input)?,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+
+	#[test]
+	fn round_trip() {
+		let mut registry = Registry::new();
+		let symbol = registry.compact_type(&<Result<Option<u8>, bool>>::meta_type());
+		registry.alias_type("Flag", bool::meta_type());
+		let encoded = registry.encode();
+		assert_eq!(
+			nested::Registry::decode(&mut &encoded[..]),
+			Ok(nested::Registry::from(&registry))
+		);
+		let id_def = registry.resolve(&symbol).unwrap();
+		assert_eq!(id_def.encode(), nested::TypeIdDef::from(id_def).encode());
+		assert_eq!([id_def.id().encode(), id_def.def().encode()].concat(), id_def.encode());
+
+		let mut flat = encoded;
+		flat[..4].copy_from_slice(&Layout::Flat.version().encode());
+		assert!(nested::Registry::decode(&mut &flat[..]).is_err());
+	}
+}
@@ -0,0 +1,210 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Size contributions of namespaces to a registry.
+//!
+//! Reviewers of on-chain metadata want to know which pallets contribute the most bytes.
+//! Since strings are interned, every string is attributed to the namespace of the first
+//! custom type referring to it in order of the type symbols. Strings only referred to by
+//! aliases or by non-custom types are not attributed to any namespace.
+
+use super::nested::{DiscriminantValue, EnumRepr, EnumVariant, NamedField, TypeDef, TypeId, TypeIdDef};
+use crate::tm_std::*;
+use crate::Registry;
+use serde::{Deserialize, Serialize};
+
+/// The size contribution of a namespace to a registry.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct NamespaceStats {
+	/// The path of the namespace, e.g. `"pallet_balances::types"`.
+	///
+	/// Empty for the prelude namespace.
+	pub namespace: String,
+	/// The number of custom types in the namespace.
+	pub types: usize,
+	/// The number of strings attributed to the namespace.
+	pub strings: usize,
+	/// The total length of the strings attributed to the namespace in bytes.
+	pub bytes: usize,
+}
+
+impl Registry {
+	/// Returns the size contributions of the namespaces of all custom types.
+	///
+	/// The namespaces are ordered from the largest to the smallest number of bytes.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	/// let stats = registry.namespace_stats();
+	/// assert_eq!((stats[0].types, stats[0].strings, stats[0].bytes), (1, 3, 14));
+	/// ```
+	pub fn namespace_stats(&self) -> Vec<NamespaceStats> {
+		let strings = self.strings();
+		let mut attributed = vec![false; strings.len()];
+		let mut stats = BTreeMap::<String, NamespaceStats>::new();
+		for (_, id_def) in self.types() {
+			let id_def = TypeIdDef::from(id_def);
+			let namespace = match &id_def.id {
+				TypeId::Custom { namespace, .. } => namespace
+					.iter()
+					.map(|segment| strings.get(*segment as usize - 1).copied().unwrap_or_default())
+					.collect::<Vec<_>>()
+					.join("::"),
+				_ => continue,
+			};
+			let entry = stats.entry(namespace.clone()).or_insert_with(|| NamespaceStats {
+				namespace,
+				types: 0,
+				strings: 0,
+				bytes: 0,
+			});
+			entry.types += 1;
+			let mut symbols = Vec::new();
+			collect_strings(&id_def, &mut symbols);
+			for symbol in symbols {
+				let index = symbol as usize - 1;
+				match (attributed.get_mut(index), strings.get(index)) {
+					(Some(attributed), Some(string)) if !*attributed => {
+						*attributed = true;
+						entry.strings += 1;
+						entry.bytes += string.len();
+					}
+					_ => (),
+				}
+			}
+		}
+		let mut stats = stats.into_values().collect::<Vec<_>>();
+		stats.sort_by_key(|stats| core::cmp::Reverse(stats.bytes));
+		stats
+	}
+}
+
+/// Appends the symbols of all strings the type identifier and definition refer to.
+fn collect_strings(id_def: &TypeIdDef, symbols: &mut Vec<u32>) {
+	fn named_fields(fields: &[NamedField], symbols: &mut Vec<u32>) {
+		for field in fields {
+			symbols.push(field.name);
+			symbols.extend(field.group);
+			symbols.extend(field.display);
+			symbols.extend(&field.docs);
+		}
+	}
+
+	if let TypeId::Custom {
+		name,
+		namespace,
+		id,
+		docs,
+		..
+	} = &id_def.id
+	{
+		symbols.push(*name);
+		symbols.extend(namespace);
+		symbols.extend(id);
+		symbols.extend(docs);
+	}
+	match &id_def.def {
+		TypeDef::Builtin | TypeDef::Json | TypeDef::Tombstone | TypeDef::Raw { .. } | TypeDef::TupleStruct { .. } => (),
+		TypeDef::Struct { fields } => named_fields(fields, symbols),
+		TypeDef::ClikeEnum { variants } => symbols.extend(variants.iter().map(|variant| variant.name)),
+		TypeDef::Enum { variants, repr, .. } => {
+			for variant in variants {
+				match variant {
+					EnumVariant::Unit { name, docs, .. } | EnumVariant::TupleStruct { name, docs, .. } => {
+						symbols.push(*name);
+						symbols.extend(docs);
+					}
+					EnumVariant::Struct { name, fields, docs, .. } => {
+						symbols.push(*name);
+						symbols.extend(docs);
+						named_fields(fields, symbols);
+					}
+				}
+			}
+			match repr {
+				EnumRepr::External | EnumRepr::Untagged => (),
+				EnumRepr::Internal { tag } => symbols.push(*tag),
+				EnumRepr::Adjacent { tag, content } => symbols.extend(&[*tag, *content]),
+			}
+		}
+		TypeDef::Union { fields, discriminator } => {
+			named_fields(fields, symbols);
+			if let Some(discriminator) = discriminator {
+				symbols.extend(&discriminator.field);
+				for mapping in &discriminator.mapping {
+					if let DiscriminantValue::Str(value) = mapping.value {
+						symbols.push(value);
+					}
+					symbols.push(mapping.member);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HasTypeDef, HasTypeId, Metadata, Namespace, TypeDefComposite, TypeIdCustom};
+
+	struct Account;
+
+	impl HasTypeId for Account {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Account", Namespace::new(vec!["balances"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Account {
+		fn type_def() -> crate::TypeDef {
+			TypeDefComposite::new(vec![
+				crate::NamedField::of::<Option<u64>>("free"),
+				crate::NamedField::of::<u64>("reserved"),
+			])
+			.into()
+		}
+	}
+
+	#[test]
+	fn attributes_strings_to_first_namespace() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<u64>>::meta_type());
+		registry.compact_type(&Account::meta_type());
+		registry.compact_type(&<Option<Account>>::meta_type());
+		assert_eq!(
+			registry.namespace_stats(),
+			vec![
+				NamespaceStats {
+					namespace: "balances".into(),
+					types: 1,
+					strings: 4,
+					bytes: 27,
+				},
+				NamespaceStats {
+					namespace: "".into(),
+					types: 2,
+					strings: 3,
+					bytes: 14,
+				},
+			]
+		);
+	}
+}
@@ -0,0 +1,526 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The structures of the tagged registry layout.
+//!
+//! The flat layout serializes enums untagged which is ambiguous upon deserialization,
+//! e.g. a struct without fields and a tuple-struct without fields look alike. The tagged
+//! layout, `Layout::Tagged`, tags every enum internally with a `"kind"` field next to the
+//! fields of the variant, e.g. `{"kind": "custom", "name": 1, ...}`, so that tooling which
+//! re-reads the output, e.g. in JavaScript, dispatches on a single field.
+//!
+//! The structures mirror the ones of the `nested` module and convert losslessly from and to them.
+
+use super::{nested, Layout};
+use crate::tm_std::*;
+use crate::{Capabilities, ConstParam, Encoding, RawFormat, TypeIdPrimitive};
+use serde::{
+	de::{Error as _, Unexpected},
+	Deserialize, Deserializer, Serialize,
+};
+
+/// Deserializes the format version and rejects all but the tagged layout.
+fn deserialize_version<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let version = u32::deserialize(deserializer)?;
+	match Layout::from_version(version) {
+		Some(Layout::Tagged) => Ok(version),
+		_ => Err(D::Error::invalid_value(
+			Unexpected::Unsigned(version.into()),
+			&"the format version of the tagged layout",
+		)),
+	}
+}
+
+/// A registry in the tagged layout.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+pub struct Registry {
+	/// The format version of the tagged layout.
+	#[serde(deserialize_with = "deserialize_version")]
+	pub version: u32,
+	/// The capabilities of the producer of the registry.
+	#[serde(default)]
+	pub capabilities: Capabilities,
+	/// The registered strings in order of their symbols.
+	pub strings: Vec<String>,
+	/// The registered types in order of their symbols.
+	pub types: Vec<TypeIdDef>,
+	/// The domain-level names of registered types.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<nested::Alias>,
+}
+
+impl From<&crate::Registry> for Registry {
+	fn from(registry: &crate::Registry) -> Self {
+		nested::Registry::from(registry).into()
+	}
+}
+
+impl From<nested::Registry> for Registry {
+	fn from(registry: nested::Registry) -> Self {
+		Self {
+			version: Layout::Tagged.version(),
+			capabilities: registry.capabilities,
+			strings: registry.strings,
+			types: registry.types.into_iter().map(Into::into).collect(),
+			aliases: registry.aliases,
+		}
+	}
+}
+
+impl From<Registry> for nested::Registry {
+	fn from(registry: Registry) -> Self {
+		Self {
+			version: Layout::Nested.version(),
+			capabilities: registry.capabilities,
+			strings: registry.strings,
+			types: registry.types.into_iter().map(Into::into).collect(),
+			aliases: registry.aliases,
+		}
+	}
+}
+
+/// The pair of associated type identifier and definition.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+pub struct TypeIdDef {
+	/// The identifier of the type.
+	pub id: TypeId,
+	/// The definition of the type.
+	pub def: TypeDef,
+}
+
+impl From<nested::TypeIdDef> for TypeIdDef {
+	fn from(id_def: nested::TypeIdDef) -> Self {
+		Self {
+			id: id_def.id.into(),
+			def: id_def.def.into(),
+		}
+	}
+}
+
+impl From<TypeIdDef> for nested::TypeIdDef {
+	fn from(id_def: TypeIdDef) -> Self {
+		Self {
+			id: id_def.id.into(),
+			def: id_def.def.into(),
+		}
+	}
+}
+
+/// A type identifier.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeId {
+	/// A custom type defined by the user.
+	Custom {
+		/// The name of the custom type.
+		name: u32,
+		/// The namespace of the custom type.
+		namespace: Vec<u32>,
+		/// The generic type parameters of the custom type.
+		params: Vec<u32>,
+		/// The values of the const generic parameters of the custom type.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		consts: Vec<ConstParam>,
+		/// The stable identifier of the custom type if one has been assigned.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		id: Option<u32>,
+		/// The documentation of the custom type, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+	/// A slice type with runtime known length.
+	Slice {
+		/// The element type of the slice.
+		#[serde(rename = "type")]
+		type_param: u32,
+		/// The encoding of the slice if it is known to represent raw bytes.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		encoding: Option<Encoding>,
+	},
+	/// An array type with compile-time known length.
+	Array {
+		/// The length of the array.
+		len: u16,
+		/// The element type of the array.
+		#[serde(rename = "type")]
+		type_param: u32,
+	},
+	/// A tuple type.
+	Tuple {
+		/// The types of the fields of the tuple.
+		params: Vec<u32>,
+	},
+	/// A Rust primitive type.
+	Primitive {
+		/// The primitive type.
+		primitive: TypeIdPrimitive,
+	},
+}
+
+impl From<nested::TypeId> for TypeId {
+	fn from(id: nested::TypeId) -> Self {
+		match id {
+			nested::TypeId::Custom {
+				name,
+				namespace,
+				params,
+				consts,
+				id,
+				docs,
+			} => TypeId::Custom {
+				name,
+				namespace,
+				params,
+				consts,
+				id,
+				docs,
+			},
+			nested::TypeId::Slice { type_param, encoding } => TypeId::Slice { type_param, encoding },
+			nested::TypeId::Array { len, type_param } => TypeId::Array { len, type_param },
+			nested::TypeId::Tuple(params) => TypeId::Tuple { params },
+			nested::TypeId::Primitive(primitive) => TypeId::Primitive { primitive },
+		}
+	}
+}
+
+impl From<TypeId> for nested::TypeId {
+	fn from(id: TypeId) -> Self {
+		match id {
+			TypeId::Custom {
+				name,
+				namespace,
+				params,
+				consts,
+				id,
+				docs,
+			} => nested::TypeId::Custom {
+				name,
+				namespace,
+				params,
+				consts,
+				id,
+				docs,
+			},
+			TypeId::Slice { type_param, encoding } => nested::TypeId::Slice { type_param, encoding },
+			TypeId::Array { len, type_param } => nested::TypeId::Array { len, type_param },
+			TypeId::Tuple { params } => nested::TypeId::Tuple(params),
+			TypeId::Primitive { primitive } => nested::TypeId::Primitive(primitive),
+		}
+	}
+}
+
+/// A type definition.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeDef {
+	/// A builtin type that has an implied and known internal structure.
+	Builtin,
+	/// An arbitrary JSON value whose structure is not known statically.
+	Json,
+	/// A retired type whose definition has been removed.
+	Tombstone,
+	/// An opaque payload in the given format.
+	Raw {
+		/// The declared format of the payload.
+		format: RawFormat,
+	},
+	/// A struct with named fields.
+	Struct {
+		/// The named fields of the struct.
+		fields: Vec<nested::NamedField>,
+	},
+	/// A tuple-struct with unnamed fields.
+	TupleStruct {
+		/// The types of the unnamed fields of the tuple-struct.
+		types: Vec<u32>,
+	},
+	/// A C-like enum with simple named variants.
+	ClikeEnum {
+		/// The variants of the C-like enum.
+		variants: Vec<nested::ClikeEnumVariant>,
+	},
+	/// A Rust enum with different kinds of variants.
+	Enum {
+		/// The variants of the enum.
+		variants: Vec<EnumVariant>,
+		/// The representation of the enum upon serialization.
+		#[serde(default, skip_serializing_if = "EnumRepr::is_external")]
+		repr: EnumRepr,
+		/// Whether the fieldless variant is encoded as the zero value of the field of the other variant.
+		#[serde(default, skip_serializing_if = "core::ops::Not::not")]
+		niche: bool,
+	},
+	/// An unsafe Rust union type.
+	Union {
+		/// The fields of the union.
+		fields: Vec<nested::NamedField>,
+		/// The description of the field that selects the active member, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminator: Option<nested::UnionDiscriminator>,
+	},
+}
+
+impl From<nested::TypeDef> for TypeDef {
+	fn from(def: nested::TypeDef) -> Self {
+		match def {
+			nested::TypeDef::Builtin => TypeDef::Builtin,
+			nested::TypeDef::Json => TypeDef::Json,
+			nested::TypeDef::Tombstone => TypeDef::Tombstone,
+			nested::TypeDef::Raw { format } => TypeDef::Raw { format },
+			nested::TypeDef::Struct { fields } => TypeDef::Struct { fields },
+			nested::TypeDef::TupleStruct { types } => TypeDef::TupleStruct { types },
+			nested::TypeDef::ClikeEnum { variants } => TypeDef::ClikeEnum { variants },
+			nested::TypeDef::Enum { variants, repr, niche } => TypeDef::Enum {
+				variants: variants.into_iter().map(Into::into).collect(),
+				repr: repr.into(),
+				niche,
+			},
+			nested::TypeDef::Union { fields, discriminator } => TypeDef::Union { fields, discriminator },
+		}
+	}
+}
+
+impl From<TypeDef> for nested::TypeDef {
+	fn from(def: TypeDef) -> Self {
+		match def {
+			TypeDef::Builtin => nested::TypeDef::Builtin,
+			TypeDef::Json => nested::TypeDef::Json,
+			TypeDef::Tombstone => nested::TypeDef::Tombstone,
+			TypeDef::Raw { format } => nested::TypeDef::Raw { format },
+			TypeDef::Struct { fields } => nested::TypeDef::Struct { fields },
+			TypeDef::TupleStruct { types } => nested::TypeDef::TupleStruct { types },
+			TypeDef::ClikeEnum { variants } => nested::TypeDef::ClikeEnum { variants },
+			TypeDef::Enum { variants, repr, niche } => nested::TypeDef::Enum {
+				variants: variants.into_iter().map(Into::into).collect(),
+				repr: repr.into(),
+				niche,
+			},
+			TypeDef::Union { fields, discriminator } => nested::TypeDef::Union { fields, discriminator },
+		}
+	}
+}
+
+/// A Rust enum variant.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EnumVariant {
+	/// A unit struct variant.
+	Unit {
+		/// The name of the variant.
+		name: u32,
+		/// The application-level error code of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminant: Option<u64>,
+		/// The documentation of the variant, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+	/// A struct variant with named fields.
+	Struct {
+		/// The name of the variant.
+		name: u32,
+		/// The named fields of the variant.
+		fields: Vec<nested::NamedField>,
+		/// The application-level error code of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminant: Option<u64>,
+		/// The documentation of the variant, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+	/// A tuple-struct variant with unnamed fields.
+	TupleStruct {
+		/// The name of the variant.
+		name: u32,
+		/// The types of the unnamed fields of the variant.
+		types: Vec<u32>,
+		/// The application-level error code of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		discriminant: Option<u64>,
+		/// The documentation of the variant, one entry per line.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		docs: Vec<u32>,
+	},
+}
+
+impl From<nested::EnumVariant> for EnumVariant {
+	fn from(variant: nested::EnumVariant) -> Self {
+		match variant {
+			nested::EnumVariant::Unit {
+				name,
+				code,
+				discriminant,
+				docs,
+			} => EnumVariant::Unit {
+				name,
+				code,
+				discriminant,
+				docs,
+			},
+			nested::EnumVariant::Struct {
+				name,
+				fields,
+				code,
+				discriminant,
+				docs,
+			} => EnumVariant::Struct {
+				name,
+				fields,
+				code,
+				discriminant,
+				docs,
+			},
+			nested::EnumVariant::TupleStruct {
+				name,
+				types,
+				code,
+				discriminant,
+				docs,
+			} => EnumVariant::TupleStruct {
+				name,
+				types,
+				code,
+				discriminant,
+				docs,
+			},
+		}
+	}
+}
+
+impl From<EnumVariant> for nested::EnumVariant {
+	fn from(variant: EnumVariant) -> Self {
+		match variant {
+			EnumVariant::Unit {
+				name,
+				code,
+				discriminant,
+				docs,
+			} => nested::EnumVariant::Unit {
+				name,
+				code,
+				discriminant,
+				docs,
+			},
+			EnumVariant::Struct {
+				name,
+				fields,
+				code,
+				discriminant,
+				docs,
+			} => nested::EnumVariant::Struct {
+				name,
+				fields,
+				code,
+				discriminant,
+				docs,
+			},
+			EnumVariant::TupleStruct {
+				name,
+				types,
+				code,
+				discriminant,
+				docs,
+			} => nested::EnumVariant::TupleStruct {
+				name,
+				types,
+				code,
+				discriminant,
+				docs,
+			},
+		}
+	}
+}
+
+/// The representation of an enum upon serialization.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EnumRepr {
+	/// The variant is externally tagged.
+	#[default]
+	External,
+	/// The variant is internally tagged.
+	Internal {
+		/// The name of the tag field.
+		tag: u32,
+	},
+	/// The variant is adjacently tagged.
+	Adjacent {
+		/// The name of the tag field.
+		tag: u32,
+		/// The name of the content field.
+		content: u32,
+	},
+	/// The variant is not tagged at all.
+	Untagged,
+}
+
+impl EnumRepr {
+	/// Returns `true` if this is the default externally tagged representation.
+	pub fn is_external(&self) -> bool {
+		matches!(self, EnumRepr::External)
+	}
+}
+
+impl From<nested::EnumRepr> for EnumRepr {
+	fn from(repr: nested::EnumRepr) -> Self {
+		match repr {
+			nested::EnumRepr::External => EnumRepr::External,
+			nested::EnumRepr::Internal { tag } => EnumRepr::Internal { tag },
+			nested::EnumRepr::Adjacent { tag, content } => EnumRepr::Adjacent { tag, content },
+			nested::EnumRepr::Untagged => EnumRepr::Untagged,
+		}
+	}
+}
+
+impl From<EnumRepr> for nested::EnumRepr {
+	fn from(repr: EnumRepr) -> Self {
+		match repr {
+			EnumRepr::External => nested::EnumRepr::External,
+			EnumRepr::Internal { tag } => nested::EnumRepr::Internal { tag },
+			EnumRepr::Adjacent { tag, content } => nested::EnumRepr::Adjacent { tag, content },
+			EnumRepr::Untagged => nested::EnumRepr::Untagged,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+
+	#[test]
+	fn converts_losslessly_from_nested() {
+		let mut registry = crate::Registry::new();
+		registry.compact_type(&<Result<(u8, [bool; 2]), Option<&'static str>>>::meta_type());
+		registry.alias_type("Flags", <[bool; 2]>::meta_type());
+		let nested = nested::Registry::from(&registry);
+		let tagged = Registry::from(&registry);
+		assert_eq!(tagged.version, Layout::Tagged.version());
+		assert_eq!(nested::Registry::from(tagged), nested);
+	}
+}
@@ -15,6 +15,18 @@
 // limitations under the License.
 
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(
+	all(feature = "no-panic", not(test)),
+	deny(
+		clippy::expect_used,
+		clippy::indexing_slicing,
+		clippy::panic,
+		clippy::todo,
+		clippy::unimplemented,
+		clippy::unreachable,
+		clippy::unwrap_used
+	)
+)]
 
 //! Efficient and compact serialization of Rust types.
 //!
@@ -76,15 +88,15 @@ extern crate alloc;
 /// ```
 /// # use type_metadata::tuple_meta_type;
 /// assert_eq!(
-/// 	tuple_meta_type!(i32, [u8; 32], String),
-/// 	{
-/// 		use type_metadata::MetaType;
-/// 		let mut vec = Vec::new();
-/// 		vec.push(MetaType::new::<i32>());
-/// 		vec.push(MetaType::new::<[u8; 32]>());
-/// 		vec.push(MetaType::new::<String>());
-/// 		vec
-/// 	}
+///     tuple_meta_type!(i32, [u8; 32], String),
+///     {
+///         use type_metadata::MetaType;
+///         let mut vec = Vec::new();
+///         vec.push(MetaType::new::<i32>());
+///         vec.push(MetaType::new::<[u8; 32]>());
+///         vec.push(MetaType::new::<String>());
+///         vec
+///     }
 /// );
 /// ```
 #[macro_export]
@@ -94,42 +106,421 @@ macro_rules! tuple_meta_type {
 			#[cfg(not(feature = "std"))]
 			extern crate alloc as _alloc;
 			#[cfg(not(feature = "std"))]
-			#[allow(unused_mut)]
-			let mut v = _alloc::vec![];
+			let v: _alloc::vec::Vec<$crate::MetaType> = _alloc::vec![ $( $crate::MetaType::new::<$ty>() ),* ];
 
 			#[cfg(feature = "std")]
-			#[allow(unused_mut)]
-			let mut v = std::vec![];
+			let v: std::vec::Vec<$crate::MetaType> = std::vec![ $( $crate::MetaType::new::<$ty>() ),* ];
 
-			$(
-				v.push($crate::MetaType::new::<$ty>());
-			)*
 			v
 		}
 	}
 }
 
+/// Implements `HasTypeId` and `HasTypeDef` for a type described like its declaration.
+///
+/// Structs with named, unnamed or no fields and enums whose variants have named, unnamed or
+/// no fields are supported, optionally with type parameters which are bounded by `Metadata`.
+/// The path of the type determines its namespace, e.g. `external::Foo` is named `Foo` within
+/// the namespace `external`, while types given by their name only are placed into the
+/// namespace of the invoking module like derived types.
+///
+/// This describes types without writing the implementations by hand, e.g. for crates that
+/// do not use the derive. Due to the orphan rule it has to be invoked within the crate that
+/// defines the type.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{impl_metadata, HasTypeDef, NamedField, TypeDefComposite};
+/// mod external {
+///     pub struct Foo {
+///         pub a: u32,
+///         pub b: Vec<u8>,
+///     }
+///
+///     pub enum Event<T> {
+///         Created(T),
+///         Deleted { id: u32 },
+///         Cleared,
+///     }
+/// }
+///
+/// impl_metadata!(struct external::Foo { a: u32, b: Vec<u8> });
+/// impl_metadata!(enum external::Event<T> { Created(T), Deleted { id: u32 }, Cleared });
+///
+/// assert_eq!(
+///     external::Foo::type_def(),
+///     TypeDefComposite::new(vec![NamedField::of::<u32>("a"), NamedField::of::<Vec<u8>>("b")]).into()
+/// );
+/// ```
+#[macro_export]
+macro_rules! impl_metadata {
+	( struct $($seg:ident)::+ $(< $($param:ident),* $(,)? >)? { $($field:ident : $ty:ty),* $(,)? } $(;)? ) => {
+		$crate::impl_metadata!(@impl [$($seg),+] [$($($param),*)?] {
+			let fields: &[$crate::Field] = &[
+				$( $crate::Field::named(stringify!($field), $crate::MetaType::new::<$ty>()) ),*
+			];
+			$crate::TypeDefComposite::new(fields.iter().cloned())
+		});
+	};
+	( struct $($seg:ident)::+ $(< $($param:ident),* $(,)? >)? ( $($ty:ty),* $(,)? ) $(;)? ) => {
+		$crate::impl_metadata!(@impl [$($seg),+] [$($($param),*)?] {
+			let fields: &[$crate::Field] = &[ $( $crate::Field::unnamed($crate::MetaType::new::<$ty>()) ),* ];
+			$crate::TypeDefComposite::new(fields.iter().cloned())
+		});
+	};
+	( struct $($seg:ident)::+ $(< $($param:ident),* $(,)? >)? $(;)? ) => {
+		$crate::impl_metadata!(@impl [$($seg),+] [$($($param),*)?] {
+			$crate::TypeDefComposite::unit()
+		});
+	};
+	(
+		enum $($seg:ident)::+ $(< $($param:ident),* $(,)? >)? {
+			$(
+				$variant:ident
+				$( ( $($unnamed:ty),* $(,)? ) )?
+				$( { $($field:ident : $named:ty),* $(,)? } )?
+			),* $(,)?
+		} $(;)?
+	) => {
+		$crate::impl_metadata!(@impl [$($seg),+] [$($($param),*)?] {
+			let variants: &[$crate::Variant] = &[
+				$(
+					$crate::Variant::new(stringify!($variant))
+					$(.with_fields({
+						let fields: &[$crate::Field] = &[
+							$( $crate::Field::unnamed($crate::MetaType::new::<$unnamed>()) ),*
+						];
+						fields.to_vec()
+					}))?
+					$(.with_fields({
+						let fields: &[$crate::Field] = &[
+							$( $crate::Field::named(stringify!($field), $crate::MetaType::new::<$named>()) ),*
+						];
+						fields.to_vec()
+					}))?
+				),*
+			];
+			$crate::TypeDefVariant::new(variants.iter().cloned())
+		});
+	};
+	( @impl [$($seg:ident),+] [$($param:ident),*] $def:block ) => {
+		impl<$($param: $crate::Metadata + 'static),*> $crate::HasTypeId for $($seg)::+ <$($param),*> {
+			fn type_id() -> $crate::TypeId {
+				let params: &[$crate::MetaType] = &[ $( $crate::MetaType::new::<$param>() ),* ];
+				$crate::TypeIdCustom::from_path(&[ $( stringify!($seg) ),+ ], module_path!(), params.iter().cloned())
+					.into()
+			}
+		}
+
+		impl<$($param: $crate::Metadata + 'static),*> $crate::HasTypeDef for $($seg)::+ <$($param),*> {
+			fn type_def() -> $crate::TypeDef {
+				let def = $def;
+				def.into()
+			}
+		}
+	};
+}
+
+/// Generates a unit test asserting that the metadata of a type matches the expected one.
+///
+/// The expected type definition, and optionally the expected type identifier,
+/// are written using the usual constructors such as `TypeDefComposite::new`.
+/// This turns accidental changes to derived metadata into test failures.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{assert_metadata_matches, NamedField, TypeDefComposite};
+/// assert_metadata_matches!(
+///     vec_metadata,
+///     Vec<bool>,
+///     TypeDefComposite::new(vec![NamedField::of::<[bool]>("elems")]),
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_metadata_matches {
+	( $test_name:ident, $ty:ty, id: $id:expr, def: $def:expr $(,)? ) => {
+		#[test]
+		fn $test_name() {
+			assert_eq!(
+				<$ty as $crate::HasTypeId>::type_id(),
+				$crate::TypeId::from($id),
+				"type identifier of `{}` does not match",
+				stringify!($ty),
+			);
+			assert_eq!(
+				<$ty as $crate::HasTypeDef>::type_def(),
+				$crate::TypeDef::from($def),
+				"type definition of `{}` does not match",
+				stringify!($ty),
+			);
+		}
+	};
+	( $test_name:ident, $ty:ty, $def:expr $(,)? ) => {
+		#[test]
+		fn $test_name() {
+			assert_eq!(
+				<$ty as $crate::HasTypeDef>::type_def(),
+				$crate::TypeDef::from($def),
+				"type definition of `{}` does not match",
+				stringify!($ty),
+			);
+		}
+	};
+}
+
+/// Registers the given types into the given `RegistryBuilder` in order.
+///
+/// This is useful for implementations of `RegisterSubsystem` that
+/// otherwise consist of long lists of `register_type` calls.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{register_types, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// register_types!(&mut builder, u32, Option<bool>, [u8; 32]);
+///
+/// let mut expected = RegistryBuilder::new();
+/// expected.register::<u32>();
+/// expected.register::<Option<bool>>();
+/// expected.register::<[u8; 32]>();
+/// assert_eq!(builder.build(), expected.build());
+/// ```
+#[macro_export]
+macro_rules! register_types {
+	( $builder:expr, $($ty:ty),* $(,)? ) => {
+		{
+			let builder = &mut *$builder;
+			$(
+				builder.register_type(&$crate::MetaType::new::<$ty>());
+			)*
+		}
+	};
+}
+
+/// Returns the fully qualified path of the given type as described by its metadata.
+///
+/// See `MetaType::path` for the format of the path.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::metadata_type_name;
+/// assert_eq!(metadata_type_name!(Option<[u8; 32]>), "Option<[u8; 32]>");
+/// ```
+#[macro_export]
+macro_rules! metadata_type_name {
+	( $ty:ty $(,)? ) => {
+		$crate::MetaType::new::<$ty>().path()
+	};
+}
+
+/// Asserts that both types resolve to the identical entry of a registry.
+///
+/// This allows framework code to enforce that associated types of different crates,
+/// e.g. `<A as Config>::AccountId` and `<B as Config>::AccountId`, are the same type
+/// since they are only known to be after monomorphization. Types that merely share
+/// their type identifiers, e.g. the same type of two versions of a crate, are
+/// registered as different entries and thus fail the assertion.
+///
+/// # Panics
+///
+/// If the types differ, reporting the metadata paths of both.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::metadata_assert_same_type;
+/// type AccountId = [u8; 32];
+/// metadata_assert_same_type!(AccountId, [u8; 32]);
+/// ```
+#[macro_export]
+macro_rules! metadata_assert_same_type {
+	( $a:ty, $b:ty $(,)? ) => {{
+		let (a, b) = ($crate::MetaType::new::<$a>(), $crate::MetaType::new::<$b>());
+		assert!(
+			a == b,
+			"`{}` and `{}` resolve to different types: `{}` and `{}`",
+			stringify!($a),
+			stringify!($b),
+			a.path(),
+			b.path(),
+		);
+	}};
+}
+
+/// Defines a static registry that is filled with the given types upon its first access.
+///
+/// The types are registered in the given order so that their type symbols are
+/// deterministic. Since `MetaType` instances cannot be created in const contexts
+/// the registry is initialized lazily, exactly once, using `once_cell`.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::static_registry;
+/// static_registry! {
+///     /// The registry of all types used by the protocol.
+///     pub static REGISTRY = [u32, Option<bool>, Vec<u8>];
+/// }
+///
+/// assert_eq!(REGISTRY.reserved_len(), 0);
+/// ```
+#[cfg(feature = "once_cell")]
+#[macro_export]
+macro_rules! static_registry {
+	( $(#[$attr:meta])* $vis:vis static $name:ident = [ $($ty:ty),* $(,)? ]; ) => {
+		$(#[$attr])*
+		$vis static $name: $crate::__once_cell::sync::Lazy<$crate::Registry> =
+			$crate::__once_cell::sync::Lazy::new(|| {
+				#[allow(unused_mut)]
+				let mut builder = $crate::RegistryBuilder::new();
+				$crate::register_types!(&mut builder, $($ty),*);
+				builder.build()
+			});
+	};
+}
+
+#[cfg(feature = "once_cell")]
+#[doc(hidden)]
+pub use once_cell as __once_cell;
+
+/// Annotates the given types for registration into the `global_registry`.
+///
+/// The annotation may be placed in any crate of the crate graph, usually the one
+/// defining the types, as an item or a statement.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::register_global;
+/// register_global! { u32, Option<bool>, Vec<u8> }
+/// ```
+#[cfg(feature = "global-registry")]
+#[macro_export]
+macro_rules! register_global {
+	( $($ty:ty),* $(,)? ) => {
+		$(
+			$crate::__inventory::submit! {
+				$crate::global::GlobalType {
+					meta_type: <$ty as $crate::Metadata>::meta_type,
+				}
+			}
+		)*
+	};
+}
+
+#[cfg(feature = "global-registry")]
+#[doc(hidden)]
+pub use inventory as __inventory;
+
+/// Asserts that the registry matches the committed snapshot of the given name.
+///
+/// The snapshot is stored as `snapshots/<name>.json` within the manifest directory
+/// of the calling crate. See the `snapshot` module for how snapshots are created and updated.
+///
+/// # Example
+///
+/// ```no_run
+/// # use type_metadata::{assert_registry_snapshot, register_types, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// register_types!(&mut builder, u32, Option<bool>);
+/// let registry = builder.build();
+/// assert_registry_snapshot!(registry, "protocol");
+/// ```
+#[cfg(feature = "test-utils")]
+#[macro_export]
+macro_rules! assert_registry_snapshot {
+	( $registry:expr, $name:expr $(,)? ) => {
+		$crate::snapshot::assert_registry_snapshot(&$registry, env!("CARGO_MANIFEST_DIR"), $name)
+	};
+}
+
 mod tm_std;
 
+mod arena;
+#[cfg(feature = "audit")]
+pub mod audit;
+mod builder;
+mod capabilities;
+pub mod compat;
+#[cfg(feature = "test-utils")]
+pub mod conformance;
+mod expand;
+pub mod export;
 pub mod form;
+#[cfg(feature = "global-registry")]
+#[doc(hidden)]
+pub mod global;
 mod impls;
+#[cfg(feature = "serde")]
+mod ingest;
 pub mod interner;
+#[cfg(feature = "serde")]
+pub mod layout;
 mod meta_type;
+#[cfg(feature = "serde")]
+mod owned;
+#[cfg(feature = "ffi")]
+pub mod plugin;
+#[cfg(feature = "std")]
+mod pool;
+pub mod prelude;
+#[cfg(feature = "serde")]
+mod portable;
+#[cfg(feature = "serde")]
+mod raw;
+mod recording;
 mod registry;
+pub mod schema;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "test-utils")]
+pub mod snapshot;
+pub mod storage;
+pub mod telemetry;
+#[cfg(feature = "transcode")]
+pub mod transcode;
+mod tree;
 mod type_def;
 mod type_id;
 mod utils;
+pub mod value;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::{
+	builder::RegistryBuilder,
+	capabilities::Capabilities,
+	expand::{expand_type, ExpandedType, ExpandedTypeDef},
 	meta_type::MetaType,
-	registry::{IntoCompact, Registry},
+	recording::Registration,
+	registry::{
+		FindOptions, IntoCompact, RegisterSubsystem, Registry, ReservedError, Transaction, TransactionError,
+		TypeIdDef,
+	},
+	tree::{NamespaceIter, NamespaceNode, NamespaceTree},
 	type_def::*,
 	type_id::*,
 };
 
+#[cfg(feature = "serde")]
+pub use self::{
+	ingest::{IngestError, IngestOptions},
+	owned::{OwnedRegistry, OwnedRegistryError},
+	portable::PortableRegistry,
+	raw::RawTypeError,
+	registry::ExtendError,
+};
+#[cfg(feature = "std")]
+pub use self::{pool::StringPool, shared::SharedRegistry};
+
+#[cfg(feature = "global-registry")]
+pub use self::global::global_registry;
+
 #[cfg(feature = "derive")]
 pub use type_metadata_derive::{Metadata, TypeDef, TypeId};
 
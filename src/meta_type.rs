@@ -24,6 +24,12 @@ use crate::{form::MetaForm, HasTypeDef, HasTypeId, Metadata, TypeDef, TypeId};
 ///
 /// This needs a conversion to another representation of types
 /// in order to be serializable.
+///
+/// # Note
+///
+/// Meta types cannot be created in const contexts since neither `core::any::TypeId::of`
+/// nor `core::any::type_name` are usable in const functions. Use the `static_registry!`
+/// macro in order to build a registry once, lazily, instead of upon program startup.
 #[derive(Clone, Copy)]
 pub struct MetaType {
 	/// Function pointer to type ID.
@@ -32,8 +38,15 @@ pub struct MetaType {
 	fn_type_def: fn() -> TypeDef<MetaForm>,
 	// The standard type ID (ab)used in order to provide
 	// cheap implementations of the standard traits
-	// such as `PartialEq`, `PartialOrd`, `Debug` and `Hash`.
+	// such as `PartialEq`, `PartialOrd` and `Hash`.
 	any_id: AnyTypeId,
+	/// The name of the type as provided by `core::any::type_name`.
+	///
+	/// # Note
+	///
+	/// This is meant for diagnostic purposes only and must not
+	/// be used to uniquely identify types.
+	name: &'static str,
 }
 
 impl PartialEq for MetaType {
@@ -46,7 +59,7 @@ impl Eq for MetaType {}
 
 impl PartialOrd for MetaType {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		self.any_id.partial_cmp(&other.any_id)
+		Some(self.cmp(other))
 	}
 }
 
@@ -67,7 +80,13 @@ impl Hash for MetaType {
 
 impl Debug for MetaType {
 	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
-		self.any_id.fmt(f)
+		f.debug_tuple("MetaType").field(&self.name).finish()
+	}
+}
+
+impl Display for MetaType {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.write_str(self.name)
 	}
 }
 
@@ -81,6 +100,7 @@ impl MetaType {
 			fn_type_id: <T as HasTypeId>::type_id,
 			fn_type_def: <T as HasTypeDef>::type_def,
 			any_id: AnyTypeId::of::<T>(),
+			name: core::any::type_name::<T>(),
 		}
 	}
 
@@ -92,6 +112,41 @@ impl MetaType {
 		Self::new::<T>()
 	}
 
+	/// Creates a new meta type that is described by the given functions instead of a type.
+	///
+	/// The meta type is identified by the type of `key`, usually the function item of
+	/// `fn_type_def`, so that all meta types created with the same key refer to the same
+	/// registered type. This backs the `#[metadata(with = "...")]` field attribute.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{MetaType, TypeDef, TypeId, TypeIdPrimitive};
+	/// // Describes a timestamp that is serialized as an RFC 3339 string.
+	/// fn type_id() -> TypeId {
+	///     TypeIdPrimitive::Str.into()
+	/// }
+	///
+	/// fn type_def() -> TypeDef {
+	///     TypeDef::builtin()
+	/// }
+	///
+	/// let meta_type = MetaType::from_fns(type_def, type_id, type_def);
+	/// assert_eq!(meta_type, MetaType::from_fns(type_def, type_id, type_def));
+	/// assert_eq!(meta_type.type_id(), TypeIdPrimitive::Str.into());
+	/// ```
+	pub fn from_fns<K>(_key: K, fn_type_id: fn() -> TypeId<MetaForm>, fn_type_def: fn() -> TypeDef<MetaForm>) -> Self
+	where
+		K: 'static,
+	{
+		Self {
+			fn_type_id,
+			fn_type_def,
+			any_id: AnyTypeId::of::<K>(),
+			name: core::any::type_name::<K>(),
+		}
+	}
+
 	/// Returns the meta type identifier.
 	pub fn type_id(&self) -> TypeId<MetaForm> {
 		(self.fn_type_id)()
@@ -106,4 +161,72 @@ impl MetaType {
 	pub fn any_id(&self) -> AnyTypeId {
 		self.any_id
 	}
+
+	/// Returns the fully qualified path of the type as described by its type identifier,
+	/// e.g. `my_crate::module::Foo<Option<u32>>`.
+	///
+	/// Types within the prelude namespace are returned without any prefix. In contrast to
+	/// `name` this is stable across compiler versions and reflects renames of the metadata.
+	pub fn path(&self) -> String {
+		let paths = |params: &[MetaType]| params.iter().map(MetaType::path).collect::<Vec<_>>().join(", ");
+		match self.type_id() {
+			TypeId::Custom(custom) => {
+				let mut path = String::new();
+				for segment in custom.namespace().segments() {
+					path.push_str(segment);
+					path.push_str("::");
+				}
+				path.push_str(custom.name());
+				let params = custom
+					.type_params()
+					.iter()
+					.map(MetaType::path)
+					.chain(custom.const_params().iter().map(ToString::to_string))
+					.collect::<Vec<_>>();
+				if !params.is_empty() {
+					path.push_str(&format!("<{}>", params.join(", ")));
+				}
+				path
+			}
+			TypeId::Slice(slice) => format!("[{}]", slice.type_param().path()),
+			TypeId::Array(array) => format!("[{}; {}]", array.type_param().path(), array.len()),
+			TypeId::Tuple(tuple) if tuple.type_params().len() == 1 => format!("({},)", paths(tuple.type_params())),
+			TypeId::Tuple(tuple) => format!("({})", paths(tuple.type_params())),
+			TypeId::Primitive(primitive) => primitive.name().to_string(),
+		}
+	}
+
+	/// Returns the name of the type as provided by `core::any::type_name`.
+	///
+	/// # Note
+	///
+	/// The returned name is meant for diagnostics only since its exact
+	/// contents are not guaranteed to be stable across compiler versions.
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(not(feature = "std"))]
+	use alloc::format;
+
+	#[test]
+	fn name_and_formatting() {
+		let meta_type = MetaType::new::<Option<u32>>();
+		assert_eq!(meta_type.name(), "core::option::Option<u32>");
+		assert_eq!(format!("{}", meta_type), "core::option::Option<u32>");
+		assert_eq!(format!("{:?}", meta_type), "MetaType(\"core::option::Option<u32>\")");
+	}
+
+	#[test]
+	fn path() {
+		assert_eq!(MetaType::new::<Option<(u8,)>>().path(), "Option<(u8,)>");
+		assert_eq!(MetaType::new::<[Vec<bool>; 2]>().path(), "[Vec<bool>; 2]");
+		assert_eq!(MetaType::new::<(bool, String)>().path(), "(bool, str)");
+		assert_eq!(MetaType::new::<[u8]>().path(), "[u8]");
+	}
 }
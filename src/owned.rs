@@ -0,0 +1,555 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A registry owning all its strings for consumers that load metadata at runtime.
+//!
+//! The type identifiers and definitions of a `Registry` refer to `'static` strings, so
+//! they cannot be deserialized from a file or network buffer. An `OwnedRegistry` holds
+//! them in the `OwnedForm` with owned strings instead. It is deserialized from the nested
+//! layout, see `Registry::with_layout`, and validates all symbols while doing so.
+//!
+//! Definitions are read back in the unified forms `TypeDefComposite` and `TypeDefVariant`,
+//! whatever form they have been registered in. Structs without fields, tuple-structs
+//! without fields and unit structs all read back as the unit composite.
+
+use crate::form::{Form, OwnedForm};
+use crate::interner::UntrackedSymbol;
+use crate::layout::nested;
+use crate::tm_std::*;
+use crate::utils::{new_items, Items};
+use crate::{
+	Builtin, Capabilities, DiscriminantValue, EnumRepr, Field, NamedField, Namespace, Registry, Sensitivity, TypeDef,
+	TypeDefComposite, TypeDefUnion, TypeDefVariant, TypeId, TypeIdArray, TypeIdCustom, TypeIdSlice, TypeIdTuple,
+	UnionDiscriminator, UnionDiscriminatorMapping, Variant,
+};
+use core::fmt::{Display, Error as FmtError, Formatter};
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+/// An error upon converting a registry in the nested layout into an `OwnedRegistry`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OwnedRegistryError {
+	/// A string symbol does not refer to any string of the registry.
+	UnknownString(u32),
+	/// A type symbol does not refer to any type of the registry.
+	UnknownType(u32),
+}
+
+impl Display for OwnedRegistryError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			OwnedRegistryError::UnknownString(symbol) => write!(f, "unknown string symbol {}", symbol),
+			OwnedRegistryError::UnknownType(symbol) => write!(f, "unknown type symbol {}", symbol),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OwnedRegistryError {}
+
+/// A registry whose type identifiers and definitions own their strings.
+///
+/// Types are referred to by the same type symbols as in the registry it has been
+/// serialized from.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{Metadata, RegistryBuilder, TypeId};
+/// let mut builder = RegistryBuilder::new();
+/// let symbol = builder.register_type(&<Option<u32>>::meta_type());
+/// let registry = builder.build();
+/// let owned = registry.try_into_owned().unwrap();
+/// match owned.resolve(&symbol) {
+///     Some((TypeId::Custom(custom), _)) => assert_eq!(custom.name(), "Option"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct OwnedRegistry {
+	/// The capabilities of the producer of the registry.
+	capabilities: Capabilities,
+	/// The types in order of their symbols.
+	types: Vec<(TypeId<OwnedForm>, TypeDef<OwnedForm>)>,
+	/// The domain-level names of the types.
+	aliases: Vec<(String, UntrackedSymbol<AnyTypeId>)>,
+}
+
+impl Registry {
+	/// Converts the registry into an `OwnedRegistry`.
+	///
+	/// Deferred types are completed beforehand so that all type symbols resolve.
+	///
+	/// # Panics
+	///
+	/// If the registry has run out of symbols. Not available with the `no-panic` feature,
+	/// use `try_into_owned` instead.
+	#[cfg(not(feature = "no-panic"))]
+	pub fn into_owned(self) -> OwnedRegistry {
+		self.try_into_owned().expect("symbols of a registry always resolve")
+	}
+
+	/// Converts the registry into an `OwnedRegistry`.
+	///
+	/// Deferred types are completed beforehand so that all type symbols resolve.
+	///
+	/// # Errors
+	///
+	/// If a symbol of the registry does not resolve, which only happens
+	/// for registries that have run out of symbols.
+	pub fn try_into_owned(mut self) -> Result<OwnedRegistry, OwnedRegistryError> {
+		self.complete_deferred();
+		OwnedRegistry::try_from(nested::Registry::from(&self))
+	}
+}
+
+impl<'de> Deserialize<'de> for OwnedRegistry {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let registry = nested::Registry::deserialize(deserializer)?;
+		Self::try_from(registry).map_err(D::Error::custom)
+	}
+}
+
+impl TryFrom<nested::Registry> for OwnedRegistry {
+	type Error = OwnedRegistryError;
+
+	fn try_from(registry: nested::Registry) -> Result<Self, Self::Error> {
+		let symbols = OwnedSymbols {
+			strings: &registry.strings,
+			types: registry.types.len(),
+		};
+		let types = registry
+			.types
+			.iter()
+			.map(|id_def| Ok((symbols.type_id(&id_def.id)?, symbols.type_def(&id_def.def)?)))
+			.collect::<Result<Vec<_>, _>>()?;
+		let aliases = registry
+			.aliases
+			.iter()
+			.map(|alias| Ok((symbols.string(alias.name)?, symbols.ty(alias.ty)?)))
+			.collect::<Result<Vec<_>, _>>()?;
+		Ok(Self {
+			capabilities: registry.capabilities,
+			types,
+			aliases,
+		})
+	}
+}
+
+impl OwnedRegistry {
+	/// Deserializes a registry in the nested layout rejecting unknown fields of the registry,
+	/// see `nested::Registry::deserialize_strict`.
+	pub fn deserialize_strict<'de, D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let registry = nested::Registry::deserialize_strict(deserializer)?;
+		Self::try_from(registry).map_err(D::Error::custom)
+	}
+
+	/// Returns the capabilities of the producer of the registry.
+	pub fn capabilities(&self) -> Capabilities {
+		self.capabilities
+	}
+
+	/// Returns the number of types of the registry.
+	pub fn len(&self) -> usize {
+		self.types.len()
+	}
+
+	/// Returns `true` if the registry has no types.
+	pub fn is_empty(&self) -> bool {
+		self.types.is_empty()
+	}
+
+	/// Returns the type identifier and definition of the given type symbol, if any.
+	pub fn resolve(&self, symbol: &UntrackedSymbol<AnyTypeId>) -> Option<(&TypeId<OwnedForm>, &TypeDef<OwnedForm>)> {
+		self.types
+			.get(symbol.id().get() as usize - 1)
+			.map(|(id, def)| (id, def))
+	}
+
+	/// Returns the type symbol aliased by the given name, if any.
+	pub fn alias(&self, name: &str) -> Option<UntrackedSymbol<AnyTypeId>> {
+		self.aliases.iter().find(|(alias, _)| alias == name).map(|(_, ty)| *ty)
+	}
+
+	/// Returns an iterator over all types together with their type symbols in order of registration.
+	pub fn types(
+		&self,
+	) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, &TypeId<OwnedForm>, &TypeDef<OwnedForm>)> + '_ {
+		(1..)
+			.filter_map(NonZeroU32::new)
+			.map(UntrackedSymbol::from_id)
+			.zip(&self.types)
+			.map(|(symbol, (id, def))| (symbol, id, def))
+	}
+}
+
+/// Resolves the raw symbols of type identifiers and definitions in the nested layout.
+///
+/// Implementors resolve single symbols while the identifiers and definitions are
+/// converted the same way whatever form they are resolved into.
+pub(crate) trait Symbols {
+	/// The form of the resolved type identifiers and definitions.
+	type Form: Form<TypeId = UntrackedSymbol<AnyTypeId>, IndirectTypeId = UntrackedSymbol<AnyTypeId>>;
+
+	/// Returns the string of the given string symbol.
+	fn string(&self, symbol: u32) -> Result<<Self::Form as Form>::String, OwnedRegistryError>;
+
+	/// Returns the type symbol with the given raw identifier.
+	fn ty(&self, symbol: u32) -> Result<UntrackedSymbol<AnyTypeId>, OwnedRegistryError>;
+
+	/// Returns the strings of the given string symbols.
+	fn strings(&self, symbols: &[u32]) -> Result<Vec<<Self::Form as Form>::String>, OwnedRegistryError> {
+		symbols.iter().map(|symbol| self.string(*symbol)).collect()
+	}
+
+	/// Returns the resolved form of the given type identifier.
+	fn type_id(&self, id: &nested::TypeId) -> Result<TypeId<Self::Form>, OwnedRegistryError> {
+		Ok(match id {
+			nested::TypeId::Custom {
+				name,
+				namespace,
+				params,
+				consts,
+				id,
+				docs,
+			} => TypeIdCustom::from_parts(
+				self.string(*name)?,
+				Namespace::from_segments(self.strings(namespace)?),
+				params.iter().map(|param| self.ty(*param)).collect::<Result<_, _>>()?,
+				consts.clone(),
+				id.map(|id| self.string(id)).transpose()?,
+				self.strings(docs)?,
+			)
+			.into(),
+			nested::TypeId::Slice { type_param, encoding } => {
+				TypeIdSlice::from_parts(self.ty(*type_param)?, *encoding).into()
+			}
+			nested::TypeId::Array { len, type_param } => TypeIdArray {
+				len: *len,
+				type_param: self.ty(*type_param)?,
+			}
+			.into(),
+			nested::TypeId::Tuple(params) => TypeIdTuple {
+				type_params: params.iter().map(|param| self.ty(*param)).collect::<Result<_, _>>()?,
+			}
+			.into(),
+			nested::TypeId::Primitive(primitive) => primitive.clone().into(),
+		})
+	}
+
+	/// Returns the resolved form of the given type definition.
+	///
+	/// Structs and tuple-structs become composites while C-like enums and enums become
+	/// variant definitions, just like the definitions of a registry built today.
+	fn type_def(&self, def: &nested::TypeDef) -> Result<TypeDef<Self::Form>, OwnedRegistryError> {
+		Ok(match def {
+			nested::TypeDef::Builtin => TypeDef::Builtin(Builtin::Builtin),
+			nested::TypeDef::Json => TypeDef::Builtin(Builtin::Json),
+			nested::TypeDef::Tombstone => TypeDef::Builtin(Builtin::Tombstone),
+			nested::TypeDef::Raw { format } => TypeDef::Builtin(Builtin::Raw(*format)),
+			nested::TypeDef::Struct { fields } => TypeDefComposite::from_fields(self.fields(fields)?).into(),
+			nested::TypeDef::TupleStruct { types } => TypeDefComposite::from_fields(self.unnamed_fields(types)?).into(),
+			nested::TypeDef::ClikeEnum { variants } => TypeDefVariant::from_parts(
+				variants
+					.iter()
+					.map(|variant| {
+						Ok(Variant::from_parts(
+							self.string(variant.name)?,
+							new_items(),
+							Some(variant.discriminant),
+							variant.code,
+							Vec::new(),
+						))
+					})
+					.collect::<Result<_, _>>()?,
+				EnumRepr::External,
+				false,
+			)
+			.into(),
+			nested::TypeDef::Enum { variants, repr, niche } => TypeDefVariant::from_parts(
+				variants
+					.iter()
+					.map(|variant| self.variant(variant))
+					.collect::<Result<_, _>>()?,
+				self.repr(repr)?,
+				*niche,
+			)
+			.into(),
+			nested::TypeDef::Union { fields, discriminator } => TypeDefUnion::from_parts(
+				fields
+					.iter()
+					.map(|field| {
+						Ok(NamedField::from_parts(
+							self.string(field.name)?,
+							self.ty(field.ty)?,
+							field.group.map(|group| self.string(group)).transpose()?,
+							field.sensitivity,
+							field.display.map(|display| self.string(display)).transpose()?,
+							self.strings(&field.docs)?,
+						))
+					})
+					.collect::<Result<_, _>>()?,
+				discriminator
+					.as_ref()
+					.map(|discriminator| {
+						Ok(UnionDiscriminator::from_parts(
+							self.strings(&discriminator.field)?,
+							discriminator
+								.mapping
+								.iter()
+								.map(|mapping| {
+									Ok(UnionDiscriminatorMapping::from_parts(
+										match mapping.value {
+											nested::DiscriminantValue::UInt(value) => DiscriminantValue::UInt(value),
+											nested::DiscriminantValue::Int(value) => DiscriminantValue::Int(value),
+											nested::DiscriminantValue::Str(value) => {
+												DiscriminantValue::Str(self.string(value)?)
+											}
+										},
+										self.string(mapping.member)?,
+									))
+								})
+								.collect::<Result<_, _>>()?,
+						))
+					})
+					.transpose()?,
+			)
+			.into(),
+		})
+	}
+
+	/// Returns the resolved form of the given enum variant.
+	fn variant(&self, variant: &nested::EnumVariant) -> Result<Variant<Self::Form>, OwnedRegistryError> {
+		let (name, fields, code, discriminant, docs) = match variant {
+			nested::EnumVariant::Unit {
+				name,
+				code,
+				discriminant,
+				docs,
+			} => (name, new_items(), code, discriminant, docs),
+			nested::EnumVariant::Struct {
+				name,
+				fields,
+				code,
+				discriminant,
+				docs,
+			} => (name, self.fields(fields)?, code, discriminant, docs),
+			nested::EnumVariant::TupleStruct {
+				name,
+				types,
+				code,
+				discriminant,
+				docs,
+			} => (name, self.unnamed_fields(types)?, code, discriminant, docs),
+		};
+		Ok(Variant::from_parts(
+			self.string(*name)?,
+			fields,
+			*discriminant,
+			*code,
+			self.strings(docs)?,
+		))
+	}
+
+	/// Returns the resolved form of the given enum representation.
+	fn repr(&self, repr: &nested::EnumRepr) -> Result<EnumRepr<Self::Form>, OwnedRegistryError> {
+		Ok(match repr {
+			nested::EnumRepr::External => EnumRepr::External,
+			nested::EnumRepr::Internal { tag } => EnumRepr::Internal {
+				tag: self.string(*tag)?,
+			},
+			nested::EnumRepr::Adjacent { tag, content } => EnumRepr::Adjacent {
+				tag: self.string(*tag)?,
+				content: self.string(*content)?,
+			},
+			nested::EnumRepr::Untagged => EnumRepr::Untagged,
+		})
+	}
+
+	/// Returns the resolved form of the given named fields.
+	fn fields(&self, fields: &[nested::NamedField]) -> Result<Items<Field<Self::Form>>, OwnedRegistryError> {
+		fields
+			.iter()
+			.map(|field| {
+				Ok(Field::from_parts(
+					Some(self.string(field.name)?),
+					self.ty(field.ty)?,
+					field.group.map(|group| self.string(group)).transpose()?,
+					field.sensitivity,
+					field.display.map(|display| self.string(display)).transpose()?,
+					self.strings(&field.docs)?,
+				))
+			})
+			.collect()
+	}
+
+	/// Returns the resolved form of the unnamed fields of the given types.
+	fn unnamed_fields(&self, types: &[u32]) -> Result<Items<Field<Self::Form>>, OwnedRegistryError> {
+		types
+			.iter()
+			.map(|ty| {
+				Ok(Field::from_parts(
+					None,
+					self.ty(*ty)?,
+					None,
+					Sensitivity::Public,
+					None,
+					Vec::new(),
+				))
+			})
+			.collect()
+	}
+}
+
+/// Resolves the raw symbols of a registry in the nested layout into the owned form.
+struct OwnedSymbols<'a> {
+	/// The strings in order of their symbols.
+	strings: &'a [String],
+	/// The number of types.
+	types: usize,
+}
+
+impl Symbols for OwnedSymbols<'_> {
+	type Form = OwnedForm;
+
+	fn string(&self, symbol: u32) -> Result<String, OwnedRegistryError> {
+		(symbol as usize)
+			.checked_sub(1)
+			.and_then(|index| self.strings.get(index))
+			.cloned()
+			.ok_or(OwnedRegistryError::UnknownString(symbol))
+	}
+
+	fn ty(&self, symbol: u32) -> Result<UntrackedSymbol<AnyTypeId>, OwnedRegistryError> {
+		NonZeroU32::new(symbol)
+			.filter(|id| id.get() as usize <= self.types)
+			.map(UntrackedSymbol::from_id)
+			.ok_or(OwnedRegistryError::UnknownType(symbol))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HasTypeDef, HasTypeId, Metadata};
+
+	#[test]
+	fn owns_strings_of_registry() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<(u8, bool)>>::meta_type());
+		registry.alias_type("Maybe", <Option<(u8, bool)>>::meta_type());
+		let owned = OwnedRegistry::try_from(nested::Registry::from(&registry)).unwrap();
+		assert_eq!(owned.len(), registry.types().count());
+		assert_eq!(owned.alias("Maybe"), Some(option));
+		let (id, def) = owned.resolve(&option).unwrap();
+		match (id, def) {
+			(TypeId::Custom(custom), TypeDef::Variant(variant)) => {
+				assert_eq!(custom.name(), "Option");
+				let names = variant.variants().iter().map(Variant::name).collect::<Vec<_>>();
+				assert_eq!(names, vec!["None", "Some"]);
+			}
+			_ => panic!("unexpected type {:?}", id),
+		}
+		let symbols = owned.types().map(|(symbol, _, _)| symbol).collect::<Vec<_>>();
+		assert_eq!(symbols, registry.types().map(|(symbol, _)| symbol).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn reads_back_docs() {
+		struct Documented;
+
+		impl HasTypeId for Documented {
+			fn type_id() -> TypeId {
+				TypeIdCustom::new("Documented", Namespace::new(vec!["docs"]).unwrap(), vec![])
+					.with_docs(&["A documented type."])
+					.into()
+			}
+		}
+
+		impl HasTypeDef for Documented {
+			fn type_def() -> TypeDef {
+				TypeDefVariant::new(vec![Variant::new("Set")
+					.with_fields(vec![NamedField::of::<u8>("value").with_docs(&["The new value."])])
+					.with_docs(&["Sets the value."])])
+				.into()
+			}
+		}
+
+		let mut registry = Registry::new();
+		let symbol = registry.compact_type(&Documented::meta_type());
+		let owned = registry.try_into_owned().unwrap();
+		match owned.resolve(&symbol).unwrap() {
+			(TypeId::Custom(custom), TypeDef::Variant(def)) => {
+				assert_eq!(custom.docs(), ["A documented type."]);
+				assert_eq!(def.variants()[0].docs(), ["Sets the value."]);
+				assert_eq!(def.variants()[0].fields()[0].docs(), ["The new value."]);
+			}
+			(id, _) => panic!("unexpected type {:?}", id),
+		}
+	}
+
+	#[test]
+	fn reads_back_union_discriminators() {
+		struct Payload;
+
+		impl HasTypeId for Payload {
+			fn type_id() -> TypeId {
+				TypeIdCustom::new("Payload", Namespace::new(vec!["unions"]).unwrap(), vec![]).into()
+			}
+		}
+
+		impl HasTypeDef for Payload {
+			fn type_def() -> TypeDef {
+				TypeDefUnion::new(vec![NamedField::of::<u32>("int"), NamedField::of::<bool>("flag")])
+					.with_discriminator(UnionDiscriminator::new(vec!["kind"], vec![("i", "int"), ("f", "flag")]))
+					.unwrap()
+					.into()
+			}
+		}
+
+		let mut registry = Registry::new();
+		let symbol = registry.compact_type(&Payload::meta_type());
+		let owned = registry.try_into_owned().unwrap();
+		match owned.resolve(&symbol).unwrap() {
+			(_, TypeDef::Union(def)) => {
+				let mapping = def.discriminator().unwrap().mapping();
+				assert_eq!(*mapping[0].value(), DiscriminantValue::Str("i".to_string()));
+				assert_eq!(mapping[1].member(), "flag");
+			}
+			(id, _) => panic!("unexpected type {:?}", id),
+		}
+	}
+
+	#[test]
+	fn rejects_unknown_symbols() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<bool>>::meta_type());
+		let mut nested = nested::Registry::from(&registry);
+		nested.strings.pop();
+		assert_eq!(
+			OwnedRegistry::try_from(nested.clone()),
+			Err(OwnedRegistryError::UnknownString(3))
+		);
+		nested.types.pop();
+		nested.strings.push("Some".into());
+		assert_eq!(OwnedRegistry::try_from(nested), Err(OwnedRegistryError::UnknownType(2)));
+	}
+}
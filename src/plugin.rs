@@ -0,0 +1,352 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registration of types contributed by dynamically loaded plugins.
+//!
+//! Plugins built as `cdylib` cannot hand out `MetaType`s to their host since neither
+//! `core::any::TypeId` nor the layout of Rust types is stable across separately compiled
+//! libraries. Instead a plugin registers its types into a `RegistryBuilder` of its own and transfers
+//! it in the nested layout through the C ABI entry point defined by `export_plugin!`.
+//! The host calls that entry point with `Registry::register_plugin` which validates the
+//! transferred types and merges them into its registry, see `Registry::register_nested_registry`.
+//!
+//! # String ownership
+//!
+//! Buffers only ever cross the boundary borrowed for the duration of a call:
+//!
+//! - The plugin owns the serialized registry and frees it after the host has returned.
+//! - The host copies all strings it keeps. Since registries refer to `'static` strings the
+//!   copies are leaked and thus outlive the plugin, which may be unloaded at any time.
+//!   Strings already known to the host are not copied again.
+//!
+//! No allocation is ever freed on the other side of the boundary, so host and plugin may
+//! use different allocators.
+//!
+//! # Example
+//!
+//! The plugin exports its registration function:
+//!
+//! ```ignore
+//! fn register(builder: &mut RegistryBuilder) {
+//!     builder.register::<Config>();
+//! }
+//!
+//! type_metadata::export_plugin!(register);
+//! ```
+//!
+//! The host looks up the entry point, e.g. with the `libloading` crate, and calls it:
+//!
+//! ```ignore
+//! let library = libloading::Library::new("libplugin.so")?;
+//! let entry = library.get::<PluginEntry>(PLUGIN_ENTRY_SYMBOL)?;
+//! unsafe { registry.register_plugin(*entry)? };
+//! ```
+
+use crate::layout::Layout;
+use crate::tm_std::*;
+use crate::{RawTypeError, Registry, RegistryBuilder};
+use core::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The version of the plugin ABI.
+///
+/// Incremented upon every incompatible change of `PluginHost` or `PluginEntry`.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The null-terminated name of the entry point exported by `export_plugin!`.
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"type_metadata_plugin_entry\0";
+
+/// The entry point of a plugin.
+///
+/// Returns the status code of the registration, see `PluginError::status`.
+pub type PluginEntry = unsafe extern "C" fn(host: *const PluginHost) -> u32;
+
+/// The callback of the host that merges a registry serialized as JSON in the nested layout.
+type RegisterFn = unsafe extern "C" fn(context: *mut c_void, json: *const u8, len: usize) -> u32;
+
+/// An error upon registering the types of a plugin.
+#[derive(PartialEq, Eq, Debug)]
+pub enum PluginError {
+	/// The plugin was built against another version of the plugin ABI.
+	AbiMismatch,
+	/// The registry of the plugin cannot be serialized, e.g. since it has run out of symbols.
+	Unserializable,
+	/// The host rejected the transferred registry.
+	///
+	/// The cause is only known to the host, plugins observe `RawTypeError::Json` with an empty message.
+	Rejected(RawTypeError),
+	/// The registration function of the plugin panicked.
+	Panicked,
+	/// The plugin returned an unknown status code.
+	Unknown(u32),
+}
+
+impl PluginError {
+	/// Returns the status code of the given result of a registration.
+	pub fn status(result: &Result<(), PluginError>) -> u32 {
+		match result {
+			Ok(()) => 0,
+			Err(PluginError::AbiMismatch) => 1,
+			Err(PluginError::Unserializable) => 2,
+			Err(PluginError::Rejected(_)) => 3,
+			Err(PluginError::Panicked) => 4,
+			Err(PluginError::Unknown(status)) => *status,
+		}
+	}
+
+	/// Returns the result of a registration with the given status code.
+	pub fn from_status(status: u32) -> Result<(), PluginError> {
+		match status {
+			0 => Ok(()),
+			1 => Err(PluginError::AbiMismatch),
+			2 => Err(PluginError::Unserializable),
+			3 => Err(PluginError::Rejected(RawTypeError::Json(String::new()))),
+			4 => Err(PluginError::Panicked),
+			status => Err(PluginError::Unknown(status)),
+		}
+	}
+}
+
+impl Display for PluginError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			PluginError::AbiMismatch => write!(f, "the plugin was built against another plugin ABI version"),
+			PluginError::Unserializable => write!(f, "the registry of the plugin cannot be serialized"),
+			PluginError::Rejected(error) => write!(f, "the host rejected the types of the plugin: {}", error),
+			PluginError::Panicked => write!(f, "the plugin panicked"),
+			PluginError::Unknown(status) => write!(f, "unknown plugin status code {}", status),
+		}
+	}
+}
+
+impl std::error::Error for PluginError {}
+
+/// The host side of the plugin ABI passed to the entry point of a plugin.
+///
+/// Only valid for the duration of the call of the entry point.
+#[repr(C)]
+pub struct PluginHost {
+	/// The plugin ABI version of the host.
+	abi_version: u32,
+	/// The state of the host passed back to `register`.
+	context: *mut c_void,
+	/// Merges the given registry into the registry of the host.
+	register: RegisterFn,
+}
+
+impl PluginHost {
+	/// Returns the plugin ABI version of the host.
+	pub fn abi_version(&self) -> u32 {
+		self.abi_version
+	}
+
+	/// Transfers all types of the given registry to the host.
+	///
+	/// # Errors
+	///
+	/// - If the host uses another plugin ABI version.
+	/// - If the registry cannot be serialized.
+	/// - If the host rejected the types of the registry.
+	pub fn register(&self, registry: &Registry) -> Result<(), PluginError> {
+		if self.abi_version != PLUGIN_ABI_VERSION {
+			return Err(PluginError::AbiMismatch);
+		}
+		let json =
+			serde_json::to_vec(&registry.with_layout(Layout::Nested)).map_err(|_| PluginError::Unserializable)?;
+		// SAFETY: The host keeps its context alive for the duration of the entry point call
+		// and only borrows the buffer for the duration of this call.
+		PluginError::from_status(unsafe { (self.register)(self.context, json.as_ptr(), json.len()) })
+	}
+}
+
+/// The state of a host during the call of the entry point of a plugin.
+struct HostContext<'a> {
+	/// The registry the types of the plugin are merged into.
+	registry: &'a mut Registry,
+	/// The cause of the last rejected registration, if any.
+	error: Option<RawTypeError>,
+}
+
+/// Merges the registry serialized as JSON in the nested layout into the registry of the host.
+///
+/// # Safety
+///
+/// The context has to be a `HostContext` and the buffer has to be valid for `len` bytes.
+unsafe extern "C" fn register_json(context: *mut c_void, json: *const u8, len: usize) -> u32 {
+	let context = &mut *(context as *mut HostContext);
+	let json = core::slice::from_raw_parts(json, len);
+	let result = panic::catch_unwind(AssertUnwindSafe(|| {
+		let registry = serde_json::from_slice(json).map_err(|error| RawTypeError::Json(error.to_string()))?;
+		context.registry.register_nested_registry(&registry).map(drop)
+	}));
+	let result = match result {
+		Ok(Ok(())) => Ok(()),
+		Ok(Err(error)) => {
+			context.error = Some(error.clone());
+			Err(PluginError::Rejected(error))
+		}
+		Err(_) => Err(PluginError::Panicked),
+	};
+	PluginError::status(&result)
+}
+
+impl Registry {
+	/// Calls the given entry point of a plugin and merges the types it contributes.
+	///
+	/// # Errors
+	///
+	/// - If the plugin was built against another plugin ABI version.
+	/// - If the registry of the plugin cannot be serialized.
+	/// - If the types of the plugin are rejected, see `register_nested_registry`.
+	///   All types of the plugin transferred before are kept.
+	/// - If the plugin panicked.
+	///
+	/// # Safety
+	///
+	/// The entry point has to be the one defined by `export_plugin!` of a plugin that is
+	/// still loaded, e.g. the `PLUGIN_ENTRY_SYMBOL` of a dynamic library.
+	pub unsafe fn register_plugin(&mut self, entry: PluginEntry) -> Result<(), PluginError> {
+		let mut context = HostContext {
+			registry: self,
+			error: None,
+		};
+		let host = PluginHost {
+			abi_version: PLUGIN_ABI_VERSION,
+			context: &mut context as *mut HostContext as *mut c_void,
+			register: register_json,
+		};
+		let status = entry(&host);
+		match (PluginError::from_status(status), context.error) {
+			(Err(PluginError::Rejected(_)), Some(error)) => Err(PluginError::Rejected(error)),
+			(result, _) => result,
+		}
+	}
+}
+
+/// Runs the given registration function of a plugin and transfers its types to the host.
+///
+/// Used by `export_plugin!`, panics never unwind into the host.
+///
+/// # Safety
+///
+/// The host has to be the one passed to the entry point of the plugin.
+#[doc(hidden)]
+pub unsafe fn run_plugin(host: *const PluginHost, register: fn(&mut RegistryBuilder)) -> u32 {
+	let host = match host.as_ref() {
+		Some(host) => host,
+		None => return PluginError::status(&Err(PluginError::AbiMismatch)),
+	};
+	let registry = panic::catch_unwind(|| {
+		let mut builder = RegistryBuilder::new();
+		register(&mut builder);
+		builder.build()
+	});
+	let result = match registry {
+		Ok(registry) => panic::catch_unwind(AssertUnwindSafe(|| host.register(&registry)))
+			.unwrap_or(Err(PluginError::Panicked)),
+		Err(_) => Err(PluginError::Panicked),
+	};
+	PluginError::status(&result)
+}
+
+/// Defines the entry point of a plugin that registers its types by the given function.
+///
+/// The function has the signature `fn(&mut RegistryBuilder)` and the entry point is exported
+/// as `PLUGIN_ENTRY_SYMBOL`, so a plugin exports at most one entry point.
+///
+/// # Example
+///
+/// ```ignore
+/// fn register(builder: &mut RegistryBuilder) {
+///     builder.register::<Config>();
+/// }
+///
+/// type_metadata::export_plugin!(register);
+/// ```
+#[macro_export]
+macro_rules! export_plugin {
+	($register:path) => {
+		#[no_mangle]
+		pub unsafe extern "C" fn type_metadata_plugin_entry(host: *const $crate::plugin::PluginHost) -> u32 {
+			$crate::plugin::run_plugin(host, $register)
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compat::{diff, NamespaceEquivalence};
+	use crate::{Metadata, TypeDef, TypeId};
+
+	/// A list of bytes that refers to itself.
+	struct List;
+
+	impl crate::HasTypeId for List {
+		fn type_id() -> TypeId {
+			crate::TypeIdCustom::new("List", crate::Namespace::new(vec!["plugin"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for List {
+		fn type_def() -> TypeDef {
+			crate::TypeDefComposite::new(vec![
+				crate::NamedField::of::<u8>("head"),
+				crate::NamedField::of::<Option<Box<List>>>("tail"),
+			])
+			.into()
+		}
+	}
+
+	fn register(builder: &mut RegistryBuilder) {
+		builder.register::<(List, bool)>();
+	}
+
+	crate::export_plugin!(register);
+
+	#[test]
+	fn registers_types_of_plugins() {
+		let mut host = Registry::new();
+		let bool_ty = host.compact_type(&bool::meta_type());
+		unsafe { host.register_plugin(type_metadata_plugin_entry) }.unwrap();
+
+		let mut expected = RegistryBuilder::new();
+		register(&mut expected);
+		let expected = expected.build();
+		assert_eq!(diff(&expected, &host, &NamespaceEquivalence::new()), vec![]);
+		assert_eq!(host.types().count(), expected.types().count());
+		assert_eq!(host.compact_type(&bool::meta_type()), bool_ty);
+
+		let len = host.types().count();
+		unsafe { host.register_plugin(type_metadata_plugin_entry) }.unwrap();
+		assert_eq!(host.types().count(), len);
+	}
+
+	#[test]
+	fn rejects_panicking_plugins() {
+		fn register(_: &mut RegistryBuilder) {
+			panic!("plugin failure");
+		}
+
+		unsafe extern "C" fn entry(host: *const PluginHost) -> u32 {
+			run_plugin(host, register)
+		}
+
+		let mut host = Registry::new();
+		assert_eq!(unsafe { host.register_plugin(entry) }, Err(PluginError::Panicked));
+		assert_eq!(host, Registry::new());
+	}
+}
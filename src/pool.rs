@@ -0,0 +1,197 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A string pool that can be shared between registries.
+//!
+//! Processes that build many registries, e.g. one per module, would otherwise
+//! intern the same identifier strings again and again within every registry.
+//! Registries created with `Registry::with_string_pool` instead intern their
+//! strings into the given pool and thus also share their string symbols.
+//!
+//! By default the synchronization is done by `std::sync::RwLock`.
+//! With the `parking_lot` feature enabled its `RwLock` is used instead.
+
+use crate::interner::{CapacityError, Interner, UntrackedSymbol};
+use crate::tm_std::*;
+use std::sync::OnceLock;
+
+#[cfg(feature = "parking_lot")]
+use parking_lot::RwLock;
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{PoisonError, RwLock};
+
+/// A string interner with interior synchronization.
+///
+/// # Note
+///
+/// The string symbols of registries using the same pool refer to the same
+/// strings of the pool. A serialized registry only carries the strings it refers
+/// to though, with its own string symbols in order of their first use, and thus
+/// serializes the same no matter what other registries registered into the pool.
+#[derive(Debug, Default)]
+pub struct StringPool {
+	/// The synchronized interned strings.
+	strings: RwLock<Interner<&'static str>>,
+}
+
+impl StringPool {
+	/// Creates a new empty string pool.
+	pub fn new() -> Self {
+		Self {
+			strings: RwLock::new(Interner::new()),
+		}
+	}
+
+	/// Returns the process-global string pool.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Registry, StringPool};
+	/// let mut a = Registry::with_string_pool(StringPool::global());
+	/// let mut b = Registry::with_string_pool(StringPool::global());
+	/// assert_eq!(a.register_string("Hello"), b.register_string("Hello"));
+	/// ```
+	pub fn global() -> &'static StringPool {
+		static GLOBAL: OnceLock<StringPool> = OnceLock::new();
+		GLOBAL.get_or_init(StringPool::new)
+	}
+
+	/// Interns the given string into the pool and returns its associated string symbol.
+	///
+	/// # Panics
+	///
+	/// If the pool has run out of string symbols. Not available with the `no-panic` feature,
+	/// use `try_intern` instead.
+	#[cfg(not(feature = "no-panic"))]
+	pub fn intern(&self, string: &'static str) -> UntrackedSymbol<&'static str> {
+		self.try_intern(string).expect("string pools hold at most `u32::MAX` strings")
+	}
+
+	/// Interns the given string into the pool and returns its associated string symbol.
+	///
+	/// # Errors
+	///
+	/// If the string has not been interned yet and the pool has run out of string symbols.
+	pub fn try_intern(&self, string: &'static str) -> Result<UntrackedSymbol<&'static str>, CapacityError> {
+		self.try_intern_or_get(string).map(|(_, symbol)| symbol)
+	}
+
+	/// Interns the given string into the pool and returns its associated string symbol
+	/// along with `true` if the string has not been interned before.
+	pub(crate) fn try_intern_or_get(
+		&self,
+		string: &'static str,
+	) -> Result<(bool, UntrackedSymbol<&'static str>), CapacityError> {
+		if let Some(symbol) = self.read(|strings| strings.get(&string).map(|symbol| symbol.into_untracked())) {
+			return Ok((false, symbol));
+		}
+		self.write(|strings| {
+			strings
+				.try_intern_or_get(string)
+				.map(|(inserted, symbol)| (inserted, symbol.into_untracked()))
+		})
+	}
+
+	/// Returns the string associated with the given string symbol of this pool.
+	pub fn resolve(&self, symbol: UntrackedSymbol<&'static str>) -> Option<&'static str> {
+		self.read(|strings| strings.resolve_untracked(symbol).copied())
+	}
+
+	/// Returns the number of strings interned into the pool.
+	pub fn len(&self) -> usize {
+		self.read(|strings| strings.len())
+	}
+
+	/// Returns `true` if no strings have been interned into the pool.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns all interned strings in order of their string symbols.
+	#[cfg(feature = "serde")]
+	pub(crate) fn strings(&self) -> Vec<&'static str> {
+		self.read(|strings| strings.elements().to_vec())
+	}
+
+	/// Runs the given closure with shared read access to the interned strings.
+	fn read<F, R>(&self, f: F) -> R
+	where
+		F: FnOnce(&Interner<&'static str>) -> R,
+	{
+		#[cfg(feature = "parking_lot")]
+		let strings = self.strings.read();
+		#[cfg(not(feature = "parking_lot"))]
+		let strings = self.strings.read().unwrap_or_else(PoisonError::into_inner);
+		f(&strings)
+	}
+
+	/// Runs the given closure with exclusive write access to the interned strings.
+	fn write<F, R>(&self, f: F) -> R
+	where
+		F: FnOnce(&mut Interner<&'static str>) -> R,
+	{
+		#[cfg(feature = "parking_lot")]
+		let mut strings = self.strings.write();
+		#[cfg(not(feature = "parking_lot"))]
+		let mut strings = self.strings.write().unwrap_or_else(PoisonError::into_inner);
+		f(&mut strings)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Metadata, Registry};
+
+	#[test]
+	fn registries_share_string_symbols() {
+		let pool: &'static StringPool = Box::leak(Box::new(StringPool::new()));
+		let mut a = Registry::with_string_pool(pool);
+		let mut b = Registry::with_string_pool(pool);
+		a.compact_type(&<Option<u32>>::meta_type());
+		let len = pool.len();
+		b.compact_type(&<Option<u32>>::meta_type());
+		assert_eq!(pool.len(), len);
+		let symbol = b.compact_string("Option");
+		assert_eq!(a.compact_string("Option"), symbol);
+		assert_eq!(pool.resolve(symbol), Some("Option"));
+		// The registries still have their own type symbols.
+		assert_eq!(
+			a.iter_resolved().map(|(_, id, _)| id).collect::<Vec<_>>(),
+			b.iter_resolved().map(|(_, id, _)| id).collect::<Vec<_>>(),
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn registries_serialize_only_their_strings() {
+		use crate::layout::nested;
+
+		let pool: &'static StringPool = Box::leak(Box::new(StringPool::new()));
+		let mut a = Registry::with_string_pool(pool);
+		let mut b = Registry::with_string_pool(pool);
+		let mut local = Registry::new();
+		a.compact_type(&<Result<bool, ()>>::meta_type());
+		b.compact_type(&<Option<u32>>::meta_type());
+		b.alias_type("Maybe", <Option<u32>>::meta_type());
+		local.compact_type(&<Option<u32>>::meta_type());
+		local.alias_type("Maybe", <Option<u32>>::meta_type());
+		let nested = nested::Registry::from(&b);
+		assert_eq!(nested.strings, vec!["Option", "None", "Some", "Maybe"]);
+		assert_eq!(nested, nested::Registry::from(&local));
+	}
+}
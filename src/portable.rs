@@ -0,0 +1,162 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A frozen registry for consumers that only read metadata.
+//!
+//! A `Registry` interns its types in order to register them, which makes it awkward to
+//! consume. A `PortableRegistry` owns the strings and types of a registry in dense vectors
+//! indexed by their symbols, so that it resolves them in constant time and survives serde
+//! round-trips. It serializes exactly like `Registry::with_layout(Layout::Nested)`.
+//!
+//! This is not to be confused with the `PortableRegistry` of the `scale-info` crate, see
+//! `layout::portable` for conversions from and to it.
+
+use crate::layout::nested::{self, TypeIdDef};
+use crate::tm_std::*;
+use crate::{Capabilities, Registry};
+#[cfg(feature = "scale")]
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// A read-only registry resolving type and string symbols in constant time.
+///
+/// Symbols are the raw identifiers of the type and string symbols of the registry it has been
+/// created from, e.g. `symbol.id().get()` of a type symbol returned by `RegistryBuilder::register_type`.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{layout::nested::TypeId, Metadata, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// let symbol = builder.register_type(&<Option<u32>>::meta_type());
+/// let registry = builder.build();
+/// let portable = registry.into_portable();
+/// match &portable.resolve(symbol.id().get()).unwrap().id {
+///     TypeId::Custom { name, .. } => assert_eq!(portable.resolve_str(*name), Some("Option")),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[serde(transparent)]
+pub struct PortableRegistry {
+	/// The strings and types in order of their symbols.
+	registry: nested::Registry,
+}
+
+impl Registry {
+	/// Freezes the registry into a `PortableRegistry`.
+	///
+	/// Deferred types are completed beforehand so that all type symbols resolve.
+	pub fn into_portable(mut self) -> PortableRegistry {
+		self.complete_deferred();
+		PortableRegistry::from(nested::Registry::from(&self))
+	}
+}
+
+impl From<nested::Registry> for PortableRegistry {
+	fn from(registry: nested::Registry) -> Self {
+		Self { registry }
+	}
+}
+
+impl From<PortableRegistry> for nested::Registry {
+	fn from(registry: PortableRegistry) -> Self {
+		registry.registry
+	}
+}
+
+impl PortableRegistry {
+	/// Returns the capabilities of the producer of the registry.
+	pub fn capabilities(&self) -> Capabilities {
+		self.registry.capabilities
+	}
+
+	/// Returns the number of types of the registry.
+	pub fn len(&self) -> usize {
+		self.registry.types.len()
+	}
+
+	/// Returns `true` if the registry has no types.
+	pub fn is_empty(&self) -> bool {
+		self.registry.types.is_empty()
+	}
+
+	/// Returns the type identifier and definition of the given type symbol, if any.
+	pub fn resolve(&self, symbol: u32) -> Option<&TypeIdDef> {
+		let index = (symbol as usize).checked_sub(1)?;
+		self.registry.types.get(index)
+	}
+
+	/// Returns the string of the given string symbol, if any.
+	pub fn resolve_str(&self, symbol: u32) -> Option<&str> {
+		let index = (symbol as usize).checked_sub(1)?;
+		self.registry.strings.get(index).map(String::as_str)
+	}
+
+	/// Returns the type symbol aliased by the given name, if any.
+	pub fn alias(&self, name: &str) -> Option<u32> {
+		self.registry
+			.aliases
+			.iter()
+			.find(|alias| self.resolve_str(alias.name) == Some(name))
+			.map(|alias| alias.ty)
+	}
+
+	/// Returns an iterator over all types together with their type symbols in order of registration.
+	pub fn iter(&self) -> impl Iterator<Item = (u32, &TypeIdDef)> + '_ {
+		(1..).zip(&self.registry.types)
+	}
+
+	/// Returns the registry in the nested layout.
+	pub fn as_nested(&self) -> &nested::Registry {
+		&self.registry
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+
+	#[test]
+	fn resolves_in_registration_order() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<u8>>::meta_type());
+		let deferred = registry.defer_type(&bool::meta_type());
+		registry.alias_type("Flag", bool::meta_type());
+		let nested = nested::Registry::from(&{
+			let mut registry = Registry::new();
+			registry.compact_type(&<Option<u8>>::meta_type());
+			registry.compact_type(&bool::meta_type());
+			registry.alias_type("Flag", bool::meta_type());
+			registry
+		});
+
+		let portable = registry.into_portable();
+		assert_eq!(portable.as_nested(), &nested);
+		assert_eq!(portable.len(), 3);
+		assert_eq!(portable.resolve(option.id().get()), Some(&nested.types[0]));
+		assert_eq!(portable.resolve(deferred.id().get()), Some(&nested.types[2]));
+		assert_eq!(portable.resolve(0), None);
+		assert_eq!(portable.resolve(4), None);
+		assert_eq!(portable.alias("Flag"), Some(deferred.id().get()));
+		assert_eq!(
+			portable.iter().map(|(symbol, _)| symbol).collect::<Vec<_>>(),
+			vec![1, 2, 3]
+		);
+	}
+}
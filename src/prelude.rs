@@ -0,0 +1,44 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The stable surface of the crate for glob imports.
+//!
+//! ```
+//! use type_metadata::{prelude::*, RegistryBuilder};
+//!
+//! let mut builder = RegistryBuilder::new();
+//! register_types!(&mut builder, Option<u32>);
+//! assert_eq!(
+//!     <Option<u32>>::type_id(),
+//!     TypeIdCustom::new("Option", Namespace::prelude(), tuple_meta_type!(u32)).into()
+//! );
+//! ```
+//!
+//! # Stability
+//!
+//! The prelude only changes with major versions. Minor and patch versions neither add,
+//! remove nor rename any of its items, so that upgrading never makes the names of a glob
+//! import ambiguous with the ones of other crates. New items are added to the crate root
+//! first and join the prelude with the next major version.
+//!
+//! The items and the signatures of their commonly used functions are pinned by the
+//! `prelude` test of the test suite which may only be changed along with a major version.
+
+pub use crate::{
+	form::{CompactForm, Form, MetaForm},
+	register_types, tuple_meta_type, HasTypeDef, HasTypeId, IntoCompact, MetaType, Metadata, NamedField, Namespace,
+	Registry, TypeDef, TypeDefComposite, TypeDefVariant, TypeId, TypeIdCustom, TypeIdPrimitive, UnnamedField, Variant,
+};
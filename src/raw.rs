@@ -0,0 +1,749 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Injection of type definitions that do not originate from Rust types.
+//!
+//! Producers integrating non-Rust sources, e.g. schemas of other languages, describe their
+//! types in the nested layout, see `Registry::with_layout`, and inject them with
+//! `Registry::register_nested` or `Registry::register_raw`. The registry validates the
+//! definitions and assigns their type symbols just like for registered Rust types.
+//!
+//! Injected definitions refer to strings by their string symbols and to types by their type
+//! symbols in the registry, so the strings have to be registered by `register_string` first.
+//! A definition may refer to itself by the symbol returned by `Registry::next_raw_symbol`.
+
+use crate::form::CompactForm;
+use crate::interner::UntrackedSymbol;
+use crate::layout::nested;
+use crate::owned::Symbols;
+use crate::tm_std::*;
+use crate::{Builtin, OwnedRegistryError, Registry, TypeDef, TypeId, TypeIdDef};
+
+/// An error upon injecting a type definition into a registry.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum RawTypeError {
+	/// The type identifier or definition is malformed JSON or does not follow the nested layout.
+	Json(String),
+	/// A string symbol does not refer to any string of the registry.
+	UnknownString(u32),
+	/// A type symbol does not refer to any type of the registry.
+	UnknownType(u32),
+	/// Either a custom type identifier has a builtin definition or another identifier has not.
+	Mismatch,
+	/// The type with the given symbol has the identifier of an injected type but another definition.
+	Conflict(u32),
+	/// The registry has run out of type symbols.
+	CapacityExceeded,
+}
+
+impl From<OwnedRegistryError> for RawTypeError {
+	fn from(error: OwnedRegistryError) -> Self {
+		match error {
+			OwnedRegistryError::UnknownString(symbol) => RawTypeError::UnknownString(symbol),
+			OwnedRegistryError::UnknownType(symbol) => RawTypeError::UnknownType(symbol),
+		}
+	}
+}
+
+impl Display for RawTypeError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			RawTypeError::Json(error) => write!(f, "invalid raw type: {}", error),
+			RawTypeError::UnknownString(symbol) => write!(f, "unknown string symbol {}", symbol),
+			RawTypeError::UnknownType(symbol) => write!(f, "unknown type symbol {}", symbol),
+			RawTypeError::Mismatch => write!(f, "type identifier does not match its definition"),
+			RawTypeError::Conflict(symbol) => write!(f, "type symbol {} has another definition", symbol),
+			RawTypeError::CapacityExceeded => write!(f, "the registry has run out of type symbols"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RawTypeError {}
+
+/// The identifier and definition of a type in the compact form.
+pub(crate) type CompactType = (TypeId<CompactForm>, TypeDef<CompactForm>);
+
+/// Resolves the raw symbols of an injected type against the registry it is injected into.
+struct RegistrySymbols<'a> {
+	/// The registry the type is injected into.
+	registry: &'a Registry,
+	/// The type symbol the injected type is going to be assigned.
+	next: UntrackedSymbol<AnyTypeId>,
+}
+
+impl Symbols for RegistrySymbols<'_> {
+	type Form = CompactForm;
+
+	fn string(&self, symbol: u32) -> Result<UntrackedSymbol<&'static str>, OwnedRegistryError> {
+		NonZeroU32::new(symbol)
+			.map(UntrackedSymbol::from_id)
+			.filter(|symbol| self.registry.resolve_string(*symbol).is_some())
+			.ok_or(OwnedRegistryError::UnknownString(symbol))
+	}
+
+	fn ty(&self, symbol: u32) -> Result<UntrackedSymbol<AnyTypeId>, OwnedRegistryError> {
+		NonZeroU32::new(symbol)
+			.filter(|id| *id <= self.next.id())
+			.map(UntrackedSymbol::from_id)
+			.ok_or(OwnedRegistryError::UnknownType(symbol))
+	}
+}
+
+/// Resolves the raw symbols of the types of an injected registry against the registry
+/// they are injected into.
+struct MergedSymbols<'a> {
+	/// The string symbols the strings of the injected registry are mapped onto.
+	strings: &'a [UntrackedSymbol<&'static str>],
+	/// The type symbols the types of the injected registry are mapped onto by their indices, if already known.
+	types: &'a BTreeMap<usize, UntrackedSymbol<AnyTypeId>>,
+	/// The index of the injected type and the type symbol it is going to be assigned,
+	/// so that recursive types may refer to themselves.
+	this: Option<(usize, UntrackedSymbol<AnyTypeId>)>,
+}
+
+impl Symbols for MergedSymbols<'_> {
+	type Form = CompactForm;
+
+	fn string(&self, symbol: u32) -> Result<UntrackedSymbol<&'static str>, OwnedRegistryError> {
+		(symbol as usize)
+			.checked_sub(1)
+			.and_then(|index| self.strings.get(index))
+			.copied()
+			.ok_or(OwnedRegistryError::UnknownString(symbol))
+	}
+
+	fn ty(&self, symbol: u32) -> Result<UntrackedSymbol<AnyTypeId>, OwnedRegistryError> {
+		let index = (symbol as usize).checked_sub(1);
+		match self.this {
+			Some((this, next)) if index == Some(this) => Ok(next),
+			_ => index
+				.and_then(|index| self.types.get(&index).copied())
+				.ok_or(OwnedRegistryError::UnknownType(symbol)),
+		}
+	}
+}
+
+/// Resolves the given type identifier and definition in the nested layout and checks
+/// that the identifier matches the definition.
+fn checked_type<S>(
+	symbols: &S,
+	id: &nested::TypeId,
+	def: &nested::TypeDef,
+) -> Result<(TypeId<CompactForm>, TypeDef<CompactForm>), RawTypeError>
+where
+	S: Symbols<Form = CompactForm>,
+{
+	let id = symbols.type_id(id)?;
+	let def = symbols.type_def(def)?;
+	match (&id, &def) {
+		(TypeId::Custom(_), TypeDef::Builtin(Builtin::Builtin)) => Err(RawTypeError::Mismatch),
+		(TypeId::Custom(_), _) | (_, TypeDef::Builtin(Builtin::Builtin)) => Ok((id, def)),
+		_ => Err(RawTypeError::Mismatch),
+	}
+}
+
+/// Resolves the raw symbols of types that are compared by their structure.
+///
+/// Strings are mapped onto the given string symbols, if any, while type symbols are kept
+/// as they are or collapsed onto a single one in order to ignore the types referred to.
+struct ShapeSymbols<'a> {
+	/// The string symbols the raw string symbols are mapped onto, if any.
+	strings: Option<&'a [UntrackedSymbol<&'static str>]>,
+	/// Whether all type symbols are collapsed onto a single one.
+	collapse: bool,
+}
+
+impl Symbols for ShapeSymbols<'_> {
+	type Form = CompactForm;
+
+	fn string(&self, symbol: u32) -> Result<UntrackedSymbol<&'static str>, OwnedRegistryError> {
+		match self.strings {
+			Some(strings) => (symbol as usize)
+				.checked_sub(1)
+				.and_then(|index| strings.get(index))
+				.copied(),
+			None => NonZeroU32::new(symbol).map(UntrackedSymbol::from_id),
+		}
+		.ok_or(OwnedRegistryError::UnknownString(symbol))
+	}
+
+	fn ty(&self, symbol: u32) -> Result<UntrackedSymbol<AnyTypeId>, OwnedRegistryError> {
+		NonZeroU32::new(if self.collapse { 1 } else { symbol })
+			.map(UntrackedSymbol::from_id)
+			.ok_or(OwnedRegistryError::UnknownType(symbol))
+	}
+}
+
+/// A type of either registry that takes part in the search for bisimilar types.
+struct Node {
+	/// The identifier and definition of the type regardless of the types they refer to.
+	shape: (TypeId<CompactForm>, TypeDef<CompactForm>),
+	/// The indices of the nodes of all types referred to in order of their occurrence.
+	refs: Vec<usize>,
+}
+
+impl Node {
+	/// Returns the node of the given type of this registry, which is preceded by the
+	/// nodes of all types of this registry in order of their symbols.
+	fn existing(id_def: &TypeIdDef, symbols: &BTreeMap<UntrackedSymbol<AnyTypeId>, usize>) -> Option<Self> {
+		let nested = nested::TypeIdDef::from(id_def);
+		let shape = ShapeSymbols {
+			strings: None,
+			collapse: true,
+		};
+		let mut refs = Vec::new();
+		id_def.id().collect_type_refs(&mut refs);
+		id_def.def().collect_type_refs(&mut refs);
+		Some(Self {
+			shape: (shape.type_id(&nested.id).ok()?, shape.type_def(&nested.def).ok()?),
+			refs: refs
+				.iter()
+				.map(|symbol| symbols.get(symbol).copied())
+				.collect::<Option<_>>()?,
+		})
+	}
+
+	/// Returns the node of the given type of an injected registry, which is preceded by the
+	/// nodes of all types of this registry followed by the nodes of the injected types.
+	fn injected(
+		id_def: &nested::TypeIdDef,
+		strings: &[UntrackedSymbol<&'static str>],
+		existing: usize,
+		injected: usize,
+	) -> Option<Self> {
+		let shape = ShapeSymbols {
+			strings: Some(strings),
+			collapse: true,
+		};
+		let raw = ShapeSymbols {
+			strings: Some(strings),
+			collapse: false,
+		};
+		let mut refs = Vec::new();
+		raw.type_id(&id_def.id).ok()?.collect_type_refs(&mut refs);
+		raw.type_def(&id_def.def).ok()?.collect_type_refs(&mut refs);
+		Some(Self {
+			shape: (shape.type_id(&id_def.id).ok()?, shape.type_def(&id_def.def).ok()?),
+			refs: refs
+				.iter()
+				.map(|symbol| {
+					let index = (symbol.id().get() as usize).checked_sub(1)?;
+					Some(existing.checked_add(index)?).filter(|_| index < injected)
+				})
+				.collect::<Option<_>>()?,
+		})
+	}
+}
+
+/// Maps the types of the injected registry onto the bisimilar types of the given registry by their indices.
+///
+/// Two types are bisimilar if they have the same identifier and definition up to the types they refer
+/// to, which are bisimilar in turn. Unlike comparing identifiers and definitions, this also maps types
+/// referring to each other as well as duplicates, e.g. of `List` and `Box<List>`, onto existing types.
+/// Malformed injected types are never mapped.
+fn bisimilar_types(
+	registry: &Registry,
+	injected: &nested::Registry,
+	strings: &[UntrackedSymbol<&'static str>],
+) -> BTreeMap<usize, UntrackedSymbol<AnyTypeId>> {
+	let symbols = registry
+		.types()
+		.enumerate()
+		.map(|(node, (symbol, _))| (symbol, node))
+		.collect::<BTreeMap<_, _>>();
+	let existing = symbols.len();
+	let nodes = registry
+		.types()
+		.map(|(_, id_def)| Node::existing(id_def, &symbols))
+		.chain(
+			injected
+				.types
+				.iter()
+				.map(|id_def| Node::injected(id_def, strings, existing, injected.types.len())),
+		)
+		.collect::<Vec<_>>();
+	// Partitions the nodes by their shapes first, malformed nodes are never merged.
+	let mut shapes: Vec<&(TypeId<CompactForm>, TypeDef<CompactForm>)> = Vec::new();
+	let mut classes = nodes
+		.iter()
+		.enumerate()
+		.map(|(index, node)| match node {
+			Some(node) => shapes
+				.iter()
+				.position(|shape| **shape == node.shape)
+				.unwrap_or_else(|| {
+					shapes.push(&node.shape);
+					shapes.len() - 1
+				}),
+			None => nodes.len() + index,
+		})
+		.collect::<Vec<_>>();
+	// Refines the partition by the classes of the referred types until it is stable.
+	let mut len = 0;
+	loop {
+		let mut signatures = BTreeMap::new();
+		let refined = nodes
+			.iter()
+			.zip(&classes)
+			.map(|(node, class)| {
+				let refs = node
+					.iter()
+					.flat_map(|node| node.refs.iter().map(|node| classes.get(*node).copied()));
+				let next = signatures.len();
+				*signatures.entry((*class, refs.collect::<Vec<_>>())).or_insert(next)
+			})
+			.collect::<Vec<_>>();
+		classes = refined;
+		if signatures.len() == len {
+			break;
+		}
+		len = signatures.len();
+	}
+	let mut representatives = BTreeMap::new();
+	for ((symbol, _), class) in registry.types().zip(&classes) {
+		representatives.entry(*class).or_insert(symbol);
+	}
+	classes
+		.iter()
+		.skip(existing)
+		.enumerate()
+		.filter_map(|(index, class)| Some((index, *representatives.get(class)?)))
+		.collect()
+}
+
+/// Returns the identifier and definition of the given type with its definition normalized
+/// the way the definitions of injected types are.
+pub(crate) fn normalized_type(id_def: &TypeIdDef) -> Option<CompactType> {
+	let nested = nested::TypeIdDef::from(id_def);
+	let symbols = ShapeSymbols {
+		strings: None,
+		collapse: false,
+	};
+	Some((symbols.type_id(&nested.id).ok()?, symbols.type_def(&nested.def).ok()?))
+}
+
+/// Returns the identifiers and definitions of the types of the given registry in the nested layout
+/// referring to the given string symbols, with the type symbols kept as they are.
+///
+/// Definitions in the legacy forms are normalized, see `Registry::register_nested`.
+pub(crate) fn preserved_types(
+	registry: &nested::Registry,
+	strings: &[UntrackedSymbol<&'static str>],
+) -> Result<Vec<CompactType>, RawTypeError> {
+	let types = (0..registry.types.len())
+		.zip((1..).filter_map(NonZeroU32::new).map(UntrackedSymbol::from_id))
+		.collect();
+	let symbols = MergedSymbols {
+		strings,
+		types: &types,
+		this: None,
+	};
+	registry
+		.types
+		.iter()
+		.map(|id_def| checked_type(&symbols, &id_def.id, &id_def.def))
+		.collect()
+}
+
+impl Registry {
+	/// Returns the type symbol the next injected type is going to be assigned.
+	///
+	/// Recursive types refer to themselves by this symbol.
+	/// Returns `None` if the registry has run out of type symbols.
+	pub fn next_raw_symbol(&self) -> Option<UntrackedSymbol<AnyTypeId>> {
+		self.next_type_symbol()
+	}
+
+	/// Injects the given type identifier and definition in the nested layout and returns
+	/// the type symbol assigned to them.
+	///
+	/// Returns the existing type symbol if the registry already has a type with the same
+	/// identifier and definition. Definitions in the legacy forms are normalized into
+	/// `TypeDefComposite` and `TypeDefVariant`.
+	///
+	/// # Errors
+	///
+	/// - If a string or type symbol does not refer to any string or type of the registry.
+	/// - If a custom type identifier has a builtin definition or another identifier has not.
+	/// - If the registry has run out of type symbols.
+	///
+	/// In these cases the registry is left unchanged.
+	pub fn register_nested(
+		&mut self,
+		id: &nested::TypeId,
+		def: &nested::TypeDef,
+	) -> Result<UntrackedSymbol<AnyTypeId>, RawTypeError> {
+		let symbols = RegistrySymbols {
+			registry: self,
+			next: self.next_type_symbol().ok_or(RawTypeError::CapacityExceeded)?,
+		};
+		let (id, def) = checked_type(&symbols, id, def)?;
+		let existing = self
+			.types()
+			.find(|(_, id_def)| id_def.id() == &id && id_def.def() == &def)
+			.map(|(symbol, _)| symbol);
+		Ok(existing.unwrap_or_else(|| self.insert_raw_type(id, def)))
+	}
+
+	/// Injects all types of the given registry in the nested layout and returns the type
+	/// symbols assigned to them in order of their symbols within the given registry.
+	///
+	/// Unlike `register_nested` the types refer to the strings and types of the given
+	/// registry which are mapped onto the strings and types of this registry. Strings
+	/// unknown to this registry are copied and leaked since registries refer to `'static`
+	/// strings. Types equal to existing types are mapped onto them, where types referring to
+	/// each other are compared by their structure, so injecting a registry twice leaves the
+	/// registry unchanged. Other types referring to each other are mapped onto existing types
+	/// by their identifiers. Aliases are not injected.
+	///
+	/// # Errors
+	///
+	/// - If a string or type symbol does not refer to any string or type of the given registry.
+	/// - If a custom type identifier has a builtin definition or another identifier has not.
+	/// - If a type referring to other types that refer back to it has the identifier of an
+	///   existing type but another definition.
+	/// - If the registry has run out of string or type symbols.
+	///
+	/// In these cases the registry is left unchanged.
+	pub fn register_nested_registry(
+		&mut self,
+		registry: &nested::Registry,
+	) -> Result<Vec<UntrackedSymbol<AnyTypeId>>, RawTypeError> {
+		let mut extended = self.branch();
+		let strings = extended.register_nested_strings(&registry.strings);
+		let mut types = bisimilar_types(&extended, registry, &strings);
+		// The types mapped onto existing types by their identifiers only.
+		let mut assumed = Vec::new();
+		let mut pending = registry
+			.types
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| !types.contains_key(index))
+			.collect::<Vec<_>>();
+		while !pending.is_empty() {
+			let len = pending.len();
+			let mut unresolved = Vec::new();
+			for (index, id_def) in pending {
+				let next = extended.next_type_symbol().ok_or(RawTypeError::CapacityExceeded)?;
+				let symbols = MergedSymbols {
+					strings: &strings,
+					types: &types,
+					this: Some((index, next)),
+				};
+				match checked_type(&symbols, &id_def.id, &id_def.def) {
+					Ok((id, def)) => {
+						let existing = extended
+							.types()
+							.find(|(_, id_def)| id_def.id() == &id && id_def.def() == &def)
+							.map(|(symbol, _)| symbol);
+						let symbol = existing.unwrap_or_else(|| extended.insert_raw_type(id, def));
+						types.insert(index, symbol);
+					}
+					Err(_) => unresolved.push((index, id_def)),
+				}
+			}
+			if unresolved.len() == len {
+				// The remaining types refer to each other, so they are mapped onto existing
+				// types by their identifiers and their definitions are compared afterwards.
+				for (index, id_def) in &unresolved {
+					let symbols = MergedSymbols {
+						strings: &strings,
+						types: &types,
+						this: None,
+					};
+					let existing = symbols.type_id(&id_def.id).ok().and_then(|id| {
+						extended
+							.types()
+							.find(|(_, id_def)| id_def.id() == &id)
+							.map(|(symbol, _)| symbol)
+					});
+					if let Some(existing) = existing {
+						types.insert(*index, existing);
+						assumed.push((*index, *id_def));
+					}
+				}
+				unresolved.retain(|(index, _)| !types.contains_key(index));
+			}
+			if unresolved.len() == len {
+				// Otherwise they are assigned consecutive type symbols upfront
+				// which are allocated by inserting them in order.
+				let first = extended.next_type_symbol().ok_or(RawTypeError::CapacityExceeded)?;
+				for (offset, (index, _)) in unresolved.iter().enumerate() {
+					let offset: Option<u32> = TryFrom::try_from(offset).ok();
+					let symbol = offset
+						.and_then(|offset| first.id().get().checked_add(offset))
+						.and_then(NonZeroU32::new)
+						.ok_or(RawTypeError::CapacityExceeded)?;
+					types.insert(*index, UntrackedSymbol::from_id(symbol));
+				}
+				for (_, id_def) in unresolved.drain(..) {
+					let symbols = MergedSymbols {
+						strings: &strings,
+						types: &types,
+						this: None,
+					};
+					let (id, def) = checked_type(&symbols, &id_def.id, &id_def.def)?;
+					extended.insert_raw_type(id, def);
+				}
+			}
+			pending = unresolved;
+		}
+		for (index, id_def) in assumed {
+			let symbols = MergedSymbols {
+				strings: &strings,
+				types: &types,
+				this: None,
+			};
+			let (_, def) = checked_type(&symbols, &id_def.id, &id_def.def)?;
+			let symbol = match types.get(&index) {
+				Some(symbol) => *symbol,
+				None => continue,
+			};
+			if extended.resolve_type(symbol).map(|id_def| id_def.def()) != Some(&def) {
+				return Err(RawTypeError::Conflict(symbol.id().get()));
+			}
+		}
+		if extended.is_exhausted() {
+			return Err(RawTypeError::CapacityExceeded);
+		}
+		extended.recording = self.recording.take();
+		*self = extended;
+		Ok(types.into_values().collect())
+	}
+
+	/// Registers the given strings of a registry in the nested layout and returns their string symbols.
+	///
+	/// Strings unknown to this registry are copied and leaked since registries refer to `'static` strings.
+	pub(crate) fn register_nested_strings(&mut self, strings: &[String]) -> Vec<UntrackedSymbol<&'static str>> {
+		let known = self
+			.strings()
+			.into_iter()
+			.map(|string| (string, string))
+			.collect::<BTreeMap<&str, &'static str>>();
+		strings
+			.iter()
+			.map(|string| {
+				let string = match known.get(string.as_str()) {
+					Some(known) => *known,
+					None => Box::leak(string.clone().into_boxed_str()),
+				};
+				self.compact_string(string)
+			})
+			.collect()
+	}
+
+	/// Injects the given JSON type identifier and definition in the nested layout and
+	/// returns the type symbol assigned to them.
+	///
+	/// See `register_nested` for details.
+	///
+	/// # Errors
+	///
+	/// - If the JSON is malformed or does not follow the nested layout.
+	/// - If the identifier or definition is rejected by `register_nested`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder, TypeDef};
+	/// let mut builder = RegistryBuilder::new();
+	/// let u32_ty = builder.register_type(&u32::meta_type());
+	/// let name = builder.register_string("Point");
+	/// let x = builder.register_string("x");
+	/// let y = builder.register_string("y");
+	/// let mut registry = builder.build();
+	///
+	/// let id = format!(r#"{{"custom": {{"name": {}, "namespace": [], "params": []}}}}"#, name.id());
+	/// let def = format!(
+	///     r#"{{"struct": {{"fields": [{{"name": {}, "type": {ty}}}, {{"name": {}, "type": {ty}}}]}}}}"#,
+	///     x.id(),
+	///     y.id(),
+	///     ty = u32_ty.id(),
+	/// );
+	/// let point = registry.register_raw(&id, &def).unwrap();
+	/// assert!(matches!(registry.resolve(&point).unwrap().def(), TypeDef::Composite(_)));
+	/// assert_eq!(registry.register_raw(&id, &def), Ok(point));
+	/// ```
+	#[cfg(feature = "serde_json")]
+	pub fn register_raw(&mut self, id_json: &str, def_json: &str) -> Result<UntrackedSymbol<AnyTypeId>, RawTypeError> {
+		let id = serde_json::from_str(id_json).map_err(|error| RawTypeError::Json(error.to_string()))?;
+		let def = serde_json::from_str(def_json).map_err(|error| RawTypeError::Json(error.to_string()))?;
+		self.register_nested(&id, &def)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ExtendError, Metadata};
+
+	#[test]
+	fn injects_recursive_types() {
+		let mut registry = Registry::new();
+		let u8_ty = registry.compact_type(&u8::meta_type());
+		let name = registry.compact_string("List");
+		let head = registry.compact_string("head");
+		let tail = registry.compact_string("tail");
+
+		let next = registry.next_raw_symbol().unwrap();
+		let id = nested::TypeId::Custom {
+			name: name.id().get(),
+			namespace: vec![],
+			params: vec![],
+			consts: vec![],
+			id: None,
+			docs: vec![],
+		};
+		let def = nested::TypeDef::Struct {
+			fields: vec![
+				nested::NamedField {
+					name: head.id().get(),
+					ty: u8_ty.id().get(),
+					group: None,
+					sensitivity: Default::default(),
+					display: None,
+					docs: vec![],
+				},
+				nested::NamedField {
+					name: tail.id().get(),
+					ty: next.id().get() + 1,
+					group: None,
+					sensitivity: Default::default(),
+					display: None,
+					docs: vec![],
+				},
+			],
+		};
+		let unknown = next.id().get() + 1;
+		assert_eq!(
+			registry.register_nested(&id, &def),
+			Err(RawTypeError::UnknownType(unknown))
+		);
+		assert_eq!(registry.next_raw_symbol(), Some(next));
+
+		let def = match def {
+			nested::TypeDef::Struct { mut fields } => {
+				fields[1].ty = next.id().get();
+				nested::TypeDef::Struct { fields }
+			}
+			_ => unreachable!(),
+		};
+		let list = registry.register_nested(&id, &def).unwrap();
+		assert_eq!(list, next);
+		assert!(matches!(
+			registry.resolve(&list).unwrap().def(),
+			TypeDef::Composite(composite) if composite.fields()[1].ty() == &list
+		));
+		assert_eq!(registry.register_nested(&id, &def), Ok(list));
+		assert_eq!(registry.compact_type(&u8::meta_type()), u8_ty);
+	}
+
+	/// A list of bytes that refers to itself.
+	struct List;
+
+	impl crate::HasTypeId for List {
+		fn type_id() -> TypeId {
+			crate::TypeIdCustom::new("List", crate::Namespace::new(vec!["raw"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl crate::HasTypeDef for List {
+		fn type_def() -> TypeDef {
+			crate::TypeDefComposite::new(vec![
+				crate::NamedField::of::<u8>("head"),
+				crate::NamedField::of::<Option<Box<List>>>("tail"),
+			])
+			.into()
+		}
+	}
+
+	#[test]
+	fn injects_registries_of_recursive_types_once() {
+		let mut injected = Registry::new();
+		injected.compact_type(&<(List, bool)>::meta_type());
+		let injected = nested::Registry::from(&injected);
+
+		let mut registry = Registry::new();
+		let symbols = registry.register_nested_registry(&injected).unwrap();
+		let extended = registry.clone();
+		let again = registry.register_nested_registry(&injected).unwrap();
+		assert_eq!(registry, extended);
+		// Duplicates, e.g. of `List` from `Box<List>`, may be mapped onto each other.
+		let ids = |symbols: Vec<UntrackedSymbol<AnyTypeId>>| {
+			symbols
+				.into_iter()
+				.map(|symbol| registry.resolve_type(symbol).map(|id_def| id_def.id().clone()))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(ids(again), ids(symbols));
+
+		let mut native = Registry::new();
+		native.compact_type(&<(List, bool)>::meta_type());
+		let len = native.types().count();
+		native.register_nested_registry(&injected).unwrap();
+		assert_eq!(native.types().count(), len);
+	}
+
+	#[test]
+	fn rejects_invalid_types() {
+		let mut registry = Registry::new();
+		let name = registry.compact_string("Unit");
+		let id = nested::TypeId::Custom {
+			name: name.id().get(),
+			namespace: vec![42],
+			params: vec![],
+			consts: vec![],
+			id: None,
+			docs: vec![],
+		};
+		let unit = nested::TypeDef::TupleStruct { types: vec![] };
+		assert_eq!(
+			registry.register_nested(&id, &unit),
+			Err(RawTypeError::UnknownString(42))
+		);
+
+		let id = nested::TypeId::Custom {
+			name: name.id().get(),
+			namespace: vec![],
+			params: vec![],
+			consts: vec![],
+			id: None,
+			docs: vec![],
+		};
+		assert_eq!(
+			registry.register_nested(&id, &nested::TypeDef::Builtin),
+			Err(RawTypeError::Mismatch)
+		);
+		let tuple = nested::TypeId::Tuple(vec![]);
+		assert_eq!(registry.register_nested(&tuple, &unit), Err(RawTypeError::Mismatch));
+		assert_eq!(registry, {
+			let mut empty = Registry::new();
+			empty.compact_string("Unit");
+			empty
+		});
+
+		let unit = registry.register_nested(&id, &unit).unwrap();
+		assert!(matches!(
+			registry.resolve(&unit).unwrap().def(),
+			TypeDef::Composite(composite) if composite.fields().is_empty()
+		));
+		assert_eq!(
+			registry.extend_preserving(&nested::Registry::from(&Registry::new())),
+			Err(ExtendError::RawType { symbol: unit })
+		);
+	}
+}
@@ -0,0 +1,176 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recording of the order in which types are registered.
+//!
+//! Type symbols are handed out in registration order, so two builds registering the same
+//! types in different orders end up with different symbols. A registry in recording mode
+//! logs every newly registered type together with a caller-provided tag, e.g. the name of
+//! the module that registered it, so that the logs of both builds can be compared.
+
+use crate::tm_std::*;
+use crate::{interner::UntrackedSymbol, Registry};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// A type registered while the registry was recording.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Registration {
+	/// The tag that was active upon registration, if any.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	tag: Option<&'static str>,
+	/// The name of the registered type.
+	name: &'static str,
+	/// The type symbol assigned to the registered type.
+	symbol: UntrackedSymbol<core::any::TypeId>,
+}
+
+impl Registration {
+	/// Returns the tag that was active upon registration, if any.
+	pub fn tag(&self) -> Option<&'static str> {
+		self.tag
+	}
+
+	/// Returns the name of the registered type as given by `core::any::type_name`.
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// Returns the type symbol assigned to the registered type.
+	pub fn symbol(&self) -> UntrackedSymbol<core::any::TypeId> {
+		self.symbol
+	}
+}
+
+/// The registrations recorded by a registry so far.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub(crate) struct Recording {
+	/// The currently active tag, if any.
+	tag: Option<&'static str>,
+	/// The recorded registrations in order of registration.
+	registrations: Vec<Registration>,
+}
+
+impl Recording {
+	/// Records the registration of a type under the currently active tag.
+	pub(crate) fn record(&mut self, name: &'static str, symbol: UntrackedSymbol<core::any::TypeId>) {
+		self.registrations.push(Registration {
+			tag: self.tag,
+			name,
+			symbol,
+		})
+	}
+
+	/// Returns the number of recorded registrations.
+	pub(crate) fn len(&self) -> usize {
+		self.registrations.len()
+	}
+
+	/// Discards all but the first `len` recorded registrations.
+	pub(crate) fn truncate(&mut self, len: usize) {
+		self.registrations.truncate(len)
+	}
+}
+
+impl Registry {
+	/// Starts recording every subsequently registered type, discarding previous recordings.
+	///
+	/// Only types registered for the first time are recorded, including sub-types
+	/// that are registered recursively.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::RegistryBuilder;
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.start_recording();
+	/// builder.tagged("protocol", |builder| builder.register::<Option<u32>>());
+	/// let registry = builder.build();
+	/// let names = registry.recorded().iter().map(|r| (r.tag(), r.name())).collect::<Vec<_>>();
+	/// assert_eq!(
+	///     names,
+	///     vec![
+	///         (Some("protocol"), "core::option::Option<u32>"),
+	///         (Some("protocol"), "u32"),
+	///     ]
+	/// );
+	/// ```
+	pub fn start_recording(&mut self) {
+		self.recording = Some(Recording::default());
+	}
+
+	/// Stops recording and returns the recorded registrations in order of registration.
+	///
+	/// The registrations serialize to a list of objects with the `tag`, `name` and
+	/// `symbol` of the registered types which may be exported, e.g. as JSON.
+	pub fn stop_recording(&mut self) -> Vec<Registration> {
+		self.recording
+			.take()
+			.map(|recording| recording.registrations)
+			.unwrap_or_default()
+	}
+
+	/// Returns the registrations recorded so far in order of registration.
+	///
+	/// This is empty unless the registry is recording.
+	pub fn recorded(&self) -> &[Registration] {
+		self.recording
+			.as_ref()
+			.map(|recording| &recording.registrations[..])
+			.unwrap_or_default()
+	}
+
+	/// Replaces the currently active tag and returns the previous one.
+	///
+	/// This has no effect unless the registry is recording.
+	pub(crate) fn swap_tag(&mut self, tag: Option<&'static str>) -> Option<&'static str> {
+		self.recording
+			.as_mut()
+			.and_then(|recording| core::mem::replace(&mut recording.tag, tag))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::RegistryBuilder;
+
+	#[test]
+	fn records_registration_order() {
+		let mut builder = RegistryBuilder::new();
+		builder.register::<u8>();
+		builder.start_recording();
+		builder.tagged("outer", |builder| {
+			builder.register::<Option<u8>>();
+			builder.tagged("inner", |builder| builder.register::<bool>());
+			builder.register::<[bool; 2]>();
+		});
+		builder.register::<u16>();
+		let mut registry = builder.build();
+		let recorded = registry
+			.recorded()
+			.iter()
+			.map(|registration| (registration.tag(), registration.symbol().id().get()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			recorded,
+			vec![(Some("outer"), 2), (Some("inner"), 3), (Some("outer"), 4), (None, 5)]
+		);
+		assert_eq!(registry.stop_recording().len(), 4);
+		assert!(registry.recorded().is_empty());
+	}
+}
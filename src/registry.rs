@@ -31,14 +31,38 @@
 //! In general namespaces are ordered sequences of symbols and thus also profit from
 //! string deduplication.
 
+#[cfg(feature = "serde")]
+use crate::layout::{Layout, LayoutRegistry};
+#[cfg(all(feature = "serde", feature = "std"))]
+use crate::owned::{OwnedRegistryError, Symbols};
+#[cfg(feature = "std")]
+use crate::pool::StringPool;
 use crate::tm_std::*;
 use crate::{
-	form::CompactForm,
-	interner::{Interner, UntrackedSymbol},
+	arena::DefArena,
+	form::{CompactForm, ResolvedForm},
+	interner::{Interner, Symbol, UntrackedSymbol},
 	meta_type::MetaType,
-	TypeDef, TypeId,
+	recording::Recording,
+	telemetry::Hooks,
+	Builtin, Metadata, RegistryBuilder, TypeDef, TypeId,
+};
+#[cfg(feature = "serde")]
+use crate::{
+	interner::symbol_id,
+	layout::nested,
+	raw::{normalized_type, preserved_types, RawTypeError},
+	schema::{type_ref, TypeRef},
+};
+#[cfg(feature = "serde")]
+use core::cell::Cell;
+#[cfg(all(feature = "serde", feature = "std"))]
+use core::cell::RefCell;
+#[cfg(feature = "serde")]
+use serde::{
+	ser::{Error as _, SerializeStruct, Serializer},
+	Serialize,
 };
-use serde::Serialize;
 
 /// Compacts the implementor using a registry.
 pub trait IntoCompact {
@@ -49,15 +73,82 @@ pub trait IntoCompact {
 	fn into_compact(self, registry: &mut Registry) -> Self::Output;
 }
 
+/// Implemented by subsystems that register all of their types at once.
+///
+/// Frameworks call the registration hook of each of their subsystems
+/// instead of registering the types of all subsystems individually.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{register_types, RegistryBuilder, RegisterSubsystem};
+/// struct Storage;
+///
+/// impl RegisterSubsystem for Storage {
+///     fn register_all(builder: &mut RegistryBuilder) {
+///         register_types!(builder, u64, Option<Vec<u8>>);
+///     }
+/// }
+///
+/// let mut builder = RegistryBuilder::new();
+/// builder.register_subsystem::<Storage>();
+/// ```
+pub trait RegisterSubsystem {
+	/// Registers all types of the subsystem into the given registry builder.
+	fn register_all(builder: &mut RegistryBuilder);
+}
+
+/// Resolves the string symbols of the implementor using the registry that compacted it.
+pub(crate) trait Resolve {
+	/// The resolved version of `Self`.
+	type Output;
+
+	/// Resolves all string symbols of `self` using the registry.
+	fn resolve(&self, registry: &Registry) -> Self::Output;
+}
+
 /// The pair of associated type identifier and structure.
 ///
 /// This exists only as compactified version and is part of the registry.
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TypeIdDef {
 	/// The identifier of the type.
 	id: TypeId<CompactForm>,
 	/// The definition (aka internal structure) of the type.
-	def: TypeDef<CompactForm>,
+	///
+	/// Shared with all types of the registry that have the same definition.
+	def: Arc<TypeDef<CompactForm>>,
+}
+
+impl TypeIdDef {
+	/// Returns the compact identifier of the type.
+	pub fn id(&self) -> &TypeId<CompactForm> {
+		&self.id
+	}
+
+	/// Returns the compact definition of the type.
+	pub fn def(&self) -> &TypeDef<CompactForm> {
+		&self.def
+	}
+
+	/// Returns the symbols of all types directly referred to by the identifier
+	/// or definition of the type in order of their first occurrence.
+	pub(crate) fn type_refs(&self) -> Vec<UntrackedSymbol<AnyTypeId>> {
+		let mut refs = Vec::new();
+		self.id.collect_type_refs(&mut refs);
+		self.def.collect_type_refs(&mut refs);
+		let mut seen = Vec::with_capacity(refs.len());
+		refs.retain(|symbol| {
+			if seen.contains(symbol) {
+				false
+			} else {
+				seen.push(*symbol);
+				true
+			}
+		});
+		refs
+	}
 }
 
 /// The registry for compaction of type identifiers and definitions.
@@ -73,35 +164,427 @@ pub struct TypeIdDef {
 ///
 /// A type can be a sub-type of itself. In this case the registry has a builtin
 /// mechanism to stop recursion before going into an infinite loop.
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone)]
 pub struct Registry {
 	/// The cache for already registered strings.
-	#[serde(rename = "strings")]
-	string_table: Interner<&'static str>,
+	string_table: StringTable,
 	/// The cache for already registered types.
 	///
 	/// This is just an accessor to the actual database
 	/// for all types found in the `types` field.
-	#[serde(skip)]
-	type_table: Interner<AnyTypeId>,
+	///
+	/// Shared with branches of the registry until either side registers a type.
+	type_table: Arc<Interner<TypeKey>>,
+	/// The meta types of all registered types in order of their type symbols.
+	///
+	/// Types injected by `register_nested` have no meta type.
+	meta_types: Arc<Vec<Option<MetaType>>>,
 	/// The database where registered types actually reside.
 	///
 	/// This is going to be serialized upon serlialization.
-	#[serde(serialize_with = "serialize_registry_types")]
-	types: BTreeMap<UntrackedSymbol<core::any::TypeId>, TypeIdDef>,
+	types: Arc<BTreeMap<UntrackedSymbol<core::any::TypeId>, TypeIdDef>>,
+	/// The distinct definitions of the registered types.
+	///
+	/// Shared with branches of the registry until either side registers a type.
+	defs: Arc<DefArena>,
+	/// The number of type symbols reserved for well-known types.
+	///
+	/// These types occupy the type symbols `1..=reserved`.
+	reserved: usize,
+	/// Domain-level names of registered types.
+	///
+	/// Maps the string symbols of the alias names to the aliased type symbols.
+	aliases: BTreeMap<UntrackedSymbol<&'static str>, UntrackedSymbol<AnyTypeId>>,
+	/// Types registered without computing their identifiers and definitions yet.
+	///
+	/// A registry with deferred types refuses to be serialized.
+	deferred: BTreeMap<UntrackedSymbol<AnyTypeId>, MetaType>,
+	/// Whether the registry has run out of string or type symbols.
+	///
+	/// Registrations beyond the symbol range are dropped and refer to the first symbol
+	/// instead. An exhausted registry refuses to be serialized and fails transactions.
+	exhausted: bool,
+	/// The registrations recorded since `start_recording`, if the registry is recording.
+	pub(crate) recording: Option<Recording>,
+	/// The hooks called upon operations of the registry, shared with its branches.
+	pub(crate) hooks: Hooks,
+}
+
+/// Registries are equal if they have the same contents.
+///
+/// The definition arena is skipped since the registered types already hold their
+/// definitions, and so are the hooks.
+impl PartialEq for Registry {
+	fn eq(&self, other: &Self) -> bool {
+		let Self {
+			string_table,
+			type_table,
+			meta_types,
+			types,
+			defs: _,
+			reserved,
+			aliases,
+			deferred,
+			exhausted,
+			recording,
+			hooks: _,
+		} = self;
+		*string_table == other.string_table
+			&& *type_table == other.type_table
+			&& *meta_types == other.meta_types
+			&& *types == other.types
+			&& *reserved == other.reserved
+			&& *aliases == other.aliases
+			&& *deferred == other.deferred
+			&& *exhausted == other.exhausted
+			&& *recording == other.recording
+	}
+}
+
+impl Eq for Registry {}
+
+/// The key under which a type is interned into the type table of a registry.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+enum TypeKey {
+	/// A Rust type identified by its `core::any::TypeId`.
+	Meta(AnyTypeId),
+	/// A type injected by `register_nested` identified by the number of types before it.
+	#[cfg(feature = "serde")]
+	Raw(usize),
+}
+
+/// Returns the type symbol of the given symbol of the type table.
+fn type_symbol(symbol: Symbol<TypeKey>) -> UntrackedSymbol<AnyTypeId> {
+	UntrackedSymbol::from_id(symbol.into_untracked().id())
+}
+
+/// Returns the identity of the type with the given index among the given type identifiers
+/// in order of their type symbols.
+///
+/// The identity is the type reference of its identifier with the types it refers to turned
+/// into their identities in turn. Identifiers referring to unknown types or to themselves
+/// have no identity.
+#[cfg(feature = "serde")]
+fn identity(ids: &[TypeId<ResolvedForm>], index: usize) -> Option<TypeRef> {
+	fn identity_within(ids: &[TypeId<ResolvedForm>], index: usize, depth: usize, unresolved: &Cell<bool>) -> TypeRef {
+		match (depth.checked_sub(1), ids.get(index)) {
+			(Some(depth), Some(id)) => type_ref(id, |param| {
+				identity_within(ids, param.id().get() as usize - 1, depth, unresolved)
+			}),
+			_ => {
+				unresolved.set(true);
+				TypeRef::Tuple(Vec::new())
+			}
+		}
+	}
+	let unresolved = Cell::new(false);
+	let identity = identity_within(ids, index, ids.len(), &unresolved);
+	Some(identity).filter(|_| !unresolved.get())
+}
+
+/// The strings of a registry.
+#[derive(Debug, Clone)]
+enum StringTable {
+	/// The strings are interned by the registry itself.
+	///
+	/// Shared with branches of the registry until either side registers a string.
+	Local(Arc<Interner<&'static str>>),
+	/// The strings are interned by a string pool shared with other registries.
+	#[cfg(feature = "std")]
+	Pooled(&'static StringPool),
+}
+
+impl PartialEq for StringTable {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(StringTable::Local(lhs), StringTable::Local(rhs)) => lhs == rhs,
+			#[cfg(feature = "std")]
+			(StringTable::Pooled(lhs), StringTable::Pooled(rhs)) => core::ptr::eq(*lhs, *rhs),
+			#[cfg(feature = "std")]
+			_ => false,
+		}
+	}
+}
+
+impl Eq for StringTable {}
+
+/// The serialized form of a type alias.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct Alias {
+	/// The name of the alias.
+	name: UntrackedSymbol<&'static str>,
+	/// The aliased type.
+	#[serde(rename = "type")]
+	ty: UntrackedSymbol<AnyTypeId>,
+}
+
+/// Serializes the strings of the registry in order of their string symbols, the types
+/// in order of their type symbols and the aliases in order of the string symbols of their names.
+///
+/// Registries interning their strings into a string pool are serialized with only the strings
+/// they refer to, see `Registry::localized`.
+#[cfg(feature = "serde")]
+impl Serialize for Registry {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		// The definitions of deferred types would be missing from the serialized types
+		// which would shift the type symbols of all types registered after them.
+		if !self.deferred.is_empty() {
+			return Err(S::Error::custom(DEFERRED_TYPES_ERROR));
+		}
+		// Some registrations of an exhausted registry have been dropped and refer to wrong symbols.
+		if self.exhausted {
+			return Err(S::Error::custom(EXHAUSTED_ERROR));
+		}
+		let registry = self.localized();
+		let types = registry.types().map(|(_, id_def)| id_def).collect::<Vec<_>>();
+		let aliases = registry
+			.alias_symbols()
+			.map(|(name, ty)| Alias { name, ty })
+			.collect::<Vec<_>>();
+		let mut state = serializer.serialize_struct("Registry", 3)?;
+		state.serialize_field("strings", &registry.strings())?;
+		state.serialize_field("types", &types)?;
+		if aliases.is_empty() {
+			state.skip_field("aliases")?;
+		} else {
+			state.serialize_field("aliases", &aliases)?;
+		}
+		state.end()
+	}
+}
+
+/// The error message upon serializing a registry that has run out of symbols.
+#[cfg(feature = "serde")]
+pub(crate) const EXHAUSTED_ERROR: &str = "the registry has run out of symbols, some of its registrations have been dropped";
+
+/// The error message upon serializing a registry that still has deferred types.
+#[cfg(feature = "serde")]
+pub(crate) const DEFERRED_TYPES_ERROR: &str =
+	"the registry has deferred types, call `Registry::complete_deferred` before serialization";
+
+/// An error that may be encountered upon reserving type symbols.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ReservedError {
+	/// If the same type has been reserved more than once.
+	DuplicateType {
+		/// The index of the duplicate reserved type.
+		index: usize,
+	},
+	/// If a reserved type is missing from the registry.
+	MissingType {
+		/// The index of the missing reserved type.
+		index: usize,
+	},
+	/// If a reserved type does not occupy its expected type symbol.
+	Mismatch {
+		/// The index of the misplaced reserved type.
+		index: usize,
+	},
+}
+
+/// An error that may be encountered upon extending a registry while preserving its type symbols.
+#[cfg(feature = "serde")]
+#[derive(PartialEq, Eq, Debug)]
+pub enum ExtendError {
+	/// If the identifier or definition of a type of the old registry has changed.
+	DefinitionChanged {
+		/// The preserved type symbol of the changed type.
+		symbol: UntrackedSymbol<AnyTypeId>,
+	},
+	/// If the registry has types injected by `register_nested` which cannot be re-registered.
+	RawType {
+		/// The type symbol of the injected type.
+		symbol: UntrackedSymbol<AnyTypeId>,
+	},
+	/// If a type or alias of the old registry is malformed, see `Registry::register_nested`,
+	/// or the registry has run out of symbols.
+	InvalidOld(RawTypeError),
+}
+
+/// How `Registry::find` compares names.
+///
+/// By default names are compared exactly. User-facing searches, e.g. in explorer UIs,
+/// usually fold cases so that `"accountid"` matches `"AccountId"`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct FindOptions {
+	/// Whether names are compared in lowercase.
+	case_folding: bool,
+	/// Whether names are compared in their unicode compatibility normalization form (NFKC).
+	#[cfg(feature = "unicode-normalization")]
+	normalization: bool,
+}
+
+impl FindOptions {
+	/// Creates options comparing names exactly.
+	pub const fn exact() -> Self {
+		Self {
+			case_folding: false,
+			#[cfg(feature = "unicode-normalization")]
+			normalization: false,
+		}
+	}
+
+	/// Sets whether names are compared case-insensitively.
+	pub const fn with_case_folding(mut self, case_folding: bool) -> Self {
+		self.case_folding = case_folding;
+		self
+	}
+
+	/// Sets whether names are compared in their unicode compatibility normalization form,
+	/// e.g. so that the ligature `"ﬁ"` matches `"fi"`.
+	#[cfg(feature = "unicode-normalization")]
+	pub const fn with_normalization(mut self, normalization: bool) -> Self {
+		self.normalization = normalization;
+		self
+	}
+
+	/// Returns the form of the given name that is compared.
+	pub(crate) fn fold(self, name: &str) -> String {
+		#[cfg(feature = "unicode-normalization")]
+		let name = &if self.normalization {
+			unicode_normalization::UnicodeNormalization::nfkc(name).collect::<String>()
+		} else {
+			name.to_string()
+		};
+		if self.case_folding {
+			name.chars().flat_map(char::to_lowercase).collect()
+		} else {
+			name.to_string()
+		}
+	}
+}
+
+/// An error that may be encountered upon committing a registry transaction.
+#[derive(PartialEq, Eq, Debug)]
+pub enum TransactionError {
+	/// If the transaction has been aborted explicitly.
+	Aborted,
+	/// If an alias name of the transaction already aliases another type.
+	AliasConflict {
+		/// The conflicting alias name.
+		name: &'static str,
+	},
+	/// If a type of the transaction shares its identifier with another type
+	/// that has a different definition.
+	DefinitionConflict {
+		/// The type symbol of the conflicting type registered by the transaction.
+		symbol: UntrackedSymbol<AnyTypeId>,
+		/// The type symbol of the other type with the same identifier.
+		other: UntrackedSymbol<AnyTypeId>,
+	},
+	/// If the registry has run out of string or type symbols.
+	CapacityExceeded,
+}
+
+/// The state of a registry before a transaction used to roll it back.
+struct Checkpoint {
+	/// The number of locally interned strings.
+	strings: usize,
+	/// The number of interned types.
+	types: usize,
+	/// The definition arena before the transaction.
+	///
+	/// Sharing it makes the first registration of the transaction copy the arena.
+	defs: Arc<DefArena>,
+	/// The aliases before the transaction.
+	aliases: BTreeMap<UntrackedSymbol<&'static str>, UntrackedSymbol<AnyTypeId>>,
+	/// Whether the registry had run out of symbols before the transaction.
+	exhausted: bool,
+	/// The number of recorded registrations, if the registry is recording.
+	recorded: Option<usize>,
+}
+
+/// A set of type registrations that are either all applied to a registry or none.
+///
+/// Created by `RegistryBuilder::transaction`.
+pub struct Transaction<'a> {
+	/// The registry into which the types are registered.
+	registry: &'a mut Registry,
+	/// The first error encountered by the transaction, if any.
+	error: Option<TransactionError>,
+}
+
+impl<'a> Transaction<'a> {
+	/// Registers the given type and returns its associated type ID symbol.
+	pub fn register<T>(&mut self) -> UntrackedSymbol<AnyTypeId>
+	where
+		T: Metadata + ?Sized,
+	{
+		self.register_type(&T::meta_type())
+	}
+
+	/// Registers the given type and returns its associated type ID symbol.
+	pub fn register_type(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.registry.compact_type(ty)
+	}
+
+	/// Registers the given type under the given alias name and returns its associated type ID symbol.
+	///
+	/// In contrast to `RegistryBuilder::register_alias` the transaction fails if the alias
+	/// name already aliases another type.
+	pub fn register_alias(&mut self, name: &'static str, ty: MetaType) -> UntrackedSymbol<AnyTypeId> {
+		let symbol = self.registry.compact_type(&ty);
+		match self.registry.alias(name) {
+			Some(aliased) if aliased != symbol => self.fail(TransactionError::AliasConflict { name }),
+			_ => {
+				self.registry.alias_type(name, ty);
+			}
+		}
+		symbol
+	}
+
+	/// Aborts the transaction so that none of its types are registered.
+	///
+	/// This is useful in order to reject the transaction upon failed custom validations.
+	pub fn abort(&mut self) {
+		self.fail(TransactionError::Aborted)
+	}
+
+	/// Returns the registry including all types registered by the transaction so far.
+	pub fn registry(&self) -> &Registry {
+		self.registry
+	}
+
+	/// Records the given error unless the transaction has already failed.
+	fn fail(&mut self, error: TransactionError) {
+		if self.error.is_none() {
+			self.error = Some(error);
+		}
+	}
+}
+
+/// Maps the string symbols of a registry interning its strings into a string pool onto
+/// the ones of a local string table, see `Registry::localize`.
+///
+/// Type symbols are kept as they are.
+#[cfg(all(feature = "serde", feature = "std"))]
+struct LocalizedSymbols<'a> {
+	/// The registry interning its strings into a string pool.
+	pooled: &'a Registry,
+	/// The registry the strings are registered into in order of their first use.
+	local: RefCell<Registry>,
 }
 
-/// Serializes the types of the registry by removing their unique IDs
-/// and instead serialize them in order of their removed unique ID.
-fn serialize_registry_types<S>(
-	types: &BTreeMap<UntrackedSymbol<core::any::TypeId>, TypeIdDef>,
-	serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-	S: serde::Serializer,
-{
-	let types = types.values().collect::<Vec<_>>();
-	types.serialize(serializer)
+#[cfg(all(feature = "serde", feature = "std"))]
+impl Symbols for LocalizedSymbols<'_> {
+	type Form = CompactForm;
+
+	fn string(&self, symbol: u32) -> Result<UntrackedSymbol<&'static str>, OwnedRegistryError> {
+		NonZeroU32::new(symbol)
+			.and_then(|id| self.pooled.resolve_string(UntrackedSymbol::from_id(id)))
+			.map(|string| self.local.borrow_mut().compact_string(string))
+			.ok_or(OwnedRegistryError::UnknownString(symbol))
+	}
+
+	fn ty(&self, symbol: u32) -> Result<UntrackedSymbol<AnyTypeId>, OwnedRegistryError> {
+		NonZeroU32::new(symbol)
+			.map(UntrackedSymbol::from_id)
+			.ok_or(OwnedRegistryError::UnknownType(symbol))
+	}
 }
 
 impl Default for Registry {
@@ -114,16 +597,141 @@ impl Registry {
 	/// Creates a new empty registry.
 	pub fn new() -> Self {
 		Self {
-			string_table: Interner::new(),
-			type_table: Interner::new(),
-			types: BTreeMap::new(),
+			string_table: StringTable::Local(Arc::new(Interner::new())),
+			type_table: Arc::new(Interner::new()),
+			meta_types: Arc::new(Vec::new()),
+			types: Arc::new(BTreeMap::new()),
+			defs: Arc::new(DefArena::default()),
+			reserved: 0,
+			aliases: BTreeMap::new(),
+			deferred: BTreeMap::new(),
+			exhausted: false,
+			recording: None,
+			hooks: Hooks::default(),
 		}
 	}
 
+	/// Creates a new empty registry that interns its strings into the given string pool.
+	///
+	/// Registries sharing the same pool share their string symbols while their
+	/// type symbols stay independent. Use `StringPool::global()` in order to share
+	/// strings between all registries of the process.
+	#[cfg(feature = "std")]
+	pub fn with_string_pool(pool: &'static StringPool) -> Self {
+		Self {
+			string_table: StringTable::Pooled(pool),
+			..Self::new()
+		}
+	}
+
+	/// Creates a new registry with the given types reserving the first type symbols.
+	///
+	/// The reserved type at index `n` is guaranteed to be associated with the type
+	/// symbol `n + 1` so that clients are able to hard code the symbols of these
+	/// protocol-level well-known types. Sub-types of the reserved types are registered
+	/// after all reserved types.
+	///
+	/// # Errors
+	///
+	/// If the same type is reserved more than once.
+	pub fn with_reserved<T>(reserved: T) -> Result<Self, ReservedError>
+	where
+		T: IntoIterator<Item = MetaType>,
+	{
+		let reserved = reserved.into_iter().collect::<Vec<_>>();
+		let mut registry = Self::new();
+		let mut symbols = Vec::with_capacity(reserved.len());
+		for (index, ty) in reserved.iter().enumerate() {
+			let (inserted, symbol) = registry.intern_type_id(ty);
+			if !inserted {
+				return Err(ReservedError::DuplicateType { index });
+			}
+			symbols.push(symbol);
+		}
+		for (ty, symbol) in reserved.iter().zip(symbols) {
+			registry.insert_type(symbol, ty);
+		}
+		registry.reserved = reserved.len();
+		Ok(registry)
+	}
+
+	/// Returns the number of type symbols reserved for well-known types.
+	pub fn reserved_len(&self) -> usize {
+		self.reserved
+	}
+
+	/// Verifies that the reserved type symbols are filled consistently with the given types.
+	///
+	/// This is useful to assert that two parties agree upon the same set of
+	/// well-known types before exchanging hard coded type symbols.
+	///
+	/// # Errors
+	///
+	/// - If the number of given types differs from the number of reserved symbols.
+	/// - If any of the given types does not occupy its expected type symbol.
+	pub fn verify_reserved<T>(&self, reserved: T) -> Result<(), ReservedError>
+	where
+		T: IntoIterator<Item = MetaType>,
+	{
+		let mut len = 0;
+		for (index, ty) in reserved.into_iter().enumerate() {
+			len += 1;
+			if index >= self.reserved {
+				return Err(ReservedError::Mismatch { index });
+			}
+			match self.type_table.get(&TypeKey::Meta(ty.any_id())) {
+				Some(symbol) if symbol.into_untracked().id().get() as usize == index + 1 => (),
+				Some(_) => return Err(ReservedError::Mismatch { index }),
+				None => return Err(ReservedError::MissingType { index }),
+			}
+		}
+		if len < self.reserved {
+			return Err(ReservedError::MissingType { index: len });
+		}
+		Ok(())
+	}
+
 	/// Registeres the given string into the registry and returns
 	/// its respective associated string symbol.
-	pub fn register_string(&mut self, string: &'static str) -> UntrackedSymbol<&'static str> {
-		self.string_table.intern_or_get(string).1.into_untracked()
+	///
+	/// If the registry has run out of string symbols the string is dropped,
+	/// the first string symbol is returned and the registry is marked as exhausted.
+	pub(crate) fn compact_string(&mut self, string: &'static str) -> UntrackedSymbol<&'static str> {
+		let interned = match &mut self.string_table {
+			StringTable::Local(strings) => match strings.get(&string) {
+				Some(symbol) => Ok((false, symbol.into_untracked())),
+				None => Arc::make_mut(strings)
+					.try_intern_or_get(string)
+					.map(|(inserted, symbol)| (inserted, symbol.into_untracked())),
+			},
+			#[cfg(feature = "std")]
+			StringTable::Pooled(pool) => pool.try_intern_or_get(string),
+		};
+		match interned {
+			Ok((inserted, symbol)) => {
+				if inserted {
+					self.hooks.interned(string, symbol);
+				}
+				symbol
+			}
+			Err(_) => self.exhaust(),
+		}
+	}
+
+	/// Marks the registry as having run out of symbols and returns the first symbol
+	/// in place of the symbol that could not be allocated.
+	fn exhaust<T>(&mut self) -> UntrackedSymbol<T> {
+		self.exhausted = true;
+		UntrackedSymbol::from_id(NonZeroU32::MIN)
+	}
+
+	/// Returns `true` if the registry has run out of string or type symbols.
+	///
+	/// Registrations of an exhausted registry may have been dropped, so it
+	/// refuses to be serialized. Use `transaction` in order to roll back
+	/// registrations that exhaust the registry instead.
+	pub fn is_exhausted(&self) -> bool {
+		self.exhausted
 	}
 
 	/// Registeres the given type ID into the registry.
@@ -135,9 +743,25 @@ impl Registry {
 	/// # Note
 	///
 	/// This is an internal API and should not be called directly from the outside.
-	fn intern_type_id(&mut self, any_type_id: AnyTypeId) -> (bool, UntrackedSymbol<AnyTypeId>) {
-		let (inserted, symbol) = self.type_table.intern_or_get(any_type_id);
-		(inserted, symbol.into_untracked())
+	fn intern_type_id(&mut self, ty: &MetaType) -> (bool, UntrackedSymbol<AnyTypeId>) {
+		// Looking up registered types first avoids copying the tables shared with branches.
+		if let Some(symbol) = self.type_table.get(&TypeKey::Meta(ty.any_id())) {
+			return (false, type_symbol(symbol));
+		}
+		let interned = Arc::make_mut(&mut self.type_table)
+			.try_intern_or_get(TypeKey::Meta(ty.any_id()))
+			.map(|(inserted, symbol)| (inserted, type_symbol(symbol)));
+		let (inserted, symbol) = match interned {
+			Ok(interned) => interned,
+			Err(_) => return (false, self.exhaust()),
+		};
+		if inserted {
+			Arc::make_mut(&mut self.meta_types).push(Some(*ty));
+			if let Some(recording) = &mut self.recording {
+				recording.record(ty.name(), symbol);
+			}
+		}
+		(inserted, symbol)
 	}
 
 	/// Registers the given type into the registry and returns
@@ -149,19 +773,1141 @@ impl Registry {
 	/// be used later to resolve back to the associated type definition.
 	/// However, since this facility is going to be used for serialization
 	/// purposes this functionality isn't needed anyway.
-	pub fn register_type(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
-		let (inserted, symbol) = self.intern_type_id(ty.any_id());
+	pub(crate) fn compact_type(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		self.compact_new_type(ty).1
+	}
+
+	/// Registers the given type into the registry and returns its associated type ID symbol.
+	///
+	/// Returns `false` as the first return value if the type has already been registered.
+	pub(crate) fn compact_new_type(&mut self, ty: &MetaType) -> (bool, UntrackedSymbol<AnyTypeId>) {
+		let (inserted, symbol) = self.intern_type_id(ty);
+		if inserted {
+			self.insert_type(symbol, ty);
+		}
+		(inserted, symbol)
+	}
+
+	/// Registers the given type into the registry without computing its identifier
+	/// and definition and returns its associated type ID symbol.
+	///
+	/// The type is not compacted until `complete_deferred` is called, see
+	/// `RegistryBuilder::register_type_id_only`.
+	pub(crate) fn defer_type(&mut self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		let (inserted, symbol) = self.intern_type_id(ty);
 		if inserted {
-			let compact_id = ty.type_id().into_compact(self);
-			let compact_def = ty.type_def().into_compact(self);
-			self.types.insert(
-				symbol,
-				TypeIdDef {
-					id: compact_id,
-					def: compact_def,
-				},
-			);
+			self.deferred.insert(symbol, *ty);
 		}
 		symbol
 	}
+
+	/// Computes the identifiers and definitions of all deferred types
+	/// and registers their sub-types in order of the deferred type symbols.
+	pub fn complete_deferred(&mut self) {
+		for (symbol, ty) in core::mem::take(&mut self.deferred) {
+			self.insert_type(symbol, &ty);
+		}
+	}
+
+	/// Retires the type of the given type symbol by replacing its definition with a tombstone.
+	///
+	/// The type symbol stays allocated and the type keeps its identifier so that references
+	/// of other types remain valid and the type symbol is never reused for another type.
+	/// Registering the type again returns the same type symbol without reviving its definition.
+	///
+	/// Returns `false` if the type symbol does not originate from this registry.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type(&<Option<u32>>::meta_type());
+	/// let mut registry = builder.build();
+	/// assert!(registry.deprecate_type(symbol));
+	/// let mut builder = RegistryBuilder::branch(&registry);
+	/// assert_eq!(builder.register_type(&<Option<u32>>::meta_type()), symbol);
+	/// let registry = builder.build();
+	/// let (_, _, def) = registry.iter_resolved().find(|(other, _, _)| *other == symbol).unwrap();
+	/// assert!(def.is_tombstone());
+	/// ```
+	pub fn deprecate_type(&mut self, symbol: UntrackedSymbol<AnyTypeId>) -> bool {
+		if let Some(ty) = self.deferred.remove(&symbol) {
+			let id = ty.type_id().into_compact(self);
+			let def = Arc::make_mut(&mut self.defs).share(TypeDef::Builtin(Builtin::Tombstone));
+			Arc::make_mut(&mut self.types).insert(symbol, TypeIdDef { id, def });
+			return true;
+		}
+		if !self.types.contains_key(&symbol) {
+			return false;
+		}
+		let tombstone = Arc::make_mut(&mut self.defs).share(TypeDef::Builtin(Builtin::Tombstone));
+		match Arc::make_mut(&mut self.types).get_mut(&symbol) {
+			Some(id_def) => {
+				id_def.def = tombstone;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Renumbers the types of the registry so that all types of the given old registry,
+	/// e.g. the deserialized registry of a previous release, keep their exact type symbols.
+	///
+	/// The types of the old registry occupy their original type symbols while all
+	/// types only found in this registry are registered after them in order of their
+	/// previous type symbols. Types are matched by their identifiers, where the types
+	/// their identifiers refer to are matched by their identifiers in turn. Types of the
+	/// old registry no longer found in this registry are kept as injected types, see
+	/// `register_nested`. The aliases of both registries are kept with the aliases
+	/// of this registry taking precedence. String symbols are not preserved.
+	///
+	/// # Errors
+	///
+	/// - If the identifier or definition of any type of the old registry has changed.
+	/// - If this registry has types injected by `register_nested`.
+	/// - If a type or alias of the old registry is malformed.
+	///
+	/// In these cases the registry is left unchanged. Types retired in the old registry
+	/// stay retired.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{layout::nested, Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type(&u64::meta_type());
+	/// let old = builder.build();
+	/// let old = nested::Registry::from(&old);
+	///
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.register_type(&bool::meta_type());
+	/// builder.register_type(&u64::meta_type());
+	/// let mut new = builder.build();
+	/// new.extend_preserving(&old).unwrap();
+	/// assert_eq!(new.type_symbol::<u64>(), Some(symbol));
+	/// ```
+	#[cfg(feature = "serde")]
+	pub fn extend_preserving(&mut self, old: &nested::Registry) -> Result<(), ExtendError> {
+		if let Some(symbol) = self.first_raw_type() {
+			return Err(ExtendError::RawType { symbol });
+		}
+		// The identities of the types of this registry are computed from a copy
+		// with all deferred types completed.
+		let mut completed = Self {
+			hooks: Hooks::default(),
+			recording: None,
+			..self.clone()
+		};
+		completed.complete_deferred();
+		let resolved = completed.iter_resolved().map(|(_, id, _)| id).collect::<Vec<_>>();
+		// Distinct types with the same identity, e.g. wrappers sharing the identifier
+		// of their wrapped type, are matched in order of their type symbols.
+		let mut current = BTreeMap::<_, VecDeque<MetaType>>::new();
+		for (index, ty) in completed.meta_types.iter().enumerate() {
+			if let (Some(ty), Some(identity)) = (ty, identity(&resolved, index)) {
+				current.entry(identity).or_default().push_back(*ty);
+			}
+		}
+		let mut extended = Self {
+			string_table: match &self.string_table {
+				StringTable::Local(_) => StringTable::Local(Arc::new(Interner::new())),
+				#[cfg(feature = "std")]
+				StringTable::Pooled(pool) => StringTable::Pooled(pool),
+			},
+			hooks: self.hooks.clone(),
+			..Self::new()
+		};
+		let strings = extended.register_nested_strings(&old.strings);
+		let old_types = preserved_types(old, &strings).map_err(ExtendError::InvalidOld)?;
+		let old_ids = old_types
+			.iter()
+			.map(|(id, _)| id.resolve(&extended))
+			.collect::<Vec<_>>();
+		let mut matched = Vec::new();
+		for (index, (id, def)) in old_types.iter().enumerate() {
+			let ty = identity(&old_ids, index)
+				.and_then(|identity| current.get_mut(&identity))
+				.and_then(VecDeque::pop_front)
+				.filter(|ty| extended.type_table.get(&TypeKey::Meta(ty.any_id())).is_none());
+			let symbol = match ty {
+				Some(ty) => {
+					let symbol = extended.intern_type_id(&ty).1;
+					matched.push((symbol, ty));
+					symbol
+				}
+				None => extended.insert_raw_type(id.clone(), def.clone()),
+			};
+			if symbol_id(index) != Some(symbol.id()) {
+				return Err(ExtendError::InvalidOld(RawTypeError::CapacityExceeded));
+			}
+		}
+		for (symbol, ty) in matched {
+			extended.insert_type(symbol, &ty);
+		}
+		for (index, (_, def)) in old_types.iter().enumerate() {
+			if let (Some(symbol), true) = (symbol_id(index), def.is_tombstone()) {
+				extended.deprecate_type(UntrackedSymbol::from_id(symbol));
+			}
+		}
+		extended.reserved = self.reserved;
+		for ty in self.meta_types.iter().flatten() {
+			if self.deferred.values().any(|deferred| deferred == ty) {
+				extended.defer_type(ty);
+			} else {
+				extended.compact_type(ty);
+			}
+		}
+		for alias in &old.aliases {
+			let name = (alias.name as usize)
+				.checked_sub(1)
+				.and_then(|index| strings.get(index))
+				.ok_or(ExtendError::InvalidOld(RawTypeError::UnknownString(alias.name)))?;
+			let ty = NonZeroU32::new(alias.ty)
+				.filter(|ty| ty.get() as usize <= old_types.len())
+				.ok_or(ExtendError::InvalidOld(RawTypeError::UnknownType(alias.ty)))?;
+			extended.aliases.insert(*name, UntrackedSymbol::from_id(ty));
+		}
+		for (name, symbol) in self.aliases() {
+			if let Some(&Some(ty)) = self.meta_types.get(symbol.id().get() as usize - 1) {
+				extended.alias_type(name, ty);
+			}
+		}
+		for (index, (id, def)) in old_types.iter().enumerate() {
+			let symbol = symbol_id(index).map(UntrackedSymbol::from_id);
+			let unchanged = symbol
+				.and_then(|symbol| extended.resolve_type(symbol))
+				.and_then(normalized_type)
+				.is_some_and(|(new_id, new_def)| (&new_id, &new_def) == (id, def));
+			if let (Some(symbol), false) = (symbol, unchanged) {
+				return Err(ExtendError::DefinitionChanged { symbol });
+			}
+		}
+		extended.recording = self.recording.take();
+		*self = extended;
+		Ok(())
+	}
+
+	/// Runs the given closure and commits all of its registrations at once,
+	/// see `RegistryBuilder::transaction`.
+	pub(crate) fn transact<F, T>(&mut self, f: F) -> Result<T, TransactionError>
+	where
+		F: FnOnce(&mut Transaction<'_>) -> T,
+	{
+		let checkpoint = Checkpoint {
+			strings: match &self.string_table {
+				StringTable::Local(strings) => strings.len(),
+				#[cfg(feature = "std")]
+				StringTable::Pooled(_) => 0,
+			},
+			types: self.type_table.len(),
+			defs: Arc::clone(&self.defs),
+			aliases: self.aliases.clone(),
+			exhausted: self.exhausted,
+			recorded: self.recording.as_ref().map(Recording::len),
+		};
+		let mut tx = Transaction {
+			registry: self,
+			error: None,
+		};
+		let output = f(&mut tx);
+		let error = match tx.error {
+			Some(error) => Some(error),
+			None if self.exhausted => Some(TransactionError::CapacityExceeded),
+			None => self.find_definition_conflict(checkpoint.types),
+		};
+		match error {
+			Some(error) => {
+				self.rollback(checkpoint);
+				Err(error)
+			}
+			None => Ok(output),
+		}
+	}
+
+	/// Returns a copy-on-write branch of the registry.
+	///
+	/// Branching is cheap since the branch shares the string, type and definition tables
+	/// with the registry. A table is copied only once either of them registers into it,
+	/// so tooling may explore speculative changes, e.g. compare the registry with a branch
+	/// that has some types added, without copying large registries upfront. In contrast to
+	/// `clone` the branch does not continue a recording started with `start_recording`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	///
+	/// let branch = registry.branch();
+	/// assert_eq!(branch, registry);
+	/// ```
+	///
+	/// Use `RegistryBuilder::branch` in order to register further types into a branch.
+	pub fn branch(&self) -> Self {
+		Self {
+			string_table: self.string_table.clone(),
+			type_table: Arc::clone(&self.type_table),
+			meta_types: Arc::clone(&self.meta_types),
+			types: Arc::clone(&self.types),
+			defs: Arc::clone(&self.defs),
+			reserved: self.reserved,
+			aliases: self.aliases.clone(),
+			deferred: self.deferred.clone(),
+			exhausted: self.exhausted,
+			recording: None,
+			hooks: self.hooks.clone(),
+		}
+	}
+
+	/// Returns a conflict of any type registered after the first `len` types
+	/// sharing its identifier with another type with a different definition.
+	///
+	/// Conflicts among the first `len` types are not reported.
+	fn find_definition_conflict(&self, len: usize) -> Option<TransactionError> {
+		let is_custom = |id_def: &&TypeIdDef| matches!(id_def.id, TypeId::Custom(_));
+		let mut customs = BTreeMap::new();
+		let start = UntrackedSymbol::from_id(u32::try_from(len + 1).ok().and_then(NonZeroU32::new)?);
+		for (&symbol, id_def) in self.types.range(start..).filter(|(_, id_def)| is_custom(id_def)) {
+			match customs.get(&id_def.id) {
+				Some(&(other, other_def)) if other_def != &id_def.def => {
+					return Some(TransactionError::DefinitionConflict { symbol, other });
+				}
+				Some(_) => (),
+				None => {
+					customs.insert(&id_def.id, (symbol, &id_def.def));
+				}
+			}
+		}
+		if customs.is_empty() {
+			return None;
+		}
+		for (&other, id_def) in self.types.range(..start).filter(|(_, id_def)| is_custom(id_def)) {
+			match customs.get(&id_def.id) {
+				Some(&(symbol, def)) if def != &id_def.def => {
+					return Some(TransactionError::DefinitionConflict { symbol, other });
+				}
+				_ => (),
+			}
+		}
+		None
+	}
+
+	/// Removes everything registered since the given checkpoint.
+	fn rollback(&mut self, checkpoint: Checkpoint) {
+		match &mut self.string_table {
+			StringTable::Local(strings) => Arc::make_mut(strings).truncate(checkpoint.strings),
+			#[cfg(feature = "std")]
+			StringTable::Pooled(_) => (),
+		}
+		Arc::make_mut(&mut self.type_table).truncate(checkpoint.types);
+		Arc::make_mut(&mut self.meta_types).truncate(checkpoint.types);
+		Arc::make_mut(&mut self.types).retain(|symbol, _| symbol.id().get() as usize <= checkpoint.types);
+		self.deferred
+			.retain(|symbol, _| symbol.id().get() as usize <= checkpoint.types);
+		self.defs = checkpoint.defs;
+		self.aliases = checkpoint.aliases;
+		self.exhausted = checkpoint.exhausted;
+		if let (Some(recording), Some(len)) = (&mut self.recording, checkpoint.recorded) {
+			recording.truncate(len);
+		}
+	}
+
+	/// Returns the deferred type associated with the given type symbol, if any.
+	pub(crate) fn deferred_type(&self, symbol: UntrackedSymbol<AnyTypeId>) -> Option<&MetaType> {
+		self.deferred.get(&symbol)
+	}
+
+	/// Returns the number of deferred types whose identifiers and definitions
+	/// are yet to be computed by `complete_deferred`.
+	pub fn deferred_len(&self) -> usize {
+		self.deferred.len()
+	}
+
+	/// Returns the string associated with the given string symbol of this registry.
+	pub(crate) fn resolve_string(&self, symbol: UntrackedSymbol<&'static str>) -> Option<&'static str> {
+		match &self.string_table {
+			StringTable::Local(strings) => strings.resolve_untracked(symbol).copied(),
+			#[cfg(feature = "std")]
+			StringTable::Pooled(pool) => pool.resolve(symbol),
+		}
+	}
+
+	/// Returns the string associated with the given string symbol of this registry.
+	///
+	/// Returns an empty string if the string symbol does not originate from this registry.
+	pub(crate) fn resolved_string(&self, symbol: UntrackedSymbol<&'static str>) -> &'static str {
+		self.resolve_string(symbol).unwrap_or_default()
+	}
+
+	/// Returns the compact type identifier and definition associated with
+	/// the given type symbol of this registry.
+	pub(crate) fn resolve_type(&self, symbol: UntrackedSymbol<AnyTypeId>) -> Option<&TypeIdDef> {
+		self.types.get(&symbol)
+	}
+
+	/// Returns the compact type identifier and definition associated with
+	/// the given type symbol, e.g. as returned by `register_type`.
+	///
+	/// Returns `None` if the type symbol does not originate from this registry or if the
+	/// type has been registered by `register_type_id_only` and is not yet completed.
+	/// The string symbols of the returned identifier and definition are resolved by `resolve_str`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder, TypeId};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	/// let id_def = registry.resolve(&symbol).unwrap();
+	/// match id_def.id() {
+	///     TypeId::Custom(custom) => assert_eq!(registry.resolve_str(custom.name()), Some("Option")),
+	///     _ => unreachable!(),
+	/// }
+	/// ```
+	pub fn resolve(&self, symbol: &UntrackedSymbol<AnyTypeId>) -> Option<&TypeIdDef> {
+		self.resolve_type(*symbol)
+	}
+
+	/// Returns the string associated with the given string symbol,
+	/// e.g. the name of a compact type identifier returned by `resolve`.
+	///
+	/// Returns `None` if the string symbol does not originate from this registry.
+	pub fn resolve_str(&self, symbol: &UntrackedSymbol<&'static str>) -> Option<&'static str> {
+		self.resolve_string(*symbol)
+	}
+
+	/// Registers the given type under the given domain-level alias name
+	/// and returns its associated type ID symbol.
+	///
+	/// Registering an alias name that already exists replaces the aliased type.
+	pub(crate) fn alias_type(&mut self, name: &'static str, ty: MetaType) -> UntrackedSymbol<AnyTypeId> {
+		let name = self.compact_string(name);
+		let symbol = self.compact_type(&ty);
+		self.aliases.insert(name, symbol);
+		symbol
+	}
+
+	/// Returns the type symbol aliased by the given name if any.
+	pub fn alias(&self, name: &str) -> Option<UntrackedSymbol<AnyTypeId>> {
+		self.aliases()
+			.find(|(alias, _)| *alias == name)
+			.map(|(_, symbol)| symbol)
+	}
+
+	/// Returns the type symbol of the given type if it has been registered.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let symbol = builder.register_type(&<Option<u32>>::meta_type());
+	/// let registry = builder.build();
+	/// assert_eq!(registry.type_symbol::<Option<u32>>(), Some(symbol));
+	/// assert!(registry.type_symbol::<u32>().is_some());
+	/// assert_eq!(registry.type_symbol::<bool>(), None);
+	/// ```
+	pub fn type_symbol<T>(&self) -> Option<UntrackedSymbol<AnyTypeId>>
+	where
+		T: Metadata + ?Sized,
+	{
+		self.type_symbol_of(&T::meta_type())
+	}
+
+	/// Returns the type symbol of the given meta type if it has been registered.
+	pub fn type_symbol_of(&self, ty: &MetaType) -> Option<UntrackedSymbol<AnyTypeId>> {
+		self.type_table.get(&TypeKey::Meta(ty.any_id())).map(type_symbol)
+	}
+
+	/// Returns the symbols of all types whose name, path or alias matches the given name.
+	///
+	/// Custom types are matched by their names without type parameters, e.g. `"Option"`, and by
+	/// their paths, e.g. `"app::AccountId"`. The symbols are returned in order without duplicates.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{FindOptions, Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let balance = builder.register_alias("Balance", u128::meta_type());
+	/// let registry = builder.build();
+	/// let options = FindOptions::exact().with_case_folding(true);
+	/// assert_eq!(registry.find("balance", options), vec![balance]);
+	/// assert_eq!(registry.find("balance", FindOptions::exact()), vec![]);
+	/// ```
+	pub fn find(&self, name: &str, options: FindOptions) -> Vec<UntrackedSymbol<AnyTypeId>> {
+		let name = options.fold(name);
+		let mut found = self
+			.types()
+			.filter(|(_, id_def)| match id_def.id.resolve(self) {
+				TypeId::Custom(custom) => {
+					let mut path = String::new();
+					for segment in custom.namespace().segments() {
+						path.push_str(segment);
+						path.push_str("::");
+					}
+					path.push_str(custom.name());
+					options.fold(custom.name()) == name || options.fold(&path) == name
+				}
+				_ => false,
+			})
+			.map(|(symbol, _)| symbol)
+			.chain(
+				self.aliases()
+					.filter(|(alias, _)| options.fold(alias) == name)
+					.map(|(_, symbol)| symbol),
+			)
+			.collect::<Vec<_>>();
+		found.sort();
+		found.dedup();
+		found
+	}
+
+	/// Returns an iterator over all aliases and their aliased type symbols
+	/// in order of the string symbols of their names.
+	pub fn aliases(&self) -> impl Iterator<Item = (&'static str, UntrackedSymbol<AnyTypeId>)> + '_ {
+		self.aliases
+			.iter()
+			.map(move |(name, symbol)| (self.resolved_string(*name), *symbol))
+	}
+
+	/// Returns a view of the registry that serializes in the given layout.
+	///
+	/// The serialized registry additionally carries the format version of the layout.
+	#[cfg(feature = "serde")]
+	pub fn with_layout(&self, layout: Layout) -> LayoutRegistry<'_> {
+		LayoutRegistry::new(self, layout)
+	}
+
+	/// Returns an iterator over the string symbols of all aliases and their aliased type symbols.
+	#[cfg(feature = "serde")]
+	pub(crate) fn alias_symbols(
+		&self,
+	) -> impl Iterator<Item = (UntrackedSymbol<&'static str>, UntrackedSymbol<AnyTypeId>)> + '_ {
+		self.aliases.iter().map(|(name, symbol)| (*name, *symbol))
+	}
+
+	/// Returns all registered strings in order of their string symbols.
+	#[cfg(feature = "serde")]
+	pub(crate) fn strings(&self) -> Vec<&'static str> {
+		match &self.string_table {
+			StringTable::Local(strings) => strings.elements().to_vec(),
+			#[cfg(feature = "std")]
+			StringTable::Pooled(pool) => pool.strings(),
+		}
+	}
+
+	/// Returns the registry with only the strings its types and aliases refer to.
+	///
+	/// The strings of a registry interning them into a string pool include the ones only
+	/// registered by other registries sharing the pool. Such a registry is copied with the
+	/// strings it refers to registered locally in order of their first use, so their string
+	/// symbols are dense and independent of the other registries. Other registries, as well
+	/// as registries with deferred types or that have run out of symbols, are returned as they are.
+	#[cfg(feature = "serde")]
+	pub(crate) fn localized(&self) -> Cow<'_, Registry> {
+		#[cfg(feature = "std")]
+		if let StringTable::Pooled(_) = self.string_table {
+			if let Some(localized) = self.localize() {
+				return Cow::Owned(localized);
+			}
+		}
+		Cow::Borrowed(self)
+	}
+
+	/// Copies the registry into one with a local string table holding only the strings it refers to.
+	///
+	/// Returns `None` if the registry has deferred types or has run out of symbols.
+	#[cfg(all(feature = "serde", feature = "std"))]
+	fn localize(&self) -> Option<Registry> {
+		if !self.deferred.is_empty() || self.exhausted {
+			return None;
+		}
+		let symbols = LocalizedSymbols {
+			pooled: self,
+			local: RefCell::new(Registry::new()),
+		};
+		for (_, id_def) in self.types() {
+			let id_def = nested::TypeIdDef::from(id_def);
+			let id = symbols.type_id(&id_def.id).ok()?;
+			let def = symbols.type_def(&id_def.def).ok()?;
+			symbols.local.borrow_mut().insert_raw_type(id, def);
+		}
+		let mut local = symbols.local.into_inner();
+		for (name, ty) in self.alias_symbols() {
+			let name = local.compact_string(self.resolve_string(name)?);
+			local.aliases.insert(name, ty);
+		}
+		local.reserved = self.reserved;
+		local.hooks = self.hooks.clone();
+		Some(local).filter(|local| !local.exhausted)
+	}
+
+	/// Returns an iterator over all registered types in order of their type symbols.
+	pub(crate) fn types(&self) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, &TypeIdDef)> {
+		self.types.iter().map(|(symbol, id_def)| (*symbol, id_def))
+	}
+
+	/// Returns an iterator over all registered types in order of their type symbols.
+	///
+	/// Yields the type symbol together with the type identifier and definition
+	/// which have all their string symbols already resolved through the registry.
+	pub fn iter_resolved(
+		&self,
+	) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, TypeId<ResolvedForm>, TypeDef<ResolvedForm>)> + '_ {
+		self.types()
+			.map(move |(symbol, id_def)| (symbol, id_def.id.resolve(self), id_def.def.resolve(self)))
+	}
+
+	/// Returns the number of other types referring to each registered type by their identifier or definition.
+	///
+	/// Types referred to by many others are hot spots whose evolution affects large parts of the registry,
+	/// while types without any references, apart from the registered roots, are dead. Each type counts at
+	/// most once per referred type and references of types to themselves are not counted.
+	pub fn usage_counts(&self) -> BTreeMap<UntrackedSymbol<AnyTypeId>, usize> {
+		let mut counts = self.types().map(|(symbol, _)| (symbol, 0)).collect::<BTreeMap<_, usize>>();
+		for (symbol, id_def) in self.types() {
+			for type_ref in id_def.type_refs().into_iter().filter(|type_ref| *type_ref != symbol) {
+				let count = counts.entry(type_ref).or_default();
+				*count = count.saturating_add(1);
+			}
+		}
+		counts
+	}
+
+	/// Returns the type symbol of the first type injected by `register_nested`, if any.
+	#[cfg(feature = "serde")]
+	fn first_raw_type(&self) -> Option<UntrackedSymbol<AnyTypeId>> {
+		let index = self.meta_types.iter().position(Option::is_none)?;
+		Some(UntrackedSymbol::from_id(symbol_id(index)?))
+	}
+
+	/// Returns the type symbol the next registered type is going to be assigned.
+	///
+	/// Returns `None` if the registry has run out of type symbols.
+	#[cfg(feature = "serde")]
+	pub(crate) fn next_type_symbol(&self) -> Option<UntrackedSymbol<AnyTypeId>> {
+		symbol_id(self.type_table.len()).map(UntrackedSymbol::from_id)
+	}
+
+	/// Stores the given type identifier and definition under a newly allocated type symbol.
+	///
+	/// The identifier and definition may refer to the allocated symbol as returned
+	/// by `next_type_symbol` beforehand. The type is not recorded since it has no
+	/// Rust type name.
+	#[cfg(feature = "serde")]
+	pub(crate) fn insert_raw_type(
+		&mut self,
+		id: TypeId<CompactForm>,
+		def: TypeDef<CompactForm>,
+	) -> UntrackedSymbol<AnyTypeId> {
+		let key = TypeKey::Raw(self.type_table.len());
+		let interned = Arc::make_mut(&mut self.type_table)
+			.try_intern_or_get(key)
+			.map(|(_, symbol)| type_symbol(symbol));
+		let symbol = match interned {
+			Ok(symbol) => symbol,
+			Err(_) => return self.exhaust(),
+		};
+		Arc::make_mut(&mut self.meta_types).push(None);
+		let def = Arc::make_mut(&mut self.defs).share(def);
+		Arc::make_mut(&mut self.types).insert(symbol, TypeIdDef { id, def });
+		symbol
+	}
+
+	/// Compacts the identifier and definition of the given type and stores
+	/// them under the given type symbol.
+	fn insert_type(&mut self, symbol: UntrackedSymbol<AnyTypeId>, ty: &MetaType) {
+		let started = self.hooks.start();
+		let compact_id = ty.type_id().into_compact(self);
+		let compact_def = ty.type_def().into_compact(self);
+		let compact_def = Arc::make_mut(&mut self.defs).share(compact_def);
+		Arc::make_mut(&mut self.types).insert(
+			symbol,
+			TypeIdDef {
+				id: compact_id,
+				def: compact_def,
+			},
+		);
+		self.hooks.registered(ty.name(), symbol, started);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HasTypeDef, HasTypeId, Metadata, NamedField, Namespace, TypeDefComposite, TypeIdCustom};
+
+	#[allow(unused)]
+	struct Account {
+		balance: u64,
+		nonce: u32,
+		limit: Option<u64>,
+	}
+
+	impl HasTypeId for Account {
+		fn type_id() -> TypeId {
+			TypeIdCustom::new("Account", Namespace::new(vec!["usage"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Account {
+		fn type_def() -> TypeDef {
+			TypeDefComposite::new(vec![
+				NamedField::of::<u64>("balance"),
+				NamedField::of::<u32>("nonce"),
+				NamedField::of::<Option<u64>>("limit"),
+			])
+			.into()
+		}
+	}
+
+	#[test]
+	fn usage_counts_references_by_other_types() {
+		let mut registry = Registry::new();
+		let account = registry.compact_type(&Account::meta_type());
+		let pair = registry.compact_type(&<(u64, bool)>::meta_type());
+		let [int, nonce, limit] =
+			[u64::meta_type(), u32::meta_type(), <Option<u64>>::meta_type()].map(|ty| registry.compact_type(&ty));
+		let counts = registry.usage_counts();
+		assert_eq!(counts.get(&int), Some(&3));
+		assert_eq!(counts.get(&nonce), Some(&1));
+		assert_eq!(counts.get(&limit), Some(&1));
+		assert_eq!(counts.get(&account), Some(&0));
+		assert_eq!(counts.get(&pair), Some(&0));
+		assert_eq!(counts.len(), registry.types().count());
+	}
+
+	#[test]
+	fn identical_definitions_are_shared() {
+		let mut registry = Registry::new();
+		let tuple = registry.compact_type(&<(u8, u16)>::meta_type());
+		let array = registry.compact_type(&<[u32; 4]>::meta_type());
+		let option = registry.compact_type(&<Option<u8>>::meta_type());
+		let branch = registry.branch();
+		registry.compact_type(&<Option<u16>>::meta_type());
+
+		let def = |symbol| registry.resolve(&symbol).unwrap().def();
+		assert!(core::ptr::eq(def(tuple), def(array)));
+		assert!(!core::ptr::eq(def(tuple), def(option)));
+		assert_eq!(registry.defs.len(), 3);
+		assert_eq!(branch.defs.len(), 2);
+	}
+
+	#[test]
+	fn reserved_types_occupy_first_symbols() {
+		let reserved = vec![<Vec<u8>>::meta_type(), u32::meta_type(), bool::meta_type()];
+		let mut registry = Registry::with_reserved(reserved.clone()).unwrap();
+		assert_eq!(registry.reserved_len(), 3);
+		assert_eq!(registry.compact_type(&u32::meta_type()).id().get(), 2);
+		assert_eq!(registry.compact_type(&bool::meta_type()).id().get(), 3);
+		// Sub-types of reserved types are registered after all reserved types.
+		assert_eq!(registry.compact_type(&u8::meta_type()).id().get(), 4);
+		assert_eq!(registry.verify_reserved(reserved), Ok(()));
+	}
+
+	#[test]
+	fn reserved_types_errors() {
+		assert_eq!(
+			Registry::with_reserved(vec![u32::meta_type(), u32::meta_type()]),
+			Err(ReservedError::DuplicateType { index: 1 })
+		);
+		let registry = Registry::with_reserved(vec![u32::meta_type(), bool::meta_type()]).unwrap();
+		assert_eq!(
+			registry.verify_reserved(vec![bool::meta_type(), u32::meta_type()]),
+			Err(ReservedError::Mismatch { index: 0 })
+		);
+		assert_eq!(
+			registry.verify_reserved(vec![u32::meta_type()]),
+			Err(ReservedError::MissingType { index: 1 })
+		);
+		assert_eq!(
+			registry.verify_reserved(vec![u32::meta_type(), bool::meta_type(), u8::meta_type()]),
+			Err(ReservedError::Mismatch { index: 2 })
+		);
+	}
+
+	#[test]
+	fn deferred_types() {
+		let mut eager = Registry::new();
+		eager.compact_type(&<Option<u32>>::meta_type());
+		let mut lazy = Registry::new();
+		lazy.defer_type(&<Option<u32>>::meta_type());
+		assert_eq!(lazy.deferred_len(), 1);
+		assert_eq!(lazy.iter_resolved().count(), 0);
+		lazy.complete_deferred();
+		assert_eq!(lazy.deferred_len(), 0);
+		assert_eq!(lazy, eager);
+	}
+
+	#[test]
+	fn deprecated_types() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<u32>>::meta_type());
+		let deferred = registry.defer_type(&<Vec<bool>>::meta_type());
+		assert!(registry.deprecate_type(option));
+		assert!(registry.deprecate_type(deferred));
+		assert_eq!(registry.deferred_len(), 0);
+		assert_eq!(registry.compact_type(&<Option<u32>>::meta_type()), option);
+
+		let defs = registry
+			.iter_resolved()
+			.map(|(_, _, def)| def.is_tombstone())
+			.collect::<Vec<_>>();
+		// `u32` and `bool` are still referred to by the identifiers of the retired types.
+		assert_eq!(defs, vec![true, false, true, false]);
+
+		let mut other = Registry::new();
+		other.compact_type(&u8::meta_type());
+		assert!(!other.deprecate_type(deferred));
+
+		#[cfg(feature = "serde")]
+		{
+			let mut new = Registry::new();
+			new.compact_type(&<Option<u32>>::meta_type());
+			new.extend_preserving(&nested::Registry::from(&registry)).unwrap();
+			let (_, _, def) = new.iter_resolved().find(|(symbol, _, _)| *symbol == option).unwrap();
+			assert!(def.is_tombstone());
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn extend_preserving_keeps_old_symbols() {
+		let mut old = Registry::new();
+		let option = old.compact_type(&<Option<u32>>::meta_type());
+		let u32_symbol = old.compact_type(&u32::meta_type());
+		let balance = old.alias_type("Balance", u128::meta_type());
+		let old = nested::Registry::from(&old);
+
+		let mut new = Registry::new();
+		new.compact_type(&bool::meta_type());
+		new.compact_type(&u128::meta_type());
+		new.compact_type(&<Option<u32>>::meta_type());
+		new.extend_preserving(&old).unwrap();
+
+		assert_eq!(new.compact_type(&<Option<u32>>::meta_type()), option);
+		assert_eq!(new.compact_type(&u32::meta_type()), u32_symbol);
+		assert_eq!(new.alias("Balance"), Some(balance));
+		// Types only found in the new registry are registered after all old types.
+		assert_eq!(new.compact_type(&bool::meta_type()).id().get(), 4);
+		assert_eq!(new.iter_resolved().count(), 4);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn extend_preserving_keeps_removed_types() {
+		let mut old = Registry::new();
+		let option = old.compact_type(&<Option<u32>>::meta_type());
+		let removed = old.compact_type(&<Result<bool, ()>>::meta_type());
+		let old = nested::Registry::from(&old);
+
+		let mut new = Registry::new();
+		new.compact_type(&<Option<u32>>::meta_type());
+		new.compact_type(&u8::meta_type());
+		new.extend_preserving(&old).unwrap();
+
+		assert_eq!(new.compact_type(&<Option<u32>>::meta_type()), option);
+		// Types no longer registered keep their symbols as injected types.
+		let (_, id, _) = new.iter_resolved().find(|(symbol, _, _)| *symbol == removed).unwrap();
+		assert!(matches!(id, TypeId::Custom(custom) if *custom.name() == "Result"));
+		assert_eq!(new.compact_type(&u8::meta_type()).id().get(), 6);
+		// The old registry is a prefix of the extended one.
+		let extended = nested::Registry::from(&new);
+		assert_eq!(extended.strings[..old.strings.len()], old.strings[..]);
+		assert_eq!(extended.types[..old.types.len()], old.types[..]);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn extend_preserving_detects_changed_definitions() {
+		struct A;
+		struct B;
+
+		impl HasTypeId for A {
+			fn type_id() -> TypeId {
+				TypeIdCustom::new("S", Namespace::new(vec!["dup"]).unwrap(), vec![]).into()
+			}
+		}
+
+		impl HasTypeDef for A {
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![NamedField::of::<u8>("a")]).into()
+			}
+		}
+
+		impl HasTypeId for B {
+			fn type_id() -> TypeId {
+				A::type_id()
+			}
+		}
+
+		impl HasTypeDef for B {
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![NamedField::of::<bool>("b")]).into()
+			}
+		}
+
+		let mut old = Registry::new();
+		old.compact_type(&u32::meta_type());
+		old.compact_type(&<Option<A>>::meta_type());
+		let a = old.compact_type(&A::meta_type());
+		let old = nested::Registry::from(&old);
+
+		let mut new = Registry::new();
+		new.compact_type(&<Option<B>>::meta_type());
+		new.start_recording();
+		new.compact_type(&u64::meta_type());
+		let expected = new.clone();
+		assert_eq!(
+			new.extend_preserving(&old),
+			Err(ExtendError::DefinitionChanged { symbol: a })
+		);
+		assert_eq!(new, expected);
+		// The recording continues after a failed extension.
+		new.compact_type(&u16::meta_type());
+		assert_eq!(new.stop_recording().len(), 2);
+	}
+
+	#[test]
+	fn aliases() {
+		let mut registry = Registry::new();
+		let balance = registry.alias_type("Balance", u128::meta_type());
+		let account = registry.alias_type("AccountId", <[u8; 32]>::meta_type());
+		assert_eq!(registry.compact_type(&u128::meta_type()), balance);
+		assert_eq!(registry.alias("Balance"), Some(balance));
+		assert_eq!(registry.alias("AccountId"), Some(account));
+		assert_eq!(registry.alias("Unknown"), None);
+		assert_eq!(
+			registry.aliases().collect::<Vec<_>>(),
+			vec![("Balance", balance), ("AccountId", account)]
+		);
+		// Aliasing another type under the same name replaces the alias.
+		let index = registry.alias_type("Balance", u64::meta_type());
+		assert_eq!(registry.alias("Balance"), Some(index));
+	}
+
+	#[test]
+	fn clones_are_independent() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<u32>>::meta_type());
+		let mut clone = registry.clone();
+		assert_eq!(clone, registry);
+		clone.compact_type(&bool::meta_type());
+		assert_ne!(clone, registry);
+
+		let mut expected = Registry::new();
+		expected.compact_type(&<Option<u32>>::meta_type());
+		assert_eq!(registry, expected);
+	}
+
+	#[test]
+	fn branches_share_tables_until_written() {
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<u32>>::meta_type());
+		let mut branch = registry.branch();
+		assert!(Arc::ptr_eq(&branch.types, &registry.types));
+		assert!(Arc::ptr_eq(&branch.type_table, &registry.type_table));
+
+		branch.compact_type(&bool::meta_type());
+		assert!(!Arc::ptr_eq(&branch.types, &registry.types));
+		assert_eq!(registry.types.len(), 2);
+		assert_eq!(branch.types.len(), 3);
+		// Registering already registered types does not copy the tables.
+		let mut branch = registry.branch();
+		branch.compact_type(&u32::meta_type());
+		branch.compact_string("Option");
+		assert!(Arc::ptr_eq(&branch.types, &registry.types));
+		assert!(Arc::ptr_eq(&branch.type_table, &registry.type_table));
+		assert_eq!(branch.string_table, registry.string_table);
+		match (&branch.string_table, &registry.string_table) {
+			(StringTable::Local(lhs), StringTable::Local(rhs)) => assert!(Arc::ptr_eq(lhs, rhs)),
+			#[cfg(feature = "std")]
+			_ => unreachable!(),
+		}
+	}
+
+	#[test]
+	fn resolves_symbols() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<u8>>::meta_type());
+		let u8_symbol = registry.compact_type(&u8::meta_type());
+		let deferred = registry.defer_type(&bool::meta_type());
+		let id_def = registry.resolve(&option).unwrap();
+		let custom = match id_def.id() {
+			TypeId::Custom(custom) => custom,
+			id => panic!("unexpected type identifier: {:?}", id),
+		};
+		assert_eq!(registry.resolve_str(custom.name()), Some("Option"));
+		assert_eq!(custom.type_params(), &[u8_symbol]);
+		assert_eq!(registry.resolve(&deferred), None);
+	}
+
+	#[test]
+	fn find_folds_cases() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<u8>>::meta_type());
+		let account = registry.alias_type("AccountId", <[u8; 32]>::meta_type());
+		let exact = FindOptions::exact();
+		let folded = exact.with_case_folding(true);
+		assert_eq!(registry.find("Option", exact), vec![option]);
+		assert_eq!(registry.find("option", exact), vec![]);
+		assert_eq!(registry.find("OPTION", folded), vec![option]);
+		assert_eq!(registry.find("accountid", folded), vec![account]);
+		// Primitives and other non-custom types have no name to match.
+		assert_eq!(registry.find("u8", folded), vec![]);
+	}
+
+	#[cfg(feature = "unicode-normalization")]
+	#[test]
+	fn find_normalizes_names() {
+		let mut builder = RegistryBuilder::new();
+		let account = builder.register_alias("Proﬁle", u32::meta_type());
+		let registry = builder.build();
+		let normalized = FindOptions::exact().with_normalization(true).with_case_folding(true);
+		assert_eq!(registry.find("profile", normalized), vec![account]);
+		assert_eq!(
+			registry.find("profile", FindOptions::exact().with_case_folding(true)),
+			vec![]
+		);
+	}
+
+	#[test]
+	fn transaction_commits_all_types() {
+		let mut registry = Registry::new();
+		let symbol = registry
+			.transact(|tx| {
+				tx.register::<u32>();
+				tx.register_alias("Balance", u128::meta_type());
+				tx.register::<Option<bool>>()
+			})
+			.unwrap();
+
+		let mut expected = Registry::new();
+		expected.compact_type(&u32::meta_type());
+		expected.alias_type("Balance", u128::meta_type());
+		assert_eq!(expected.compact_type(&<Option<bool>>::meta_type()), symbol);
+		assert_eq!(registry, expected);
+	}
+
+	#[test]
+	fn transaction_rolls_back_all_types() {
+		let mut registry = Registry::new();
+		registry.alias_type("Balance", u128::meta_type());
+		registry.defer_type(&u8::meta_type());
+
+		let mut expected = Registry::new();
+		expected.alias_type("Balance", u128::meta_type());
+		expected.defer_type(&u8::meta_type());
+
+		let result = registry.transact(|tx| {
+			tx.register::<Option<u32>>();
+			tx.registry.defer_type(&bool::meta_type());
+			tx.register_alias("Index", u64::meta_type());
+			tx.abort();
+		});
+		assert_eq!(result, Err(TransactionError::Aborted));
+		assert_eq!(registry, expected);
+
+		let result = registry.transact(|tx| {
+			tx.register_alias("Balance", u64::meta_type());
+		});
+		assert_eq!(result, Err(TransactionError::AliasConflict { name: "Balance" }));
+		assert_eq!(registry, expected);
+		// Registering the same alias again is not a conflict.
+		assert_eq!(
+			registry.transact(|tx| tx.register_alias("Balance", u128::meta_type())),
+			Ok(registry.compact_type(&u128::meta_type()))
+		);
+	}
+
+	#[test]
+	fn transaction_rolls_back_definitions_and_recordings() {
+		let mut registry = Registry::new();
+		registry.start_recording();
+		registry.compact_type(&u32::meta_type());
+		let defs = registry.defs.len();
+
+		let result = registry.transact(|tx| {
+			tx.register::<(bool, Option<u64>)>();
+			tx.abort();
+		});
+		assert_eq!(result, Err(TransactionError::Aborted));
+		assert_eq!(registry.defs.len(), defs);
+		assert_eq!(registry.recorded().len(), 1);
+	}
+
+	#[test]
+	fn transaction_detects_definition_conflicts() {
+		use crate::{HasTypeDef, HasTypeId, NamedField, Namespace, TypeIdCustom};
+
+		struct A;
+		struct B;
+
+		impl HasTypeId for A {
+			fn type_id() -> TypeId {
+				TypeIdCustom::new("S", Namespace::new(vec!["dup"]).unwrap(), vec![]).into()
+			}
+		}
+
+		impl HasTypeDef for A {
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![NamedField::of::<u8>("a")]).into()
+			}
+		}
+
+		impl HasTypeId for B {
+			fn type_id() -> TypeId {
+				A::type_id()
+			}
+		}
+
+		impl HasTypeDef for B {
+			fn type_def() -> TypeDef {
+				TypeDefComposite::new(vec![NamedField::of::<bool>("b")]).into()
+			}
+		}
+
+		let mut registry = Registry::new();
+		let a = registry.compact_type(&A::meta_type());
+		// Wrappers share the identifier and definition of their wrapped type.
+		assert!(registry.transact(|tx| tx.register::<Box<A>>()).is_ok());
+		let result = registry.transact(|tx| {
+			tx.register::<B>();
+		});
+		match result {
+			Err(TransactionError::DefinitionConflict { other, .. }) => assert_eq!(other, a),
+			_ => panic!("expected a definition conflict"),
+		}
+		assert_eq!(registry.iter_resolved().count(), 3);
+
+		// Conflicts registered outside of transactions do not fail later transactions.
+		registry.compact_type(&B::meta_type());
+		assert!(registry.transact(|tx| tx.register::<u64>()).is_ok());
+	}
+
+	#[test]
+	fn iter_resolved_resolves_strings() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<bool>>::meta_type());
+		let resolved = registry.iter_resolved().collect::<Vec<_>>();
+		assert_eq!(resolved.len(), 2);
+		let (symbol, id, def) = &resolved[0];
+		assert_eq!(*symbol, option);
+		match id {
+			TypeId::Custom(custom) => {
+				assert_eq!(*custom.name(), "Option");
+				assert!(custom.namespace().segments().is_empty());
+			}
+			_ => panic!("expected a custom type identifier"),
+		}
+		match def {
+			TypeDef::Variant(def) => assert_eq!(*def.variants()[1].name(), "Some"),
+			_ => panic!("expected a variant definition"),
+		}
+	}
 }
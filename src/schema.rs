@@ -0,0 +1,1616 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A textual schema language for type definitions.
+//!
+//! Schemas allow to author or patch type definitions by hand, e.g. for types of
+//! foreign languages, and to review the contents of a registry. A `Schema` is parsed
+//! from text via `str::parse` and printed back via `Display`. Converting a registry
+//! into a schema declares all of its custom types. A hand-written schema can then be
+//! merged into the schema of a derive-generated registry.
+//!
+//! Since type definitions of the meta form refer to actual Rust types a schema owns
+//! its declarations instead and refers to types by their paths.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::schema::Schema;
+//! let schema: Schema = "type app::Foo { a: u32, b: Vec<app::Bar> }".parse().unwrap();
+//! assert_eq!(schema.to_string(), "type app::Foo {\n\ta: u32,\n\tb: Vec<app::Bar>,\n}\n");
+//! ```
+//!
+//! # Grammar
+//!
+//! Whitespace separates tokens and `//` starts a comment until the end of the line.
+//!
+//! ```text
+//! schema       = { decl } ;
+//! decl         = { annotation } ( kind custom ";"
+//!                               | "raw" custom ":" format ";"
+//!                               | "type" custom [ "{" fields "}" | "(" types ")" ";" | ";" ]
+//!                               | "enum" custom "{" variants "}"
+//!                               | "union" custom "{" fields "}" ) ;
+//! kind         = "builtin" | "json" | "tombstone" ;
+//! format       = "json" | "scale" ;
+//! fields       = [ field { "," field } [ "," ] ] ;
+//! field        = { annotation } ident ":" type ;
+//! types        = [ type { "," type } [ "," ] ] ;
+//! variants     = [ variant { "," variant } [ "," ] ] ;
+//! variant      = { annotation } ident [ "(" types ")" | "{" fields "}" ] [ "=" integer ] ;
+//! type         = primitive | custom | "[" type [ ";" integer ] "]" | "(" [ type "," types ] ")" ;
+//! custom       = ident { "::" ident } [ "<" params ">" ] ;
+//! params       = [ param { "," param } [ "," ] ] ;
+//! param        = type | "true" | "false" | [ "-" ] integer ;
+//! annotation   = "@" ident [ "(" arguments ")" ] ;
+//! ```
+//!
+//! A `type` declaration with braces declares a struct, with parentheses a tuple struct
+//! and without either a unit struct. A `raw` declaration declares an opaque payload
+//! in the given format. Either all or none of the variants of an enum have
+//! a discriminant. Enums whose variants all have a discriminant but no fields are C-like
+//! enums. The following annotations are supported:
+//!
+//! - `@id("...")`: The stable identifier of the declared type.
+//! - `@tag("...")`, `@content("...")` and `@untagged`: The representation of an enum.
+//! - `@niche`: The fieldless variant of an enum is encoded as the zero value of the field
+//!   of the other variant.
+//! - `@discriminator(a.b; 0 => x, -1 => y, "z" => z)`: The field path and mapping that selects
+//!   the active member of a union by an integer or string value.
+//! - `@group("...")`: The label of the logical section of a named field.
+//! - `@sensitivity(secret)`: The sensitivity of a named field, `internal` or `secret`.
+//! - `@display("...")`: The format hint for displaying the values of a named field, e.g. `"hex"`.
+//! - `@code(42)`: The application-level error code of an enum variant.
+
+use crate::tm_std::*;
+use crate::{
+	form::{Form, OwnedForm, ResolvedForm},
+	interner::UntrackedSymbol,
+	registry::Resolve,
+	Builtin, ConstParam, DiscriminantValue, EnumRepr, EnumVariant, MetaType, NamedField, RawFormat, Registry,
+	Sensitivity, TypeDef, TypeId, TypeIdPrimitive,
+};
+
+/// All raw payload formats by their names.
+const RAW_FORMATS: [RawFormat; 2] = [RawFormat::Json, RawFormat::Scale];
+
+/// All primitive types by their names.
+const PRIMITIVES: [TypeIdPrimitive; 13] = [
+	TypeIdPrimitive::Bool,
+	TypeIdPrimitive::Char,
+	TypeIdPrimitive::Str,
+	TypeIdPrimitive::U8,
+	TypeIdPrimitive::U16,
+	TypeIdPrimitive::U32,
+	TypeIdPrimitive::U64,
+	TypeIdPrimitive::U128,
+	TypeIdPrimitive::I8,
+	TypeIdPrimitive::I16,
+	TypeIdPrimitive::I32,
+	TypeIdPrimitive::I64,
+	TypeIdPrimitive::I128,
+];
+
+/// A schema of type declarations.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct Schema {
+	/// The declared types in order of their declaration.
+	pub decls: Vec<Decl>,
+}
+
+impl Schema {
+	/// Merges the given schema into this one.
+	///
+	/// Declarations of the given schema replace the declarations of the same types
+	/// in this schema. All other declarations are appended in order.
+	pub fn merge(&mut self, other: Schema) {
+		for decl in other.decls {
+			match self.decls.iter_mut().find(|existing| existing.ty == decl.ty) {
+				Some(existing) => *existing = decl,
+				None => self.decls.push(decl),
+			}
+		}
+	}
+}
+
+/// A declaration of a custom type.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Decl {
+	/// The declared custom type.
+	pub ty: TypeRef,
+	/// The stable identifier of the type, if any.
+	pub id: Option<String>,
+	/// The definition of the type.
+	pub def: Def,
+}
+
+/// A reference to a type.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TypeRef {
+	/// A primitive type.
+	Primitive(TypeIdPrimitive),
+	/// A custom type.
+	Custom {
+		/// The segments of the namespace of the type.
+		namespace: Vec<String>,
+		/// The name of the type.
+		name: String,
+		/// The generic type parameters of the type.
+		params: Vec<TypeRef>,
+		/// The values of the const generic parameters of the type.
+		consts: Vec<ConstParam>,
+	},
+	/// A slice type.
+	Slice(Box<TypeRef>),
+	/// An array type.
+	Array {
+		/// The length of the array.
+		len: u16,
+		/// The element type of the array.
+		ty: Box<TypeRef>,
+	},
+	/// A tuple type.
+	Tuple(Vec<TypeRef>),
+}
+
+/// The definition of a declared type.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Def {
+	/// A type that is built into the format.
+	Builtin,
+	/// An arbitrary JSON value.
+	Json,
+	/// A retired type whose definition has been removed.
+	Tombstone,
+	/// An opaque payload in the given format.
+	Raw(RawFormat),
+	/// A struct with named fields.
+	Struct {
+		/// The named fields of the struct.
+		fields: Vec<Field>,
+	},
+	/// A tuple-struct with unnamed fields.
+	TupleStruct {
+		/// The types of the unnamed fields of the tuple-struct.
+		types: Vec<TypeRef>,
+	},
+	/// A C-like enum with simple named variants.
+	ClikeEnum {
+		/// The variants of the C-like enum.
+		variants: Vec<ClikeVariant>,
+	},
+	/// A Rust enum with different kinds of variants.
+	Enum {
+		/// The variants of the enum.
+		variants: Vec<Variant>,
+		/// The representation of the enum upon serialization.
+		repr: Repr,
+		/// Whether the fieldless variant is encoded as the zero value of the field of the other variant.
+		niche: bool,
+	},
+	/// An unsafe Rust union type.
+	Union {
+		/// The fields of the union.
+		fields: Vec<Field>,
+		/// The description of the field that selects the active member, if any.
+		discriminator: Option<Discriminator>,
+	},
+}
+
+/// A named field.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Field {
+	/// The name of the field.
+	pub name: String,
+	/// The type of the field.
+	pub ty: TypeRef,
+	/// The label of the logical section the field belongs to, if any.
+	pub group: Option<String>,
+	/// How sensitive the values of the field are.
+	pub sensitivity: Sensitivity,
+	/// The format hint for displaying the values of the field, if any.
+	pub display: Option<String>,
+}
+
+/// A C-like enum variant.
+#[derive(PartialEq, Eq, Debug)]
+pub struct ClikeVariant {
+	/// The name of the variant.
+	pub name: String,
+	/// The discriminant of the variant.
+	pub discriminant: u64,
+	/// The application-level error code of the variant, if any.
+	pub code: Option<u32>,
+}
+
+/// A Rust enum variant.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Variant {
+	/// A unit struct variant.
+	Unit {
+		/// The name of the variant.
+		name: String,
+		/// The application-level error code of the variant, if any.
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		discriminant: Option<u64>,
+	},
+	/// A struct variant with named fields.
+	Struct {
+		/// The name of the variant.
+		name: String,
+		/// The named fields of the variant.
+		fields: Vec<Field>,
+		/// The application-level error code of the variant, if any.
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		discriminant: Option<u64>,
+	},
+	/// A tuple-struct variant with unnamed fields.
+	TupleStruct {
+		/// The name of the variant.
+		name: String,
+		/// The types of the unnamed fields of the variant.
+		types: Vec<TypeRef>,
+		/// The application-level error code of the variant, if any.
+		code: Option<u32>,
+		/// The discriminant of the variant, if any.
+		discriminant: Option<u64>,
+	},
+}
+
+impl Variant {
+	/// Returns the discriminant of the variant, if any.
+	pub fn discriminant(&self) -> Option<u64> {
+		match self {
+			Variant::Unit { discriminant, .. }
+			| Variant::Struct { discriminant, .. }
+			| Variant::TupleStruct { discriminant, .. } => *discriminant,
+		}
+	}
+}
+
+/// The representation of an enum upon serialization.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Repr {
+	/// The variant is externally tagged.
+	External,
+	/// The variant is internally tagged.
+	Internal {
+		/// The name of the field holding the variant name.
+		tag: String,
+	},
+	/// The variant is adjacently tagged.
+	Adjacent {
+		/// The name of the field holding the variant name.
+		tag: String,
+		/// The name of the field holding the variant contents.
+		content: String,
+	},
+	/// The variant is not tagged at all.
+	Untagged,
+}
+
+/// The description of the field that selects the active member of a union.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Discriminator {
+	/// The path of field names leading to the discriminating field.
+	pub field: Vec<String>,
+	/// The union member selected by each value of the discriminating field.
+	pub mapping: Vec<(DiscriminantValue<OwnedForm>, String)>,
+}
+
+impl From<&Registry> for Schema {
+	/// Declares all custom types of the given registry in order of their type symbols.
+	///
+	/// References to deferred types are declared by their type identifiers only.
+	fn from(registry: &Registry) -> Self {
+		let declarations = Declarations { registry };
+		Self {
+			decls: registry
+				.iter_resolved()
+				.filter_map(|(symbol, id, def)| match id {
+					TypeId::Custom(custom) => Some(Decl {
+						ty: declarations.type_ref(symbol),
+						id: custom.stable_id().map(|id| id.to_string()),
+						def: declarations.def(&def),
+					}),
+					_ => None,
+				})
+				.collect(),
+		}
+	}
+}
+
+/// Returns the type reference of the given type identifier whose type parameters
+/// are turned into type references by the given function.
+pub(crate) fn type_ref<F, P>(id: &TypeId<F>, param: P) -> TypeRef
+where
+	F: Form<String = &'static str, IndirectTypeId = <F as Form>::TypeId>,
+	P: Fn(&F::TypeId) -> TypeRef,
+{
+	match id {
+		TypeId::Custom(custom) => TypeRef::Custom {
+			namespace: custom
+				.namespace()
+				.segments()
+				.iter()
+				.map(|segment| segment.to_string())
+				.collect(),
+			name: custom.name().to_string(),
+			params: custom.type_params().iter().map(param).collect(),
+			consts: custom.const_params().to_vec(),
+		},
+		TypeId::Slice(slice) => TypeRef::Slice(Box::new(param(slice.type_param()))),
+		TypeId::Array(array) => TypeRef::Array {
+			len: array.len,
+			ty: Box::new(param(&array.type_param)),
+		},
+		TypeId::Tuple(tuple) => TypeRef::Tuple(tuple.type_params.iter().map(param).collect()),
+		TypeId::Primitive(primitive) => TypeRef::Primitive(primitive.clone()),
+	}
+}
+
+/// Returns the type reference of the given type.
+fn meta_type_ref(ty: &MetaType) -> TypeRef {
+	type_ref(&ty.type_id(), meta_type_ref)
+}
+
+/// Returns the declared representation of the given enum representation.
+fn repr(repr: &EnumRepr<ResolvedForm>) -> Repr {
+	match repr {
+		EnumRepr::External => Repr::External,
+		EnumRepr::Internal { tag } => Repr::Internal { tag: tag.to_string() },
+		EnumRepr::Adjacent { tag, content } => Repr::Adjacent {
+			tag: tag.to_string(),
+			content: content.to_string(),
+		},
+		EnumRepr::Untagged => Repr::Untagged,
+	}
+}
+
+/// Declares the types of a registry.
+struct Declarations<'a> {
+	/// The registry of the types.
+	registry: &'a Registry,
+}
+
+impl Declarations<'_> {
+	/// Returns the type reference of the given type symbol.
+	///
+	/// Type symbols that do not originate from the registry refer to the unit type.
+	fn type_ref(&self, symbol: UntrackedSymbol<AnyTypeId>) -> TypeRef {
+		match self.registry.resolve_type(symbol) {
+			Some(id_def) => type_ref(&id_def.id().resolve(self.registry), |param| self.type_ref(*param)),
+			None => match self.registry.deferred_type(symbol) {
+				Some(ty) => meta_type_ref(ty),
+				None => TypeRef::Tuple(Vec::new()),
+			},
+		}
+	}
+
+	/// Returns the declared named fields.
+	fn fields(&self, fields: &[NamedField<ResolvedForm>]) -> Vec<Field> {
+		fields
+			.iter()
+			.map(|field| Field {
+				name: field.name().to_string(),
+				ty: self.type_ref(*field.ty()),
+				group: field.group().map(|group| group.to_string()),
+				sensitivity: field.sensitivity(),
+				display: field.display().map(|display| display.to_string()),
+			})
+			.collect()
+	}
+
+	/// Returns the declared fields of the given fields that are all named.
+	fn named_fields(&self, fields: &[crate::Field<ResolvedForm>]) -> Vec<Field> {
+		fields
+			.iter()
+			.map(|field| Field {
+				name: field.name().map(ToString::to_string).unwrap_or_default(),
+				ty: self.type_ref(*field.ty()),
+				group: field.group().map(|group| group.to_string()),
+				sensitivity: field.sensitivity(),
+				display: field.display().map(|display| display.to_string()),
+			})
+			.collect()
+	}
+
+	/// Returns the declared types of the given type symbols.
+	fn types<'b, I>(&self, symbols: I) -> Vec<TypeRef>
+	where
+		I: IntoIterator<Item = &'b UntrackedSymbol<AnyTypeId>>,
+	{
+		symbols.into_iter().map(|symbol| self.type_ref(*symbol)).collect()
+	}
+
+	/// Returns the declared definition of the given type definition.
+	fn def(&self, def: &TypeDef<ResolvedForm>) -> Def {
+		match def {
+			TypeDef::Builtin(Builtin::Builtin) => Def::Builtin,
+			TypeDef::Builtin(Builtin::Json) => Def::Json,
+			TypeDef::Builtin(Builtin::Tombstone) => Def::Tombstone,
+			TypeDef::Builtin(Builtin::Raw(format)) => Def::Raw(*format),
+			TypeDef::Struct(def) => Def::Struct {
+				fields: self.fields(def.fields()),
+			},
+			TypeDef::TupleStruct(def) => Def::TupleStruct {
+				types: self.types(def.fields().iter().map(|field| field.ty())),
+			},
+			TypeDef::Composite(def) if def.is_named() => Def::Struct {
+				fields: self.named_fields(def.fields()),
+			},
+			TypeDef::Composite(def) => Def::TupleStruct {
+				types: self.types(def.fields().iter().map(|field| field.ty())),
+			},
+			TypeDef::ClikeEnum(def) => Def::ClikeEnum {
+				variants: def
+					.variants()
+					.iter()
+					.map(|variant| ClikeVariant {
+						name: variant.name().to_string(),
+						discriminant: variant.discriminant(),
+						code: variant.code(),
+					})
+					.collect(),
+			},
+			TypeDef::Enum(def) => Def::Enum {
+				variants: def
+					.variants()
+					.iter()
+					.map(|variant| match variant {
+						EnumVariant::Unit(variant) => Variant::Unit {
+							name: variant.name().to_string(),
+							code: variant.code(),
+							discriminant: None,
+						},
+						EnumVariant::Struct(variant) => Variant::Struct {
+							name: variant.name().to_string(),
+							fields: self.fields(variant.fields()),
+							code: variant.code(),
+							discriminant: None,
+						},
+						EnumVariant::TupleStruct(variant) => Variant::TupleStruct {
+							name: variant.name().to_string(),
+							types: self.types(variant.fields().iter().map(|field| field.ty())),
+							code: variant.code(),
+							discriminant: None,
+						},
+					})
+					.collect(),
+				repr: repr(def.repr()),
+				niche: false,
+			},
+			TypeDef::Variant(def) if def.is_clike() => Def::ClikeEnum {
+				variants: def
+					.variants()
+					.iter()
+					.map(|variant| ClikeVariant {
+						name: variant.name().to_string(),
+						discriminant: variant.discriminant().unwrap_or_default(),
+						code: variant.code(),
+					})
+					.collect(),
+			},
+			TypeDef::Variant(def) => Def::Enum {
+				variants: def
+					.variants()
+					.iter()
+					.map(|variant| {
+						let (name, code, discriminant) =
+							(variant.name().to_string(), variant.code(), variant.discriminant());
+						if variant.fields().is_empty() {
+							Variant::Unit {
+								name,
+								code,
+								discriminant,
+							}
+						} else if variant.fields().iter().all(|field| field.name().is_some()) {
+							Variant::Struct {
+								name,
+								fields: self.named_fields(variant.fields()),
+								code,
+								discriminant,
+							}
+						} else {
+							Variant::TupleStruct {
+								name,
+								types: self.types(variant.fields().iter().map(|field| field.ty())),
+								code,
+								discriminant,
+							}
+						}
+					})
+					.collect(),
+				repr: repr(def.repr()),
+				niche: def.is_niche(),
+			},
+			TypeDef::Union(def) => Def::Union {
+				fields: self.fields(def.fields()),
+				discriminator: def.discriminator().map(|discriminator| Discriminator {
+					field: discriminator
+						.field_path()
+						.iter()
+						.map(|segment| segment.to_string())
+						.collect(),
+					mapping: discriminator
+						.mapping()
+						.iter()
+						.map(|mapping| {
+							let value = match *mapping.value() {
+								DiscriminantValue::UInt(value) => DiscriminantValue::UInt(value),
+								DiscriminantValue::Int(value) => DiscriminantValue::Int(value),
+								DiscriminantValue::Str(value) => DiscriminantValue::Str(value.to_string()),
+							};
+							(value, mapping.member().to_string())
+						})
+						.collect(),
+				}),
+			},
+		}
+	}
+}
+
+/// Writes the given string as a string literal.
+fn write_str_lit(f: &mut Formatter, value: &str) -> Result<(), FmtError> {
+	f.write_str("\"")?;
+	for c in value.chars() {
+		if c == '"' || c == '\\' {
+			f.write_str("\\")?;
+		}
+		write!(f, "{}", c)?;
+	}
+	f.write_str("\"")
+}
+
+/// Writes the given items separated by commas.
+fn write_list<T: Display>(f: &mut Formatter, items: &[T]) -> Result<(), FmtError> {
+	for (index, item) in items.iter().enumerate() {
+		if index > 0 {
+			f.write_str(", ")?;
+		}
+		write!(f, "{}", item)?;
+	}
+	Ok(())
+}
+
+/// Writes the given named fields, each on its own line with the given indentation.
+fn write_fields(f: &mut Formatter, fields: &[Field], indent: &str) -> Result<(), FmtError> {
+	if fields.is_empty() {
+		return f.write_str("{}");
+	}
+	f.write_str("{\n")?;
+	for field in fields {
+		writeln!(f, "{}\t{},", indent, field)?;
+	}
+	write!(f, "{}}}", indent)
+}
+
+/// Writes the given optional error code annotation.
+fn write_code(f: &mut Formatter, code: Option<u32>) -> Result<(), FmtError> {
+	match code {
+		Some(code) => write!(f, "@code({}) ", code),
+		None => Ok(()),
+	}
+}
+
+impl Display for Schema {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		for (index, decl) in self.decls.iter().enumerate() {
+			if index > 0 {
+				f.write_str("\n")?;
+			}
+			writeln!(f, "{}", decl)?;
+		}
+		Ok(())
+	}
+}
+
+impl Display for Decl {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		if let Some(id) = &self.id {
+			f.write_str("@id(")?;
+			write_str_lit(f, id)?;
+			f.write_str(")\n")?;
+		}
+		match &self.def {
+			Def::Builtin => write!(f, "builtin {};", self.ty),
+			Def::Json => write!(f, "json {};", self.ty),
+			Def::Tombstone => write!(f, "tombstone {};", self.ty),
+			Def::Raw(format) => write!(f, "raw {}: {};", self.ty, format.name()),
+			Def::Struct { fields } => {
+				write!(f, "type {} ", self.ty)?;
+				write_fields(f, fields, "")
+			}
+			Def::TupleStruct { types } if types.is_empty() => write!(f, "type {};", self.ty),
+			Def::TupleStruct { types } => {
+				write!(f, "type {}(", self.ty)?;
+				write_list(f, types)?;
+				f.write_str(");")
+			}
+			Def::ClikeEnum { variants } => {
+				writeln!(f, "enum {} {{", self.ty)?;
+				for variant in variants {
+					f.write_str("\t")?;
+					write_code(f, variant.code)?;
+					writeln!(f, "{} = {},", variant.name, variant.discriminant)?;
+				}
+				f.write_str("}")
+			}
+			Def::Enum { variants, repr, niche } => {
+				if *niche {
+					f.write_str("@niche\n")?;
+				}
+				match repr {
+					Repr::External => (),
+					Repr::Internal { tag } => {
+						f.write_str("@tag(")?;
+						write_str_lit(f, tag)?;
+						f.write_str(")\n")?;
+					}
+					Repr::Adjacent { tag, content } => {
+						f.write_str("@tag(")?;
+						write_str_lit(f, tag)?;
+						f.write_str(") @content(")?;
+						write_str_lit(f, content)?;
+						f.write_str(")\n")?;
+					}
+					Repr::Untagged => f.write_str("@untagged\n")?,
+				}
+				writeln!(f, "enum {} {{", self.ty)?;
+				for variant in variants {
+					writeln!(f, "\t{},", variant)?;
+				}
+				f.write_str("}")
+			}
+			Def::Union { fields, discriminator } => {
+				if let Some(discriminator) = discriminator {
+					write!(f, "@discriminator({};", discriminator.field.join("."))?;
+					for (index, (value, member)) in discriminator.mapping.iter().enumerate() {
+						let separator = if index > 0 { "," } else { "" };
+						write!(f, "{} ", separator)?;
+						match value {
+							DiscriminantValue::UInt(value) => write!(f, "{}", value)?,
+							DiscriminantValue::Int(value) => write!(f, "{}", value)?,
+							DiscriminantValue::Str(value) => write_str_lit(f, value)?,
+						}
+						write!(f, " => {}", member)?;
+					}
+					f.write_str(")\n")?;
+				}
+				write!(f, "union {} ", self.ty)?;
+				write_fields(f, fields, "")
+			}
+		}
+	}
+}
+
+impl Display for TypeRef {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			TypeRef::Primitive(primitive) => f.write_str(primitive.name()),
+			TypeRef::Custom {
+				namespace,
+				name,
+				params,
+				consts,
+			} => {
+				for segment in namespace {
+					write!(f, "{}::", segment)?;
+				}
+				f.write_str(name)?;
+				if !params.is_empty() || !consts.is_empty() {
+					f.write_str("<")?;
+					write_list(f, params)?;
+					if !params.is_empty() && !consts.is_empty() {
+						f.write_str(", ")?;
+					}
+					write_list(f, consts)?;
+					f.write_str(">")?;
+				}
+				Ok(())
+			}
+			TypeRef::Slice(ty) => write!(f, "[{}]", ty),
+			TypeRef::Array { len, ty } => write!(f, "[{}; {}]", ty, len),
+			TypeRef::Tuple(types) => {
+				f.write_str("(")?;
+				write_list(f, types)?;
+				if types.len() == 1 {
+					f.write_str(",")?;
+				}
+				f.write_str(")")
+			}
+		}
+	}
+}
+
+impl Display for Field {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		if let Some(group) = &self.group {
+			f.write_str("@group(")?;
+			write_str_lit(f, group)?;
+			f.write_str(") ")?;
+		}
+		if !self.sensitivity.is_public() {
+			write!(f, "@sensitivity({}) ", self.sensitivity.name())?;
+		}
+		if let Some(display) = &self.display {
+			f.write_str("@display(")?;
+			write_str_lit(f, display)?;
+			f.write_str(") ")?;
+		}
+		write!(f, "{}: {}", self.name, self.ty)
+	}
+}
+
+impl Display for Variant {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			Variant::Unit { name, code, .. } => {
+				write_code(f, *code)?;
+				f.write_str(name)?;
+			}
+			Variant::Struct { name, fields, code, .. } => {
+				write_code(f, *code)?;
+				write!(f, "{} ", name)?;
+				write_fields(f, fields, "\t")?;
+			}
+			Variant::TupleStruct { name, types, code, .. } => {
+				write_code(f, *code)?;
+				write!(f, "{}(", name)?;
+				write_list(f, types)?;
+				f.write_str(")")?;
+			}
+		}
+		if let Some(discriminant) = self.discriminant() {
+			write!(f, " = {}", discriminant)?;
+		}
+		Ok(())
+	}
+}
+
+/// An error that may be encountered upon parsing a schema.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+	/// If the input does not match the grammar.
+	Unexpected {
+		/// The byte offset of the unexpected input.
+		offset: usize,
+		/// A description of the expected input.
+		expected: &'static str,
+	},
+	/// If an annotation is unknown or not supported at its position.
+	InvalidAnnotation {
+		/// The byte offset of the annotation.
+		offset: usize,
+	},
+	/// If an integer literal is out of the range of its target type.
+	IntegerOverflow {
+		/// The byte offset of the integer literal.
+		offset: usize,
+	},
+}
+
+impl FromStr for Schema {
+	type Err = ParseError;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser::new(tokenize(input)?, input.len());
+		let mut decls = Vec::new();
+		while !parser.is_done() {
+			decls.push(parser.decl()?);
+		}
+		Ok(Self { decls })
+	}
+}
+
+/// A token of the schema language.
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Token {
+	/// An identifier or keyword.
+	Ident(String),
+	/// An unsigned integer literal.
+	Int(u64),
+	/// A string literal.
+	Str(String),
+	/// A punctuation.
+	Punct(&'static str),
+}
+
+/// All punctuations, multi-character punctuations first.
+const PUNCTS: [&str; 17] = [
+	"::", "=>", "{", "}", "(", ")", "<", ">", "[", "]", ";", ":", ",", "=", "@", ".", "-",
+];
+
+/// Splits the given input into tokens and their byte offsets.
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+	let mut tokens = Vec::new();
+	let mut offset = 0;
+	while let Some(rest) = input.get(offset..) {
+		let c = match rest.chars().next() {
+			Some(c) => c,
+			None => break,
+		};
+		let start = offset;
+		if c.is_whitespace() {
+			offset += c.len_utf8();
+			continue;
+		}
+		if rest.starts_with("//") {
+			offset += rest.find('\n').unwrap_or(rest.len());
+			continue;
+		}
+		let token = if c.is_ascii_alphabetic() || c == '_' {
+			let ident = rest
+				.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+				.next()
+				.unwrap_or_default();
+			offset += ident.len();
+			Token::Ident(ident.to_string())
+		} else if c.is_ascii_digit() {
+			let digits = rest.split(|c: char| !c.is_ascii_digit()).next().unwrap_or_default();
+			offset += digits.len();
+			Token::Int(
+				digits
+					.parse()
+					.map_err(|_| ParseError::IntegerOverflow { offset: start })?,
+			)
+		} else if c == '"' {
+			let mut value = String::new();
+			let mut chars = rest.char_indices().skip(1);
+			loop {
+				match chars.next() {
+					Some((index, '"')) => {
+						offset += index + 1;
+						break;
+					}
+					Some((_, '\\')) => match chars.next() {
+						Some((_, c)) if c == '"' || c == '\\' => value.push(c),
+						_ => {
+							return Err(ParseError::Unexpected {
+								offset: start,
+								expected: "an escaped `\"` or `\\`",
+							})
+						}
+					},
+					Some((_, c)) => value.push(c),
+					None => {
+						return Err(ParseError::Unexpected {
+							offset: start,
+							expected: "a terminated string literal",
+						})
+					}
+				}
+			}
+			Token::Str(value)
+		} else if let Some(punct) = PUNCTS.iter().find(|punct| rest.starts_with(*punct)) {
+			offset += punct.len();
+			Token::Punct(punct)
+		} else {
+			return Err(ParseError::Unexpected {
+				offset,
+				expected: "a token",
+			});
+		};
+		tokens.push((start, token));
+	}
+	Ok(tokens)
+}
+
+/// A generic parameter of a custom type reference.
+enum GenericParam {
+	/// A generic type parameter.
+	Type(TypeRef),
+	/// The value of a const generic parameter.
+	Const(ConstParam),
+}
+
+/// An annotation with the tokens of its arguments.
+struct Annotation {
+	/// The byte offset of the annotation.
+	offset: usize,
+	/// The name of the annotation.
+	name: String,
+	/// A parser of the arguments of the annotation.
+	args: Parser,
+}
+
+/// A recursive descent parser of the schema language.
+struct Parser {
+	/// The tokens to parse and their byte offsets.
+	tokens: Vec<(usize, Token)>,
+	/// The index of the next token.
+	position: usize,
+	/// The byte offset of the end of the input.
+	end: usize,
+}
+
+impl Parser {
+	/// Creates a parser of the given tokens.
+	fn new(tokens: Vec<(usize, Token)>, end: usize) -> Self {
+		Self {
+			tokens,
+			position: 0,
+			end,
+		}
+	}
+
+	/// Returns `true` if all tokens have been parsed.
+	fn is_done(&self) -> bool {
+		self.position == self.tokens.len()
+	}
+
+	/// Returns the byte offset of the next token.
+	fn offset(&self) -> usize {
+		self.tokens
+			.get(self.position)
+			.map(|(offset, _)| *offset)
+			.unwrap_or(self.end)
+	}
+
+	/// Returns an error that the next token does not match the given expectation.
+	fn unexpected(&self, expected: &'static str) -> ParseError {
+		ParseError::Unexpected {
+			offset: self.offset(),
+			expected,
+		}
+	}
+
+	/// Returns the next token and advances the parser.
+	fn next(&mut self) -> Option<Token> {
+		let token = self.tokens.get(self.position).map(|(_, token)| token.clone());
+		if token.is_some() {
+			self.position += 1;
+		}
+		token
+	}
+
+	/// Returns `true` if the next token is the given punctuation.
+	fn is_punct(&self, punct: &str) -> bool {
+		match self.tokens.get(self.position) {
+			Some((_, Token::Punct(next))) => *next == punct,
+			_ => false,
+		}
+	}
+
+	/// Advances the parser if the next token is the given punctuation.
+	fn eat_punct(&mut self, punct: &str) -> bool {
+		let is_punct = self.is_punct(punct);
+		if is_punct {
+			self.position += 1;
+		}
+		is_punct
+	}
+
+	/// Expects the next token to be the given punctuation.
+	fn expect_punct(&mut self, punct: &'static str) -> Result<(), ParseError> {
+		if self.eat_punct(punct) {
+			Ok(())
+		} else {
+			Err(self.unexpected(punct))
+		}
+	}
+
+	/// Expects all tokens to be parsed.
+	fn expect_done(&self) -> Result<(), ParseError> {
+		if self.is_done() {
+			Ok(())
+		} else {
+			Err(self.unexpected("the end of the input"))
+		}
+	}
+
+	/// Parses an identifier.
+	fn ident(&mut self) -> Result<String, ParseError> {
+		match self.tokens.get(self.position) {
+			Some((_, Token::Ident(ident))) => {
+				let ident = ident.clone();
+				self.position += 1;
+				Ok(ident)
+			}
+			_ => Err(self.unexpected("an identifier")),
+		}
+	}
+
+	/// Parses an integer literal that fits into the target type.
+	fn int<T>(&mut self) -> Result<T, ParseError>
+	where
+		T: TryFrom<u64>,
+	{
+		match self.tokens.get(self.position) {
+			Some((offset, Token::Int(value))) => {
+				let value = T::try_from(*value).map_err(|_| ParseError::IntegerOverflow { offset: *offset })?;
+				self.position += 1;
+				Ok(value)
+			}
+			_ => Err(self.unexpected("an integer literal")),
+		}
+	}
+
+	/// Parses a string literal.
+	fn string(&mut self) -> Result<String, ParseError> {
+		match self.tokens.get(self.position) {
+			Some((_, Token::Str(value))) => {
+				let value = value.clone();
+				self.position += 1;
+				Ok(value)
+			}
+			_ => Err(self.unexpected("a string literal")),
+		}
+	}
+
+	/// Parses a comma separated list of items up to the given closing punctuation.
+	fn list<T, P>(&mut self, close: &'static str, mut item: P) -> Result<Vec<T>, ParseError>
+	where
+		P: FnMut(&mut Self) -> Result<T, ParseError>,
+	{
+		let mut items = Vec::new();
+		while !self.eat_punct(close) {
+			items.push(item(self)?);
+			if !self.eat_punct(",") {
+				self.expect_punct(close)?;
+				break;
+			}
+		}
+		Ok(items)
+	}
+
+	/// Parses the annotations in front of an item.
+	fn annotations(&mut self) -> Result<Vec<Annotation>, ParseError> {
+		let mut annotations = Vec::new();
+		while self.is_punct("@") {
+			let offset = self.offset();
+			self.position += 1;
+			let name = self.ident()?;
+			let mut args = Vec::new();
+			let mut end = self.offset();
+			if self.eat_punct("(") {
+				while !self.is_punct(")") {
+					end = self.offset();
+					match self.next() {
+						Some(token) => args.push((end, token)),
+						None => return Err(self.unexpected(")")),
+					}
+				}
+				end = self.offset();
+				self.position += 1;
+			}
+			annotations.push(Annotation {
+				offset,
+				name,
+				args: Parser::new(args, end),
+			});
+		}
+		Ok(annotations)
+	}
+
+	/// Parses a type reference.
+	fn type_ref(&mut self) -> Result<TypeRef, ParseError> {
+		if self.eat_punct("[") {
+			let ty = Box::new(self.type_ref()?);
+			if self.eat_punct(";") {
+				let len = self.int()?;
+				self.expect_punct("]")?;
+				return Ok(TypeRef::Array { len, ty });
+			}
+			self.expect_punct("]")?;
+			return Ok(TypeRef::Slice(ty));
+		}
+		if self.eat_punct("(") {
+			return Ok(TypeRef::Tuple(self.list(")", Self::type_ref)?));
+		}
+		let mut segments = Vec::new();
+		let mut name = self.ident()?;
+		while self.eat_punct("::") {
+			segments.push(core::mem::replace(&mut name, self.ident()?));
+		}
+		let (mut params, mut consts) = (Vec::new(), Vec::new());
+		if self.eat_punct("<") {
+			for param in self.list(">", Self::generic_param)? {
+				match param {
+					GenericParam::Type(ty) => params.push(ty),
+					GenericParam::Const(value) => consts.push(value),
+				}
+			}
+		}
+		if segments.is_empty() && params.is_empty() && consts.is_empty() {
+			if let Some(primitive) = PRIMITIVES.iter().find(|primitive| primitive.name() == name) {
+				return Ok(TypeRef::Primitive(primitive.clone()));
+			}
+		}
+		Ok(TypeRef::Custom {
+			namespace: segments,
+			name,
+			params,
+			consts,
+		})
+	}
+
+	/// Parses a negative integer literal, i.e. a `-` followed by an integer.
+	fn negative_int(&mut self) -> Result<i64, ParseError> {
+		self.expect_punct("-")?;
+		let offset = self.offset();
+		let value: u64 = self.int()?;
+		TryFrom::try_from(-(value as i128)).map_err(|_| ParseError::IntegerOverflow { offset })
+	}
+
+	/// Parses the value of a discriminating field, i.e. an integer or a string literal.
+	fn discriminant_value(&mut self) -> Result<DiscriminantValue<OwnedForm>, ParseError> {
+		match self.tokens.get(self.position) {
+			Some((_, Token::Str(_))) => self.string().map(DiscriminantValue::Str),
+			Some((_, Token::Punct("-"))) => self.negative_int().map(DiscriminantValue::Int),
+			_ => self.int().map(DiscriminantValue::UInt),
+		}
+	}
+
+	/// Parses a generic parameter, i.e. a type reference or the value of a const generic parameter.
+	fn generic_param(&mut self) -> Result<GenericParam, ParseError> {
+		match self.tokens.get(self.position) {
+			Some((_, Token::Int(_))) => Ok(GenericParam::Const(ConstParam::UInt(self.int()?))),
+			Some((_, Token::Ident(ident))) if ident == "true" || ident == "false" => {
+				let value = ident == "true";
+				self.position += 1;
+				Ok(GenericParam::Const(ConstParam::Bool(value)))
+			}
+			Some((_, Token::Punct("-"))) => self
+				.negative_int()
+				.map(|value| GenericParam::Const(ConstParam::Int(value))),
+			_ => self.type_ref().map(GenericParam::Type),
+		}
+	}
+
+	/// Parses a custom type reference.
+	fn custom_type_ref(&mut self) -> Result<TypeRef, ParseError> {
+		let offset = self.offset();
+		match self.type_ref()? {
+			ty @ TypeRef::Custom { .. } => Ok(ty),
+			_ => Err(ParseError::Unexpected {
+				offset,
+				expected: "a custom type",
+			}),
+		}
+	}
+
+	/// Parses a named field.
+	fn field(&mut self) -> Result<Field, ParseError> {
+		let mut group = None;
+		let mut sensitivity = Sensitivity::Public;
+		let mut display = None;
+		for mut annotation in self.annotations()? {
+			match annotation.name.as_str() {
+				"group" => group = Some(annotation.args.string()?),
+				"sensitivity" => {
+					let offset = annotation.args.offset();
+					sensitivity = Sensitivity::from_name(&annotation.args.ident()?)
+						.ok_or(ParseError::InvalidAnnotation { offset })?;
+				}
+				"display" => display = Some(annotation.args.string()?),
+				_ => {
+					return Err(ParseError::InvalidAnnotation {
+						offset: annotation.offset,
+					})
+				}
+			}
+			annotation.args.expect_done()?;
+		}
+		let name = self.ident()?;
+		self.expect_punct(":")?;
+		Ok(Field {
+			name,
+			ty: self.type_ref()?,
+			group,
+			sensitivity,
+			display,
+		})
+	}
+
+	/// Parses the variants of an enum.
+	fn enum_def(&mut self) -> Result<Def, ParseError> {
+		let mut variants: Vec<Variant> = Vec::new();
+		let offset = self.offset();
+		self.expect_punct("{")?;
+		while !self.eat_punct("}") {
+			let mut code = None;
+			for mut annotation in self.annotations()? {
+				match annotation.name.as_str() {
+					"code" => code = Some(annotation.args.int()?),
+					_ => {
+						return Err(ParseError::InvalidAnnotation {
+							offset: annotation.offset,
+						})
+					}
+				}
+				annotation.args.expect_done()?;
+			}
+			let name = self.ident()?;
+			let mut variant = if self.eat_punct("(") {
+				let types = self.list(")", Self::type_ref)?;
+				Variant::TupleStruct {
+					name,
+					types,
+					code,
+					discriminant: None,
+				}
+			} else if self.eat_punct("{") {
+				let fields = self.list("}", Self::field)?;
+				Variant::Struct {
+					name,
+					fields,
+					code,
+					discriminant: None,
+				}
+			} else {
+				Variant::Unit {
+					name,
+					code,
+					discriminant: None,
+				}
+			};
+			if self.eat_punct("=") {
+				let value = self.int()?;
+				match &mut variant {
+					Variant::Unit { discriminant, .. }
+					| Variant::Struct { discriminant, .. }
+					| Variant::TupleStruct { discriminant, .. } => *discriminant = Some(value),
+				}
+			}
+			if let Some(first) = variants.first() {
+				if first.discriminant().is_some() != variant.discriminant().is_some() {
+					return Err(ParseError::Unexpected {
+						offset,
+						expected: "discriminants for all or none of the variants",
+					});
+				}
+			}
+			variants.push(variant);
+			if !self.eat_punct(",") {
+				self.expect_punct("}")?;
+				break;
+			}
+		}
+		let clike = !variants.is_empty()
+			&& variants.iter().all(|variant| {
+				matches!(
+					variant,
+					Variant::Unit {
+						discriminant: Some(_),
+						..
+					}
+				)
+			});
+		if clike {
+			Ok(Def::ClikeEnum {
+				variants: variants
+					.into_iter()
+					.filter_map(|variant| match variant {
+						Variant::Unit {
+							name,
+							code,
+							discriminant: Some(discriminant),
+						} => Some(ClikeVariant {
+							name,
+							discriminant,
+							code,
+						}),
+						_ => None,
+					})
+					.collect(),
+			})
+		} else {
+			Ok(Def::Enum {
+				variants,
+				repr: Repr::External,
+				niche: false,
+			})
+		}
+	}
+
+	/// Parses a declaration.
+	fn decl(&mut self) -> Result<Decl, ParseError> {
+		let annotations = self.annotations()?;
+		let offset = self.offset();
+		let kind = self.ident()?;
+		let ty = self.custom_type_ref()?;
+		let mut def = match kind.as_str() {
+			"builtin" => {
+				self.expect_punct(";")?;
+				Def::Builtin
+			}
+			"json" => {
+				self.expect_punct(";")?;
+				Def::Json
+			}
+			"tombstone" => {
+				self.expect_punct(";")?;
+				Def::Tombstone
+			}
+			"raw" => {
+				self.expect_punct(":")?;
+				let offset = self.offset();
+				let format = self.ident()?;
+				let format = RAW_FORMATS
+					.iter()
+					.find(|raw_format| raw_format.name() == format)
+					.ok_or(ParseError::Unexpected {
+						offset,
+						expected: "`json` or `scale`",
+					})?;
+				self.expect_punct(";")?;
+				Def::Raw(*format)
+			}
+			"type" if self.eat_punct("{") => Def::Struct {
+				fields: self.list("}", Self::field)?,
+			},
+			"type" if self.eat_punct("(") => {
+				let types = self.list(")", Self::type_ref)?;
+				self.expect_punct(";")?;
+				Def::TupleStruct { types }
+			}
+			"type" => {
+				self.expect_punct(";")?;
+				Def::TupleStruct { types: Vec::new() }
+			}
+			"enum" => self.enum_def()?,
+			"union" => {
+				self.expect_punct("{")?;
+				Def::Union {
+					fields: self.list("}", Self::field)?,
+					discriminator: None,
+				}
+			}
+			_ => {
+				return Err(ParseError::Unexpected {
+					offset,
+					expected: "`builtin`, `json`, `raw`, `type`, `enum` or `union`",
+				})
+			}
+		};
+		let mut id = None;
+		let (mut tag, mut content, mut untagged) = (None, None, None);
+		for mut annotation in annotations {
+			let invalid = ParseError::InvalidAnnotation {
+				offset: annotation.offset,
+			};
+			let args = &mut annotation.args;
+			match (annotation.name.as_str(), &mut def) {
+				("id", _) => id = Some(args.string()?),
+				("tag", Def::Enum { .. }) => tag = Some((annotation.offset, args.string()?)),
+				("content", Def::Enum { .. }) => content = Some((annotation.offset, args.string()?)),
+				("untagged", Def::Enum { .. }) => untagged = Some(annotation.offset),
+				("niche", Def::Enum { niche, .. }) => *niche = true,
+				("discriminator", Def::Union { discriminator, .. }) => {
+					let mut field = vec![args.ident()?];
+					while args.eat_punct(".") {
+						field.push(args.ident()?);
+					}
+					args.expect_punct(";")?;
+					let mut mapping = Vec::new();
+					while !args.is_done() {
+						let value = args.discriminant_value()?;
+						args.expect_punct("=>")?;
+						mapping.push((value, args.ident()?));
+						if !args.eat_punct(",") {
+							break;
+						}
+					}
+					*discriminator = Some(Discriminator { field, mapping });
+				}
+				_ => return Err(invalid),
+			}
+			args.expect_done()?;
+		}
+		if let Def::Enum { repr, .. } = &mut def {
+			*repr = match (tag, content, untagged) {
+				(None, None, None) => Repr::External,
+				(None, None, Some(_)) => Repr::Untagged,
+				(Some((_, tag)), None, None) => Repr::Internal { tag },
+				(Some((_, tag)), Some((_, content)), None) => Repr::Adjacent { tag, content },
+				(_, _, Some(offset)) | (None, Some((offset, _)), None) => {
+					return Err(ParseError::InvalidAnnotation { offset })
+				}
+			};
+		}
+		Ok(Decl { ty, id, def })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Relies on boxes and references sharing the metadata of the referenced type.
+	#[cfg(not(feature = "reference-types"))]
+	#[test]
+	fn registry_round_trip() {
+		use crate::Metadata;
+
+		let mut registry = Registry::new();
+		registry.compact_type(&<Option<Vec<(u8, [bool; 2])>>>::meta_type());
+		registry.compact_type(&<Result<&[u32], String>>::meta_type());
+		let schema = Schema::from(&registry);
+		let text = schema.to_string();
+		assert_eq!(
+			text,
+			"enum Option<Vec<(u8, [bool; 2])>> {\n\tNone,\n\tSome(Vec<(u8, [bool; 2])>),\n}\n\n\
+			 type Vec<(u8, [bool; 2])> {\n\telems: [(u8, [bool; 2])],\n}\n\n\
+			 enum Result<[u32], str> {\n\tOk([u32]),\n\tErr(str),\n}\n\n\
+			 type Vec<u8> {\n\telems: [u8],\n}\n"
+		);
+		assert_eq!(text.parse::<Schema>(), Ok(schema));
+	}
+
+	#[test]
+	fn annotations_round_trip() {
+		let text = "\
+			@id(\"0a1b\")\n\
+			type app::Config {\n\t@group(\"limits\") max: u32,\n\t@sensitivity(secret) name: str,\n\t@display(\"hex\") key: [u8; 32],\n}\n\n\
+			@tag(\"type\") @content(\"value\")\n\
+			enum app::Error {\n\t@code(404) NotFound,\n\t@code(500) Internal {\n\t\treason: (u8,),\n\t},\n}\n\n\
+			enum app::Kind {\n\t@code(1) A = 0,\n\tB = 42,\n}\n\n\
+			@discriminator(header.kind; 0 => a, -1 => b, \"c\" => c)\n\
+			union app::Payload {\n\ta: u32,\n\tb: [u8; 4],\n\tc: bool,\n}\n\n\
+			type app::Unit;\n\n\
+			type app::Pair(u8, i128);\n";
+		let schema = text.parse::<Schema>().unwrap();
+		assert_eq!(schema.decls[0].id.as_deref(), Some("0a1b"));
+		assert_eq!(
+			schema.decls[3].def,
+			Def::Union {
+				fields: vec![
+					Field {
+						name: "a".to_string(),
+						ty: TypeRef::Primitive(TypeIdPrimitive::U32),
+						group: None,
+						sensitivity: Sensitivity::Public,
+						display: None,
+					},
+					Field {
+						name: "b".to_string(),
+						ty: TypeRef::Array {
+							len: 4,
+							ty: Box::new(TypeRef::Primitive(TypeIdPrimitive::U8)),
+						},
+						group: None,
+						sensitivity: Sensitivity::Public,
+						display: None,
+					},
+					Field {
+						name: "c".to_string(),
+						ty: TypeRef::Primitive(TypeIdPrimitive::Bool),
+						group: None,
+						sensitivity: Sensitivity::Public,
+						display: None,
+					},
+				],
+				discriminator: Some(Discriminator {
+					field: vec!["header".to_string(), "kind".to_string()],
+					mapping: vec![
+						(DiscriminantValue::UInt(0), "a".to_string()),
+						(DiscriminantValue::Int(-1), "b".to_string()),
+						(DiscriminantValue::Str("c".to_string()), "c".to_string()),
+					],
+				}),
+			}
+		);
+		assert_eq!(schema.to_string(), text);
+	}
+
+	#[test]
+	fn discriminated_variants_round_trip() {
+		let text = "enum app::Command {\n\tStop = 1,\n\tMove {\n\t\tx: i32,\n\t} = 4,\n\tSay(str) = 5,\n}\n";
+		let schema = text.parse::<Schema>().unwrap();
+		match &schema.decls[0].def {
+			Def::Enum { variants, .. } => {
+				let discriminants = variants.iter().map(Variant::discriminant).collect::<Vec<_>>();
+				assert_eq!(discriminants, vec![Some(1), Some(4), Some(5)]);
+			}
+			def => panic!("expected an enum declaration, got {:?}", def),
+		}
+		assert_eq!(schema.to_string(), text);
+	}
+
+	#[test]
+	fn raw_round_trip() {
+		let text = "raw a::Payload: scale;\n\nraw a::Document: json;\n";
+		let schema = text.parse::<Schema>().unwrap();
+		assert_eq!(schema.decls[0].def, Def::Raw(RawFormat::Scale));
+		assert_eq!(schema.to_string(), text);
+		assert_eq!(
+			"raw a::Payload: xml;".parse::<Schema>(),
+			Err(ParseError::Unexpected {
+				offset: 16,
+				expected: "`json` or `scale`",
+			})
+		);
+	}
+
+	#[test]
+	fn niche_round_trip() {
+		let text = "@niche\nenum Option<NonZeroU32> {\n\tNone,\n\tSome(NonZeroU32),\n}\n";
+		let schema = text.parse::<Schema>().unwrap();
+		assert!(matches!(schema.decls[0].def, Def::Enum { niche: true, .. }));
+		assert_eq!(schema.to_string(), text);
+		assert_eq!(
+			"@niche type a::A;".parse::<Schema>(),
+			Err(ParseError::InvalidAnnotation { offset: 0 })
+		);
+	}
+
+	#[test]
+	fn const_params_round_trip() {
+		let text = "type app::Buf<u8, 4, true, -1>(u8);\n";
+		let schema = text.parse::<Schema>().unwrap();
+		assert_eq!(
+			schema.decls[0].ty,
+			TypeRef::Custom {
+				namespace: vec!["app".to_string()],
+				name: "Buf".to_string(),
+				params: vec![TypeRef::Primitive(TypeIdPrimitive::U8)],
+				consts: vec![ConstParam::UInt(4), ConstParam::Bool(true), ConstParam::Int(-1)],
+			}
+		);
+		assert_eq!(schema.to_string(), text);
+		assert_eq!(
+			"type a::A<-9223372036854775809>;".parse::<Schema>(),
+			Err(ParseError::IntegerOverflow { offset: 11 })
+		);
+	}
+
+	#[test]
+	fn merge_replaces_declarations() {
+		let mut schema: Schema = "type a::A; type a::B;".parse().unwrap();
+		schema.merge("type a::B(u8); type a::C;".parse().unwrap());
+		assert_eq!(schema.to_string(), "type a::A;\n\ntype a::B(u8);\n\ntype a::C;\n");
+	}
+
+	#[test]
+	fn parse_errors() {
+		assert_eq!(
+			"type u8;".parse::<Schema>(),
+			Err(ParseError::Unexpected {
+				offset: 5,
+				expected: "a custom type",
+			})
+		);
+		assert_eq!(
+			"type a::A { b: [u8; 70000] }".parse::<Schema>(),
+			Err(ParseError::IntegerOverflow { offset: 20 })
+		);
+		assert_eq!(
+			"@group(\"g\") type a::A;".parse::<Schema>(),
+			Err(ParseError::InvalidAnnotation { offset: 0 })
+		);
+		assert_eq!(
+			"type a::A { @sensitivity(top) b: u8 }".parse::<Schema>(),
+			Err(ParseError::InvalidAnnotation { offset: 25 })
+		);
+		assert_eq!(
+			"enum a::A { B = 1, C }".parse::<Schema>(),
+			Err(ParseError::Unexpected {
+				offset: 10,
+				expected: "discriminants for all or none of the variants",
+			})
+		);
+		assert_eq!(
+			"type a::A".parse::<Schema>(),
+			Err(ParseError::Unexpected {
+				offset: 9,
+				expected: ";",
+			})
+		);
+	}
+}
@@ -0,0 +1,153 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A registry that can be shared between threads.
+//!
+//! Subsystems that register their types concurrently, e.g. during startup,
+//! can share a single `SharedRegistry` instead of wrapping a `Registry`
+//! into their own ad-hoc synchronization primitives.
+//!
+//! By default the synchronization is done by `std::sync::RwLock`.
+//! With the `parking_lot` feature enabled its `RwLock` is used instead.
+
+use crate::{interner::UntrackedSymbol, tm_std::*, MetaType, Registry};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+#[cfg(feature = "parking_lot")]
+use parking_lot::RwLock;
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{PoisonError, RwLock};
+
+/// A registry with interior synchronization.
+///
+/// Reads are performed concurrently while registrations are exclusive.
+/// Registering a type that has already been registered only requires read access.
+///
+/// # Note
+///
+/// The closures given to `read` are executed while holding the lock and thus
+/// must not call back into the same shared registry.
+#[derive(Debug, Default)]
+pub struct SharedRegistry {
+	/// The synchronized registry.
+	inner: RwLock<Registry>,
+}
+
+impl From<Registry> for SharedRegistry {
+	fn from(registry: Registry) -> Self {
+		Self {
+			inner: RwLock::new(registry),
+		}
+	}
+}
+
+impl SharedRegistry {
+	/// Creates a new empty shared registry.
+	pub fn new() -> Self {
+		Self::from(Registry::new())
+	}
+
+	/// Registers the given type into the registry and returns its associated type ID symbol.
+	///
+	/// See `RegistryBuilder::register_type` for further details.
+	pub fn register_type(&self, ty: &MetaType) -> UntrackedSymbol<AnyTypeId> {
+		if let Some(symbol) = self.read(|registry| registry.type_symbol_of(ty)) {
+			return symbol;
+		}
+		self.write(|registry| registry.compact_type(ty))
+	}
+
+	/// Registers the given string into the registry and returns its associated string symbol.
+	pub fn register_string(&self, string: &'static str) -> UntrackedSymbol<&'static str> {
+		self.write(|registry| registry.compact_string(string))
+	}
+
+	/// Runs the given closure with shared read access to the underlying registry.
+	pub fn read<F, R>(&self, f: F) -> R
+	where
+		F: FnOnce(&Registry) -> R,
+	{
+		#[cfg(feature = "parking_lot")]
+		let registry = self.inner.read();
+		#[cfg(not(feature = "parking_lot"))]
+		let registry = self.inner.read().unwrap_or_else(PoisonError::into_inner);
+		f(&registry)
+	}
+
+	/// Runs the given closure with exclusive write access to the underlying registry.
+	fn write<F, R>(&self, f: F) -> R
+	where
+		F: FnOnce(&mut Registry) -> R,
+	{
+		#[cfg(feature = "parking_lot")]
+		let mut registry = self.inner.write();
+		#[cfg(not(feature = "parking_lot"))]
+		let mut registry = self.inner.write().unwrap_or_else(PoisonError::into_inner);
+		f(&mut registry)
+	}
+
+	/// Consumes the shared registry and returns the underlying registry.
+	pub fn into_inner(self) -> Registry {
+		#[cfg(feature = "parking_lot")]
+		let registry = self.inner.into_inner();
+		#[cfg(not(feature = "parking_lot"))]
+		let registry = self.inner.into_inner().unwrap_or_else(PoisonError::into_inner);
+		registry
+	}
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SharedRegistry {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		self.read(|registry| registry.serialize(serializer))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+	use std::{sync::Arc, thread};
+
+	#[test]
+	fn concurrent_registration() {
+		let shared = Arc::new(SharedRegistry::new());
+		let handles = (0..4)
+			.map(|_| {
+				let shared = Arc::clone(&shared);
+				thread::spawn(move || {
+					(
+						shared.register_type(&<Vec<u32>>::meta_type()),
+						shared.register_type(&<Option<bool>>::meta_type()),
+					)
+				})
+			})
+			.collect::<Vec<_>>();
+		let symbols = handles
+			.into_iter()
+			.map(|handle| handle.join().unwrap())
+			.collect::<Vec<_>>();
+		assert!(symbols.windows(2).all(|pair| pair[0] == pair[1]));
+
+		let mut registry = Arc::try_unwrap(shared).unwrap().into_inner();
+		assert_eq!(registry.compact_type(&<Vec<u32>>::meta_type()), symbols[0].0);
+		assert_eq!(registry.compact_type(&<Option<bool>>::meta_type()), symbols[0].1);
+	}
+}
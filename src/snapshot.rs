@@ -0,0 +1,240 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Snapshot testing of registries.
+//!
+//! Use the `assert_registry_snapshot!` macro in order to compare the serialization of a
+//! registry against a snapshot file committed next to the tests. This turns accidental
+//! changes to the metadata of a crate into test failures with a readable diff.
+//!
+//! Snapshots are stored as pretty printed JSON in the nested layout within the `snapshots`
+//! directory of the crate under test. Missing snapshots are created upon the first run
+//! unless the `CI` environment variable is set. Set the `UPDATE_SNAPSHOTS` environment
+//! variable in order to overwrite snapshots that no longer match.
+//!
+//! Snapshots also guard the determinism of the serialized metadata across targets, e.g.
+//! between 32-bit and 64-bit ones, by comparing the snapshot of `register_corpus` produced
+//! on each target against the same committed file.
+
+// Test utilities report mismatches by panicking just like the assertions they complement.
+#![cfg_attr(
+	feature = "no-panic",
+	allow(clippy::expect_used, clippy::indexing_slicing, clippy::panic)
+)]
+
+use crate::{
+	layout::Layout, HasTypeDef, HasTypeId, Metadata, NamedField, Namespace, Registry, RegistryBuilder, TypeDef,
+	TypeDefComposite, TypeId, TypeIdCustom,
+};
+use core::{cmp::Ordering, marker::PhantomData, num::NonZeroU32, ops::ControlFlow, task::Poll};
+use std::{collections::BTreeMap, env, fs, path::Path};
+
+/// The environment variable that enables overwriting of mismatching snapshots.
+pub const UPDATE_SNAPSHOTS: &str = "UPDATE_SNAPSHOTS";
+
+/// Asserts that the registry matches the snapshot of the given name.
+///
+/// The snapshot is stored as `snapshots/<name>.json` within the given directory,
+/// usually the manifest directory of the crate under test.
+///
+/// # Panics
+///
+/// - If the registry does not match the snapshot and `UPDATE_SNAPSHOTS` is not set.
+/// - If the snapshot is missing and the `CI` environment variable is set.
+/// - If the registry has deferred types or the snapshot cannot be read or written.
+pub fn assert_registry_snapshot<P>(registry: &Registry, dir: P, name: &str)
+where
+	P: AsRef<Path>,
+{
+	let path = dir.as_ref().join("snapshots").join(format!("{}.json", name));
+	let actual = to_snapshot(registry);
+	let expected = match fs::read_to_string(&path) {
+		Ok(expected) => expected,
+		Err(_) if env::var_os("CI").is_some() => {
+			panic!("missing registry snapshot `{}`", path.display())
+		}
+		Err(_) => return write_snapshot(&path, &actual),
+	};
+	if expected == actual {
+		return;
+	}
+	if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+		return write_snapshot(&path, &actual);
+	}
+	panic!(
+		"registry snapshot `{}` does not match, set `{}=1` to update it:\n{}",
+		path.display(),
+		UPDATE_SNAPSHOTS,
+		diff(&expected, &actual),
+	);
+}
+
+/// Returns the snapshot of the registry as it is stored in snapshot files.
+///
+/// # Panics
+///
+/// If the registry has deferred types.
+pub fn to_snapshot(registry: &Registry) -> String {
+	let mut snapshot = serde_json::to_string_pretty(&registry.with_layout(Layout::Nested))
+		.expect("registries without deferred types are always serializable");
+	snapshot.push('\n');
+	snapshot
+}
+
+/// A buffer whose const generic length has the width of a pointer.
+#[allow(unused)]
+struct Buffer<const N: usize> {
+	bytes: [u8; N],
+}
+
+impl<const N: usize> HasTypeId for Buffer<N> {
+	fn type_id() -> TypeId {
+		TypeIdCustom::new(
+			"Buffer",
+			Namespace::from_validated(vec!["type_metadata", "snapshot"]),
+			vec![],
+		)
+		.with_const_params(vec![N.into()])
+		.into()
+	}
+}
+
+impl<const N: usize> HasTypeDef for Buffer<N> {
+	fn type_def() -> TypeDef {
+		TypeDefComposite::new(vec![NamedField::of::<[u8; N]>("bytes")]).into()
+	}
+}
+
+/// Registers a representative corpus of standard library types into the registry builder.
+///
+/// The corpus covers all kinds of type identifiers and definitions provided by this
+/// crate as well as const generic parameters of the width of a pointer, aliases and
+/// deferred types. Its snapshot is expected to be byte-identical on all targets.
+pub fn register_corpus(builder: &mut RegistryBuilder) {
+	register_types!(
+		builder,
+		bool,
+		char,
+		u8,
+		u16,
+		u32,
+		u64,
+		u128,
+		i8,
+		i16,
+		i32,
+		i64,
+		i128,
+		String,
+		&'static str,
+		[u8],
+		[u8; 32],
+		[[u16; 4]; 2],
+		(),
+		(bool,),
+		(u8, i64, String),
+		Vec<Option<u64>>,
+		Result<Vec<u8>, String>,
+		BTreeMap<String, (u32, bool)>,
+		Box<[i8]>,
+		PhantomData<u128>,
+		ControlFlow<bool, u8>,
+		Poll<Option<u16>>,
+		Option<NonZeroU32>,
+		Ordering,
+		Buffer<16>,
+	);
+	builder.register_alias("Balance", u128::meta_type());
+	builder.register_type_id_only(&<Option<[u8; 20]>>::meta_type());
+}
+
+/// Writes the given snapshot creating its parent directories as needed.
+fn write_snapshot(path: &Path, snapshot: &str) {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent).expect("failed to create the snapshot directory");
+	}
+	fs::write(path, snapshot).expect("failed to write the registry snapshot");
+}
+
+/// Returns a line based diff from the expected to the actual text.
+///
+/// Removed lines are prefixed with `-`, added lines with `+` and all other lines with a space.
+pub(crate) fn diff(expected: &str, actual: &str) -> String {
+	let expected = expected.lines().collect::<Vec<_>>();
+	let actual = actual.lines().collect::<Vec<_>>();
+	// The lengths of the longest common subsequences of all suffixes.
+	let width = actual.len() + 1;
+	let mut lcs = vec![0usize; (expected.len() + 1) * width];
+	for i in (0..expected.len()).rev() {
+		for j in (0..actual.len()).rev() {
+			lcs[i * width + j] = if expected[i] == actual[j] {
+				lcs[(i + 1) * width + j + 1] + 1
+			} else {
+				lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+			};
+		}
+	}
+	let mut out = String::new();
+	let (mut i, mut j) = (0, 0);
+	while i < expected.len() || j < actual.len() {
+		let line = if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+			i += 1;
+			j += 1;
+			format!(" {}", actual[j - 1])
+		} else if j < actual.len() && (i == expected.len() || lcs[i * width + j + 1] >= lcs[(i + 1) * width + j]) {
+			j += 1;
+			format!("+{}", actual[j - 1])
+		} else {
+			i += 1;
+			format!("-{}", expected[i - 1])
+		};
+		out.push_str(&line);
+		out.push('\n');
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_dir(name: &str) -> std::path::PathBuf {
+		let dir = env::temp_dir().join(format!("type-metadata-{}-{}", name, std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		dir
+	}
+
+	#[test]
+	fn creates_and_matches_snapshots() {
+		if env::var_os("CI").is_some() {
+			return;
+		}
+		let dir = temp_dir("snapshot");
+		let mut builder = RegistryBuilder::new();
+		builder.register::<Option<u32>>();
+		let registry = builder.build();
+		assert_registry_snapshot(&registry, &dir, "option");
+		assert!(dir.join("snapshots").join("option.json").exists());
+		assert_registry_snapshot(&registry, &dir, "option");
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn diff_marks_changed_lines() {
+		assert_eq!(diff("a\nb\nc\n", "a\nx\nc\nd\n"), " a\n+x\n-b\n c\n+d\n");
+		assert_eq!(diff("a\n", "a\n"), " a\n");
+	}
+}
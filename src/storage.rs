@@ -0,0 +1,199 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metadata of map-like storage items.
+//!
+//! Storage items such as the maps of a blockchain runtime are keyed by one or more key
+//! types, each of which is hashed by a named hasher before being combined into the final
+//! storage key. A `StorageMap` describes the ordered key components along with their hashers
+//! and the type of the stored values as a first-class metadata item.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{storage::{StorageKey, StorageMap}, IntoCompact, Registry};
+//! let balances = StorageMap::of::<u128>(vec![
+//!     StorageKey::of::<[u8; 32]>("blake2_128_concat"),
+//!     StorageKey::of::<u32>("twox_64_concat"),
+//! ]);
+//!
+//! let mut registry = Registry::new();
+//! let balances = balances.into_compact(&mut registry);
+//! assert_eq!(balances.keys().len(), 2);
+//! ```
+
+use crate::tm_std::*;
+use crate::{
+	form::{CompactForm, Form, MetaForm},
+	IntoCompact, MetaType, Metadata, Registry,
+};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// A component of a storage key.
+///
+/// The hasher is referred to by name, e.g. `"blake2_128_concat"`, since the set of
+/// hashers depends on the storage backend.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+pub struct StorageKey<F: Form = MetaForm> {
+	/// The name of the hasher applied to the key component.
+	hasher: F::String,
+	/// The type of the key component.
+	#[cfg_attr(feature = "serde", serde(rename = "type"))]
+	ty: F::TypeId,
+}
+
+impl IntoCompact for StorageKey {
+	type Output = StorageKey<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		StorageKey {
+			hasher: registry.compact_string(self.hasher),
+			ty: registry.compact_type(&self.ty),
+		}
+	}
+}
+
+impl StorageKey {
+	/// Creates a new key component hashed by the given hasher.
+	///
+	/// Use this constructor if you want to instantiate from a given meta type.
+	pub const fn new(hasher: &'static str, meta_type: MetaType) -> Self {
+		Self { hasher, ty: meta_type }
+	}
+
+	/// Creates a new key component hashed by the given hasher.
+	///
+	/// Use this constructor if you want to instantiate from a given compile-time type.
+	pub fn of<T>(hasher: &'static str) -> Self
+	where
+		T: Metadata + ?Sized + 'static,
+	{
+		Self::new(hasher, MetaType::new::<T>())
+	}
+}
+
+impl<F: Form> StorageKey<F> {
+	/// Returns the name of the hasher of the key component.
+	pub fn hasher(&self) -> &F::String {
+		&self.hasher
+	}
+
+	/// Returns the type of the key component.
+	pub fn ty(&self) -> &F::TypeId {
+		&self.ty
+	}
+}
+
+/// A map-like storage item.
+///
+/// Maps the values of its key components in order to values of the value type.
+/// A storage item without key components holds a single value.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+pub struct StorageMap<F: Form = MetaForm> {
+	/// The key components in order.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+	keys: Vec<StorageKey<F>>,
+	/// The type of the stored values.
+	value: F::TypeId,
+}
+
+impl IntoCompact for StorageMap {
+	type Output = StorageMap<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		StorageMap {
+			keys: self.keys.into_iter().map(|key| key.into_compact(registry)).collect(),
+			value: registry.compact_type(&self.value),
+		}
+	}
+}
+
+impl StorageMap {
+	/// Creates a new storage map with the given key components in order.
+	///
+	/// Use this constructor if you want to instantiate from a given meta type.
+	pub fn new<K>(keys: K, value: MetaType) -> Self
+	where
+		K: IntoIterator<Item = StorageKey>,
+	{
+		Self {
+			keys: keys.into_iter().collect(),
+			value,
+		}
+	}
+
+	/// Creates a new storage map with the given key components in order.
+	///
+	/// Use this constructor if you want to instantiate from a given compile-time type.
+	pub fn of<V>(keys: Vec<StorageKey>) -> Self
+	where
+		V: Metadata + ?Sized + 'static,
+	{
+		Self::new(keys, MetaType::new::<V>())
+	}
+}
+
+impl<F: Form> StorageMap<F> {
+	/// Returns the key components of the storage map in order.
+	pub fn keys(&self) -> &[StorageKey<F>] {
+		&self.keys
+	}
+
+	/// Returns the type of the stored values.
+	pub fn value(&self) -> &F::TypeId {
+		&self.value
+	}
+
+	/// Returns `true` if the storage item holds a single value without keys.
+	pub fn is_plain(&self) -> bool {
+		self.keys.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compact_storage_map() {
+		let mut registry = Registry::new();
+		let map = StorageMap::of::<Option<u128>>(vec![
+			StorageKey::of::<[u8; 32]>("blake2_128_concat"),
+			StorageKey::of::<u32>("twox_64_concat"),
+		])
+		.into_compact(&mut registry);
+		assert!(!map.is_plain());
+		assert_eq!(*map.value(), registry.compact_type(&<Option<u128>>::meta_type()));
+		let keys = map
+			.keys()
+			.iter()
+			.map(|key| (registry.resolved_string(*key.hasher()), *key.ty()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			keys,
+			vec![
+				("blake2_128_concat", registry.compact_type(&<[u8; 32]>::meta_type())),
+				("twox_64_concat", registry.compact_type(&u32::meta_type())),
+			]
+		);
+		assert!(StorageMap::of::<bool>(vec![]).is_plain());
+	}
+}
@@ -0,0 +1,254 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hooks into the operations of a registry for monitoring.
+//!
+//! Services that register schemas at runtime may install `RegistryHooks` with
+//! `RegistryBuilder::set_hooks` in order to count registrations and measure their latency
+//! with their existing observability stack. All hooks default to doing nothing and
+//! a registry without hooks neither reads the clock nor calls into them.
+//!
+//! Latencies are only measured with the `std` feature and are `None` otherwise.
+//! With the `tracing` feature `TracingHooks` emits every operation as `tracing` event.
+
+#[cfg(feature = "serde")]
+use crate::layout::Layout;
+use crate::tm_std::*;
+use crate::{interner::UntrackedSymbol, Registry};
+use core::time::Duration;
+
+/// Hooks called by a registry upon its operations.
+///
+/// Hooks are shared with branches and clones of the registry
+/// and thus have to be synchronized by the implementor.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// # use type_metadata::{interner::UntrackedSymbol, telemetry::RegistryHooks, Metadata, RegistryBuilder};
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+///
+/// impl RegistryHooks for Counter {
+///     fn on_register(&self, _: &'static str, _: UntrackedSymbol<core::any::TypeId>, _: Option<core::time::Duration>) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(Counter::default());
+/// let mut builder = RegistryBuilder::new();
+/// builder.set_hooks(counter.clone());
+/// builder.register_type(&<Option<u32>>::meta_type());
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+/// ```
+#[allow(unused_variables)]
+pub trait RegistryHooks: Send + Sync {
+	/// Called after a type has been registered for the first time.
+	///
+	/// The latency includes the registration of all sub-types that have
+	/// been registered along with the type.
+	fn on_register(&self, name: &'static str, symbol: UntrackedSymbol<AnyTypeId>, elapsed: Option<Duration>) {}
+
+	/// Called after a string has been interned for the first time.
+	///
+	/// Registries sharing a string pool only report the strings that are new to the pool.
+	fn on_intern_string(&self, string: &'static str, symbol: UntrackedSymbol<&'static str>) {}
+
+	/// Called after the registry has been serialized in the given layout with `Registry::with_layout`.
+	///
+	/// Failed serializations are not reported.
+	#[cfg(feature = "serde")]
+	fn on_serialize(&self, layout: Layout, types: usize, elapsed: Option<Duration>) {}
+}
+
+impl<T> RegistryHooks for Arc<T>
+where
+	T: RegistryHooks + ?Sized,
+{
+	fn on_register(&self, name: &'static str, symbol: UntrackedSymbol<AnyTypeId>, elapsed: Option<Duration>) {
+		(**self).on_register(name, symbol, elapsed)
+	}
+
+	fn on_intern_string(&self, string: &'static str, symbol: UntrackedSymbol<&'static str>) {
+		(**self).on_intern_string(string, symbol)
+	}
+
+	#[cfg(feature = "serde")]
+	fn on_serialize(&self, layout: Layout, types: usize, elapsed: Option<Duration>) {
+		(**self).on_serialize(layout, types, elapsed)
+	}
+}
+
+/// Emits the operations of a registry as `tracing` events of the `type_metadata` target.
+///
+/// Registrations and interned strings are emitted at the `TRACE` level
+/// and serializations at the `DEBUG` level.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingHooks;
+
+#[cfg(feature = "tracing")]
+impl RegistryHooks for TracingHooks {
+	fn on_register(&self, name: &'static str, symbol: UntrackedSymbol<AnyTypeId>, elapsed: Option<Duration>) {
+		tracing::trace!(target: "type_metadata", name, symbol = symbol.id().get(), elapsed = ?elapsed, "registered type");
+	}
+
+	fn on_intern_string(&self, string: &'static str, symbol: UntrackedSymbol<&'static str>) {
+		tracing::trace!(target: "type_metadata", string, symbol = symbol.id().get(), "interned string");
+	}
+
+	fn on_serialize(&self, layout: Layout, types: usize, elapsed: Option<Duration>) {
+		tracing::debug!(target: "type_metadata", layout = ?layout, types, elapsed = ?elapsed, "serialized registry");
+	}
+}
+
+/// The point in time at which a measured operation started.
+#[derive(Clone, Copy)]
+pub(crate) struct Started {
+	#[cfg(feature = "std")]
+	at: std::time::Instant,
+}
+
+impl Started {
+	/// Returns the time passed since the operation started.
+	#[cfg(feature = "std")]
+	fn elapsed(self) -> Option<Duration> {
+		Some(self.at.elapsed())
+	}
+
+	/// Returns `None` since there is no clock without `std`.
+	#[cfg(not(feature = "std"))]
+	fn elapsed(self) -> Option<Duration> {
+		None
+	}
+}
+
+/// The hooks installed into a registry, if any.
+///
+/// Hooks do not take part in the comparison of registries.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks(Option<Arc<dyn RegistryHooks>>);
+
+impl Hooks {
+	/// Starts measuring an operation if hooks are installed.
+	pub(crate) fn start(&self) -> Option<Started> {
+		self.0.as_ref().map(|_| Started {
+			#[cfg(feature = "std")]
+			at: std::time::Instant::now(),
+		})
+	}
+
+	/// Reports the registration of a type that started at `started`.
+	pub(crate) fn registered(&self, name: &'static str, symbol: UntrackedSymbol<AnyTypeId>, started: Option<Started>) {
+		if let (Some(hooks), Some(started)) = (&self.0, started) {
+			hooks.on_register(name, symbol, started.elapsed());
+		}
+	}
+
+	/// Reports a newly interned string.
+	pub(crate) fn interned(&self, string: &'static str, symbol: UntrackedSymbol<&'static str>) {
+		if let Some(hooks) = &self.0 {
+			hooks.on_intern_string(string, symbol);
+		}
+	}
+
+	/// Reports the serialization of a registry that started at `started`.
+	#[cfg(feature = "serde")]
+	pub(crate) fn serialized(&self, layout: Layout, types: usize, started: Option<Started>) {
+		if let (Some(hooks), Some(started)) = (&self.0, started) {
+			hooks.on_serialize(layout, types, started.elapsed());
+		}
+	}
+}
+
+impl Debug for Hooks {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.debug_tuple("Hooks").field(&self.0.is_some()).finish()
+	}
+}
+
+impl Registry {
+	/// Installs the given hooks, replacing the ones installed before.
+	pub fn set_hooks<H>(&mut self, hooks: H)
+	where
+		H: RegistryHooks + 'static,
+	{
+		self.hooks = Hooks(Some(Arc::new(hooks)));
+	}
+
+	/// Removes the installed hooks, if any.
+	pub fn clear_hooks(&mut self) {
+		self.hooks = Hooks(None);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+	use core::sync::atomic::{AtomicUsize, Ordering};
+
+	#[derive(Default)]
+	struct Counts {
+		registered: AtomicUsize,
+		interned: AtomicUsize,
+		#[cfg(feature = "serde")]
+		serialized: AtomicUsize,
+	}
+
+	impl RegistryHooks for Counts {
+		fn on_register(&self, _: &'static str, _: UntrackedSymbol<AnyTypeId>, elapsed: Option<Duration>) {
+			assert_eq!(elapsed.is_some(), cfg!(feature = "std"));
+			self.registered.fetch_add(1, Ordering::SeqCst);
+		}
+
+		fn on_intern_string(&self, _: &'static str, _: UntrackedSymbol<&'static str>) {
+			self.interned.fetch_add(1, Ordering::SeqCst);
+		}
+
+		#[cfg(feature = "serde")]
+		fn on_serialize(&self, _: Layout, types: usize, _: Option<Duration>) {
+			self.serialized.fetch_add(types, Ordering::SeqCst);
+		}
+	}
+
+	#[test]
+	fn hooks_count_operations() {
+		let counts = Arc::new(Counts::default());
+		let mut registry = Registry::new();
+		registry.set_hooks(Arc::clone(&counts));
+		registry.compact_type(&<Option<u32>>::meta_type());
+		registry.compact_type(&<Option<u32>>::meta_type());
+		registry.compact_string("None");
+		assert_eq!(counts.registered.load(Ordering::SeqCst), 2);
+		assert_eq!(counts.interned.load(Ordering::SeqCst), 3);
+		let mut plain = Registry::new();
+		plain.compact_type(&<Option<u32>>::meta_type());
+		assert_eq!(registry, plain);
+
+		#[cfg(feature = "serde_json")]
+		{
+			serde_json::to_string(&registry.with_layout(Layout::Nested)).unwrap();
+			assert_eq!(counts.serialized.load(Ordering::SeqCst), 2);
+		}
+
+		registry.clear_hooks();
+		registry.compact_type(&bool::meta_type());
+		assert_eq!(counts.registered.load(Ordering::SeqCst), 2);
+	}
+}
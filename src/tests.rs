@@ -14,11 +14,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::*;
+use crate::{form::CompactForm, *};
 use core::marker::PhantomData;
 
 #[cfg(not(feature = "std"))]
-use alloc::{boxed::Box, string::String, vec};
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
 
 fn assert_type_id<T, E>(expected: E)
 where
@@ -38,14 +38,84 @@ macro_rules! assert_type_id {
 fn primitives() {
 	assert_type_id!(bool, TypeIdPrimitive::Bool);
 	assert_type_id!(String, TypeIdPrimitive::Str);
-	assert_type_id!(&str, TypeIdPrimitive::Str);
 	assert_type_id!(i8, TypeIdPrimitive::I8);
 
-	assert_type_id!(Box<String>, TypeIdPrimitive::Str);
-	assert_type_id!(&String, TypeIdPrimitive::Str);
+	#[cfg(not(feature = "reference-types"))]
+	{
+		assert_type_id!(&str, TypeIdPrimitive::Str);
+		assert_type_id!(Box<String>, TypeIdPrimitive::Str);
+		assert_type_id!(&String, TypeIdPrimitive::Str);
+	}
 	assert_type_id!([bool], TypeIdSlice::new(bool::meta_type()));
 }
 
+#[test]
+fn references_erase_lifetimes() {
+	fn type_id_of<'a, T: Metadata + ?Sized + 'static>(_: &'a T) -> TypeId {
+		<&'a T>::type_id()
+	}
+
+	let local = String::from("local");
+	assert_eq!(type_id_of(local.as_str()), <&'static str>::type_id());
+	assert_eq!(type_id_of(&[1u8][..]), <&'static [u8]>::type_id());
+	assert_eq!(<&mut str>::type_id(), <&str>::type_id());
+}
+
+#[cfg(not(feature = "reference-types"))]
+#[test]
+fn references_are_transparent() {
+	assert_type_id!(&str, TypeIdPrimitive::Str);
+	assert_type_id!(Box<str>, TypeIdPrimitive::Str);
+	assert_type_id!(&[u8], TypeIdSlice::of::<u8>());
+	assert_type_id!(Box<[u8]>, TypeIdSlice::of::<u8>());
+	assert_eq!(<&[u8]>::type_def(), <[u8]>::type_def());
+	assert_eq!(<Box<str>>::type_def(), str::type_def());
+}
+
+#[test]
+fn shared_pointers_are_transparent() {
+	use crate::tm_std::{Arc, Cow, Rc};
+
+	assert_type_id!(Rc<str>, TypeIdPrimitive::Str);
+	assert_type_id!(Arc<[u8]>, TypeIdSlice::of::<u8>());
+	assert_type_id!(Cow<'static, str>, TypeIdPrimitive::Str);
+	assert_type_id!(Arc<Option<u8>>, <Option<u8>>::type_id());
+	assert_eq!(<Rc<String>>::type_def(), String::type_def());
+	assert_eq!(<Cow<'static, [u8]>>::type_def(), <[u8]>::type_def());
+	assert_eq!(<Arc<core::num::NonZeroU32> as HasTypeDef>::NICHE, <core::num::NonZeroU32>::NICHE);
+}
+
+#[cfg(feature = "reference-types")]
+#[test]
+fn references_are_kept() {
+	assert_type_id!(
+		&str,
+		TypeIdCustom::new("Ref", Namespace::prelude(), tuple_meta_type!(str))
+	);
+	assert_type_id!(
+		Box<[u8]>,
+		TypeIdCustom::new("Box", Namespace::prelude(), tuple_meta_type!([u8]))
+	);
+	assert_eq!(
+		<Box<[u8]>>::type_def(),
+		TypeDefComposite::new(vec![UnnamedField::of::<[u8]>()]).into()
+	);
+}
+
+#[test]
+fn encodings() {
+	#[cfg(not(feature = "reference-types"))]
+	{
+		assert_eq!(<&str>::type_id().encoding(), Some(Encoding::Utf8));
+		assert_eq!(<&[u8]>::type_id().encoding(), Some(Encoding::Bytes));
+	}
+	assert_eq!(String::type_id().encoding(), Some(Encoding::Utf8));
+	assert_eq!(<[u8]>::type_id().encoding(), Some(Encoding::Bytes));
+	assert_eq!(<[bool]>::type_id().encoding(), None);
+	assert_eq!(<Vec<u8>>::type_id().encoding(), None);
+	assert_eq!(u8::type_id().encoding(), None);
+}
+
 #[test]
 fn prelude_items() {
 	assert_type_id!(
@@ -59,7 +129,163 @@ fn prelude_items() {
 	assert_type_id!(
 		PhantomData<i32>,
 		TypeIdCustom::new("PhantomData", Namespace::prelude(), tuple_meta_type!(i32))
-	)
+	);
+	assert_type_id!(
+		core::ops::ControlFlow<bool, u8>,
+		TypeIdCustom::new("ControlFlow", Namespace::prelude(), tuple_meta_type!(bool, u8))
+	);
+	assert_type_id!(
+		core::task::Poll<u8>,
+		TypeIdCustom::new("Poll", Namespace::prelude(), tuple_meta_type!(u8))
+	);
+	assert_type_id!(
+		core::cmp::Ordering,
+		TypeIdCustom::new("Ordering", Namespace::prelude(), vec![])
+	);
+}
+
+#[test]
+fn non_zero_niches() {
+	assert_type_id!(
+		core::num::NonZeroU32,
+		TypeIdCustom::new("NonZeroU32", Namespace::prelude(), vec![])
+	);
+	assert_eq!(
+		<core::num::NonZeroI8>::type_def(),
+		TypeDefComposite::new(vec![UnnamedField::of::<i8>()]).into()
+	);
+	let option = |niche| {
+		TypeDefVariant::new(vec![
+			Variant::new("None"),
+			Variant::new("Some").with_fields(vec![UnnamedField::of::<core::num::NonZeroU64>()]),
+		])
+		.with_niche(niche)
+	};
+	assert_eq!(<Option<core::num::NonZeroU64>>::type_def(), option(true).into());
+	// Boxes and references keep the niche of the referenced type.
+	assert!(matches!(
+		<Option<&core::num::NonZeroU64>>::type_def(),
+		TypeDef::Variant(def) if def.is_niche()
+	));
+	assert!(matches!(<Option<u64>>::type_def(), TypeDef::Variant(def) if !def.is_niche()));
+	assert!(!option(true).is_clike());
+}
+
+#[cfg(all(feature = "futures", not(feature = "reference-types")))]
+#[test]
+fn boxed_futures_are_opaque() {
+	use core::{future::Future, pin::Pin};
+
+	type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+	assert_type_id!(
+		BoxFuture<Result<u8, String>>,
+		TypeIdCustom::new("Future", Namespace::prelude(), tuple_meta_type!(Result<u8, String>))
+	);
+	assert_eq!(<BoxFuture<u8>>::type_def(), TypeDef::builtin());
+	// Pinning is transparent.
+	assert_type_id!(Pin<Box<u32>>, TypeIdPrimitive::U32);
+	assert_type_id!(Pin<&mut str>, TypeIdPrimitive::Str);
+	assert_eq!(
+		<dyn Future<Output = u8> + Send + Sync>::type_id(),
+		<dyn Future<Output = u8>>::type_id()
+	);
+}
+
+#[test]
+fn core_enum_definitions() {
+	assert_eq!(
+		<core::ops::ControlFlow<bool, u8>>::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Continue").with_fields(vec![UnnamedField::of::<u8>()]),
+			Variant::new("Break").with_fields(vec![UnnamedField::of::<bool>()]),
+		])
+		.into()
+	);
+	assert_eq!(
+		<core::task::Poll<u8>>::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Ready").with_fields(vec![UnnamedField::of::<u8>()]),
+			Variant::new("Pending"),
+		])
+		.into()
+	);
+	assert_eq!(
+		core::cmp::Ordering::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Less"),
+			Variant::new("Equal"),
+			Variant::new("Greater"),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn collection_definitions() {
+	use crate::tm_std::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
+
+	assert_type_id!(
+		BTreeMap<u8, bool>,
+		TypeIdCustom::new("BTreeMap", Namespace::prelude(), tuple_meta_type!(u8, bool))
+	);
+	assert_type_id!(
+		BTreeSet<u8>,
+		TypeIdCustom::new("BTreeSet", Namespace::prelude(), tuple_meta_type!(u8))
+	);
+	assert_type_id!(
+		VecDeque<u8>,
+		TypeIdCustom::new("VecDeque", Namespace::prelude(), tuple_meta_type!(u8))
+	);
+	assert_type_id!(
+		BinaryHeap<u8>,
+		TypeIdCustom::new("BinaryHeap", Namespace::prelude(), tuple_meta_type!(u8))
+	);
+	let elems = TypeDef::from(TypeDefComposite::new(vec![NamedField::of::<[u8]>("elems")]));
+	assert_eq!(<BTreeSet<u8>>::type_def(), elems);
+	assert_eq!(<VecDeque<u8>>::type_def(), elems);
+	assert_eq!(<BinaryHeap<u8>>::type_def(), elems);
+
+	#[cfg(feature = "std")]
+	{
+		use crate::tm_std::{HashMap, HashSet};
+
+		assert_type_id!(
+			HashMap<u8, bool>,
+			TypeIdCustom::new("HashMap", Namespace::prelude(), tuple_meta_type!(u8, bool))
+		);
+		assert_eq!(<HashMap<u8, bool>>::type_def(), <BTreeMap<u8, bool>>::type_def());
+		assert_type_id!(
+			HashSet<u8>,
+			TypeIdCustom::new("HashSet", Namespace::prelude(), tuple_meta_type!(u8))
+		);
+		assert_eq!(<HashSet<u8>>::type_def(), elems);
+	}
+}
+
+#[test]
+fn time_definitions() {
+	use crate::tm_std::Duration;
+
+	assert_type_id!(Duration, TypeIdCustom::new("Duration", Namespace::prelude(), vec![]));
+	assert_eq!(
+		Duration::type_def(),
+		TypeDefComposite::new(vec![NamedField::of::<u64>("secs"), NamedField::of::<u32>("nanos")]).into()
+	);
+
+	#[cfg(feature = "std")]
+	{
+		use crate::tm_std::SystemTime;
+
+		assert_type_id!(SystemTime, TypeIdCustom::new("SystemTime", Namespace::prelude(), vec![]));
+		assert_eq!(
+			SystemTime::type_def(),
+			TypeDefComposite::new(vec![
+				NamedField::of::<u64>("secs_since_epoch"),
+				NamedField::of::<u32>("nanos_since_epoch"),
+			])
+			.into()
+		);
+	}
 }
 
 #[test]
@@ -96,6 +322,7 @@ fn array_primitives() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn struct_with_generics() {
 	#[allow(unused)]
 	struct MyStruct<T> {
@@ -149,3 +376,230 @@ fn struct_with_generics() {
 		TypeDefStruct::new(vec![NamedField::new("data", <Box<MyStruct<bool>>>::meta_type()),]).into(),
 	);
 }
+
+#[test]
+#[allow(deprecated)]
+fn composite_definitions() {
+	let named = TypeDefComposite::new(vec![
+		NamedField::of::<u8>("a").with_group("g"),
+		NamedField::of::<bool>("b"),
+	]);
+	assert!(named.is_named());
+	assert_eq!(named.fields()[0].name(), Some(&"a"));
+	assert_eq!(named.fields()[0].group(), Some(&"g"));
+	assert_eq!(
+		TypeDefComposite::from(TypeDefStruct::new(vec![
+			NamedField::of::<u8>("a").with_group("g"),
+			NamedField::of::<bool>("b"),
+		])),
+		named
+	);
+
+	let unnamed = TypeDefComposite::new(vec![Field::unnamed(bool::meta_type())]);
+	assert!(!unnamed.is_named());
+	assert_eq!(unnamed.fields()[0].name(), None);
+	assert_eq!(
+		TypeDefComposite::from(TypeDefTupleStruct::new(vec![UnnamedField::of::<bool>()])),
+		unnamed
+	);
+	assert!(!TypeDefComposite::unit().is_named());
+
+	assert_eq!(
+		TypeDef::from(TypeDefTupleStruct::unit()).normalize(),
+		TypeDefComposite::unit().into()
+	);
+	assert_eq!(TypeDef::json().normalize(), TypeDef::json());
+}
+
+#[test]
+#[allow(deprecated)]
+fn variant_definitions() {
+	let clike = TypeDefVariant::new(vec![
+		Variant::new("A").with_discriminant(0).with_code(404),
+		Variant::new("B").with_discriminant(10),
+	]);
+	assert!(clike.is_clike());
+	assert_eq!(clike.variants()[1].discriminant(), Some(10));
+	assert_eq!(
+		TypeDefVariant::from(TypeDefClikeEnum::new(vec![
+			ClikeEnumVariant::new("A", 0u64).with_code(404),
+			ClikeEnumVariant::new("B", 10u64),
+		])),
+		clike
+	);
+
+	let r#enum = TypeDefVariant::new(vec![
+		Variant::new("A").with_fields(vec![UnnamedField::of::<u8>()]),
+		Variant::new("B").with_fields(vec![NamedField::of::<bool>("b")]),
+		Variant::new("C"),
+	]);
+	assert!(!r#enum.is_clike());
+	assert_eq!(r#enum.variants()[0].fields()[0].name(), None);
+	assert_eq!(
+		TypeDefVariant::from(TypeDefEnum::new(vec![
+			EnumVariantTupleStruct::new("A", vec![UnnamedField::of::<u8>()]).into(),
+			EnumVariantStruct::new("B", vec![NamedField::of::<bool>("b")]).into(),
+			EnumVariantUnit::new("C").into(),
+		])),
+		r#enum
+	);
+
+	// Variants carrying fields may have discriminants as well.
+	let mixed = TypeDefVariant::new(vec![
+		Variant::new("Stop").with_discriminant(1),
+		Variant::new("Say")
+			.with_fields(vec![UnnamedField::of::<u8>()])
+			.with_discriminant(4),
+	]);
+	assert!(!mixed.is_clike());
+	assert!(TypeDefVariant::new(Vec::<Variant>::new()).is_clike());
+	assert!(!TypeDefVariant::new(vec![Variant::new("A").with_discriminant(0)])
+		.with_repr(EnumRepr::Untagged)
+		.is_clike());
+
+	assert_eq!(
+		TypeDef::from(TypeDefEnum::new(vec![EnumVariantUnit::new("A").into()])).normalize(),
+		TypeDefVariant::new(vec![Variant::new("A")]).into()
+	);
+}
+
+#[test]
+fn union_discriminators() {
+	let union = || TypeDefUnion::new(vec![NamedField::of::<u32>("int"), NamedField::of::<bool>("flag")]);
+
+	let discriminator = UnionDiscriminator::new(vec!["kind"], vec![(-1i8, "int"), (1, "flag")]);
+	let def = union().with_discriminator(discriminator.clone()).unwrap();
+	assert_eq!(def.discriminator(), Some(&discriminator));
+	assert_eq!(*discriminator.mapping()[0].value(), DiscriminantValue::Int(-1));
+
+	let discriminator = UnionDiscriminator::new(vec!["kind"], vec![("int", "int"), ("text", "text")]);
+	assert_eq!(*discriminator.mapping()[0].value(), DiscriminantValue::Str("int"));
+	assert_eq!(
+		union().with_discriminator(discriminator),
+		Err(UnionDiscriminatorError::UnknownMember { mapping: 1 })
+	);
+}
+
+#[test]
+fn builtin_definitions() {
+	assert_eq!(TypeDef::builtin(), TypeDef::Builtin(Builtin::Builtin));
+	assert_eq!(TypeDef::json(), TypeDef::Builtin(Builtin::Json));
+	assert_ne!(TypeDef::builtin(), TypeDef::json());
+}
+
+#[test]
+#[allow(deprecated)]
+fn const_constructors() {
+	const PRELUDE: Namespace = Namespace::prelude();
+	const UNIT: TypeIdTuple = TypeIdTuple::unit();
+	const BUILTIN: TypeDef = TypeDef::builtin();
+	const EMPTY: TypeDefTupleStruct = TypeDefTupleStruct::unit();
+	const NONE: EnumVariantUnit = EnumVariantUnit::new("None");
+
+	assert!(PRELUDE.segments().is_empty());
+	assert_eq!(UNIT, TypeIdTuple::new(vec![]));
+	assert_eq!(BUILTIN, TypeDef::builtin());
+	assert_eq!(EMPTY, TypeDefTupleStruct::new(vec![]));
+	assert_eq!(NONE, EnumVariantUnit::new("None"));
+}
+
+#[test]
+fn accessors_of_both_forms() {
+	let array = TypeIdArray::new(4, u8::meta_type());
+	assert_eq!((array.len(), array.type_param()), (4, &u8::meta_type()));
+	assert!(TypeIdTuple::unit().is_unit());
+	let variant = EnumVariant::from(EnumVariantUnit::new("None"));
+	assert_eq!(*variant.name(), "None");
+
+	let mut registry = Registry::new();
+	let compact = TypeIdTuple::new(vec![u8::meta_type(), bool::meta_type()]).into_compact(&mut registry);
+	assert_eq!(compact.type_params().len(), 2);
+	assert!(!compact.is_unit());
+	let compact = array.into_compact(&mut registry);
+	assert_eq!(*compact.type_param(), registry.compact_type(&u8::meta_type()));
+	let name = *variant.into_compact(&mut registry).name();
+	assert_eq!(registry.resolve_str(&name), Some("None"));
+}
+
+#[test]
+fn resolved_type_params() {
+	let mut registry = Registry::new();
+	let option = registry.compact_type(&<Option<(u32, [bool; 2])>>::meta_type());
+	let tuple = registry.compact_type(&<(u32, [bool; 2])>::meta_type());
+	let array = registry.compact_type(&<[bool; 2]>::meta_type());
+	let (int, boolean) = (registry.compact_type(&u32::meta_type()), registry.compact_type(&bool::meta_type()));
+	let ids = |id: &TypeId<CompactForm>| {
+		id.params_resolved(&registry)
+			.map(|(param, def)| (param, def.id().clone()))
+			.collect::<Vec<_>>()
+	};
+
+	let option_id = registry.resolve(&option).unwrap().id();
+	assert_eq!(ids(option_id), vec![(tuple, registry.resolve(&tuple).unwrap().id().clone())]);
+	let tuple_id = registry.resolve(&tuple).unwrap().id();
+	let params = ids(tuple_id).into_iter().map(|(param, _)| param).collect::<Vec<_>>();
+	assert_eq!(params, vec![int, array]);
+	let array_id = registry.resolve(&array).unwrap().id();
+	assert_eq!(ids(array_id).len(), 1);
+	assert!(ids(&TypeId::Primitive(TypeIdPrimitive::Bool)).is_empty());
+
+	if let TypeId::Custom(custom) = option_id {
+		assert_eq!(custom.params_resolved(&registry).count(), 1);
+	}
+	if let TypeId::Array(array) = array_id {
+		let (param, def) = array.param_resolved(&registry).unwrap();
+		assert_eq!(param, boolean);
+		assert_eq!(def.id(), &TypeId::Primitive(TypeIdPrimitive::Bool));
+	}
+}
+
+trait Config {
+	type AccountId: Metadata + 'static;
+}
+
+struct Native;
+
+impl Config for Native {
+	type AccountId = [u8; 32];
+}
+
+struct Foreign;
+
+impl Config for Foreign {
+	type AccountId = [u16; 16];
+}
+
+fn assert_same_account_id<A: Config, B: Config>() {
+	metadata_assert_same_type!(A::AccountId, B::AccountId);
+}
+
+#[test]
+fn same_type_assertions() {
+	assert_same_account_id::<Native, Native>();
+	metadata_assert_same_type!(<Native as Config>::AccountId, [u8; 32]);
+	assert_eq!(metadata_type_name!(<Foreign as Config>::AccountId), "[u16; 16]");
+}
+
+#[test]
+#[should_panic(expected = "resolve to different types: `[u8; 32]` and `[u16; 16]`")]
+fn same_type_assertion_fails() {
+	assert_same_account_id::<Native, Foreign>();
+}
+
+#[cfg(feature = "once_cell")]
+mod static_registry {
+	use crate::*;
+
+	static_registry! {
+		static REGISTRY = [u32, Option<bool>];
+	}
+
+	#[test]
+	fn registers_types_in_order() {
+		let mut builder = RegistryBuilder::new();
+		builder.register_type(&u32::meta_type());
+		builder.register_type(&<Option<bool>>::meta_type());
+		let registry = builder.build();
+		assert_eq!(*REGISTRY, registry);
+	}
+}
@@ -26,22 +26,25 @@ mod core {
 
 #[rustfmt::skip]
 pub use self::core::{
-	i8, i16, i32, i64, i128,
-	u8, u16, u32, u64, u128,
-
 	marker::PhantomData,
-	num::NonZeroU32,
+	num::{
+		NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128,
+		NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128,
+	},
 	option::Option,
 	result::Result,
+	str::FromStr,
 
 	any::{TypeId as AnyTypeId},
 
 	clone::{Clone},
 	cmp::{Eq, PartialEq, Ordering},
-	convert::{From, Into},
-	fmt::{Debug, Error as FmtError, Formatter},
+	convert::{From, Into, TryFrom},
+	fmt::{Debug, Display, Error as FmtError, Formatter},
 	hash::{Hash, Hasher},
-	iter,
+	ops::ControlFlow,
+	task::Poll,
+	time::Duration,
 };
 
 mod alloc {
@@ -54,8 +57,20 @@ mod alloc {
 
 #[rustfmt::skip]
 pub use self::alloc::{
+	borrow::{Cow, ToOwned},
 	boxed::Box,
-	collections::btree_map::{BTreeMap, Entry},
+	collections::btree_map::BTreeMap,
+	collections::btree_set::BTreeSet,
+	collections::{BinaryHeap, VecDeque},
+	format,
+	rc::Rc,
 	string::{String, ToString},
+	sync::Arc,
 	vec, vec::Vec,
 };
+
+#[cfg(feature = "std")]
+pub use std::{
+	collections::{HashMap, HashSet},
+	time::SystemTime,
+};
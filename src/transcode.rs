@@ -0,0 +1,157 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transcoding between human-readable JSON and SCALE driven by the metadata of a registry.
+//!
+//! SCALE bytes are transcoded to JSON by decoding them into a dynamic `Value` which is
+//! serialized as described for `Value`: named fields become objects, unnamed fields and
+//! sequences become arrays and variants become objects with the variant name as only key.
+//!
+//! JSON is transcoded to SCALE by encoding the deserialized `Value` as the given type,
+//! so that the output of `scale_to_json` is always accepted by `json_to_scale`. Besides,
+//! variants without fields may be given by their name only, e.g. `"None"`, and variants
+//! and composites with a single field by the value of that field, e.g. `{"Some": 42}`.
+//!
+//! Floats are not supported and integers beyond the range of `u64` and `i64` are only
+//! supported when transcoding to JSON since `serde_json` parses them as floats.
+//!
+//! # Example
+//!
+//! ```
+//! # use type_metadata::{transcode, Metadata, RegistryBuilder};
+//! let mut builder = RegistryBuilder::new();
+//! let ty = builder.register_type(&<Option<(u16, bool)>>::meta_type());
+//! let registry = builder.build();
+//!
+//! let bytes = transcode::json_to_scale(r#"{"Some": [[42, true]]}"#, ty, &registry).unwrap();
+//! assert_eq!(bytes, vec![1, 42, 0, 1]);
+//! assert_eq!(transcode::scale_to_json(&bytes, ty, &registry).unwrap(), r#"{"Some":[[42,true]]}"#);
+//! ```
+
+use crate::tm_std::*;
+use crate::{
+	interner::UntrackedSymbol,
+	value::{decode_scale, encode_scale, DecodeError, EncodeError, Value},
+	Registry,
+};
+
+/// An error upon transcoding between JSON and SCALE.
+#[derive(PartialEq, Eq, Debug)]
+pub enum TranscodeError {
+	/// The JSON is malformed or contains values that are not supported, e.g. floats.
+	Json(String),
+	/// The SCALE bytes are not a valid encoding of the type.
+	Decode(DecodeError),
+	/// The JSON does not match the type.
+	Encode(EncodeError),
+	/// The SCALE bytes continue after the encoded value.
+	TrailingBytes {
+		/// The number of trailing bytes.
+		len: usize,
+	},
+}
+
+impl From<DecodeError> for TranscodeError {
+	fn from(error: DecodeError) -> Self {
+		TranscodeError::Decode(error)
+	}
+}
+
+impl From<EncodeError> for TranscodeError {
+	fn from(error: EncodeError) -> Self {
+		TranscodeError::Encode(error)
+	}
+}
+
+impl Display for TranscodeError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			TranscodeError::Json(error) => write!(f, "invalid JSON: {}", error),
+			TranscodeError::Decode(error) => write!(f, "invalid SCALE encoding: {}", error),
+			TranscodeError::Encode(error) => write!(f, "JSON does not match type: {}", error),
+			TranscodeError::TrailingBytes { len } => write!(f, "{} trailing bytes after encoded value", len),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TranscodeError {}
+
+/// Transcodes the JSON representation of a value of the given registered type to its SCALE encoding.
+///
+/// # Errors
+///
+/// If the JSON is malformed or does not match the type.
+pub fn json_to_scale(
+	json: &str,
+	ty: UntrackedSymbol<AnyTypeId>,
+	registry: &Registry,
+) -> Result<Vec<u8>, TranscodeError> {
+	let value = serde_json::from_str::<Value>(json).map_err(|error| TranscodeError::Json(error.to_string()))?;
+	encode_scale(&value, ty, registry).map_err(Into::into)
+}
+
+/// Transcodes the SCALE encoding of a value of the given registered type to its JSON representation.
+///
+/// # Errors
+///
+/// If the bytes are not exactly the encoding of a value of the type.
+pub fn scale_to_json(
+	bytes: &[u8],
+	ty: UntrackedSymbol<AnyTypeId>,
+	registry: &Registry,
+) -> Result<String, TranscodeError> {
+	let mut bytes = bytes;
+	let value = decode_scale(&mut bytes, ty, registry)?;
+	if !bytes.is_empty() {
+		return Err(TranscodeError::TrailingBytes { len: bytes.len() });
+	}
+	serde_json::to_string(&value).map_err(|error| TranscodeError::Json(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Metadata, RegistryBuilder};
+
+	#[test]
+	fn transcodes_both_ways() {
+		let mut builder = RegistryBuilder::new();
+		let vec_ty = builder.register_type(&<Vec<(String, Result<u128, char>)>>::meta_type());
+		let ty = builder.register_type(&<Option<u8>>::meta_type());
+		let u8_ty = builder.register_type(&u8::meta_type());
+		let registry = builder.build();
+		let json = r#"{"elems":[["a",{"Ok":[18446744073709551615]}],["b",{"Err":["x"]}]]}"#;
+		let bytes = json_to_scale(json, vec_ty, &registry).unwrap();
+		assert_eq!(bytes.len(), 1 + 2 + 1 + 16 + 2 + 1 + 4);
+		assert_eq!(scale_to_json(&bytes, vec_ty, &registry).unwrap(), json);
+
+		assert_eq!(json_to_scale(r#"{"Some": 7}"#, ty, &registry), Ok(vec![1, 7]));
+		assert_eq!(json_to_scale(r#""None""#, ty, &registry), Ok(vec![0]));
+		assert_eq!(
+			scale_to_json(&[1, 7, 0], ty, &registry),
+			Err(TranscodeError::TrailingBytes { len: 1 })
+		);
+		assert!(matches!(
+			json_to_scale("1.5", ty, &registry),
+			Err(TranscodeError::Json(_))
+		));
+		assert_eq!(
+			json_to_scale(r#"{"Some": 256}"#, ty, &registry),
+			Err(TranscodeError::Encode(EncodeError::OutOfRange { id: u8_ty.id().get() }))
+		);
+	}
+}
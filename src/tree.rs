@@ -0,0 +1,274 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A view of the registered types organized by their namespaces.
+//!
+//! Explorer UIs and command line tools may present a registry by module rather than as a
+//! flat list. `Registry::namespace_tree` builds a tree of namespaces in which every node
+//! holds its child namespaces and the custom types defined directly within it. Types of the
+//! prelude, e.g. `Option<T>`, are held by the root node.
+//!
+//! Primitives, slices, arrays and tuples have no namespace and are not part of the tree.
+
+use crate::interner::UntrackedSymbol;
+use crate::registry::Resolve;
+use crate::tm_std::*;
+use crate::{FindOptions, Registry, TypeId};
+
+/// The namespaces of a registry and the custom types defined within them.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct NamespaceTree {
+	/// The root namespace holding the prelude types.
+	root: NamespaceNode,
+}
+
+/// A namespace within a `NamespaceTree`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct NamespaceNode {
+	/// The segments of the namespace, empty for the root namespace.
+	path: Vec<&'static str>,
+	/// The child namespaces by their last segment.
+	children: BTreeMap<&'static str, NamespaceNode>,
+	/// The names and symbols of the types defined directly within the namespace.
+	types: Vec<(&'static str, UntrackedSymbol<AnyTypeId>)>,
+}
+
+impl NamespaceNode {
+	/// Creates an empty namespace node of the given path.
+	fn new(path: Vec<&'static str>) -> Self {
+		Self {
+			path,
+			children: BTreeMap::new(),
+			types: Vec::new(),
+		}
+	}
+
+	/// Returns the segments of the namespace, empty for the root namespace.
+	pub fn path(&self) -> &[&'static str] {
+		&self.path
+	}
+
+	/// Returns the last segment of the namespace, empty for the root namespace.
+	pub fn name(&self) -> &'static str {
+		self.path.last().copied().unwrap_or_default()
+	}
+
+	/// Returns an iterator over the child namespaces in order of their names.
+	pub fn children(&self) -> impl Iterator<Item = &NamespaceNode> {
+		self.children.values()
+	}
+
+	/// Returns the child namespace of the given name if any.
+	pub fn child(&self, name: &str) -> Option<&NamespaceNode> {
+		self.children.get(name)
+	}
+
+	/// Returns the names and symbols of the types defined directly within the namespace
+	/// in order of their type symbols.
+	///
+	/// Generic types are listed once per instantiation, e.g. `Option<u8>` and `Option<u16>`.
+	pub fn types(&self) -> &[(&'static str, UntrackedSymbol<AnyTypeId>)] {
+		&self.types
+	}
+
+	/// Returns the number of types defined within the namespace and all of its descendants.
+	pub fn type_count(&self) -> usize {
+		self.types.len() + self.children().map(NamespaceNode::type_count).sum::<usize>()
+	}
+}
+
+impl NamespaceTree {
+	/// Returns the root namespace holding the prelude types.
+	pub fn root(&self) -> &NamespaceNode {
+		&self.root
+	}
+
+	/// Returns the namespace of the given segments if any.
+	///
+	/// The empty path refers to the root namespace.
+	pub fn get(&self, path: &[&str]) -> Option<&NamespaceNode> {
+		path.iter().try_fold(&self.root, |node, segment| node.child(segment))
+	}
+
+	/// Returns an iterator over all namespaces in depth-first pre-order, starting with the root.
+	pub fn iter(&self) -> NamespaceIter<'_> {
+		NamespaceIter {
+			stack: vec![&self.root],
+		}
+	}
+
+	/// Returns the namespaces whose last segment matches the given name in the order of `iter`.
+	pub fn find_namespaces(&self, name: &str, options: FindOptions) -> Vec<&NamespaceNode> {
+		let name = options.fold(name);
+		self.iter()
+			.filter(|node| !node.path.is_empty() && options.fold(node.name()) == name)
+			.collect()
+	}
+
+	/// Returns the types whose name matches the given name together with their namespace
+	/// in the order of `iter`.
+	///
+	/// Unlike `Registry::find` only names are matched, neither paths nor aliases.
+	pub fn find_types(&self, name: &str, options: FindOptions) -> Vec<(&NamespaceNode, UntrackedSymbol<AnyTypeId>)> {
+		let name = options.fold(name);
+		self.iter()
+			.flat_map(|node| {
+				node.types
+					.iter()
+					.filter(|(type_name, _)| options.fold(type_name) == name)
+					.map(move |(_, symbol)| (node, *symbol))
+			})
+			.collect()
+	}
+}
+
+impl<'a> IntoIterator for &'a NamespaceTree {
+	type Item = &'a NamespaceNode;
+	type IntoIter = NamespaceIter<'a>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+/// An iterator over the namespaces of a `NamespaceTree` in depth-first pre-order.
+#[derive(Clone, Debug)]
+pub struct NamespaceIter<'a> {
+	/// The namespaces yet to be visited, the next one last.
+	stack: Vec<&'a NamespaceNode>,
+}
+
+impl<'a> Iterator for NamespaceIter<'a> {
+	type Item = &'a NamespaceNode;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let node = self.stack.pop()?;
+		self.stack.extend(node.children.values().rev());
+		Some(node)
+	}
+}
+
+impl Registry {
+	/// Returns the custom types of the registry organized by their namespaces.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use type_metadata::{FindOptions, Metadata, RegistryBuilder};
+	/// let mut builder = RegistryBuilder::new();
+	/// let option = builder.register_type(&<Option<u8>>::meta_type());
+	/// let registry = builder.build();
+	/// let tree = registry.namespace_tree();
+	/// assert_eq!(tree.root().types(), &[("Option", option)]);
+	/// assert_eq!(tree.find_types("option", FindOptions::exact().with_case_folding(true)).len(), 1);
+	/// ```
+	pub fn namespace_tree(&self) -> NamespaceTree {
+		let mut root = NamespaceNode::new(Vec::new());
+		for (symbol, id_def) in self.types() {
+			if let TypeId::Custom(custom) = id_def.id().resolve(self) {
+				let mut node = &mut root;
+				for segment in custom.namespace().segments() {
+					let mut path = node.path.clone();
+					path.push(*segment);
+					node = node
+						.children
+						.entry(*segment)
+						.or_insert_with(|| NamespaceNode::new(path));
+				}
+				node.types.push((custom.name(), symbol));
+			}
+		}
+		NamespaceTree { root }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{HasTypeDef, HasTypeId, Metadata, Namespace, TypeDefComposite, TypeIdCustom};
+
+	struct Account;
+
+	impl HasTypeId for Account {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Account", Namespace::new(vec!["app", "balances"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Account {
+		fn type_def() -> crate::TypeDef {
+			TypeDefComposite::new(vec![crate::NamedField::of::<u64>("free")]).into()
+		}
+	}
+
+	struct Call;
+
+	impl HasTypeId for Call {
+		fn type_id() -> crate::TypeId {
+			TypeIdCustom::new("Call", Namespace::new(vec!["app"]).unwrap(), vec![]).into()
+		}
+	}
+
+	impl HasTypeDef for Call {
+		fn type_def() -> crate::TypeDef {
+			TypeDefComposite::new(vec![crate::NamedField::of::<Option<Account>>("account")]).into()
+		}
+	}
+
+	#[test]
+	fn organizes_types_by_namespace() {
+		let mut registry = Registry::new();
+		let call = registry.compact_type(&Call::meta_type());
+		// Registering the sub-types again only looks up their type symbols.
+		let option = registry.compact_type(&<Option<Account>>::meta_type());
+		let account = registry.compact_type(&Account::meta_type());
+		let tree = registry.namespace_tree();
+
+		assert_eq!(tree.root().types(), &[("Option", option)]);
+		assert_eq!(tree.root().type_count(), 3);
+		assert_eq!(
+			tree.iter().map(NamespaceNode::path).collect::<Vec<_>>(),
+			vec![&[][..], &["app"][..], &["app", "balances"][..]]
+		);
+		let app = tree.get(&["app"]).unwrap();
+		assert_eq!(app.name(), "app");
+		assert_eq!(app.types(), &[("Call", call)]);
+		assert_eq!(app.child("balances").unwrap().types(), &[("Account", account)]);
+		assert_eq!(tree.get(&["app", "staking"]), None);
+	}
+
+	#[test]
+	fn finds_types_and_namespaces() {
+		let mut registry = Registry::new();
+		registry.compact_type(&Call::meta_type());
+		let account = registry.compact_type(&Account::meta_type());
+		let tree = registry.namespace_tree();
+		let folding = FindOptions::exact().with_case_folding(true);
+
+		let found = tree.find_types("account", folding);
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[0].0.path(), &["app", "balances"]);
+		assert_eq!(found[0].1, account);
+		assert!(tree.find_types("account", FindOptions::exact()).is_empty());
+		assert_eq!(
+			tree.find_namespaces("Balances", folding)
+				.into_iter()
+				.map(NamespaceNode::path)
+				.collect::<Vec<_>>(),
+			vec![&["app", "balances"][..]]
+		);
+	}
+}
@@ -17,11 +17,18 @@
 use crate::tm_std::*;
 
 use crate::{
-	form::{CompactForm, Form, MetaForm},
+	expand::{Expander, IntoExpanded},
+	form::{CompactForm, ExpandedForm, Form, MetaForm, ResolvedForm},
+	interner::UntrackedSymbol,
+	registry::Resolve,
+	utils::{new_items, Items},
 	IntoCompact, MetaType, Metadata, Registry,
 };
 use derive_more::From;
-use serde::Serialize;
+#[cfg(feature = "scale")]
+use parity_scale_codec::{Decode, Encode};
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 /// Types implementing this trait can communicate their type structure.
 ///
@@ -29,61 +36,1089 @@ use serde::Serialize;
 /// `registry`. For instance, `<Option<MyStruct>>::type_def()` would register `MyStruct` metadata. All
 /// implementation must register these contained types' metadata.
 pub trait HasTypeDef {
+	/// Whether the zero value of `Self` is invalid.
+	///
+	/// This allows `Option<Self>` to encode `None` as zero instead of a separate tag,
+	/// e.g. `Option<NonZeroU32>` in four bytes.
+	const NICHE: bool = false;
+
 	/// Returns the type definition for `Self` type.
 	fn type_def() -> TypeDef;
 }
 
+/// Compile-time facts about the definition of a derived type.
+///
+/// Implemented by `#[derive(TypeDef)]` so that assumptions about the shape of a type can be
+/// asserted in const contexts without constructing a registry.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Metadata)]
+/// struct Transfer {
+///     from: u64,
+///     to: u64,
+/// }
+///
+/// const _: () = assert!(<Transfer as TypeConsts>::FIELD_COUNT == 2);
+/// ```
+pub trait TypeConsts {
+	/// The number of fields of a struct or union without `PhantomData` fields, zero for enums.
+	const FIELD_COUNT: usize;
+	/// The number of variants of an enum, zero for structs and unions.
+	const VARIANT_COUNT: usize;
+	/// The hash of the name and the derived definition of the type.
+	///
+	/// Changes whenever the derived definition changes, e.g. upon renaming or retyping a field.
+	/// The hash is only comparable between builds with the same version of the derive.
+	const METADATA_HASH: u64;
+}
+
 /// A type definition represents the internal structure of a concrete type.
-#[derive(PartialEq, Eq, Debug, Serialize, From)]
-#[serde(bound = "F::TypeId: Serialize")]
-#[serde(untagged)]
+#[allow(deprecated)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, From)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum TypeDef<F: Form = MetaForm> {
 	/// A builtin type that has an implied and known internal structure.
 	Builtin(Builtin),
 	/// A struct with named fields.
+	///
+	/// Superseded by `Composite` and kept for backwards compatibility.
+	/// New definitions shall use `TypeDefComposite` instead.
+	#[from(ignore)]
 	Struct(TypeDefStruct<F>),
 	/// A tuple-struct with unnamed fields.
+	///
+	/// Superseded by `Composite` and kept for backwards compatibility.
+	/// New definitions shall use `TypeDefComposite` instead.
+	#[from(ignore)]
 	TupleStruct(TypeDefTupleStruct<F>),
+	/// A struct or tuple-struct with either named or unnamed fields.
+	Composite(TypeDefComposite<F>),
 	/// A C-like enum with simple named variants.
+	///
+	/// Superseded by `Variant` and kept for backwards compatibility.
+	/// New definitions shall use `TypeDefVariant` instead.
+	#[from(ignore)]
 	ClikeEnum(TypeDefClikeEnum<F>),
 	/// A Rust enum with different kinds of variants.
+	///
+	/// Superseded by `Variant` and kept for backwards compatibility.
+	/// New definitions shall use `TypeDefVariant` instead.
+	#[from(ignore)]
 	Enum(TypeDefEnum<F>),
+	/// A Rust enum whose variants may carry fields and discriminants.
+	Variant(TypeDefVariant<F>),
 	/// An unsafe Rust union type.
 	Union(TypeDefUnion<F>),
 }
 
-impl TypeDef {
-	/// Preferred way to create a builtin type definition.
-	pub fn builtin() -> Self {
-		TypeDef::Builtin(Builtin::Builtin)
+// Implemented manually since derived implementations cannot allow the deprecation.
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefStruct<F>> for TypeDef<F> {
+	fn from(def: TypeDefStruct<F>) -> Self {
+		TypeDef::Struct(def)
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefTupleStruct<F>> for TypeDef<F> {
+	fn from(def: TypeDefTupleStruct<F>) -> Self {
+		TypeDef::TupleStruct(def)
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefClikeEnum<F>> for TypeDef<F> {
+	fn from(def: TypeDefClikeEnum<F>) -> Self {
+		TypeDef::ClikeEnum(def)
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefEnum<F>> for TypeDef<F> {
+	fn from(def: TypeDefEnum<F>) -> Self {
+		TypeDef::Enum(def)
+	}
+}
+
+impl TypeDef {
+	/// Preferred way to create a builtin type definition.
+	pub const fn builtin() -> Self {
+		TypeDef::Builtin(Builtin::Builtin)
+	}
+
+	/// Creates the definition of a type holding an arbitrary JSON value.
+	pub const fn json() -> Self {
+		TypeDef::Builtin(Builtin::Json)
+	}
+
+	/// Creates the definition of a type holding an opaque payload in the given format.
+	pub const fn raw(format: RawFormat) -> Self {
+		TypeDef::Builtin(Builtin::Raw(format))
+	}
+
+	/// Creates the definition of a type that has been retired.
+	pub const fn tombstone() -> Self {
+		TypeDef::Builtin(Builtin::Tombstone)
+	}
+}
+
+impl<F: Form> TypeDef<F> {
+	/// Returns `true` if the type has been retired, see `Registry::deprecate_type`.
+	pub fn is_tombstone(&self) -> bool {
+		matches!(self, TypeDef::Builtin(Builtin::Tombstone))
+	}
+
+	/// Converts superseded definitions into the equivalent composite and variant definitions.
+	///
+	/// Visitors of normalized definitions only have to handle `TypeDef::Composite` for
+	/// structs and `TypeDef::Variant` for enums.
+	pub fn normalize(self) -> Self {
+		match self {
+			TypeDef::Struct(def) => TypeDef::Composite(def.into()),
+			TypeDef::TupleStruct(def) => TypeDef::Composite(def.into()),
+			TypeDef::ClikeEnum(def) => TypeDef::Variant(def.into()),
+			TypeDef::Enum(def) => TypeDef::Variant(def.into()),
+			def => def,
+		}
+	}
+}
+
+/// This struct just exists for the purpose of better JSON output.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Builtin {
+	/// This enum variant just exists for the purpose of special JSON output.
+	#[cfg_attr(feature = "serde", serde(rename = "builtin"))]
+	Builtin,
+	/// An arbitrary JSON value whose structure is not known statically.
+	#[cfg_attr(feature = "serde", serde(rename = "json"))]
+	Json,
+	/// A retired type whose definition has been removed while its type symbol stays allocated.
+	#[cfg_attr(feature = "serde", serde(rename = "tombstone"))]
+	Tombstone,
+	/// An opaque payload in the given format that is passed through without being parsed.
+	#[cfg_attr(feature = "serde", serde(rename = "raw"))]
+	Raw(RawFormat),
+}
+
+/// The declared format of an opaque raw payload.
+///
+/// Gateways that forward payloads unparsed only validate the envelope around them.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RawFormat {
+	/// A JSON document, e.g. a `serde_json::value::RawValue`.
+	Json,
+	/// SCALE encoded bytes.
+	Scale,
+}
+
+impl RawFormat {
+	/// Returns the lowercase name of the format.
+	pub fn name(self) -> &'static str {
+		match self {
+			RawFormat::Json => "json",
+			RawFormat::Scale => "scale",
+		}
+	}
+}
+
+impl TypeDef<CompactForm> {
+	/// Appends the symbols of all field types of the type definition.
+	#[allow(deprecated)]
+	pub(crate) fn collect_type_refs(&self, refs: &mut Vec<UntrackedSymbol<AnyTypeId>>) {
+		match self {
+			TypeDef::Builtin(_) | TypeDef::ClikeEnum(_) => (),
+			TypeDef::Struct(def) => refs.extend(def.fields.iter().map(|field| field.ty)),
+			TypeDef::TupleStruct(def) => refs.extend(def.fields.iter().map(|field| field.ty)),
+			TypeDef::Composite(def) => refs.extend(def.fields.iter().map(|field| field.ty)),
+			TypeDef::Enum(def) => {
+				for variant in &def.variants {
+					match variant {
+						EnumVariant::Unit(_) => (),
+						EnumVariant::Struct(variant) => refs.extend(variant.fields.iter().map(|field| field.ty)),
+						EnumVariant::TupleStruct(variant) => refs.extend(variant.fields.iter().map(|field| field.ty)),
+					}
+				}
+			}
+			TypeDef::Variant(def) => {
+				for variant in &def.variants {
+					refs.extend(variant.fields.iter().map(|field| field.ty))
+				}
+			}
+			TypeDef::Union(def) => refs.extend(def.fields.iter().map(|field| field.ty)),
+		}
+	}
+}
+
+impl IntoCompact for TypeDef {
+	type Output = TypeDef<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		match self {
+			TypeDef::Builtin(builtin) => TypeDef::Builtin(builtin),
+			TypeDef::Struct(r#struct) => r#struct.into_compact(registry).into(),
+			TypeDef::TupleStruct(tuple_struct) => tuple_struct.into_compact(registry).into(),
+			TypeDef::Composite(composite) => composite.into_compact(registry).into(),
+			TypeDef::ClikeEnum(clike_enum) => clike_enum.into_compact(registry).into(),
+			TypeDef::Enum(r#enum) => r#enum.into_compact(registry).into(),
+			TypeDef::Variant(variant) => variant.into_compact(registry).into(),
+			TypeDef::Union(union) => union.into_compact(registry).into(),
+		}
+	}
+}
+
+impl Resolve for TypeDef<CompactForm> {
+	type Output = TypeDef<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		match self {
+			TypeDef::Builtin(builtin) => TypeDef::Builtin(*builtin),
+			TypeDef::Struct(r#struct) => r#struct.resolve(registry).into(),
+			TypeDef::TupleStruct(tuple_struct) => tuple_struct.resolve(registry).into(),
+			TypeDef::Composite(composite) => composite.resolve(registry).into(),
+			TypeDef::ClikeEnum(clike_enum) => clike_enum.resolve(registry).into(),
+			TypeDef::Enum(r#enum) => r#enum.resolve(registry).into(),
+			TypeDef::Variant(variant) => variant.resolve(registry).into(),
+			TypeDef::Union(union) => union.resolve(registry).into(),
+		}
+	}
+}
+
+impl IntoExpanded for TypeDef {
+	type Output = TypeDef<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		match self {
+			TypeDef::Builtin(builtin) => TypeDef::Builtin(builtin),
+			TypeDef::Struct(r#struct) => r#struct.into_expanded(expander).into(),
+			TypeDef::TupleStruct(tuple_struct) => tuple_struct.into_expanded(expander).into(),
+			TypeDef::Composite(composite) => composite.into_expanded(expander).into(),
+			TypeDef::ClikeEnum(clike_enum) => clike_enum.into_expanded(expander).into(),
+			TypeDef::Enum(r#enum) => r#enum.into_expanded(expander).into(),
+			TypeDef::Variant(variant) => variant.into_expanded(expander).into(),
+			TypeDef::Union(union) => union.into_expanded(expander).into(),
+		}
+	}
+}
+
+/// A struct or tuple-struct with either named or unnamed fields.
+///
+/// This unifies `TypeDefStruct` and `TypeDefTupleStruct` so that consumers only have to
+/// handle a single shape. Either all or none of the fields shall be named. Composites
+/// without fields represent unit structs.
+///
+/// Composites serialize exactly like the struct or tuple-struct they are equivalent to.
+///
+/// # Example
+///
+/// ```
+/// struct Person {
+///     name: String,
+///     age_in_years: u8,
+/// }
+/// ```
+/// or
+/// ```
+/// struct Color(u8, u8, u8);
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct TypeDefComposite<F: Form = MetaForm> {
+	/// The fields of the composite.
+	fields: Items<Field<F>>,
+}
+
+#[cfg(feature = "serde")]
+impl<F: Form> Serialize for TypeDefComposite<F>
+where
+	F::TypeId: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let mut state = serializer.serialize_struct("TypeDefComposite", 1)?;
+		if self.is_named() {
+			state.serialize_field("struct.fields", &self.fields[..])?;
+		} else {
+			let types = self.fields.iter().map(Field::ty).collect::<Vec<_>>();
+			state.serialize_field("tuple_struct.types", &types)?;
+		}
+		state.end()
+	}
+}
+
+impl IntoCompact for TypeDefComposite {
+	type Output = TypeDefComposite<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		TypeDefComposite {
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_compact(registry))
+				.collect::<Items<_>>(),
+		}
+	}
+}
+
+impl Resolve for TypeDefComposite<CompactForm> {
+	type Output = TypeDefComposite<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefComposite {
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for TypeDefComposite {
+	type Output = TypeDefComposite<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefComposite {
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefStruct<F>> for TypeDefComposite<F> {
+	fn from(def: TypeDefStruct<F>) -> Self {
+		Self {
+			fields: def.fields.into_iter().map(Field::from).collect(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefTupleStruct<F>> for TypeDefComposite<F> {
+	fn from(def: TypeDefTupleStruct<F>) -> Self {
+		Self {
+			fields: def.fields.into_iter().map(Field::from).collect(),
+		}
+	}
+}
+
+impl TypeDefComposite {
+	/// Creates a new composite definition with the given named or unnamed fields.
+	pub fn new<I>(fields: I) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Into<Field>,
+	{
+		Self {
+			fields: fields.into_iter().map(Into::into).collect(),
+		}
+	}
+
+	/// Creates the unit composite that has no fields.
+	pub const fn unit() -> Self {
+		Self { fields: new_items() }
+	}
+}
+
+impl<F: Form> TypeDefComposite<F> {
+	/// Creates a composite in any form from its fields.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_fields(fields: Items<Field<F>>) -> Self {
+		Self { fields }
+	}
+
+	/// Returns the fields of the composite.
+	pub fn fields(&self) -> &[Field<F>] {
+		&self.fields
+	}
+
+	/// Returns `true` if the composite has fields and all of them are named.
+	pub fn is_named(&self) -> bool {
+		!self.fields.is_empty() && self.fields.iter().all(|field| field.name.is_some())
+	}
+}
+
+/// A field of a composite that is named for structs and unnamed for tuple-structs.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+pub struct Field<F: Form = MetaForm> {
+	/// The name of the field, if any.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	name: Option<F::String>,
+	/// The type of the field.
+	#[cfg_attr(feature = "serde", serde(rename = "type"))]
+	ty: F::TypeId,
+	/// The label of the logical section the field belongs to, if any.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	group: Option<F::String>,
+	/// How sensitive the values of the field are.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Sensitivity::is_public"))]
+	sensitivity: Sensitivity,
+	/// The format hint for displaying the values of the field, e.g. `"hex"`, if any.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	display: Option<F::String>,
+	/// The documentation of the field, one entry per line.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+	docs: Vec<F::String>,
+}
+
+impl IntoCompact for Field {
+	type Output = Field<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		Field {
+			name: self.name.map(|name| registry.compact_string(name)),
+			ty: registry.compact_type(&self.ty),
+			group: self.group.map(|group| registry.compact_string(group)),
+			sensitivity: self.sensitivity,
+			display: self.display.map(|display| registry.compact_string(display)),
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for Field<CompactForm> {
+	type Output = Field<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		Field {
+			name: self.name.map(|name| registry.resolved_string(name)),
+			ty: self.ty,
+			group: self.group.map(|group| registry.resolved_string(group)),
+			sensitivity: self.sensitivity,
+			display: self.display.map(|display| registry.resolved_string(display)),
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for Field {
+	type Output = Field<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		Field {
+			name: self.name,
+			ty: expander.expand(&self.ty),
+			group: self.group,
+			sensitivity: self.sensitivity,
+			display: self.display,
+			docs: self.docs,
+		}
+	}
+}
+
+impl<F: Form> From<NamedField<F>> for Field<F> {
+	fn from(field: NamedField<F>) -> Self {
+		Self {
+			name: Some(field.name),
+			ty: field.ty,
+			group: field.group,
+			sensitivity: field.sensitivity,
+			display: field.display,
+			docs: field.docs,
+		}
+	}
+}
+
+impl<F: Form> From<UnnamedField<F>> for Field<F> {
+	fn from(field: UnnamedField<F>) -> Self {
+		Self {
+			name: None,
+			ty: field.ty,
+			group: None,
+			sensitivity: Sensitivity::Public,
+			display: None,
+			docs: Vec::new(),
+		}
+	}
+}
+
+impl Field {
+	/// Creates a new named field.
+	pub const fn named(name: <MetaForm as Form>::String, ty: MetaType) -> Self {
+		Self {
+			name: Some(name),
+			ty,
+			group: None,
+			sensitivity: Sensitivity::Public,
+			display: None,
+			docs: Vec::new(),
+		}
+	}
+
+	/// Creates a new unnamed field.
+	pub const fn unnamed(ty: MetaType) -> Self {
+		Self {
+			name: None,
+			ty,
+			group: None,
+			sensitivity: Sensitivity::Public,
+			display: None,
+			docs: Vec::new(),
+		}
+	}
+
+	/// Assigns the field to the logical section with the given label, e.g. `"limits"`.
+	pub const fn with_group(mut self, group: <MetaForm as Form>::String) -> Self {
+		self.group = Some(group);
+		self
+	}
+
+	/// Marks the values of the field with the given sensitivity, e.g. `Sensitivity::Secret`.
+	pub const fn with_sensitivity(mut self, sensitivity: Sensitivity) -> Self {
+		self.sensitivity = sensitivity;
+		self
+	}
+
+	/// Hints how to display the values of the field, e.g. `"hex"`, `"ss58"`, `"percentage"` or `"duration-ms"`.
+	pub const fn with_display(mut self, display: <MetaForm as Form>::String) -> Self {
+		self.display = Some(display);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the field.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> Field<F> {
+	/// Creates a field in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(
+		name: Option<F::String>,
+		ty: F::TypeId,
+		group: Option<F::String>,
+		sensitivity: Sensitivity,
+		display: Option<F::String>,
+		docs: Vec<F::String>,
+	) -> Self {
+		Self {
+			name,
+			ty,
+			group,
+			sensitivity,
+			display,
+			docs,
+		}
+	}
+
+	/// Returns the name of the field, if any.
+	pub fn name(&self) -> Option<&F::String> {
+		self.name.as_ref()
+	}
+
+	/// Returns the type of the field.
+	pub fn ty(&self) -> &F::TypeId {
+		&self.ty
+	}
+
+	/// Returns the label of the logical section the field belongs to, if any.
+	pub fn group(&self) -> Option<&F::String> {
+		self.group.as_ref()
+	}
+
+	/// Returns how sensitive the values of the field are.
+	pub fn sensitivity(&self) -> Sensitivity {
+		self.sensitivity
+	}
+
+	/// Returns the format hint for displaying the values of the field, if any.
+	pub fn display(&self) -> Option<&F::String> {
+		self.display.as_ref()
+	}
+
+	/// Returns the documentation of the field, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
+	}
+}
+
+/// A Rust enum whose variants may carry fields and discriminants.
+///
+/// This unifies `TypeDefClikeEnum` and `TypeDefEnum` so that consumers only have to
+/// handle a single shape. Each variant has optional fields and an optional discriminant
+/// which also covers enums mixing variants that carry data with explicit discriminants.
+///
+/// Enums whose variants all lack fields but have discriminants serialize exactly like C-like
+/// enums. All other enums serialize like Rust enums where each variant additionally carries
+/// its discriminant, if any.
+///
+/// # Example
+///
+/// ```
+/// #[repr(u8)]
+/// enum Command {
+///     Stop = 1,
+///     Move { x: i32, y: i32 } = 4,
+///     Say(String),
+/// }
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct TypeDefVariant<F: Form = MetaForm> {
+	/// The variants of the enum.
+	variants: Items<Variant<F>>,
+	/// The representation of the enum upon serialization.
+	repr: EnumRepr<F>,
+	/// Whether the fieldless variant is encoded as the zero value of the field of the other variant.
+	niche: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<F: Form> Serialize for TypeDefVariant<F>
+where
+	F::TypeId: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		if self.is_clike() {
+			let mut state = serializer.serialize_struct("TypeDefVariant", 1)?;
+			let variants = self.variants.iter().map(ClikeVariant).collect::<Vec<_>>();
+			state.serialize_field("clike_enum.variants", &variants)?;
+			return state.end();
+		}
+		let mut state = serializer.serialize_struct("TypeDefVariant", 3)?;
+		state.serialize_field("enum.variants", &self.variants[..])?;
+		if !self.repr.is_external() {
+			state.serialize_field("enum.repr", &self.repr)?;
+		}
+		if self.niche {
+			state.serialize_field("enum.niche", &self.niche)?;
+		}
+		state.end()
+	}
+}
+
+/// Serializes a variant of a C-like enum just like `ClikeEnumVariant`.
+#[cfg(feature = "serde")]
+struct ClikeVariant<'a, F: Form>(&'a Variant<F>);
+
+#[cfg(feature = "serde")]
+impl<'a, F: Form> Serialize for ClikeVariant<'a, F> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let mut state = serializer.serialize_struct("ClikeEnumVariant", 3)?;
+		state.serialize_field("name", &self.0.name)?;
+		state.serialize_field("discriminant", &self.0.discriminant.unwrap_or_default())?;
+		if let Some(code) = self.0.code {
+			state.serialize_field("code", &code)?;
+		}
+		state.end()
+	}
+}
+
+impl IntoCompact for TypeDefVariant {
+	type Output = TypeDefVariant<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		TypeDefVariant {
+			variants: self
+				.variants
+				.into_iter()
+				.map(|variant| variant.into_compact(registry))
+				.collect::<Items<_>>(),
+			repr: self.repr.into_compact(registry),
+			niche: self.niche,
+		}
+	}
+}
+
+impl Resolve for TypeDefVariant<CompactForm> {
+	type Output = TypeDefVariant<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefVariant {
+			variants: self.variants.iter().map(|variant| variant.resolve(registry)).collect(),
+			repr: self.repr.resolve(registry),
+			niche: self.niche,
+		}
+	}
+}
+
+impl IntoExpanded for TypeDefVariant {
+	type Output = TypeDefVariant<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefVariant {
+			variants: self
+				.variants
+				.into_iter()
+				.map(|variant| variant.into_expanded(expander))
+				.collect::<Items<_>>(),
+			repr: self.repr.into_expanded(expander),
+			niche: self.niche,
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefClikeEnum<F>> for TypeDefVariant<F> {
+	fn from(def: TypeDefClikeEnum<F>) -> Self {
+		Self {
+			variants: def.variants.into_iter().map(Variant::from).collect(),
+			repr: EnumRepr::External,
+			niche: false,
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> From<TypeDefEnum<F>> for TypeDefVariant<F> {
+	fn from(def: TypeDefEnum<F>) -> Self {
+		Self {
+			variants: def.variants.into_iter().map(Variant::from).collect(),
+			repr: def.repr,
+			niche: false,
+		}
+	}
+}
+
+impl TypeDefVariant {
+	/// Creates a new enum definition from the given variants.
+	pub fn new<I>(variants: I) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Into<Variant>,
+	{
+		Self {
+			variants: variants.into_iter().map(Into::into).collect(),
+			repr: EnumRepr::External,
+			niche: false,
+		}
+	}
+
+	/// Sets the representation of the enum upon serialization.
+	pub fn with_repr(mut self, repr: EnumRepr) -> Self {
+		self.repr = repr;
+		self
+	}
+
+	/// Sets whether the fieldless variant is encoded as the zero value of the single
+	/// field of the other variant, e.g. `None` of `Option<NonZeroU32>`.
+	pub fn with_niche(mut self, niche: bool) -> Self {
+		self.niche = niche;
+		self
+	}
+}
+
+impl<F: Form> TypeDefVariant<F> {
+	/// Creates an enum definition in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(variants: Items<Variant<F>>, repr: EnumRepr<F>, niche: bool) -> Self {
+		Self { variants, repr, niche }
+	}
+
+	/// Returns the variants of the enum.
+	pub fn variants(&self) -> &[Variant<F>] {
+		&self.variants
+	}
+
+	/// Returns the representation of the enum upon serialization.
+	pub fn repr(&self) -> &EnumRepr<F> {
+		&self.repr
+	}
+
+	/// Returns `true` if the fieldless variant is encoded as the zero value of the
+	/// field of the other variant instead of a separate tag.
+	pub fn is_niche(&self) -> bool {
+		self.niche
+	}
+
+	/// Returns `true` if the enum is C-like.
+	///
+	/// This is the case if it is externally tagged and none of its variants carries fields
+	/// while all of them have a discriminant.
+	pub fn is_clike(&self) -> bool {
+		self.repr.is_external()
+			&& !self.niche
+			&& self
+				.variants
+				.iter()
+				.all(|variant| variant.fields.is_empty() && variant.discriminant.is_some())
+	}
+}
+
+/// A variant of an enum with optional fields and an optional discriminant.
+///
+/// Variants without fields are unit variants. Otherwise either all or none of the fields
+/// shall be named for struct and tuple-struct variants respectively.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Variant<F: Form = MetaForm> {
+	/// The name of the variant.
+	name: F::String,
+	/// The fields of the variant.
+	fields: Items<Field<F>>,
+	/// The discriminant of the variant, if any.
+	discriminant: Option<u64>,
+	/// The application-level error code of the variant, if any.
+	code: Option<u32>,
+	/// The documentation of the variant, one entry per line.
+	docs: Vec<F::String>,
+}
+
+/// The serialized keys of name, fields, code, discriminant and docs of unit variants.
+#[cfg(feature = "serde")]
+const UNIT_VARIANT_KEYS: [&str; 5] = [
+	"unit_variant.name",
+	"unit_variant.fields",
+	"unit_variant.code",
+	"unit_variant.discriminant",
+	"unit_variant.docs",
+];
+
+/// The serialized keys of name, fields, code, discriminant and docs of struct variants.
+#[cfg(feature = "serde")]
+const STRUCT_VARIANT_KEYS: [&str; 5] = [
+	"struct_variant.name",
+	"struct_variant.fields",
+	"struct_variant.code",
+	"struct_variant.discriminant",
+	"struct_variant.docs",
+];
+
+/// The serialized keys of name, fields, code, discriminant and docs of tuple-struct variants.
+#[cfg(feature = "serde")]
+const TUPLE_STRUCT_VARIANT_KEYS: [&str; 5] = [
+	"tuple_struct_variant.name",
+	"tuple_struct_variant.types",
+	"tuple_struct_variant.code",
+	"tuple_struct_variant.discriminant",
+	"tuple_struct_variant.docs",
+];
+
+#[cfg(feature = "serde")]
+impl<F: Form> Serialize for Variant<F>
+where
+	F::TypeId: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let named = self.fields.iter().all(|field| field.name.is_some());
+		let [name_key, fields_key, code_key, discriminant_key, docs_key] = if self.fields.is_empty() {
+			UNIT_VARIANT_KEYS
+		} else if named {
+			STRUCT_VARIANT_KEYS
+		} else {
+			TUPLE_STRUCT_VARIANT_KEYS
+		};
+		let mut state = serializer.serialize_struct("Variant", 5)?;
+		state.serialize_field(name_key, &self.name)?;
+		if !self.fields.is_empty() {
+			if named {
+				state.serialize_field(fields_key, &self.fields[..])?;
+			} else {
+				let types = self.fields.iter().map(Field::ty).collect::<Vec<_>>();
+				state.serialize_field(fields_key, &types)?;
+			}
+		}
+		if let Some(code) = self.code {
+			state.serialize_field(code_key, &code)?;
+		}
+		if let Some(discriminant) = self.discriminant {
+			state.serialize_field(discriminant_key, &discriminant)?;
+		}
+		if !self.docs.is_empty() {
+			state.serialize_field(docs_key, &self.docs)?;
+		}
+		state.end()
+	}
+}
+
+impl IntoCompact for Variant {
+	type Output = Variant<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		Variant {
+			name: registry.compact_string(self.name),
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_compact(registry))
+				.collect::<Items<_>>(),
+			discriminant: self.discriminant,
+			code: self.code,
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for Variant<CompactForm> {
+	type Output = Variant<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		Variant {
+			name: registry.resolved_string(self.name),
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+			discriminant: self.discriminant,
+			code: self.code,
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for Variant {
+	type Output = Variant<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		Variant {
+			name: self.name,
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
+			discriminant: self.discriminant,
+			code: self.code,
+			docs: self.docs,
+		}
+	}
+}
+
+impl<F: Form> From<ClikeEnumVariant<F>> for Variant<F> {
+	fn from(variant: ClikeEnumVariant<F>) -> Self {
+		Self {
+			name: variant.name,
+			fields: new_items(),
+			discriminant: Some(variant.discriminant),
+			code: variant.code,
+			docs: Vec::new(),
+		}
 	}
 }
 
-/// This struct just exists for the purpose of better JSON output.
-#[derive(PartialEq, Eq, Debug, Serialize)]
-pub enum Builtin {
-	/// This enum variant just exists for the purpose of special JSON output.
-	#[serde(rename = "builtin")]
-	Builtin,
+impl<F: Form> From<EnumVariant<F>> for Variant<F> {
+	fn from(variant: EnumVariant<F>) -> Self {
+		let (name, fields, code, docs) = match variant {
+			EnumVariant::Unit(unit) => (unit.name, new_items(), unit.code, unit.docs),
+			EnumVariant::Struct(r#struct) => (
+				r#struct.name,
+				r#struct.fields.into_iter().map(Field::from).collect(),
+				r#struct.code,
+				r#struct.docs,
+			),
+			EnumVariant::TupleStruct(tuple_struct) => (
+				tuple_struct.name,
+				tuple_struct.fields.into_iter().map(Field::from).collect(),
+				tuple_struct.code,
+				tuple_struct.docs,
+			),
+		};
+		Self {
+			name,
+			fields,
+			discriminant: None,
+			code,
+			docs,
+		}
+	}
 }
 
-impl IntoCompact for TypeDef {
-	type Output = TypeDef<CompactForm>;
+impl Variant {
+	/// Creates a new unit variant without discriminant.
+	pub const fn new(name: <MetaForm as Form>::String) -> Self {
+		Self {
+			name,
+			fields: new_items(),
+			discriminant: None,
+			code: None,
+			docs: Vec::new(),
+		}
+	}
 
-	fn into_compact(self, registry: &mut Registry) -> Self::Output {
-		match self {
-			TypeDef::Builtin(builtin) => TypeDef::Builtin(builtin),
-			TypeDef::Struct(r#struct) => r#struct.into_compact(registry).into(),
-			TypeDef::TupleStruct(tuple_struct) => tuple_struct.into_compact(registry).into(),
-			TypeDef::ClikeEnum(clike_enum) => clike_enum.into_compact(registry).into(),
-			TypeDef::Enum(r#enum) => r#enum.into_compact(registry).into(),
-			TypeDef::Union(union) => union.into_compact(registry).into(),
+	/// Assigns the given named or unnamed fields to the variant.
+	pub fn with_fields<I>(mut self, fields: I) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Into<Field>,
+	{
+		self.fields = fields.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Assigns the given discriminant to the variant.
+	pub const fn with_discriminant(mut self, discriminant: u64) -> Self {
+		self.discriminant = Some(discriminant);
+		self
+	}
+
+	/// Assigns the given application-level error code to the variant.
+	pub const fn with_code(mut self, code: u32) -> Self {
+		self.code = Some(code);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the variant.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> Variant<F> {
+	/// Creates a variant in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(
+		name: F::String,
+		fields: Items<Field<F>>,
+		discriminant: Option<u64>,
+		code: Option<u32>,
+		docs: Vec<F::String>,
+	) -> Self {
+		Self {
+			name,
+			fields,
+			discriminant,
+			code,
+			docs,
 		}
 	}
+
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the fields of the variant.
+	pub fn fields(&self) -> &[Field<F>] {
+		&self.fields
+	}
+
+	/// Returns the discriminant of the variant, if any.
+	pub fn discriminant(&self) -> Option<u64> {
+		self.discriminant
+	}
+
+	/// Returns the application-level error code of the variant, if any.
+	pub fn code(&self) -> Option<u32> {
+		self.code
+	}
+
+	/// Returns the documentation of the variant, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
+	}
 }
 
 /// A Rust struct with named fields.
 ///
+/// Superseded by `TypeDefComposite` and kept for backwards compatibility.
+///
 /// # Example
 ///
 /// ```
@@ -93,14 +1128,17 @@ impl IntoCompact for TypeDef {
 ///     friends: Vec<Person>,
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[deprecated(note = "use `TypeDefComposite` instead")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct TypeDefStruct<F: Form = MetaForm> {
 	/// The named fields of the struct.
-	#[serde(rename = "struct.fields")]
-	fields: Vec<NamedField<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "struct.fields"))]
+	fields: Items<NamedField<F>>,
 }
 
+#[allow(deprecated)]
 impl IntoCompact for TypeDefStruct {
 	type Output = TypeDefStruct<CompactForm>;
 
@@ -110,13 +1148,41 @@ impl IntoCompact for TypeDefStruct {
 				.fields
 				.into_iter()
 				.map(|field| field.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl Resolve for TypeDefStruct<CompactForm> {
+	type Output = TypeDefStruct<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefStruct {
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl IntoExpanded for TypeDefStruct {
+	type Output = TypeDefStruct<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefStruct {
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
 		}
 	}
 }
 
+#[allow(deprecated)]
 impl TypeDefStruct {
 	/// Creates a new struct definition with named fields.
+	#[deprecated(note = "use `TypeDefComposite` instead")]
 	pub fn new<F>(fields: F) -> Self
 	where
 		F: IntoIterator<Item = NamedField>,
@@ -127,17 +1193,40 @@ impl TypeDefStruct {
 	}
 }
 
+#[allow(deprecated)]
+impl<F: Form> TypeDefStruct<F> {
+	/// Returns the named fields of the struct.
+	pub fn fields(&self) -> &[NamedField<F>] {
+		&self.fields
+	}
+}
+
 /// A named field.
 ///
 /// This can be a named field of a struct type or a struct variant.
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct NamedField<F: Form = MetaForm> {
 	/// The name of the field.
 	name: F::String,
 	/// The type of the field.
-	#[serde(rename = "type")]
+	#[cfg_attr(feature = "serde", serde(rename = "type"))]
 	ty: F::TypeId,
+	/// The label of the logical section the field belongs to, if any.
+	///
+	/// User interface generators may use it to render grouped forms.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	group: Option<F::String>,
+	/// How sensitive the values of the field are.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Sensitivity::is_public"))]
+	sensitivity: Sensitivity,
+	/// The format hint for displaying the values of the field, e.g. `"hex"`, if any.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	display: Option<F::String>,
+	/// The documentation of the field, one entry per line.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+	docs: Vec<F::String>,
 }
 
 impl IntoCompact for NamedField {
@@ -145,8 +1234,42 @@ impl IntoCompact for NamedField {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		NamedField {
-			name: registry.register_string(self.name),
-			ty: registry.register_type(&self.ty),
+			name: registry.compact_string(self.name),
+			ty: registry.compact_type(&self.ty),
+			group: self.group.map(|group| registry.compact_string(group)),
+			sensitivity: self.sensitivity,
+			display: self.display.map(|display| registry.compact_string(display)),
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for NamedField<CompactForm> {
+	type Output = NamedField<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		NamedField {
+			name: registry.resolved_string(self.name),
+			ty: self.ty,
+			group: self.group.map(|group| registry.resolved_string(group)),
+			sensitivity: self.sensitivity,
+			display: self.display.map(|display| registry.resolved_string(display)),
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for NamedField {
+	type Output = NamedField<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		NamedField {
+			name: self.name,
+			ty: expander.expand(&self.ty),
+			group: self.group,
+			sensitivity: self.sensitivity,
+			display: self.display,
+			docs: self.docs,
 		}
 	}
 }
@@ -155,8 +1278,15 @@ impl NamedField {
 	/// Creates a new named field.
 	///
 	/// Use this constructor if you want to instantiate from a given meta type.
-	pub fn new(name: <MetaForm as Form>::String, ty: MetaType) -> Self {
-		Self { name, ty }
+	pub const fn new(name: <MetaForm as Form>::String, ty: MetaType) -> Self {
+		Self {
+			name,
+			ty,
+			group: None,
+			sensitivity: Sensitivity::Public,
+			display: None,
+			docs: Vec::new(),
+		}
 	}
 
 	/// Creates a new named field.
@@ -168,10 +1298,133 @@ impl NamedField {
 	{
 		Self::new(name, MetaType::new::<T>())
 	}
+
+	/// Assigns the field to the logical section with the given label, e.g. `"limits"`.
+	pub const fn with_group(mut self, group: <MetaForm as Form>::String) -> Self {
+		self.group = Some(group);
+		self
+	}
+
+	/// Marks the values of the field with the given sensitivity, e.g. `Sensitivity::Secret`.
+	pub const fn with_sensitivity(mut self, sensitivity: Sensitivity) -> Self {
+		self.sensitivity = sensitivity;
+		self
+	}
+
+	/// Hints how to display the values of the field, e.g. `"hex"`, `"ss58"`, `"percentage"` or `"duration-ms"`.
+	pub const fn with_display(mut self, display: <MetaForm as Form>::String) -> Self {
+		self.display = Some(display);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the field.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> NamedField<F> {
+	/// Creates a named field in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(
+		name: F::String,
+		ty: F::TypeId,
+		group: Option<F::String>,
+		sensitivity: Sensitivity,
+		display: Option<F::String>,
+		docs: Vec<F::String>,
+	) -> Self {
+		Self {
+			name,
+			ty,
+			group,
+			sensitivity,
+			display,
+			docs,
+		}
+	}
+
+	/// Returns the name of the field.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the type of the field.
+	pub fn ty(&self) -> &F::TypeId {
+		&self.ty
+	}
+
+	/// Returns the label of the logical section the field belongs to, if any.
+	pub fn group(&self) -> Option<&F::String> {
+		self.group.as_ref()
+	}
+
+	/// Returns how sensitive the values of the field are.
+	pub fn sensitivity(&self) -> Sensitivity {
+		self.sensitivity
+	}
+
+	/// Returns the format hint for displaying the values of the field, if any.
+	pub fn display(&self) -> Option<&F::String> {
+		self.display.as_ref()
+	}
+
+	/// Returns the documentation of the field, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
+	}
+}
+
+/// How sensitive the values of a field are.
+///
+/// Log scrubbing middleware and export filters may use it in order to mask or drop the
+/// values of sensitive fields. Sensitivities are ordered from the least to the most
+/// sensitive so that filters can check for e.g. `sensitivity >= Sensitivity::Internal`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Sensitivity {
+	/// The values may be shown to anyone.
+	#[default]
+	Public,
+	/// The values may only be shown within the organization, e.g. in internal logs.
+	Internal,
+	/// The values must never be shown, e.g. passwords or private keys.
+	Secret,
+}
+
+impl Sensitivity {
+	/// Returns `true` if the values may be shown to anyone.
+	pub fn is_public(&self) -> bool {
+		*self == Sensitivity::Public
+	}
+
+	/// Returns the name of the sensitivity as used in attributes, e.g. `"secret"`.
+	pub fn name(self) -> &'static str {
+		match self {
+			Sensitivity::Public => "public",
+			Sensitivity::Internal => "internal",
+			Sensitivity::Secret => "secret",
+		}
+	}
+
+	/// Returns the sensitivity of the given name, e.g. `"secret"`, if any.
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"public" => Some(Sensitivity::Public),
+			"internal" => Some(Sensitivity::Internal),
+			"secret" => Some(Sensitivity::Secret),
+			_ => None,
+		}
+	}
 }
 
 /// A tuple struct with unnamed fields.
 ///
+/// Superseded by `TypeDefComposite` and kept for backwards compatibility.
+///
 /// # Example
 ///
 /// ```
@@ -181,14 +1434,17 @@ impl NamedField {
 /// ```
 /// struct JustAMarker;
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[deprecated(note = "use `TypeDefComposite` instead")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct TypeDefTupleStruct<F: Form = MetaForm> {
 	/// The unnamed fields.
-	#[serde(rename = "tuple_struct.types")]
-	fields: Vec<UnnamedField<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "tuple_struct.types"))]
+	fields: Items<UnnamedField<F>>,
 }
 
+#[allow(deprecated)]
 impl IntoCompact for TypeDefTupleStruct {
 	type Output = TypeDefTupleStruct<CompactForm>;
 
@@ -198,13 +1454,41 @@ impl IntoCompact for TypeDefTupleStruct {
 				.fields
 				.into_iter()
 				.map(|field| field.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl Resolve for TypeDefTupleStruct<CompactForm> {
+	type Output = TypeDefTupleStruct<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefTupleStruct {
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl IntoExpanded for TypeDefTupleStruct {
+	type Output = TypeDefTupleStruct<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefTupleStruct {
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
 		}
 	}
 }
 
+#[allow(deprecated)]
 impl TypeDefTupleStruct {
 	/// Creates a new tuple-struct.
+	#[deprecated(note = "use `TypeDefComposite` instead")]
 	pub fn new<F>(fields: F) -> Self
 	where
 		F: IntoIterator<Item = UnnamedField>,
@@ -215,18 +1499,28 @@ impl TypeDefTupleStruct {
 	}
 
 	/// Creates the unit tuple-struct that has no fields.
-	pub fn unit() -> Self {
-		Self { fields: vec![] }
+	#[deprecated(note = "use `TypeDefComposite` instead")]
+	pub const fn unit() -> Self {
+		Self { fields: new_items() }
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> TypeDefTupleStruct<F> {
+	/// Returns the unnamed fields of the tuple-struct.
+	pub fn fields(&self) -> &[UnnamedField<F>] {
+		&self.fields
 	}
 }
 
 /// An unnamed field from either a tuple-struct type or a tuple-struct variant.
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
-#[serde(transparent)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct UnnamedField<F: Form = MetaForm> {
 	/// The type of the unnamed field.
-	#[serde(rename = "type")]
+	#[cfg_attr(feature = "serde", serde(rename = "type"))]
 	ty: F::TypeId,
 }
 
@@ -235,7 +1529,25 @@ impl IntoCompact for UnnamedField {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		UnnamedField {
-			ty: registry.register_type(&self.ty),
+			ty: registry.compact_type(&self.ty),
+		}
+	}
+}
+
+impl Resolve for UnnamedField<CompactForm> {
+	type Output = UnnamedField<ResolvedForm>;
+
+	fn resolve(&self, _registry: &Registry) -> Self::Output {
+		UnnamedField { ty: self.ty }
+	}
+}
+
+impl IntoExpanded for UnnamedField {
+	type Output = UnnamedField<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		UnnamedField {
+			ty: expander.expand(&self.ty),
 		}
 	}
 }
@@ -244,7 +1556,7 @@ impl UnnamedField {
 	/// Creates a new unnamed field.
 	///
 	/// Use this constructor if you want to instantiate from a given meta type.
-	pub fn new(meta_type: MetaType) -> Self {
+	pub const fn new(meta_type: MetaType) -> Self {
 		Self { ty: meta_type }
 	}
 
@@ -259,8 +1571,17 @@ impl UnnamedField {
 	}
 }
 
+impl<F: Form> UnnamedField<F> {
+	/// Returns the type of the field.
+	pub fn ty(&self) -> &F::TypeId {
+		&self.ty
+	}
+}
+
 /// A C-like enum type.
 ///
+/// Superseded by `TypeDefVariant` and kept for backwards compatibility.
+///
 /// # Example
 ///
 /// ```
@@ -278,14 +1599,17 @@ impl UnnamedField {
 /// ```
 /// enum JustAMarker {}
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[deprecated(note = "use `TypeDefVariant` instead")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct TypeDefClikeEnum<F: Form = MetaForm> {
 	/// The variants of the C-like enum.
-	#[serde(rename = "clike_enum.variants")]
-	variants: Vec<ClikeEnumVariant<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "clike_enum.variants"))]
+	variants: Items<ClikeEnumVariant<F>>,
 }
 
+#[allow(deprecated)]
 impl IntoCompact for TypeDefClikeEnum {
 	type Output = TypeDefClikeEnum<CompactForm>;
 
@@ -295,13 +1619,41 @@ impl IntoCompact for TypeDefClikeEnum {
 				.variants
 				.into_iter()
 				.map(|variant| variant.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl Resolve for TypeDefClikeEnum<CompactForm> {
+	type Output = TypeDefClikeEnum<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefClikeEnum {
+			variants: self.variants.iter().map(|variant| variant.resolve(registry)).collect(),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl IntoExpanded for TypeDefClikeEnum {
+	type Output = TypeDefClikeEnum<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefClikeEnum {
+			variants: self
+				.variants
+				.into_iter()
+				.map(|variant| variant.into_expanded(expander))
+				.collect::<Items<_>>(),
 		}
 	}
 }
 
+#[allow(deprecated)]
 impl TypeDefClikeEnum {
 	/// Creates a new C-like enum from the given variants.
+	#[deprecated(note = "use `TypeDefVariant` instead")]
 	pub fn new<V>(variants: V) -> Self
 	where
 		V: IntoIterator<Item = ClikeEnumVariant>,
@@ -312,6 +1664,14 @@ impl TypeDefClikeEnum {
 	}
 }
 
+#[allow(deprecated)]
+impl<F: Form> TypeDefClikeEnum<F> {
+	/// Returns the variants of the C-like enum.
+	pub fn variants(&self) -> &[ClikeEnumVariant<F>] {
+		&self.variants
+	}
+}
+
 /// A C-like enum variant.
 ///
 /// # Example
@@ -326,7 +1686,8 @@ impl TypeDefClikeEnum {
 /// //  ^^^^^ and this
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct ClikeEnumVariant<F: Form = MetaForm> {
 	/// The name of the variant.
 	name: F::String,
@@ -338,6 +1699,9 @@ pub struct ClikeEnumVariant<F: Form = MetaForm> {
 	/// every C-like enum variant has a discriminant specified
 	/// upon compile-time.
 	discriminant: u64,
+	/// The application-level error code of the variant, if any.
+	#[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+	code: Option<u32>,
 }
 
 impl IntoCompact for ClikeEnumVariant {
@@ -345,8 +1709,33 @@ impl IntoCompact for ClikeEnumVariant {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		ClikeEnumVariant {
-			name: registry.register_string(self.name),
+			name: registry.compact_string(self.name),
+			discriminant: self.discriminant,
+			code: self.code,
+		}
+	}
+}
+
+impl Resolve for ClikeEnumVariant<CompactForm> {
+	type Output = ClikeEnumVariant<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		ClikeEnumVariant {
+			name: registry.resolved_string(self.name),
+			discriminant: self.discriminant,
+			code: self.code,
+		}
+	}
+}
+
+impl IntoExpanded for ClikeEnumVariant {
+	type Output = ClikeEnumVariant<ExpandedForm>;
+
+	fn into_expanded(self, _expander: &mut Expander) -> Self::Output {
+		ClikeEnumVariant {
+			name: self.name,
 			discriminant: self.discriminant,
+			code: self.code,
 		}
 	}
 }
@@ -360,12 +1749,41 @@ impl ClikeEnumVariant {
 		Self {
 			name,
 			discriminant: discriminant.into(),
+			code: None,
 		}
 	}
+
+	/// Assigns the given application-level error code to the variant.
+	///
+	/// Error codes are distinct from discriminants and allow to map error enums
+	/// onto e.g. HTTP status codes.
+	pub const fn with_code(mut self, code: u32) -> Self {
+		self.code = Some(code);
+		self
+	}
+}
+
+impl<F: Form> ClikeEnumVariant<F> {
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the discriminant of the variant.
+	pub fn discriminant(&self) -> u64 {
+		self.discriminant
+	}
+
+	/// Returns the application-level error code of the variant, if any.
+	pub fn code(&self) -> Option<u32> {
+		self.code
+	}
 }
 
 /// A Rust enum, aka tagged union.
 ///
+/// Superseded by `TypeDefVariant` and kept for backwards compatibility.
+///
 /// # Examples
 ///
 /// ```
@@ -380,14 +1798,23 @@ impl ClikeEnumVariant {
 ///     ItIsntPossibleToSetADiscriminantThough,
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[deprecated(note = "use `TypeDefVariant` instead")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct TypeDefEnum<F: Form = MetaForm> {
 	/// The variants of the enum.
-	#[serde(rename = "enum.variants")]
-	variants: Vec<EnumVariant<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "enum.variants"))]
+	variants: Items<EnumVariant<F>>,
+	/// The representation of the enum upon serialization.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "enum.repr", skip_serializing_if = "EnumRepr::is_external")
+	)]
+	repr: EnumRepr<F>,
 }
 
+#[allow(deprecated)]
 impl IntoCompact for TypeDefEnum {
 	type Output = TypeDefEnum<CompactForm>;
 
@@ -397,31 +1824,179 @@ impl IntoCompact for TypeDefEnum {
 				.variants
 				.into_iter()
 				.map(|variant| variant.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+			repr: self.repr.into_compact(registry),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl Resolve for TypeDefEnum<CompactForm> {
+	type Output = TypeDefEnum<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefEnum {
+			variants: self.variants.iter().map(|variant| variant.resolve(registry)).collect(),
+			repr: self.repr.resolve(registry),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl IntoExpanded for TypeDefEnum {
+	type Output = TypeDefEnum<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefEnum {
+			variants: self
+				.variants
+				.into_iter()
+				.map(|variant| variant.into_expanded(expander))
+				.collect::<Items<_>>(),
+			repr: self.repr.into_expanded(expander),
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl TypeDefEnum {
+	/// Creates a new Rust enum from the given variants.
+	#[deprecated(note = "use `TypeDefVariant` instead")]
+	pub fn new<V>(variants: V) -> Self
+	where
+		V: IntoIterator<Item = EnumVariant>,
+	{
+		Self {
+			variants: variants.into_iter().collect(),
+			repr: EnumRepr::External,
+		}
+	}
+
+	/// Sets the representation of the enum upon serialization.
+	pub fn with_repr(mut self, repr: EnumRepr) -> Self {
+		self.repr = repr;
+		self
+	}
+}
+
+#[allow(deprecated)]
+impl<F: Form> TypeDefEnum<F> {
+	/// Returns the variants of the enum.
+	pub fn variants(&self) -> &[EnumVariant<F>] {
+		&self.variants
+	}
+
+	/// Returns the representation of the enum upon serialization.
+	pub fn repr(&self) -> &EnumRepr<F> {
+		&self.repr
+	}
+}
+
+/// The representation of a Rust enum upon serialization.
+///
+/// This mirrors the enum representations supported by `serde` so that
+/// exporters and dynamic decoders are able to replicate the actual wire shape.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EnumRepr<F: Form = MetaForm> {
+	/// The variant name is the key of a map with the variant contents as value.
+	///
+	/// This is the default representation.
+	External,
+	/// The variant name is stored in the given tag field next to the variant fields.
+	Internal {
+		/// The name of the field holding the variant name.
+		tag: F::String,
+	},
+	/// The variant name and contents are stored in the given tag and content fields.
+	Adjacent {
+		/// The name of the field holding the variant name.
+		tag: F::String,
+		/// The name of the field holding the variant contents.
+		content: F::String,
+	},
+	/// Only the variant contents are stored without any variant name.
+	Untagged,
+}
+
+impl IntoCompact for EnumRepr {
+	type Output = EnumRepr<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		match self {
+			EnumRepr::External => EnumRepr::External,
+			EnumRepr::Internal { tag } => EnumRepr::Internal {
+				tag: registry.compact_string(tag),
+			},
+			EnumRepr::Adjacent { tag, content } => EnumRepr::Adjacent {
+				tag: registry.compact_string(tag),
+				content: registry.compact_string(content),
+			},
+			EnumRepr::Untagged => EnumRepr::Untagged,
+		}
+	}
+}
+
+impl Resolve for EnumRepr<CompactForm> {
+	type Output = EnumRepr<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		match self {
+			EnumRepr::External => EnumRepr::External,
+			EnumRepr::Internal { tag } => EnumRepr::Internal {
+				tag: registry.resolved_string(*tag),
+			},
+			EnumRepr::Adjacent { tag, content } => EnumRepr::Adjacent {
+				tag: registry.resolved_string(*tag),
+				content: registry.resolved_string(*content),
+			},
+			EnumRepr::Untagged => EnumRepr::Untagged,
 		}
 	}
 }
 
-impl TypeDefEnum {
-	/// Creates a new Rust enum from the given variants.
-	pub fn new<V>(variants: V) -> Self
-	where
-		V: IntoIterator<Item = EnumVariant>,
-	{
-		Self {
-			variants: variants.into_iter().collect(),
+impl IntoExpanded for EnumRepr {
+	type Output = EnumRepr<ExpandedForm>;
+
+	fn into_expanded(self, _expander: &mut Expander) -> Self::Output {
+		match self {
+			EnumRepr::External => EnumRepr::External,
+			EnumRepr::Internal { tag } => EnumRepr::Internal { tag },
+			EnumRepr::Adjacent { tag, content } => EnumRepr::Adjacent { tag, content },
+			EnumRepr::Untagged => EnumRepr::Untagged,
 		}
 	}
 }
 
+impl EnumRepr {
+	/// Creates an internally tagged enum representation.
+	pub const fn internal(tag: <MetaForm as Form>::String) -> Self {
+		EnumRepr::Internal { tag }
+	}
+
+	/// Creates an adjacently tagged enum representation.
+	pub const fn adjacent(tag: <MetaForm as Form>::String, content: <MetaForm as Form>::String) -> Self {
+		EnumRepr::Adjacent { tag, content }
+	}
+}
+
+impl<F: Form> EnumRepr<F> {
+	/// Returns `true` if this is the default externally tagged representation.
+	pub fn is_external(&self) -> bool {
+		matches!(self, EnumRepr::External)
+	}
+}
+
 /// A Rust enum variant.
 ///
 /// This can either be a unit struct, just like in C-like enums,
 /// a tuple-struct with unnamed fields,
 /// or a struct with named fields.
-#[derive(PartialEq, Eq, Debug, Serialize, From)]
-#[serde(bound = "F::TypeId: Serialize")]
-#[serde(untagged)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, From)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum EnumVariant<F: Form = MetaForm> {
 	/// A unit struct variant.
 	Unit(EnumVariantUnit<F>),
@@ -431,6 +2006,26 @@ pub enum EnumVariant<F: Form = MetaForm> {
 	TupleStruct(EnumVariantTupleStruct<F>),
 }
 
+impl<F: Form> EnumVariant<F> {
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &F::String {
+		match self {
+			EnumVariant::Unit(unit) => unit.name(),
+			EnumVariant::Struct(r#struct) => r#struct.name(),
+			EnumVariant::TupleStruct(tuple_struct) => tuple_struct.name(),
+		}
+	}
+
+	/// Returns the application-level error code of the variant, if any.
+	pub fn code(&self) -> Option<u32> {
+		match self {
+			EnumVariant::Unit(unit) => unit.code(),
+			EnumVariant::Struct(r#struct) => r#struct.code(),
+			EnumVariant::TupleStruct(tuple_struct) => tuple_struct.code(),
+		}
+	}
+}
+
 impl IntoCompact for EnumVariant {
 	type Output = EnumVariant<CompactForm>;
 
@@ -443,6 +2038,30 @@ impl IntoCompact for EnumVariant {
 	}
 }
 
+impl Resolve for EnumVariant<CompactForm> {
+	type Output = EnumVariant<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		match self {
+			EnumVariant::Unit(unit) => unit.resolve(registry).into(),
+			EnumVariant::Struct(r#struct) => r#struct.resolve(registry).into(),
+			EnumVariant::TupleStruct(tuple_struct) => tuple_struct.resolve(registry).into(),
+		}
+	}
+}
+
+impl IntoExpanded for EnumVariant {
+	type Output = EnumVariant<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		match self {
+			EnumVariant::Unit(unit) => unit.into_expanded(expander).into(),
+			EnumVariant::Struct(r#struct) => r#struct.into_expanded(expander).into(),
+			EnumVariant::TupleStruct(tuple_struct) => tuple_struct.into_expanded(expander).into(),
+		}
+	}
+}
+
 /// An unit struct enum variant.
 ///
 /// These are similar to the variants in C-like enums.
@@ -457,11 +2076,24 @@ impl IntoCompact for EnumVariant {
 ///     Minus { source: i32 }
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct EnumVariantUnit<F: Form = MetaForm> {
 	/// The name of the variant.
-	#[serde(rename = "unit_variant.name")]
+	#[cfg_attr(feature = "serde", serde(rename = "unit_variant.name"))]
 	name: F::String,
+	/// The application-level error code of the variant, if any.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "unit_variant.code", skip_serializing_if = "Option::is_none")
+	)]
+	code: Option<u32>,
+	/// The documentation of the variant, one entry per line.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "unit_variant.docs", skip_serializing_if = "Vec::is_empty")
+	)]
+	docs: Vec<F::String>,
 }
 
 impl IntoCompact for EnumVariantUnit {
@@ -469,15 +2101,74 @@ impl IntoCompact for EnumVariantUnit {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		EnumVariantUnit {
-			name: registry.register_string(self.name),
+			name: registry.compact_string(self.name),
+			code: self.code,
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for EnumVariantUnit<CompactForm> {
+	type Output = EnumVariantUnit<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		EnumVariantUnit {
+			name: registry.resolved_string(self.name),
+			code: self.code,
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for EnumVariantUnit {
+	type Output = EnumVariantUnit<ExpandedForm>;
+
+	fn into_expanded(self, _expander: &mut Expander) -> Self::Output {
+		EnumVariantUnit {
+			name: self.name,
+			code: self.code,
+			docs: self.docs,
 		}
 	}
 }
 
 impl EnumVariantUnit {
 	/// Creates a new unit struct variant.
-	pub fn new(name: &'static str) -> Self {
-		Self { name }
+	pub const fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			code: None,
+			docs: Vec::new(),
+		}
+	}
+
+	/// Assigns the given application-level error code to the variant.
+	pub const fn with_code(mut self, code: u32) -> Self {
+		self.code = Some(code);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the variant.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> EnumVariantUnit<F> {
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the application-level error code of the variant, if any.
+	pub fn code(&self) -> Option<u32> {
+		self.code
+	}
+
+	/// Returns the documentation of the variant, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
 	}
 }
 
@@ -493,15 +2184,28 @@ impl EnumVariantUnit {
 /// //  ^^^^^^^^^^^^^^^^^^^^^ this is a struct enum variant
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct EnumVariantStruct<F: Form = MetaForm> {
 	/// The name of the struct variant.
-	#[serde(rename = "struct_variant.name")]
+	#[cfg_attr(feature = "serde", serde(rename = "struct_variant.name"))]
 	name: F::String,
 	/// The fields of the struct variant.
-	#[serde(rename = "struct_variant.fields")]
-	fields: Vec<NamedField<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "struct_variant.fields"))]
+	fields: Items<NamedField<F>>,
+	/// The application-level error code of the variant, if any.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "struct_variant.code", skip_serializing_if = "Option::is_none")
+	)]
+	code: Option<u32>,
+	/// The documentation of the variant, one entry per line.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "struct_variant.docs", skip_serializing_if = "Vec::is_empty")
+	)]
+	docs: Vec<F::String>,
 }
 
 impl IntoCompact for EnumVariantStruct {
@@ -509,12 +2213,44 @@ impl IntoCompact for EnumVariantStruct {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		EnumVariantStruct {
-			name: registry.register_string(self.name),
+			name: registry.compact_string(self.name),
 			fields: self
 				.fields
 				.into_iter()
 				.map(|field| field.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+			code: self.code,
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for EnumVariantStruct<CompactForm> {
+	type Output = EnumVariantStruct<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		EnumVariantStruct {
+			name: registry.resolved_string(self.name),
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+			code: self.code,
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for EnumVariantStruct {
+	type Output = EnumVariantStruct<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		EnumVariantStruct {
+			name: self.name,
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
+			code: self.code,
+			docs: self.docs,
 		}
 	}
 }
@@ -528,8 +2264,44 @@ impl EnumVariantStruct {
 		Self {
 			name,
 			fields: fields.into_iter().collect(),
+			code: None,
+			docs: Vec::new(),
 		}
 	}
+
+	/// Assigns the given application-level error code to the variant.
+	pub const fn with_code(mut self, code: u32) -> Self {
+		self.code = Some(code);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the variant.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> EnumVariantStruct<F> {
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the named fields of the variant.
+	pub fn fields(&self) -> &[NamedField<F>] {
+		&self.fields
+	}
+
+	/// Returns the application-level error code of the variant, if any.
+	pub fn code(&self) -> Option<u32> {
+		self.code
+	}
+
+	/// Returns the documentation of the variant, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
+	}
 }
 
 /// A tuple struct enum variant.
@@ -546,15 +2318,28 @@ impl EnumVariantStruct {
 ///     }
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct EnumVariantTupleStruct<F: Form = MetaForm> {
 	/// The name of the variant.
-	#[serde(rename = "tuple_struct_variant.name")]
+	#[cfg_attr(feature = "serde", serde(rename = "tuple_struct_variant.name"))]
 	name: F::String,
 	/// The fields of the variant.
-	#[serde(rename = "tuple_struct_variant.types")]
-	fields: Vec<UnnamedField<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "tuple_struct_variant.types"))]
+	fields: Items<UnnamedField<F>>,
+	/// The application-level error code of the variant, if any.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "tuple_struct_variant.code", skip_serializing_if = "Option::is_none")
+	)]
+	code: Option<u32>,
+	/// The documentation of the variant, one entry per line.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "tuple_struct_variant.docs", skip_serializing_if = "Vec::is_empty")
+	)]
+	docs: Vec<F::String>,
 }
 
 impl IntoCompact for EnumVariantTupleStruct {
@@ -562,12 +2347,44 @@ impl IntoCompact for EnumVariantTupleStruct {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		EnumVariantTupleStruct {
-			name: registry.register_string(self.name),
+			name: registry.compact_string(self.name),
 			fields: self
 				.fields
 				.into_iter()
 				.map(|field| field.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+			code: self.code,
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for EnumVariantTupleStruct<CompactForm> {
+	type Output = EnumVariantTupleStruct<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		EnumVariantTupleStruct {
+			name: registry.resolved_string(self.name),
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+			code: self.code,
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for EnumVariantTupleStruct {
+	type Output = EnumVariantTupleStruct<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		EnumVariantTupleStruct {
+			name: self.name,
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
+			code: self.code,
+			docs: self.docs,
 		}
 	}
 }
@@ -581,8 +2398,44 @@ impl EnumVariantTupleStruct {
 		Self {
 			name,
 			fields: fields.into_iter().collect(),
+			code: None,
+			docs: Vec::new(),
 		}
 	}
+
+	/// Assigns the given application-level error code to the variant.
+	pub const fn with_code(mut self, code: u32) -> Self {
+		self.code = Some(code);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the variant.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> EnumVariantTupleStruct<F> {
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the unnamed fields of the variant.
+	pub fn fields(&self) -> &[UnnamedField<F>] {
+		&self.fields
+	}
+
+	/// Returns the application-level error code of the variant, if any.
+	pub fn code(&self) -> Option<u32> {
+		self.code
+	}
+
+	/// Returns the documentation of the variant, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
+	}
 }
 
 /// A union, aka untagged union, type definition.
@@ -595,12 +2448,19 @@ impl EnumVariantTupleStruct {
 ///     ext: *mut i32,
 /// }
 /// ```
-#[derive(PartialEq, Eq, Debug, Serialize)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct TypeDefUnion<F: Form = MetaForm> {
 	/// The fields of the union.
-	#[serde(rename = "union.fields")]
-	fields: Vec<NamedField<F>>,
+	#[cfg_attr(feature = "serde", serde(rename = "union.fields"))]
+	fields: Items<NamedField<F>>,
+	/// The optional description of the field that selects the active union member.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "union.discriminator", skip_serializing_if = "Option::is_none")
+	)]
+	discriminator: Option<UnionDiscriminator<F>>,
 }
 
 impl IntoCompact for TypeDefUnion {
@@ -612,7 +2472,41 @@ impl IntoCompact for TypeDefUnion {
 				.fields
 				.into_iter()
 				.map(|field| field.into_compact(registry))
-				.collect::<Vec<_>>(),
+				.collect::<Items<_>>(),
+			discriminator: self
+				.discriminator
+				.map(|discriminator| discriminator.into_compact(registry)),
+		}
+	}
+}
+
+impl Resolve for TypeDefUnion<CompactForm> {
+	type Output = TypeDefUnion<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeDefUnion {
+			fields: self.fields.iter().map(|field| field.resolve(registry)).collect(),
+			discriminator: self
+				.discriminator
+				.as_ref()
+				.map(|discriminator| discriminator.resolve(registry)),
+		}
+	}
+}
+
+impl IntoExpanded for TypeDefUnion {
+	type Output = TypeDefUnion<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeDefUnion {
+			fields: self
+				.fields
+				.into_iter()
+				.map(|field| field.into_expanded(expander))
+				.collect::<Items<_>>(),
+			discriminator: self
+				.discriminator
+				.map(|discriminator| discriminator.into_expanded(expander)),
 		}
 	}
 }
@@ -625,6 +2519,307 @@ impl TypeDefUnion {
 	{
 		Self {
 			fields: fields.into_iter().collect(),
+			discriminator: None,
+		}
+	}
+
+	/// Describes the field that selects the active member of the union.
+	///
+	/// Fails if the discriminator maps a value onto a member that is not a field of the union.
+	pub fn with_discriminator(mut self, discriminator: UnionDiscriminator) -> Result<Self, UnionDiscriminatorError> {
+		let unknown = discriminator
+			.mapping
+			.iter()
+			.position(|mapping| self.fields.iter().all(|field| field.name() != mapping.member()));
+		if let Some(mapping) = unknown {
+			return Err(UnionDiscriminatorError::UnknownMember { mapping });
+		}
+		self.discriminator = Some(discriminator);
+		Ok(self)
+	}
+}
+
+/// An error that may be encountered upon describing the discriminator of a union.
+#[derive(PartialEq, Eq, Debug)]
+pub enum UnionDiscriminatorError {
+	/// If a mapping selects a member that is not a field of the union.
+	UnknownMember {
+		/// The index of the errorneous mapping.
+		mapping: usize,
+	},
+}
+
+impl<F: Form> TypeDefUnion<F> {
+	/// Creates a union definition in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(fields: Items<NamedField<F>>, discriminator: Option<UnionDiscriminator<F>>) -> Self {
+		Self { fields, discriminator }
+	}
+
+	/// Returns the fields of the union.
+	pub fn fields(&self) -> &[NamedField<F>] {
+		&self.fields
+	}
+
+	/// Returns the description of the field that selects the active member, if any.
+	pub fn discriminator(&self) -> Option<&UnionDiscriminator<F>> {
+		self.discriminator.as_ref()
+	}
+}
+
+/// Describes the out-of-band field that selects the active member of a union.
+///
+/// Unions do not carry a tag themselves. However, in practice some other field of
+/// the type containing the union decides which of its members is active.
+///
+/// # Example
+///
+/// ```
+/// union Payload {
+///     int: u32,
+///     flag: bool,
+/// }
+///
+/// struct Message {
+///     kind: u8, // 0 selects `int`, 1 selects `flag`
+///     payload: Payload,
+/// }
+/// ```
+///
+/// Discriminating fields may also be signed integers or strings.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::String: Serialize"))]
+pub struct UnionDiscriminator<F: Form = MetaForm> {
+	/// The path of field names leading to the discriminating field.
+	///
+	/// The path is relative to the type that contains the union.
+	#[cfg_attr(feature = "serde", serde(rename = "field"))]
+	field_path: Vec<F::String>,
+	/// The mapping from discriminant values to the selected union members.
+	mapping: Vec<UnionDiscriminatorMapping<F>>,
+}
+
+impl IntoCompact for UnionDiscriminator {
+	type Output = UnionDiscriminator<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		UnionDiscriminator {
+			field_path: self
+				.field_path
+				.into_iter()
+				.map(|segment| registry.compact_string(segment))
+				.collect::<Vec<_>>(),
+			mapping: self
+				.mapping
+				.into_iter()
+				.map(|mapping| mapping.into_compact(registry))
+				.collect::<Vec<_>>(),
+		}
+	}
+}
+
+impl Resolve for UnionDiscriminator<CompactForm> {
+	type Output = UnionDiscriminator<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		UnionDiscriminator {
+			field_path: self
+				.field_path
+				.iter()
+				.map(|segment| registry.resolved_string(*segment))
+				.collect(),
+			mapping: self.mapping.iter().map(|mapping| mapping.resolve(registry)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for UnionDiscriminator {
+	type Output = UnionDiscriminator<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		UnionDiscriminator {
+			field_path: self.field_path,
+			mapping: self
+				.mapping
+				.into_iter()
+				.map(|mapping| mapping.into_expanded(expander))
+				.collect::<Vec<_>>(),
+		}
+	}
+}
+
+impl UnionDiscriminator {
+	/// Creates a new union discriminator from the given field path and mapping
+	/// from discriminant values to the names of the union members.
+	pub fn new<P, M, V>(field_path: P, mapping: M) -> Self
+	where
+		P: IntoIterator<Item = <MetaForm as Form>::String>,
+		M: IntoIterator<Item = (V, <MetaForm as Form>::String)>,
+		V: Into<DiscriminantValue>,
+	{
+		Self {
+			field_path: field_path.into_iter().collect(),
+			mapping: mapping
+				.into_iter()
+				.map(|(value, member)| UnionDiscriminatorMapping {
+					value: value.into(),
+					member,
+				})
+				.collect(),
+		}
+	}
+}
+
+impl<F: Form> UnionDiscriminator<F> {
+	/// Creates a union discriminator in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(field_path: Vec<F::String>, mapping: Vec<UnionDiscriminatorMapping<F>>) -> Self {
+		Self { field_path, mapping }
+	}
+
+	/// Returns the path of field names leading to the discriminating field.
+	pub fn field_path(&self) -> &[F::String] {
+		&self.field_path
+	}
+
+	/// Returns the mapping from discriminant values to union members.
+	pub fn mapping(&self) -> &[UnionDiscriminatorMapping<F>] {
+		&self.mapping
+	}
+}
+
+/// Maps a discriminant value to the union member it selects.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::String: Serialize"))]
+pub struct UnionDiscriminatorMapping<F: Form = MetaForm> {
+	/// The value of the discriminating field.
+	value: DiscriminantValue<F>,
+	/// The name of the selected union member.
+	member: F::String,
+}
+
+impl IntoCompact for UnionDiscriminatorMapping {
+	type Output = UnionDiscriminatorMapping<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		UnionDiscriminatorMapping {
+			value: self.value.into_compact(registry),
+			member: registry.compact_string(self.member),
+		}
+	}
+}
+
+impl Resolve for UnionDiscriminatorMapping<CompactForm> {
+	type Output = UnionDiscriminatorMapping<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		UnionDiscriminatorMapping {
+			value: self.value.resolve(registry),
+			member: registry.resolved_string(self.member),
+		}
+	}
+}
+
+impl IntoExpanded for UnionDiscriminatorMapping {
+	type Output = UnionDiscriminatorMapping<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		UnionDiscriminatorMapping {
+			value: self.value.into_expanded(expander),
+			member: self.member,
+		}
+	}
+}
+
+impl<F: Form> UnionDiscriminatorMapping<F> {
+	/// Creates a discriminator mapping in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(value: DiscriminantValue<F>, member: F::String) -> Self {
+		Self { value, member }
+	}
+
+	/// Returns the value of the discriminating field.
+	pub fn value(&self) -> &DiscriminantValue<F> {
+		&self.value
+	}
+
+	/// Returns the name of the selected union member.
+	pub fn member(&self) -> &F::String {
+		&self.member
+	}
+}
+
+/// A value of the discriminating field of a union.
+///
+/// The type of the value is implied by the discriminating field. Characters are
+/// represented by their code points.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::String: Serialize", rename_all = "lowercase"))]
+pub enum DiscriminantValue<F: Form = MetaForm> {
+	/// An unsigned integer of up to 64 bits or a character.
+	UInt(u64),
+	/// A signed integer of up to 64 bits.
+	Int(i64),
+	/// A string.
+	Str(F::String),
+}
+
+impl IntoCompact for DiscriminantValue {
+	type Output = DiscriminantValue<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		match self {
+			DiscriminantValue::UInt(value) => DiscriminantValue::UInt(value),
+			DiscriminantValue::Int(value) => DiscriminantValue::Int(value),
+			DiscriminantValue::Str(value) => DiscriminantValue::Str(registry.compact_string(value)),
+		}
+	}
+}
+
+impl Resolve for DiscriminantValue<CompactForm> {
+	type Output = DiscriminantValue<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		match self {
+			DiscriminantValue::UInt(value) => DiscriminantValue::UInt(*value),
+			DiscriminantValue::Int(value) => DiscriminantValue::Int(*value),
+			DiscriminantValue::Str(value) => DiscriminantValue::Str(registry.resolved_string(*value)),
+		}
+	}
+}
+
+impl IntoExpanded for DiscriminantValue {
+	type Output = DiscriminantValue<ExpandedForm>;
+
+	fn into_expanded(self, _expander: &mut Expander) -> Self::Output {
+		match self {
+			DiscriminantValue::UInt(value) => DiscriminantValue::UInt(value),
+			DiscriminantValue::Int(value) => DiscriminantValue::Int(value),
+			DiscriminantValue::Str(value) => DiscriminantValue::Str(value),
 		}
 	}
 }
+
+macro_rules! impl_discriminant_value_from {
+	( $variant:ident: $($ty:ty),* ) => {
+		$(
+			impl From<$ty> for DiscriminantValue {
+				fn from(value: $ty) -> Self {
+					DiscriminantValue::$variant(value as _)
+				}
+			}
+		)*
+	};
+}
+
+impl_discriminant_value_from!(UInt: u8, u16, u32, u64, usize, char);
+impl_discriminant_value_from!(Int: i8, i16, i32, i64, isize);
+
+impl From<<MetaForm as Form>::String> for DiscriminantValue {
+	fn from(value: <MetaForm as Form>::String) -> Self {
+		DiscriminantValue::Str(value)
+	}
+}
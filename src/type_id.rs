@@ -17,12 +17,18 @@
 use crate::tm_std::*;
 
 use crate::{
-	form::{CompactForm, Form, MetaForm},
-	utils::is_rust_identifier,
-	IntoCompact, MetaType, Metadata, Registry,
+	expand::{Expander, IntoExpanded},
+	form::{CompactForm, ExpandedForm, Form, MetaForm, ResolvedForm},
+	interner::UntrackedSymbol,
+	registry::Resolve,
+	utils::{is_rust_identifier, Items},
+	IntoCompact, MetaType, Metadata, Registry, TypeIdDef,
 };
 use derive_more::From;
-use serde::Serialize;
+#[cfg(feature = "scale")]
+use parity_scale_codec::{Decode, Encode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Implementors return their meta type identifiers.
 pub trait HasTypeId {
@@ -36,8 +42,9 @@ pub trait HasTypeId {
 /// The first segment represents the crate name in which the type has been defined.
 ///
 /// Rust prelude type may have an empty namespace definition.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(transparent)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Namespace<F: Form = MetaForm> {
 	/// The segments of the namespace.
 	segments: Vec<F::String>,
@@ -64,12 +71,36 @@ impl IntoCompact for Namespace {
 			segments: self
 				.segments
 				.into_iter()
-				.map(|seg| registry.register_string(seg))
+				.map(|seg| registry.compact_string(seg))
 				.collect::<Vec<_>>(),
 		}
 	}
 }
 
+impl Resolve for Namespace<CompactForm> {
+	type Output = Namespace<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		Namespace {
+			segments: self
+				.segments
+				.iter()
+				.map(|segment| registry.resolved_string(*segment))
+				.collect(),
+		}
+	}
+}
+
+impl IntoExpanded for Namespace {
+	type Output = Namespace<ExpandedForm>;
+
+	fn into_expanded(self, _expander: &mut Expander) -> Self::Output {
+		Namespace {
+			segments: self.segments,
+		}
+	}
+}
+
 impl Namespace {
 	/// Creates a new namespace from the given segments.
 	pub fn new<S>(segments: S) -> Result<Self, NamespaceError>
@@ -96,20 +127,46 @@ impl Namespace {
 	}
 
 	/// Creates the prelude namespace.
-	pub fn prelude() -> Self {
-		Self { segments: vec![] }
+	pub const fn prelude() -> Self {
+		Self { segments: Vec::new() }
+	}
+
+	/// Creates a namespace from segments that are known to be Rust identifiers.
+	///
+	/// Used by the derive and by builtin implementations whose segments have been
+	/// validated at compile time so that they need not handle `NamespaceError`.
+	#[doc(hidden)]
+	pub fn from_validated(segments: Vec<<MetaForm as Form>::String>) -> Self {
+		Self { segments }
+	}
+}
+
+impl<F: Form> Namespace<F> {
+	/// Creates a namespace from the given segments in any form without validating them.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_segments(segments: Vec<F::String>) -> Self {
+		Self { segments }
+	}
+
+	/// Returns the segments of the namespace.
+	pub fn segments(&self) -> &[F::String] {
+		&self.segments
 	}
 }
 
 /// A type identifier.
 ///
 /// This uniquely identifies types and can be used to refer to type definitions.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, From, Debug, Serialize)]
-#[serde(bound = "
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, From, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(
+	feature = "serde",
+	serde(bound = "
 	F::TypeId: Serialize,
 	F::IndirectTypeId: Serialize
-")]
-#[serde(untagged)]
+")
+)]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum TypeId<F: Form = MetaForm> {
 	/// A custom type defined by the user.
 	Custom(TypeIdCustom<F>),
@@ -123,6 +180,62 @@ pub enum TypeId<F: Form = MetaForm> {
 	Primitive(TypeIdPrimitive),
 }
 
+impl<F: Form> TypeId<F> {
+	/// Returns the encoding of the identified type if it is string-like or byte-like.
+	///
+	/// This is `Encoding::Utf8` for `str` and `Encoding::Bytes` for `[u8]`.
+	pub fn encoding(&self) -> Option<Encoding> {
+		match self {
+			TypeId::Primitive(TypeIdPrimitive::Str) => Some(Encoding::Utf8),
+			TypeId::Slice(slice) => slice.encoding,
+			_ => None,
+		}
+	}
+}
+
+impl TypeId<CompactForm> {
+	/// Appends the symbols of all types referred to by the type identifier.
+	pub(crate) fn collect_type_refs(&self, refs: &mut Vec<UntrackedSymbol<AnyTypeId>>) {
+		match self {
+			TypeId::Custom(custom) => refs.extend(custom.type_params.iter().copied()),
+			TypeId::Slice(slice) => refs.push(slice.type_param),
+			TypeId::Array(array) => refs.push(array.type_param),
+			TypeId::Tuple(tuple) => refs.extend(tuple.type_params.iter().copied()),
+			TypeId::Primitive(_) => (),
+		}
+	}
+
+	/// Returns the type parameters of the identified type along with their definitions in the registry.
+	///
+	/// These are the generic parameters of custom types and tuples and the element type of
+	/// slices and arrays, in order. Primitives have no type parameters.
+	pub fn params_resolved<'a>(
+		&'a self,
+		registry: &'a Registry,
+	) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, &'a TypeIdDef)> + 'a {
+		let params = match self {
+			TypeId::Custom(custom) => custom.type_params(),
+			TypeId::Slice(slice) => core::slice::from_ref(&slice.type_param),
+			TypeId::Array(array) => core::slice::from_ref(&array.type_param),
+			TypeId::Tuple(tuple) => tuple.type_params(),
+			TypeId::Primitive(_) => &[],
+		};
+		resolve_params(params, registry)
+	}
+}
+
+/// Pairs the given type parameters with their definitions in the registry.
+///
+/// Parameters that cannot be resolved, e.g. those of another registry, are skipped.
+fn resolve_params<'a>(
+	params: &'a [UntrackedSymbol<AnyTypeId>],
+	registry: &'a Registry,
+) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, &'a TypeIdDef)> + 'a {
+	params
+		.iter()
+		.filter_map(move |param| registry.resolve(param).map(|def| (*param, def)))
+}
+
 impl IntoCompact for TypeId {
 	type Output = TypeId<CompactForm>;
 
@@ -137,15 +250,56 @@ impl IntoCompact for TypeId {
 	}
 }
 
+impl Resolve for TypeId<CompactForm> {
+	type Output = TypeId<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		match self {
+			TypeId::Custom(custom) => custom.resolve(registry).into(),
+			TypeId::Slice(slice) => TypeIdSlice {
+				type_param: slice.type_param,
+				encoding: slice.encoding,
+			}
+			.into(),
+			TypeId::Array(array) => TypeIdArray {
+				len: array.len,
+				type_param: array.type_param,
+			}
+			.into(),
+			TypeId::Tuple(tuple) => TypeIdTuple {
+				type_params: tuple.type_params.clone(),
+			}
+			.into(),
+			TypeId::Primitive(primitive) => TypeId::Primitive(primitive.clone()),
+		}
+	}
+}
+
+impl IntoExpanded for TypeId {
+	type Output = TypeId<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		match self {
+			TypeId::Custom(custom) => custom.into_expanded(expander).into(),
+			TypeId::Slice(slice) => slice.into_expanded(expander).into(),
+			TypeId::Array(array) => array.into_expanded(expander).into(),
+			TypeId::Tuple(tuple) => tuple.into_expanded(expander).into(),
+			TypeId::Primitive(primitive) => primitive.into(),
+		}
+	}
+}
+
 /// Identifies a primitive Rust type.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(rename_all = "lowercase")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum TypeIdPrimitive {
 	/// `bool` type
 	Bool,
 	/// `char` type
 	Char,
-	/// `str` type
+	/// `str` type, guaranteed to be valid UTF-8
 	Str,
 	/// `u8`
 	U8,
@@ -169,23 +323,66 @@ pub enum TypeIdPrimitive {
 	I128,
 }
 
+impl TypeIdPrimitive {
+	/// Returns the Rust name of the primitive type, e.g. `"u32"`.
+	pub fn name(&self) -> &'static str {
+		match self {
+			TypeIdPrimitive::Bool => "bool",
+			TypeIdPrimitive::Char => "char",
+			TypeIdPrimitive::Str => "str",
+			TypeIdPrimitive::U8 => "u8",
+			TypeIdPrimitive::U16 => "u16",
+			TypeIdPrimitive::U32 => "u32",
+			TypeIdPrimitive::U64 => "u64",
+			TypeIdPrimitive::U128 => "u128",
+			TypeIdPrimitive::I8 => "i8",
+			TypeIdPrimitive::I16 => "i16",
+			TypeIdPrimitive::I32 => "i32",
+			TypeIdPrimitive::I64 => "i64",
+			TypeIdPrimitive::I128 => "i128",
+		}
+	}
+}
+
 /// A type identifier for custom type definitions.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
 pub struct TypeIdCustom<F: Form = MetaForm> {
 	/// The name of the custom type.
-	#[serde(rename = "custom.name")]
+	#[cfg_attr(feature = "serde", serde(rename = "custom.name"))]
 	name: F::String,
 	/// The namespace in which the custom type has been defined.
 	///
 	/// # Note
 	///
 	/// For Rust prelude types the root (empty) namespace is used.
-	#[serde(rename = "custom.namespace")]
+	#[cfg_attr(feature = "serde", serde(rename = "custom.namespace"))]
 	namespace: Namespace<F>,
 	/// The generic type parameters of the custom type in use.
-	#[serde(rename = "custom.params")]
-	type_params: Vec<F::TypeId>,
+	#[cfg_attr(feature = "serde", serde(rename = "custom.params"))]
+	type_params: Items<F::TypeId>,
+	/// The values of the const generic parameters of the custom type in use.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "custom.consts", skip_serializing_if = "Vec::is_empty")
+	)]
+	const_params: Vec<ConstParam>,
+	/// The stable identifier of the custom type, e.g. a UUID, if any.
+	///
+	/// In contrast to its name and namespace this identifier is kept
+	/// unchanged across renames and moves of the custom type.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "custom.id", skip_serializing_if = "Option::is_none")
+	)]
+	stable_id: Option<F::String>,
+	/// The documentation of the custom type, one entry per line.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "custom.docs", skip_serializing_if = "Vec::is_empty")
+	)]
+	docs: Vec<F::String>,
 }
 
 impl IntoCompact for TypeIdCustom {
@@ -193,13 +390,50 @@ impl IntoCompact for TypeIdCustom {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		TypeIdCustom {
-			name: registry.register_string(self.name),
+			name: registry.compact_string(self.name),
 			namespace: self.namespace.into_compact(registry),
 			type_params: self
 				.type_params
 				.into_iter()
-				.map(|param| registry.register_type(&param))
-				.collect::<Vec<_>>(),
+				.map(|param| registry.compact_type(&param))
+				.collect::<Items<_>>(),
+			const_params: self.const_params,
+			stable_id: self.stable_id.map(|stable_id| registry.compact_string(stable_id)),
+			docs: self.docs.into_iter().map(|doc| registry.compact_string(doc)).collect(),
+		}
+	}
+}
+
+impl Resolve for TypeIdCustom<CompactForm> {
+	type Output = TypeIdCustom<ResolvedForm>;
+
+	fn resolve(&self, registry: &Registry) -> Self::Output {
+		TypeIdCustom {
+			name: registry.resolved_string(self.name),
+			namespace: self.namespace.resolve(registry),
+			type_params: self.type_params.clone(),
+			const_params: self.const_params.clone(),
+			stable_id: self.stable_id.map(|stable_id| registry.resolved_string(stable_id)),
+			docs: self.docs.iter().map(|doc| registry.resolved_string(*doc)).collect(),
+		}
+	}
+}
+
+impl IntoExpanded for TypeIdCustom {
+	type Output = TypeIdCustom<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeIdCustom {
+			name: self.name,
+			namespace: self.namespace.into_expanded(expander),
+			type_params: self
+				.type_params
+				.into_iter()
+				.map(|param| expander.expand(&param))
+				.collect::<Items<_>>(),
+			const_params: self.const_params,
+			stable_id: self.stable_id,
+			docs: self.docs,
 		}
 	}
 }
@@ -214,19 +448,173 @@ impl TypeIdCustom {
 			name,
 			namespace,
 			type_params: type_params.into_iter().collect(),
+			const_params: Vec::new(),
+			stable_id: None,
+			docs: Vec::new(),
+		}
+	}
+
+	/// Creates a new type identifier from the path of a type as written in `impl_metadata!`.
+	///
+	/// The last segment is the name and all others are the namespace. Types given by their
+	/// name only are placed into the namespace of the given module path.
+	#[doc(hidden)]
+	pub fn from_path<T>(path: &[&'static str], module_path: &'static str, type_params: T) -> Self
+	where
+		T: IntoIterator<Item = MetaType>,
+	{
+		let (name, namespace) = match path.split_last() {
+			Some((name, [])) => (*name, Namespace::from_validated(module_path.split("::").collect())),
+			Some((name, namespace)) => (*name, Namespace::from_validated(namespace.to_vec())),
+			None => ("", Namespace::prelude()),
+		};
+		Self::new(name, namespace, type_params)
+	}
+
+	/// Assigns the values of the const generic parameters, e.g. the `4` of `Buf<4>`, to the custom type.
+	pub fn with_const_params<T>(mut self, const_params: T) -> Self
+	where
+		T: IntoIterator<Item = ConstParam>,
+	{
+		self.const_params = const_params.into_iter().collect();
+		self
+	}
+
+	/// Assigns the given stable identifier, e.g. a UUID, to the custom type.
+	///
+	/// External systems can use it to track the custom type across renames and moves.
+	pub fn with_stable_id(mut self, stable_id: &'static str) -> Self {
+		self.stable_id = Some(stable_id);
+		self
+	}
+
+	/// Assigns the given documentation, one entry per line, to the custom type.
+	pub fn with_docs(mut self, docs: &[&'static str]) -> Self {
+		self.docs = docs.to_vec();
+		self
+	}
+}
+
+impl<F: Form> TypeIdCustom<F> {
+	/// Creates a custom type identifier in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(
+		name: F::String,
+		namespace: Namespace<F>,
+		type_params: Items<F::TypeId>,
+		const_params: Vec<ConstParam>,
+		stable_id: Option<F::String>,
+		docs: Vec<F::String>,
+	) -> Self {
+		Self {
+			name,
+			namespace,
+			type_params,
+			const_params,
+			stable_id,
+			docs,
+		}
+	}
+
+	/// Returns the name of the custom type.
+	pub fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// Returns the namespace in which the custom type has been defined.
+	pub fn namespace(&self) -> &Namespace<F> {
+		&self.namespace
+	}
+
+	/// Returns the generic type parameters of the custom type.
+	pub fn type_params(&self) -> &[F::TypeId] {
+		&self.type_params
+	}
+
+	/// Returns the values of the const generic parameters of the custom type.
+	pub fn const_params(&self) -> &[ConstParam] {
+		&self.const_params
+	}
+
+	/// Returns the stable identifier of the custom type if one has been assigned.
+	pub fn stable_id(&self) -> Option<&F::String> {
+		self.stable_id.as_ref()
+	}
+
+	/// Returns the documentation of the custom type, one entry per line.
+	pub fn docs(&self) -> &[F::String] {
+		&self.docs
+	}
+}
+
+impl TypeIdCustom<CompactForm> {
+	/// Returns the generic type parameters of the custom type along with their definitions in the registry.
+	pub fn params_resolved<'a>(
+		&'a self,
+		registry: &'a Registry,
+	) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, &'a TypeIdDef)> + 'a {
+		resolve_params(&self.type_params, registry)
+	}
+}
+
+/// The value of a const generic parameter of a custom type, e.g. the `4` of `Buf<4>`.
+///
+/// The type of the value is implied by the declaration of the custom type. Characters
+/// are represented by their code points.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ConstParam {
+	/// A boolean.
+	Bool(bool),
+	/// An unsigned integer of up to 64 bits or a character.
+	UInt(u64),
+	/// A signed integer of up to 64 bits.
+	Int(i64),
+}
+
+macro_rules! impl_const_param_from {
+	( $variant:ident: $($ty:ty),* ) => {
+		$(
+			impl From<$ty> for ConstParam {
+				fn from(value: $ty) -> Self {
+					ConstParam::$variant(value as _)
+				}
+			}
+		)*
+	};
+}
+
+impl_const_param_from!(UInt: u8, u16, u32, u64, usize, char);
+impl_const_param_from!(Int: i8, i16, i32, i64, isize);
+
+impl From<bool> for ConstParam {
+	fn from(value: bool) -> Self {
+		ConstParam::Bool(value)
+	}
+}
+
+impl Display for ConstParam {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			ConstParam::Bool(value) => write!(f, "{}", value),
+			ConstParam::UInt(value) => write!(f, "{}", value),
+			ConstParam::Int(value) => write!(f, "{}", value),
 		}
 	}
 }
 
 /// An array type identifier.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::IndirectTypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::IndirectTypeId: Serialize"))]
 pub struct TypeIdArray<F: Form = MetaForm> {
 	/// The length of the array type definition.
-	#[serde(rename = "array.len")]
+	#[cfg_attr(feature = "serde", serde(rename = "array.len"))]
 	pub len: u16,
 	/// The element type of the array type definition.
-	#[serde(rename = "array.type")]
+	#[cfg_attr(feature = "serde", serde(rename = "array.type"))]
 	pub type_param: F::IndirectTypeId,
 }
 
@@ -236,22 +624,58 @@ impl IntoCompact for TypeIdArray {
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		TypeIdArray {
 			len: self.len,
-			type_param: registry.register_type(&self.type_param),
+			type_param: registry.compact_type(&self.type_param),
+		}
+	}
+}
+
+impl IntoExpanded for TypeIdArray {
+	type Output = TypeIdArray<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeIdArray {
+			len: self.len,
+			type_param: expander.expand(&self.type_param),
 		}
 	}
 }
 
 impl TypeIdArray {
 	/// Creates a new identifier to refer to array type definition.
-	pub fn new(len: u16, type_param: MetaType) -> Self {
+	pub const fn new(len: u16, type_param: MetaType) -> Self {
 		Self { len, type_param }
 	}
 }
 
+impl<F: Form> TypeIdArray<F> {
+	/// Returns the length of the array.
+	pub fn len(&self) -> u16 {
+		self.len
+	}
+
+	/// Returns `true` if the array has no elements, e.g. `[u8; 0]`.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Returns the element type of the array.
+	pub fn type_param(&self) -> &F::IndirectTypeId {
+		&self.type_param
+	}
+}
+
+impl TypeIdArray<CompactForm> {
+	/// Returns the element type of the array along with its definition in the registry.
+	pub fn param_resolved<'a>(&self, registry: &'a Registry) -> Option<(UntrackedSymbol<AnyTypeId>, &'a TypeIdDef)> {
+		registry.resolve(&self.type_param).map(|def| (self.type_param, def))
+	}
+}
+
 /// A type identifier to refer to tuple types.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::TypeId: Serialize")]
-#[serde(transparent)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::TypeId: Serialize"))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct TypeIdTuple<F: Form = MetaForm> {
 	/// The types in the tuple type definition.
 	pub type_params: Vec<F::TypeId>,
@@ -265,7 +689,21 @@ impl IntoCompact for TypeIdTuple {
 			type_params: self
 				.type_params
 				.into_iter()
-				.map(|param| registry.register_type(&param))
+				.map(|param| registry.compact_type(&param))
+				.collect::<Vec<_>>(),
+		}
+	}
+}
+
+impl IntoExpanded for TypeIdTuple {
+	type Output = TypeIdTuple<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeIdTuple {
+			type_params: self
+				.type_params
+				.into_iter()
+				.map(|param| expander.expand(&param))
 				.collect::<Vec<_>>(),
 		}
 	}
@@ -283,18 +721,49 @@ impl TypeIdTuple {
 	}
 
 	/// Creates a new unit tuple to represent the unit type, `()`.
-	pub fn unit() -> Self {
-		Self::new(vec![])
+	pub const fn unit() -> Self {
+		Self {
+			type_params: Vec::new(),
+		}
+	}
+}
+
+impl<F: Form> TypeIdTuple<F> {
+	/// Returns the types of the fields of the tuple.
+	pub fn type_params(&self) -> &[F::TypeId] {
+		&self.type_params
+	}
+
+	/// Returns `true` if this is the unit tuple, `()`.
+	pub fn is_unit(&self) -> bool {
+		self.type_params.is_empty()
+	}
+}
+
+impl TypeIdTuple<CompactForm> {
+	/// Returns the types of the fields of the tuple along with their definitions in the registry.
+	pub fn params_resolved<'a>(
+		&'a self,
+		registry: &'a Registry,
+	) -> impl Iterator<Item = (UntrackedSymbol<AnyTypeId>, &'a TypeIdDef)> + 'a {
+		resolve_params(&self.type_params, registry)
 	}
 }
 
 /// A type identifier to refer to slice type definitions.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::IndirectTypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F::IndirectTypeId: Serialize"))]
 pub struct TypeIdSlice<F: Form = MetaForm> {
 	/// The element type of the slice type definition.
-	#[serde(rename = "slice.type")]
+	#[cfg_attr(feature = "serde", serde(rename = "slice.type"))]
 	type_param: F::IndirectTypeId,
+	/// The encoding of the slice if it is known to represent raw bytes.
+	#[cfg_attr(
+		feature = "serde",
+		serde(rename = "slice.encoding", skip_serializing_if = "Option::is_none")
+	)]
+	encoding: Option<Encoding>,
 }
 
 impl IntoCompact for TypeIdSlice {
@@ -302,7 +771,19 @@ impl IntoCompact for TypeIdSlice {
 
 	fn into_compact(self, registry: &mut Registry) -> Self::Output {
 		TypeIdSlice {
-			type_param: registry.register_type(&self.type_param),
+			type_param: registry.compact_type(&self.type_param),
+			encoding: self.encoding,
+		}
+	}
+}
+
+impl IntoExpanded for TypeIdSlice {
+	type Output = TypeIdSlice<ExpandedForm>;
+
+	fn into_expanded(self, expander: &mut Expander) -> Self::Output {
+		TypeIdSlice {
+			type_param: expander.expand(&self.type_param),
+			encoding: self.encoding,
 		}
 	}
 }
@@ -311,8 +792,15 @@ impl TypeIdSlice {
 	/// Creates a new type identifier to refer to slice type definitions.
 	///
 	/// Use this constructor if you want to instantiate from a given meta type.
+	///
+	/// Slices of `u8` are marked as being encoded as raw bytes.
 	pub fn new(type_param: MetaType) -> Self {
-		Self { type_param }
+		let encoding = if type_param.any_id() == AnyTypeId::of::<u8>() {
+			Some(Encoding::Bytes)
+		} else {
+			None
+		};
+		Self { type_param, encoding }
 	}
 
 	/// Creates a new type identifier to refer to slice type definitions.
@@ -326,6 +814,47 @@ impl TypeIdSlice {
 	}
 }
 
+impl<F: Form> TypeIdSlice<F> {
+	/// Creates a slice type identifier in any form from its parts.
+	#[cfg(feature = "serde")]
+	pub(crate) fn from_parts(type_param: F::IndirectTypeId, encoding: Option<Encoding>) -> Self {
+		Self { type_param, encoding }
+	}
+
+	/// Returns the element type of the slice.
+	pub fn type_param(&self) -> &F::IndirectTypeId {
+		&self.type_param
+	}
+
+	/// Returns the encoding of the slice if it is known to represent raw bytes.
+	pub fn encoding(&self) -> Option<Encoding> {
+		self.encoding
+	}
+}
+
+impl TypeIdSlice<CompactForm> {
+	/// Returns the element type of the slice along with its definition in the registry.
+	pub fn param_resolved<'a>(&self, registry: &'a Registry) -> Option<(UntrackedSymbol<AnyTypeId>, &'a TypeIdDef)> {
+		registry.resolve(&self.type_param).map(|def| (self.type_param, def))
+	}
+}
+
+/// The encoding of string-like and byte-like types.
+///
+/// Both `str` and `[u8]` are sequences of bytes but only the former is guaranteed
+/// to be valid UTF-8. Exporters may use this to map `str` to a string type and `[u8]`
+/// to a binary type, e.g. base64 encoded strings in JSON.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Encoding {
+	/// A sequence of bytes that is guaranteed to be valid UTF-8.
+	Utf8,
+	/// A sequence of arbitrary bytes.
+	Bytes,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
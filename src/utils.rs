@@ -14,6 +14,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// The storage of fields, variants and type parameters of type identifiers and definitions.
+///
+/// Most types have less than eight of each which are stored inline with the `smallvec`
+/// feature enabled in order to save an allocation per list.
+#[cfg(not(feature = "smallvec"))]
+pub type Items<T> = Vec<T>;
+
+/// The storage of fields, variants and type parameters of type identifiers and definitions.
+///
+/// Most types have less than eight of each which are stored inline with the `smallvec`
+/// feature enabled in order to save an allocation per list.
+#[cfg(feature = "smallvec")]
+pub type Items<T> = smallvec::SmallVec<[T; 8]>;
+
+/// Creates a new empty list of items without allocating.
+pub const fn new_items<T>() -> Items<T> {
+	#[cfg(not(feature = "smallvec"))]
+	{
+		Vec::new()
+	}
+	#[cfg(feature = "smallvec")]
+	{
+		smallvec::SmallVec::new_const()
+	}
+}
+
 /// Returns `true` if the given string is a proper Rust identifier.
 pub fn is_rust_identifier(s: &str) -> bool {
 	// Only ascii encoding is allowed.
@@ -23,10 +52,8 @@ pub fn is_rust_identifier(s: &str) -> bool {
 	}
 	if let Some((&head, tail)) = s.as_bytes().split_first() {
 		// Check if head and tail make up a proper Rust identifier.
-		let head_ok = head == b'_' || head >= b'a' && head <= b'z' || head >= b'A' && head <= b'Z';
-		let tail_ok = tail
-			.iter()
-			.all(|&ch| ch == b'_' || ch >= b'a' && ch <= b'z' || ch >= b'A' && ch <= b'Z' || ch >= b'0' && ch <= b'9');
+		let head_ok = head == b'_' || head.is_ascii_alphabetic();
+		let tail_ok = tail.iter().all(|&ch| ch == b'_' || ch.is_ascii_alphanumeric());
 		head_ok && tail_ok
 	} else {
 		// String is empty and thus not a valid Rust identifier.
@@ -0,0 +1,497 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dynamic values of registered types.
+//!
+//! A `Value` is an owned tree that mirrors the shape of a type definition without
+//! requiring the concrete Rust type. Values convert from common Rust primitives and
+//! collections, render for humans through `Display` and, with the `serde` feature,
+//! serialize to and deserialize from self-describing formats such as JSON.
+//!
+//! # Serialization
+//!
+//! Composites with named fields serialize as maps, composites with unnamed fields
+//! and sequences serialize as sequences and variants serialize as a map with the
+//! variant name as the only key. Deserialization cannot recover variants or unnamed
+//! composites and yields maps and sequences instead. Floats are not supported.
+//!
+//! # SCALE
+//!
+//! Values of registered types are decoded from their SCALE encoding with `decode_scale`
+//! and encoded to it with `encode_scale`.
+
+mod scale;
+
+pub use self::scale::{decode_scale, encode_scale, DecodeError, EncodeError};
+
+use crate::tm_std::*;
+
+/// A dynamic value of a registered type.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Value {
+	/// A boolean.
+	Bool(bool),
+	/// A unicode scalar value.
+	Char(char),
+	/// An unsigned integer of up to 128 bits.
+	UInt(u128),
+	/// A signed integer of up to 128 bits.
+	Int(i128),
+	/// A string.
+	Str(String),
+	/// A sequence of values, e.g. of a slice, an array or a vector.
+	Sequence(Vec<Value>),
+	/// A composite of fields, e.g. of a struct or a tuple.
+	Composite(Composite),
+	/// A variant of an enum.
+	Variant(Variant),
+}
+
+impl Value {
+	/// Creates the unit value.
+	pub fn unit() -> Self {
+		Value::Composite(Composite::unit())
+	}
+}
+
+/// The fields of a composite value or variant.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Composite {
+	/// Named fields in order of their declaration.
+	Named(Vec<(String, Value)>),
+	/// Unnamed fields in order of their declaration.
+	Unnamed(Vec<Value>),
+}
+
+impl Composite {
+	/// Creates a composite without fields.
+	pub fn unit() -> Self {
+		Composite::Unnamed(Vec::new())
+	}
+
+	/// Returns `true` if the composite has no fields.
+	pub fn is_empty(&self) -> bool {
+		match self {
+			Composite::Named(fields) => fields.is_empty(),
+			Composite::Unnamed(fields) => fields.is_empty(),
+		}
+	}
+}
+
+/// A variant of an enum value.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Variant {
+	/// The name of the variant.
+	name: String,
+	/// The fields of the variant.
+	fields: Composite,
+}
+
+impl Variant {
+	/// Creates a new variant with the given name and fields.
+	pub fn new<S>(name: S, fields: Composite) -> Self
+	where
+		S: Into<String>,
+	{
+		Self {
+			name: name.into(),
+			fields,
+		}
+	}
+
+	/// Creates a new variant with the given name and without fields.
+	pub fn unit<S>(name: S) -> Self
+	where
+		S: Into<String>,
+	{
+		Self::new(name, Composite::unit())
+	}
+
+	/// Returns the name of the variant.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns the fields of the variant.
+	pub fn fields(&self) -> &Composite {
+		&self.fields
+	}
+}
+
+macro_rules! impl_from_integer {
+	( $variant:ident: $($ty:ty),* ) => {
+		$(
+			impl From<$ty> for Value {
+				fn from(value: $ty) -> Self {
+					Value::$variant(value as _)
+				}
+			}
+		)*
+	};
+}
+
+impl_from_integer!(UInt: u8, u16, u32, u64, u128, usize);
+impl_from_integer!(Int: i8, i16, i32, i64, i128, isize);
+
+impl From<bool> for Value {
+	fn from(value: bool) -> Self {
+		Value::Bool(value)
+	}
+}
+
+impl From<char> for Value {
+	fn from(value: char) -> Self {
+		Value::Char(value)
+	}
+}
+
+impl From<&str> for Value {
+	fn from(value: &str) -> Self {
+		Value::Str(value.to_string())
+	}
+}
+
+impl From<String> for Value {
+	fn from(value: String) -> Self {
+		Value::Str(value)
+	}
+}
+
+impl From<()> for Value {
+	fn from(_: ()) -> Self {
+		Value::unit()
+	}
+}
+
+impl<T> From<Vec<T>> for Value
+where
+	T: Into<Value>,
+{
+	fn from(values: Vec<T>) -> Self {
+		Value::Sequence(values.into_iter().map(Into::into).collect())
+	}
+}
+
+impl<T> From<&[T]> for Value
+where
+	T: Clone + Into<Value>,
+{
+	fn from(values: &[T]) -> Self {
+		Value::Sequence(values.iter().cloned().map(Into::into).collect())
+	}
+}
+
+impl<T> From<Option<T>> for Value
+where
+	T: Into<Value>,
+{
+	fn from(value: Option<T>) -> Self {
+		Value::Variant(match value {
+			Some(value) => Variant::new("Some", Composite::Unnamed(vec![value.into()])),
+			None => Variant::unit("None"),
+		})
+	}
+}
+
+impl<K, V> From<BTreeMap<K, V>> for Value
+where
+	K: Into<String>,
+	V: Into<Value>,
+{
+	fn from(fields: BTreeMap<K, V>) -> Self {
+		Value::Composite(Composite::Named(
+			fields
+				.into_iter()
+				.map(|(name, value)| (name.into(), value.into()))
+				.collect(),
+		))
+	}
+}
+
+impl From<Composite> for Value {
+	fn from(composite: Composite) -> Self {
+		Value::Composite(composite)
+	}
+}
+
+impl From<Variant> for Value {
+	fn from(variant: Variant) -> Self {
+		Value::Variant(variant)
+	}
+}
+
+/// Writes the given items separated by commas and enclosed by the given delimiters.
+fn write_list<I, T>(f: &mut Formatter, open: &str, items: I, close: &str) -> Result<(), FmtError>
+where
+	I: IntoIterator<Item = T>,
+	T: Display,
+{
+	f.write_str(open)?;
+	for (index, item) in items.into_iter().enumerate() {
+		if index > 0 {
+			f.write_str(", ")?;
+		}
+		write!(f, "{}", item)?;
+	}
+	f.write_str(close)
+}
+
+/// Displays a named field as `name: value`.
+struct NamedField<'a>(&'a str, &'a Value);
+
+impl Display for NamedField<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		write!(f, "{}: {}", self.0, self.1)
+	}
+}
+
+impl Display for Value {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			Value::Bool(value) => write!(f, "{}", value),
+			Value::Char(value) => write!(f, "{:?}", value),
+			Value::UInt(value) => write!(f, "{}", value),
+			Value::Int(value) => write!(f, "{}", value),
+			Value::Str(value) => write!(f, "{:?}", value),
+			Value::Sequence(values) => write_list(f, "[", values, "]"),
+			Value::Composite(composite) => write!(f, "{}", composite),
+			Value::Variant(variant) => write!(f, "{}", variant),
+		}
+	}
+}
+
+impl Display for Composite {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			Composite::Named(fields) if fields.is_empty() => f.write_str("{}"),
+			Composite::Named(fields) => write_list(
+				f,
+				"{ ",
+				fields.iter().map(|(name, value)| NamedField(name, value)),
+				" }",
+			),
+			Composite::Unnamed(fields) => write_list(f, "(", fields, ")"),
+		}
+	}
+}
+
+impl Display for Variant {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		f.write_str(&self.name)?;
+		match &self.fields {
+			fields if fields.is_empty() => Ok(()),
+			fields @ Composite::Named(_) => write!(f, " {}", fields),
+			fields @ Composite::Unnamed(_) => write!(f, "{}", fields),
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+	use super::{Composite, Value, Variant};
+	use crate::tm_std::*;
+	use serde::{
+		de::{Error, MapAccess, SeqAccess, Visitor},
+		ser::SerializeMap,
+		Deserialize, Deserializer, Serialize, Serializer,
+	};
+
+	impl Serialize for Value {
+		fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+		where
+			S: Serializer,
+		{
+			match self {
+				Value::Bool(value) => serializer.serialize_bool(*value),
+				Value::Char(value) => serializer.serialize_char(*value),
+				// Not all formats support 128-bit integers.
+				Value::UInt(value) => match u64::try_from(*value) {
+					Ok(value) => serializer.serialize_u64(value),
+					Err(_) => serializer.serialize_u128(*value),
+				},
+				Value::Int(value) => match i64::try_from(*value) {
+					Ok(value) => serializer.serialize_i64(value),
+					Err(_) => serializer.serialize_i128(*value),
+				},
+				Value::Str(value) => serializer.serialize_str(value),
+				Value::Sequence(values) => serializer.collect_seq(values),
+				Value::Composite(composite) => composite.serialize(serializer),
+				Value::Variant(variant) => variant.serialize(serializer),
+			}
+		}
+	}
+
+	impl Serialize for Composite {
+		fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+		where
+			S: Serializer,
+		{
+			match self {
+				Composite::Named(fields) => serializer.collect_map(fields.iter().map(|(name, value)| (name, value))),
+				Composite::Unnamed(fields) => serializer.collect_seq(fields),
+			}
+		}
+	}
+
+	impl Serialize for Variant {
+		fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+		where
+			S: Serializer,
+		{
+			let mut map = serializer.serialize_map(Some(1))?;
+			map.serialize_entry(&self.name, &self.fields)?;
+			map.end()
+		}
+	}
+
+	impl<'de> Deserialize<'de> for Value {
+		fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+		where
+			D: Deserializer<'de>,
+		{
+			deserializer.deserialize_any(ValueVisitor)
+		}
+	}
+
+	/// Deserializes a value from any self-describing format.
+	struct ValueVisitor;
+
+	impl<'de> Visitor<'de> for ValueVisitor {
+		type Value = Value;
+
+		fn expecting(&self, f: &mut Formatter) -> Result<(), FmtError> {
+			f.write_str("a dynamic value")
+		}
+
+		fn visit_bool<E: Error>(self, value: bool) -> Result<Value, E> {
+			Ok(Value::Bool(value))
+		}
+
+		fn visit_i64<E: Error>(self, value: i64) -> Result<Value, E> {
+			self.visit_i128(value.into())
+		}
+
+		fn visit_i128<E: Error>(self, value: i128) -> Result<Value, E> {
+			Ok(match u128::try_from(value) {
+				Ok(value) => Value::UInt(value),
+				Err(_) => Value::Int(value),
+			})
+		}
+
+		fn visit_u64<E: Error>(self, value: u64) -> Result<Value, E> {
+			self.visit_u128(value.into())
+		}
+
+		fn visit_u128<E: Error>(self, value: u128) -> Result<Value, E> {
+			Ok(Value::UInt(value))
+		}
+
+		fn visit_char<E: Error>(self, value: char) -> Result<Value, E> {
+			Ok(Value::Char(value))
+		}
+
+		fn visit_str<E: Error>(self, value: &str) -> Result<Value, E> {
+			Ok(Value::Str(value.to_string()))
+		}
+
+		fn visit_string<E: Error>(self, value: String) -> Result<Value, E> {
+			Ok(Value::Str(value))
+		}
+
+		fn visit_bytes<E: Error>(self, value: &[u8]) -> Result<Value, E> {
+			Ok(value.into())
+		}
+
+		fn visit_none<E: Error>(self) -> Result<Value, E> {
+			Ok(Value::unit())
+		}
+
+		fn visit_unit<E: Error>(self) -> Result<Value, E> {
+			Ok(Value::unit())
+		}
+
+		fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+		where
+			D: Deserializer<'de>,
+		{
+			Value::deserialize(deserializer)
+		}
+
+		fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+		where
+			D: Deserializer<'de>,
+		{
+			Value::deserialize(deserializer)
+		}
+
+		fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+		where
+			A: SeqAccess<'de>,
+		{
+			let mut values = Vec::new();
+			while let Some(value) = seq.next_element()? {
+				values.push(value);
+			}
+			Ok(Value::Sequence(values))
+		}
+
+		fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+		where
+			A: MapAccess<'de>,
+		{
+			let mut fields = Vec::new();
+			while let Some(field) = map.next_entry::<String, Value>()? {
+				fields.push(field);
+			}
+			Ok(Value::Composite(Composite::Named(fields)))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn display() {
+		let value = Value::from(vec![
+			Value::from(Some(42u8)),
+			Value::from(None::<u8>),
+			Composite::Named(vec![("a".to_string(), (-1i32).into()), ("b".to_string(), "x".into())]).into(),
+			Variant::new("V", Composite::Named(vec![("c".to_string(), 'c'.into())])).into(),
+			Composite::Unnamed(vec![true.into(), Value::unit()]).into(),
+		]);
+		assert_eq!(
+			value.to_string(),
+			r#"[Some(42), None, { a: -1, b: "x" }, V { c: 'c' }, (true, ())]"#
+		);
+	}
+
+	#[test]
+	fn from_collections() {
+		let mut map = BTreeMap::new();
+		map.insert("b", vec![1u32, 2]);
+		map.insert("a", vec![]);
+		assert_eq!(
+			Value::from(map),
+			Value::Composite(Composite::Named(vec![
+				("a".to_string(), Value::Sequence(vec![])),
+				("b".to_string(), Value::Sequence(vec![Value::UInt(1), Value::UInt(2)])),
+			]))
+		);
+		assert_eq!(Value::from(&[1i8][..]), Value::Sequence(vec![Value::Int(1)]));
+	}
+}
@@ -0,0 +1,769 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding and encoding of SCALE encoded values driven by the metadata of their types.
+//!
+//! Both follow the encoding of the `parity-scale-codec` crate:
+//!
+//! - Integers are little endian, booleans a single byte and chars their `u32` code point.
+//! - Strings and slices are prefixed by their compact encoded length while arrays are not.
+//! - Tuples and composites are the concatenation of their fields.
+//! - Variants are prefixed by a single byte index which is the discriminant of the
+//!   variant if it has one and its position otherwise. Niches, e.g. of `Option<NonZeroU32>`,
+//!   are not taken into account since the codec always encodes the index.
+//!
+//! Unions and builtin types without a statically known structure, e.g. JSON or raw payloads,
+//! can neither be decoded nor encoded.
+//!
+//! The encoder expects values of the shape produced by the decoder. Integers may be given
+//! as `Value::UInt` or `Value::Int` as long as they fit into the integer type and fieldless
+//! composites and variants may have either named or unnamed fields. Since deserialized
+//! values lack variants and unnamed composites, the encoder also accepts
+//!
+//! - sequences in place of unnamed fields of tuples, composites and variants,
+//! - any other value in place of the fields of a composite or variant with a single field,
+//! - maps with a single entry from the variant name to its fields in place of variants,
+//! - strings in place of variants without fields and strings of a single char in place of chars.
+
+use super::{Composite, Value, Variant};
+use crate::tm_std::*;
+use crate::{form::CompactForm, interner::UntrackedSymbol, Field, Registry, TypeDef, TypeId, TypeIdPrimitive};
+
+/// The maximum nesting of types the decoder and encoder descend into.
+///
+/// This protects against stack overflows by recursive types decoded from untrusted input.
+const MAX_DEPTH: usize = 256;
+
+/// Decodes a SCALE encoded value of the given registered type.
+///
+/// Advances `bytes` past the decoded value, so that callers may check for trailing bytes
+/// or decode further values.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{value::{decode_scale, Composite, Value, Variant}, Metadata, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// let ty = builder.register_type(&<Option<(u16, bool)>>::meta_type());
+/// let registry = builder.build();
+///
+/// let mut bytes = &[1, 42, 0, 1][..];
+/// let value = decode_scale(&mut bytes, ty, &registry).unwrap();
+/// assert_eq!(
+///     value,
+///     Variant::new("Some", Composite::Unnamed(vec![Composite::Unnamed(vec![42u16.into(), true.into()]).into()])).into()
+/// );
+/// assert!(bytes.is_empty());
+/// ```
+///
+/// # Errors
+///
+/// If the bytes are not a valid encoding of the type or the type cannot be decoded.
+pub fn decode_scale(
+	bytes: &mut &[u8],
+	ty: UntrackedSymbol<AnyTypeId>,
+	registry: &Registry,
+) -> Result<Value, DecodeError> {
+	Decoder { registry, depth: 0 }.decode(bytes, ty)
+}
+
+/// An error upon decoding a SCALE encoded value.
+#[derive(PartialEq, Eq, Debug)]
+pub enum DecodeError {
+	/// The bytes ended before the value was fully decoded.
+	UnexpectedEnd,
+	/// The type is not registered with the registry or has been registered by identifier only.
+	UnknownType {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// A compact encoded length is invalid or does not fit into a `usize`.
+	InvalidCompact,
+	/// A char is not a valid unicode scalar value.
+	InvalidChar {
+		/// The decoded code point.
+		code: u32,
+	},
+	/// A string is not valid UTF-8.
+	InvalidUtf8,
+	/// No variant of the enum has the decoded index.
+	InvalidVariant {
+		/// The decoded index.
+		index: u8,
+	},
+	/// The structure of the type is not known statically, e.g. of unions or raw payloads.
+	Unsupported {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The types are nested deeper than the decoder descends.
+	TooDeep,
+}
+
+impl Display for DecodeError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
+			DecodeError::UnknownType { id } => write!(f, "unknown type with symbol {}", id),
+			DecodeError::InvalidCompact => write!(f, "invalid compact encoded length"),
+			DecodeError::InvalidChar { code } => write!(f, "invalid char with code point {:#x}", code),
+			DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8 string"),
+			DecodeError::InvalidVariant { index } => write!(f, "no variant with index {}", index),
+			DecodeError::Unsupported { id } => write!(f, "type with symbol {} cannot be decoded", id),
+			DecodeError::TooDeep => write!(f, "types are nested deeper than {} levels", MAX_DEPTH),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Decodes values using the definitions of a registry.
+struct Decoder<'a> {
+	registry: &'a Registry,
+	/// The nesting of the type currently decoded.
+	depth: usize,
+}
+
+impl Decoder<'_> {
+	/// Decodes a value of the given type.
+	fn decode(&mut self, bytes: &mut &[u8], ty: UntrackedSymbol<AnyTypeId>) -> Result<Value, DecodeError> {
+		if self.depth == MAX_DEPTH {
+			return Err(DecodeError::TooDeep);
+		}
+		self.depth += 1;
+		let value = self.decode_type(bytes, ty);
+		self.depth -= 1;
+		value
+	}
+
+	fn decode_type(&mut self, bytes: &mut &[u8], ty: UntrackedSymbol<AnyTypeId>) -> Result<Value, DecodeError> {
+		let id = ty.id().get();
+		let id_def = self.registry.resolve(&ty).ok_or(DecodeError::UnknownType { id })?;
+		match id_def.id() {
+			TypeId::Primitive(primitive) => decode_primitive(bytes, primitive),
+			TypeId::Array(array) => (0..array.len())
+				.map(|_| self.decode(bytes, *array.type_param()))
+				.collect::<Result<_, _>>()
+				.map(Value::Sequence),
+			TypeId::Slice(slice) => {
+				let len = decode_compact_len(bytes)?;
+				(0..len)
+					.map(|_| self.decode(bytes, *slice.type_param()))
+					.collect::<Result<_, _>>()
+					.map(Value::Sequence)
+			}
+			TypeId::Tuple(tuple) => tuple
+				.type_params()
+				.iter()
+				.map(|ty| self.decode(bytes, *ty))
+				.collect::<Result<_, _>>()
+				.map(|fields| Value::Composite(Composite::Unnamed(fields))),
+			TypeId::Custom(_) => match id_def.def().clone().normalize() {
+				TypeDef::Composite(composite) => self.decode_fields(bytes, composite.fields()).map(Value::Composite),
+				TypeDef::Variant(def) => {
+					let index = take::<1>(bytes)?[0];
+					let variant = def
+						.variants()
+						.iter()
+						.enumerate()
+						.find(|(position, variant)| {
+							variant.discriminant().unwrap_or(*position as u64) == u64::from(index)
+						})
+						.map(|(_, variant)| variant)
+						.ok_or(DecodeError::InvalidVariant { index })?;
+					let fields = self.decode_fields(bytes, variant.fields())?;
+					Ok(Value::Variant(Variant::new(
+						self.registry.resolved_string(*variant.name()),
+						fields,
+					)))
+				}
+				_ => Err(DecodeError::Unsupported { id }),
+			},
+		}
+	}
+
+	/// Decodes the fields of a composite or variant.
+	fn decode_fields(&mut self, bytes: &mut &[u8], fields: &[Field<CompactForm>]) -> Result<Composite, DecodeError> {
+		if fields.iter().all(|field| field.name().is_some()) && !fields.is_empty() {
+			fields
+				.iter()
+				.map(|field| {
+					let name = field.name().map(|name| self.registry.resolved_string(*name));
+					Ok((name.unwrap_or_default().to_string(), self.decode(bytes, *field.ty())?))
+				})
+				.collect::<Result<_, _>>()
+				.map(Composite::Named)
+		} else {
+			fields
+				.iter()
+				.map(|field| self.decode(bytes, *field.ty()))
+				.collect::<Result<_, _>>()
+				.map(Composite::Unnamed)
+		}
+	}
+}
+
+/// Takes the next `N` bytes.
+fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], DecodeError> {
+	if bytes.len() < N {
+		return Err(DecodeError::UnexpectedEnd);
+	}
+	let (head, tail) = bytes.split_at(N);
+	*bytes = tail;
+	let mut array = [0; N];
+	array.copy_from_slice(head);
+	Ok(array)
+}
+
+/// Decodes a compact encoded length.
+fn decode_compact_len(bytes: &mut &[u8]) -> Result<usize, DecodeError> {
+	let first = take::<1>(bytes)?[0];
+	let len = match first & 0b11 {
+		0b00 => u64::from(first >> 2),
+		0b01 => u64::from(u16::from_le_bytes([first, take::<1>(bytes)?[0]]) >> 2),
+		0b10 => {
+			let [b1, b2, b3] = take::<3>(bytes)?;
+			u64::from(u32::from_le_bytes([first, b1, b2, b3]) >> 2)
+		}
+		_ => {
+			let len = usize::from(first >> 2) + 4;
+			if len > 8 {
+				return Err(DecodeError::InvalidCompact);
+			}
+			let mut le = [0; 8];
+			for byte in le.iter_mut().take(len) {
+				*byte = take::<1>(bytes)?[0];
+			}
+			u64::from_le_bytes(le)
+		}
+	};
+	usize::try_from(len).map_err(|_| DecodeError::InvalidCompact)
+}
+
+/// Decodes a value of a primitive type.
+fn decode_primitive(bytes: &mut &[u8], primitive: &TypeIdPrimitive) -> Result<Value, DecodeError> {
+	Ok(match primitive {
+		TypeIdPrimitive::Bool => Value::Bool(take::<1>(bytes)?[0] != 0),
+		TypeIdPrimitive::Char => {
+			let code = u32::from_le_bytes(take(bytes)?);
+			Value::Char(core::char::from_u32(code).ok_or(DecodeError::InvalidChar { code })?)
+		}
+		TypeIdPrimitive::Str => {
+			let len = decode_compact_len(bytes)?;
+			if bytes.len() < len {
+				return Err(DecodeError::UnexpectedEnd);
+			}
+			let (string, tail) = bytes.split_at(len);
+			*bytes = tail;
+			Value::Str(
+				core::str::from_utf8(string)
+					.map_err(|_| DecodeError::InvalidUtf8)?
+					.to_string(),
+			)
+		}
+		TypeIdPrimitive::U8 => u8::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::U16 => u16::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::U32 => u32::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::U64 => u64::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::U128 => u128::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::I8 => i8::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::I16 => i16::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::I32 => i32::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::I64 => i64::from_le_bytes(take(bytes)?).into(),
+		TypeIdPrimitive::I128 => i128::from_le_bytes(take(bytes)?).into(),
+	})
+}
+
+/// Encodes the value of the given registered type to its SCALE encoding.
+///
+/// # Example
+///
+/// ```
+/// # use type_metadata::{value::{encode_scale, Composite, Variant}, Metadata, RegistryBuilder};
+/// let mut builder = RegistryBuilder::new();
+/// let ty = builder.register_type(&<Option<(u16, bool)>>::meta_type());
+/// let registry = builder.build();
+///
+/// let value = Variant::new("Some", Composite::Unnamed(vec![Composite::Unnamed(vec![42u16.into(), true.into()]).into()]));
+/// assert_eq!(encode_scale(&value.into(), ty, &registry), Ok(vec![1, 42, 0, 1]));
+/// ```
+///
+/// # Errors
+///
+/// If the shape of the value does not match the type or the type cannot be encoded.
+pub fn encode_scale(
+	value: &Value,
+	ty: UntrackedSymbol<AnyTypeId>,
+	registry: &Registry,
+) -> Result<Vec<u8>, EncodeError> {
+	let mut bytes = Vec::new();
+	Encoder {
+		registry,
+		depth: 0,
+		bytes: &mut bytes,
+	}
+	.encode(value, ty)?;
+	Ok(bytes)
+}
+
+/// An error upon encoding a value.
+#[derive(PartialEq, Eq, Debug)]
+pub enum EncodeError {
+	/// The type is not registered with the registry or has been registered by identifier only.
+	UnknownType {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The shape of the value does not match the type, e.g. a string for an integer type.
+	Mismatch {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// An integer does not fit into the integer type.
+	OutOfRange {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The number of elements of an array or fields of a tuple or composite does not match the type.
+	LengthMismatch {
+		/// The identifier of the type symbol.
+		id: u32,
+		/// The expected number of elements or fields.
+		expected: usize,
+		/// The actual number of elements or fields.
+		actual: usize,
+	},
+	/// A named field of the type is missing from the value or the value has a field the type lacks.
+	Field {
+		/// The identifier of the type symbol.
+		id: u32,
+		/// The name of the missing or unknown field.
+		name: String,
+	},
+	/// The enum has no variant of the name of the value.
+	UnknownVariant {
+		/// The identifier of the type symbol.
+		id: u32,
+		/// The name of the variant of the value.
+		name: String,
+	},
+	/// The structure of the type is not known statically, e.g. of unions or raw payloads,
+	/// or the index of a variant does not fit into a byte.
+	Unsupported {
+		/// The identifier of the type symbol.
+		id: u32,
+	},
+	/// The types are nested deeper than the encoder descends.
+	TooDeep,
+}
+
+impl Display for EncodeError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+		match self {
+			EncodeError::UnknownType { id } => write!(f, "unknown type with symbol {}", id),
+			EncodeError::Mismatch { id } => write!(f, "value does not match type with symbol {}", id),
+			EncodeError::OutOfRange { id } => write!(f, "integer out of range of type with symbol {}", id),
+			EncodeError::LengthMismatch { id, expected, actual } => write!(
+				f,
+				"expected {} elements of type with symbol {} but found {}",
+				expected, id, actual
+			),
+			EncodeError::Field { id, name } => {
+				write!(f, "missing or unknown field `{}` of type with symbol {}", name, id)
+			}
+			EncodeError::UnknownVariant { id, name } => {
+				write!(f, "unknown variant `{}` of type with symbol {}", name, id)
+			}
+			EncodeError::Unsupported { id } => write!(f, "type with symbol {} cannot be encoded", id),
+			EncodeError::TooDeep => write!(f, "types are nested deeper than {} levels", MAX_DEPTH),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Encodes values using the definitions of a registry.
+struct Encoder<'a> {
+	registry: &'a Registry,
+	/// The nesting of the type currently encoded.
+	depth: usize,
+	/// The encoded bytes.
+	bytes: &'a mut Vec<u8>,
+}
+
+impl Encoder<'_> {
+	/// Encodes a value of the given type.
+	fn encode(&mut self, value: &Value, ty: UntrackedSymbol<AnyTypeId>) -> Result<(), EncodeError> {
+		if self.depth == MAX_DEPTH {
+			return Err(EncodeError::TooDeep);
+		}
+		self.depth += 1;
+		let encoded = self.encode_type(value, ty);
+		self.depth -= 1;
+		encoded
+	}
+
+	fn encode_type(&mut self, value: &Value, ty: UntrackedSymbol<AnyTypeId>) -> Result<(), EncodeError> {
+		let id = ty.id().get();
+		let id_def = self.registry.resolve(&ty).ok_or(EncodeError::UnknownType { id })?;
+		match (id_def.id(), value) {
+			(TypeId::Primitive(primitive), value) => encode_primitive(self.bytes, value, primitive, id),
+			(TypeId::Array(array), Value::Sequence(values)) => {
+				check_len(id, usize::from(array.len()), values.len())?;
+				values
+					.iter()
+					.try_for_each(|value| self.encode(value, *array.type_param()))
+			}
+			(TypeId::Slice(slice), Value::Sequence(values)) => {
+				encode_compact_len(self.bytes, values.len());
+				values
+					.iter()
+					.try_for_each(|value| self.encode(value, *slice.type_param()))
+			}
+			(TypeId::Tuple(tuple), Value::Composite(Composite::Unnamed(values)))
+			| (TypeId::Tuple(tuple), Value::Sequence(values)) => {
+				check_len(id, tuple.type_params().len(), values.len())?;
+				values
+					.iter()
+					.zip(tuple.type_params())
+					.try_for_each(|(value, ty)| self.encode(value, *ty))
+			}
+			(TypeId::Custom(_), value) => match id_def.def().clone().normalize() {
+				TypeDef::Composite(composite) => self.encode_fields(Fields::of(value), composite.fields(), id),
+				TypeDef::Variant(def) => {
+					let (name, fields) = match value {
+						Value::Variant(variant) => (variant.name(), Fields::from(variant.fields())),
+						Value::Composite(Composite::Named(entries)) => match entries.as_slice() {
+							[(name, value)] => (name.as_str(), Fields::of(value)),
+							_ => return Err(EncodeError::Mismatch { id }),
+						},
+						Value::Str(name) => (name.as_str(), Fields::Unnamed(&[])),
+						_ => return Err(EncodeError::Mismatch { id }),
+					};
+					let (index, def_variant) = def
+						.variants()
+						.iter()
+						.enumerate()
+						.find(|(_, def_variant)| self.registry.resolved_string(*def_variant.name()) == name)
+						.ok_or_else(|| EncodeError::UnknownVariant {
+							id,
+							name: name.to_string(),
+						})?;
+					let index = def_variant.discriminant().unwrap_or(index as u64);
+					self.bytes
+						.push(u8::try_from(index).map_err(|_| EncodeError::Unsupported { id })?);
+					self.encode_fields(fields, def_variant.fields(), id)
+				}
+				_ => Err(EncodeError::Unsupported { id }),
+			},
+			_ => Err(EncodeError::Mismatch { id }),
+		}
+	}
+
+	/// Encodes the fields of a composite or variant.
+	fn encode_fields(&mut self, value: Fields, fields: &[Field<CompactForm>], id: u32) -> Result<(), EncodeError> {
+		match value {
+			Fields::Named([]) => check_len(id, fields.len(), 0),
+			Fields::Named(values) => {
+				if let Some((name, _)) = values.iter().find(|(name, _)| {
+					fields
+						.iter()
+						.all(|field| field.name().map(|field| self.registry.resolved_string(*field)) != Some(name))
+				}) {
+					return Err(EncodeError::Field { id, name: name.clone() });
+				}
+				fields.iter().try_for_each(|field| {
+					let name = field
+						.name()
+						.map(|name| self.registry.resolved_string(*name))
+						.ok_or(EncodeError::Mismatch { id })?;
+					let value = values
+						.iter()
+						.find(|(other, _)| other == name)
+						.map(|(_, value)| value)
+						.ok_or_else(|| EncodeError::Field {
+							id,
+							name: name.to_string(),
+						})?;
+					self.encode(value, *field.ty())
+				})
+			}
+			Fields::Unnamed(values) => {
+				check_len(id, fields.len(), values.len())?;
+				values
+					.iter()
+					.zip(fields)
+					.try_for_each(|(value, field)| self.encode(value, *field.ty()))
+			}
+			Fields::Single(value) => match fields {
+				[field] => self.encode(value, *field.ty()),
+				_ => Err(EncodeError::Mismatch { id }),
+			},
+		}
+	}
+}
+
+/// The fields of a composite or variant value to encode.
+enum Fields<'a> {
+	/// Named fields, e.g. of a composite or a deserialized map.
+	Named(&'a [(String, Value)]),
+	/// Unnamed fields, e.g. of a composite or a deserialized sequence.
+	Unnamed(&'a [Value]),
+	/// The value of the only field.
+	Single(&'a Value),
+}
+
+impl<'a> Fields<'a> {
+	/// Returns the fields given by the value.
+	///
+	/// Sequences are taken as unnamed fields and any other value as the only field.
+	fn of(value: &'a Value) -> Self {
+		match value {
+			Value::Composite(composite) => composite.into(),
+			Value::Sequence(values) => Fields::Unnamed(values),
+			value => Fields::Single(value),
+		}
+	}
+}
+
+impl<'a> From<&'a Composite> for Fields<'a> {
+	fn from(composite: &'a Composite) -> Self {
+		match composite {
+			Composite::Named(values) => Fields::Named(values),
+			Composite::Unnamed(values) => Fields::Unnamed(values),
+		}
+	}
+}
+
+/// Checks the number of elements or fields of a value.
+fn check_len(id: u32, expected: usize, actual: usize) -> Result<(), EncodeError> {
+	if expected == actual {
+		Ok(())
+	} else {
+		Err(EncodeError::LengthMismatch { id, expected, actual })
+	}
+}
+
+/// Encodes a length in its compact encoding.
+fn encode_compact_len(bytes: &mut Vec<u8>, len: usize) {
+	let len = len as u64;
+	match len {
+		0..=0b0011_1111 => bytes.push((len as u8) << 2),
+		0b0100_0000..=0b0011_1111_1111_1111 => bytes.extend_from_slice(&(((len as u16) << 2) | 0b01).to_le_bytes()),
+		0b0100_0000_0000_0000..=0x3fff_ffff => bytes.extend_from_slice(&(((len as u32) << 2) | 0b10).to_le_bytes()),
+		_ => {
+			let le = len.to_le_bytes();
+			let significant = 8 - le.iter().rev().take_while(|byte| **byte == 0).count();
+			bytes.push((((significant - 4) as u8) << 2) | 0b11);
+			bytes.extend(le.iter().take(significant));
+		}
+	}
+}
+
+/// Encodes a value of a primitive type.
+fn encode_primitive(
+	bytes: &mut Vec<u8>,
+	value: &Value,
+	primitive: &TypeIdPrimitive,
+	id: u32,
+) -> Result<(), EncodeError> {
+	/// Converts the integer value into the integer type.
+	fn int<T: TryFrom<u128> + TryFrom<i128>>(value: &Value, id: u32) -> Result<T, EncodeError> {
+		match value {
+			Value::UInt(value) => T::try_from(*value).map_err(|_| EncodeError::OutOfRange { id }),
+			Value::Int(value) => T::try_from(*value).map_err(|_| EncodeError::OutOfRange { id }),
+			_ => Err(EncodeError::Mismatch { id }),
+		}
+	}
+	match (primitive, value) {
+		(TypeIdPrimitive::Bool, Value::Bool(value)) => bytes.push(u8::from(*value)),
+		(TypeIdPrimitive::Char, Value::Char(value)) => bytes.extend_from_slice(&u32::from(*value).to_le_bytes()),
+		(TypeIdPrimitive::Char, Value::Str(value)) if value.chars().count() == 1 => {
+			let value = value.chars().next().unwrap_or_default();
+			bytes.extend_from_slice(&u32::from(value).to_le_bytes())
+		}
+		(TypeIdPrimitive::Str, Value::Str(value)) => {
+			encode_compact_len(bytes, value.len());
+			bytes.extend_from_slice(value.as_bytes());
+		}
+		(TypeIdPrimitive::U8, value) => bytes.extend_from_slice(&int::<u8>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::U16, value) => bytes.extend_from_slice(&int::<u16>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::U32, value) => bytes.extend_from_slice(&int::<u32>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::U64, value) => bytes.extend_from_slice(&int::<u64>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::U128, value) => bytes.extend_from_slice(&int::<u128>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::I8, value) => bytes.extend_from_slice(&int::<i8>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::I16, value) => bytes.extend_from_slice(&int::<i16>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::I32, value) => bytes.extend_from_slice(&int::<i32>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::I64, value) => bytes.extend_from_slice(&int::<i64>(value, id)?.to_le_bytes()),
+		(TypeIdPrimitive::I128, value) => bytes.extend_from_slice(&int::<i128>(value, id)?.to_le_bytes()),
+		_ => return Err(EncodeError::Mismatch { id }),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Metadata;
+
+	#[test]
+	fn compact_lengths() {
+		let cases: [(&[u8], usize); 5] = [
+			(&[0b0000_0100], 1),
+			(&[0b1111_1100], 63),
+			(&[0b0000_0001, 0b0000_0001], 64),
+			(&[0b0000_0010, 0, 1, 0], 1 << 14),
+			(&[0b0000_0011, 0, 0, 0, 0b0100_0000], 1 << 30),
+		];
+		for (mut bytes, len) in cases.iter().copied() {
+			let mut encoded = Vec::new();
+			encode_compact_len(&mut encoded, len);
+			assert_eq!(encoded, bytes);
+			assert_eq!(decode_compact_len(&mut bytes), Ok(len));
+			assert!(bytes.is_empty());
+		}
+		let mut encoded = Vec::new();
+		encode_compact_len(&mut encoded, u32::MAX as usize);
+		assert_eq!(decode_compact_len(&mut &encoded[..]), Ok(u32::MAX as usize));
+	}
+
+	#[test]
+	fn decodes_registered_types() {
+		let mut registry = Registry::new();
+		let ty = registry.compact_type(&<Vec<(bool, Result<i16, char>)>>::meta_type());
+		let mut bytes = &[8, 1, 0, 0xfe, 0xff, 0, 1, 0x78, 0, 0, 0][..];
+		assert_eq!(
+			decode_scale(&mut bytes, ty, &registry),
+			Ok(Value::Composite(Composite::Named(vec![(
+				"elems".to_string(),
+				Value::Sequence(vec![
+					Composite::Unnamed(vec![
+						true.into(),
+						Variant::new("Ok", Composite::Unnamed(vec![(-2i16).into()])).into(),
+					])
+					.into(),
+					Composite::Unnamed(vec![
+						false.into(),
+						Variant::new("Err", Composite::Unnamed(vec!['x'.into()])).into(),
+					])
+					.into(),
+				])
+			)])))
+		);
+		assert!(bytes.is_empty());
+
+		let ty = registry.compact_type(&<&'static str>::meta_type());
+		let decoded = decode_scale(&mut &[8, b'h', b'i'][..], ty, &registry);
+		#[cfg(not(feature = "reference-types"))]
+		assert_eq!(decoded, Ok("hi".into()));
+		// References wrap the type they refer to with the `reference-types` feature.
+		#[cfg(feature = "reference-types")]
+		assert_eq!(decoded, Ok(Value::Composite(Composite::Unnamed(vec!["hi".into()]))));
+	}
+
+	#[test]
+	fn rejects_invalid_input() {
+		let mut registry = Registry::new();
+		let option = registry.compact_type(&<Option<u32>>::meta_type());
+		let deferred = registry.defer_type(&bool::meta_type());
+		assert_eq!(
+			decode_scale(&mut &[1, 0, 0][..], option, &registry),
+			Err(DecodeError::UnexpectedEnd)
+		);
+		assert_eq!(
+			decode_scale(&mut &[2][..], option, &registry),
+			Err(DecodeError::InvalidVariant { index: 2 })
+		);
+		assert_eq!(
+			decode_scale(&mut &[0][..], deferred, &registry),
+			Err(DecodeError::UnknownType {
+				id: deferred.id().get()
+			})
+		);
+	}
+
+	#[test]
+	fn encodes_decoded_values() {
+		let mut registry = Registry::new();
+		let ty = registry.compact_type(&<Vec<(bool, Result<i16, char>)>>::meta_type());
+		let bytes = [8, 1, 0, 0xfe, 0xff, 0, 1, 0x78, 0, 0, 0];
+		let value = decode_scale(&mut &bytes[..], ty, &registry).unwrap();
+		assert_eq!(encode_scale(&value, ty, &registry), Ok(bytes.to_vec()));
+
+		let ty = registry.compact_type(&<([u8; 2], String)>::meta_type());
+		let value = Composite::Unnamed(vec![vec![1u8, 2].into(), "hi".into()]).into();
+		assert_eq!(encode_scale(&value, ty, &registry), Ok(vec![1, 2, 8, b'h', b'i']));
+	}
+
+	#[test]
+	fn encodes_deserialized_shapes() {
+		let mut registry = Registry::new();
+		let ty = registry.compact_type(&<Vec<(bool, Result<i16, char>)>>::meta_type());
+		let err = Composite::Named(vec![("Err".to_string(), "x".into())]);
+		let ok = Composite::Named(vec![("Ok".to_string(), vec![Value::Int(-2)].into())]);
+		let value = Composite::Named(vec![(
+			"elems".to_string(),
+			vec![
+				Value::Sequence(vec![true.into(), ok.into()]),
+				Value::Sequence(vec![false.into(), err.into()]),
+			]
+			.into(),
+		)]);
+		assert_eq!(
+			encode_scale(&value.into(), ty, &registry),
+			Ok(vec![8, 1, 0, 0xfe, 0xff, 0, 1, 0x78, 0, 0, 0])
+		);
+
+		let ty = registry.compact_type(&<Option<()>>::meta_type());
+		assert_eq!(encode_scale(&"None".into(), ty, &registry), Ok(vec![0]));
+	}
+
+	#[test]
+	fn rejects_mismatching_values() {
+		let mut registry = Registry::new();
+		let array = registry.compact_type(&<[u8; 2]>::meta_type());
+		let u8_symbol = registry.compact_type(&u8::meta_type());
+		let option = registry.compact_type(&<Option<u8>>::meta_type());
+		let string = registry.compact_type(&String::meta_type());
+		assert_eq!(
+			encode_scale(&vec![1u8].into(), array, &registry),
+			Err(EncodeError::LengthMismatch {
+				id: array.id().get(),
+				expected: 2,
+				actual: 1
+			})
+		);
+		assert_eq!(
+			encode_scale(&256u16.into(), u8_symbol, &registry),
+			Err(EncodeError::OutOfRange {
+				id: u8_symbol.id().get()
+			})
+		);
+		assert_eq!(
+			encode_scale(&Variant::unit("Nothing").into(), option, &registry),
+			Err(EncodeError::UnknownVariant {
+				id: option.id().get(),
+				name: "Nothing".to_string()
+			})
+		);
+		assert_eq!(
+			encode_scale(&true.into(), string, &registry),
+			Err(EncodeError::Mismatch { id: string.id().get() })
+		);
+	}
+}
@@ -19,7 +19,18 @@
 
 #[start]
 fn start(_argc: isize, _argv: *const *const u8) -> isize {
-	0
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&Struct::<E<u8>>::meta_type());
+	builder.register_type(&CLike::meta_type());
+	let registry = builder.build();
+	let json = match serde_json::to_string(&registry.with_layout(Layout::Nested)) {
+		Ok(json) => json,
+		Err(_) => return 1,
+	};
+	match serde_json::from_str::<nested::Registry>(&json) {
+		Ok(nested) if nested == nested::Registry::from(&registry) => 0,
+		_ => 1,
+	}
 }
 
 #[lang = "eh_personality"]
@@ -58,7 +69,10 @@ unsafe impl GlobalAlloc for Allocator {
 	}
 }
 
-use type_metadata::Metadata;
+use type_metadata::{
+	layout::{nested, Layout},
+	Metadata, RegistryBuilder,
+};
 
 #[allow(unused)]
 #[derive(Metadata)]
@@ -0,0 +1,64 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verifies the reference implementation against the conformance test vectors.
+//!
+//! The vectors of a published version never change. When bumping `conformance::VERSION`
+//! add the new directory and generate its vectors with
+//! `UPDATE_SNAPSHOTS=1 cargo test --test conformance`.
+
+use std::{env, fs, path::Path};
+use type_metadata::{
+	conformance::{reference_producer, vectors, verify_conformance, ConformanceError, LAYOUTS, VERSION},
+	layout::Layout,
+	snapshot::UPDATE_SNAPSHOTS,
+};
+
+#[test]
+fn reference_conforms() {
+	if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+		let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+			.join("../conformance")
+			.join(format!("v{}", VERSION));
+		for vector in vectors() {
+			for layout in LAYOUTS.iter().copied() {
+				fs::write(dir.join(vector.file_name(layout)), reference_producer(vector, layout))
+					.expect("failed to write the conformance vector");
+			}
+		}
+		return;
+	}
+	verify_conformance(reference_producer).unwrap();
+}
+
+#[test]
+fn deviations_are_reported() {
+	let producer = |vector: &_, layout| match layout {
+		Layout::Tagged => reference_producer(vector, Layout::Nested),
+		_ => reference_producer(vector, layout),
+	};
+	match verify_conformance(producer) {
+		Err(ConformanceError::Mismatch { vector, layout, diff }) => {
+			assert_eq!((vector, layout), (vectors()[0].name(), Layout::Tagged));
+			assert!(diff.lines().any(|line| line.starts_with('+')));
+		}
+		other => panic!("unexpected result {:?}", other),
+	}
+	assert!(matches!(
+		verify_conformance(|_, _| String::from("{")),
+		Err(ConformanceError::InvalidJson { .. })
+	));
+}
@@ -20,12 +20,12 @@
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
-use alloc::{boxed::Box, vec};
+use alloc::{boxed::Box, vec, vec::Vec};
 
 use type_metadata::{
-	tuple_meta_type, ClikeEnumVariant, EnumVariantStruct, EnumVariantTupleStruct, EnumVariantUnit, HasTypeDef,
-	HasTypeId, MetaType, Metadata, NamedField, Namespace, TypeDefClikeEnum, TypeDefEnum, TypeDefStruct,
-	TypeDefTupleStruct, TypeDefUnion, TypeId, TypeIdCustom, UnnamedField,
+	assert_metadata_matches, tuple_meta_type, EnumRepr, HasTypeDef, HasTypeId, MetaType, Metadata, NamedField,
+	Namespace, RawFormat, Sensitivity, TypeConsts, TypeDef, TypeDefComposite, TypeDefUnion, TypeDefVariant, TypeId,
+	TypeIdCustom, UnnamedField, Variant,
 };
 
 fn assert_type_id<T, E>(expected: E)
@@ -54,7 +54,7 @@ fn struct_derive() {
 	let type_id = TypeIdCustom::new("S", Namespace::new(vec!["derive"]).unwrap(), tuple_meta_type!(bool, u8));
 	assert_type_id!(S<bool, u8>, type_id.clone());
 
-	let type_def = TypeDefStruct::new(vec![
+	let type_def = TypeDefComposite::new(vec![
 		NamedField::new("t", bool::meta_type()),
 		NamedField::new("u", u8::meta_type()),
 	])
@@ -74,7 +74,7 @@ fn struct_derive() {
 
 	assert_eq!(
 		SelfTyped::type_def(),
-		TypeDefStruct::new(vec![
+		TypeDefComposite::new(vec![
 			NamedField::new("t", <Box<S<bool, u8>>>::meta_type()),
 			NamedField::new("u", bool::meta_type()),
 		])
@@ -91,7 +91,7 @@ fn tuple_struct_derive() {
 	let type_id = TypeIdCustom::new("S", Namespace::new(vec!["derive"]).unwrap(), tuple_meta_type!(bool));
 	assert_type_id!(S<bool>, type_id);
 
-	let type_def = TypeDefTupleStruct::new(vec![UnnamedField::of::<bool>()]).into();
+	let type_def = TypeDefComposite::new(vec![UnnamedField::of::<bool>()]).into();
 	assert_eq!(<S<bool>>::type_def(), type_def);
 }
 
@@ -104,7 +104,7 @@ fn unit_struct_derive() {
 	let type_id = TypeIdCustom::new("S", Namespace::new(vec!["derive"]).unwrap(), vec![]);
 	assert_type_id!(S, type_id);
 
-	let type_def = TypeDefTupleStruct::unit().into();
+	let type_def = TypeDefComposite::unit().into();
 	assert_eq!(S::type_def(), type_def);
 }
 
@@ -120,9 +120,9 @@ fn c_like_enum_derive() {
 	let type_id = TypeIdCustom::new("E", Namespace::new(vec!["derive"]).unwrap(), vec![]);
 	assert_type_id!(E, type_id);
 
-	let type_def = TypeDefClikeEnum::new(vec![
-		ClikeEnumVariant::new("A", 0u64),
-		ClikeEnumVariant::new("B", 10u64),
+	let type_def = TypeDefVariant::new(vec![
+		Variant::new("A").with_discriminant(0),
+		Variant::new("B").with_discriminant(10),
 	])
 	.into();
 	assert_eq!(E::type_def(), type_def);
@@ -141,17 +141,367 @@ fn enum_derive() {
 	let type_id = TypeIdCustom::new("E", Namespace::new(vec!["derive"]).unwrap(), tuple_meta_type!(bool));
 	assert_type_id!(E<bool>, type_id);
 
-	let type_def = TypeDefEnum::new(vec![
-		EnumVariantTupleStruct::new("A", vec![UnnamedField::of::<bool>()]).into(),
-		EnumVariantStruct::new("B", vec![NamedField::new("b", bool::meta_type())]).into(),
-		EnumVariantUnit::new("C").into(),
+	let type_def = TypeDefVariant::new(vec![
+		Variant::new("A").with_fields(vec![UnnamedField::of::<bool>()]),
+		Variant::new("B").with_fields(vec![NamedField::new("b", bool::meta_type())]),
+		Variant::new("C"),
 	])
 	.into();
 	assert_eq!(<E<bool>>::type_def(), type_def);
 }
 
 #[test]
-// #[should_panic] // TODO: remove #[should_panic]
+fn discriminated_enum_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[repr(u8)]
+	enum Command {
+		Stop = 1,
+		Move { x: i32, y: i32 } = 4,
+		Say(u8) = 5,
+	}
+
+	assert_eq!(
+		Command::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Stop").with_discriminant(1),
+			Variant::new("Move")
+				.with_fields(vec![NamedField::of::<i32>("x"), NamedField::of::<i32>("y")])
+				.with_discriminant(4),
+			Variant::new("Say")
+				.with_fields(vec![UnnamedField::of::<u8>()])
+				.with_discriminant(5),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn enum_repr_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(tag = "type")]
+	enum Internal {
+		A { a: u8 },
+		B,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(tag = "t", content = "c")]
+	enum Adjacent {
+		A(u8),
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(untagged)]
+	enum Untagged {
+		A,
+		B,
+	}
+
+	assert_eq!(
+		Internal::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("A").with_fields(vec![NamedField::of::<u8>("a")]),
+			Variant::new("B"),
+		])
+		.with_repr(EnumRepr::internal("type"))
+		.into()
+	);
+	assert_eq!(
+		Adjacent::type_def(),
+		TypeDefVariant::new(vec![Variant::new("A").with_fields(vec![UnnamedField::of::<u8>()])])
+			.with_repr(EnumRepr::adjacent("t", "c"))
+			.into()
+	);
+	assert_eq!(
+		Untagged::type_def(),
+		TypeDefVariant::new(vec![Variant::new("A"), Variant::new("B")])
+			.with_repr(EnumRepr::Untagged)
+			.into()
+	);
+}
+
+#[test]
+fn stable_id_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(id = "5f0c3e1a-8d2b-4c6e-9a7f-1b3d5e7f9a0c")]
+	struct Tracked {
+		a: u8,
+	}
+
+	let type_id = TypeIdCustom::new("Tracked", Namespace::new(vec!["derive"]).unwrap(), vec![])
+		.with_stable_id("5f0c3e1a-8d2b-4c6e-9a7f-1b3d5e7f9a0c");
+	assert_type_id!(Tracked, type_id);
+}
+
+#[test]
+fn name_and_namespace_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(name = "Balance", namespace = "runtime::balances")]
+	struct GeneratedBalance(u128);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(namespace = "runtime")]
+	struct Call<T> {
+		arg: T,
+	}
+
+	assert_type_id!(
+		GeneratedBalance,
+		TypeIdCustom::new("Balance", Namespace::new(vec!["runtime", "balances"]).unwrap(), vec![])
+	);
+	assert_type_id!(
+		Call<bool>,
+		TypeIdCustom::new(
+			"Call",
+			Namespace::new(vec!["runtime"]).unwrap(),
+			vec![bool::meta_type()]
+		)
+	);
+}
+
+#[test]
+fn docs_derive() {
+	/// A point in time.
+	///
+	/// Seconds since the epoch.
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Timestamp {
+		/// The seconds.
+		secs: u64,
+		nanos: u32,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum Event {
+		/// The node started.
+		Started,
+		Stopped(u8),
+	}
+
+	assert_type_id!(
+		Timestamp,
+		TypeIdCustom::new(
+			"Timestamp",
+			Namespace::from_module_path(module_path!()).unwrap(),
+			vec![]
+		)
+		.with_docs(&["A point in time.", "", "Seconds since the epoch."])
+	);
+	assert_eq!(
+		Timestamp::type_def(),
+		TypeDefComposite::new(vec![
+			NamedField::of::<u64>("secs").with_docs(&["The seconds."]),
+			NamedField::of::<u32>("nanos"),
+		])
+		.into()
+	);
+	assert_eq!(
+		Event::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Started").with_docs(&["The node started."]),
+			Variant::new("Stopped").with_fields(vec![UnnamedField::of::<u8>()]),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn field_group_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Config {
+		#[metadata(group = "limits")]
+		max_connections: u32,
+		#[metadata(group = "limits")]
+		max_requests: u32,
+		name: bool,
+	}
+
+	assert_eq!(
+		Config::type_def(),
+		TypeDefComposite::new(vec![
+			NamedField::of::<u32>("max_connections").with_group("limits"),
+			NamedField::of::<u32>("max_requests").with_group("limits"),
+			NamedField::of::<bool>("name"),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn sensitivity_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Credentials {
+		user: u8,
+		#[metadata(sensitivity = "internal")]
+		email: u8,
+		#[metadata(group = "auth", sensitivity = "secret")]
+		password: u8,
+	}
+
+	assert_eq!(
+		Credentials::type_def(),
+		TypeDefComposite::new(vec![
+			NamedField::of::<u8>("user"),
+			NamedField::of::<u8>("email").with_sensitivity(Sensitivity::Internal),
+			NamedField::of::<u8>("password")
+				.with_group("auth")
+				.with_sensitivity(Sensitivity::Secret),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn display_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Transfer {
+		#[metadata(display = "ss58")]
+		to: [u8; 32],
+		#[metadata(display = "duration-ms", group = "limits")]
+		timeout: u64,
+		amount: u128,
+	}
+
+	assert_eq!(
+		Transfer::type_def(),
+		TypeDefComposite::new(vec![
+			NamedField::of::<[u8; 32]>("to").with_display("ss58"),
+			NamedField::of::<u64>("timeout")
+				.with_group("limits")
+				.with_display("duration-ms"),
+			NamedField::of::<u128>("amount"),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn with_derive() {
+	/// A timestamp that does not implement `Metadata`.
+	struct Timestamp(#[allow(unused)] u64);
+
+	/// Describes timestamps as they are serialized, i.e. as RFC 3339 strings.
+	mod rfc3339 {
+		use type_metadata::{TypeDef, TypeId, TypeIdPrimitive};
+
+		pub fn type_id() -> TypeId {
+			TypeIdPrimitive::Str.into()
+		}
+
+		pub fn type_def() -> TypeDef {
+			TypeDef::builtin()
+		}
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Event {
+		#[metadata(with = "rfc3339")]
+		created: Timestamp,
+		id: u32,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Expiry(#[metadata(with = "rfc3339")] Timestamp);
+
+	let timestamp = MetaType::from_fns(rfc3339::type_def, rfc3339::type_id, rfc3339::type_def);
+	assert_eq!(
+		Event::type_def(),
+		TypeDefComposite::new(vec![NamedField::new("created", timestamp), NamedField::of::<u32>("id")]).into()
+	);
+	assert_eq!(
+		Expiry::type_def(),
+		TypeDefComposite::new(vec![UnnamedField::new(timestamp)]).into()
+	);
+	assert_eq!(timestamp.type_id(), rfc3339::type_id());
+	assert_eq!(timestamp.type_def(), rfc3339::type_def());
+}
+
+#[test]
+fn transparent_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(transparent)]
+	struct Balance(u128);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(transparent)]
+	struct Account<T> {
+		id: T,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(transparent)]
+	struct Index(core::num::NonZeroU32);
+
+	assert_type_id!(Balance, u128::type_id());
+	assert_eq!(Balance::type_def(), u128::type_def());
+	assert_type_id!(Account<Option<bool>>, <Option<bool>>::type_id());
+	assert_eq!(<Account<Option<bool>>>::type_def(), <Option<bool>>::type_def());
+	assert_eq!([<Index as HasTypeDef>::NICHE, <Balance as HasTypeDef>::NICHE], [true, false]);
+}
+
+#[test]
+fn error_code_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum Simple {
+		#[metadata(code = 404)]
+		NotFound,
+		Unknown,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum Detailed {
+		#[metadata(code = 400)]
+		Invalid {
+			reason: u8,
+		},
+		#[metadata(code = 500)]
+		Internal(u8),
+		Unknown,
+	}
+
+	assert_eq!(
+		Simple::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("NotFound").with_discriminant(0).with_code(404),
+			Variant::new("Unknown").with_discriminant(1),
+		])
+		.into()
+	);
+	assert_eq!(
+		Detailed::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Invalid")
+				.with_fields(vec![NamedField::of::<u8>("reason")])
+				.with_code(400),
+			Variant::new("Internal")
+				.with_fields(vec![UnnamedField::of::<u8>()])
+				.with_code(500),
+			Variant::new("Unknown"),
+		])
+		.into()
+	);
+}
+
+#[test]
 fn union_derive() {
 	#[allow(unused)]
 	#[derive(Metadata)]
@@ -164,4 +514,107 @@ fn union_derive() {
 
 	let type_def = TypeDefUnion::new(vec![NamedField::new("u", bool::meta_type())]).into();
 	assert_eq!(<U<bool>>::type_def(), type_def);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	union Word<T: Copy, U: Copy> {
+		/// The word as a whole.
+		whole: T,
+		#[metadata(group = "halves")]
+		halves: [U; 2],
+	}
+
+	let type_id = TypeIdCustom::new("Word", Namespace::new(vec!["derive"]).unwrap(), tuple_meta_type!(u32, u16));
+	assert_type_id!(Word<u32, u16>, type_id);
+
+	let type_def = TypeDefUnion::new(vec![
+		NamedField::of::<u32>("whole").with_docs(&["The word as a whole."]),
+		NamedField::of::<[u16; 2]>("halves").with_group("halves"),
+	])
+	.into();
+	assert_eq!(<Word<u32, u16>>::type_def(), type_def);
+}
+
+#[test]
+fn raw_payload_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(raw = "scale")]
+	struct Payload(Vec<u8>);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Envelope {
+		to: u64,
+		payload: Payload,
+	}
+
+	assert_eq!(Payload::type_def(), TypeDef::raw(RawFormat::Scale));
+	assert_eq!(
+		Envelope::type_def(),
+		TypeDefComposite::new(vec![NamedField::of::<u64>("to"), NamedField::of::<Payload>("payload")]).into()
+	);
+}
+
+#[test]
+fn type_consts_derive() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct S(u8, bool);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum E {
+		A,
+		B { b: u8 },
+		C(bool),
+	}
+
+	mod renamed {
+		#[allow(unused)]
+		#[derive(type_metadata::Metadata)]
+		pub struct S(u8, u16);
+	}
+
+	const _: () = assert!(<S as TypeConsts>::FIELD_COUNT == 2 && <S as TypeConsts>::VARIANT_COUNT == 0);
+	const _: () = assert!(<E as TypeConsts>::FIELD_COUNT == 0 && <E as TypeConsts>::VARIANT_COUNT == 3);
+	assert_eq!(<Point<u8> as TypeConsts>::FIELD_COUNT, 2);
+	assert_ne!(
+		<S as TypeConsts>::METADATA_HASH,
+		<renamed::S as TypeConsts>::METADATA_HASH
+	);
+	assert_eq!(
+		<Point<u8> as TypeConsts>::METADATA_HASH,
+		<Point<bool> as TypeConsts>::METADATA_HASH
+	);
 }
+
+#[allow(unused)]
+#[derive(Metadata)]
+struct Point<T> {
+	x: T,
+	y: T,
+}
+
+assert_metadata_matches!(
+	point_metadata_matches,
+	Point<u32>,
+	id: TypeIdCustom::new("Point", Namespace::new(vec!["derive"]).unwrap(), tuple_meta_type!(u32)),
+	def: TypeDefComposite::new(vec![NamedField::of::<u32>("x"), NamedField::of::<u32>("y")]),
+);
+
+#[allow(unused)]
+#[derive(Metadata)]
+enum Direction {
+	Left,
+	Right,
+}
+
+assert_metadata_matches!(
+	direction_metadata_matches,
+	Direction,
+	TypeDefVariant::new(vec![
+		Variant::new("Left").with_discriminant(0),
+		Variant::new("Right").with_discriminant(1),
+	]),
+);
@@ -0,0 +1,323 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Expansion corpus of the derive for generic types.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::marker::PhantomData;
+use type_metadata::{
+	tuple_meta_type, ConstParam, HasTypeDef, HasTypeId, MetaType, Metadata, NamedField, Namespace, TypeConsts, TypeDef,
+	TypeDefComposite, TypeDefVariant, TypeId, TypeIdCustom, UnnamedField, Variant,
+};
+
+fn custom(name: &'static str, type_params: Vec<MetaType>) -> TypeId {
+	TypeIdCustom::new(name, Namespace::new(vec!["derive_generics"]).unwrap(), type_params).into()
+}
+
+fn custom_with_consts(name: &'static str, type_params: Vec<MetaType>, consts: Vec<ConstParam>) -> TypeId {
+	TypeIdCustom::new(name, Namespace::new(vec!["derive_generics"]).unwrap(), type_params)
+		.with_const_params(consts)
+		.into()
+}
+
+fn composite<T: Into<TypeDef>>(def: T) -> TypeDef {
+	def.into()
+}
+
+trait Config {
+	type AccountId;
+	type Balance;
+}
+
+#[derive(Metadata)]
+struct Runtime;
+
+impl Config for Runtime {
+	type AccountId = [u8; 32];
+	type Balance = u128;
+}
+
+/// A runtime that does not implement `Metadata`.
+struct Opaque;
+
+impl Config for Opaque {
+	type AccountId = u64;
+	type Balance = u32;
+}
+
+#[test]
+fn associated_types_of_params() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Account<T: Config> {
+		who: T::AccountId,
+		free: Vec<<T as Config>::Balance>,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum Event<T: Config> {
+		Transfer(T::AccountId, T::AccountId, T::Balance),
+		Reaped { who: T::AccountId },
+	}
+
+	assert_eq!(
+		Account::<Runtime>::type_id(),
+		custom("Account", tuple_meta_type!(Runtime))
+	);
+	assert_eq!(
+		Account::<Runtime>::type_def(),
+		composite(TypeDefComposite::new(vec![
+			NamedField::of::<[u8; 32]>("who"),
+			NamedField::of::<Vec<u128>>("free"),
+		]))
+	);
+	assert_eq!(
+		Event::<Runtime>::type_def(),
+		composite(TypeDefVariant::new(vec![
+			Variant::new("Transfer").with_fields(vec![
+				UnnamedField::of::<[u8; 32]>(),
+				UnnamedField::of::<[u8; 32]>(),
+				UnnamedField::of::<u128>(),
+			]),
+			Variant::new("Reaped").with_fields(vec![NamedField::of::<[u8; 32]>("who")]),
+		]))
+	);
+}
+
+#[test]
+fn default_type_params() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Pair<A, B = A> {
+		a: A,
+		b: B,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Defaulted<T: Config = Runtime>(T::Balance);
+
+	assert_eq!(<Pair<u8>>::type_id(), custom("Pair", tuple_meta_type!(u8, u8)));
+	assert_eq!(<Pair<u8, bool>>::type_id(), custom("Pair", tuple_meta_type!(u8, bool)));
+	assert_eq!(
+		<Defaulted>::type_def(),
+		composite(TypeDefComposite::new(vec![UnnamedField::of::<u128>()]))
+	);
+}
+
+#[test]
+fn const_generic_params() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Buf<const N: usize>([u8; N]);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Matrix<T, const R: usize, const C: usize> {
+		rows: [[T; C]; R],
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Flags<const B: bool, const C: char, const I: i8>;
+
+	assert_eq!(
+		<Buf<4>>::type_id(),
+		custom_with_consts("Buf", vec![], vec![ConstParam::UInt(4)])
+	);
+	assert_ne!(<Buf<4>>::type_id(), <Buf<8>>::type_id());
+	assert_eq!(
+		<Buf<4>>::type_def(),
+		composite(TypeDefComposite::new(vec![UnnamedField::of::<[u8; 4]>()]))
+	);
+	assert_eq!(
+		<Matrix<bool, 2, 3>>::type_id(),
+		custom_with_consts(
+			"Matrix",
+			tuple_meta_type!(bool),
+			vec![ConstParam::UInt(2), ConstParam::UInt(3)]
+		)
+	);
+	assert_eq!(
+		<Matrix<bool, 2, 3>>::type_def(),
+		composite(TypeDefComposite::new(vec![NamedField::of::<[[bool; 3]; 2]>("rows")]))
+	);
+	assert_eq!(
+		<Flags<true, 'x', { -1 }>>::type_id(),
+		custom_with_consts(
+			"Flags",
+			vec![],
+			vec![ConstParam::Bool(true), ConstParam::UInt(0x78), ConstParam::Int(-1)]
+		)
+	);
+}
+
+#[test]
+fn phantom_data_only_params() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Marker<T>(PhantomData<T>);
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Unsized<T: ?Sized> {
+		marker: PhantomData<T>,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(skip_type_params(T))]
+	enum Tagged<T: Config> {
+		Id(u32, PhantomData<T>),
+		Account { id: PhantomData<T::AccountId>, nonce: u64 },
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(transparent)]
+	struct Amount<T>(u128, PhantomData<T>);
+
+	assert_eq!(<Marker<u32>>::type_id(), custom("Marker", tuple_meta_type!(u32)));
+	assert_eq!(
+		<Marker<u32>>::type_def(),
+		composite(TypeDefComposite::new(Vec::<UnnamedField>::new()))
+	);
+	assert_eq!(<Unsized<str>>::type_id(), custom("Unsized", vec![str::meta_type()]));
+	assert_eq!(<Unsized<str>>::type_def(), composite(TypeDefComposite::new(Vec::<NamedField>::new())));
+	assert_eq!(<Tagged<Opaque>>::type_id(), custom("Tagged", vec![]));
+	assert_eq!(
+		<Tagged<Opaque>>::type_def(),
+		composite(TypeDefVariant::new(vec![
+			Variant::new("Id").with_fields(vec![UnnamedField::of::<u32>()]),
+			Variant::new("Account").with_fields(vec![NamedField::of::<u64>("nonce")]),
+		]))
+	);
+	assert_eq!(<Amount<bool>>::type_id(), u128::type_id());
+	assert_eq!(<Amount<bool>>::type_def(), u128::type_def());
+	assert_eq!(<Marker<u32> as TypeConsts>::FIELD_COUNT, 0);
+}
+
+#[test]
+fn skipped_type_params() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(skip_type_params(T))]
+	struct Transfer<T: Config> {
+		to: T::AccountId,
+		amount: T::Balance,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(skip_type_params(T), bounds(T::AccountId: Metadata + 'static, U: Metadata + 'static))]
+	struct Keyed<T: Config, U> {
+		key: T::AccountId,
+		value: U,
+	}
+
+	assert_eq!(<Transfer<Opaque>>::type_id(), custom("Transfer", vec![]));
+	assert_eq!(
+		<Transfer<Opaque>>::type_def(),
+		composite(TypeDefComposite::new(vec![
+			NamedField::of::<u64>("to"),
+			NamedField::of::<u32>("amount"),
+		]))
+	);
+	assert_eq!(<Keyed<Opaque, bool>>::type_id(), custom("Keyed", tuple_meta_type!(bool)));
+	assert_eq!(
+		<Keyed<Opaque, bool>>::type_def(),
+		composite(TypeDefComposite::new(vec![
+			NamedField::of::<u64>("key"),
+			NamedField::of::<bool>("value"),
+		]))
+	);
+}
+
+#[test]
+fn lifetime_params() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Ref<'a> {
+		x: &'a u32,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum Cow<'a, T: 'a> {
+		Borrowed(&'a [T]),
+		Owned(Vec<T>),
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	#[metadata(transparent)]
+	struct Name<'a>(&'a str);
+
+	assert_eq!(<Ref<'static>>::type_id(), custom("Ref", vec![]));
+	assert_eq!(
+		<Ref<'static>>::type_def(),
+		composite(TypeDefComposite::new(vec![NamedField::of::<&u32>("x")]))
+	);
+	assert_eq!(<Cow<'static, u8>>::type_id(), custom("Cow", tuple_meta_type!(u8)));
+	assert_eq!(
+		<Cow<'static, u8>>::type_def(),
+		composite(TypeDefVariant::new(vec![
+			Variant::new("Borrowed").with_fields(vec![UnnamedField::of::<&[u8]>()]),
+			Variant::new("Owned").with_fields(vec![UnnamedField::of::<Vec<u8>>()]),
+		]))
+	);
+	assert_eq!(<Name<'static>>::type_id(), str::type_id());
+}
+
+#[test]
+fn where_clauses() {
+	#[allow(unused)]
+	#[derive(Metadata)]
+	struct Bounded<T>
+	where
+		T: Clone,
+		Self: Sized,
+	{
+		t: T,
+	}
+
+	#[allow(unused)]
+	#[derive(Metadata)]
+	enum Either<L, R>
+	where
+		L: Copy,
+	{
+		Left(L),
+		Right(R),
+	}
+
+	assert_eq!(<Bounded<u8>>::type_id(), custom("Bounded", tuple_meta_type!(u8)));
+	assert_eq!(
+		<Either<u8, bool>>::type_def(),
+		composite(TypeDefVariant::new(vec![
+			Variant::new("Left").with_fields(vec![UnnamedField::of::<u8>()]),
+			Variant::new("Right").with_fields(vec![UnnamedField::of::<bool>()]),
+		]))
+	);
+}
@@ -0,0 +1,101 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guards the determinism of serialized metadata across targets.
+//!
+//! Run these tests on 32-bit targets, e.g. `wasm32-wasi`, as well as on 64-bit targets.
+//! Since all of them compare against the same committed snapshot they fail whenever the
+//! serialized output depends on the pointer width or other properties of the target.
+//! Regenerate the snapshot with `UPDATE_SNAPSHOTS=1 cargo test --test determinism`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use type_metadata::{
+	snapshot::{register_corpus, to_snapshot},
+	Metadata, Registry, RegistryBuilder,
+};
+
+#[allow(unused)]
+#[derive(Metadata)]
+#[metadata(id = "9b2c4e6f-1a3d-4f5b-8c7e-0d2f4a6b8c0e")]
+struct Account<T> {
+	#[metadata(group = "identity")]
+	name: String,
+	#[metadata(sensitivity = "secret")]
+	key: [u8; 32],
+	balance: T,
+}
+
+#[allow(unused)]
+#[derive(Metadata)]
+struct Wrapper(u32, (bool, char));
+
+#[allow(unused)]
+#[derive(Metadata)]
+struct Marker;
+
+#[allow(unused)]
+#[derive(Metadata)]
+enum Status {
+	#[metadata(code = 200)]
+	Ok,
+	Failed = 42,
+}
+
+#[allow(unused)]
+#[derive(Metadata)]
+#[metadata(tag = "type", content = "value")]
+enum Event {
+	Created {
+		account: Account<u128>,
+	},
+	#[metadata(code = 7)]
+	Moved(u16, u16),
+	Deleted,
+}
+
+#[allow(unused)]
+#[derive(Metadata)]
+union Bits {
+	int: u32,
+	bytes: [u8; 4],
+}
+
+fn corpus() -> Registry {
+	let mut builder = RegistryBuilder::new();
+	register_corpus(&mut builder);
+	type_metadata::register_types!(&mut builder, Event, Status, Wrapper, Marker, Bits);
+	builder.build()
+}
+
+#[test]
+fn corpus_is_deterministic() {
+	let registry = corpus();
+	#[cfg(not(target_arch = "wasm32"))]
+	type_metadata::assert_registry_snapshot!(registry, "corpus");
+	assert_eq!(to_snapshot(&registry), include_str!("../snapshots/corpus.json"));
+}
+
+#[test]
+fn registration_is_repeatable() {
+	assert_eq!(to_snapshot(&corpus()), to_snapshot(&corpus()));
+}
@@ -0,0 +1,101 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementations of `impl_metadata!` for types declared without the derive.
+
+use type_metadata::{
+	impl_metadata, tuple_meta_type, HasTypeDef, HasTypeId, MetaType, NamedField, Namespace, TypeDefComposite,
+	TypeDefVariant, TypeIdCustom, UnnamedField, Variant,
+};
+
+#[allow(unused)]
+mod external {
+	pub struct Account {
+		pub name: String,
+		pub balance: u128,
+	}
+
+	pub struct Pair<A, B>(pub A, pub B);
+
+	pub struct Marker;
+
+	pub enum Event<T> {
+		Created(T),
+		Moved { from: u32, to: u32 },
+		Cleared,
+	}
+}
+
+#[allow(unused)]
+struct Local(u8);
+
+impl_metadata!(struct external::Account { name: String, balance: u128 });
+impl_metadata!(struct external::Pair<A, B>(A, B));
+impl_metadata!(struct external::Marker;);
+impl_metadata!(enum external::Event<T> { Created(T), Moved { from: u32, to: u32 }, Cleared });
+impl_metadata!(struct Local(u8));
+
+fn external() -> Namespace {
+	Namespace::new(vec!["external"]).unwrap()
+}
+
+#[test]
+fn structs() {
+	assert_eq!(
+		external::Account::type_id(),
+		TypeIdCustom::new("Account", external(), Vec::<MetaType>::new()).into()
+	);
+	assert_eq!(
+		external::Account::type_def(),
+		TypeDefComposite::new(vec![NamedField::of::<String>("name"), NamedField::of::<u128>("balance")]).into()
+	);
+
+	assert_eq!(
+		<external::Pair<u8, bool>>::type_id(),
+		TypeIdCustom::new("Pair", external(), tuple_meta_type!(u8, bool)).into()
+	);
+	assert_eq!(
+		<external::Pair<u8, bool>>::type_def(),
+		TypeDefComposite::new(vec![UnnamedField::of::<u8>(), UnnamedField::of::<bool>()]).into()
+	);
+
+	assert_eq!(external::Marker::type_def(), TypeDefComposite::unit().into());
+}
+
+#[test]
+fn enums() {
+	assert_eq!(
+		<external::Event<bool>>::type_id(),
+		TypeIdCustom::new("Event", external(), tuple_meta_type!(bool)).into()
+	);
+	assert_eq!(
+		<external::Event<bool>>::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Created").with_fields(vec![UnnamedField::of::<bool>()]),
+			Variant::new("Moved").with_fields(vec![NamedField::of::<u32>("from"), NamedField::of::<u32>("to")]),
+			Variant::new("Cleared"),
+		])
+		.into()
+	);
+}
+
+#[test]
+fn local_types_use_the_module_path() {
+	assert_eq!(
+		Local::type_id(),
+		TypeIdCustom::new("Local", Namespace::from_module_path(module_path!()).unwrap(), Vec::<MetaType>::new()).into()
+	);
+}
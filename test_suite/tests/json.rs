@@ -15,16 +15,24 @@
 // limitations under the License.
 
 #![cfg_attr(not(feature = "std"), no_std)]
+// The fixtures only exist to derive their metadata.
+#![allow(dead_code, clippy::enum_variant_names)]
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
-use alloc::{vec, vec::Vec};
+use alloc::{string::ToString, vec, vec::Vec};
 
 use serde::Serialize;
 use serde_json::json;
-use type_metadata::{form::CompactForm, IntoCompact as _, Metadata, Registry, TypeDef, TypeId};
+use type_metadata::{
+	form::CompactForm,
+	layout::{nested, tagged, Layout},
+	value::{Composite, Value},
+	Capabilities, HasTypeDef, IngestOptions, IntoCompact as _, Metadata, NamedField, OwnedRegistry, PortableRegistry,
+	Registry, RegistryBuilder, TypeDef, TypeDefUnion, TypeId, UnionDiscriminator,
+};
 
 #[derive(Serialize)]
 struct TypeIdDef {
@@ -82,6 +90,42 @@ fn test_tuple_struct() {
 	}));
 }
 
+#[test]
+fn test_stable_id() {
+	#[derive(Metadata)]
+	#[metadata(id = "5f0c3e1a-8d2b-4c6e-9a7f-1b3d5e7f9a0c")]
+	struct Tracked;
+
+	assert_json_for_type::<Tracked>(json!({
+		"id": {
+			"custom.name": 1,
+			"custom.namespace": [2],
+			"custom.params": [],
+			"custom.id": 3,
+		},
+		"def": {
+			"tuple_struct.types": []
+		},
+	}));
+}
+
+#[test]
+fn test_byte_slice() {
+	assert_json_for_type::<&'static [u8]>(json!({
+		"id": {
+			"slice.type": 1,
+			"slice.encoding": "bytes",
+		},
+		"def": "builtin",
+	}));
+	assert_json_for_type::<&'static [bool]>(json!({
+		"id": {
+			"slice.type": 1,
+		},
+		"def": "builtin",
+	}));
+}
+
 #[test]
 fn test_struct() {
 	#[derive(Metadata)]
@@ -107,6 +151,78 @@ fn test_struct() {
 	}));
 }
 
+#[test]
+fn test_struct_field_groups() {
+	#[derive(Metadata)]
+	struct Grouped {
+		#[metadata(group = "limits")]
+		a: i32,
+		b: bool,
+	}
+
+	assert_json_for_type::<Grouped>(json!({
+		"id": {
+			"custom.name": 1,
+			"custom.namespace": [2],
+			"custom.params": [],
+		},
+		"def": {
+			"struct.fields": [
+				{ "name": 3, "type": 1, "group": 4, },
+				{ "name": 5, "type": 2, },
+			]
+		},
+	}));
+}
+
+#[test]
+fn test_struct_field_sensitivities() {
+	#[derive(Metadata)]
+	struct Credentials {
+		#[metadata(sensitivity = "secret")]
+		a: i32,
+		b: bool,
+	}
+
+	assert_json_for_type::<Credentials>(json!({
+		"id": {
+			"custom.name": 1,
+			"custom.namespace": [2],
+			"custom.params": [],
+		},
+		"def": {
+			"struct.fields": [
+				{ "name": 3, "type": 1, "sensitivity": "secret", },
+				{ "name": 4, "type": 2, },
+			]
+		},
+	}));
+}
+
+#[test]
+fn test_struct_field_display_hints() {
+	#[derive(Metadata)]
+	struct Hinted {
+		#[metadata(display = "hex")]
+		a: i32,
+		b: bool,
+	}
+
+	assert_json_for_type::<Hinted>(json!({
+		"id": {
+			"custom.name": 1,
+			"custom.namespace": [2],
+			"custom.params": [],
+		},
+		"def": {
+			"struct.fields": [
+				{ "name": 3, "type": 1, "display": 4, },
+				{ "name": 5, "type": 2, },
+			]
+		},
+	}));
+}
+
 #[test]
 fn test_clike_enum() {
 	#[derive(Metadata)]
@@ -169,6 +285,38 @@ fn test_enum() {
 	}));
 }
 
+#[test]
+fn test_enum_error_codes() {
+	#[derive(Metadata)]
+	enum Error {
+		#[metadata(code = 404)]
+		NotFound,
+		#[metadata(code = 500)]
+		Internal(u8),
+	}
+
+	assert_json_for_type::<Error>(json!({
+		"id": {
+			"custom.name": 1,
+			"custom.namespace": [2],
+			"custom.params": [],
+		},
+		"def": {
+			"enum.variants": [
+				{
+					"unit_variant.name": 3,
+					"unit_variant.code": 404,
+				},
+				{
+					"tuple_struct_variant.name": 4,
+					"tuple_struct_variant.types": [1],
+					"tuple_struct_variant.code": 500,
+				},
+			]
+		},
+	}));
+}
+
 #[test]
 fn test_union() {
 	#[derive(Metadata)]
@@ -192,9 +340,49 @@ fn test_union() {
 	}));
 }
 
+#[test]
+fn test_union_discriminator() {
+	#[allow(unused)]
+	#[derive(TypeId)]
+	union Payload {
+		int: u32,
+		flag: bool,
+	}
+
+	impl HasTypeDef for Payload {
+		fn type_def() -> TypeDef {
+			TypeDefUnion::new(vec![NamedField::of::<u32>("int"), NamedField::of::<bool>("flag")])
+				.with_discriminator(UnionDiscriminator::new(vec!["kind"], vec![(0u8, "int"), (1, "flag")]))
+				.unwrap()
+				.into()
+		}
+	}
+
+	assert_json_for_type::<Payload>(json!({
+		"id": {
+			"custom.name": 1,
+			"custom.namespace": [2],
+			"custom.params": [],
+		},
+		"def": {
+			"union.fields": [
+				{ "name": 3, "type": 1, },
+				{ "name": 4, "type": 2, },
+			],
+			"union.discriminator": {
+				"field": [5],
+				"mapping": [
+					{ "value": { "uint": 0 }, "member": 3, },
+					{ "value": { "uint": 1 }, "member": 4, },
+				],
+			},
+		},
+	}));
+}
+
 #[test]
 fn test_registry() {
-	let mut registry = Registry::new();
+	let mut builder = RegistryBuilder::new();
 
 	#[derive(Metadata)]
 	struct UnitStruct;
@@ -223,12 +411,13 @@ fn test_registry() {
 		C { a: u8, b: u32, c: [u8; 32] },
 	}
 
-	registry.register_type(&UnitStruct::meta_type());
-	registry.register_type(&TupleStruct::meta_type());
-	registry.register_type(&Struct::meta_type());
-	registry.register_type(&RecursiveStruct::meta_type());
-	registry.register_type(&ClikeEnum::meta_type());
-	registry.register_type(&RustEnum::meta_type());
+	builder.register_type(&UnitStruct::meta_type());
+	builder.register_type(&TupleStruct::meta_type());
+	builder.register_type(&Struct::meta_type());
+	builder.register_type(&RecursiveStruct::meta_type());
+	builder.register_type(&ClikeEnum::meta_type());
+	builder.register_type(&RustEnum::meta_type());
+	let registry = builder.build();
 
 	let expected_json = json!({
 		"strings": [
@@ -415,3 +604,511 @@ fn test_registry() {
 
 	assert_eq!(serde_json::to_value(registry).unwrap(), expected_json,);
 }
+
+#[test]
+fn test_registry_nested_layout() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&<Option<&'static [u8]>>::meta_type());
+	let registry = builder.build();
+
+	let expected_json = json!({
+		"version": 2,
+		"capabilities": Capabilities::current().bits(),
+		"strings": [
+			"Option", // 1
+			"None",   // 2
+			"Some",   // 3
+		],
+		"types": [
+			{ // type 1
+				"id": {
+					"custom": {
+						"name": 1, // Option
+						"namespace": [],
+						"params": [2], // [u8]
+					}
+				},
+				"def": {
+					"enum": {
+						"variants": [
+							{ "unit": { "name": 2 } }, // None
+							{ "tuple_struct": { "name": 3, "types": [2] } }, // Some
+						]
+					}
+				}
+			},
+			{ // type 2
+				"id": {
+					"slice": {
+						"type": 3, // u8
+						"encoding": "bytes",
+					}
+				},
+				"def": "builtin",
+			},
+			{ // type 3
+				"id": { "primitive": "u8" },
+				"def": "builtin",
+			},
+		]
+	});
+
+	assert_eq!(
+		serde_json::to_value(registry.with_layout(Layout::Nested)).unwrap(),
+		expected_json,
+	);
+	assert_eq!(
+		serde_json::from_value::<nested::Registry>(expected_json).unwrap(),
+		nested::Registry::from(&registry),
+	);
+	assert_eq!(
+		serde_json::to_value(registry.with_layout(Layout::Flat)).unwrap()["version"],
+		json!(1),
+	);
+	// Producers that predate capabilities have none.
+	assert_eq!(
+		serde_json::from_value::<nested::Registry>(json!({
+			"version": 2,
+			"strings": [],
+			"types": [],
+		}))
+		.unwrap()
+		.capabilities,
+		Capabilities::empty(),
+	);
+	assert!(serde_json::from_value::<nested::Registry>(json!({
+		"version": 1,
+		"strings": [],
+		"types": [],
+	}))
+	.is_err());
+}
+
+#[test]
+fn test_registry_fields_of_nested_layout() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_alias("Flag", bool::meta_type());
+	let registry = builder.build();
+	let expected = nested::Registry::from(&registry);
+
+	// Fields are accepted in any order.
+	let reordered = json!({
+		"aliases": [{ "name": 1, "type": 1 }],
+		"types": [{ "id": { "primitive": "bool" }, "def": "builtin" }],
+		"strings": ["Flag"],
+		"version": 2,
+	});
+	assert_eq!(
+		serde_json::from_value::<nested::Registry>(reordered.clone()).unwrap().types,
+		expected.types,
+	);
+
+	// Missing fields are reported by their name.
+	let error = serde_json::from_value::<nested::Registry>(json!({ "version": 2, "strings": [] })).unwrap_err();
+	assert_eq!(error.to_string(), "missing field `types`");
+	let error = serde_json::from_value::<nested::Registry>(json!({ "version": 2, "types": [] })).unwrap_err();
+	assert_eq!(error.to_string(), "missing field `strings`");
+
+	// Unknown fields of newer producers are ignored unless deserializing strictly.
+	let mut future = reordered;
+	future["signature"] = json!("0x00");
+	assert!(serde_json::from_value::<nested::Registry>(future.clone()).is_ok());
+	assert!(serde_json::from_value::<OwnedRegistry>(future.clone()).is_ok());
+	let error = nested::Registry::deserialize_strict(future.clone()).unwrap_err();
+	assert!(error.to_string().starts_with("unknown field `signature`"));
+	assert!(OwnedRegistry::deserialize_strict(future).is_err());
+
+	// The stats appendix is part of the layout.
+	let with_stats = serde_json::to_value(registry.with_layout(Layout::Nested).with_stats()).unwrap();
+	assert_eq!(nested::Registry::deserialize_strict(with_stats).unwrap(), expected);
+	let owned = serde_json::to_value(registry.with_layout(Layout::Nested)).unwrap();
+	assert_eq!(OwnedRegistry::deserialize_strict(owned).unwrap(), registry.into_owned());
+}
+
+#[test]
+fn test_ingest_options_of_nested_layout() {
+	let untrusted = json!({
+		"version": 2,
+		"strings": ["Account", "app", "my crate"],
+		"types": [{
+			"id": { "custom": { "name": 1, "namespace": [2, 3], "params": [] } },
+			"def": { "tuple_struct": { "types": [] } },
+		}],
+	});
+	OwnedRegistry::deserialize_with(untrusted.clone(), IngestOptions::permissive()).unwrap();
+	let options = IngestOptions::permissive().with_max_string_len(7);
+	let error = OwnedRegistry::deserialize_with(untrusted.clone(), options).unwrap_err();
+	assert_eq!(error.to_string(), "string 3 is too long with 8 bytes");
+	let options = IngestOptions::permissive().with_identifier_namespaces(true);
+	let error = OwnedRegistry::deserialize_with(untrusted.clone(), options).unwrap_err();
+	assert_eq!(error.to_string(), "namespace segment 1 of type 1 is not an identifier");
+
+	// Strings are checked while reading them, before the malformed types that follow.
+	let permissive = IngestOptions::permissive();
+	let read = |buffer: &str, options| {
+		OwnedRegistry::deserialize_with(&mut serde_json::Deserializer::from_str(buffer), options)
+	};
+	let buffer = r#"{"version": 2, "strings": ["Account", "Grüße"], "types": [{"id": 42}]}"#;
+	let error = read(buffer, permissive.with_ascii_only(true)).unwrap_err();
+	assert!(error
+		.to_string()
+		.starts_with("string 2 has a non-ASCII character at byte 2 at line 1"));
+	assert!(read(buffer, permissive).is_err());
+
+	// Fields are read just like the ones of the nested layout.
+	let mut reordered = untrusted;
+	reordered["stats"] = json!([]);
+	let buffer = serde_json::to_string(&reordered).unwrap();
+	assert_eq!(
+		read(&buffer, permissive).unwrap(),
+		serde_json::from_value::<OwnedRegistry>(reordered).unwrap(),
+	);
+	let error = read(r#"{"version": 1, "strings": [], "types": []}"#, permissive).unwrap_err();
+	assert!(error.to_string().contains("the format version of the nested layout"));
+	let error = read(r#"{"version": 2, "types": []}"#, permissive).unwrap_err();
+	assert!(error.to_string().starts_with("missing field `strings`"));
+}
+
+#[test]
+fn test_registry_tagged_layout() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&<Option<&'static [u8]>>::meta_type());
+	let registry = builder.build();
+
+	let expected_json = json!({
+		"version": 3,
+		"capabilities": Capabilities::current().bits(),
+		"strings": [
+			"Option", // 1
+			"None",   // 2
+			"Some",   // 3
+		],
+		"types": [
+			{ // type 1
+				"id": {
+					"kind": "custom",
+					"name": 1, // Option
+					"namespace": [],
+					"params": [2], // [u8]
+				},
+				"def": {
+					"kind": "enum",
+					"variants": [
+						{ "kind": "unit", "name": 2 }, // None
+						{ "kind": "tuple_struct", "name": 3, "types": [2] }, // Some
+					]
+				}
+			},
+			{ // type 2
+				"id": {
+					"kind": "slice",
+					"type": 3, // u8
+					"encoding": "bytes",
+				},
+				"def": { "kind": "builtin" },
+			},
+			{ // type 3
+				"id": { "kind": "primitive", "primitive": "u8" },
+				"def": { "kind": "builtin" },
+			},
+		]
+	});
+
+	assert_eq!(
+		serde_json::to_value(registry.with_layout(Layout::Tagged)).unwrap(),
+		expected_json,
+	);
+	let tagged = serde_json::from_value::<tagged::Registry>(expected_json).unwrap();
+	assert_eq!(tagged, tagged::Registry::from(&registry));
+	assert_eq!(nested::Registry::from(tagged), nested::Registry::from(&registry));
+}
+
+/// Returns a registry with types of every kind of type identifier and definition.
+fn registry_of_every_kind() -> Registry {
+	#[derive(Metadata)]
+	#[metadata(id = "0e7c1a52-3b9d-4f6e-8a2c-5d1b7f3e9c04")]
+	struct Named {
+		a: u8,
+		b: [bool; 2],
+	}
+	#[derive(Metadata)]
+	struct Unnamed(i32, ());
+	#[derive(Metadata)]
+	struct Unit;
+	#[derive(Metadata)]
+	enum Clike {
+		A,
+		B,
+	}
+	#[derive(Metadata)]
+	#[metadata(tag = "type", content = "value")]
+	enum Mixed {
+		A,
+		B(u8),
+		C { d: &'static [u8] },
+	}
+	#[allow(unused)]
+	#[derive(TypeId)]
+	union Payload {
+		int: u32,
+		flag: bool,
+	}
+
+	impl HasTypeDef for Payload {
+		fn type_def() -> TypeDef {
+			TypeDefUnion::new(vec![NamedField::of::<u32>("int"), NamedField::of::<bool>("flag")])
+				.with_discriminator(UnionDiscriminator::new(vec!["kind"], vec![(0u8, "int"), (1, "flag")]))
+				.unwrap()
+				.into()
+		}
+	}
+
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&Named::meta_type());
+	builder.register_type(&Unnamed::meta_type());
+	builder.register_type(&Unit::meta_type());
+	builder.register_type(&Clike::meta_type());
+	builder.register_type(&Mixed::meta_type());
+	builder.register_type(&<Option<core::num::NonZeroU32>>::meta_type());
+	builder.register_type(&<Vec<bool>>::meta_type());
+	builder.register_type(&Payload::meta_type());
+	builder.register_type(&serde_json::Value::meta_type());
+	builder.register_type(&serde_json::value::RawValue::meta_type());
+	let retired = builder.register_type(&<Option<u64>>::meta_type());
+	builder.register_alias("Everything", Named::meta_type());
+	let mut registry = builder.build();
+	registry.deprecate_type(retired);
+	registry
+}
+
+#[test]
+fn test_round_trip_every_kind() {
+	let registry = registry_of_every_kind();
+	let nested = nested::Registry::from(&registry);
+
+	let mut id_kinds = nested
+		.types
+		.iter()
+		.map(|id_def| match id_def.id {
+			nested::TypeId::Custom { .. } => "custom",
+			nested::TypeId::Slice { .. } => "slice",
+			nested::TypeId::Array { .. } => "array",
+			nested::TypeId::Tuple(_) => "tuple",
+			nested::TypeId::Primitive(_) => "primitive",
+		})
+		.collect::<Vec<_>>();
+	id_kinds.sort_unstable();
+	id_kinds.dedup();
+	assert_eq!(id_kinds, ["array", "custom", "primitive", "slice", "tuple"]);
+	let mut def_kinds = nested
+		.types
+		.iter()
+		.map(|id_def| match id_def.def {
+			nested::TypeDef::Builtin => "builtin",
+			nested::TypeDef::Json => "json",
+			nested::TypeDef::Tombstone => "tombstone",
+			nested::TypeDef::Raw { .. } => "raw",
+			nested::TypeDef::Struct { .. } => "struct",
+			nested::TypeDef::TupleStruct { .. } => "tuple_struct",
+			nested::TypeDef::ClikeEnum { .. } => "clike_enum",
+			nested::TypeDef::Enum { .. } => "enum",
+			nested::TypeDef::Union { .. } => "union",
+		})
+		.collect::<Vec<_>>();
+	def_kinds.sort_unstable();
+	def_kinds.dedup();
+	assert_eq!(
+		def_kinds,
+		[
+			"builtin",
+			"clike_enum",
+			"enum",
+			"json",
+			"raw",
+			"struct",
+			"tombstone",
+			"tuple_struct",
+			"union"
+		]
+	);
+
+	let nested_json = serde_json::to_value(registry.with_layout(Layout::Nested)).unwrap();
+	let read = serde_json::from_value::<nested::Registry>(nested_json.clone()).unwrap();
+	assert_eq!(read, nested);
+	assert_eq!(serde_json::to_value(&read).unwrap(), nested_json);
+
+	let tagged_json = serde_json::to_value(registry.with_layout(Layout::Tagged)).unwrap();
+	let read = serde_json::from_value::<tagged::Registry>(tagged_json.clone()).unwrap();
+	assert_eq!(serde_json::to_value(&read).unwrap(), tagged_json);
+	assert_eq!(nested::Registry::from(read), nested);
+
+	let owned = serde_json::from_value::<OwnedRegistry>(nested_json).unwrap();
+	assert_eq!(owned, registry.into_owned());
+}
+
+#[test]
+fn test_conformance_vectors_deserialize() {
+	use type_metadata::conformance::vectors;
+
+	for vector in vectors() {
+		let expected = nested::Registry::from(&{
+			let mut builder = RegistryBuilder::new();
+			vector.register(&mut builder);
+			builder.build()
+		});
+		let nested = serde_json::from_str::<nested::Registry>(vector.expected(Layout::Nested)).unwrap();
+		let tagged = serde_json::from_str::<tagged::Registry>(vector.expected(Layout::Tagged)).unwrap();
+		// Vectors omit the capabilities of the producer.
+		assert_eq!(nested.capabilities, Capabilities::default());
+		assert_eq!(nested.strings, expected.strings);
+		assert_eq!(nested.types, expected.types);
+		assert_eq!(nested, nested::Registry::from(tagged));
+	}
+}
+
+#[test]
+fn test_portable_registry_round_trip() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&<Option<bool>>::meta_type());
+	let registry = builder.build();
+	let expected_json = serde_json::to_value(registry.with_layout(Layout::Nested)).unwrap();
+
+	let portable = registry.into_portable();
+	assert_eq!(serde_json::to_value(&portable).unwrap(), expected_json);
+	assert_eq!(
+		serde_json::from_value::<PortableRegistry>(expected_json).unwrap(),
+		portable
+	);
+}
+
+#[test]
+fn test_owned_registry_from_buffer() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&<Result<(u8, bool), Option<u32>>>::meta_type());
+	builder.register_alias("Outcome", <Result<(u8, bool), Option<u32>>>::meta_type());
+	let registry = builder.build();
+	let buffer = serde_json::to_string(&registry.with_layout(Layout::Nested)).unwrap();
+
+	let owned = serde_json::from_str::<OwnedRegistry>(&buffer).unwrap();
+	drop(buffer);
+	assert_eq!(owned, registry.into_owned());
+}
+
+#[test]
+fn test_registry_ids_only() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&<Option<bool>>::meta_type());
+	let registry = builder.build();
+
+	let expected_json = json!({
+		"version": 2,
+		"capabilities": Capabilities::current().bits(),
+		"ids_only": true,
+		"strings": [
+			"Option", // 1
+			null,     // None
+			null,     // Some
+		],
+		"types": [
+			{ // type 1
+				"custom": {
+					"name": 1, // Option
+					"namespace": [],
+					"params": [2], // bool
+				}
+			},
+			{ "primitive": "bool" }, // type 2
+		]
+	});
+
+	assert_eq!(
+		serde_json::to_value(registry.with_layout(Layout::Nested).ids_only()).unwrap(),
+		expected_json,
+	);
+	let interface = serde_json::from_value::<nested::Interface>(expected_json).unwrap();
+	assert_eq!(interface.types.len(), 2);
+	assert_eq!(
+		serde_json::to_value(registry.with_layout(Layout::Flat).ids_only()).unwrap()["types"][1],
+		json!("bool"),
+	);
+}
+
+#[test]
+fn test_registry_aliases() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_alias("Balance", u128::meta_type());
+	let registry = builder.build();
+
+	let expected_json = json!({
+		"strings": [
+			"Balance", // 1
+		],
+		"types": [
+			{ // type 1
+				"id": "u128",
+				"def": "builtin",
+			},
+		],
+		"aliases": [
+			{
+				"name": 1, // Balance
+				"type": 1, // u128
+			},
+		],
+	});
+
+	assert_eq!(serde_json::to_value(&registry).unwrap(), expected_json);
+}
+
+#[test]
+fn test_registry_deferred() {
+	let mut registry = Registry::new();
+	// Registries built by a `RegistryBuilder` never have deferred types.
+	#[allow(deprecated)]
+	registry.register_type_id_only(&u128::meta_type());
+	assert!(serde_json::to_value(&registry).is_err());
+	assert!(serde_json::to_value(registry.with_layout(Layout::Nested)).is_err());
+
+	registry.complete_deferred();
+	let expected_json = json!({
+		"strings": [],
+		"types": [
+			{ // type 1
+				"id": "u128",
+				"def": "builtin",
+			},
+		],
+	});
+
+	assert_eq!(serde_json::to_value(&registry).unwrap(), expected_json);
+}
+
+#[test]
+fn test_value() {
+	let value = Value::from(vec![
+		Value::from(Some(42u128)),
+		Value::from(None::<u8>),
+		Composite::Named(vec![("a".into(), (-1i8).into()), ("b".into(), 'c'.into())]).into(),
+		Composite::Unnamed(vec![true.into(), "x".into()]).into(),
+	]);
+	let expected_json = json!([
+		{ "Some": [42] },
+		{ "None": [] },
+		{ "a": -1, "b": "c" },
+		[true, "x"],
+	]);
+
+	assert_eq!(serde_json::to_value(&value).unwrap(), expected_json);
+
+	let value: Value = serde_json::from_value(json!({ "a": 1, "b": [true, -2, "x", null] })).unwrap();
+	assert_eq!(
+		value,
+		Value::Composite(Composite::Named(vec![
+			("a".into(), Value::UInt(1)),
+			(
+				"b".into(),
+				Value::Sequence(vec![Value::Bool(true), Value::Int(-2), "x".into(), Value::unit()])
+			),
+		]))
+	);
+}
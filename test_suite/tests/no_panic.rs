@@ -0,0 +1,166 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feeds malformed inputs through the fallible entry points of the library
+//! with a panic hook installed that records every panic.
+//!
+//! The library is linted against panicking constructs with the `no-panic` feature,
+//! i.e. `cargo clippy --features no-panic`, while this harness catches the panics
+//! that slip through the lints, e.g. due to arithmetic or assertions.
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use type_metadata::{
+	schema::Schema,
+	value::{decode_scale, encode_scale, Composite, Value, Variant},
+	Metadata, OwnedRegistry, RegistryBuilder, TransactionError,
+};
+
+static PANICS: AtomicUsize = AtomicUsize::new(0);
+
+#[allow(unused)]
+#[derive(Metadata)]
+enum Command {
+	Transfer { to: [u8; 4], amount: u128 },
+	Memo(String, Option<char>),
+	Halt,
+}
+
+/// Returns deterministic pseudo-random bytes.
+fn noise(seed: u32, len: usize) -> Vec<u8> {
+	let mut state = seed;
+	(0..len)
+		.map(|_| {
+			state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+			(state >> 16) as u8
+		})
+		.collect()
+}
+
+fn decode_malformed_bytes() {
+	let mut builder = RegistryBuilder::new();
+	let types = vec![
+		builder.register_type(&Command::meta_type()),
+		builder.register_type(&<Vec<Option<(u32, bool)>>>::meta_type()),
+		builder.register_type(&<(char, String, i64)>::meta_type()),
+	];
+	let registry = builder.build();
+	let valid = [0u8, 1, 2, 3, 4, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+	for ty in types {
+		for len in 0..valid.len() {
+			let _ = decode_scale(&mut &valid[..len], ty, &registry);
+		}
+		for seed in 0..256 {
+			let bytes = noise(seed, 64);
+			let _ = decode_scale(&mut &bytes[..], ty, &registry);
+		}
+	}
+}
+
+fn encode_mismatched_values() {
+	let mut builder = RegistryBuilder::new();
+	let command = builder.register_type(&Command::meta_type());
+	let registry = builder.build();
+	let values: Vec<Value> = vec![
+		Value::Str("Unknown".to_string()),
+		Value::Str("Transfer".to_string()),
+		Value::Composite(Composite::Named(vec![])),
+		Value::Composite(Composite::Unnamed(vec![Value::Bool(true)])),
+		Variant::new("Memo", Composite::Unnamed(vec![Value::Str("αβ".to_string())])).into(),
+		Variant::new("Memo", Composite::Unnamed(vec![Value::Str("".to_string()), Value::Str("xy".to_string())])).into(),
+		Variant::new("Transfer", Composite::Named(vec![("to".to_string(), Value::UInt(u128::MAX))])).into(),
+	];
+	for value in &values {
+		let _ = encode_scale(value, command, &registry);
+	}
+}
+
+fn parse_malformed_schemas() {
+	let schema = r#"
+		// A comment with unicode: äöü
+		type app::Account { name: str, balance: u128, "key": [u8; 99999999999999999999] }
+		enum app::Status { Active = 1, Frozen(u8) }
+		type app::List<T>(Vec<T>, @group("x") T);
+	"#;
+	for (index, _) in schema.char_indices() {
+		let _ = schema[..index].parse::<Schema>();
+		let _ = schema[index..].parse::<Schema>();
+	}
+	for input in &["::", "type", "type ::", "type a::<", "\"\\", "\"", "é", "type a::B { x: (", "@"] {
+		let _ = input.parse::<Schema>();
+	}
+}
+
+fn deserialize_malformed_registries() {
+	let inputs = [
+		r#"{"strings": [], "types": [{"id": {"custom": {"name": 0, "namespace": [], "params": []}}, "def": "builtin"}]}"#,
+		r#"{"strings": ["A"], "types": [{"id": {"custom": {"name": 4294967295, "namespace": [], "params": [0]}}, "def": "builtin"}]}"#,
+		r#"{"strings": ["A"], "types": [{"id": {"slice": {"type": 7}}, "def": "builtin"}]}"#,
+		r#"{"strings": ["A"], "types": [], "aliases": [{"name": 2, "type": 1}]}"#,
+		r#"{"strings": "#,
+	];
+	for input in &inputs {
+		let _ = serde_json::from_str::<OwnedRegistry>(input);
+	}
+}
+
+fn inject_malformed_types() {
+	let mut builder = RegistryBuilder::new();
+	builder.register_type(&u32::meta_type());
+	let mut registry = builder.build();
+	for symbol in &[0u64, 2, 4_294_967_295, 4_294_967_296] {
+		let id = format!(r#"{{"custom": {{"name": {}, "namespace": [], "params": []}}}}"#, symbol);
+		let def = format!(r#"{{"struct": {{"fields": [{{"type": {}}}]}}}}"#, symbol);
+		let _ = registry.register_raw(&id, &def);
+		let _ = registry.register_raw(&id, "{");
+	}
+	let mut builder = RegistryBuilder::branch(&registry);
+	let result = builder.transaction(|tx| {
+		tx.register::<Command>();
+	});
+	assert_ne!(result, Err(TransactionError::CapacityExceeded));
+	let registry = builder.build();
+	assert!(!registry.is_exhausted());
+	assert!(registry.try_into_owned().is_ok());
+}
+
+#[test]
+fn malformed_inputs_do_not_panic() {
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		PANICS.fetch_add(1, Ordering::SeqCst);
+		default_hook(info);
+	}));
+	let cases: [(&str, fn()); 5] = [
+		("decode_malformed_bytes", decode_malformed_bytes),
+		("encode_mismatched_values", encode_mismatched_values),
+		("parse_malformed_schemas", parse_malformed_schemas),
+		("deserialize_malformed_registries", deserialize_malformed_registries),
+		("inject_malformed_types", inject_malformed_types),
+	];
+	let failed = cases
+		.iter()
+		.filter(|(_, case)| panic::catch_unwind(case).is_err())
+		.map(|(name, _)| *name)
+		.collect::<Vec<_>>();
+	let _ = panic::take_hook();
+	assert_eq!(failed, Vec::<&str>::new());
+	assert_eq!(PANICS.load(Ordering::SeqCst), 0);
+}
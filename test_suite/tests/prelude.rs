@@ -0,0 +1,80 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pins the items of the prelude and the signatures downstream code relies on.
+//!
+//! Changes to this test require a major version, see the stability policy of the `prelude` module.
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+use type_metadata::{prelude::*, RegistryBuilder};
+
+#[allow(unused)]
+#[derive(Metadata)]
+struct Transfer {
+	to: [u8; 32],
+	amount: u128,
+}
+
+#[allow(unused)]
+#[derive(Metadata)]
+enum Call {
+	Transfer(Transfer),
+	Halt,
+}
+
+/// Returns the number of fields of a composite in any form.
+fn field_count<F: Form>(def: &TypeDefComposite<F>) -> usize {
+	def.fields().len()
+}
+
+#[test]
+fn prelude_signatures() {
+	let _: fn() -> TypeId = <Transfer as HasTypeId>::type_id;
+	let _: fn() -> TypeDef = <Transfer as HasTypeDef>::type_def;
+	let _: fn() -> MetaType = <Transfer as Metadata>::meta_type;
+	let _: fn() -> Registry = Registry::new;
+	let _: fn(TypeId, &mut Registry) -> TypeId<CompactForm> = <TypeId<MetaForm> as IntoCompact>::into_compact;
+	let _: fn(&'static str) -> NamedField = NamedField::of::<u8>;
+	let _: fn() -> UnnamedField = UnnamedField::of::<u8>;
+	let _: fn(&'static str) -> Variant = Variant::new;
+	let _: TypeId = TypeIdPrimitive::U8.into();
+	let _: Vec<MetaType> = tuple_meta_type!(u8, bool);
+}
+
+#[test]
+fn prelude_items() {
+	let mut builder = RegistryBuilder::new();
+	register_types!(&mut builder, Call);
+	let mut registry = builder.build();
+
+	assert_eq!(
+		Transfer::type_id(),
+		TypeIdCustom::new("Transfer", Namespace::new(vec!["prelude"]).unwrap(), Vec::<MetaType>::new()).into()
+	);
+	assert_eq!(
+		Call::type_def(),
+		TypeDefVariant::new(vec![
+			Variant::new("Transfer").with_fields(vec![UnnamedField::of::<Transfer>()]),
+			Variant::new("Halt"),
+		])
+		.into()
+	);
+	let transfer = TypeDefComposite::new(vec![NamedField::of::<[u8; 32]>("to"), NamedField::of::<u128>("amount")]);
+	assert_eq!(Transfer::type_def(), transfer.clone().into());
+	assert_eq!(field_count(&transfer.into_compact(&mut registry)), 2);
+}